@@ -1,9 +1,12 @@
 use alloc::string::String;
 use axerrno::AxResult;
 use axfs::fops::{Directory, File};
+use core::time::Duration;
 
+pub use axfs::api::MountFlags as AxMountFlags;
 pub use axfs::fops::DirEntry as AxDirEntry;
 pub use axfs::fops::FileAttr as AxFileAttr;
+pub use axfs::fops::FileMeta as AxFileMeta;
 pub use axfs::fops::FilePerm as AxFilePerm;
 pub use axfs::fops::FileType as AxFileType;
 pub use axfs::fops::OpenOptions as AxOpenOptions;
@@ -58,6 +61,22 @@ pub fn ax_file_attr(file: &AxFileHandle) -> AxResult<AxFileAttr> {
     file.0.get_attr()
 }
 
+pub fn ax_file_meta(file: &AxFileHandle) -> AxResult<AxFileMeta> {
+    file.0.get_meta()
+}
+
+pub fn ax_set_file_perm(file: &AxFileHandle, perm: AxFilePerm) -> AxResult {
+    file.0.set_perm(perm)
+}
+
+pub fn ax_set_file_times(
+    file: &AxFileHandle,
+    atime: Option<Duration>,
+    mtime: Option<Duration>,
+) -> AxResult {
+    file.0.set_times(atime, mtime)
+}
+
 pub fn ax_read_dir(dir: &mut AxDirHandle, dirents: &mut [AxDirEntry]) -> AxResult<usize> {
     dir.0.read_dir(dirents)
 }
@@ -78,6 +97,34 @@ pub fn ax_rename(old: &str, new: &str) -> AxResult {
     axfs::api::rename(old, new)
 }
 
+pub fn ax_symlink(original: &str, link: &str) -> AxResult {
+    axfs::api::symlink(original, link)
+}
+
+pub fn ax_hard_link(original: &str, link: &str) -> AxResult {
+    axfs::api::hard_link(original, link)
+}
+
+pub fn ax_read_link(path: &str) -> AxResult<String> {
+    axfs::api::read_link(path)
+}
+
+pub fn ax_symlink_metadata(path: &str) -> AxResult<(AxFileAttr, AxFileMeta)> {
+    axfs::api::symlink_metadata(path).map(|m| m.into_parts())
+}
+
+pub fn ax_set_permissions(path: &str, perm: AxFilePerm) -> AxResult {
+    axfs::api::set_permissions(path, perm)
+}
+
+pub fn ax_chown(path: &str, uid: Option<u32>, gid: Option<u32>) -> AxResult {
+    axfs::api::chown(path, uid, gid)
+}
+
+pub fn ax_set_times(path: &str, atime: Option<Duration>, mtime: Option<Duration>) -> AxResult {
+    axfs::api::set_times(path, atime, mtime)
+}
+
 pub fn ax_current_dir() -> AxResult<String> {
     axfs::api::current_dir()
 }
@@ -85,3 +132,11 @@ pub fn ax_current_dir() -> AxResult<String> {
 pub fn ax_set_current_dir(path: &str) -> AxResult {
     axfs::api::set_current_dir(path)
 }
+
+pub fn ax_mount(source: &str, target: &str, fstype: &str, flags: AxMountFlags) -> AxResult {
+    axfs::api::mount(source, target, fstype, flags)
+}
+
+pub fn ax_umount(target: &str) -> AxResult {
+    axfs::api::umount(target)
+}
@@ -1,14 +1,26 @@
 use crate::io::AxPollState;
 use axerrno::AxResult;
-use axnet::{UdpSocket, TcpSocket};
+use axnet::{IcmpSocket, RawSocket, UdpSocket, TcpSocket};
 use core::net::{IpAddr, SocketAddr};
 
+pub use axnet::DhcpLease as AxDhcpLease;
+pub use axnet::DnsOptions as AxDnsOptions;
+pub use axnet::DnsRecordType as AxDnsRecordType;
+pub use axnet::InterfaceInfo as AxNetInterfaceInfo;
+pub use axnet::SocketOptions as AxSocketOptions;
+
 /// A handle to a TCP socket.
 pub struct AxTcpSocketHandle(TcpSocket);
 
 /// A handle to a UDP socket.
 pub struct AxUdpSocketHandle(UdpSocket);
 
+/// A handle to an ICMP socket.
+pub struct AxIcmpSocketHandle(IcmpSocket);
+
+/// A handle to a raw socket.
+pub struct AxRawSocketHandle(RawSocket);
+
 ////////////////////////////////////////////////////////////////////////////////
 // TCP socket
 ////////////////////////////////////////////////////////////////////////////////
@@ -64,6 +76,18 @@ pub fn ax_tcp_shutdown(socket: &AxTcpSocketHandle) -> AxResult {
     socket.0.shutdown()
 }
 
+pub fn ax_tcp_options(socket: &AxTcpSocketHandle) -> AxSocketOptions {
+    socket.0.options()
+}
+
+pub fn ax_tcp_set_options(socket: &AxTcpSocketHandle, options: AxSocketOptions) -> AxResult {
+    socket.0.set_options(options)
+}
+
+pub fn ax_tcp_buf_sizes(socket: &AxTcpSocketHandle) -> (usize, usize) {
+    socket.0.buf_sizes()
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // UDP socket
 ////////////////////////////////////////////////////////////////////////////////
@@ -117,6 +141,92 @@ pub fn ax_udp_poll(socket: &AxUdpSocketHandle) -> AxResult<AxPollState> {
     socket.0.poll()
 }
 
+pub fn ax_udp_options(socket: &AxUdpSocketHandle) -> AxSocketOptions {
+    socket.0.options()
+}
+
+pub fn ax_udp_set_options(socket: &AxUdpSocketHandle, options: AxSocketOptions) -> AxResult {
+    socket.0.set_options(options)
+}
+
+pub fn ax_udp_buf_sizes(socket: &AxUdpSocketHandle) -> (usize, usize) {
+    socket.0.buf_sizes()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// ICMP socket
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn ax_icmp_socket(ipv6: bool) -> AxIcmpSocketHandle {
+    AxIcmpSocketHandle(IcmpSocket::new(ipv6))
+}
+
+pub fn ax_icmp_socket_addr(socket: &AxIcmpSocketHandle) -> AxResult<SocketAddr> {
+    socket.0.local_addr()
+}
+
+pub fn ax_icmp_set_nonblocking(socket: &AxIcmpSocketHandle, nonblocking: bool) -> AxResult {
+    socket.0.set_nonblocking(nonblocking);
+    Ok(())
+}
+
+pub fn ax_icmp_bind(socket: &AxIcmpSocketHandle, addr: SocketAddr) -> AxResult {
+    socket.0.bind(addr)
+}
+
+pub fn ax_icmp_send_to(socket: &AxIcmpSocketHandle, buf: &[u8], addr: IpAddr) -> AxResult<usize> {
+    socket.0.send_to(buf, addr)
+}
+
+pub fn ax_icmp_recv_from(socket: &AxIcmpSocketHandle, buf: &mut [u8]) -> AxResult<(usize, IpAddr)> {
+    socket.0.recv_from(buf)
+}
+
+pub fn ax_icmp_poll(socket: &AxIcmpSocketHandle) -> AxResult<AxPollState> {
+    socket.0.poll()
+}
+
+pub fn ax_icmp_options(socket: &AxIcmpSocketHandle) -> AxSocketOptions {
+    socket.0.options()
+}
+
+pub fn ax_icmp_set_options(socket: &AxIcmpSocketHandle, options: AxSocketOptions) -> AxResult {
+    socket.0.set_options(options)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Raw socket
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn ax_raw_socket(ipv6: bool, protocol: u8) -> AxRawSocketHandle {
+    AxRawSocketHandle(RawSocket::new(ipv6, protocol))
+}
+
+pub fn ax_raw_set_nonblocking(socket: &AxRawSocketHandle, nonblocking: bool) -> AxResult {
+    socket.0.set_nonblocking(nonblocking);
+    Ok(())
+}
+
+pub fn ax_raw_send_to(socket: &AxRawSocketHandle, buf: &[u8], addr: IpAddr) -> AxResult<usize> {
+    socket.0.send_to(buf, addr)
+}
+
+pub fn ax_raw_recv_from(socket: &AxRawSocketHandle, buf: &mut [u8]) -> AxResult<(usize, IpAddr)> {
+    socket.0.recv_from(buf)
+}
+
+pub fn ax_raw_poll(socket: &AxRawSocketHandle) -> AxResult<AxPollState> {
+    socket.0.poll()
+}
+
+pub fn ax_raw_options(socket: &AxRawSocketHandle) -> AxSocketOptions {
+    socket.0.options()
+}
+
+pub fn ax_raw_set_options(socket: &AxRawSocketHandle, options: AxSocketOptions) -> AxResult {
+    socket.0.set_options(options)
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // Miscellaneous
 ////////////////////////////////////////////////////////////////////////////////
@@ -125,7 +235,74 @@ pub fn ax_dns_query(domain_name: &str) -> AxResult<alloc::vec::Vec<IpAddr>> {
     axnet::dns_query(domain_name)
 }
 
+pub fn ax_dns_query_type(
+    domain_name: &str,
+    record_type: AxDnsRecordType,
+) -> AxResult<alloc::vec::Vec<IpAddr>> {
+    axnet::dns_query_type(domain_name, record_type)
+}
+
+pub fn ax_dns_reverse_query(addr: IpAddr) -> AxResult<alloc::string::String> {
+    axnet::dns_reverse_query(addr)
+}
+
+pub fn ax_dns_servers() -> alloc::vec::Vec<IpAddr> {
+    axnet::dns_servers()
+}
+
+pub fn ax_dns_set_servers(servers: &[IpAddr]) -> AxResult {
+    axnet::set_dns_servers(servers)
+}
+
+pub fn ax_dns_options() -> AxDnsOptions {
+    axnet::dns_options()
+}
+
+pub fn ax_dns_set_options(options: AxDnsOptions) -> AxResult {
+    axnet::set_dns_options(options)
+}
+
+pub fn ax_dns_add_host(name: &str, addr: IpAddr) -> AxResult {
+    axnet::add_dns_host(name, addr)
+}
+
+pub fn ax_dns_remove_host(name: &str) -> AxResult {
+    axnet::remove_dns_host(name)
+}
+
+pub fn ax_dns_clear_cache() {
+    axnet::clear_dns_cache()
+}
+
 pub fn ax_poll_interfaces() -> AxResult {
     axnet::poll_interfaces();
     Ok(())
 }
+
+////////////////////////////////////////////////////////////////////////////////
+// Interface configuration
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn ax_net_interfaces() -> alloc::vec::Vec<AxNetInterfaceInfo> {
+    axnet::interfaces()
+}
+
+pub fn ax_net_set_ip_addr(name: &str, addr: IpAddr, prefix_len: u8) -> AxResult {
+    axnet::set_ip_addr(name, addr, prefix_len)
+}
+
+pub fn ax_net_clear_ip_addrs(name: &str) -> AxResult {
+    axnet::clear_ip_addrs(name)
+}
+
+pub fn ax_net_set_default_gateway(name: &str, gateway: IpAddr) -> AxResult {
+    axnet::set_default_gateway(name, gateway)
+}
+
+pub fn ax_net_clear_default_gateway() -> AxResult {
+    axnet::clear_default_gateway()
+}
+
+pub fn ax_net_dhcp_lease(name: &str) -> AxResult<Option<AxDhcpLease>> {
+    axnet::dhcp_lease(name)
+}
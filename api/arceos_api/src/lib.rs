@@ -188,10 +188,12 @@ pub mod fs {
         pub type AxDirHandle;
         pub type AxOpenOptions;
         pub type AxFileAttr;
+        pub type AxFileMeta;
         pub type AxFileType;
         pub type AxFilePerm;
         pub type AxDirEntry;
         pub type AxSeekFrom;
+        pub type AxMountFlags;
         #[cfg(feature = "myfs")]
         pub type AxDisk;
         #[cfg(feature = "myfs")]
@@ -236,6 +238,17 @@ pub mod fs {
         pub fn ax_seek_file(file: &mut AxFileHandle, pos: AxSeekFrom) -> AxResult<u64>;
         /// Returns attributes of the file.
         pub fn ax_file_attr(file: &AxFileHandle) -> AxResult<AxFileAttr>;
+        /// Returns the owner and timestamps of the file.
+        pub fn ax_file_meta(file: &AxFileHandle) -> AxResult<AxFileMeta>;
+        /// Changes the permissions of the file.
+        pub fn ax_set_file_perm(file: &AxFileHandle, perm: AxFilePerm) -> AxResult;
+        /// Changes the access and modification times of the file, which are
+        /// unchanged if `None`.
+        pub fn ax_set_file_times(
+            file: &AxFileHandle,
+            atime: Option<core::time::Duration>,
+            mtime: Option<core::time::Duration>,
+        ) -> AxResult;
 
         /// Reads directory entries starts from the current position into the
         /// given buffer, returns the number of entries read.
@@ -255,11 +268,40 @@ pub mod fs {
         ///
         /// It will delete the original file if `old` already exists.
         pub fn ax_rename(old: &str, new: &str) -> AxResult;
+        /// Creates a symbolic link at `link` that points to `original`.
+        pub fn ax_symlink(original: &str, link: &str) -> AxResult;
+        /// Creates a hard link at `link` to the file at `original`.
+        ///
+        /// Both paths must be in the same mounted filesystem.
+        pub fn ax_hard_link(original: &str, link: &str) -> AxResult;
+        /// Returns the path that the symbolic link at `path` points to.
+        pub fn ax_read_link(path: &str) -> AxResult<alloc::string::String>;
+        /// Returns attributes, owner and timestamps of the file at `path`,
+        /// without following the symbolic link of the last component.
+        pub fn ax_symlink_metadata(path: &str) -> AxResult<(AxFileAttr, AxFileMeta)>;
+        /// Changes the permissions of the file or directory at `path`.
+        pub fn ax_set_permissions(path: &str, perm: AxFilePerm) -> AxResult;
+        /// Changes the owner and group of the file or directory at `path`,
+        /// which are unchanged if `None`.
+        pub fn ax_chown(path: &str, uid: Option<u32>, gid: Option<u32>) -> AxResult;
+        /// Changes the access and modification times of the file or directory
+        /// at `path`, which are unchanged if `None`.
+        pub fn ax_set_times(
+            path: &str,
+            atime: Option<core::time::Duration>,
+            mtime: Option<core::time::Duration>,
+        ) -> AxResult;
 
         /// Returns the current working directory.
         pub fn ax_current_dir() -> AxResult<alloc::string::String>;
         /// Changes the current working directory to the specified path.
         pub fn ax_set_current_dir(path: &str) -> AxResult;
+
+        /// Mounts a new filesystem of type `fstype` on the directory `target`,
+        /// or attaches the directory `source` if `flags` has `BIND`.
+        pub fn ax_mount(source: &str, target: &str, fstype: &str, flags: AxMountFlags) -> AxResult;
+        /// Unmounts the filesystem mounted on `target`, which fails if it is busy.
+        pub fn ax_umount(target: &str) -> AxResult;
     }
 }
 
@@ -272,6 +314,13 @@ pub mod net {
         @cfg "net";
         pub type AxTcpSocketHandle;
         pub type AxUdpSocketHandle;
+        pub type AxIcmpSocketHandle;
+        pub type AxRawSocketHandle;
+        pub type AxNetInterfaceInfo;
+        pub type AxSocketOptions;
+        pub type AxDhcpLease;
+        pub type AxDnsOptions;
+        pub type AxDnsRecordType;
     }
 
     define_api! {
@@ -309,6 +358,13 @@ pub mod net {
         pub fn ax_tcp_poll(socket: &AxTcpSocketHandle) -> AxResult<AxPollState>;
         /// Closes the connection on the TCP socket.
         pub fn ax_tcp_shutdown(socket: &AxTcpSocketHandle) -> AxResult;
+        /// Returns the options of the TCP socket.
+        pub fn ax_tcp_options(socket: &AxTcpSocketHandle) -> AxSocketOptions;
+        /// Sets the options of the TCP socket.
+        pub fn ax_tcp_set_options(socket: &AxTcpSocketHandle, options: AxSocketOptions) -> AxResult;
+        /// Returns the sizes of the receive and the send buffers of the TCP
+        /// socket in use.
+        pub fn ax_tcp_buf_sizes(socket: &AxTcpSocketHandle) -> (usize, usize);
 
         // UDP socket
 
@@ -345,16 +401,107 @@ pub mod net {
         pub fn ax_udp_recv(socket: &AxUdpSocketHandle, buf: &mut [u8]) -> AxResult<usize>;
         /// Returns whether the UDP socket is readable or writable.
         pub fn ax_udp_poll(socket: &AxUdpSocketHandle) -> AxResult<AxPollState>;
+        /// Returns the options of the UDP socket.
+        pub fn ax_udp_options(socket: &AxUdpSocketHandle) -> AxSocketOptions;
+        /// Sets the options of the UDP socket.
+        pub fn ax_udp_set_options(socket: &AxUdpSocketHandle, options: AxSocketOptions) -> AxResult;
+        /// Returns the sizes of the receive and the send buffers of the UDP
+        /// socket in use.
+        pub fn ax_udp_buf_sizes(socket: &AxUdpSocketHandle) -> (usize, usize);
+
+        // ICMP socket
+
+        /// Creates a new ICMP socket for pinging, over IPv6 if `ipv6` is true,
+        /// or IPv4 otherwise.
+        pub fn ax_icmp_socket(ipv6: bool) -> AxIcmpSocketHandle;
+        /// Returns the local address of the ICMP socket, with the ICMP
+        /// identifier as the port.
+        pub fn ax_icmp_socket_addr(socket: &AxIcmpSocketHandle) -> AxResult<SocketAddr>;
+        /// Moves this ICMP socket into or out of nonblocking mode.
+        pub fn ax_icmp_set_nonblocking(socket: &AxIcmpSocketHandle, nonblocking: bool) -> AxResult;
+        /// Binds the ICMP socket to the given address, using the port as the
+        /// ICMP identifier.
+        pub fn ax_icmp_bind(socket: &AxIcmpSocketHandle, addr: SocketAddr) -> AxResult;
+        /// Sends an ICMP echo request in the given buffer to the given address.
+        /// On success, returns the number of bytes written.
+        pub fn ax_icmp_send_to(socket: &AxIcmpSocketHandle, buf: &[u8], addr: IpAddr) -> AxResult<usize>;
+        /// Receives a single ICMP message on the ICMP socket.
+        pub fn ax_icmp_recv_from(socket: &AxIcmpSocketHandle, buf: &mut [u8]) -> AxResult<(usize, IpAddr)>;
+        /// Returns whether the ICMP socket is readable or writable.
+        pub fn ax_icmp_poll(socket: &AxIcmpSocketHandle) -> AxResult<AxPollState>;
+        /// Returns the options of the ICMP socket.
+        pub fn ax_icmp_options(socket: &AxIcmpSocketHandle) -> AxSocketOptions;
+        /// Sets the options of the ICMP socket.
+        pub fn ax_icmp_set_options(socket: &AxIcmpSocketHandle, options: AxSocketOptions) -> AxResult;
+
+        // Raw socket
+
+        /// Creates a new raw socket for the given IP protocol number, over
+        /// IPv6 if `ipv6` is true, or IPv4 otherwise.
+        pub fn ax_raw_socket(ipv6: bool, protocol: u8) -> AxRawSocketHandle;
+        /// Moves this raw socket into or out of nonblocking mode.
+        pub fn ax_raw_set_nonblocking(socket: &AxRawSocketHandle, nonblocking: bool) -> AxResult;
+        /// Sends a packet with the payload in the given buffer to the given
+        /// address. On success, returns the number of bytes written.
+        pub fn ax_raw_send_to(socket: &AxRawSocketHandle, buf: &[u8], addr: IpAddr) -> AxResult<usize>;
+        /// Receives a single packet on the raw socket.
+        pub fn ax_raw_recv_from(socket: &AxRawSocketHandle, buf: &mut [u8]) -> AxResult<(usize, IpAddr)>;
+        /// Returns whether the raw socket is readable or writable.
+        pub fn ax_raw_poll(socket: &AxRawSocketHandle) -> AxResult<AxPollState>;
+        /// Returns the options of the raw socket.
+        pub fn ax_raw_options(socket: &AxRawSocketHandle) -> AxSocketOptions;
+        /// Sets the options of the raw socket.
+        pub fn ax_raw_set_options(socket: &AxRawSocketHandle, options: AxSocketOptions) -> AxResult;
 
         // Miscellaneous
 
         /// Resolves the host name to a list of IP addresses.
         pub fn ax_dns_query(domain_name: &str) -> AxResult<alloc::vec::Vec<IpAddr>>;
+        /// Resolves the host name to a list of IP addresses of the given type.
+        pub fn ax_dns_query_type(domain_name: &str, record_type: AxDnsRecordType) -> AxResult<alloc::vec::Vec<IpAddr>>;
+        /// Resolves the IP address to a host name (reverse lookup).
+        pub fn ax_dns_reverse_query(addr: IpAddr) -> AxResult<alloc::string::String>;
         /// Poll the network stack.
         ///
         /// It may receive packets from the NIC and process them, and transmit queued
         /// packets to the NIC.
         pub fn ax_poll_interfaces() -> AxResult;
+
+        // Interface configuration
+
+        /// Returns information about all network interfaces.
+        pub fn ax_net_interfaces() -> alloc::vec::Vec<AxNetInterfaceInfo>;
+        /// Assigns an IP address with the given prefix length to the network
+        /// interface, replacing its existing addresses of the same family.
+        pub fn ax_net_set_ip_addr(name: &str, addr: IpAddr, prefix_len: u8) -> AxResult;
+        /// Removes all IP addresses from the network interface, except the
+        /// IPv6 link-local address.
+        pub fn ax_net_clear_ip_addrs(name: &str) -> AxResult;
+        /// Sets the default gateway of the address family of `gateway`, which
+        /// is reached through the given network interface.
+        pub fn ax_net_set_default_gateway(name: &str, gateway: IpAddr) -> AxResult;
+        /// Removes the default IPv4 and IPv6 gateways.
+        pub fn ax_net_clear_default_gateway() -> AxResult;
+        /// Returns the current DHCP lease of the network interface, or `None`
+        /// if it is not configured by DHCP.
+        pub fn ax_net_dhcp_lease(name: &str) -> AxResult<Option<AxDhcpLease>>;
+
+        // DNS resolver configuration
+
+        /// Returns the DNS servers that are queried.
+        pub fn ax_dns_servers() -> alloc::vec::Vec<IpAddr>;
+        /// Sets the DNS servers. An empty list restores the default servers.
+        pub fn ax_dns_set_servers(servers: &[IpAddr]) -> AxResult;
+        /// Returns the timeout and the number of attempts of DNS queries.
+        pub fn ax_dns_options() -> AxDnsOptions;
+        /// Sets the timeout and the number of attempts of DNS queries.
+        pub fn ax_dns_set_options(options: AxDnsOptions) -> AxResult;
+        /// Adds a static host entry, which takes precedence over DNS servers.
+        pub fn ax_dns_add_host(name: &str, addr: IpAddr) -> AxResult;
+        /// Removes all the static entries of the host name.
+        pub fn ax_dns_remove_host(name: &str) -> AxResult;
+        /// Removes all cached DNS answers.
+        pub fn ax_dns_clear_cache();
     }
 }
 
@@ -48,11 +48,32 @@ typedef struct {{
             "pthread_attr_t",
             "pthread_mutex_t",
             "pthread_mutexattr_t",
+            "pthread_cond_t",
+            "pthread_condattr_t",
+            "pthread_rwlock_t",
+            "pthread_rwlockattr_t",
+            "pthread_barrier_t",
+            "pthread_barrierattr_t",
+            "pthread_once_t",
+            "pthread_key_t",
+            "sem_t",
+            "timespec",
             "epoll_event",
+            "pollfd",
+            "nfds_t",
+            "sigset_t",
+            "sigaction",
+            "siginfo_t",
+            "sigevent",
+            "pid_t",
+            "timer_t",
+            "itimerval",
+            "itimerspec",
             "iovec",
             "clockid_t",
             "rlimit",
             "aibuf",
+            "linger",
         ];
         let allow_vars = [
             "CLOCK_.*",
@@ -60,14 +81,34 @@ typedef struct {{
             "AF_.*",
             "SOCK_.*",
             "IPPROTO_.*",
+            "SOL_.*",
+            "SO_.*",
+            "TCP_.*",
+            "IP_.*",
+            "IPV6_.*",
             "FD_.*",
             "F_.*",
             "_SC_.*",
             "EPOLL_CTL_.*",
             "EPOLL.*",
+            "POLL.*",
+            "SIG.*",
+            "SA_.*",
+            "SI_.*",
+            "ITIMER_.*",
+            "TIMER_ABSTIME",
             "RLIMIT_.*",
             "EAI_.*",
             "MAXADDRS",
+            "MS_.*",
+            "MNT_.*",
+            "MAP_.*",
+            "PROT_.*",
+            "MREMAP_.*",
+            "UTIME_.*",
+            "AT_SYMLINK_NOFOLLOW",
+            "PTHREAD_.*",
+            "SEM_VALUE_MAX",
         ];
 
         #[derive(Debug)]
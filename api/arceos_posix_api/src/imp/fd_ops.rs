@@ -1,14 +1,28 @@
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use core::ffi::c_int;
+use core::task::Waker;
 
 use axerrno::{LinuxError, LinuxResult};
 use axio::PollState;
 use axns::{ResArc, def_resource};
+use axsync::spin::SpinNoIrq;
 use flatten_objects::FlattenObjects;
 use spin::RwLock;
 
 use crate::ctypes;
 use crate::imp::stdio::{stdin, stdout};
+#[cfg(any(
+    feature = "unix",
+    feature = "select",
+    feature = "poll",
+    feature = "epoll"
+))]
+use {
+    alloc::task::Wake,
+    core::sync::atomic::{AtomicBool, Ordering},
+    core::time::Duration,
+};
 
 pub const AX_FILE_LIMIT: usize = 1024;
 
@@ -20,6 +34,137 @@ pub trait FileLike: Send + Sync {
     fn into_any(self: Arc<Self>) -> Arc<dyn core::any::Any + Send + Sync>;
     fn poll(&self) -> LinuxResult<PollState>;
     fn set_nonblocking(&self, nonblocking: bool) -> LinuxResult;
+
+    /// Registers `waker` to be woken once, the next time the file may have
+    /// become ready.
+    ///
+    /// Returns `false` if the file cannot wake anyone, so that it has to be
+    /// polled periodically.
+    fn register_waker(&self, _waker: &Waker) -> bool {
+        false
+    }
+
+    /// Returns whether the peer has closed the connection or shut down
+    /// writing, for `EPOLLRDHUP`.
+    fn peer_closed(&self) -> bool {
+        false
+    }
+
+    /// Returns whether the file is hung up, for `POLLHUP`: the other end of
+    /// a pipe is closed, or the connection is closed in both directions.
+    fn hung_up(&self) -> bool {
+        false
+    }
+}
+
+/// The wakers registered on a file by [`FileLike::register_waker`].
+pub struct PollWakers(SpinNoIrq<Vec<Waker>>);
+
+impl Default for PollWakers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PollWakers {
+    pub const fn new() -> Self {
+        Self(SpinNoIrq::new(Vec::new()))
+    }
+
+    pub fn register(&self, waker: &Waker) {
+        let mut wakers = self.0.lock();
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+
+    /// Wakes up and unregisters all the wakers, as the file may have become
+    /// ready.
+    pub fn wake(&self) {
+        let wakers = core::mem::take(&mut *self.0.lock());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// Blocks the current task until the files it has registered its waker on
+/// may have become ready, see [`FileLike::register_waker`].
+#[cfg(any(
+    feature = "unix",
+    feature = "select",
+    feature = "poll",
+    feature = "epoll"
+))]
+pub struct FileWaiter {
+    woken: AtomicBool,
+    #[cfg(feature = "multitask")]
+    wq: axtask::WaitQueue,
+}
+
+#[cfg(any(
+    feature = "unix",
+    feature = "select",
+    feature = "poll",
+    feature = "epoll"
+))]
+impl FileWaiter {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
+            woken: AtomicBool::new(false),
+            #[cfg(feature = "multitask")]
+            wq: axtask::WaitQueue::new(),
+        })
+    }
+
+    /// Forgets the previous wakeups, before the waker is registered again.
+    pub fn reset(&self) {
+        self.woken.store(false, Ordering::Release);
+    }
+
+    /// Sleeps until the waker has been woken since the last [`reset`], or
+    /// for at most `timeout` if any.
+    ///
+    /// It only yields the CPU without the `multitask` feature, or if the
+    /// timeout cannot be waited for without the `irq` feature.
+    ///
+    /// [`reset`]: Self::reset
+    pub fn wait(&self, timeout: Option<Duration>) {
+        let woken = || self.woken.load(Ordering::Acquire);
+        #[cfg(all(feature = "multitask", feature = "irq"))]
+        if let Some(timeout) = timeout {
+            self.wq.wait_timeout_until(timeout, woken);
+            return;
+        }
+        #[cfg(feature = "multitask")]
+        if timeout.is_none() {
+            self.wq.wait_until(woken);
+            return;
+        }
+        // the timeout cannot be waited for without interrupts
+        let _ = timeout;
+        if !woken() {
+            crate::sys_sched_yield();
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "unix",
+    feature = "select",
+    feature = "poll",
+    feature = "epoll"
+))]
+impl Wake for FileWaiter {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+        #[cfg(feature = "multitask")]
+        self.wq.notify_all(false);
+    }
 }
 
 def_resource! {
@@ -1,14 +1,17 @@
 use alloc::string::{String, ToString};
 use alloc::sync::Arc;
-use core::ffi::{c_char, c_int};
+use alloc::vec;
+use core::ffi::{c_char, c_int, c_long, c_ulong, c_void};
+use core::time::Duration;
 
-use axerrno::{LinuxError, LinuxResult};
-use axfs::fops::OpenOptions;
+use axerrno::{AxError, AxResult, LinuxError, LinuxResult};
+use axfs::api::MountFlags;
+use axfs::fops::{FileAttr, FileMeta, FilePerm, OpenOptions};
 use axio::{PollState, SeekFrom};
 use axsync::Mutex;
 
 use super::fd_ops::{FileLike, get_file_like};
-use crate::AT_FDCWD;
+use crate::{AT_FDCWD, handle_file_path};
 use crate::{ctypes, utils::char_ptr_to_str};
 
 /// File wrapper for `axfs::fops::File`.
@@ -29,7 +32,7 @@ impl File {
         super::fd_ops::add_file_like(Arc::new(self))
     }
 
-    fn from_fd(fd: c_int) -> LinuxResult<Arc<Self>> {
+    pub(crate) fn from_fd(fd: c_int) -> LinuxResult<Arc<Self>> {
         let f = super::fd_ops::get_file_like(fd)?;
         f.into_any()
             .downcast::<Self>()
@@ -47,31 +50,52 @@ impl File {
     }
 }
 
+/// The size of the chunks that reads and writes of files are copied through.
+///
+/// The buffers may be mapped to files by `mmap`, whose page faults read the
+/// files, so they are not accessed with a file locked.
+const BOUNCE_SIZE: usize = 16 * 1024;
+
 impl FileLike for File {
     fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
-        Ok(self.inner.lock().read(buf)?)
+        let mut bounce = vec![0; buf.len().min(BOUNCE_SIZE)];
+        let mut read_len = 0;
+        for chunk in buf.chunks_mut(BOUNCE_SIZE) {
+            let n = match self.inner.lock().read(&mut bounce[..chunk.len()]) {
+                Ok(n) => n,
+                Err(_) if read_len > 0 => break,
+                Err(e) => return Err(e.into()),
+            };
+            chunk[..n].copy_from_slice(&bounce[..n]);
+            read_len += n;
+            if n < chunk.len() {
+                break;
+            }
+        }
+        Ok(read_len)
     }
 
     fn write(&self, buf: &[u8]) -> LinuxResult<usize> {
-        Ok(self.inner.lock().write(buf)?)
+        let mut bounce = vec![0; buf.len().min(BOUNCE_SIZE)];
+        let mut write_len = 0;
+        for chunk in buf.chunks(BOUNCE_SIZE) {
+            bounce[..chunk.len()].copy_from_slice(chunk);
+            let n = match self.inner.lock().write(&bounce[..chunk.len()]) {
+                Ok(n) => n,
+                Err(_) if write_len > 0 => break,
+                Err(e) => return Err(e.into()),
+            };
+            write_len += n;
+            if n < chunk.len() {
+                break;
+            }
+        }
+        Ok(write_len)
     }
 
     fn stat(&self) -> LinuxResult<ctypes::stat> {
-        let metadata = self.inner.lock().get_attr()?;
-        let ty = metadata.file_type() as u8;
-        let perm = metadata.perm().bits() as u32;
-        let st_mode = ((ty as u32) << 12) | perm;
-        Ok(ctypes::stat {
-            st_ino: 1,
-            st_nlink: 1,
-            st_mode,
-            st_uid: 1000,
-            st_gid: 1000,
-            st_size: metadata.size() as _,
-            st_blocks: metadata.blocks() as _,
-            st_blksize: 512,
-            ..Default::default()
-        })
+        let inner = self.inner.lock();
+        Ok(attr_to_stat(&inner.get_attr()?, &inner.get_meta()?))
     }
 
     fn into_any(self: Arc<Self>) -> Arc<dyn core::any::Any + Send + Sync> {
@@ -90,6 +114,54 @@ impl FileLike for File {
     }
 }
 
+/// Convert file attributes and metadata to `struct stat`.
+fn attr_to_stat(attr: &FileAttr, meta: &FileMeta) -> ctypes::stat {
+    let ty = attr.file_type() as u8;
+    let perm = attr.perm().bits() as u32;
+    let st_mode = ((ty as u32) << 12) | perm;
+    ctypes::stat {
+        st_ino: meta.ino as _,
+        st_nlink: meta.nlink.max(1) as _,
+        st_mode,
+        st_uid: meta.uid,
+        st_gid: meta.gid,
+        st_size: attr.size() as _,
+        st_blocks: attr.blocks() as _,
+        st_blksize: 512,
+        st_atim: meta.atime.into(),
+        st_mtim: meta.mtime.into(),
+        st_ctim: meta.ctime.into(),
+        ..Default::default()
+    }
+}
+
+/// Convert the permission bits of `mode` to [`FilePerm`].
+fn mode_to_perm(mode: ctypes::mode_t) -> FilePerm {
+    FilePerm::from_bits_truncate((mode & 0o777) as u16)
+}
+
+/// Convert an owner ID to `Option`, where `-1` means unchanged.
+fn owner_id(id: u32) -> Option<u32> {
+    (id != u32::MAX).then_some(id)
+}
+
+/// Apply `on_file` or `on_dir` to the file or directory indicated by `fd`.
+fn with_fd_node(
+    fd: c_int,
+    on_file: impl FnOnce(&axfs::fops::File) -> AxResult,
+    on_dir: impl FnOnce(&axfs::fops::Directory) -> AxResult,
+) -> LinuxResult {
+    let f = get_file_like(fd)?.into_any();
+    if let Some(file) = f.downcast_ref::<File>() {
+        on_file(&file.inner.lock())?;
+    } else if let Some(dir) = f.downcast_ref::<Directory>() {
+        on_dir(&dir.inner.lock())?;
+    } else {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(())
+}
+
 /// Convert open flags to [`OpenOptions`].
 fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
     let flags = flags as u32;
@@ -118,6 +190,9 @@ fn flags_to_options(flags: c_int, _mode: ctypes::mode_t) -> OpenOptions {
     if flags & ctypes::O_DIRECTORY != 0 {
         options.directory(true);
     }
+    if flags & ctypes::O_NOFOLLOW != 0 {
+        options.nofollow(true);
+    }
     options
 }
 
@@ -129,10 +204,11 @@ pub fn sys_open(filename: *const c_char, flags: c_int, mode: ctypes::mode_t) ->
     let filename = char_ptr_to_str(filename);
     debug!("sys_open <= {:?} {:#o} {:#o}", filename, flags, mode);
     syscall_body!(sys_open, {
+        let filename = filename?;
         add_file_or_directory_fd(
             axfs::fops::File::open,
             axfs::fops::Directory::open_dir,
-            filename?,
+            filename,
             &flags_to_options(flags, mode),
         )
     })
@@ -227,6 +303,40 @@ pub fn sys_lseek(fd: c_int, offset: ctypes::off_t, whence: c_int) -> ctypes::off
     })
 }
 
+/// Write back the data of the file indicated by `fd` to the device.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_fsync(fd: c_int) -> c_int {
+    debug!("sys_fsync <= {}", fd);
+    syscall_body!(sys_fsync, {
+        let f = get_file_like(fd)?;
+        if let Ok(file) = f.clone().into_any().downcast::<File>() {
+            file.inner.lock().flush()?;
+        } else if f.into_any().is::<Directory>() {
+            // the entries of directories are in the block cache
+            axfs::api::sync()?;
+        } else {
+            return Err(LinuxError::EINVAL);
+        }
+        Ok(0)
+    })
+}
+
+/// Write back the data of the file indicated by `fd` to the device.
+///
+/// It is the same as [`sys_fsync`], as metadata is always written back.
+pub fn sys_fdatasync(fd: c_int) -> c_int {
+    sys_fsync(fd)
+}
+
+/// Write back all cached data of the filesystems to the devices.
+pub fn sys_sync() {
+    debug!("sys_sync");
+    if let Err(e) = axfs::api::sync() {
+        warn!("sys_sync: {:?}", e);
+    }
+}
+
 /// Get the file metadata by `path` and write into `buf`.
 ///
 /// Return 0 if success.
@@ -237,11 +347,9 @@ pub unsafe fn sys_stat(path: *const c_char, buf: *mut ctypes::stat) -> c_int {
         if buf.is_null() {
             return Err(LinuxError::EFAULT);
         }
-        let mut options = OpenOptions::new();
-        options.read(true);
-        let file = axfs::fops::File::open(path?, &options)?;
-        let st = File::new(file, path?.to_string()).stat()?;
-        unsafe { *buf = st };
+        let path = path?;
+        let (attr, meta) = axfs::fops::file_attr(path, true)?;
+        unsafe { *buf = attr_to_stat(&attr, &meta) };
         Ok(0)
     })
 }
@@ -271,7 +379,122 @@ pub unsafe fn sys_lstat(path: *const c_char, buf: *mut ctypes::stat) -> ctypes::
         if buf.is_null() {
             return Err(LinuxError::EFAULT);
         }
-        unsafe { *buf = Default::default() }; // TODO
+        let path = path?;
+        let (attr, meta) = axfs::fops::file_attr(path, false)?;
+        unsafe { *buf = attr_to_stat(&attr, &meta) };
+        Ok(0)
+    })
+}
+
+/// Change the permissions of the file `path` to `mode`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_chmod(path: *const c_char, mode: ctypes::mode_t) -> c_int {
+    syscall_body!(sys_chmod, {
+        let path = char_ptr_to_str(path)?;
+        debug!("sys_chmod <= {:?} {:#o}", path, mode);
+        axfs::api::set_permissions(path, mode_to_perm(mode))?;
+        Ok(0)
+    })
+}
+
+/// Change the permissions of the file indicated by `fd` to `mode`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_fchmod(fd: c_int, mode: ctypes::mode_t) -> c_int {
+    debug!("sys_fchmod <= {} {:#o}", fd, mode);
+    syscall_body!(sys_fchmod, {
+        let perm = mode_to_perm(mode);
+        with_fd_node(fd, |f| f.set_perm(perm), |d| d.set_perm(perm))?;
+        Ok(0)
+    })
+}
+
+/// Change the owner and group of the file `path`. An ID of `-1` is left
+/// unchanged.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_chown(path: *const c_char, owner: u32, group: u32) -> c_int {
+    syscall_body!(sys_chown, {
+        let path = char_ptr_to_str(path)?;
+        debug!("sys_chown <= {:?} {} {}", path, owner, group);
+        axfs::api::chown(path, owner_id(owner), owner_id(group))?;
+        Ok(0)
+    })
+}
+
+/// Change the owner and group of the file indicated by `fd`. An ID of `-1`
+/// is left unchanged.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_fchown(fd: c_int, owner: u32, group: u32) -> c_int {
+    debug!("sys_fchown <= {} {} {}", fd, owner, group);
+    syscall_body!(sys_fchown, {
+        let (uid, gid) = (owner_id(owner), owner_id(group));
+        with_fd_node(fd, |f| f.set_owner(uid, gid), |d| d.set_owner(uid, gid))?;
+        Ok(0)
+    })
+}
+
+/// Convert a timestamp of `utimensat` to the time to set, `None` if it is
+/// `UTIME_OMIT`.
+fn utime_to_duration(ts: &ctypes::timespec, now: Duration) -> LinuxResult<Option<Duration>> {
+    match ts.tv_nsec {
+        n if n == ctypes::UTIME_NOW as c_long => Ok(Some(now)),
+        n if n == ctypes::UTIME_OMIT as c_long => Ok(None),
+        0..=999_999_999 if ts.tv_sec >= 0 => Ok(Some((*ts).into())),
+        _ => Err(LinuxError::EINVAL),
+    }
+}
+
+/// Change the access and modification times of the file `path` relative to
+/// `dirfd`, or of the file indicated by `dirfd` if `path` is null.
+///
+/// `times` holds the access and modification times. A time of `UTIME_NOW` is
+/// set to the current time, and `UTIME_OMIT` is left unchanged. Both are set
+/// to the current time if `times` is null. A symbolic link `path` is not
+/// followed if `flags` has `AT_SYMLINK_NOFOLLOW`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub unsafe fn sys_utimensat(
+    dirfd: c_int,
+    path: *const c_char,
+    times: *const ctypes::timespec,
+    flags: c_int,
+) -> c_int {
+    debug!(
+        "sys_utimensat <= {} {:#x} {:#x} {:#x}",
+        dirfd, path as usize, times as usize, flags
+    );
+    syscall_body!(sys_utimensat, {
+        let flags = flags as u32;
+        if flags & !ctypes::AT_SYMLINK_NOFOLLOW != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let now = axhal::time::wall_time();
+        let (atime, mtime) = if times.is_null() {
+            (Some(now), Some(now))
+        } else {
+            let times = unsafe { core::slice::from_raw_parts(times, 2) };
+            (
+                utime_to_duration(&times[0], now)?,
+                utime_to_duration(&times[1], now)?,
+            )
+        };
+        if path.is_null() {
+            with_fd_node(
+                dirfd,
+                |f| f.set_times(atime, mtime),
+                |d| d.set_times(atime, mtime),
+            )?;
+        } else {
+            let path = handle_file_path(dirfd as isize, Some(path as _), false)?;
+            if flags & ctypes::AT_SYMLINK_NOFOLLOW != 0 {
+                axfs::api::set_symlink_times(path.as_str(), atime, mtime)?;
+            } else {
+                axfs::api::set_times(path.as_str(), atime, mtime)?;
+            }
+        }
         Ok(0)
     })
 }
@@ -310,6 +533,134 @@ pub fn sys_rename(old: *const c_char, new: *const c_char) -> c_int {
     })
 }
 
+/// Create a symbolic link `linkpath` that points to `target`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_symlink(target: *const c_char, linkpath: *const c_char) -> c_int {
+    syscall_body!(sys_symlink, {
+        let target = char_ptr_to_str(target)?;
+        let linkpath = char_ptr_to_str(linkpath)?;
+        debug!("sys_symlink <= {:?} {:?}", target, linkpath);
+        axfs::api::symlink(target, linkpath)?;
+        Ok(0)
+    })
+}
+
+/// Read the target of the symbolic link `path` into `buf`, which is not
+/// null-terminated, and truncated if it is longer than `bufsiz`.
+///
+/// Return the number of bytes placed in `buf`.
+pub fn sys_readlink(path: *const c_char, buf: *mut c_char, bufsiz: usize) -> ctypes::ssize_t {
+    syscall_body!(sys_readlink, {
+        let path = char_ptr_to_str(path)?;
+        debug!("sys_readlink <= {:?} {:#x} {}", path, buf as usize, bufsiz);
+        if buf.is_null() {
+            return Err(LinuxError::EFAULT);
+        }
+        if bufsiz == 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let target = axfs::fops::read_link(path)?;
+        let len = target.len().min(bufsiz);
+        let dst = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, len) };
+        dst.copy_from_slice(&target.as_bytes()[..len]);
+        Ok(len as ctypes::ssize_t)
+    })
+}
+
+/// Create a hard link `new` to the file `old`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_link(old: *const c_char, new: *const c_char) -> c_int {
+    syscall_body!(sys_link, {
+        let old_path = char_ptr_to_str(old)?;
+        let new_path = char_ptr_to_str(new)?;
+        debug!("sys_link <= old: {:?}, new: {:?}", old_path, new_path);
+        axfs::api::hard_link(old_path, new_path).map_err(|e| match e {
+            AxError::InvalidInput => LinuxError::EXDEV,
+            e => e.into(),
+        })?;
+        Ok(0)
+    })
+}
+
+/// Remove the name `path`, and the file if it has no other links.
+///
+/// Symbolic links are removed themselves, not the files they point to.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_unlink(path: *const c_char) -> c_int {
+    syscall_body!(sys_unlink, {
+        let path = char_ptr_to_str(path)?;
+        debug!("sys_unlink <= {:?}", path);
+        axfs::api::remove_file(path)?;
+        Ok(0)
+    })
+}
+
+/// Mount a filesystem of type `fstype` on `target`, or attach the directory
+/// `source` to it if `flags` has `MS_BIND`.
+///
+/// Remounting and moving mounts are not supported. Other flags and `data` are
+/// ignored.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_mount(
+    source: *const c_char,
+    target: *const c_char,
+    fstype: *const c_char,
+    flags: c_ulong,
+    _data: *const c_void,
+) -> c_int {
+    syscall_body!(sys_mount, {
+        let target = char_ptr_to_str(target)?;
+        // `source` and `fstype` are unused by some kinds of mounts
+        let source = if source.is_null() {
+            ""
+        } else {
+            char_ptr_to_str(source)?
+        };
+        let fstype = if fstype.is_null() {
+            ""
+        } else {
+            char_ptr_to_str(fstype)?
+        };
+        debug!(
+            "sys_mount <= source: {:?}, target: {:?}, fstype: {:?}, flags: {:#x}",
+            source, target, fstype, flags
+        );
+        if flags & (ctypes::MS_REMOUNT | ctypes::MS_MOVE) as c_ulong != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let mut mount_flags = MountFlags::empty();
+        if flags & ctypes::MS_BIND as c_ulong != 0 {
+            mount_flags |= MountFlags::BIND;
+        }
+        axfs::api::mount(source, target, fstype, mount_flags).map_err(|e| match e {
+            AxError::Unsupported => LinuxError::ENODEV, // unknown filesystem type
+            e => e.into(),
+        })?;
+        Ok(0)
+    })
+}
+
+/// Unmount the filesystem mounted on `target`.
+///
+/// No flags are supported.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+pub fn sys_umount2(target: *const c_char, flags: c_int) -> c_int {
+    syscall_body!(sys_umount2, {
+        let target = char_ptr_to_str(target)?;
+        debug!("sys_umount2 <= target: {:?}, flags: {:#x}", target, flags);
+        if flags != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        axfs::api::umount(target)?;
+        Ok(0)
+    })
+}
+
 /// Directory wrapper for `axfs::fops::Directory`.
 pub struct Directory {
     inner: Mutex<axfs::fops::Directory>,
@@ -352,7 +703,8 @@ impl FileLike for Directory {
     }
 
     fn stat(&self) -> LinuxResult<ctypes::stat> {
-        Err(LinuxError::EBADF)
+        let inner = self.inner.lock();
+        Ok(attr_to_stat(&inner.get_attr()?, &inner.get_meta()?))
     }
 
     fn into_any(self: Arc<Self>) -> Arc<dyn core::any::Any + Send + Sync> {
@@ -1,31 +1,244 @@
 //! `epoll` implementation.
 //!
-//! TODO: do not support `EPOLLET` flag
+//! Each registered file is given a waker, which puts it on the ready list of
+//! the epoll instance when the file may have become ready. `epoll_wait` only
+//! polls the files on the ready list, and sleeps while none of them is ready,
+//! until the ready list of its own instance wakes it up. The files that cannot
+//! wake anyone stay on the ready list, and are polled periodically.
 
-use alloc::collections::BTreeMap;
 use alloc::collections::btree_map::Entry;
-use alloc::sync::Arc;
-use core::{ffi::c_int, time::Duration};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::{Arc, Weak};
+use alloc::task::Wake;
+use alloc::vec::Vec;
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use core::task::Waker;
+use core::time::Duration;
 
 use axerrno::{LinuxError, LinuxResult};
-use axhal::time::wall_time;
+use axhal::time::monotonic_time;
 use axsync::Mutex;
+use axsync::spin::SpinNoIrq;
 
 use crate::ctypes;
-use crate::imp::fd_ops::{FileLike, add_file_like, get_file_like};
+use crate::imp::fd_ops::{FileLike, PollWakers, add_file_like, get_file_like};
 
-pub struct EpollInstance {
-    events: Mutex<BTreeMap<usize, ctypes::epoll_event>>,
-}
+/// The flags that are not events, which are kept when an `EPOLLONESHOT`
+/// interest is disabled.
+const PRIVATE_FLAGS: u32 =
+    ctypes::EPOLLONESHOT | ctypes::EPOLLET | ctypes::EPOLLEXCLUSIVE | ctypes::EPOLLWAKEUP;
+
+/// The flags that may be combined with `EPOLLEXCLUSIVE`.
+const EXCLUSIVE_FLAGS: u32 = ctypes::EPOLLIN
+    | ctypes::EPOLLOUT
+    | ctypes::EPOLLERR
+    | ctypes::EPOLLHUP
+    | ctypes::EPOLLWAKEUP
+    | ctypes::EPOLLET
+    | ctypes::EPOLLEXCLUSIVE;
+
+/// The groups of `EPOLLEXCLUSIVE` interests, by the address of their file.
+static EXCLUSIVE_GROUPS: SpinNoIrq<BTreeMap<usize, Weak<ExclusiveGroup>>> =
+    SpinNoIrq::new(BTreeMap::new());
 
 unsafe impl Send for ctypes::epoll_event {}
 unsafe impl Sync for ctypes::epoll_event {}
 
+/// A file registered on an epoll instance.
+struct Interest {
+    file: Weak<dyn FileLike>,
+    /// The requested events and the user data.
+    event: SpinNoIrq<ctypes::epoll_event>,
+    /// Whether the file could not wake up the interest when last polled.
+    polled: AtomicBool,
+    /// The events that were ready when last polled, to find the edges of the
+    /// files that are polled.
+    last: AtomicU32,
+    /// Whether the interest is on the ready list.
+    queued: AtomicBool,
+    /// Set by `EPOLL_CTL_DEL`, as the file may still hold the waker.
+    removed: AtomicBool,
+    ready: Weak<ReadyList>,
+    /// Woken by the file instead of the interest, with `EPOLLEXCLUSIVE`.
+    group: Option<Arc<ExclusiveGroup>>,
+}
+
+impl Interest {
+    /// Returns the file, unless the interest is removed or the file is
+    /// closed.
+    fn file(&self) -> Option<Arc<dyn FileLike>> {
+        if self.removed.load(Ordering::Acquire) {
+            return None;
+        }
+        self.file.upgrade()
+    }
+
+    /// Whether the interest refers to `file`.
+    fn is_for(&self, file: &Arc<dyn FileLike>) -> bool {
+        core::ptr::addr_eq(self.file.as_ptr(), Arc::as_ptr(file))
+    }
+
+    /// Whether the interest is disabled by `EPOLLONESHOT`.
+    fn disabled(&self) -> bool {
+        self.event.lock().events & !PRIVATE_FLAGS == 0
+    }
+
+    fn has_waiters(&self) -> bool {
+        self.ready
+            .upgrade()
+            .is_some_and(|ready| ready.waiters.load(Ordering::Acquire) > 0)
+    }
+
+    /// Registers the waker of the interest on `file`, and returns whether
+    /// the file can wake it up.
+    fn arm(self: &Arc<Self>, file: &dyn FileLike) -> bool {
+        let wakes = match &self.group {
+            Some(group) => file.register_waker(&Waker::from(group.clone())),
+            None => file.register_waker(&Waker::from(self.clone())),
+        };
+        self.polled.store(!wakes, Ordering::Release);
+        wakes
+    }
+
+    /// Polls `file` for the requested events.
+    ///
+    /// If the file is polled, an edge-triggered interest only gets the events
+    /// that were not ready when last polled, and `update` records the ready
+    /// events for the next poll.
+    fn ready_events(&self, file: &dyn FileLike, update: bool) -> u32 {
+        let events = self.event.lock().events;
+        let mut ready = match file.poll() {
+            Ok(state) => {
+                let mut ready = 0;
+                if state.readable {
+                    ready |= ctypes::EPOLLIN;
+                }
+                if state.writable {
+                    ready |= ctypes::EPOLLOUT;
+                }
+                ready
+            }
+            Err(_) => ctypes::EPOLLERR,
+        };
+        if file.hung_up() {
+            ready |= ctypes::EPOLLHUP;
+        }
+        if events & ctypes::EPOLLRDHUP != 0 && file.peer_closed() {
+            ready |= ctypes::EPOLLRDHUP;
+        }
+        ready &= events;
+        if events & ctypes::EPOLLET != 0 && self.polled.load(Ordering::Acquire) {
+            let last = match update {
+                true => self.last.swap(ready, Ordering::AcqRel),
+                false => self.last.load(Ordering::Acquire),
+            };
+            ready &= !last;
+        }
+        ready
+    }
+}
+
+impl Wake for Interest {
+    fn wake(self: Arc<Self>) {
+        if let Some(ready) = self.ready.upgrade() {
+            ready.push(self);
+        }
+    }
+}
+
+/// The `EPOLLEXCLUSIVE` interests in a file, of which only one is woken up
+/// at a time.
+struct ExclusiveGroup {
+    members: SpinNoIrq<VecDeque<Weak<Interest>>>,
+    /// Keeps the address of the file from being reused by another file.
+    _file: Weak<dyn FileLike>,
+}
+
+impl ExclusiveGroup {
+    /// Returns the group of the interests in `file`.
+    fn of(file: &Arc<dyn FileLike>) -> Arc<Self> {
+        let key = Arc::as_ptr(file) as *const () as usize;
+        let mut groups = EXCLUSIVE_GROUPS.lock();
+        groups.retain(|_, group| group.strong_count() > 0);
+        if let Some(group) = groups.get(&key).and_then(Weak::upgrade) {
+            return group;
+        }
+        let group = Arc::new(Self {
+            members: SpinNoIrq::new(VecDeque::new()),
+            _file: Arc::downgrade(file),
+        });
+        groups.insert(key, Arc::downgrade(&group));
+        group
+    }
+}
+
+impl Wake for ExclusiveGroup {
+    fn wake(self: Arc<Self>) {
+        let member = {
+            let mut members = self.members.lock();
+            members.retain(|m| {
+                m.upgrade()
+                    .is_some_and(|m| !m.removed.load(Ordering::Acquire))
+            });
+            // prefer an instance that a task is waiting on, and let the
+            // members take turns
+            let pos = members
+                .iter()
+                .position(|m| m.upgrade().is_some_and(|m| m.has_waiters()))
+                .unwrap_or(0);
+            members.remove(pos).and_then(|member| {
+                members.push_back(member.clone());
+                member.upgrade()
+            })
+        };
+        if let Some(member) = member {
+            member.wake();
+        }
+    }
+}
+
+/// The interests of an epoll instance that may be ready.
+struct ReadyList {
+    queue: SpinNoIrq<VecDeque<Arc<Interest>>>,
+    /// The number of tasks in `epoll_wait`.
+    waiters: AtomicUsize,
+    /// The wakers of the tasks in `epoll_wait`, and of the epoll instances
+    /// that this one is registered on.
+    wakers: PollWakers,
+}
+
+impl ReadyList {
+    fn push(&self, interest: Arc<Interest>) {
+        self.push_back(interest);
+        // wake up the tasks in `epoll_wait`, and the epoll instances watching
+        // this one, which may be waiting for an interest queued already
+        self.wakers.wake();
+    }
+
+    /// Puts back an interest taken from the list, without waking anyone.
+    fn push_back(&self, interest: Arc<Interest>) {
+        if !interest.queued.swap(true, Ordering::AcqRel) {
+            self.queue.lock().push_back(interest);
+        }
+    }
+}
+
+pub struct EpollInstance {
+    interests: Mutex<BTreeMap<c_int, Arc<Interest>>>,
+    ready: Arc<ReadyList>,
+}
+
 impl EpollInstance {
     // TODO: parse flags
     pub fn new(_flags: usize) -> Self {
         Self {
-            events: Mutex::new(BTreeMap::new()),
+            interests: Mutex::new(BTreeMap::new()),
+            ready: Arc::new(ReadyList {
+                queue: SpinNoIrq::new(VecDeque::new()),
+                waiters: AtomicUsize::new(0),
+                wakers: PollWakers::new(),
+            }),
         }
     }
 
@@ -36,72 +249,186 @@ impl EpollInstance {
             .map_err(|_| LinuxError::EINVAL)
     }
 
-    fn control(&self, op: usize, fd: usize, event: &ctypes::epoll_event) -> LinuxResult<usize> {
-        match get_file_like(fd as c_int) {
-            Ok(_) => {}
-            Err(e) => return Err(e),
+    /// Whether `target` is this instance, or is registered on it directly or
+    /// through other epoll instances.
+    fn reaches(&self, target: &EpollInstance) -> bool {
+        if core::ptr::eq(self, target) {
+            return true;
+        }
+        let files: Vec<_> = self
+            .interests
+            .lock()
+            .values()
+            .filter_map(|interest| interest.file())
+            .collect();
+        files.into_iter().any(|file| {
+            file.into_any()
+                .downcast::<EpollInstance>()
+                .is_ok_and(|epoll| epoll.reaches(target))
+        })
+    }
+
+    fn control(&self, op: u32, fd: c_int, event: Option<ctypes::epoll_event>) -> LinuxResult {
+        let file = get_file_like(fd)?;
+        let epoll = file.clone().into_any().downcast::<EpollInstance>().ok();
+        if epoll
+            .as_ref()
+            .is_some_and(|epoll| core::ptr::eq(epoll.as_ref(), self))
+        {
+            return Err(LinuxError::EINVAL);
         }
 
-        match op as u32 {
+        match op {
             ctypes::EPOLL_CTL_ADD => {
-                if let Entry::Vacant(e) = self.events.lock().entry(fd) {
-                    e.insert(*event);
-                } else {
-                    return Err(LinuxError::EEXIST);
+                let mut event = event.ok_or(LinuxError::EFAULT)?;
+                let exclusive = event.events & ctypes::EPOLLEXCLUSIVE != 0;
+                if exclusive && (event.events & !EXCLUSIVE_FLAGS != 0 || epoll.is_some()) {
+                    return Err(LinuxError::EINVAL);
                 }
+                if epoll.is_some_and(|epoll| epoll.reaches(self)) {
+                    return Err(LinuxError::ELOOP);
+                }
+                // errors and hang-ups are always reported
+                event.events |= ctypes::EPOLLERR | ctypes::EPOLLHUP;
+                let interest = Arc::new(Interest {
+                    file: Arc::downgrade(&file),
+                    event: SpinNoIrq::new(event),
+                    polled: AtomicBool::new(false),
+                    last: AtomicU32::new(0),
+                    queued: AtomicBool::new(false),
+                    removed: AtomicBool::new(false),
+                    ready: Arc::downgrade(&self.ready),
+                    group: exclusive.then(|| ExclusiveGroup::of(&file)),
+                });
+                match self.interests.lock().entry(fd) {
+                    Entry::Occupied(e) if e.get().is_for(&file) => {
+                        return Err(LinuxError::EEXIST);
+                    }
+                    // the file of the interest was closed
+                    Entry::Occupied(mut e) => {
+                        e.insert(interest.clone())
+                            .removed
+                            .store(true, Ordering::Release);
+                    }
+                    Entry::Vacant(e) => {
+                        e.insert(interest.clone());
+                    }
+                }
+                if let Some(group) = &interest.group {
+                    group.members.lock().push_back(Arc::downgrade(&interest));
+                }
+                // the file may be ready already
+                self.ready.push(interest);
             }
             ctypes::EPOLL_CTL_MOD => {
-                let mut events = self.events.lock();
-                if let Entry::Occupied(mut ocp) = events.entry(fd) {
-                    ocp.insert(*event);
-                } else {
-                    return Err(LinuxError::ENOENT);
+                let mut event = event.ok_or(LinuxError::EFAULT)?;
+                let interests = self.interests.lock();
+                let interest = interests
+                    .get(&fd)
+                    .filter(|interest| interest.is_for(&file))
+                    .ok_or(LinuxError::ENOENT)?;
+                if (event.events | interest.event.lock().events) & ctypes::EPOLLEXCLUSIVE != 0 {
+                    return Err(LinuxError::EINVAL);
                 }
+                event.events |= ctypes::EPOLLERR | ctypes::EPOLLHUP;
+                *interest.event.lock() = event;
+                interest.last.store(0, Ordering::Release);
+                // also re-enables an `EPOLLONESHOT` interest
+                self.ready.push(interest.clone());
             }
             ctypes::EPOLL_CTL_DEL => {
-                let mut events = self.events.lock();
-                if let Entry::Occupied(ocp) = events.entry(fd) {
-                    ocp.remove_entry();
-                } else {
-                    return Err(LinuxError::ENOENT);
+                let mut interests = self.interests.lock();
+                match interests.entry(fd) {
+                    Entry::Occupied(e) if e.get().is_for(&file) => {
+                        e.remove().removed.store(true, Ordering::Release);
+                    }
+                    _ => return Err(LinuxError::ENOENT),
                 }
             }
-            _ => {
-                return Err(LinuxError::EINVAL);
-            }
+            _ => return Err(LinuxError::EINVAL),
         }
-        Ok(0)
+        Ok(())
     }
 
-    fn poll_all(&self, events: &mut [ctypes::epoll_event]) -> LinuxResult<usize> {
-        let ready_list = self.events.lock();
-        let mut events_num = 0;
+    /// Whether any of the interests on the ready list is ready.
+    fn has_ready(&self) -> bool {
+        let queue: Vec<_> = self.ready.queue.lock().iter().cloned().collect();
+        queue.iter().any(|interest| {
+            interest
+                .file()
+                .is_some_and(|file| interest.ready_events(file.as_ref(), false) != 0)
+        })
+    }
 
-        for (infd, ev) in ready_list.iter() {
-            match get_file_like(*infd as c_int)?.poll() {
-                Err(_) => {
-                    if (ev.events & ctypes::EPOLLERR) != 0 {
-                        events[events_num].events = ctypes::EPOLLERR;
-                        events[events_num].data = ev.data;
-                        events_num += 1;
-                    }
-                }
-                Ok(state) => {
-                    if state.readable && (ev.events & ctypes::EPOLLIN != 0) {
-                        events[events_num].events = ctypes::EPOLLIN;
-                        events[events_num].data = ev.data;
-                        events_num += 1;
-                    }
+    /// Registers the wakers of the interests on the ready list, which may not
+    /// be armed yet, and returns whether all of their files can wake them up.
+    fn arm_ready(&self) -> bool {
+        let queue: Vec<_> = self.ready.queue.lock().iter().cloned().collect();
+        queue.iter().all(|interest| match interest.file() {
+            Some(file) if !interest.disabled() => interest.arm(file.as_ref()),
+            _ => true,
+        })
+    }
 
-                    if state.writable && (ev.events & ctypes::EPOLLOUT != 0) {
-                        events[events_num].events = ctypes::EPOLLOUT;
-                        events[events_num].data = ev.data;
-                        events_num += 1;
+    /// Fills `events` with the ready interests on the ready list, and returns
+    /// their number, and whether some of the files cannot wake up the
+    /// instance.
+    ///
+    /// The level-triggered interests that are ready, and those whose files
+    /// are polled, are put back on the list for the next call.
+    fn poll_ready(&self, events: &mut [ctypes::epoll_event]) -> (usize, bool) {
+        let mut num = 0;
+        let mut polled = false;
+        // the interests put back are not polled again
+        let len = self.ready.queue.lock().len();
+        for _ in 0..len {
+            if num == events.len() {
+                break;
+            }
+            let Some(interest) = self.ready.queue.lock().pop_front() else {
+                break;
+            };
+            interest.queued.store(false, Ordering::Release);
+            let Some(file) = interest.file() else {
+                self.forget(&interest);
+                continue;
+            };
+            if interest.disabled() {
+                continue;
+            }
+
+            // register the waker before polling, so that no wakeup is missed
+            let wakes = interest.arm(file.as_ref());
+            let ready = interest.ready_events(file.as_ref(), true);
+            let flags = {
+                let mut event = interest.event.lock();
+                if ready != 0 {
+                    events[num] = ctypes::epoll_event {
+                        events: ready,
+                        data: event.data,
+                    };
+                    num += 1;
+                    if event.events & ctypes::EPOLLONESHOT != 0 {
+                        event.events &= PRIVATE_FLAGS;
                     }
                 }
+                event.events
+            };
+            if flags & !PRIVATE_FLAGS == 0 {
+                continue;
+            }
+            if !wakes || (ready != 0 && flags & ctypes::EPOLLET == 0) {
+                polled |= !wakes;
+                self.ready.push_back(interest);
             }
         }
-        Ok(events_num)
+        (num, polled)
+    }
+
+    /// Removes an interest whose file is closed.
+    fn forget(&self, interest: &Arc<Interest>) {
+        let mut interests = self.interests.lock();
+        interests.retain(|_, i| !Arc::ptr_eq(i, interest));
     }
 }
 
@@ -129,12 +456,21 @@ impl FileLike for EpollInstance {
     }
 
     fn poll(&self) -> LinuxResult<axio::PollState> {
-        Err(LinuxError::ENOSYS)
+        // an epoll instance is readable if `epoll_wait` would not block
+        Ok(axio::PollState {
+            readable: self.has_ready(),
+            writable: false,
+        })
     }
 
     fn set_nonblocking(&self, _nonblocking: bool) -> LinuxResult {
         Ok(())
     }
+
+    fn register_waker(&self, waker: &Waker) -> bool {
+        self.ready.wakers.register(waker);
+        self.arm_ready()
+    }
 }
 
 /// Creates a new epoll instance.
@@ -152,6 +488,8 @@ pub fn sys_epoll_create(size: c_int) -> c_int {
 }
 
 /// Control interface for an epoll file descriptor
+///
+/// `EPOLLET`, `EPOLLONESHOT`, `EPOLLEXCLUSIVE` and `EPOLLRDHUP` are supported.
 pub unsafe fn sys_epoll_ctl(
     epfd: c_int,
     op: c_int,
@@ -160,10 +498,13 @@ pub unsafe fn sys_epoll_ctl(
 ) -> c_int {
     debug!("sys_epoll_ctl <= epfd: {} op: {} fd: {}", epfd, op, fd);
     syscall_body!(sys_epoll_ctl, {
-        let ret = unsafe {
-            EpollInstance::from_fd(epfd)?.control(op as usize, fd as usize, &(*event))? as c_int
+        // the event is ignored by `EPOLL_CTL_DEL`
+        let event = match op as u32 {
+            ctypes::EPOLL_CTL_DEL => None,
+            _ => Some(*unsafe { event.as_ref() }.ok_or(LinuxError::EFAULT)?),
         };
-        Ok(ret)
+        EpollInstance::from_fd(epfd)?.control(op as u32, fd, event)?;
+        Ok(0)
     })
 }
 
@@ -184,22 +525,242 @@ pub unsafe fn sys_epoll_wait(
             return Err(LinuxError::EINVAL);
         }
         let events = unsafe { core::slice::from_raw_parts_mut(events, maxevents as usize) };
-        let deadline =
-            (!timeout.is_negative()).then(|| wall_time() + Duration::from_millis(timeout as u64));
+        let deadline = (!timeout.is_negative())
+            .then(|| monotonic_time() + Duration::from_millis(timeout as u64));
         let epoll_instance = EpollInstance::from_fd(epfd)?;
-        loop {
-            #[cfg(feature = "net")]
-            axnet::poll_interfaces();
-            let events_num = epoll_instance.poll_all(events)?;
-            if events_num > 0 {
-                return Ok(events_num as c_int);
-            }
+        epoll_instance.ready.waiters.fetch_add(1, Ordering::AcqRel);
+        let res = super::poll_until(deadline, |waker| {
+            epoll_instance.ready.wakers.register(waker);
+            let (num, polled) = epoll_instance.poll_ready(events);
+            Ok((num, !polled))
+        });
+        epoll_instance.ready.waiters.fetch_sub(1, Ordering::AcqRel);
+        res
+    })
+}
 
-            if deadline.is_some_and(|ddl| wall_time() >= ddl) {
-                debug!("    timeout!");
-                return Ok(0);
-            }
-            crate::sys_sched_yield();
+#[cfg(all(test, feature = "pipe"))]
+mod tests {
+    use super::*;
+    use crate::imp::fd_ops::sys_close;
+    use crate::imp::pipe::sys_pipe;
+
+    fn pipe() -> [c_int; 2] {
+        let mut fds = [0; 2];
+        assert_eq!(sys_pipe(&mut fds), 0);
+        fds
+    }
+
+    fn err(e: LinuxError) -> c_int {
+        -e.code()
+    }
+
+    fn ctl(epfd: c_int, op: u32, fd: c_int, events: u32) -> c_int {
+        let mut event = ctypes::epoll_event {
+            events,
+            data: ctypes::epoll_data { u64: fd as u64 },
+        };
+        unsafe { sys_epoll_ctl(epfd, op as c_int, fd, &mut event) }
+    }
+
+    /// Returns the ready events, with the fds registered by `ctl`.
+    fn wait(epfd: c_int, timeout: c_int) -> Vec<(c_int, u32)> {
+        let mut events = [ctypes::epoll_event {
+            events: 0,
+            data: ctypes::epoll_data { u64: 0 },
+        }; 4];
+        let num = unsafe { sys_epoll_wait(epfd, events.as_mut_ptr(), 4, timeout) };
+        assert!(num >= 0, "epoll_wait failed: {num}");
+        events[..num as usize]
+            .iter()
+            .map(|event| (unsafe { event.data.u64 } as c_int, event.events))
+            .collect()
+    }
+
+    fn write(fd: c_int, buf: &[u8]) {
+        assert_eq!(get_file_like(fd).unwrap().write(buf), Ok(buf.len()));
+    }
+
+    fn read(fd: c_int, len: usize) {
+        let mut buf = vec![0; len];
+        assert_eq!(get_file_like(fd).unwrap().read(&mut buf), Ok(len));
+    }
+
+    #[test]
+    fn test_epoll_level_edge() {
+        let [rfd, wfd] = pipe();
+        let lt = sys_epoll_create(1);
+        let et = sys_epoll_create(1);
+        assert_eq!(ctl(lt, ctypes::EPOLL_CTL_ADD, rfd, ctypes::EPOLLIN), 0);
+        let events = ctypes::EPOLLIN | ctypes::EPOLLET;
+        assert_eq!(ctl(et, ctypes::EPOLL_CTL_ADD, rfd, events), 0);
+        assert_eq!(
+            ctl(lt, ctypes::EPOLL_CTL_ADD, rfd, ctypes::EPOLLIN),
+            err(LinuxError::EEXIST)
+        );
+        assert_eq!(wait(lt, 0), []);
+        assert_eq!(wait(et, 0), []);
+
+        // level-triggered interests are reported while the pipe is readable
+        write(wfd, b"hello");
+        assert_eq!(wait(lt, 0), [(rfd, ctypes::EPOLLIN)]);
+        assert_eq!(wait(lt, 0), [(rfd, ctypes::EPOLLIN)]);
+        // but edge-triggered ones only once for each write
+        assert_eq!(wait(et, 0), [(rfd, ctypes::EPOLLIN)]);
+        assert_eq!(wait(et, 0), []);
+        write(wfd, b"world");
+        assert_eq!(wait(et, 0), [(rfd, ctypes::EPOLLIN)]);
+        assert_eq!(wait(et, 0), []);
+
+        read(rfd, 10);
+        assert_eq!(wait(lt, 0), []);
+        assert_eq!(ctl(lt, ctypes::EPOLL_CTL_DEL, rfd, 0), 0);
+        assert_eq!(
+            ctl(lt, ctypes::EPOLL_CTL_DEL, rfd, 0),
+            err(LinuxError::ENOENT)
+        );
+        for fd in [lt, et, rfd, wfd] {
+            assert_eq!(sys_close(fd), 0);
         }
-    })
+    }
+
+    #[test]
+    fn test_epoll_oneshot() {
+        let [rfd, wfd] = pipe();
+        let epfd = sys_epoll_create(1);
+        let events = ctypes::EPOLLIN | ctypes::EPOLLONESHOT;
+        assert_eq!(ctl(epfd, ctypes::EPOLL_CTL_ADD, rfd, events), 0);
+        write(wfd, b"x");
+        assert_eq!(wait(epfd, 0), [(rfd, ctypes::EPOLLIN)]);
+        // disabled until modified, even though the pipe is still readable
+        assert_eq!(wait(epfd, 0), []);
+        write(wfd, b"y");
+        assert_eq!(wait(epfd, 0), []);
+        assert_eq!(ctl(epfd, ctypes::EPOLL_CTL_MOD, rfd, events), 0);
+        assert_eq!(wait(epfd, 0), [(rfd, ctypes::EPOLLIN)]);
+        assert_eq!(wait(epfd, 0), []);
+        for fd in [epfd, rfd, wfd] {
+            assert_eq!(sys_close(fd), 0);
+        }
+    }
+
+    #[test]
+    fn test_epoll_exclusive() {
+        let [rfd, wfd] = pipe();
+        let ep1 = sys_epoll_create(1);
+        let ep2 = sys_epoll_create(1);
+        let events = ctypes::EPOLLIN | ctypes::EPOLLEXCLUSIVE;
+        assert_eq!(ctl(ep1, ctypes::EPOLL_CTL_ADD, rfd, events), 0);
+        assert_eq!(ctl(ep2, ctypes::EPOLL_CTL_ADD, rfd, events), 0);
+        assert_eq!(wait(ep1, 0), []);
+        assert_eq!(wait(ep2, 0), []);
+
+        // only one of the instances is woken up
+        write(wfd, b"x");
+        assert_eq!(wait(ep1, 0).len() + wait(ep2, 0).len(), 1);
+
+        // and it cannot be combined with `EPOLLONESHOT` or modified
+        let ep3 = sys_epoll_create(1);
+        let oneshot = events | ctypes::EPOLLONESHOT;
+        assert_eq!(
+            ctl(ep3, ctypes::EPOLL_CTL_ADD, rfd, oneshot),
+            err(LinuxError::EINVAL)
+        );
+        assert_eq!(
+            ctl(ep1, ctypes::EPOLL_CTL_MOD, rfd, ctypes::EPOLLIN),
+            err(LinuxError::EINVAL)
+        );
+        for fd in [ep1, ep2, ep3, rfd, wfd] {
+            assert_eq!(sys_close(fd), 0);
+        }
+    }
+
+    #[test]
+    fn test_epoll_hup() {
+        let [rfd, wfd] = pipe();
+        let epfd = sys_epoll_create(1);
+        assert_eq!(ctl(epfd, ctypes::EPOLL_CTL_ADD, rfd, 0), 0);
+        assert_eq!(wait(epfd, 0), []);
+        // hang-ups are reported even if not requested
+        assert_eq!(sys_close(wfd), 0);
+        assert_eq!(wait(epfd, 0), [(rfd, ctypes::EPOLLHUP)]);
+        assert_eq!(sys_close(epfd), 0);
+        assert_eq!(sys_close(rfd), 0);
+    }
+
+    #[cfg(feature = "unix")]
+    #[test]
+    fn test_epoll_rdhup() {
+        use crate::imp::net::{sys_shutdown, sys_socketpair};
+
+        let mut sv = [0; 2];
+        let (domain, socktype) = (ctypes::AF_UNIX as c_int, ctypes::SOCK_STREAM as c_int);
+        assert_eq!(sys_socketpair(domain, socktype, 0, &mut sv), 0);
+        let epfd = sys_epoll_create(1);
+        let events = ctypes::EPOLLIN | ctypes::EPOLLRDHUP;
+        assert_eq!(ctl(epfd, ctypes::EPOLL_CTL_ADD, sv[1], events), 0);
+        assert_eq!(wait(epfd, 0), []);
+        assert_eq!(sys_shutdown(sv[0], 2 /* SHUT_RDWR */), 0);
+        let hup = ctypes::EPOLLIN | ctypes::EPOLLRDHUP | ctypes::EPOLLHUP;
+        assert_eq!(wait(epfd, 0), [(sv[1], hup)]);
+        for fd in [epfd, sv[0], sv[1]] {
+            assert_eq!(sys_close(fd), 0);
+        }
+    }
+
+    #[test]
+    fn test_epoll_nested() {
+        let [rfd, wfd] = pipe();
+        let inner = sys_epoll_create(1);
+        let outer = sys_epoll_create(1);
+        assert_eq!(ctl(inner, ctypes::EPOLL_CTL_ADD, rfd, ctypes::EPOLLIN), 0);
+        assert_eq!(ctl(outer, ctypes::EPOLL_CTL_ADD, inner, ctypes::EPOLLIN), 0);
+        assert_eq!(wait(outer, 0), []);
+
+        // the outer instance is ready when the inner one is
+        write(wfd, b"x");
+        assert_eq!(wait(outer, 0), [(inner, ctypes::EPOLLIN)]);
+        assert_eq!(wait(inner, 0), [(rfd, ctypes::EPOLLIN)]);
+
+        // but no instance may watch itself, directly or not
+        assert_eq!(
+            ctl(inner, ctypes::EPOLL_CTL_ADD, inner, ctypes::EPOLLIN),
+            err(LinuxError::EINVAL)
+        );
+        assert_eq!(
+            ctl(inner, ctypes::EPOLL_CTL_ADD, outer, ctypes::EPOLLIN),
+            err(LinuxError::ELOOP)
+        );
+        for fd in [outer, inner, rfd, wfd] {
+            assert_eq!(sys_close(fd), 0);
+        }
+    }
+
+    #[cfg(feature = "multitask")]
+    #[test]
+    fn test_epoll_wakeup() {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(axtask::init_scheduler);
+
+        // sleeps until the pipe is written
+        let [rfd, wfd] = pipe();
+        let epfd = sys_epoll_create(1);
+        let events = ctypes::EPOLLIN | ctypes::EPOLLET;
+        assert_eq!(ctl(epfd, ctypes::EPOLL_CTL_ADD, rfd, events), 0);
+        assert_eq!(wait(epfd, 0), []);
+        axtask::spawn(move || {
+            axtask::yield_now();
+            write(wfd, b"x");
+        });
+        assert_eq!(wait(epfd, -1), [(rfd, ctypes::EPOLLIN)]);
+
+        // and until it is hung up
+        axtask::spawn(move || {
+            axtask::yield_now();
+            assert_eq!(sys_close(wfd), 0);
+        });
+        assert_eq!(wait(epfd, -1), [(rfd, ctypes::EPOLLIN | ctypes::EPOLLHUP)]);
+        assert_eq!(sys_close(epfd), 0);
+        assert_eq!(sys_close(rfd), 0);
+    }
 }
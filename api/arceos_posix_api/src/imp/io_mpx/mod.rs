@@ -1,16 +1,77 @@
 //! I/O multiplexing:
 //!
 //! * [`select`](select::sys_select)
+//! * [`poll`](poll::sys_poll)
+//! * [`ppoll`](poll::sys_ppoll)
 //! * [`epoll_create`](epoll::sys_epoll_create)
 //! * [`epoll_ctl`](epoll::sys_epoll_ctl)
 //! * [`epoll_wait`](epoll::sys_epoll_wait)
 
+use core::task::Waker;
+use core::time::Duration;
+
+use axerrno::LinuxResult;
+use axhal::time::{TimeValue, monotonic_time};
+
+use super::fd_ops::FileWaiter;
+
 #[cfg(feature = "epoll")]
 mod epoll;
+#[cfg(feature = "poll")]
+mod poll;
 #[cfg(feature = "select")]
 mod select;
 
 #[cfg(feature = "epoll")]
 pub use self::epoll::{sys_epoll_create, sys_epoll_ctl, sys_epoll_wait};
+#[cfg(feature = "poll")]
+pub use self::poll::{sys_poll, sys_ppoll};
 #[cfg(feature = "select")]
 pub use self::select::sys_select;
+
+/// The longest sleep between two polls of files that cannot wake up the
+/// pollers when they become ready, such as the console, which raises no
+/// interrupt on input.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Calls `poll_all` until it returns a positive number of ready files, or
+/// returns 0 once `deadline` of the monotonic clock is reached.
+///
+/// `poll_all` registers the given waker on the files before polling them, and
+/// also returns whether all of them can wake it up. The task then sleeps until
+/// the waker is woken, or for at most [`POLL_INTERVAL`] if some files have to
+/// be polled. With the `signal` feature, it fails with `EINTR` once
+/// interrupted by a signal.
+fn poll_until<F>(deadline: Option<TimeValue>, mut poll_all: F) -> LinuxResult<usize>
+where
+    F: FnMut(&Waker) -> LinuxResult<(usize, bool)>,
+{
+    let waiter = FileWaiter::new();
+    let waker = Waker::from(waiter.clone());
+    loop {
+        // reset before polling, so that no wakeup is missed
+        waiter.reset();
+        #[cfg(feature = "signal")]
+        crate::imp::signal::register_interrupt_waker(&waker);
+        #[cfg(feature = "net")]
+        axnet::poll_interfaces();
+        let (res, wakes) = poll_all(&waker)?;
+        if res > 0 {
+            return Ok(res);
+        }
+
+        let now = monotonic_time();
+        if deadline.is_some_and(|ddl| now >= ddl) {
+            debug!("    timeout!");
+            return Ok(0);
+        }
+        #[cfg(feature = "signal")]
+        crate::imp::signal::check_interrupted(false)?;
+        let interval = (!wakes).then_some(POLL_INTERVAL);
+        let timeout = match (deadline, interval) {
+            (Some(ddl), Some(interval)) => Some((ddl - now).min(interval)),
+            (ddl, interval) => ddl.map(|ddl| ddl - now).or(interval),
+        };
+        waiter.wait(timeout);
+    }
+}
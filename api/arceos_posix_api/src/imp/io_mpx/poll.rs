@@ -0,0 +1,249 @@
+//! `poll` implementation.
+//!
+//! `POLLPRI` is never reported, as no file has urgent or out-of-band data.
+
+use core::ffi::c_int;
+use core::task::Waker;
+use core::time::Duration;
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::time::{TimeValue, monotonic_time};
+
+use crate::ctypes;
+use crate::imp::fd_ops::{AX_FILE_LIMIT, get_file_like};
+
+/// The events reported even if they are not requested.
+const ALWAYS_EVENTS: u32 = ctypes::POLLERR | ctypes::POLLHUP | ctypes::POLLNVAL;
+
+/// Registers `waker` on each file of `fds` and polls it once, and returns the
+/// number of files with non-zero `revents`, and whether all the files can wake
+/// up `waker`.
+fn poll_all(fds: &mut [ctypes::pollfd], waker: &Waker) -> (usize, bool) {
+    let mut res_num = 0;
+    let mut wakes = true;
+    for pfd in fds.iter_mut() {
+        pfd.revents = 0;
+        // negative fds are ignored, which is used to skip some entries
+        if pfd.fd < 0 {
+            continue;
+        }
+        let events = pfd.events as u32 | ALWAYS_EVENTS;
+        let revents = match get_file_like(pfd.fd) {
+            Err(_) => ctypes::POLLNVAL,
+            Ok(f) => {
+                // register the waker before polling, so that no wakeup is missed
+                wakes &= f.register_waker(waker);
+                let mut revents = match f.poll() {
+                    Ok(state) => {
+                        let mut revents = 0;
+                        if state.readable {
+                            revents |= ctypes::POLLIN | ctypes::POLLRDNORM;
+                        }
+                        if state.writable {
+                            revents |= ctypes::POLLOUT | ctypes::POLLWRNORM;
+                        }
+                        revents
+                    }
+                    Err(e) => {
+                        debug!("    error: {} {:?}", pfd.fd, e);
+                        ctypes::POLLERR
+                    }
+                };
+                if f.hung_up() {
+                    revents |= ctypes::POLLHUP;
+                }
+                if events & ctypes::POLLRDHUP != 0 && f.peer_closed() {
+                    revents |= ctypes::POLLRDHUP;
+                }
+                revents & events
+            }
+        };
+        if revents != 0 {
+            pfd.revents = revents as _;
+            res_num += 1;
+        }
+    }
+    (res_num, wakes)
+}
+
+/// Waits until one of the files of `fds` is ready, or until `deadline`.
+fn poll_fds(
+    fds: *mut ctypes::pollfd,
+    nfds: ctypes::nfds_t,
+    deadline: Option<TimeValue>,
+) -> LinuxResult<usize> {
+    if nfds as usize > AX_FILE_LIMIT {
+        return Err(LinuxError::EINVAL);
+    }
+    let fds: &mut [ctypes::pollfd] = if nfds == 0 {
+        &mut []
+    } else {
+        crate::utils::check_null_mut_ptr(fds)?;
+        unsafe { core::slice::from_raw_parts_mut(fds, nfds as usize) }
+    };
+    super::poll_until(deadline, |waker| Ok(poll_all(fds, waker)))
+}
+
+/// Wait for some event on a set of file descriptors, for `timeout`
+/// milliseconds if it is not negative.
+pub unsafe fn sys_poll(fds: *mut ctypes::pollfd, nfds: ctypes::nfds_t, timeout: c_int) -> c_int {
+    debug!("sys_poll <= {:#x}, {}, {}", fds as usize, nfds, timeout);
+    syscall_body!(sys_poll, {
+        let deadline = (!timeout.is_negative())
+            .then(|| monotonic_time() + Duration::from_millis(timeout as u64));
+        poll_fds(fds, nfds, deadline)
+    })
+}
+
+/// Wait for some event on a set of file descriptors, for `timeout` if it is
+/// not null, with the signal mask replaced by `sigmask` if it is not null.
+pub unsafe fn sys_ppoll(
+    fds: *mut ctypes::pollfd,
+    nfds: ctypes::nfds_t,
+    timeout: *const ctypes::timespec,
+    sigmask: *const ctypes::sigset_t,
+) -> c_int {
+    debug!(
+        "sys_ppoll <= {:#x}, {}, {:#x}",
+        fds as usize, nfds, timeout as usize
+    );
+    syscall_body!(sys_ppoll, {
+        let deadline = match unsafe { timeout.as_ref() } {
+            Some(ts) if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) => {
+                return Err(LinuxError::EINVAL);
+            }
+            Some(ts) => Some(monotonic_time() + Duration::from(*ts)),
+            None => None,
+        };
+        #[cfg(feature = "signal")]
+        let _mask = unsafe { sigmask.as_ref() }.map(crate::imp::signal::MaskGuard::new);
+        #[cfg(not(feature = "signal"))]
+        let _ = sigmask; // no signal is ever sent
+        poll_fds(fds, nfds, deadline)
+    })
+}
+
+#[cfg(all(test, feature = "pipe"))]
+mod tests {
+    use core::ptr;
+
+    use super::*;
+    use crate::imp::fd_ops::sys_close;
+    use crate::imp::pipe::sys_pipe;
+
+    fn pipe() -> [c_int; 2] {
+        let mut fds = [0; 2];
+        assert_eq!(sys_pipe(&mut fds), 0);
+        fds
+    }
+
+    fn pollfd(fd: c_int, events: u32) -> ctypes::pollfd {
+        ctypes::pollfd {
+            fd,
+            events: events as _,
+            revents: 0,
+        }
+    }
+
+    fn err(e: LinuxError) -> c_int {
+        -e.code()
+    }
+
+    #[test]
+    fn test_poll_pipe() {
+        let [rfd, wfd] = pipe();
+        let mut fds = [
+            pollfd(rfd, ctypes::POLLIN),
+            pollfd(wfd, ctypes::POLLOUT),
+            pollfd(-1, ctypes::POLLIN),
+        ];
+        // only the write end is ready, and negative fds are skipped
+        assert_eq!(unsafe { sys_poll(fds.as_mut_ptr(), 3, 0) }, 1);
+        assert_eq!(fds[0].revents, 0);
+        assert_eq!(fds[1].revents as u32, ctypes::POLLOUT);
+        assert_eq!(fds[2].revents, 0);
+
+        assert_eq!(get_file_like(wfd).unwrap().write(b"hello"), Ok(5));
+        assert_eq!(unsafe { sys_poll(fds.as_mut_ptr(), 3, 0) }, 2);
+        assert_eq!(fds[0].revents as u32, ctypes::POLLIN);
+
+        // hang-ups are reported even if not requested
+        assert_eq!(sys_close(wfd), 0);
+        fds[0].events = 0;
+        assert_eq!(unsafe { sys_poll(fds.as_mut_ptr(), 2, 0) }, 2);
+        assert_eq!(fds[0].revents as u32, ctypes::POLLHUP);
+        assert_eq!(fds[1].revents as u32, ctypes::POLLNVAL);
+        assert_eq!(sys_close(rfd), 0);
+    }
+
+    #[test]
+    fn test_ppoll() {
+        let [rfd, wfd] = pipe();
+        let mut fds = [pollfd(rfd, ctypes::POLLIN | ctypes::POLLPRI)];
+        let mut ts = ctypes::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        };
+        assert_eq!(
+            unsafe { sys_ppoll(fds.as_mut_ptr(), 1, &ts, ptr::null()) },
+            0
+        );
+        assert_eq!(fds[0].revents, 0);
+        assert_eq!(
+            unsafe { sys_ppoll(ptr::null_mut(), 0, &ts, ptr::null()) },
+            0
+        );
+
+        assert_eq!(get_file_like(wfd).unwrap().write(b"x"), Ok(1));
+        assert_eq!(
+            unsafe { sys_ppoll(fds.as_mut_ptr(), 1, &ts, ptr::null()) },
+            1
+        );
+        assert_eq!(fds[0].revents as u32, ctypes::POLLIN);
+
+        ts.tv_nsec = 1_000_000_000;
+        assert_eq!(
+            unsafe { sys_ppoll(fds.as_mut_ptr(), 1, &ts, ptr::null()) },
+            err(LinuxError::EINVAL)
+        );
+        assert_eq!(
+            unsafe { sys_poll(ptr::null_mut(), 1, 0) },
+            err(LinuxError::EFAULT)
+        );
+        let nfds = AX_FILE_LIMIT as ctypes::nfds_t + 1;
+        assert_eq!(
+            unsafe { sys_poll(fds.as_mut_ptr(), nfds, 0) },
+            err(LinuxError::EINVAL)
+        );
+        assert_eq!(sys_close(rfd), 0);
+        assert_eq!(sys_close(wfd), 0);
+    }
+
+    #[cfg(feature = "multitask")]
+    #[test]
+    fn test_poll_wakeup() {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(axtask::init_scheduler);
+
+        // sleeps until the pipe is written
+        let [rfd, wfd] = pipe();
+        axtask::spawn(move || {
+            axtask::yield_now();
+            assert_eq!(get_file_like(wfd).unwrap().write(b"x"), Ok(1));
+        });
+        let mut fds = [pollfd(rfd, ctypes::POLLIN)];
+        assert_eq!(unsafe { sys_poll(fds.as_mut_ptr(), 1, -1) }, 1);
+        assert_eq!(fds[0].revents as u32, ctypes::POLLIN);
+        let mut buf = [0; 1];
+        assert_eq!(get_file_like(rfd).unwrap().read(&mut buf), Ok(1));
+
+        // and until it is hung up
+        axtask::spawn(move || {
+            axtask::yield_now();
+            assert_eq!(sys_close(wfd), 0);
+        });
+        assert_eq!(unsafe { sys_poll(fds.as_mut_ptr(), 1, -1) }, 1);
+        assert_eq!(fds[0].revents as u32, ctypes::POLLIN | ctypes::POLLHUP);
+        assert_eq!(sys_close(rfd), 0);
+    }
+}
@@ -1,7 +1,8 @@
 use core::ffi::c_int;
+use core::task::Waker;
 
 use axerrno::{LinuxError, LinuxResult};
-use axhal::time::wall_time;
+use axhal::time::monotonic_time;
 
 use crate::{ctypes, imp::fd_ops::get_file_like};
 
@@ -46,17 +47,21 @@ impl FdSets {
         Self { nfds, bits }
     }
 
+    /// Registers `waker` on each file and polls it once, and returns the
+    /// number of ready events, and whether all the files can wake up `waker`.
     fn poll_all(
         &self,
         res_read_fds: *mut ctypes::fd_set,
         res_write_fds: *mut ctypes::fd_set,
         res_except_fds: *mut ctypes::fd_set,
-    ) -> LinuxResult<usize> {
+        waker: &Waker,
+    ) -> LinuxResult<(usize, bool)> {
         let mut read_bits_ptr = self.bits.as_ptr();
         let mut write_bits_ptr = unsafe { read_bits_ptr.add(FD_SETSIZE_USIZES) };
         let mut execpt_bits_ptr = unsafe { read_bits_ptr.add(FD_SETSIZE_USIZES * 2) };
         let mut i = 0;
         let mut res_num = 0;
+        let mut wakes = true;
         while i < self.nfds {
             let read_bits = unsafe { *read_bits_ptr };
             let write_bits = unsafe { *write_bits_ptr };
@@ -80,7 +85,10 @@ impl FdSets {
                     continue;
                 }
                 let fd = i + j;
-                match get_file_like(fd as _)?.poll() {
+                let file = get_file_like(fd as _)?;
+                // register the waker before polling, so that no wakeup is missed
+                wakes &= file.register_waker(waker);
+                match file.poll() {
                     Ok(state) => {
                         if state.readable && read_bits & bit != 0 {
                             unsafe { set_fd_set(res_read_fds, fd) };
@@ -103,7 +111,7 @@ impl FdSets {
             }
             i += BITS_PER_USIZE;
         }
-        Ok(res_num)
+        Ok((res_num, wakes))
     }
 }
 
@@ -124,7 +132,7 @@ pub unsafe fn sys_select(
             return Err(LinuxError::EINVAL);
         }
         let nfds = (nfds as usize).min(FD_SETSIZE);
-        let deadline = unsafe { timeout.as_ref().map(|t| wall_time() + (*t).into()) };
+        let deadline = unsafe { timeout.as_ref().map(|t| monotonic_time() + (*t).into()) };
         let fd_sets = FdSets::from(nfds, readfds, writefds, exceptfds);
 
         unsafe {
@@ -133,20 +141,9 @@ pub unsafe fn sys_select(
             zero_fd_set(exceptfds, nfds);
         }
 
-        loop {
-            #[cfg(feature = "net")]
-            axnet::poll_interfaces();
-            let res = fd_sets.poll_all(readfds, writefds, exceptfds)?;
-            if res > 0 {
-                return Ok(res);
-            }
-
-            if deadline.is_some_and(|ddl| wall_time() >= ddl) {
-                debug!("    timeout!");
-                return Ok(0);
-            }
-            crate::sys_sched_yield();
-        }
+        super::poll_until(deadline, |waker| {
+            fd_sets.poll_all(readfds, writefds, exceptfds, waker)
+        })
     })
 }
 
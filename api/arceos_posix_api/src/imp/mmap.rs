@@ -0,0 +1,890 @@
+//! Memory mappings in the kernel address space.
+//!
+//! Anonymous mappings are lazily allocated, while the pages of file mappings
+//! are read from the file by `mmap` and `mremap`, since the page fault handler
+//! cannot do file I/O.
+//!
+//! Pages of `MAP_SHARED` file mappings are mapped to the page cache of the
+//! file if it has one, so that they are shared with the reads and writes of
+//! the file. They are mapped read-only until they are written, to track the
+//! dirty pages, which are written back by `msync` and `munmap`.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::{vec, vec::Vec};
+use core::ffi::{c_int, c_void};
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::mem::{PAGE_SIZE_4K, PhysAddr, VirtAddr};
+use axhal::paging::MappingFlags;
+use axhal::trap::{PAGE_FAULT, register_trap_handler};
+use kspin::SpinNoPreempt;
+use memory_addr::{VirtAddrRange, align_down_4k, align_up_4k, is_aligned_4k};
+
+use crate::ctypes;
+
+/// The start of the mappings. The lower half of the kernel address space is
+/// left to the linear mapping of the physical memory.
+const MMAP_BASE: usize =
+    align_down_4k(axconfig::plat::KERNEL_ASPACE_BASE + axconfig::plat::KERNEL_ASPACE_SIZE / 2);
+/// The end of the mappings.
+const MMAP_END: usize = axconfig::plat::KERNEL_ASPACE_BASE + axconfig::plat::KERNEL_ASPACE_SIZE;
+
+#[cfg(feature = "fs")]
+type MappedFile = alloc::sync::Arc<super::fs::File>;
+#[cfg(not(feature = "fs"))]
+type MappedFile = core::convert::Infallible;
+
+/// The file backing a mapping.
+#[derive(Clone)]
+struct FileMapping {
+    file: MappedFile,
+    /// The file offset of the start of the mapping.
+    offset: u64,
+    /// Whether the changes are written back to the file (`MAP_SHARED`).
+    shared: bool,
+}
+
+/// A mapping created by `mmap`, whose range is also an area of the kernel
+/// address space.
+struct Mapping {
+    start: usize,
+    end: usize,
+    flags: MappingFlags,
+    file: Option<FileMapping>,
+    /// Whether each page of a `MAP_SHARED` file mapping is written since it
+    /// is mapped or written back, or empty for other mappings. It is
+    /// allocated with the mapping, so that the page fault handler marks the
+    /// pages without allocating.
+    dirty: Vec<bool>,
+    /// The pages of a `MAP_SHARED` file mapping that are mapped to the page
+    /// cache of the file, rather than to frames of the address space.
+    cached: BTreeSet<usize>,
+}
+
+impl Mapping {
+    fn new(start: usize, end: usize, flags: MappingFlags, file: Option<FileMapping>) -> Self {
+        let mut map = Self {
+            start,
+            end,
+            flags,
+            file,
+            dirty: Vec::new(),
+            cached: BTreeSet::new(),
+        };
+        map.resize(end);
+        map
+    }
+
+    /// Moves the end of the mapping to `end`, and resizes the dirty flags
+    /// along with it.
+    fn resize(&mut self, end: usize) {
+        self.end = end;
+        if self.file.as_ref().is_some_and(|file| file.shared) {
+            self.dirty.resize((end - self.start) / PAGE_SIZE_4K, false);
+        }
+    }
+
+    /// Returns whether the page at `vaddr` is dirty.
+    fn is_dirty(&self, vaddr: usize) -> bool {
+        let index = (vaddr - self.start) / PAGE_SIZE_4K;
+        self.dirty.get(index).copied().unwrap_or(false)
+    }
+
+    /// Marks the page at `vaddr` of a `MAP_SHARED` file mapping as dirty or
+    /// clean.
+    fn set_dirty(&mut self, vaddr: usize, dirty: bool) {
+        let index = (vaddr - self.start) / PAGE_SIZE_4K;
+        if let Some(flag) = self.dirty.get_mut(index) {
+            *flag = dirty;
+        }
+    }
+
+    /// Returns the first dirty page in `[start, end)`, if any.
+    fn next_dirty(&self, start: usize, end: usize) -> Option<usize> {
+        (start.max(self.start)..end.min(self.end))
+            .step_by(PAGE_SIZE_4K)
+            .find(|&vaddr| self.is_dirty(vaddr))
+    }
+
+    /// Returns the flags of the page at `vaddr`, without `WRITE` if it is a
+    /// clean page of a `MAP_SHARED` file mapping, to catch the first write.
+    fn page_flags(&self, vaddr: usize) -> MappingFlags {
+        match &self.file {
+            Some(file) if file.shared && !self.is_dirty(vaddr) => self.flags - MappingFlags::WRITE,
+            _ => self.flags,
+        }
+    }
+}
+
+/// The mappings created by `mmap`, indexed by their start addresses.
+///
+/// It is never locked across file I/O, and the page fault handler takes it,
+/// so preemption is disabled while it is locked. It is locked before the
+/// kernel address space if both are locked.
+static MAPPINGS: SpinNoPreempt<BTreeMap<usize, Mapping>> = SpinNoPreempt::new(BTreeMap::new());
+
+/// Returns the file of `fd` to map, which must be opened for reading, and
+/// also for writing if `write` is true.
+#[cfg(feature = "fs")]
+fn open_file(fd: c_int, write: bool) -> LinuxResult<MappedFile> {
+    let file = super::fs::File::from_fd(fd).map_err(|e| match e {
+        LinuxError::EINVAL => LinuxError::ENODEV,
+        e => e,
+    })?;
+    check_access(&file, write)?;
+    Ok(file)
+}
+
+#[cfg(not(feature = "fs"))]
+fn open_file(_fd: c_int, _write: bool) -> LinuxResult<MappedFile> {
+    Err(LinuxError::ENODEV)
+}
+
+/// Fails with `EACCES` if the file is not opened for reading, or for
+/// writing if `write` is true.
+#[cfg(feature = "fs")]
+fn check_access(file: &MappedFile, write: bool) -> LinuxResult {
+    Ok(file.inner().lock().check_access(write)?)
+}
+
+#[cfg(not(feature = "fs"))]
+fn check_access(file: &MappedFile, _write: bool) -> LinuxResult {
+    match *file {}
+}
+
+#[cfg(feature = "fs")]
+fn same_file(a: &MappedFile, b: &MappedFile) -> bool {
+    alloc::sync::Arc::ptr_eq(a, b)
+}
+
+#[cfg(not(feature = "fs"))]
+fn same_file(a: &MappedFile, _b: &MappedFile) -> bool {
+    match *a {}
+}
+
+/// Reads a page at `offset` of the file, the part beyond the end of file is
+/// left zeroed.
+#[cfg(feature = "fs")]
+fn read_page(file: &MappedFile, offset: u64, page: &mut [u8]) -> LinuxResult {
+    let file = file.inner().lock();
+    let mut pos = 0;
+    while pos < page.len() {
+        match file.read_at(offset + pos as u64, &mut page[pos..])? {
+            0 => break,
+            n => pos += n,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "fs"))]
+fn read_page(file: &MappedFile, _offset: u64, _page: &mut [u8]) -> LinuxResult {
+    match *file {}
+}
+
+/// Maps the page at `offset` of the file from its page cache, and returns
+/// the frame of it, or `None` if the file has no page cache.
+#[cfg(feature = "fs")]
+fn map_cached_page(file: &MappedFile, offset: u64) -> LinuxResult<Option<PhysAddr>> {
+    let data = file.inner().lock().map_page(offset)?;
+    Ok(data.map(|data| axhal::mem::virt_to_phys(VirtAddr::from(data.as_ptr() as usize))))
+}
+
+#[cfg(not(feature = "fs"))]
+fn map_cached_page(file: &MappedFile, _offset: u64) -> LinuxResult<Option<PhysAddr>> {
+    match *file {}
+}
+
+/// Unmaps the page at `offset` of the file mapped by [`map_cached_page`].
+#[cfg(feature = "fs")]
+fn unmap_cached_page(file: &MappedFile, offset: u64) {
+    file.inner().lock().unmap_page(offset);
+}
+
+#[cfg(not(feature = "fs"))]
+fn unmap_cached_page(file: &MappedFile, _offset: u64) {
+    match *file {}
+}
+
+/// Writes a dirty page back to the file at `offset`: `data` if the page is
+/// not mapped from the page cache, except the part beyond the end of file.
+#[cfg(feature = "fs")]
+fn write_page(file: &MappedFile, offset: u64, data: Option<&[u8]>) -> LinuxResult {
+    let file = file.inner().lock();
+    let Some(data) = data else {
+        return Ok(file.sync_page(offset)?);
+    };
+    let size = file.get_attr()?.size();
+    if offset < size {
+        let len = data.len().min((size - offset) as usize);
+        file.write_at(offset, &data[..len])?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "fs"))]
+fn write_page(file: &MappedFile, _offset: u64, _data: Option<&[u8]>) -> LinuxResult {
+    match *file {}
+}
+
+/// Writes the dirty pages of `MAP_SHARED` file mappings in `[start, end)`
+/// back to the files, and maps them read-only again.
+fn write_back(start: usize, end: usize) -> LinuxResult {
+    let mut page = vec![0; PAGE_SIZE_4K];
+    let mut next = start;
+    loop {
+        let (file, offset, cached) = {
+            let mut maps = MAPPINGS.lock();
+            let first = maps
+                .range(..=next)
+                .next_back()
+                .map_or(next, |(&start, _)| start);
+            let Some((map_start, vaddr, map)) =
+                maps.range_mut(first..end).find_map(|(&map_start, map)| {
+                    let vaddr = map.next_dirty(next, end)?;
+                    Some((map_start, vaddr, map))
+                })
+            else {
+                return Ok(());
+            };
+            map.set_dirty(vaddr, false);
+            let file = map.file.clone().unwrap();
+            let cached = map.cached.contains(&vaddr);
+            let mut aspace = axmm::kernel_aspace().lock();
+            aspace.protect_page(vaddr.into(), map.page_flags(vaddr))?;
+            if !cached {
+                aspace.read(vaddr.into(), &mut page)?;
+            }
+            next = vaddr + PAGE_SIZE_4K;
+            (file.file, file.offset + (vaddr - map_start) as u64, cached)
+        };
+        write_page(&file, offset, (!cached).then_some(&page[..]))?;
+    }
+}
+
+/// Writes the dirty pages of a removed mapping starting at `start` back to
+/// the file, and unmaps its pages of the page cache, before it is unmapped
+/// from the address space.
+fn release_file_pages(start: usize, map: &Mapping) -> LinuxResult {
+    let Some(file) = &map.file else {
+        return Ok(());
+    };
+    let offset_of = |vaddr: usize| file.offset + (vaddr - start) as u64;
+    let mut res = Ok(());
+    let mut page = vec![0; PAGE_SIZE_4K];
+    let dirty_pages = (start..map.end)
+        .step_by(PAGE_SIZE_4K)
+        .filter(|&vaddr| map.is_dirty(vaddr));
+    for vaddr in dirty_pages {
+        let cached = map.cached.contains(&vaddr);
+        if !cached {
+            if let Err(e) = axmm::kernel_aspace().lock().read(vaddr.into(), &mut page) {
+                res = Err(e.into());
+                continue;
+            }
+        }
+        if let Err(e) = write_page(&file.file, offset_of(vaddr), (!cached).then_some(&page[..])) {
+            res = Err(e);
+        }
+    }
+    // the frames of the page cache must not be freed with the mapping
+    for &vaddr in &map.cached {
+        if let Err(e) = axmm::kernel_aspace().lock().unmap_frame(vaddr.into()) {
+            res = Err(e.into());
+        }
+        unmap_cached_page(&file.file, offset_of(vaddr));
+    }
+    res
+}
+
+fn prot_to_flags(prot: c_int) -> LinuxResult<MappingFlags> {
+    let prot = prot as u32;
+    if prot & !(ctypes::PROT_READ | ctypes::PROT_WRITE | ctypes::PROT_EXEC) != 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let mut flags = MappingFlags::empty();
+    if prot & ctypes::PROT_READ != 0 {
+        flags |= MappingFlags::READ;
+    }
+    if prot & ctypes::PROT_WRITE != 0 {
+        flags |= MappingFlags::WRITE;
+    }
+    if prot & ctypes::PROT_EXEC != 0 {
+        flags |= MappingFlags::EXECUTE;
+    }
+    Ok(flags)
+}
+
+/// Checks that `[addr, addr + len)` is page-aligned and within the range of
+/// the mappings, and returns it with `len` rounded up to pages.
+fn user_range(addr: *mut c_void, len: usize) -> LinuxResult<(usize, usize)> {
+    let start = addr as usize;
+    if !is_aligned_4k(start) || len == 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let end = start
+        .checked_add(align_up_4k(len))
+        .ok_or(LinuxError::ENOMEM)?;
+    if start < MMAP_BASE || end > MMAP_END {
+        return Err(LinuxError::ENOMEM);
+    }
+    Ok((start, end))
+}
+
+/// Splits the mapping containing `addr`, so that a mapping starts at it.
+fn split_at(maps: &mut BTreeMap<usize, Mapping>, addr: usize) {
+    let Some((&start, map)) = maps.range_mut(..addr).next_back() else {
+        return;
+    };
+    if addr >= map.end {
+        return;
+    }
+    let mut file = map.file.clone();
+    if let Some(file) = &mut file {
+        file.offset += (addr - start) as u64;
+    }
+    let at = ((addr - start) / PAGE_SIZE_4K).min(map.dirty.len());
+    let tail = Mapping {
+        start: addr,
+        end: map.end,
+        flags: map.flags,
+        file,
+        dirty: map.dirty.split_off(at),
+        cached: map.cached.split_off(&addr),
+    };
+    map.end = addr;
+    maps.insert(addr, tail);
+}
+
+/// Returns whether `[start, end)` is fully covered by the mappings.
+fn is_mapped(maps: &BTreeMap<usize, Mapping>, start: usize, end: usize) -> bool {
+    let mut next = start;
+    while next < end {
+        match maps.range(..=next).next_back() {
+            Some((_, map)) if map.end > next => next = map.end,
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Removes the mappings in `[start, end)`, writing the shared file pages back.
+fn unmap_range(start: usize, end: usize) -> LinuxResult {
+    let removed: Vec<_> = {
+        let mut maps = MAPPINGS.lock();
+        split_at(&mut maps, start);
+        split_at(&mut maps, end);
+        let starts: Vec<_> = maps.range(start..end).map(|(&start, _)| start).collect();
+        starts
+            .into_iter()
+            .filter_map(|start| maps.remove(&start).map(|map| (start, map)))
+            .collect()
+    };
+    let mut res = Ok(());
+    for (start, map) in removed {
+        if let Err(e) = release_file_pages(start, &map) {
+            warn!(
+                "munmap: failed to write back [{:#x}, {:#x}): {:?}",
+                start, map.end, e
+            );
+            res = Err(e);
+        }
+        axmm::kernel_aspace()
+            .lock()
+            .unmap(start.into(), map.end - start)?;
+    }
+    res
+}
+
+/// Returns the mapping containing `vaddr`, if the page at `vaddr` is still
+/// at `offset` of `file`.
+fn find_file_page<'a>(
+    maps: &'a mut BTreeMap<usize, Mapping>,
+    vaddr: usize,
+    file: &MappedFile,
+    offset: u64,
+) -> Option<&'a mut Mapping> {
+    let (&start, map) = maps.range_mut(..=vaddr).next_back()?;
+    let map_file = map.file.as_ref()?;
+    let found = vaddr < map.end
+        && same_file(&map_file.file, file)
+        && map_file.offset + (vaddr - start) as u64 == offset;
+    found.then_some(map)
+}
+
+/// Maps the page at `vaddr` of a file mapping if it is not yet, from the page
+/// cache of the file for `MAP_SHARED` mappings if it has one, or by reading
+/// it to a new frame through the buffer `page`.
+fn populate_page(vaddr: usize, page: &mut [u8]) -> LinuxResult {
+    let (file, offset) = {
+        let maps = MAPPINGS.lock();
+        let Some((&start, map)) = maps.range(..=vaddr).next_back() else {
+            return Ok(());
+        };
+        let Some(file) = map.file.clone().filter(|_| vaddr < map.end) else {
+            return Ok(());
+        };
+        let aspace = axmm::kernel_aspace().lock();
+        if aspace.page_table().query(vaddr.into()).is_ok() {
+            return Ok(());
+        }
+        let offset = file.offset + (vaddr - start) as u64;
+        (file, offset)
+    };
+
+    // pages of shared mappings are mapped from the page cache if any
+    let cached = if file.shared {
+        map_cached_page(&file.file, offset)?
+    } else {
+        None
+    };
+    if cached.is_none() {
+        page.fill(0);
+        read_page(&file.file, offset, page)?;
+    }
+
+    let mut maps = MAPPINGS.lock();
+    let mut aspace = axmm::kernel_aspace().lock();
+    let mapped = match find_file_page(&mut maps, vaddr, &file.file, offset) {
+        Some(map) if aspace.page_table().query(vaddr.into()).is_err() => {
+            let flags = map.page_flags(vaddr);
+            let mapped = match cached {
+                Some(frame) => aspace.map_frame(vaddr.into(), frame, flags).is_ok(),
+                None => aspace
+                    .handle_page_fault_with(vaddr.into(), flags, |dst| dst.copy_from_slice(page)),
+            };
+            if mapped && cached.is_some() {
+                map.cached.insert(vaddr);
+            }
+            Some(mapped)
+        }
+        // unmapped or moved by another task at the same time
+        _ => None,
+    };
+    drop((aspace, maps));
+    if cached.is_some() && mapped != Some(true) {
+        unmap_cached_page(&file.file, offset);
+    }
+    match mapped {
+        Some(false) => Err(LinuxError::ENOMEM),
+        _ => Ok(()),
+    }
+}
+
+/// Maps the pages of the file mappings in `[start, end)` by
+/// [`populate_page`].
+fn populate(start: usize, end: usize) -> LinuxResult {
+    let mut page = vec![0; PAGE_SIZE_4K];
+    for vaddr in (start..end).step_by(PAGE_SIZE_4K) {
+        populate_page(vaddr, &mut page)?;
+    }
+    Ok(())
+}
+
+/// Handles a page fault at `vaddr` in a file mapping, whose pages are already
+/// mapped by [`populate`]. Only the first write to a clean page of a
+/// `MAP_SHARED` mapping is handled, by marking it dirty and making it
+/// writable.
+///
+/// Returns `None` if `vaddr` is not in a file mapping.
+fn handle_file_fault(vaddr: usize, access_flags: MappingFlags) -> Option<bool> {
+    let vaddr = align_down_4k(vaddr);
+    let mut maps = MAPPINGS.lock();
+    let (_, map) = maps.range_mut(..=vaddr).next_back()?;
+    let file = map.file.as_ref().filter(|_| vaddr < map.end)?;
+    if !file.shared
+        || !access_flags.contains(MappingFlags::WRITE)
+        || !map.flags.contains(access_flags)
+    {
+        return Some(false);
+    }
+    let mut aspace = axmm::kernel_aspace().lock();
+    if aspace.page_table().query(vaddr.into()).is_err() {
+        return Some(false);
+    }
+    map.set_dirty(vaddr, true);
+    Some(
+        aspace
+            .protect_page(vaddr.into(), map.page_flags(vaddr))
+            .is_ok(),
+    )
+}
+
+#[register_trap_handler(PAGE_FAULT)]
+fn handle_page_fault(vaddr: VirtAddr, access_flags: MappingFlags, _is_user: bool) -> bool {
+    handle_file_fault(vaddr.as_usize(), access_flags).unwrap_or_else(|| {
+        axmm::kernel_aspace()
+            .lock()
+            .handle_page_fault(vaddr, access_flags)
+    })
+}
+
+/// Map `len` bytes of anonymous memory or of the file `fd` from `off` into
+/// the address space, and return the start address.
+///
+/// Anonymous pages are allocated on the first access, while the pages of the
+/// file are read at once. Without `MAP_FIXED`, `addr` is only a hint.
+pub fn sys_mmap(
+    addr: *mut c_void,
+    len: usize,
+    prot: c_int,
+    flags: c_int,
+    fd: c_int,
+    off: ctypes::off_t,
+) -> *mut c_void {
+    debug!(
+        "sys_mmap <= {:#x}, {:#x}, {:#x}, {:#x}, {}, {}",
+        addr as usize, len, prot, flags, fd, off
+    );
+    syscall_body!(sys_mmap, {
+        let map_flags = flags as u32;
+        let shared = match map_flags & ctypes::MAP_TYPE {
+            ctypes::MAP_SHARED | ctypes::MAP_SHARED_VALIDATE => true,
+            ctypes::MAP_PRIVATE => false,
+            _ => return Err(LinuxError::EINVAL),
+        };
+        let mapping_flags = prot_to_flags(prot)?;
+        if len == 0 || len > MMAP_END - MMAP_BASE {
+            return Err(if len == 0 {
+                LinuxError::EINVAL
+            } else {
+                LinuxError::ENOMEM
+            });
+        }
+        let len = align_up_4k(len);
+        let file = if map_flags & ctypes::MAP_ANONYMOUS != 0 {
+            None
+        } else {
+            if off < 0 || !is_aligned_4k(off as usize) {
+                return Err(LinuxError::EINVAL);
+            }
+            // the changes to a writable shared mapping are written back
+            let write = shared && mapping_flags.contains(MappingFlags::WRITE);
+            Some(FileMapping {
+                file: open_file(fd, write)?,
+                offset: off as u64,
+                shared,
+            })
+        };
+
+        let start = if map_flags & ctypes::MAP_FIXED != 0 {
+            let (start, end) = user_range(addr, len)?;
+            unmap_range(start, end)?;
+            start
+        } else {
+            let hint = match user_range(addr, len) {
+                Ok((start, _)) => start,
+                Err(_) => MMAP_BASE,
+            };
+            let limit = VirtAddrRange::from_start_size(MMAP_BASE.into(), MMAP_END - MMAP_BASE);
+            axmm::kernel_aspace()
+                .lock()
+                .find_free_area(hint.into(), len, limit)
+                .ok_or(LinuxError::ENOMEM)?
+                .as_usize()
+        };
+
+        let is_file = file.is_some();
+        {
+            let mut maps = MAPPINGS.lock();
+            axmm::kernel_aspace()
+                .lock()
+                .map_alloc(start.into(), len, mapping_flags, false)?;
+            let map = Mapping::new(start, start + len, mapping_flags, file);
+            maps.insert(start, map);
+        }
+        if is_file {
+            if let Err(e) = populate(start, start + len) {
+                unmap_range(start, start + len).ok();
+                return Err(e);
+            }
+        }
+        Ok(start as *mut c_void)
+    })
+}
+
+/// Remove the mappings in `[addr, addr + len)`.
+///
+/// The dirty pages of `MAP_SHARED` file mappings are written back first.
+pub fn sys_munmap(addr: *mut c_void, len: usize) -> c_int {
+    debug!("sys_munmap <= {:#x}, {:#x}", addr as usize, len);
+    syscall_body!(sys_munmap, {
+        let (start, end) = user_range(addr, len).map_err(|_| LinuxError::EINVAL)?;
+        unmap_range(start, end)?;
+        Ok(0)
+    })
+}
+
+/// Change the access permissions of the mappings in `[addr, addr + len)`.
+///
+/// `MAP_SHARED` file mappings can only be made writable if the files are
+/// opened for writing.
+pub fn sys_mprotect(addr: *mut c_void, len: usize, prot: c_int) -> c_int {
+    debug!(
+        "sys_mprotect <= {:#x}, {:#x}, {:#x}",
+        addr as usize, len, prot
+    );
+    syscall_body!(sys_mprotect, {
+        let (start, end) = user_range(addr, len)?;
+        let flags = prot_to_flags(prot)?;
+        let write = flags.contains(MappingFlags::WRITE);
+        let shared_files: Vec<_> = {
+            let maps = MAPPINGS.lock();
+            if !is_mapped(&maps, start, end) {
+                return Err(LinuxError::ENOMEM);
+            }
+            let first = maps
+                .range(..=start)
+                .next_back()
+                .map_or(start, |(&start, _)| start);
+            maps.range(first..end)
+                .filter_map(|(_, map)| map.file.clone().filter(|file| write && file.shared))
+                .collect()
+        };
+        for file in &shared_files {
+            check_access(&file.file, true)?;
+        }
+
+        let mut maps = MAPPINGS.lock();
+        split_at(&mut maps, start);
+        split_at(&mut maps, end);
+        let mut aspace = axmm::kernel_aspace().lock();
+        aspace.protect(start.into(), end - start, flags)?;
+        for (&map_start, map) in maps.range_mut(start..end) {
+            map.flags = flags;
+            if !write || !map.file.as_ref().is_some_and(|file| file.shared) {
+                continue;
+            }
+            // the clean pages stay read-only until they are written
+            for vaddr in (map_start..map.end).step_by(PAGE_SIZE_4K) {
+                if !map.is_dirty(vaddr) && aspace.page_table().query(vaddr.into()).is_ok() {
+                    aspace.protect_page(vaddr.into(), map.page_flags(vaddr))?;
+                }
+            }
+        }
+        Ok(0)
+    })
+}
+
+/// Write the dirty pages of `MAP_SHARED` file mappings in `[addr, addr + len)`
+/// back to the files.
+///
+/// The write-back is always synchronous, even with `MS_ASYNC`.
+pub fn sys_msync(addr: *mut c_void, len: usize, flags: c_int) -> c_int {
+    debug!(
+        "sys_msync <= {:#x}, {:#x}, {:#x}",
+        addr as usize, len, flags
+    );
+    syscall_body!(sys_msync, {
+        let flags = flags as u32;
+        if flags & !(ctypes::MS_ASYNC | ctypes::MS_SYNC | ctypes::MS_INVALIDATE) != 0
+            || flags & ctypes::MS_ASYNC != 0 && flags & ctypes::MS_SYNC != 0
+        {
+            return Err(LinuxError::EINVAL);
+        }
+        if len == 0 && is_aligned_4k(addr as usize) {
+            return Ok(0);
+        }
+        let (start, end) = user_range(addr, len)?;
+        if !is_mapped(&MAPPINGS.lock(), start, end) {
+            return Err(LinuxError::ENOMEM);
+        }
+        write_back(start, end)?;
+        Ok(0)
+    })
+}
+
+/// Moves the mapping `[old_addr, old_addr + old_size)`, which must be in a
+/// single `mmap` call, to a new mapping of `new_size` bytes.
+///
+/// The mapping is shrunk or grown in place if possible, otherwise moved with
+/// its content if `MREMAP_MAYMOVE` is set. `MREMAP_FIXED` is not supported.
+///
+/// If the new pages of a file mapping cannot be read, it fails, and the
+/// mapping keeps its old size in place, or is removed if it is moved.
+pub fn sys_mremap(
+    old_addr: *mut c_void,
+    old_size: usize,
+    new_size: usize,
+    flags: c_int,
+) -> *mut c_void {
+    debug!(
+        "sys_mremap <= {:#x}, {:#x}, {:#x}, {:#x}",
+        old_addr as usize, old_size, new_size, flags
+    );
+    syscall_body!(sys_mremap, {
+        let flags = flags as u32;
+        if flags & !ctypes::MREMAP_MAYMOVE != 0 || new_size == 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        if new_size > MMAP_END - MMAP_BASE {
+            return Err(LinuxError::ENOMEM);
+        }
+        let (start, end) = user_range(old_addr, old_size)?;
+        let new_size = align_up_4k(new_size);
+
+        let mut maps = MAPPINGS.lock();
+        let Some((&map_start, map)) = maps.range(..=start).next_back() else {
+            return Err(LinuxError::EFAULT);
+        };
+        if end > map.end {
+            return Err(LinuxError::EFAULT);
+        }
+        if new_size <= end - start {
+            drop(maps);
+            if new_size < end - start {
+                unmap_range(start + new_size, end)?;
+            }
+            return Ok(old_addr);
+        }
+
+        let is_file = map.file.is_some();
+        let new_start = {
+            let mut aspace = axmm::kernel_aspace().lock();
+            let limit = VirtAddrRange::from_start_size(MMAP_BASE.into(), MMAP_END - MMAP_BASE);
+            let extra = start + new_size - end;
+            if end == map.end && aspace.find_free_area(end.into(), extra, limit) == Some(end.into())
+            {
+                aspace.map_alloc(end.into(), extra, map.flags, false)?;
+                maps.get_mut(&map_start).unwrap().resize(start + new_size);
+                start
+            } else {
+                if flags & ctypes::MREMAP_MAYMOVE == 0 {
+                    return Err(LinuxError::ENOMEM);
+                }
+                let new_start = aspace
+                    .find_free_area(MMAP_BASE.into(), new_size, limit)
+                    .ok_or(LinuxError::ENOMEM)?
+                    .as_usize();
+                aspace.map_alloc(new_start.into(), new_size, map.flags, false)?;
+                split_at(&mut maps, start);
+                split_at(&mut maps, end);
+                let old = maps.remove(&start).unwrap();
+
+                // the pages of the page cache are moved, the others are copied
+                let moved = |vaddr: usize| new_start + (vaddr - start);
+                let mut page = vec![0; PAGE_SIZE_4K];
+                for vaddr in (start..end).step_by(PAGE_SIZE_4K) {
+                    let flags = old.page_flags(vaddr);
+                    if old.cached.contains(&vaddr) {
+                        let frame = aspace.unmap_frame(vaddr.into())?;
+                        aspace.map_frame(moved(vaddr).into(), frame, flags)?;
+                    } else if aspace.page_table().query(vaddr.into()).is_ok() {
+                        aspace.read(vaddr.into(), &mut page)?;
+                        aspace.handle_page_fault_with(moved(vaddr).into(), flags, |dst| {
+                            dst.copy_from_slice(&page)
+                        });
+                    }
+                }
+                aspace.unmap(start.into(), end - start)?;
+                let mut map = Mapping {
+                    start: new_start,
+                    end: moved(end),
+                    flags: old.flags,
+                    file: old.file,
+                    dirty: old.dirty,
+                    cached: old.cached.iter().map(|&vaddr| moved(vaddr)).collect(),
+                };
+                map.resize(new_start + new_size);
+                maps.insert(new_start, map);
+                new_start
+            }
+        };
+        drop(maps);
+
+        // the new pages of a file mapping are read from the file
+        if is_file {
+            let tail = new_start + (end - start);
+            if let Err(e) = populate(tail, new_start + new_size) {
+                let removed = if new_start == start { tail } else { new_start };
+                unmap_range(removed, new_start + new_size).ok();
+                return Err(e);
+            }
+        }
+        Ok(new_start as *mut c_void)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: usize = PAGE_SIZE_4K;
+
+    #[test]
+    fn test_user_range() {
+        let addr = |addr: usize| addr as *mut c_void;
+        assert_eq!(
+            user_range(addr(MMAP_BASE), 1),
+            Ok((MMAP_BASE, MMAP_BASE + PAGE))
+        );
+        assert_eq!(
+            user_range(addr(MMAP_BASE + 1), PAGE),
+            Err(LinuxError::EINVAL)
+        );
+        assert_eq!(user_range(addr(MMAP_BASE), 0), Err(LinuxError::EINVAL));
+        assert_eq!(
+            user_range(addr(MMAP_BASE - PAGE), PAGE),
+            Err(LinuxError::ENOMEM)
+        );
+        assert_eq!(
+            user_range(addr(MMAP_END - PAGE), 2 * PAGE),
+            Err(LinuxError::ENOMEM)
+        );
+    }
+
+    #[test]
+    fn test_prot_to_flags() {
+        let prot = (ctypes::PROT_READ | ctypes::PROT_WRITE) as c_int;
+        assert_eq!(
+            prot_to_flags(prot),
+            Ok(MappingFlags::READ | MappingFlags::WRITE)
+        );
+        assert_eq!(prot_to_flags(0), Ok(MappingFlags::empty()));
+        assert_eq!(prot_to_flags(0x100), Err(LinuxError::EINVAL));
+    }
+
+    #[test]
+    fn test_split() {
+        let (start, end) = (MMAP_BASE, MMAP_BASE + 4 * PAGE);
+        let mut maps = BTreeMap::new();
+        maps.insert(start, Mapping::new(start, end, MappingFlags::READ, None));
+        split_at(&mut maps, start + PAGE);
+        split_at(&mut maps, start + PAGE);
+        split_at(&mut maps, end);
+        assert_eq!(maps.len(), 2);
+        assert_eq!(maps[&start].end, start + PAGE);
+        assert_eq!(maps[&(start + PAGE)].start, start + PAGE);
+        assert!(is_mapped(&maps, start, end));
+
+        split_at(&mut maps, start + 2 * PAGE);
+        maps.remove(&(start + PAGE));
+        assert!(!is_mapped(&maps, start, end));
+        assert!(is_mapped(&maps, start + 2 * PAGE, end));
+    }
+
+    #[test]
+    fn test_dirty_pages() {
+        let (start, end) = (MMAP_BASE, MMAP_BASE + 4 * PAGE);
+        let mut map = Mapping::new(start, end, MappingFlags::READ, None);
+        // only the dirty flags of shared file mappings are allocated
+        assert!(map.dirty.is_empty());
+        map.set_dirty(start, true);
+        assert!(!map.is_dirty(start));
+
+        map.dirty = vec![false; 4];
+        map.set_dirty(start + 2 * PAGE, true);
+        assert_eq!(map.next_dirty(start, end), Some(start + 2 * PAGE));
+        assert_eq!(map.next_dirty(start + 3 * PAGE, end), None);
+
+        // the flags are split with the mapping
+        let mut maps = BTreeMap::new();
+        maps.insert(start, map);
+        split_at(&mut maps, start + 2 * PAGE);
+        assert_eq!(maps[&start].dirty, [false; 2]);
+        assert_eq!(maps[&start].next_dirty(start, end), None);
+        let tail = &maps[&(start + 2 * PAGE)];
+        assert!(tail.is_dirty(start + 2 * PAGE));
+        assert!(!tail.is_dirty(start + 3 * PAGE));
+    }
+}
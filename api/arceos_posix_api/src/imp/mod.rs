@@ -10,9 +10,11 @@ pub mod time;
 pub mod fd_ops;
 #[cfg(feature = "fs")]
 pub mod fs;
-#[cfg(any(feature = "select", feature = "epoll"))]
+#[cfg(any(feature = "select", feature = "poll", feature = "epoll"))]
 pub mod io_mpx;
-#[cfg(feature = "net")]
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "unix")]
 pub mod net;
 #[cfg(feature = "fs")]
 pub mod path_link;
@@ -20,3 +22,7 @@ pub mod path_link;
 pub mod pipe;
 #[cfg(feature = "multitask")]
 pub mod pthread;
+#[cfg(feature = "signal")]
+pub mod signal;
+#[cfg(feature = "unix")]
+pub mod unix;
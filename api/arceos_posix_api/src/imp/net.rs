@@ -1,20 +1,75 @@
-use alloc::{sync::Arc, vec, vec::Vec};
-use core::ffi::{c_char, c_int, c_void};
+use alloc::sync::Arc;
+use core::ffi::{c_int, c_void};
 use core::mem::size_of;
-use core::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use core::net::{Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use core::task::Waker;
 
 use axerrno::{LinuxError, LinuxResult};
 use axio::PollState;
-use axnet::{TcpSocket, UdpSocket};
-use axsync::Mutex;
 
 use super::fd_ops::FileLike;
+use super::unix::{UnixAddr, UnixSocket};
 use crate::ctypes;
-use crate::utils::char_ptr_to_str;
 
+#[cfg(feature = "net")]
+use {
+    crate::utils::char_ptr_to_str,
+    alloc::{vec, vec::Vec},
+    axerrno::AxError,
+    axnet::{IcmpSocket, RawSocket, SocketOptions, TcpSocket, UdpSocket},
+    axsync::Mutex,
+    core::{ffi::c_char, net::IpAddr, time::Duration},
+};
+
+/// A socket of any supported family.
+///
+/// IP sockets are only supported with the `net` feature, Unix domain sockets
+/// with the `unix` feature as well.
 pub enum Socket {
+    #[cfg(feature = "net")]
     Udp(Mutex<UdpSocket>),
+    #[cfg(feature = "net")]
     Tcp(Mutex<TcpSocket>),
+    #[cfg(feature = "net")]
+    Icmp(Mutex<IcmpSocket>),
+    #[cfg(feature = "net")]
+    Raw(Mutex<RawSocket>),
+    Unix(UnixSocket),
+}
+
+/// A socket address of any supported family.
+#[derive(Debug)]
+enum SockAddr {
+    Inet(SocketAddr),
+    Unix(UnixAddr),
+}
+
+impl From<SocketAddr> for SockAddr {
+    fn from(addr: SocketAddr) -> Self {
+        Self::Inet(addr)
+    }
+}
+
+impl TryFrom<SockAddr> for SocketAddr {
+    type Error = LinuxError;
+
+    fn try_from(addr: SockAddr) -> LinuxResult<Self> {
+        match addr {
+            SockAddr::Inet(addr) => Ok(addr),
+            SockAddr::Unix(_) => Err(LinuxError::EAFNOSUPPORT),
+        }
+    }
+}
+
+impl TryFrom<SockAddr> for UnixAddr {
+    type Error = LinuxError;
+
+    fn try_from(addr: SockAddr) -> LinuxResult<Self> {
+        match addr {
+            SockAddr::Unix(addr) => Ok(addr),
+            SockAddr::Inet(_) => Err(LinuxError::EAFNOSUPPORT),
+        }
+    }
 }
 
 impl Socket {
@@ -30,89 +85,176 @@ impl Socket {
     }
 
     fn send(&self, buf: &[u8]) -> LinuxResult<usize> {
-        match self {
+        let res = match self {
+            #[cfg(feature = "net")]
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().send(buf)?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().send(buf)?),
+            #[cfg(feature = "net")]
+            Socket::Tcp(tcpsocket) => match tcpsocket.lock().send(buf) {
+                // closed by the peer, or shut down for writing
+                Err(AxError::ConnectionReset) => Err(LinuxError::EPIPE),
+                res => Ok(res?),
+            },
+            #[cfg(feature = "net")]
+            Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::EDESTADDRREQ),
+            Socket::Unix(unixsocket) => unixsocket.send(buf),
+        };
+        #[cfg(feature = "signal")]
+        if res == Err(LinuxError::EPIPE) {
+            super::signal::send_current(ctypes::SIGPIPE);
         }
+        res
     }
 
     fn recv(&self, buf: &mut [u8]) -> LinuxResult<usize> {
         match self {
+            #[cfg(feature = "net")]
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().recv_from(buf).map(|e| e.0)?),
+            #[cfg(feature = "net")]
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().recv(buf)?),
+            #[cfg(feature = "net")]
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().recv_from(buf).map(|e| e.0)?),
+            #[cfg(feature = "net")]
+            Socket::Raw(rawsocket) => Ok(rawsocket.lock().recv_from(buf).map(|e| e.0)?),
+            Socket::Unix(unixsocket) => unixsocket.recv_from(buf).map(|e| e.0),
         }
     }
 
     pub fn poll(&self) -> LinuxResult<PollState> {
         match self {
+            #[cfg(feature = "net")]
             Socket::Udp(udpsocket) => Ok(udpsocket.lock().poll()?),
+            #[cfg(feature = "net")]
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().poll()?),
+            #[cfg(feature = "net")]
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().poll()?),
+            #[cfg(feature = "net")]
+            Socket::Raw(rawsocket) => Ok(rawsocket.lock().poll()?),
+            Socket::Unix(unixsocket) => unixsocket.poll(),
         }
     }
 
-    fn local_addr(&self) -> LinuxResult<SocketAddr> {
+    fn local_addr(&self) -> LinuxResult<SockAddr> {
         match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().local_addr()?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().local_addr()?),
+            #[cfg(feature = "net")]
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().local_addr()?.into()),
+            #[cfg(feature = "net")]
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().local_addr()?.into()),
+            #[cfg(feature = "net")]
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().local_addr()?.into()),
+            #[cfg(feature = "net")]
+            Socket::Raw(_) => Err(LinuxError::EOPNOTSUPP),
+            Socket::Unix(unixsocket) => Ok(SockAddr::Unix(unixsocket.local_addr())),
         }
     }
 
-    fn peer_addr(&self) -> LinuxResult<SocketAddr> {
+    fn peer_addr(&self) -> LinuxResult<SockAddr> {
         match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().peer_addr()?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().peer_addr()?),
+            #[cfg(feature = "net")]
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().peer_addr()?.into()),
+            #[cfg(feature = "net")]
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().peer_addr()?.into()),
+            #[cfg(feature = "net")]
+            Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::ENOTCONN),
+            Socket::Unix(unixsocket) => Ok(SockAddr::Unix(unixsocket.peer_addr()?)),
         }
     }
 
-    fn bind(&self, addr: SocketAddr) -> LinuxResult {
+    fn bind(&self, addr: SockAddr) -> LinuxResult {
         match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().bind(addr)?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().bind(addr)?),
+            #[cfg(feature = "net")]
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().bind(addr.try_into()?)?),
+            #[cfg(feature = "net")]
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().bind(addr.try_into()?)?),
+            #[cfg(feature = "net")]
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().bind(addr.try_into()?)?),
+            #[cfg(feature = "net")]
+            Socket::Raw(_) => Err(LinuxError::EOPNOTSUPP),
+            Socket::Unix(unixsocket) => unixsocket.bind(addr.try_into()?),
         }
     }
 
-    fn connect(&self, addr: SocketAddr) -> LinuxResult {
+    fn connect(&self, addr: SockAddr) -> LinuxResult {
         match self {
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().connect(addr)?),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().connect(addr)?),
+            #[cfg(feature = "net")]
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().connect(addr.try_into()?)?),
+            #[cfg(feature = "net")]
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().connect(addr.try_into()?)?),
+            #[cfg(feature = "net")]
+            Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::EOPNOTSUPP),
+            Socket::Unix(unixsocket) => unixsocket.connect(addr.try_into()?),
         }
     }
 
-    fn sendto(&self, buf: &[u8], addr: SocketAddr) -> LinuxResult<usize> {
+    fn sendto(&self, buf: &[u8], addr: SockAddr) -> LinuxResult<usize> {
         match self {
             // diff: must bind before sendto
-            Socket::Udp(udpsocket) => Ok(udpsocket.lock().send_to(buf, addr)?),
+            #[cfg(feature = "net")]
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().send_to(buf, addr.try_into()?)?),
+            #[cfg(feature = "net")]
             Socket::Tcp(_) => Err(LinuxError::EISCONN),
+            #[cfg(feature = "net")]
+            Socket::Icmp(icmpsocket) => {
+                let addr = SocketAddr::try_from(addr)?;
+                Ok(icmpsocket.lock().send_to(buf, addr.ip())?)
+            }
+            #[cfg(feature = "net")]
+            Socket::Raw(rawsocket) => {
+                let addr = SocketAddr::try_from(addr)?;
+                Ok(rawsocket.lock().send_to(buf, addr.ip())?)
+            }
+            Socket::Unix(unixsocket) => unixsocket.send_to(buf, addr.try_into()?),
         }
     }
 
-    fn recvfrom(&self, buf: &mut [u8]) -> LinuxResult<(usize, Option<SocketAddr>)> {
+    fn recvfrom(&self, buf: &mut [u8]) -> LinuxResult<(usize, Option<SockAddr>)> {
         match self {
             // diff: must bind before recvfrom
+            #[cfg(feature = "net")]
             Socket::Udp(udpsocket) => Ok(udpsocket
                 .lock()
                 .recv_from(buf)
-                .map(|res| (res.0, Some(res.1)))?),
+                .map(|res| (res.0, Some(res.1.into())))?),
+            #[cfg(feature = "net")]
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().recv(buf).map(|res| (res, None))?),
+            #[cfg(feature = "net")]
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket
+                .lock()
+                .recv_from(buf)
+                .map(|(len, ip)| (len, Some(SocketAddr::new(ip, 0).into())))?),
+            #[cfg(feature = "net")]
+            Socket::Raw(rawsocket) => Ok(rawsocket
+                .lock()
+                .recv_from(buf)
+                .map(|(len, ip)| (len, Some(SocketAddr::new(ip, 0).into())))?),
+            Socket::Unix(unixsocket) => unixsocket
+                .recv_from(buf)
+                .map(|(len, addr)| (len, addr.map(SockAddr::Unix))),
         }
     }
 
     fn listen(&self) -> LinuxResult {
         match self {
-            Socket::Udp(_) => Err(LinuxError::EOPNOTSUPP),
+            #[cfg(feature = "net")]
+            Socket::Udp(_) | Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::EOPNOTSUPP),
+            #[cfg(feature = "net")]
             Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().listen()?),
+            Socket::Unix(unixsocket) => unixsocket.listen(),
         }
     }
 
-    fn accept(&self) -> LinuxResult<TcpSocket> {
+    fn accept(&self) -> LinuxResult<Socket> {
         match self {
-            Socket::Udp(_) => Err(LinuxError::EOPNOTSUPP),
-            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().accept()?),
+            #[cfg(feature = "net")]
+            Socket::Udp(_) | Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::EOPNOTSUPP),
+            #[cfg(feature = "net")]
+            Socket::Tcp(tcpsocket) => Ok(Socket::Tcp(Mutex::new(tcpsocket.lock().accept()?))),
+            Socket::Unix(unixsocket) => Ok(Socket::Unix(unixsocket.accept()?)),
         }
     }
 
     fn shutdown(&self) -> LinuxResult {
         match self {
+            #[cfg(feature = "net")]
             Socket::Udp(udpsocket) => {
                 let udpsocket = udpsocket.lock();
                 udpsocket.peer_addr()?;
@@ -120,12 +262,63 @@ impl Socket {
                 Ok(())
             }
 
+            #[cfg(feature = "net")]
             Socket::Tcp(tcpsocket) => {
                 let tcpsocket = tcpsocket.lock();
                 tcpsocket.peer_addr()?;
                 tcpsocket.shutdown()?;
                 Ok(())
             }
+
+            // never connected
+            #[cfg(feature = "net")]
+            Socket::Icmp(_) | Socket::Raw(_) => Err(LinuxError::ENOTCONN),
+
+            Socket::Unix(unixsocket) => unixsocket.shutdown(),
+        }
+    }
+
+    /// Returns the options of an IP socket.
+    #[cfg(feature = "net")]
+    fn options(&self) -> LinuxResult<SocketOptions> {
+        match self {
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().options()),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().options()),
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().options()),
+            Socket::Raw(rawsocket) => Ok(rawsocket.lock().options()),
+            Socket::Unix(_) => Err(LinuxError::ENOPROTOOPT),
+        }
+    }
+
+    /// Returns the sizes of the receive and send buffers of an IP socket.
+    #[cfg(feature = "net")]
+    fn buf_sizes(&self) -> LinuxResult<(usize, usize)> {
+        match self {
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().buf_sizes()),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().buf_sizes()),
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().buf_sizes()),
+            Socket::Raw(rawsocket) => Ok(rawsocket.lock().buf_sizes()),
+            Socket::Unix(_) => Err(LinuxError::ENOPROTOOPT),
+        }
+    }
+
+    /// Returns and clears the pending error of the socket.
+    fn take_error(&self) -> Option<LinuxError> {
+        match self {
+            #[cfg(feature = "net")]
+            Socket::Tcp(tcpsocket) => tcpsocket.lock().take_error().map(LinuxError::from),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "net")]
+    fn set_options(&self, options: SocketOptions) -> LinuxResult {
+        match self {
+            Socket::Udp(udpsocket) => Ok(udpsocket.lock().set_options(options)?),
+            Socket::Tcp(tcpsocket) => Ok(tcpsocket.lock().set_options(options)?),
+            Socket::Icmp(icmpsocket) => Ok(icmpsocket.lock().set_options(options)?),
+            Socket::Raw(rawsocket) => Ok(rawsocket.lock().set_options(options)?),
+            Socket::Unix(_) => Err(LinuxError::ENOPROTOOPT),
         }
     }
 }
@@ -163,11 +356,50 @@ impl FileLike for Socket {
 
     fn set_nonblocking(&self, nonblock: bool) -> LinuxResult {
         match self {
+            #[cfg(feature = "net")]
             Socket::Udp(udpsocket) => udpsocket.lock().set_nonblocking(nonblock),
+            #[cfg(feature = "net")]
             Socket::Tcp(tcpsocket) => tcpsocket.lock().set_nonblocking(nonblock),
+            #[cfg(feature = "net")]
+            Socket::Icmp(icmpsocket) => icmpsocket.lock().set_nonblocking(nonblock),
+            #[cfg(feature = "net")]
+            Socket::Raw(rawsocket) => rawsocket.lock().set_nonblocking(nonblock),
+            Socket::Unix(unixsocket) => unixsocket.set_nonblocking(nonblock),
         }
         Ok(())
     }
+
+    fn register_waker(&self, waker: &Waker) -> bool {
+        match self {
+            #[cfg(feature = "net")]
+            Socket::Udp(udpsocket) => udpsocket.lock().register_waker(waker),
+            #[cfg(feature = "net")]
+            Socket::Tcp(tcpsocket) => tcpsocket.lock().register_waker(waker),
+            #[cfg(feature = "net")]
+            Socket::Icmp(_) | Socket::Raw(_) => false,
+            Socket::Unix(unixsocket) => unixsocket.register_waker(waker),
+        }
+    }
+
+    fn peer_closed(&self) -> bool {
+        match self {
+            #[cfg(feature = "net")]
+            Socket::Tcp(tcpsocket) => tcpsocket.lock().peer_closed(),
+            Socket::Unix(unixsocket) => unixsocket.peer_closed(),
+            #[cfg(feature = "net")]
+            _ => false,
+        }
+    }
+
+    fn hung_up(&self) -> bool {
+        match self {
+            #[cfg(feature = "net")]
+            Socket::Tcp(tcpsocket) => tcpsocket.lock().hung_up(),
+            Socket::Unix(unixsocket) => unixsocket.hung_up(),
+            #[cfg(feature = "net")]
+            _ => false,
+        }
+    }
 }
 
 impl From<SocketAddrV4> for ctypes::sockaddr_in {
@@ -194,36 +426,106 @@ impl From<ctypes::sockaddr_in> for SocketAddrV4 {
     }
 }
 
-fn into_sockaddr(addr: SocketAddr) -> (ctypes::sockaddr, ctypes::socklen_t) {
-    debug!("    Sockaddr: {}", addr);
-    match addr {
-        SocketAddr::V4(addr) => (
-            unsafe { *(&ctypes::sockaddr_in::from(addr) as *const _ as *const ctypes::sockaddr) },
-            size_of::<ctypes::sockaddr>() as _,
-        ),
-        SocketAddr::V6(_) => panic!("IPv6 is not supported"),
+impl From<SocketAddrV6> for ctypes::sockaddr_in6 {
+    fn from(addr: SocketAddrV6) -> ctypes::sockaddr_in6 {
+        ctypes::sockaddr_in6 {
+            sin6_family: ctypes::AF_INET6 as u16,
+            sin6_port: addr.port().to_be(),
+            sin6_flowinfo: addr.flowinfo().to_be(),
+            sin6_addr: ctypes::in6_addr {
+                __in6_union: ctypes::in6_addr__bindgen_ty_1 {
+                    __s6_addr: addr.ip().octets(),
+                },
+            },
+            sin6_scope_id: addr.scope_id(),
+        }
+    }
+}
+
+impl From<ctypes::sockaddr_in6> for SocketAddrV6 {
+    fn from(addr: ctypes::sockaddr_in6) -> SocketAddrV6 {
+        SocketAddrV6::new(
+            // SAFETY: all variants of the union are plain byte arrays.
+            Ipv6Addr::from(unsafe { addr.sin6_addr.__in6_union.__s6_addr }),
+            u16::from_be(addr.sin6_port),
+            u32::from_be(addr.sin6_flowinfo),
+            addr.sin6_scope_id,
+        )
+    }
+}
+
+/// Writes `addr` to the buffer `dst` whose size is given by `*addrlen`, then
+/// sets `*addrlen` to the actual size of the address.
+///
+/// The address is truncated if the buffer is too small.
+unsafe fn write_sockaddr(
+    addr: SockAddr,
+    dst: *mut ctypes::sockaddr,
+    addrlen: *mut ctypes::socklen_t,
+) {
+    debug!("    Sockaddr: {:?}", addr);
+    let (sin, sin6, sun);
+    let (src, len) = match addr {
+        SockAddr::Inet(SocketAddr::V4(addr)) => {
+            sin = ctypes::sockaddr_in::from(addr);
+            (
+                &sin as *const _ as *const u8,
+                size_of::<ctypes::sockaddr_in>(),
+            )
+        }
+        SockAddr::Inet(SocketAddr::V6(addr)) => {
+            sin6 = ctypes::sockaddr_in6::from(addr);
+            (
+                &sin6 as *const _ as *const u8,
+                size_of::<ctypes::sockaddr_in6>(),
+            )
+        }
+        SockAddr::Unix(addr) => {
+            let len;
+            (sun, len) = addr.to_sockaddr();
+            (&sun as *const _ as *const u8, len)
+        }
+    };
+    unsafe {
+        let copy_len = len.min(*addrlen as usize);
+        core::ptr::copy_nonoverlapping(src, dst as *mut u8, copy_len);
+        *addrlen = len as _;
     }
 }
 
 fn from_sockaddr(
     addr: *const ctypes::sockaddr,
     addrlen: ctypes::socklen_t,
-) -> LinuxResult<SocketAddr> {
+) -> LinuxResult<SockAddr> {
     if addr.is_null() {
         return Err(LinuxError::EFAULT);
     }
-    if addrlen != size_of::<ctypes::sockaddr>() as _ {
+    if (addrlen as usize) < size_of::<ctypes::sa_family_t>() {
         return Err(LinuxError::EINVAL);
     }
 
-    let mid = unsafe { *(addr as *const ctypes::sockaddr_in) };
-    if mid.sin_family != ctypes::AF_INET as u16 {
-        return Err(LinuxError::EINVAL);
-    }
-
-    let res = SocketAddr::V4(mid.into());
+    let res = match unsafe { (*addr).sa_family } as u32 {
+        // the address of a Unix domain socket may be shorter than `sockaddr`
+        ctypes::AF_UNIX => {
+            return unsafe { UnixAddr::from_sockaddr(addr, addrlen) }.map(SockAddr::Unix);
+        }
+        _ if (addrlen as usize) < size_of::<ctypes::sockaddr>() => return Err(LinuxError::EINVAL),
+        ctypes::AF_INET => SocketAddr::V4(unsafe { *(addr as *const ctypes::sockaddr_in) }.into()),
+        ctypes::AF_INET6 => {
+            if (addrlen as usize) < size_of::<ctypes::sockaddr_in6>() {
+                return Err(LinuxError::EINVAL);
+            }
+            let addr = SocketAddrV6::from(unsafe { *(addr as *const ctypes::sockaddr_in6) });
+            // IPv4-mapped addresses are handled as IPv4 ones by the network stack.
+            match addr.ip().to_ipv4_mapped() {
+                Some(ip) => SocketAddr::new(ip.into(), addr.port()),
+                None => SocketAddr::V6(addr),
+            }
+        }
+        _ => return Err(LinuxError::EAFNOSUPPORT),
+    };
     debug!("    load sockaddr:{:#x} => {:?}", addr as usize, res);
-    Ok(res)
+    Ok(SockAddr::Inet(res))
 }
 
 /// Create an socket for communication.
@@ -234,19 +536,86 @@ pub fn sys_socket(domain: c_int, socktype: c_int, protocol: c_int) -> c_int {
     let (domain, socktype, protocol) = (domain as u32, socktype as u32, protocol as u32);
     syscall_body!(sys_socket, {
         match (domain, socktype, protocol) {
-            (ctypes::AF_INET, ctypes::SOCK_STREAM, ctypes::IPPROTO_TCP)
-            | (ctypes::AF_INET, ctypes::SOCK_STREAM, 0) => {
+            // sockets of both families are dual-stack
+            #[cfg(feature = "net")]
+            (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_STREAM, ctypes::IPPROTO_TCP)
+            | (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_STREAM, 0) => {
                 Socket::Tcp(Mutex::new(TcpSocket::new())).add_to_fd_table()
             }
-            (ctypes::AF_INET, ctypes::SOCK_DGRAM, ctypes::IPPROTO_UDP)
-            | (ctypes::AF_INET, ctypes::SOCK_DGRAM, 0) => {
+            #[cfg(feature = "net")]
+            (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_DGRAM, ctypes::IPPROTO_UDP)
+            | (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_DGRAM, 0) => {
                 Socket::Udp(Mutex::new(UdpSocket::new())).add_to_fd_table()
             }
+            #[cfg(feature = "net")]
+            (ctypes::AF_INET, ctypes::SOCK_DGRAM, ctypes::IPPROTO_ICMP) => {
+                Socket::Icmp(Mutex::new(IcmpSocket::new(false))).add_to_fd_table()
+            }
+            #[cfg(feature = "net")]
+            (ctypes::AF_INET6, ctypes::SOCK_DGRAM, ctypes::IPPROTO_ICMPV6) => {
+                Socket::Icmp(Mutex::new(IcmpSocket::new(true))).add_to_fd_table()
+            }
+            // the IP header is always built by the stack, `IPPROTO_RAW` is not supported
+            #[cfg(feature = "net")]
+            (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_RAW, 1..ctypes::IPPROTO_RAW) => {
+                let ipv6 = domain == ctypes::AF_INET6;
+                let socket = RawSocket::new(ipv6, protocol as u8);
+                Socket::Raw(Mutex::new(socket)).add_to_fd_table()
+            }
+            #[cfg(feature = "net")]
+            (ctypes::AF_INET | ctypes::AF_INET6, ctypes::SOCK_RAW, _) => {
+                Err(LinuxError::EPROTONOSUPPORT)
+            }
+            #[cfg(not(feature = "net"))]
+            (ctypes::AF_INET | ctypes::AF_INET6, _, _) => Err(LinuxError::EAFNOSUPPORT),
+            (ctypes::AF_UNIX, ctypes::SOCK_STREAM, 0) => {
+                Socket::Unix(UnixSocket::new_stream()).add_to_fd_table()
+            }
+            (ctypes::AF_UNIX, ctypes::SOCK_DGRAM, 0) => {
+                Socket::Unix(UnixSocket::new_datagram()).add_to_fd_table()
+            }
             _ => Err(LinuxError::EINVAL),
         }
     })
 }
 
+/// Create a pair of connected sockets.
+///
+/// Only Unix domain sockets of type `SOCK_STREAM` and `SOCK_DGRAM` are
+/// supported.
+///
+/// Return 0 if success.
+pub fn sys_socketpair(domain: c_int, socktype: c_int, protocol: c_int, sv: &mut [c_int]) -> c_int {
+    debug!(
+        "sys_socketpair <= {} {} {} {:#x}",
+        domain,
+        socktype,
+        protocol,
+        sv.as_ptr() as usize
+    );
+    let (domain, socktype, protocol) = (domain as u32, socktype as u32, protocol as u32);
+    syscall_body!(sys_socketpair, {
+        if sv.len() != 2 {
+            return Err(LinuxError::EFAULT);
+        }
+        let (socket0, socket1) = match (domain, socktype, protocol) {
+            (ctypes::AF_UNIX, ctypes::SOCK_STREAM, 0) => UnixSocket::pair(true),
+            (ctypes::AF_UNIX, ctypes::SOCK_DGRAM, 0) => UnixSocket::pair(false),
+            (ctypes::AF_UNIX, _, _) => return Err(LinuxError::EPROTONOSUPPORT),
+            _ => return Err(LinuxError::EOPNOTSUPP),
+        };
+        let fd0 = Socket::Unix(socket0).add_to_fd_table()?;
+        let fd1 = Socket::Unix(socket1).add_to_fd_table().inspect_err(|_| {
+            super::fd_ops::close_file_like(fd0).ok();
+        })?;
+
+        sv[0] = fd0;
+        sv[1] = fd1;
+
+        Ok(0)
+    })
+}
+
 /// Bind a address to a socket.
 ///
 /// Return 0 if success.
@@ -356,9 +725,7 @@ pub unsafe fn sys_recvfrom(
 
         let res = socket.recvfrom(buf)?;
         if let Some(addr) = res.1 {
-            unsafe {
-                (*socket_addr, *addrlen) = into_sockaddr(addr);
-            }
+            unsafe { write_sockaddr(addr, socket_addr, addrlen) };
         }
         Ok(res.0)
     })
@@ -419,10 +786,8 @@ pub unsafe fn sys_accept(
         let socket = Socket::from_fd(socket_fd)?;
         let new_socket = socket.accept()?;
         let addr = new_socket.peer_addr()?;
-        let new_fd = Socket::add_to_fd_table(Socket::Tcp(Mutex::new(new_socket)))?;
-        unsafe {
-            (*socket_addr, *socket_len) = into_sockaddr(addr);
-        }
+        let new_fd = Socket::add_to_fd_table(new_socket)?;
+        unsafe { write_sockaddr(addr, socket_addr, socket_len) };
         Ok(new_fd)
     })
 }
@@ -443,14 +808,16 @@ pub fn sys_shutdown(
 
 /// Query addresses for a domain name.
 ///
-/// Only IPv4. Ports are always 0. Ignore servname and hint.
+/// Both IPv4 and IPv6 addresses are returned, filtered by the `ai_family` of
+/// hint. Other fields of hint are ignored.
 /// Results' ai_flags and ai_canonname are 0 or NULL.
 ///
 /// Return address number if success.
+#[cfg(feature = "net")]
 pub unsafe fn sys_getaddrinfo(
     nodename: *const c_char,
     servname: *const c_char,
-    _hints: *const ctypes::addrinfo,
+    hints: *const ctypes::addrinfo,
     res: *mut *mut ctypes::addrinfo,
 ) -> c_int {
     let name = char_ptr_to_str(nodename);
@@ -463,17 +830,42 @@ pub unsafe fn sys_getaddrinfo(
         if res.is_null() {
             return Err(LinuxError::EFAULT);
         }
+        let family = if hints.is_null() {
+            ctypes::AF_UNSPEC
+        } else {
+            unsafe { (*hints).ai_family as u32 }
+        };
+        if !matches!(
+            family,
+            ctypes::AF_UNSPEC | ctypes::AF_INET | ctypes::AF_INET6
+        ) {
+            return Err(LinuxError::EAFNOSUPPORT);
+        }
 
         let port = port.map_or(0, |p| p.parse::<u16>().unwrap_or(0));
         let ip_addrs = if let Ok(domain) = name {
             if let Ok(a) = domain.parse::<IpAddr>() {
                 vec![a]
             } else {
-                axnet::dns_query(domain)?
+                match family {
+                    ctypes::AF_INET => axnet::dns_query_type(domain, axnet::DnsRecordType::A)?,
+                    ctypes::AF_INET6 => axnet::dns_query_type(domain, axnet::DnsRecordType::Aaaa)?,
+                    _ => axnet::dns_query(domain)?,
+                }
             }
+        } else if family == ctypes::AF_INET6 {
+            vec![Ipv6Addr::LOCALHOST.into()]
         } else {
             vec![Ipv4Addr::LOCALHOST.into()]
         };
+        let ip_addrs: Vec<IpAddr> = ip_addrs
+            .into_iter()
+            .filter(|ip| match family {
+                ctypes::AF_INET => ip.is_ipv4(),
+                ctypes::AF_INET6 => ip.is_ipv6(),
+                _ => true,
+            })
+            .collect();
 
         let len = ip_addrs.len().min(ctypes::MAXADDRS as usize);
         if len == 0 {
@@ -482,31 +874,40 @@ pub unsafe fn sys_getaddrinfo(
 
         let mut out: Vec<ctypes::aibuf> = Vec::with_capacity(len);
         for (i, &ip) in ip_addrs.iter().enumerate().take(len) {
-            let buf = match ip {
-                IpAddr::V4(ip) => ctypes::aibuf {
-                    ai: ctypes::addrinfo {
-                        ai_family: ctypes::AF_INET as _,
-                        // TODO: This is a hard-code part, only return TCP parameters
-                        ai_socktype: ctypes::SOCK_STREAM as _,
-                        ai_protocol: ctypes::IPPROTO_TCP as _,
-                        ai_addrlen: size_of::<ctypes::sockaddr_in>() as _,
-                        ai_addr: core::ptr::null_mut(),
-                        ai_canonname: core::ptr::null_mut(),
-                        ai_next: core::ptr::null_mut(),
-                        ai_flags: 0,
-                    },
-                    sa: ctypes::aibuf_sa {
+            let (ai_family, ai_addrlen, sa) = match ip {
+                IpAddr::V4(ip) => (
+                    ctypes::AF_INET,
+                    size_of::<ctypes::sockaddr_in>(),
+                    ctypes::aibuf_sa {
                         sin: SocketAddrV4::new(ip, port).into(),
                     },
-                    slot: i as i16,
-                    lock: [0],
-                    ref_: 0,
-                },
-                _ => panic!("IPv6 is not supported"),
+                ),
+                IpAddr::V6(ip) => (
+                    ctypes::AF_INET6,
+                    size_of::<ctypes::sockaddr_in6>(),
+                    ctypes::aibuf_sa {
+                        sin6: SocketAddrV6::new(ip, port, 0, 0).into(),
+                    },
+                ),
             };
-            out.push(buf);
-            out[i].ai.ai_addr =
-                unsafe { core::ptr::addr_of_mut!(out[i].sa.sin) as *mut ctypes::sockaddr };
+            out.push(ctypes::aibuf {
+                ai: ctypes::addrinfo {
+                    ai_family: ai_family as _,
+                    // TODO: This is a hard-code part, only return TCP parameters
+                    ai_socktype: ctypes::SOCK_STREAM as _,
+                    ai_protocol: ctypes::IPPROTO_TCP as _,
+                    ai_addrlen: ai_addrlen as _,
+                    ai_addr: core::ptr::null_mut(),
+                    ai_canonname: core::ptr::null_mut(),
+                    ai_next: core::ptr::null_mut(),
+                    ai_flags: 0,
+                },
+                sa,
+                slot: i as i16,
+                lock: [0],
+                ref_: 0,
+            });
+            out[i].ai.ai_addr = core::ptr::addr_of_mut!(out[i].sa) as *mut ctypes::sockaddr;
             if i > 0 {
                 out[i - 1].ai.ai_next = core::ptr::addr_of_mut!(out[i].ai);
             }
@@ -520,6 +921,7 @@ pub unsafe fn sys_getaddrinfo(
 }
 
 /// Free queried `addrinfo` struct
+#[cfg(feature = "net")]
 pub unsafe fn sys_freeaddrinfo(res: *mut ctypes::addrinfo) {
     if res.is_null() {
         return;
@@ -549,9 +951,8 @@ pub unsafe fn sys_getsockname(
         if unsafe { *addrlen } < size_of::<ctypes::sockaddr>() as u32 {
             return Err(LinuxError::EINVAL);
         }
-        unsafe {
-            (*addr, *addrlen) = into_sockaddr(Socket::from_fd(sock_fd)?.local_addr()?);
-        }
+        let local_addr = Socket::from_fd(sock_fd)?.local_addr()?;
+        unsafe { write_sockaddr(local_addr, addr, addrlen) };
         Ok(0)
     })
 }
@@ -573,9 +974,286 @@ pub unsafe fn sys_getpeername(
         if unsafe { *addrlen } < size_of::<ctypes::sockaddr>() as u32 {
             return Err(LinuxError::EINVAL);
         }
-        unsafe {
-            (*addr, *addrlen) = into_sockaddr(Socket::from_fd(sock_fd)?.peer_addr()?);
+        let peer_addr = Socket::from_fd(sock_fd)?.peer_addr()?;
+        unsafe { write_sockaddr(peer_addr, addr, addrlen) };
+        Ok(0)
+    })
+}
+
+/// Reads an option value of type `T` from the buffer `optval` of size
+/// `optlen`.
+#[cfg(feature = "net")]
+unsafe fn read_optval<T>(optval: *const c_void, optlen: ctypes::socklen_t) -> LinuxResult<T> {
+    if optval.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    if (optlen as usize) < size_of::<T>() {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(unsafe { (optval as *const T).read_unaligned() })
+}
+
+/// Writes an option value to the buffer `optval` whose size is given by
+/// `*optlen`, then sets `*optlen` to the number of bytes written.
+///
+/// The value is truncated if the buffer is too small.
+unsafe fn write_optval<T>(
+    val: T,
+    optval: *mut c_void,
+    optlen: *mut ctypes::socklen_t,
+) -> LinuxResult {
+    if optval.is_null() || optlen.is_null() {
+        return Err(LinuxError::EFAULT);
+    }
+    unsafe {
+        let len = size_of::<T>().min(*optlen as usize);
+        core::ptr::copy_nonoverlapping(&val as *const T as *const u8, optval as *mut u8, len);
+        *optlen = len as _;
+    }
+    Ok(())
+}
+
+/// Converts a `SO_RCVTIMEO` or `SO_SNDTIMEO` value, a zero timeout means
+/// blocking indefinitely.
+#[cfg(feature = "net")]
+fn timeout_from_timeval(tv: ctypes::timeval) -> LinuxResult<Option<Duration>> {
+    if tv.tv_sec < 0 || !(0..1_000_000).contains(&tv.tv_usec) {
+        return Err(LinuxError::EDOM);
+    }
+    let timeout = Duration::from(tv);
+    Ok((!timeout.is_zero()).then_some(timeout))
+}
+
+/// Set options on the socket.
+///
+/// Supported options are `SO_REUSEADDR`, `SO_RCVBUF`, `SO_SNDBUF`,
+/// `SO_KEEPALIVE`, `SO_LINGER`, `SO_RCVTIMEO`, `SO_SNDTIMEO` and
+/// `SO_BROADCAST` at `SOL_SOCKET`, `TCP_NODELAY` at `IPPROTO_TCP`, `IP_TTL` at
+/// `IPPROTO_IP` and `IPV6_UNICAST_HOPS` at `IPPROTO_IPV6`. They only apply to
+/// IP sockets.
+///
+/// Return 0 if success.
+pub unsafe fn sys_setsockopt(
+    socket_fd: c_int,
+    level: c_int,
+    optname: c_int,
+    optval: *const c_void,
+    optlen: ctypes::socklen_t,
+) -> c_int {
+    debug!(
+        "sys_setsockopt <= {} {} {} {:#x} {}",
+        socket_fd, level, optname, optval as usize, optlen
+    );
+    syscall_body!(sys_setsockopt, {
+        let socket = Socket::from_fd(socket_fd)?;
+        let res: LinuxResult = match *socket {
+            Socket::Unix(_) => Err(LinuxError::ENOPROTOOPT),
+            #[cfg(feature = "net")]
+            _ => unsafe { set_ip_option(&socket, level as _, optname as _, optval, optlen) },
+        };
+        res.map(|_| 0)
+    })
+}
+
+/// Sets an option of an IP socket, see [`sys_setsockopt`].
+#[cfg(feature = "net")]
+unsafe fn set_ip_option(
+    socket: &Socket,
+    level: u32,
+    optname: u32,
+    optval: *const c_void,
+    optlen: ctypes::socklen_t,
+) -> LinuxResult {
+    let mut opts = socket.options()?;
+    let int_val = || unsafe { read_optval::<c_int>(optval, optlen) };
+    match (level, optname) {
+        (ctypes::SOL_SOCKET, ctypes::SO_REUSEADDR) => opts.reuse_addr = int_val()? != 0,
+        (ctypes::SOL_SOCKET, ctypes::SO_RCVBUF) => opts.recv_buf_size = int_val()?.max(0) as _,
+        (ctypes::SOL_SOCKET, ctypes::SO_SNDBUF) => opts.send_buf_size = int_val()?.max(0) as _,
+        (ctypes::SOL_SOCKET, ctypes::SO_KEEPALIVE) => opts.keep_alive = int_val()? != 0,
+        (ctypes::SOL_SOCKET, ctypes::SO_BROADCAST) => opts.broadcast = int_val()? != 0,
+        (ctypes::SOL_SOCKET, ctypes::SO_LINGER) => {
+            let linger = unsafe { read_optval::<ctypes::linger>(optval, optlen)? };
+            opts.linger =
+                (linger.l_onoff != 0).then(|| Duration::from_secs(linger.l_linger.max(0) as u64));
+        }
+        (ctypes::SOL_SOCKET, ctypes::SO_RCVTIMEO) => {
+            let tv = unsafe { read_optval::<ctypes::timeval>(optval, optlen)? };
+            opts.recv_timeout = timeout_from_timeval(tv)?;
+        }
+        (ctypes::SOL_SOCKET, ctypes::SO_SNDTIMEO) => {
+            let tv = unsafe { read_optval::<ctypes::timeval>(optval, optlen)? };
+            opts.send_timeout = timeout_from_timeval(tv)?;
+        }
+        (ctypes::IPPROTO_TCP, ctypes::TCP_NODELAY) => opts.no_delay = int_val()? != 0,
+        (ctypes::IPPROTO_IP, ctypes::IP_TTL)
+        | (ctypes::IPPROTO_IPV6, ctypes::IPV6_UNICAST_HOPS) => {
+            opts.ttl = match int_val()? {
+                // -1 restores the default
+                -1 => SocketOptions::DEFAULT_TTL,
+                ttl @ 1..=255 => ttl as u8,
+                _ => return Err(LinuxError::EINVAL),
+            };
+        }
+        _ => return Err(LinuxError::ENOPROTOOPT),
+    }
+    socket.set_options(opts)
+}
+
+/// Get options on the socket.
+///
+/// Supports the options of [`sys_setsockopt`], as well as `SO_TYPE` and
+/// `SO_ERROR` at `SOL_SOCKET`, which also apply to Unix domain sockets.
+///
+/// Return 0 if success.
+pub unsafe fn sys_getsockopt(
+    socket_fd: c_int,
+    level: c_int,
+    optname: c_int,
+    optval: *mut c_void,
+    optlen: *mut ctypes::socklen_t,
+) -> c_int {
+    debug!(
+        "sys_getsockopt <= {} {} {} {:#x} {:#x}",
+        socket_fd, level, optname, optval as usize, optlen as usize
+    );
+    syscall_body!(sys_getsockopt, {
+        let socket = Socket::from_fd(socket_fd)?;
+        let write_int = |val: c_int| unsafe { write_optval(val, optval, optlen) };
+        match (level as u32, optname as u32) {
+            (ctypes::SOL_SOCKET, ctypes::SO_TYPE) => write_int(match *socket {
+                #[cfg(feature = "net")]
+                Socket::Udp(_) => ctypes::SOCK_DGRAM as _,
+                #[cfg(feature = "net")]
+                Socket::Tcp(_) => ctypes::SOCK_STREAM as _,
+                #[cfg(feature = "net")]
+                Socket::Icmp(_) => ctypes::SOCK_DGRAM as _,
+                #[cfg(feature = "net")]
+                Socket::Raw(_) => ctypes::SOCK_RAW as _,
+                Socket::Unix(ref unixsocket) => unixsocket.socktype() as _,
+            })?,
+            (ctypes::SOL_SOCKET, ctypes::SO_ERROR) => {
+                write_int(socket.take_error().map_or(0, |e| e.code()))?
+            }
+            #[cfg(feature = "net")]
+            (level, optname) => unsafe { get_ip_option(&socket, level, optname, optval, optlen)? },
+            #[cfg(not(feature = "net"))]
+            _ => return Err(LinuxError::ENOPROTOOPT),
         }
         Ok(0)
     })
 }
+
+/// Gets an option of an IP socket, see [`sys_getsockopt`].
+#[cfg(feature = "net")]
+unsafe fn get_ip_option(
+    socket: &Socket,
+    level: u32,
+    optname: u32,
+    optval: *mut c_void,
+    optlen: *mut ctypes::socklen_t,
+) -> LinuxResult {
+    let opts = socket.options()?;
+    let write_int = |val: c_int| unsafe { write_optval(val, optval, optlen) };
+    let write_timeout = |timeout: Option<Duration>| {
+        let tv = ctypes::timeval::from(timeout.unwrap_or_default());
+        unsafe { write_optval(tv, optval, optlen) }
+    };
+    match (level, optname) {
+        (ctypes::SOL_SOCKET, ctypes::SO_REUSEADDR) => write_int(opts.reuse_addr as _),
+        (ctypes::SOL_SOCKET, ctypes::SO_RCVBUF) => write_int(socket.buf_sizes()?.0 as _),
+        (ctypes::SOL_SOCKET, ctypes::SO_SNDBUF) => write_int(socket.buf_sizes()?.1 as _),
+        (ctypes::SOL_SOCKET, ctypes::SO_KEEPALIVE) => write_int(opts.keep_alive as _),
+        (ctypes::SOL_SOCKET, ctypes::SO_BROADCAST) => write_int(opts.broadcast as _),
+        (ctypes::SOL_SOCKET, ctypes::SO_LINGER) => {
+            let linger = ctypes::linger {
+                l_onoff: opts.linger.is_some() as _,
+                l_linger: opts.linger.map_or(0, |t| t.as_secs() as _),
+            };
+            unsafe { write_optval(linger, optval, optlen) }
+        }
+        (ctypes::SOL_SOCKET, ctypes::SO_RCVTIMEO) => write_timeout(opts.recv_timeout),
+        (ctypes::SOL_SOCKET, ctypes::SO_SNDTIMEO) => write_timeout(opts.send_timeout),
+        (ctypes::IPPROTO_TCP, ctypes::TCP_NODELAY) => write_int(opts.no_delay as _),
+        (ctypes::IPPROTO_IP, ctypes::IP_TTL)
+        | (ctypes::IPPROTO_IPV6, ctypes::IPV6_UNICAST_HOPS) => write_int(opts.ttl as _),
+        _ => Err(LinuxError::ENOPROTOOPT),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load<T>(addr: &T, addrlen: usize) -> LinuxResult<SocketAddr> {
+        match from_sockaddr(addr as *const T as *const ctypes::sockaddr, addrlen as _)? {
+            SockAddr::Inet(addr) => Ok(addr),
+            addr => panic!("unexpected address {:?}", addr),
+        }
+    }
+
+    #[test]
+    fn test_sockaddr_in6() {
+        let addr = SocketAddrV6::new("2001:db8::1".parse().unwrap(), 8080, 0x12345, 3);
+        let sin6 = ctypes::sockaddr_in6::from(addr);
+        assert_eq!(sin6.sin6_family, ctypes::AF_INET6 as u16);
+        assert_eq!(sin6.sin6_port, 8080u16.to_be());
+        assert_eq!(sin6.sin6_flowinfo, 0x12345u32.to_be());
+        assert_eq!(
+            unsafe { sin6.sin6_addr.__in6_union.__s6_addr },
+            addr.ip().octets()
+        );
+        assert_eq!(sin6.sin6_scope_id, 3);
+        assert_eq!(SocketAddrV6::from(sin6), addr);
+    }
+
+    #[test]
+    fn test_load_ipv6() {
+        let addr = SocketAddrV6::new(Ipv6Addr::LOCALHOST, 80, 0, 0);
+        let sin6 = ctypes::sockaddr_in6::from(addr);
+        let len = size_of::<ctypes::sockaddr_in6>();
+        assert_eq!(load(&sin6, len), Ok(SocketAddr::V6(addr)));
+        assert_eq!(load(&sin6, len - 1), Err(LinuxError::EINVAL));
+
+        let sin = ctypes::sockaddr_in::from(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 80));
+        let len = size_of::<ctypes::sockaddr_in>();
+        assert_eq!(load(&sin, len), Ok("127.0.0.1:80".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_load_ipv4_mapped() {
+        let ip = Ipv4Addr::new(10, 0, 2, 15);
+        let len = size_of::<ctypes::sockaddr_in6>();
+        let mapped = SocketAddrV6::new(ip.to_ipv6_mapped(), 5555, 0, 0);
+        let sin6 = ctypes::sockaddr_in6::from(mapped);
+        assert_eq!(load(&sin6, len), Ok(SocketAddr::new(ip.into(), 5555)));
+
+        // the deprecated IPv4-compatible addresses are not IPv4 ones
+        let compatible = SocketAddrV6::new(ip.to_ipv6_compatible(), 5555, 0, 0);
+        let sin6 = ctypes::sockaddr_in6::from(compatible);
+        assert_eq!(load(&sin6, len), Ok(SocketAddr::V6(compatible)));
+    }
+
+    #[test]
+    fn test_write_sockaddr_in6() {
+        let addr = SocketAddrV6::new("fe80::1".parse().unwrap(), 443, 0, 2);
+        let mut sin6: ctypes::sockaddr_in6 = unsafe { core::mem::zeroed() };
+        let mut len = size_of::<ctypes::sockaddr_in6>() as ctypes::socklen_t;
+        let dst = &mut sin6 as *mut _ as *mut ctypes::sockaddr;
+        unsafe { write_sockaddr(SockAddr::Inet(addr.into()), dst, &mut len) };
+        assert_eq!(len as usize, size_of::<ctypes::sockaddr_in6>());
+        assert_eq!(load(&sin6, len as _), Ok(SocketAddr::V6(addr)));
+
+        // truncated to the buffer, but the full length is reported
+        let mut buf = [0u8; 8];
+        let mut len = buf.len() as ctypes::socklen_t;
+        let dst = buf.as_mut_ptr() as *mut ctypes::sockaddr;
+        unsafe { write_sockaddr(SockAddr::Inet(addr.into()), dst, &mut len) };
+        assert_eq!(len as usize, size_of::<ctypes::sockaddr_in6>());
+        assert_eq!(
+            u16::from_ne_bytes([buf[0], buf[1]]),
+            ctypes::AF_INET6 as u16
+        );
+        assert_eq!(u16::from_be_bytes([buf[2], buf[3]]), 443);
+    }
+}
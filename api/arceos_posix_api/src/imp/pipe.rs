@@ -1,11 +1,13 @@
 use alloc::sync::Arc;
 use core::ffi::c_int;
+use core::mem::ManuallyDrop;
+use core::task::Waker;
 
 use axerrno::{LinuxError, LinuxResult};
 use axio::PollState;
 use axsync::Mutex;
 
-use super::fd_ops::{FileLike, add_file_like, close_file_like};
+use super::fd_ops::{FileLike, PollWakers, add_file_like, close_file_like};
 use crate::ctypes;
 
 #[derive(Copy, Clone, PartialEq)]
@@ -76,19 +78,29 @@ impl PipeRingBuffer {
 
 pub struct Pipe {
     readable: bool,
-    buffer: Arc<Mutex<PipeRingBuffer>>,
+    /// Dropped before the pollers are woken up as the pipe is closed.
+    buffer: ManuallyDrop<Arc<Mutex<PipeRingBuffer>>>,
+    wakers: Arc<PollWakers>,
+    /// The wakers of the other end.
+    peer_wakers: Arc<PollWakers>,
 }
 
 impl Pipe {
     pub fn new() -> (Pipe, Pipe) {
         let buffer = Arc::new(Mutex::new(PipeRingBuffer::new()));
+        let read_wakers = Arc::new(PollWakers::new());
+        let write_wakers = Arc::new(PollWakers::new());
         let read_end = Pipe {
             readable: true,
-            buffer: buffer.clone(),
+            buffer: ManuallyDrop::new(buffer.clone()),
+            wakers: read_wakers.clone(),
+            peer_wakers: write_wakers.clone(),
         };
         let write_end = Pipe {
             readable: false,
-            buffer,
+            buffer: ManuallyDrop::new(buffer),
+            wakers: write_wakers,
+            peer_wakers: read_wakers,
         };
         (read_end, write_end)
     }
@@ -123,15 +135,24 @@ impl FileLike for Pipe {
                 drop(ring_buffer);
                 // Data not ready, wait for write end
                 crate::sys_sched_yield(); // TODO: use synconize primitive
+                #[cfg(feature = "signal")]
+                if let Err(e) = crate::imp::signal::check_interrupted(true) {
+                    return (read_size > 0).then_some(read_size).ok_or(e);
+                }
                 continue;
             }
             for _ in 0..loop_read {
                 if read_size == max_len {
-                    return Ok(read_size);
+                    break;
                 }
                 buf[read_size] = ring_buffer.read_byte();
                 read_size += 1;
             }
+            // the write end may be waiting in `poll` for space
+            self.peer_wakers.wake();
+            if read_size == max_len {
+                return Ok(read_size);
+            }
         }
     }
 
@@ -143,20 +164,38 @@ impl FileLike for Pipe {
         let max_len = buf.len();
         loop {
             let mut ring_buffer = self.buffer.lock();
+            // the read end is closed
+            if self.write_end_close() {
+                if write_size > 0 {
+                    return Ok(write_size);
+                }
+                #[cfg(feature = "signal")]
+                crate::imp::signal::send_current(ctypes::SIGPIPE);
+                return Err(LinuxError::EPIPE);
+            }
             let loop_write = ring_buffer.available_write();
             if loop_write == 0 {
                 drop(ring_buffer);
                 // Buffer is full, wait for read end to consume
                 crate::sys_sched_yield(); // TODO: use synconize primitive
+                #[cfg(feature = "signal")]
+                if let Err(e) = crate::imp::signal::check_interrupted(true) {
+                    return (write_size > 0).then_some(write_size).ok_or(e);
+                }
                 continue;
             }
             for _ in 0..loop_write {
                 if write_size == max_len {
-                    return Ok(write_size);
+                    break;
                 }
                 ring_buffer.write_byte(buf[write_size]);
                 write_size += 1;
             }
+            // the read end may be waiting in `poll` for data
+            self.peer_wakers.wake();
+            if write_size == max_len {
+                return Ok(write_size);
+            }
         }
     }
 
@@ -180,14 +219,34 @@ impl FileLike for Pipe {
     fn poll(&self) -> LinuxResult<PollState> {
         let buf = self.buffer.lock();
         Ok(PollState {
-            readable: self.readable() && buf.available_read() > 0,
-            writable: self.writable() && buf.available_write() > 0,
+            // reading at the end of file does not block either
+            readable: self.readable() && (buf.available_read() > 0 || self.write_end_close()),
+            // writing to a closed pipe fails immediately
+            writable: self.writable() && (buf.available_write() > 0 || self.write_end_close()),
         })
     }
 
     fn set_nonblocking(&self, _nonblocking: bool) -> LinuxResult {
         Ok(())
     }
+
+    fn register_waker(&self, waker: &Waker) -> bool {
+        self.wakers.register(waker);
+        true
+    }
+
+    fn hung_up(&self) -> bool {
+        // only the read end, as writing fails with `EPIPE` instead
+        self.readable() && self.write_end_close()
+    }
+}
+
+impl Drop for Pipe {
+    fn drop(&mut self) {
+        unsafe { ManuallyDrop::drop(&mut self.buffer) };
+        // the other end may be waiting in `poll` for the end of file
+        self.peer_wakers.wake();
+    }
 }
 
 /// Create a pipe
@@ -0,0 +1,92 @@
+use crate::ctypes;
+
+use axerrno::LinuxError;
+use axtask::WaitQueue;
+use spin::Mutex;
+
+use core::ffi::{c_int, c_uint};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// The state of a `pthread_barrier_t`.
+///
+/// The generation is bumped each time `count` threads have arrived, which
+/// releases them all.
+struct Barrier {
+    count: usize,
+    arrived: Mutex<usize>,
+    generation: AtomicUsize,
+    wq: WaitQueue,
+}
+
+impl Barrier {
+    /// Waits until `count` threads have arrived, and returns whether the
+    /// current thread is the last one.
+    fn wait(&self) -> bool {
+        let mut arrived = self.arrived.lock();
+        let generation = self.generation.load(Ordering::Acquire);
+        *arrived += 1;
+        if *arrived == self.count {
+            *arrived = 0;
+            self.generation.fetch_add(1, Ordering::Release);
+            drop(arrived);
+            self.wq.notify_all(false);
+            true
+        } else {
+            drop(arrived);
+            self.wq
+                .wait_until(|| self.generation.load(Ordering::Acquire) != generation);
+            false
+        }
+    }
+}
+
+/// Initialize a barrier, which releases the threads waiting on it once
+/// `count` of them have arrived.
+pub fn sys_pthread_barrier_init(
+    barrier: *mut ctypes::pthread_barrier_t,
+    _attr: *const ctypes::pthread_barrierattr_t,
+    count: c_uint,
+) -> c_int {
+    debug!(
+        "sys_pthread_barrier_init <= {:#x}, {}",
+        barrier as usize, count
+    );
+    syscall_body!(sys_pthread_barrier_init, {
+        if count == 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let state = Barrier {
+            count: count as usize,
+            arrived: Mutex::new(0),
+            generation: AtomicUsize::new(0),
+            wq: WaitQueue::new(),
+        };
+        unsafe { super::init_state(barrier.cast(), state)? };
+        Ok(0)
+    })
+}
+
+/// Destroy a barrier.
+pub fn sys_pthread_barrier_destroy(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    debug!("sys_pthread_barrier_destroy <= {:#x}", barrier as usize);
+    syscall_body!(sys_pthread_barrier_destroy, {
+        unsafe { super::destroy_state::<Barrier>(barrier.cast())? };
+        Ok(0)
+    })
+}
+
+/// Wait on a barrier until enough threads have arrived.
+///
+/// Returns `PTHREAD_BARRIER_SERIAL_THREAD` in the last thread arrived, and 0
+/// in the others.
+pub fn sys_pthread_barrier_wait(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    debug!("sys_pthread_barrier_wait <= {:#x}", barrier as usize);
+    syscall_body!(sys_pthread_barrier_wait, {
+        let barrier: &Barrier = unsafe { super::state(barrier.cast())? };
+        if barrier.wait() {
+            Ok(ctypes::PTHREAD_BARRIER_SERIAL_THREAD)
+        } else {
+            Ok(0)
+        }
+    })
+}
@@ -0,0 +1,148 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axerrno::{LinuxError, LinuxResult};
+use axtask::WaitQueue;
+
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::time::Duration;
+
+use super::mutex::PthreadMutex;
+
+/// The state of a `pthread_cond_t`.
+///
+/// Each signal bumps the sequence number, so that a waiter wakes up once it
+/// is changed since the waiter unlocked the mutex.
+struct Condvar {
+    /// The clock of timed waits, which need the `irq` feature.
+    #[cfg_attr(not(feature = "irq"), allow(dead_code))]
+    clock: u32,
+    seq: AtomicU32,
+    wq: WaitQueue,
+}
+
+impl Default for Condvar {
+    fn default() -> Self {
+        Self::new(ctypes::CLOCK_REALTIME)
+    }
+}
+
+impl Condvar {
+    const fn new(clock: u32) -> Self {
+        Self {
+            clock,
+            seq: AtomicU32::new(0),
+            wq: WaitQueue::new(),
+        }
+    }
+
+    /// Unlocks `mutex` and waits until a signal, or until `deadline` of
+    /// [`axhal::time::monotonic_time`] if any, then locks `mutex` again.
+    fn wait(&self, mutex: *mut ctypes::pthread_mutex_t, deadline: Option<Duration>) -> LinuxResult {
+        check_null_mut_ptr(mutex)?;
+        let mutex = unsafe { &*mutex.cast::<PthreadMutex>() };
+        let seq = self.seq.load(Ordering::Acquire);
+        mutex.unlock()?;
+        let res = super::wait_until(&self.wq, deadline, || {
+            self.seq.load(Ordering::Acquire) != seq
+        });
+        mutex.lock()?;
+        res
+    }
+
+    fn signal(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        self.wq.notify_one(false);
+    }
+
+    fn broadcast(&self) {
+        self.seq.fetch_add(1, Ordering::Release);
+        self.wq.notify_all(false);
+    }
+}
+
+fn cond(cond: *mut ctypes::pthread_cond_t) -> LinuxResult<&'static Condvar> {
+    unsafe { super::state_or_default(cond.cast()) }
+}
+
+/// Initialize a condition variable, with the clock of timed waits in `attr`.
+pub unsafe fn sys_pthread_cond_init(
+    cond: *mut ctypes::pthread_cond_t,
+    attr: *const ctypes::pthread_condattr_t,
+) -> c_int {
+    debug!("sys_pthread_cond_init <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_init, {
+        let clock = if attr.is_null() {
+            ctypes::CLOCK_REALTIME
+        } else {
+            unsafe { (*attr).__attr & 0x7fffffff }
+        };
+        if clock != ctypes::CLOCK_REALTIME && clock != ctypes::CLOCK_MONOTONIC {
+            return Err(LinuxError::EINVAL);
+        }
+        unsafe { super::init_state(cond.cast(), Condvar::new(clock))? };
+        Ok(0)
+    })
+}
+
+/// Destroy a condition variable.
+pub fn sys_pthread_cond_destroy(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_destroy <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_destroy, {
+        unsafe { super::destroy_state::<Condvar>(cond.cast())? };
+        Ok(0)
+    })
+}
+
+/// Unlock `mutex` and wait on the condition variable, then lock `mutex` again.
+pub fn sys_pthread_cond_wait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+) -> c_int {
+    debug!(
+        "sys_pthread_cond_wait <= {:#x}, {:#x}",
+        cond as usize, mutex as usize
+    );
+    syscall_body!(sys_pthread_cond_wait, {
+        self::cond(cond)?.wait(mutex, None)?;
+        Ok(0)
+    })
+}
+
+/// Like [`sys_pthread_cond_wait`], but gives up with `ETIMEDOUT` at the
+/// absolute time `abstime` of the clock of the condition variable.
+#[cfg(feature = "irq")]
+pub fn sys_pthread_cond_timedwait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    debug!(
+        "sys_pthread_cond_timedwait <= {:#x}, {:#x}",
+        cond as usize, mutex as usize
+    );
+    syscall_body!(sys_pthread_cond_timedwait, {
+        let cond = self::cond(cond)?;
+        let deadline = super::deadline(abstime, cond.clock)?;
+        cond.wait(mutex, Some(deadline))?;
+        Ok(0)
+    })
+}
+
+/// Wake up one of the threads waiting on the condition variable.
+pub fn sys_pthread_cond_signal(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_signal <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_signal, {
+        self::cond(cond)?.signal();
+        Ok(0)
+    })
+}
+
+/// Wake up all threads waiting on the condition variable.
+pub fn sys_pthread_cond_broadcast(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    debug!("sys_pthread_cond_broadcast <= {:#x}", cond as usize);
+    syscall_body!(sys_pthread_cond_broadcast, {
+        self::cond(cond)?.broadcast();
+        Ok(0)
+    })
+}
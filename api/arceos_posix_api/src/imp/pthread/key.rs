@@ -0,0 +1,126 @@
+use alloc::collections::BTreeMap;
+
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axerrno::{LinuxError, LinuxResult};
+use spin::Mutex;
+
+use core::ffi::{c_int, c_void};
+
+use super::{ForceSendSync, Pthread, TID_TO_PTHREAD};
+
+const KEYS_MAX: usize = ctypes::PTHREAD_KEYS_MAX as usize;
+
+/// The destructor of a key, called with the non-null value of an exiting
+/// thread.
+pub type Destructor = Option<unsafe extern "C" fn(*mut c_void)>;
+
+/// The created keys, with their destructors.
+static KEYS: Mutex<[Option<Destructor>; KEYS_MAX]> = Mutex::new([None; KEYS_MAX]);
+
+/// The values of the keys in a thread.
+pub(super) struct ThreadSpecific(Mutex<BTreeMap<usize, ForceSendSync<*mut c_void>>>);
+
+impl ThreadSpecific {
+    pub(super) const fn new() -> Self {
+        Self(Mutex::new(BTreeMap::new()))
+    }
+
+    /// Calls the destructors of the keys with non-null values, as the thread
+    /// exits.
+    ///
+    /// The destructors may set values again, so it is repeated for up to
+    /// `PTHREAD_DESTRUCTOR_ITERATIONS` times.
+    pub(super) fn run_destructors(&self) {
+        for _ in 0..ctypes::PTHREAD_DESTRUCTOR_ITERATIONS {
+            let values = core::mem::take(&mut *self.0.lock());
+            if values.is_empty() {
+                break;
+            }
+            for (key, value) in values {
+                let destructor = KEYS.lock()[key].flatten();
+                if let Some(destructor) = destructor {
+                    unsafe { destructor(value.0) };
+                }
+            }
+        }
+    }
+}
+
+/// Returns the current thread, or [`LinuxError::EINVAL`] if the current task
+/// is not a thread created by `pthread_create` (or the main thread), which
+/// has no values of keys.
+fn current() -> LinuxResult<&'static Pthread> {
+    Pthread::current().ok_or(LinuxError::EINVAL)
+}
+
+/// Returns the index of `key` if it is created.
+fn key_index(key: ctypes::pthread_key_t) -> LinuxResult<usize> {
+    let index = key as usize;
+    match KEYS.lock().get(index) {
+        Some(Some(_)) => Ok(index),
+        _ => Err(LinuxError::EINVAL),
+    }
+}
+
+/// Create a thread-specific data key, whose value is null in all threads.
+pub unsafe fn sys_pthread_key_create(
+    key: *mut ctypes::pthread_key_t,
+    destructor: Destructor,
+) -> c_int {
+    debug!("sys_pthread_key_create <= {:#x}", key as usize);
+    syscall_body!(sys_pthread_key_create, {
+        check_null_mut_ptr(key)?;
+        let mut keys = KEYS.lock();
+        let index = keys
+            .iter()
+            .position(Option::is_none)
+            .ok_or(LinuxError::EAGAIN)?;
+        keys[index] = Some(destructor);
+        unsafe { key.write(index as _) };
+        Ok(0)
+    })
+}
+
+/// Delete a thread-specific data key, without calling its destructor.
+pub fn sys_pthread_key_delete(key: ctypes::pthread_key_t) -> c_int {
+    debug!("sys_pthread_key_delete <= {}", key);
+    syscall_body!(sys_pthread_key_delete, {
+        let index = key_index(key)?;
+        // clear the values first, so that a new key at `index` starts with
+        // null values
+        for thread in TID_TO_PTHREAD.read().values() {
+            let thread = unsafe { &*(thread.0 as *const Pthread) };
+            thread.specific.0.lock().remove(&index);
+        }
+        KEYS.lock()[index] = None;
+        Ok(0)
+    })
+}
+
+/// Returns the value of `key` in the current thread, or null if it is not
+/// set, or if the current task is not a thread.
+pub fn sys_pthread_getspecific(key: ctypes::pthread_key_t) -> *mut c_void {
+    let (Ok(index), Ok(current)) = (key_index(key), current()) else {
+        return core::ptr::null_mut();
+    };
+    let values = current.specific.0.lock();
+    values
+        .get(&index)
+        .map_or(core::ptr::null_mut(), |value| value.0)
+}
+
+/// Set the value of `key` in the current thread.
+pub fn sys_pthread_setspecific(key: ctypes::pthread_key_t, value: *const c_void) -> c_int {
+    debug!("sys_pthread_setspecific <= {}, {:#x}", key, value as usize);
+    syscall_body!(sys_pthread_setspecific, {
+        let index = key_index(key)?;
+        let mut values = current()?.specific.0.lock();
+        if value.is_null() {
+            values.remove(&index);
+        } else {
+            values.insert(index, ForceSendSync(value as *mut c_void));
+        }
+        Ok(0)
+    })
+}
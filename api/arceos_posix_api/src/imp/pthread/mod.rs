@@ -1,14 +1,22 @@
 use alloc::{boxed::Box, collections::BTreeMap, sync::Arc};
 use core::cell::UnsafeCell;
 use core::ffi::{c_int, c_void};
+use core::sync::atomic::{AtomicPtr, Ordering};
+use core::time::Duration;
 
 use axerrno::{LinuxError, LinuxResult};
-use axtask::AxTaskRef;
+use axtask::{AxTaskRef, WaitQueue};
 use spin::RwLock;
 
 use crate::ctypes;
 
+pub mod barrier;
+pub mod cond;
+pub mod key;
 pub mod mutex;
+pub mod once;
+pub mod rwlock;
+pub mod sem;
 
 lazy_static::lazy_static! {
     static ref TID_TO_PTHREAD: RwLock<BTreeMap<u64, ForceSendSync<ctypes::pthread_t>>> = {
@@ -20,6 +28,7 @@ lazy_static::lazy_static! {
             retval: Arc::new(Packet {
                 result: UnsafeCell::new(core::ptr::null_mut()),
             }),
+            specific: Arc::new(key::ThreadSpecific::new()),
         };
         let ptr = Box::into_raw(Box::new(main_thread)) as *mut c_void;
         map.insert(main_tid, ForceSendSync(ptr));
@@ -37,6 +46,7 @@ unsafe impl<T> Sync for Packet<T> {}
 pub struct Pthread {
     inner: AxTaskRef,
     retval: Arc<Packet<*mut c_void>>,
+    specific: Arc<key::ThreadSpecific>,
 }
 
 impl Pthread {
@@ -51,19 +61,26 @@ impl Pthread {
             result: UnsafeCell::new(core::ptr::null_mut()),
         });
         let their_packet = my_packet.clone();
+        let specific = Arc::new(key::ThreadSpecific::new());
+        let their_specific = specific.clone();
 
         let main = move || {
             let arg = arg_wrapper;
             let ret = start_routine(arg.0);
             unsafe { *their_packet.result.get() = ret };
             drop(their_packet);
+            their_specific.run_destructors();
         };
 
+        #[cfg(feature = "signal")]
+        let task_inner = crate::imp::signal::spawn_thread(main);
+        #[cfg(not(feature = "signal"))]
         let task_inner = axtask::spawn(main);
         let tid = task_inner.id().as_u64();
         let thread = Pthread {
             inner: task_inner,
             retval: my_packet,
+            specific,
         };
         let ptr = Box::into_raw(Box::new(thread)) as *mut c_void;
         TID_TO_PTHREAD.write().insert(tid, ForceSendSync(ptr));
@@ -85,6 +102,7 @@ impl Pthread {
     fn exit_current(retval: *mut c_void) -> ! {
         let thread = Self::current().expect("fail to get current thread");
         unsafe { *thread.retval.result.get() = retval };
+        thread.specific.run_destructors();
         axtask::exit(0);
     }
 
@@ -103,6 +121,17 @@ impl Pthread {
     }
 }
 
+/// Returns the task of the thread `ptr`, or [`None`] if it is not a thread
+/// created by `pthread_create` (or the main thread) or has been joined.
+#[cfg(feature = "signal")]
+pub(crate) fn thread_task(ptr: ctypes::pthread_t) -> Option<AxTaskRef> {
+    TID_TO_PTHREAD
+        .read()
+        .values()
+        .find(|thread| core::ptr::eq(thread.0, ptr))
+        .map(|thread| unsafe { &*(thread.0 as *const Pthread) }.inner.clone())
+}
+
 /// Returns the `pthread` struct of current thread.
 pub fn sys_pthread_self() -> ctypes::pthread_t {
     Pthread::current().expect("fail to get current thread") as *const Pthread as _
@@ -152,3 +181,440 @@ struct ForceSendSync<T>(T);
 
 unsafe impl<T> Send for ForceSendSync<T> {}
 unsafe impl<T> Sync for ForceSendSync<T> {}
+
+/// Returns the state of a synchronization object in C memory, whose first
+/// word points to the state allocated by its `init` function.
+///
+/// Returns [`LinuxError::EINVAL`] if the object is not initialized.
+unsafe fn state<'a, T>(obj: *mut c_void) -> LinuxResult<&'a T> {
+    crate::utils::check_null_mut_ptr(obj)?;
+    let ptr = unsafe { AtomicPtr::<T>::from_ptr(obj.cast()) }.load(Ordering::Acquire);
+    unsafe { ptr.as_ref() }.ok_or(LinuxError::EINVAL)
+}
+
+/// Like [`state`], but allocates the default state on first use, so that the
+/// all-zero static initializers are valid.
+unsafe fn state_or_default<'a, T: Default>(obj: *mut c_void) -> LinuxResult<&'a T> {
+    crate::utils::check_null_mut_ptr(obj)?;
+    let state = unsafe { AtomicPtr::<T>::from_ptr(obj.cast()) };
+    let ptr = state.load(Ordering::Acquire);
+    if !ptr.is_null() {
+        return Ok(unsafe { &*ptr });
+    }
+    let new = Box::into_raw(Box::<T>::default());
+    match state.compare_exchange(
+        core::ptr::null_mut(),
+        new,
+        Ordering::AcqRel,
+        Ordering::Acquire,
+    ) {
+        Ok(_) => Ok(unsafe { &*new }),
+        Err(ptr) => {
+            // initialized by another thread at the same time
+            drop(unsafe { Box::from_raw(new) });
+            Ok(unsafe { &*ptr })
+        }
+    }
+}
+
+/// Stores the state of a synchronization object in its first word, dropping
+/// the old one if it is initialized again.
+unsafe fn init_state<T>(obj: *mut c_void, value: T) -> LinuxResult {
+    crate::utils::check_null_mut_ptr(obj)?;
+    let state = unsafe { AtomicPtr::<T>::from_ptr(obj.cast()) };
+    let old = state.swap(Box::into_raw(Box::new(value)), Ordering::AcqRel);
+    if !old.is_null() {
+        drop(unsafe { Box::from_raw(old) });
+    }
+    Ok(())
+}
+
+/// Drops the state of a synchronization object, which can be initialized
+/// again afterwards.
+unsafe fn destroy_state<T>(obj: *mut c_void) -> LinuxResult {
+    crate::utils::check_null_mut_ptr(obj)?;
+    let state = unsafe { AtomicPtr::<T>::from_ptr(obj.cast()) };
+    let old = state.swap(core::ptr::null_mut(), Ordering::AcqRel);
+    if !old.is_null() {
+        drop(unsafe { Box::from_raw(old) });
+    }
+    Ok(())
+}
+
+/// Converts the absolute time `abstime` of `clock` to a deadline of
+/// [`axhal::time::monotonic_time`].
+#[cfg(feature = "irq")]
+fn deadline(abstime: *const ctypes::timespec, clock: u32) -> LinuxResult<Duration> {
+    crate::utils::check_null_ptr(abstime)?;
+    let abstime = unsafe { *abstime };
+    if !(0..1_000_000_000).contains(&abstime.tv_nsec) || abstime.tv_sec < 0 {
+        return Err(LinuxError::EINVAL);
+    }
+    let now = match clock {
+        ctypes::CLOCK_REALTIME => axhal::time::wall_time(),
+        ctypes::CLOCK_MONOTONIC => axhal::time::monotonic_time(),
+        _ => return Err(LinuxError::EINVAL),
+    };
+    let remaining = Duration::from(abstime).saturating_sub(now);
+    Ok(axhal::time::monotonic_time() + remaining)
+}
+
+/// Blocks the current thread on `wq` until `condition` becomes true, or until
+/// `deadline` of [`axhal::time::monotonic_time`] if any.
+///
+/// Returns [`LinuxError::ETIMEDOUT`] if the deadline passes first. Timed
+/// waits need the `irq` feature, as nothing wakes the thread up at the
+/// deadline without timer interrupts, so the functions doing them are only
+/// available with it.
+fn wait_until<F>(wq: &WaitQueue, deadline: Option<Duration>, condition: F) -> LinuxResult
+where
+    F: Fn() -> bool,
+{
+    let Some(deadline) = deadline else {
+        wq.wait_until(condition);
+        return Ok(());
+    };
+    #[cfg(not(feature = "irq"))]
+    let _ = deadline;
+    #[cfg(feature = "irq")]
+    {
+        let now = axhal::time::monotonic_time();
+        if now < deadline {
+            wq.wait_timeout_until(deadline - now, &condition);
+        }
+    }
+    // it may be notified right at the deadline
+    if condition() {
+        Ok(())
+    } else {
+        Err(LinuxError::ETIMEDOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use core::ptr::{self, addr_of_mut};
+    use core::sync::atomic::AtomicUsize;
+    use std::sync::{Mutex, MutexGuard, Once};
+
+    use super::barrier::*;
+    use super::cond::*;
+    use super::key::*;
+    use super::mutex::*;
+    use super::once::*;
+    use super::rwlock::*;
+    use super::sem::*;
+    use super::*;
+
+    static INIT: Once = Once::new();
+    /// The threads of all tests run on one scheduler, so the tests run one at
+    /// a time.
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    fn init() -> MutexGuard<'static, ()> {
+        let guard = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        INIT.call_once(axtask::init_scheduler);
+        guard
+    }
+
+    fn err(e: LinuxError) -> c_int {
+        -e.code()
+    }
+
+    /// Allocates a zeroed `T`, like the C objects in static storage.
+    fn new_zeroed<T>() -> *mut T {
+        Box::into_raw(Box::new(unsafe { core::mem::zeroed() }))
+    }
+
+    /// Runs `f(arg)` in `n` new threads, and returns their return values.
+    fn run_threads<T>(
+        n: usize,
+        f: extern "C" fn(*mut c_void) -> *mut c_void,
+        arg: *mut T,
+    ) -> Vec<usize> {
+        let threads: Vec<_> = (0..n)
+            .map(|_| {
+                let mut thread = ptr::null_mut();
+                let res = unsafe { sys_pthread_create(&mut thread, ptr::null(), f, arg.cast()) };
+                assert_eq!(res, 0);
+                thread
+            })
+            .collect();
+        threads
+            .into_iter()
+            .map(|thread| {
+                let mut retval = ptr::null_mut();
+                assert_eq!(unsafe { sys_pthread_join(thread, &mut retval) }, 0);
+                retval as usize
+            })
+            .collect()
+    }
+
+    struct CondTest {
+        mutex: ctypes::pthread_mutex_t,
+        cond: ctypes::pthread_cond_t,
+        /// The number of waiters that may go on.
+        ready: AtomicUsize,
+        woken: AtomicUsize,
+    }
+
+    extern "C" fn cond_waiter(arg: *mut c_void) -> *mut c_void {
+        let test = arg.cast::<CondTest>();
+        let (mutex, cond) = unsafe { (addr_of_mut!((*test).mutex), addr_of_mut!((*test).cond)) };
+        let (ready, woken) = unsafe { (&(*test).ready, &(*test).woken) };
+        assert_eq!(sys_pthread_mutex_lock(mutex), 0);
+        while ready.load(Ordering::Acquire) == 0 {
+            assert_eq!(sys_pthread_cond_wait(cond, mutex), 0);
+        }
+        ready.fetch_sub(1, Ordering::AcqRel);
+        woken.fetch_add(1, Ordering::AcqRel);
+        assert_eq!(sys_pthread_mutex_unlock(mutex), 0);
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_cond() {
+        let _guard = init();
+        let test = new_zeroed::<CondTest>();
+        let (mutex, cond) = unsafe { (addr_of_mut!((*test).mutex), addr_of_mut!((*test).cond)) };
+        let (ready, woken) = unsafe { (&(*test).ready, &(*test).woken) };
+        assert_eq!(sys_pthread_mutex_init(mutex, ptr::null()), 0);
+        // nobody waits yet, and the statically initialized one works
+        assert_eq!(sys_pthread_cond_signal(cond), 0);
+
+        let (mutex_addr, cond_addr) = (mutex as usize, cond as usize);
+        let signaler = axtask::spawn(move || {
+            let (mutex, cond) = (mutex_addr as *mut _, cond_addr as *mut _);
+            // let the waiters wait
+            axtask::yield_now();
+            // one of the waiters, then the others
+            assert_eq!(sys_pthread_mutex_lock(mutex), 0);
+            ready.store(1, Ordering::Release);
+            assert_eq!(sys_pthread_cond_signal(cond), 0);
+            assert_eq!(sys_pthread_mutex_unlock(mutex), 0);
+            while woken.load(Ordering::Acquire) < 1 {
+                axtask::yield_now();
+            }
+            axtask::yield_now();
+            assert_eq!(woken.load(Ordering::Acquire), 1);
+            assert_eq!(sys_pthread_mutex_lock(mutex), 0);
+            ready.store(2, Ordering::Release);
+            assert_eq!(sys_pthread_cond_broadcast(cond), 0);
+            assert_eq!(sys_pthread_mutex_unlock(mutex), 0);
+        });
+        run_threads(3, cond_waiter, test);
+        signaler.join();
+        assert_eq!(woken.load(Ordering::Acquire), 3);
+        assert_eq!(sys_pthread_cond_destroy(cond), 0);
+        assert_eq!(
+            sys_pthread_cond_wait(cond, ptr::null_mut()),
+            err(LinuxError::EFAULT)
+        );
+        drop(unsafe { Box::from_raw(test) });
+    }
+
+    struct RwLockTest {
+        rwlock: ctypes::pthread_rwlock_t,
+        written: AtomicUsize,
+    }
+
+    extern "C" fn rwlock_writer(arg: *mut c_void) -> *mut c_void {
+        let test = arg.cast::<RwLockTest>();
+        let rwlock = unsafe { addr_of_mut!((*test).rwlock) };
+        assert_eq!(sys_pthread_rwlock_wrlock(rwlock), 0);
+        unsafe { &(*test).written }.fetch_add(1, Ordering::AcqRel);
+        assert_eq!(sys_pthread_rwlock_unlock(rwlock), 0);
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_rwlock() {
+        let _guard = init();
+        let test = new_zeroed::<RwLockTest>();
+        let rwlock = unsafe { addr_of_mut!((*test).rwlock) };
+        let written = unsafe { &(*test).written };
+
+        // readers share the lock, and writers do not
+        assert_eq!(sys_pthread_rwlock_rdlock(rwlock), 0);
+        assert_eq!(sys_pthread_rwlock_tryrdlock(rwlock), 0);
+        assert_eq!(sys_pthread_rwlock_trywrlock(rwlock), err(LinuxError::EBUSY));
+        assert_eq!(sys_pthread_rwlock_unlock(rwlock), 0);
+        assert_eq!(sys_pthread_rwlock_unlock(rwlock), 0);
+        assert_eq!(sys_pthread_rwlock_unlock(rwlock), err(LinuxError::EPERM));
+        assert_eq!(sys_pthread_rwlock_wrlock(rwlock), 0);
+        assert_eq!(sys_pthread_rwlock_tryrdlock(rwlock), err(LinuxError::EBUSY));
+        assert_eq!(sys_pthread_rwlock_trywrlock(rwlock), err(LinuxError::EBUSY));
+        assert_eq!(sys_pthread_rwlock_unlock(rwlock), 0);
+
+        // a waiting writer keeps new readers out
+        assert_eq!(sys_pthread_rwlock_rdlock(rwlock), 0);
+        let mut writer = ptr::null_mut();
+        let res =
+            unsafe { sys_pthread_create(&mut writer, ptr::null(), rwlock_writer, test.cast()) };
+        assert_eq!(res, 0);
+        axtask::yield_now();
+        assert_eq!(sys_pthread_rwlock_tryrdlock(rwlock), err(LinuxError::EBUSY));
+        assert_eq!(written.load(Ordering::Acquire), 0);
+        assert_eq!(sys_pthread_rwlock_unlock(rwlock), 0);
+        // after the writer
+        assert_eq!(sys_pthread_rwlock_rdlock(rwlock), 0);
+        assert_eq!(written.load(Ordering::Acquire), 1);
+        assert_eq!(sys_pthread_rwlock_unlock(rwlock), 0);
+        assert_eq!(unsafe { sys_pthread_join(writer, ptr::null_mut()) }, 0);
+        assert_eq!(sys_pthread_rwlock_destroy(rwlock), 0);
+        drop(unsafe { Box::from_raw(test) });
+    }
+
+    struct BarrierTest {
+        barrier: ctypes::pthread_barrier_t,
+        arrived: AtomicUsize,
+    }
+
+    extern "C" fn barrier_waiter(arg: *mut c_void) -> *mut c_void {
+        let test = arg.cast::<BarrierTest>();
+        let arrived = unsafe { &(*test).arrived };
+        arrived.fetch_add(1, Ordering::AcqRel);
+        let ret = sys_pthread_barrier_wait(unsafe { addr_of_mut!((*test).barrier) });
+        // all of them have arrived
+        assert_eq!(arrived.load(Ordering::Acquire) % 4, 0);
+        ret as isize as *mut c_void
+    }
+
+    #[test]
+    fn test_barrier() {
+        let _guard = init();
+        let test = new_zeroed::<BarrierTest>();
+        let barrier = unsafe { addr_of_mut!((*test).barrier) };
+        assert_eq!(sys_pthread_barrier_wait(barrier), err(LinuxError::EINVAL));
+        assert_eq!(
+            sys_pthread_barrier_init(barrier, ptr::null(), 0),
+            err(LinuxError::EINVAL)
+        );
+        assert_eq!(sys_pthread_barrier_init(barrier, ptr::null(), 4), 0);
+        // twice, as it is reused
+        for _ in 0..2 {
+            let rets = run_threads(4, barrier_waiter, test);
+            let serial = ctypes::PTHREAD_BARRIER_SERIAL_THREAD as isize as usize;
+            assert_eq!(rets.iter().filter(|&&ret| ret == serial).count(), 1);
+            assert_eq!(rets.iter().filter(|&&ret| ret == 0).count(), 3);
+        }
+        assert_eq!(sys_pthread_barrier_destroy(barrier), 0);
+        drop(unsafe { Box::from_raw(test) });
+    }
+
+    static ONCE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    extern "C" fn once_routine() {
+        ONCE_CALLS.fetch_add(1, Ordering::AcqRel);
+        // the other threads wait meanwhile
+        axtask::yield_now();
+    }
+
+    extern "C" fn once_caller(arg: *mut c_void) -> *mut c_void {
+        let once_control = arg.cast::<ctypes::pthread_once_t>();
+        assert_eq!(unsafe { sys_pthread_once(once_control, once_routine) }, 0);
+        assert_eq!(ONCE_CALLS.load(Ordering::Acquire), 1);
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_once() {
+        let _guard = init();
+        let once_control = new_zeroed::<ctypes::pthread_once_t>();
+        run_threads(4, once_caller, once_control);
+        once_caller(once_control.cast());
+        assert_eq!(ONCE_CALLS.load(Ordering::Acquire), 1);
+        drop(unsafe { Box::from_raw(once_control) });
+    }
+
+    static DESTRUCTED: AtomicUsize = AtomicUsize::new(0);
+
+    unsafe extern "C" fn key_destructor(value: *mut c_void) {
+        DESTRUCTED.fetch_add(value as usize, Ordering::AcqRel);
+    }
+
+    extern "C" fn key_user(arg: *mut c_void) -> *mut c_void {
+        let key = unsafe { *arg.cast::<ctypes::pthread_key_t>() };
+        // null in a new thread
+        assert!(sys_pthread_getspecific(key).is_null());
+        assert_eq!(sys_pthread_setspecific(key, 10 as *const c_void), 0);
+        axtask::yield_now();
+        sys_pthread_getspecific(key)
+    }
+
+    #[test]
+    fn test_key() {
+        let _guard = init();
+        let mut key = 0;
+        let res = unsafe { sys_pthread_key_create(&mut key, Some(key_destructor)) };
+        assert_eq!(res, 0);
+        assert_eq!(sys_pthread_setspecific(key, 1 as *const c_void), 0);
+
+        // each thread has its own value, which is destructed as it exits
+        let rets = run_threads(2, key_user, &mut key);
+        assert_eq!(rets, [10, 10]);
+        assert_eq!(DESTRUCTED.load(Ordering::Acquire), 20);
+        assert_eq!(sys_pthread_getspecific(key) as usize, 1);
+
+        // tasks that are not threads have no values
+        axtask::spawn(move || {
+            assert!(sys_pthread_getspecific(key).is_null());
+            assert_eq!(
+                sys_pthread_setspecific(key, 2 as *const c_void),
+                err(LinuxError::EINVAL)
+            );
+        })
+        .join();
+
+        // the values of deleted keys are gone, without destructing them
+        assert_eq!(sys_pthread_key_delete(key), 0);
+        assert!(sys_pthread_getspecific(key).is_null());
+        assert_eq!(
+            sys_pthread_setspecific(key, 1 as *const c_void),
+            err(LinuxError::EINVAL)
+        );
+        assert_eq!(sys_pthread_key_delete(key), err(LinuxError::EINVAL));
+        assert_eq!(DESTRUCTED.load(Ordering::Acquire), 20);
+    }
+
+    extern "C" fn sem_poster(arg: *mut c_void) -> *mut c_void {
+        for _ in 0..3 {
+            assert_eq!(sys_sem_post(arg.cast()), 0);
+            axtask::yield_now();
+        }
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_sem() {
+        let _guard = init();
+        let sem = new_zeroed::<ctypes::sem_t>();
+        assert_eq!(sys_sem_wait(sem), err(LinuxError::EINVAL));
+        assert_eq!(
+            sys_sem_init(sem, 0, ctypes::SEM_VALUE_MAX + 1),
+            err(LinuxError::EINVAL)
+        );
+        assert_eq!(sys_sem_init(sem, 0, 0), 0);
+        assert_eq!(sys_sem_trywait(sem), err(LinuxError::EAGAIN));
+
+        // woken up by the posts of another thread
+        let sem_addr = sem as usize;
+        let waiter = axtask::spawn(move || {
+            for _ in 0..3 {
+                assert_eq!(sys_sem_wait(sem_addr as *mut _), 0);
+            }
+        });
+        run_threads(1, sem_poster, sem);
+        waiter.join();
+        let mut value = -1;
+        assert_eq!(unsafe { sys_sem_getvalue(sem, &mut value) }, 0);
+        assert_eq!(value, 0);
+
+        // the value is bounded
+        assert_eq!(sys_sem_init(sem, 0, ctypes::SEM_VALUE_MAX), 0);
+        assert_eq!(sys_sem_post(sem), err(LinuxError::EOVERFLOW));
+        assert_eq!(sys_sem_destroy(sem), 0);
+        drop(unsafe { Box::from_raw(sem) });
+    }
+}
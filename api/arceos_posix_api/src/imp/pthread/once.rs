@@ -0,0 +1,39 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axtask::WaitQueue;
+
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicI32, Ordering};
+
+const INCOMPLETE: i32 = 0;
+const RUNNING: i32 = 1;
+const COMPLETE: i32 = 2;
+
+/// The threads waiting for an `init_routine` run by another thread.
+static ONCE_WQ: WaitQueue = WaitQueue::new();
+
+/// Call `init_routine` only once for `once_control`, which is initialized by
+/// `PTHREAD_ONCE_INIT`.
+///
+/// The other threads calling it at the same time wait until `init_routine`
+/// returns.
+pub unsafe fn sys_pthread_once(
+    once_control: *mut ctypes::pthread_once_t,
+    init_routine: extern "C" fn(),
+) -> c_int {
+    debug!("sys_pthread_once <= {:#x}", once_control as usize);
+    syscall_body!(sys_pthread_once, {
+        check_null_mut_ptr(once_control)?;
+        let state = unsafe { AtomicI32::from_ptr(once_control) };
+        match state.compare_exchange(INCOMPLETE, RUNNING, Ordering::Acquire, Ordering::Acquire) {
+            Ok(_) => {
+                init_routine();
+                state.store(COMPLETE, Ordering::Release);
+                ONCE_WQ.notify_all(false);
+            }
+            Err(RUNNING) => ONCE_WQ.wait_until(|| state.load(Ordering::Acquire) == COMPLETE),
+            Err(_) => {}
+        }
+        Ok(0)
+    })
+}
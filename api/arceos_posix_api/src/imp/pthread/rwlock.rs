@@ -0,0 +1,204 @@
+use crate::ctypes;
+
+use axerrno::{LinuxError, LinuxResult};
+use axtask::WaitQueue;
+
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+
+/// The lock count of a write-locked [`RwLock`].
+const WRITER: usize = usize::MAX;
+
+/// The state of a `pthread_rwlock_t`, which is the number of readers or
+/// [`WRITER`].
+///
+/// Writers are preferred: no more readers take the lock while a writer is
+/// waiting for it, so that a stream of readers cannot starve the writers.
+/// As in glibc with `PTHREAD_RWLOCK_PREFER_WRITER_NONRECURSIVE_NP`, a thread
+/// that takes a read lock again then deadlocks with the waiting writer.
+struct RwLock {
+    state: AtomicUsize,
+    /// The number of threads waiting to write.
+    writers: AtomicUsize,
+    wq: WaitQueue,
+}
+
+impl Default for RwLock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RwLock {
+    const fn new() -> Self {
+        Self {
+            state: AtomicUsize::new(0),
+            writers: AtomicUsize::new(0),
+            wq: WaitQueue::new(),
+        }
+    }
+
+    /// Returns whether the lock can be taken for writing if `write`, or for
+    /// reading otherwise, when it is in `state`.
+    fn can_lock(&self, state: usize, write: bool) -> bool {
+        if write {
+            state == 0
+        } else {
+            state < WRITER - 1 && self.writers.load(Ordering::Acquire) == 0
+        }
+    }
+
+    fn try_lock(&self, write: bool) -> bool {
+        self.state
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |state| {
+                self.can_lock(state, write)
+                    .then(|| if write { WRITER } else { state + 1 })
+            })
+            .is_ok()
+    }
+
+    /// Takes the lock for writing if `write`, or for reading otherwise,
+    /// blocking until `deadline` of [`axhal::time::monotonic_time`] if any.
+    fn lock(&self, write: bool, deadline: Option<Duration>) -> LinuxResult {
+        if self.try_lock(write) {
+            return Ok(());
+        }
+        if write {
+            self.writers.fetch_add(1, Ordering::AcqRel);
+        }
+        let mut res = Ok(());
+        while res.is_ok() && !self.try_lock(write) {
+            res = super::wait_until(&self.wq, deadline, || {
+                self.can_lock(self.state.load(Ordering::Acquire), write)
+            });
+        }
+        if write && self.writers.fetch_sub(1, Ordering::AcqRel) == 1 && res.is_err() {
+            // the readers blocked by this writer can go on
+            self.wq.notify_all(false);
+        }
+        res
+    }
+
+    fn unlock(&self) -> LinuxResult {
+        let state = self
+            .state
+            .fetch_update(Ordering::Release, Ordering::Relaxed, |state| match state {
+                0 => None,
+                WRITER => Some(0),
+                readers => Some(readers - 1),
+            })
+            .map_err(|_| LinuxError::EPERM)?;
+        if state == WRITER || state == 1 {
+            self.wq.notify_all(false);
+        }
+        Ok(())
+    }
+}
+
+fn rwlock(rwlock: *mut ctypes::pthread_rwlock_t) -> LinuxResult<&'static RwLock> {
+    unsafe { super::state_or_default(rwlock.cast()) }
+}
+
+/// Initialize a read-write lock.
+pub fn sys_pthread_rwlock_init(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    _attr: *const ctypes::pthread_rwlockattr_t,
+) -> c_int {
+    debug!("sys_pthread_rwlock_init <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_init, {
+        unsafe { super::init_state(rwlock.cast(), RwLock::new())? };
+        Ok(0)
+    })
+}
+
+/// Destroy a read-write lock.
+pub fn sys_pthread_rwlock_destroy(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_destroy <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_destroy, {
+        unsafe { super::destroy_state::<RwLock>(rwlock.cast())? };
+        Ok(0)
+    })
+}
+
+/// Lock the given read-write lock for reading.
+pub fn sys_pthread_rwlock_rdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_rdlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_rdlock, {
+        self::rwlock(rwlock)?.lock(false, None)?;
+        Ok(0)
+    })
+}
+
+/// Lock the given read-write lock for reading, or fail with `EBUSY` if it is
+/// locked for writing, or a writer is waiting for it.
+pub fn sys_pthread_rwlock_tryrdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_tryrdlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_tryrdlock, {
+        if !self::rwlock(rwlock)?.try_lock(false) {
+            return Err(LinuxError::EBUSY);
+        }
+        Ok(0)
+    })
+}
+
+/// Lock the given read-write lock for reading, or fail with `ETIMEDOUT` at
+/// the absolute time `abstime` of `CLOCK_REALTIME`.
+#[cfg(feature = "irq")]
+pub fn sys_pthread_rwlock_timedrdlock(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    debug!("sys_pthread_rwlock_timedrdlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_timedrdlock, {
+        let deadline = super::deadline(abstime, ctypes::CLOCK_REALTIME)?;
+        self::rwlock(rwlock)?.lock(false, Some(deadline))?;
+        Ok(0)
+    })
+}
+
+/// Lock the given read-write lock for writing.
+pub fn sys_pthread_rwlock_wrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_wrlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_wrlock, {
+        self::rwlock(rwlock)?.lock(true, None)?;
+        Ok(0)
+    })
+}
+
+/// Lock the given read-write lock for writing, or fail with `EBUSY` if it is
+/// locked.
+pub fn sys_pthread_rwlock_trywrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_trywrlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_trywrlock, {
+        if !self::rwlock(rwlock)?.try_lock(true) {
+            return Err(LinuxError::EBUSY);
+        }
+        Ok(0)
+    })
+}
+
+/// Lock the given read-write lock for writing, or fail with `ETIMEDOUT` at
+/// the absolute time `abstime` of `CLOCK_REALTIME`.
+#[cfg(feature = "irq")]
+pub fn sys_pthread_rwlock_timedwrlock(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    debug!("sys_pthread_rwlock_timedwrlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_timedwrlock, {
+        let deadline = super::deadline(abstime, ctypes::CLOCK_REALTIME)?;
+        self::rwlock(rwlock)?.lock(true, Some(deadline))?;
+        Ok(0)
+    })
+}
+
+/// Unlock the given read-write lock, which is locked for either reading or
+/// writing.
+pub fn sys_pthread_rwlock_unlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    debug!("sys_pthread_rwlock_unlock <= {:#x}", rwlock as usize);
+    syscall_body!(sys_pthread_rwlock_unlock, {
+        self::rwlock(rwlock)?.unlock()?;
+        Ok(0)
+    })
+}
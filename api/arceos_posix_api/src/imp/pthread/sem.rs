@@ -0,0 +1,128 @@
+use crate::{ctypes, utils::check_null_mut_ptr};
+
+use axerrno::{LinuxError, LinuxResult};
+use axtask::WaitQueue;
+
+use core::ffi::{c_int, c_uint};
+use core::sync::atomic::{AtomicU32, Ordering};
+use core::time::Duration;
+
+/// The state of an unnamed `sem_t`.
+struct Semaphore {
+    value: AtomicU32,
+    wq: WaitQueue,
+}
+
+impl Semaphore {
+    fn try_wait(&self) -> bool {
+        self.value
+            .fetch_update(Ordering::Acquire, Ordering::Relaxed, |value| {
+                value.checked_sub(1)
+            })
+            .is_ok()
+    }
+
+    /// Decrements the value, blocking until `deadline` of
+    /// [`axhal::time::monotonic_time`] if any.
+    fn wait(&self, deadline: Option<Duration>) -> LinuxResult {
+        while !self.try_wait() {
+            super::wait_until(&self.wq, deadline, || {
+                self.value.load(Ordering::Acquire) > 0
+            })?;
+        }
+        Ok(())
+    }
+
+    fn post(&self) -> LinuxResult {
+        self.value
+            .fetch_update(Ordering::Release, Ordering::Relaxed, |value| {
+                (value < ctypes::SEM_VALUE_MAX).then_some(value + 1)
+            })
+            .map_err(|_| LinuxError::EOVERFLOW)?;
+        self.wq.notify_one(false);
+        Ok(())
+    }
+}
+
+fn sem(sem: *mut ctypes::sem_t) -> LinuxResult<&'static Semaphore> {
+    unsafe { super::state(sem.cast()) }
+}
+
+/// Initialize an unnamed semaphore with `value`.
+///
+/// As there is only one process, `pshared` makes no difference.
+pub fn sys_sem_init(sem: *mut ctypes::sem_t, _pshared: c_int, value: c_uint) -> c_int {
+    debug!("sys_sem_init <= {:#x}, {}", sem as usize, value);
+    syscall_body!(sys_sem_init, {
+        if value > ctypes::SEM_VALUE_MAX {
+            return Err(LinuxError::EINVAL);
+        }
+        let state = Semaphore {
+            value: AtomicU32::new(value),
+            wq: WaitQueue::new(),
+        };
+        unsafe { super::init_state(sem.cast(), state)? };
+        Ok(0)
+    })
+}
+
+/// Destroy an unnamed semaphore.
+pub fn sys_sem_destroy(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("sys_sem_destroy <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_destroy, {
+        unsafe { super::destroy_state::<Semaphore>(sem.cast())? };
+        Ok(0)
+    })
+}
+
+/// Decrement the semaphore, waiting until its value is positive.
+pub fn sys_sem_wait(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("sys_sem_wait <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_wait, {
+        self::sem(sem)?.wait(None)?;
+        Ok(0)
+    })
+}
+
+/// Decrement the semaphore, or fail with `EAGAIN` if its value is 0.
+pub fn sys_sem_trywait(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("sys_sem_trywait <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_trywait, {
+        if !self::sem(sem)?.try_wait() {
+            return Err(LinuxError::EAGAIN);
+        }
+        Ok(0)
+    })
+}
+
+/// Decrement the semaphore, or fail with `ETIMEDOUT` at the absolute time
+/// `abstime` of `CLOCK_REALTIME`.
+#[cfg(feature = "irq")]
+pub fn sys_sem_timedwait(sem: *mut ctypes::sem_t, abstime: *const ctypes::timespec) -> c_int {
+    debug!("sys_sem_timedwait <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_timedwait, {
+        let sem = self::sem(sem)?;
+        sem.wait(Some(super::deadline(abstime, ctypes::CLOCK_REALTIME)?))?;
+        Ok(0)
+    })
+}
+
+/// Increment the semaphore, waking up a thread waiting on it.
+pub fn sys_sem_post(sem: *mut ctypes::sem_t) -> c_int {
+    debug!("sys_sem_post <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_post, {
+        self::sem(sem)?.post()?;
+        Ok(0)
+    })
+}
+
+/// Get the value of the semaphore.
+pub unsafe fn sys_sem_getvalue(sem: *mut ctypes::sem_t, sval: *mut c_int) -> c_int {
+    debug!("sys_sem_getvalue <= {:#x}", sem as usize);
+    syscall_body!(sys_sem_getvalue, {
+        check_null_mut_ptr(sval)?;
+        let value = self::sem(sem)?.value.load(Ordering::Acquire);
+        unsafe { sval.write(value as c_int) };
+        Ok(0)
+    })
+}
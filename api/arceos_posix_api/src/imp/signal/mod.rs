@@ -0,0 +1,737 @@
+//! Signals.
+//!
+//! The signal mask and the pending signals are per-thread, and kept in a
+//! table by the task IDs. The state of a thread is created when it first uses
+//! signals, e.g., on its first POSIX call, so that every task has one, whether
+//! it is created by `pthread_create` or not. The signal actions are shared by
+//! all threads.
+//!
+//! As the application runs in the same address space and privilege level as
+//! the kernel, a handler cannot interrupt a thread at an arbitrary point, which
+//! may hold kernel locks, nor run in the trap path. Instead, the pending
+//! signals of a thread are handled by the thread itself at safe points:
+//!
+//! - when it returns from the outermost POSIX call (see `syscall_body!`);
+//! - when it is woken up in a blocking call, which then fails with `EINTR`,
+//!   unless the handler is installed with `SA_RESTART`.
+//!
+//! So a thread that makes no POSIX calls, such as a busy loop, only handles
+//! its signals once it makes one.
+//!
+//! TODO: stopping and continuing threads (`SIGSTOP`, `SIGCONT`) is not
+//! supported, their default actions are ignoring the signals.
+
+use alloc::{collections::BTreeMap, sync::Arc, vec::Vec};
+use core::ffi::{c_int, c_void};
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::task::Waker;
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::time::{TimeValue, monotonic_time};
+use axsync::spin::SpinNoIrq;
+use axtask::{AxTaskRef, TaskState, WaitQueue, WeakAxTaskRef};
+
+use crate::ctypes;
+
+pub mod timer;
+
+/// The number of supported signals, numbered from 1.
+const NSIG: usize = 64;
+
+const SIG_DFL: usize = 0;
+const SIG_IGN: usize = 1;
+
+/// The signals that can be neither caught, ignored nor blocked.
+const UNBLOCKABLE: u64 = sig_bit(ctypes::SIGKILL) | sig_bit(ctypes::SIGSTOP);
+
+const fn sig_bit(sig: u32) -> u64 {
+    1 << (sig - 1)
+}
+
+/// Checks the signal number, where 0 is only valid if `allow_zero` is set.
+fn check_signo(sig: c_int, allow_zero: bool) -> LinuxResult<u32> {
+    match sig {
+        0 if allow_zero => Ok(0),
+        1..=64 => Ok(sig as u32),
+        _ => Err(LinuxError::EINVAL),
+    }
+}
+
+fn mask_from_sigset(set: &ctypes::sigset_t) -> u64 {
+    let bits = core::ffi::c_ulong::BITS as usize;
+    let mut mask = 0;
+    for (i, word) in set.__bits.iter().take(64 / bits).enumerate() {
+        mask |= (*word as u64) << (i * bits);
+    }
+    mask
+}
+
+fn mask_to_sigset(mask: u64) -> ctypes::sigset_t {
+    let bits = core::ffi::c_ulong::BITS as usize;
+    let mut set = ctypes::sigset_t::default();
+    for (i, word) in set.__bits.iter_mut().take(64 / bits).enumerate() {
+        *word = (mask >> (i * bits)) as _;
+    }
+    set
+}
+
+#[derive(Clone, Copy)]
+struct SigAction {
+    /// The address of the handler, or [`SIG_DFL`] or [`SIG_IGN`].
+    handler: usize,
+    mask: u64,
+    flags: u32,
+}
+
+impl SigAction {
+    const DEFAULT: Self = Self {
+        handler: SIG_DFL,
+        mask: 0,
+        flags: 0,
+    };
+
+    const fn is_handler(&self) -> bool {
+        self.handler != SIG_DFL && self.handler != SIG_IGN
+    }
+
+    /// Whether the signal is discarded when it is delivered.
+    fn ignores(&self, sig: u32) -> bool {
+        match self.handler {
+            SIG_IGN => true,
+            SIG_DFL => matches!(
+                sig,
+                ctypes::SIGCHLD
+                    | ctypes::SIGCONT
+                    | ctypes::SIGURG
+                    | ctypes::SIGWINCH
+                    | ctypes::SIGSTOP
+                    | ctypes::SIGTSTP
+                    | ctypes::SIGTTIN
+                    | ctypes::SIGTTOU
+            ),
+            _ => false,
+        }
+    }
+}
+
+static ACTIONS: SpinNoIrq<[SigAction; NSIG]> = SpinNoIrq::new([SigAction::DEFAULT; NSIG]);
+
+/// The information passed to `SA_SIGINFO` handlers.
+#[derive(Clone, Copy)]
+struct SigInfo {
+    code: i32,
+    /// The `sigval` of timers.
+    value: usize,
+}
+
+impl SigInfo {
+    const fn new(code: i32) -> Self {
+        Self { code, value: 0 }
+    }
+}
+
+/// A set of pending signals, and the information of each one.
+///
+/// Standard signals are not queued, a signal sent again before it is
+/// delivered only updates the information.
+struct Pending {
+    set: AtomicU64,
+    infos: SpinNoIrq<[SigInfo; NSIG]>,
+}
+
+impl Pending {
+    const fn new() -> Self {
+        Self {
+            set: AtomicU64::new(0),
+            infos: SpinNoIrq::new([SigInfo::new(0); NSIG]),
+        }
+    }
+
+    fn add(&self, sig: u32, info: SigInfo) {
+        let mut infos = self.infos.lock();
+        infos[sig as usize - 1] = info;
+        self.set.fetch_or(sig_bit(sig), Ordering::AcqRel);
+    }
+
+    /// Removes `sig` from the set, and returns its information if it was
+    /// pending.
+    fn take(&self, sig: u32) -> Option<SigInfo> {
+        let infos = self.infos.lock();
+        let bit = sig_bit(sig);
+        (self.set.fetch_and(!bit, Ordering::AcqRel) & bit != 0).then(|| infos[sig as usize - 1])
+    }
+}
+
+/// Signals sent to the process while all threads are blocking them, which
+/// are delivered to the first thread that unblocks them.
+static PROCESS_PENDING: Pending = Pending::new();
+
+/// The signal state of a thread.
+struct ThreadSignals {
+    blocked: AtomicU64,
+    pending: Pending,
+    /// The nesting depth of the POSIX calls being executed.
+    depth: AtomicUsize,
+    /// Woken up when a signal is sent to the thread.
+    wq: WaitQueue,
+    /// Woken once when a signal is sent to the thread, see
+    /// [`register_interrupt_waker`].
+    interrupt_waker: SpinNoIrq<Option<Waker>>,
+}
+
+impl ThreadSignals {
+    const fn new(blocked: u64) -> Self {
+        Self {
+            blocked: AtomicU64::new(blocked),
+            pending: Pending::new(),
+            depth: AtomicUsize::new(0),
+            wq: WaitQueue::new(),
+            interrupt_waker: SpinNoIrq::new(None),
+        }
+    }
+
+    /// Returns the first pending signal that is not blocked.
+    fn next_signal(&self) -> Option<u32> {
+        let set = (self.pending.set.load(Ordering::Acquire)
+            | PROCESS_PENDING.set.load(Ordering::Acquire))
+            & !self.blocked.load(Ordering::Acquire);
+        (set != 0).then(|| set.trailing_zeros() + 1)
+    }
+
+    fn send(&self, sig: u32, info: SigInfo) {
+        self.pending.add(sig, info);
+        self.wake();
+    }
+
+    /// Wakes up the thread if it is blocked in an interruptible call.
+    fn wake(&self) {
+        self.wq.notify_all(false);
+        let waker = self.interrupt_waker.lock().take();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+
+    /// Delivers `sig` to the thread, which must be the current one.
+    fn deliver(&self, sig: u32) {
+        let Some(info) = self.pending.take(sig).or_else(|| PROCESS_PENDING.take(sig)) else {
+            // a process-directed signal taken by another thread
+            return;
+        };
+        let action = {
+            let mut actions = ACTIONS.lock();
+            let action = actions[sig as usize - 1];
+            if action.is_handler() && action.flags & ctypes::SA_RESETHAND != 0 {
+                actions[sig as usize - 1] = SigAction::DEFAULT;
+            }
+            action
+        };
+        if action.ignores(sig) {
+            return;
+        }
+        if !action.is_handler() {
+            warn!("Terminated by signal {}", sig);
+            axhal::misc::terminate();
+        }
+
+        let mut mask = action.mask;
+        if action.flags & ctypes::SA_NODEFER == 0 {
+            mask |= sig_bit(sig);
+        }
+        let old_blocked = self.blocked.fetch_or(mask & !UNBLOCKABLE, Ordering::AcqRel);
+        if action.flags & ctypes::SA_SIGINFO != 0 {
+            let mut siginfo = ctypes::siginfo_t {
+                si_signo: sig as _,
+                si_code: info.code,
+                ..Default::default()
+            };
+            siginfo.__si_fields.__si_common.__second.si_value = ctypes::sigval {
+                sival_ptr: info.value as *mut c_void,
+            };
+            let handler: extern "C" fn(c_int, *mut ctypes::siginfo_t, *mut c_void) =
+                unsafe { core::mem::transmute(action.handler) };
+            handler(sig as _, &mut siginfo, core::ptr::null_mut());
+        } else {
+            let handler: extern "C" fn(c_int) = unsafe { core::mem::transmute(action.handler) };
+            handler(sig as _);
+        }
+        self.blocked.store(old_blocked, Ordering::Release);
+    }
+
+    /// Delivers all pending signals that are not blocked.
+    fn handle_signals(&self) {
+        while let Some(sig) = self.next_signal() {
+            self.deliver(sig);
+        }
+    }
+}
+
+/// The signal states of the threads by their task IDs, see [`signals_of`].
+static THREADS: SpinNoIrq<BTreeMap<u64, (WeakAxTaskRef, Arc<ThreadSignals>)>> =
+    SpinNoIrq::new(BTreeMap::new());
+
+/// Returns the signal state of `task`, which is created with the signals in
+/// `blocked` blocked if it has none yet.
+///
+/// The states of the dropped tasks are removed as new ones are created.
+fn signals_or_init(task: &AxTaskRef, blocked: u64) -> Arc<ThreadSignals> {
+    let id = task.id().as_u64();
+    let mut threads = THREADS.lock();
+    if let Some((_, s)) = threads.get(&id) {
+        return s.clone();
+    }
+    threads.retain(|_, (task, _)| task.strong_count() > 0);
+    let s = Arc::new(ThreadSignals::new(blocked));
+    threads.insert(id, (Arc::downgrade(task), s.clone()));
+    s
+}
+
+/// Returns the signal state of `task`, which is created with no signals
+/// blocked if it has none yet.
+fn signals_of(task: &AxTaskRef) -> Arc<ThreadSignals> {
+    signals_or_init(task, 0)
+}
+
+fn current_signals() -> Arc<ThreadSignals> {
+    signals_of(axtask::current().as_task_ref())
+}
+
+/// Returns the IDs and the signal states of the threads that have one and
+/// have not exited, in the order of their IDs.
+fn live_threads() -> Vec<(u64, Arc<ThreadSignals>)> {
+    let threads: Vec<_> = THREADS
+        .lock()
+        .iter()
+        .map(|(&id, (task, s))| (id, task.clone(), s.clone()))
+        .collect();
+    threads
+        .into_iter()
+        .filter(|(_, task, _)| {
+            task.upgrade()
+                .is_some_and(|task| task.state() != TaskState::Exited)
+        })
+        .map(|(id, _, s)| (id, s))
+        .collect()
+}
+
+/// Creates the signal state of the main thread, which can receive the
+/// signals sent to the process before it makes any POSIX call.
+#[ctor_bare::register_ctor]
+fn init_main_signals() {
+    if let Some(curr) = axtask::current_may_uninit() {
+        signals_of(curr.as_task_ref());
+    }
+}
+
+/// Spawns a thread with the given entry, which inherits the signal mask of
+/// the current thread.
+pub(crate) fn spawn_thread<F>(f: F) -> AxTaskRef
+where
+    F: FnOnce() + Send + 'static,
+{
+    let blocked = current_signals().blocked.load(Ordering::Acquire);
+    // whichever of the threads creates the state first, it has the mask
+    let task = axtask::spawn(move || {
+        signals_or_init(axtask::current().as_task_ref(), blocked);
+        f()
+    });
+    signals_or_init(&task, blocked);
+    task
+}
+
+/// Marks the current thread as executing a POSIX call until it is dropped,
+/// when the pending signals are delivered if the call is the outermost one.
+pub struct CallGuard(Arc<ThreadSignals>);
+
+impl CallGuard {
+    pub fn enter() -> Self {
+        let signals = current_signals();
+        signals.depth.fetch_add(1, Ordering::Relaxed);
+        Self(signals)
+    }
+}
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        if self.0.depth.fetch_sub(1, Ordering::Relaxed) == 1 {
+            self.0.handle_signals();
+        }
+    }
+}
+
+/// Checks whether a blocking call of the current thread is interrupted by a
+/// signal, which should be called each time the thread is woken up.
+///
+/// Ignored signals are discarded, and if `restart` is set, signals whose
+/// handlers are installed with `SA_RESTART` are delivered right away, so that
+/// the call can go on blocking. Otherwise, returns [`LinuxError::EINTR`], and
+/// the signal is delivered when the call returns.
+///
+/// The caller must not hold any locks.
+pub(crate) fn check_interrupted(restart: bool) -> LinuxResult {
+    let s = current_signals();
+    while let Some(sig) = s.next_signal() {
+        let action = ACTIONS.lock()[sig as usize - 1];
+        let restarts = restart && action.flags & ctypes::SA_RESTART != 0;
+        if !action.ignores(sig) && !restarts {
+            return Err(LinuxError::EINTR);
+        }
+        s.deliver(sig);
+    }
+    Ok(())
+}
+
+/// Sleeps until `deadline` of the monotonic clock, or returns
+/// [`LinuxError::EINTR`] once interrupted by a signal, regardless of
+/// `SA_RESTART`.
+pub(crate) fn sleep_until(deadline: TimeValue) -> LinuxResult {
+    let s = current_signals();
+    loop {
+        check_interrupted(false)?;
+        let now = monotonic_time();
+        if now >= deadline {
+            return Ok(());
+        }
+        s.wq.wait_timeout_until(deadline - now, || s.next_signal().is_some());
+    }
+}
+
+/// Registers `waker` to be woken once, the next time a signal is sent to the
+/// current thread, so that a blocking call waiting for it can be interrupted.
+///
+/// It replaces the waker registered before, if any.
+#[cfg(any(
+    feature = "unix",
+    feature = "select",
+    feature = "poll",
+    feature = "epoll"
+))]
+pub(crate) fn register_interrupt_waker(waker: &Waker) {
+    *current_signals().interrupt_waker.lock() = Some(waker.clone());
+}
+
+/// Sends `sig` to the current thread, which is delivered when the current call
+/// returns.
+pub(crate) fn send_current(sig: u32) {
+    current_signals().send(sig, SigInfo::new(ctypes::SI_USER as _));
+}
+
+/// Sends `sig` to a thread of the process that does not block it, preferring
+/// the current one.
+///
+/// Only the threads that have a signal state are chosen, the others, such as
+/// the timer task, never handle signals.
+fn send_process(sig: u32, info: SigInfo) {
+    let bit = sig_bit(sig);
+    let accepts = |s: &ThreadSignals| s.blocked.load(Ordering::Acquire) & bit == 0;
+    let curr = axtask::current().id().as_u64();
+    let threads = live_threads();
+    let target = threads
+        .iter()
+        .find(|(id, s)| *id == curr && accepts(s))
+        .or_else(|| threads.iter().find(|(_, s)| accepts(s)));
+    match target {
+        Some((_, s)) => s.send(sig, info),
+        None => PROCESS_PENDING.add(sig, info),
+    }
+}
+
+fn send_task(task: &AxTaskRef, sig: u32, info: SigInfo) {
+    if sig != 0 {
+        signals_of(task).send(sig, info);
+    }
+}
+
+/// Examine and change a signal action.
+pub unsafe fn sys_sigaction(
+    signum: c_int,
+    act: *const ctypes::sigaction,
+    oldact: *mut ctypes::sigaction,
+) -> c_int {
+    debug!(
+        "sys_sigaction <= {}, {:#x}, {:#x}",
+        signum, act as usize, oldact as usize
+    );
+    syscall_body!(sys_sigaction, {
+        let sig = check_signo(signum, false)?;
+        let act = unsafe { act.as_ref() };
+        if act.is_some() && UNBLOCKABLE & sig_bit(sig) != 0 {
+            return Err(LinuxError::EINVAL);
+        }
+        let old = {
+            let mut actions = ACTIONS.lock();
+            let old = actions[sig as usize - 1];
+            if let Some(act) = act {
+                let handler = unsafe { act.__sa_handler.sa_handler }.map_or(0, |f| f as usize);
+                actions[sig as usize - 1] = SigAction {
+                    handler,
+                    mask: mask_from_sigset(&act.sa_mask),
+                    flags: act.sa_flags as u32,
+                };
+            }
+            old
+        };
+        if let Some(oldact) = unsafe { oldact.as_mut() } {
+            *oldact = ctypes::sigaction {
+                __sa_handler: unsafe { core::mem::transmute::<usize, _>(old.handler) },
+                sa_mask: mask_to_sigset(old.mask),
+                sa_flags: old.flags as _,
+                ..Default::default()
+            };
+        }
+        Ok(0)
+    })
+}
+
+/// Examine and change the signal mask of the current thread.
+pub unsafe fn sys_sigprocmask(
+    how: c_int,
+    set: *const ctypes::sigset_t,
+    oldset: *mut ctypes::sigset_t,
+) -> c_int {
+    debug!(
+        "sys_sigprocmask <= {}, {:#x}, {:#x}",
+        how, set as usize, oldset as usize
+    );
+    syscall_body!(sys_sigprocmask, {
+        let s = current_signals();
+        let old = s.blocked.load(Ordering::Acquire);
+        if let Some(set) = unsafe { set.as_ref() } {
+            let mask = mask_from_sigset(set) & !UNBLOCKABLE;
+            let blocked = match how as u32 {
+                ctypes::SIG_BLOCK => old | mask,
+                ctypes::SIG_UNBLOCK => old & !mask,
+                ctypes::SIG_SETMASK => mask,
+                _ => return Err(LinuxError::EINVAL),
+            };
+            // the unblocked signals are delivered when this call returns
+            s.blocked.store(blocked, Ordering::Release);
+        }
+        if let Some(oldset) = unsafe { oldset.as_mut() } {
+            *oldset = mask_to_sigset(old);
+        }
+        Ok(0)
+    })
+}
+
+/// Examine the signals that are pending while blocked.
+pub unsafe fn sys_sigpending(set: *mut ctypes::sigset_t) -> c_int {
+    syscall_body!(sys_sigpending, {
+        crate::utils::check_null_mut_ptr(set)?;
+        let s = current_signals();
+        let pending = (s.pending.set.load(Ordering::Acquire)
+            | PROCESS_PENDING.set.load(Ordering::Acquire))
+            & s.blocked.load(Ordering::Acquire);
+        unsafe { *set = mask_to_sigset(pending) };
+        Ok(0)
+    })
+}
+
+/// Replaces the signal mask of the current thread until it is dropped, for the
+/// calls that take a temporary mask, such as `ppoll`.
+///
+/// The pending signals that the temporary mask does not block are delivered
+/// as it is dropped, before the old mask is restored.
+pub(crate) struct MaskGuard(Arc<ThreadSignals>, u64);
+
+impl MaskGuard {
+    pub(crate) fn new(set: &ctypes::sigset_t) -> Self {
+        let mask = mask_from_sigset(set) & !UNBLOCKABLE;
+        let s = current_signals();
+        let old = s.blocked.swap(mask, Ordering::AcqRel);
+        Self(s, old)
+    }
+}
+
+impl Drop for MaskGuard {
+    fn drop(&mut self) {
+        self.0.handle_signals();
+        self.0.blocked.store(self.1, Ordering::Release);
+    }
+}
+
+/// Replaces the signal mask of the current thread with `mask` (or keeps it if
+/// [`None`]), and waits until a signal is delivered.
+///
+/// Always returns [`LinuxError::EINTR`].
+fn suspend(mask: Option<u64>) -> LinuxResult<c_int> {
+    let s = current_signals();
+    let old = s.blocked.load(Ordering::Acquire);
+    if let Some(mask) = mask {
+        s.blocked.store(mask & !UNBLOCKABLE, Ordering::Release);
+    }
+    loop {
+        s.wq.wait_until(|| s.next_signal().is_some());
+        // discard the ignored signals, which do not end the waiting
+        let Some(sig) = s.next_signal() else {
+            continue;
+        };
+        if ACTIONS.lock()[sig as usize - 1].ignores(sig) {
+            s.deliver(sig);
+            continue;
+        }
+        break;
+    }
+    // the handlers run with the temporary mask
+    s.handle_signals();
+    s.blocked.store(old, Ordering::Release);
+    Err(LinuxError::EINTR)
+}
+
+/// Wait for a signal with the given signal mask.
+pub unsafe fn sys_sigsuspend(mask: *const ctypes::sigset_t) -> c_int {
+    debug!("sys_sigsuspend <= {:#x}", mask as usize);
+    syscall_body!(sys_sigsuspend, {
+        crate::utils::check_null_ptr(mask)?;
+        suspend(Some(mask_from_sigset(unsafe { &*mask })))
+    })
+}
+
+/// Wait for a signal.
+pub fn sys_pause() -> c_int {
+    debug!("sys_pause");
+    syscall_body!(sys_pause, suspend(None))
+}
+
+/// Send a signal to the process.
+///
+/// As there is only one process, which is also the only process group, `pid`
+/// can be 0, -1, or the process ID (as returned by `getpid`) or its negation.
+pub fn sys_kill(pid: ctypes::pid_t, sig: c_int) -> c_int {
+    debug!("sys_kill <= {}, {}", pid, sig);
+    syscall_body!(sys_kill, {
+        let sig = check_signo(sig, true)?;
+        let process = super::task::main_tid() as ctypes::pid_t;
+        if !matches!(pid, 0 | -1) && pid != process && pid != -process {
+            return Err(LinuxError::ESRCH);
+        }
+        if sig != 0 {
+            send_process(sig, SigInfo::new(ctypes::SI_USER as _));
+        }
+        Ok(0)
+    })
+}
+
+/// Send a signal to the current thread.
+pub fn sys_raise(sig: c_int) -> c_int {
+    debug!("sys_raise <= {}", sig);
+    syscall_body!(sys_raise, {
+        let sig = check_signo(sig, false)?;
+        send_task(
+            axtask::current().as_task_ref(),
+            sig,
+            SigInfo::new(ctypes::SI_TKILL),
+        );
+        Ok(0)
+    })
+}
+
+/// Send a signal to a thread.
+pub unsafe fn sys_pthread_kill(thread: ctypes::pthread_t, sig: c_int) -> c_int {
+    debug!("sys_pthread_kill <= {:#x}, {}", thread as usize, sig);
+    syscall_body!(sys_pthread_kill, {
+        let sig = check_signo(sig, true)?;
+        let task = super::pthread::thread_task(thread).ok_or(LinuxError::ESRCH)?;
+        send_task(&task, sig, SigInfo::new(ctypes::SI_TKILL));
+        Ok(0)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr;
+    use std::sync::{Mutex, MutexGuard, Once};
+
+    use super::*;
+
+    static INIT: Once = Once::new();
+    /// The tests share the signal actions, so they run one at a time.
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    fn init() -> MutexGuard<'static, ()> {
+        let guard = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        INIT.call_once(axtask::init_scheduler);
+        guard
+    }
+
+    /// Runs `f` in a task spawned by `axtask::spawn` rather than
+    /// `pthread_create`, and returns its result.
+    fn run_task<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+        let result = Arc::new(SpinNoIrq::new(None));
+        let task_result = result.clone();
+        axtask::spawn(move || *task_result.lock() = Some(f())).join();
+        result.lock().take().unwrap()
+    }
+
+    fn sigchld() -> ctypes::sigset_t {
+        mask_to_sigset(sig_bit(ctypes::SIGCHLD))
+    }
+
+    fn pending() -> u64 {
+        let mut set = ctypes::sigset_t::default();
+        assert_eq!(unsafe { sys_sigpending(&mut set) }, 0);
+        mask_from_sigset(&set)
+    }
+
+    #[test]
+    fn test_sigset() {
+        let mask = sig_bit(1) | sig_bit(33) | sig_bit(64);
+        assert_eq!(mask_from_sigset(&mask_to_sigset(mask)), mask);
+        assert_eq!(check_signo(0, false), Err(LinuxError::EINVAL));
+        assert_eq!(check_signo(0, true), Ok(0));
+        assert_eq!(check_signo(65, true), Err(LinuxError::EINVAL));
+    }
+
+    #[test]
+    fn test_task_signals() {
+        let _guard = init();
+        let (res, old, blocked, unblocked) = run_task(|| {
+            let mut old = ctypes::sigset_t::default();
+            let how = ctypes::SIG_BLOCK as c_int;
+            let res = unsafe { sys_sigprocmask(how, &sigchld(), &mut old) };
+            // pending while it is blocked
+            sys_raise(ctypes::SIGCHLD as c_int);
+            let blocked = pending();
+            // delivered and ignored by default as it is unblocked
+            let how = ctypes::SIG_UNBLOCK as c_int;
+            unsafe { sys_sigprocmask(how, &sigchld(), ptr::null_mut()) };
+            (res, mask_from_sigset(&old), blocked, pending())
+        });
+        assert_eq!(res, 0);
+        assert_eq!(old, 0);
+        assert_eq!(blocked, sig_bit(ctypes::SIGCHLD));
+        assert_eq!(unblocked, 0);
+    }
+
+    #[test]
+    fn test_inherited_mask() {
+        let _guard = init();
+        let (parent, child) = run_task(|| {
+            let how = ctypes::SIG_BLOCK as c_int;
+            unsafe { sys_sigprocmask(how, &sigchld(), ptr::null_mut()) };
+            let child = Arc::new(AtomicU64::new(0));
+            let child_mask = child.clone();
+            spawn_thread(move || {
+                let mask = current_signals().blocked.load(Ordering::Acquire);
+                child_mask.store(mask, Ordering::Release);
+            })
+            .join();
+            let parent = current_signals().blocked.load(Ordering::Acquire);
+            (parent, child.load(Ordering::Acquire))
+        });
+        assert_eq!(parent, sig_bit(ctypes::SIGCHLD));
+        assert_eq!(child, parent);
+    }
+
+    #[test]
+    fn test_separate_tasks() {
+        let _guard = init();
+        run_task(|| {
+            let how = ctypes::SIG_BLOCK as c_int;
+            unsafe { sys_sigprocmask(how, &sigchld(), ptr::null_mut()) };
+        });
+        // a new task has its own state, with nothing blocked
+        let blocked = run_task(|| current_signals().blocked.load(Ordering::Acquire));
+        assert_eq!(blocked, 0);
+    }
+}
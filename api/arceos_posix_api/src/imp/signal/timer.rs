@@ -0,0 +1,409 @@
+//! Timers that send signals as they expire: `alarm`, `setitimer` and the
+//! POSIX per-process timers.
+//!
+//! All timers are served by a single task, which is spawned on first use and
+//! sleeps until the earliest expiration.
+//!
+//! TODO: `ITIMER_VIRTUAL` and `ITIMER_PROF` need CPU time accounting, and
+//! `SIGEV_THREAD` is not supported.
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::ffi::{c_int, c_uint};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+
+use axerrno::{LinuxError, LinuxResult};
+use axhal::time::{TimeValue, monotonic_time, wall_time};
+use axsync::spin::SpinNoIrq;
+use axtask::{AxTaskRef, TaskState, WaitQueue};
+use spin::Once;
+
+use super::{SigInfo, send_process, send_task};
+use crate::ctypes;
+
+/// The ID of the timer of `alarm` and `setitimer(ITIMER_REAL)`.
+const ITIMER_REAL_ID: usize = 0;
+
+/// The signal sent when a timer expires.
+struct Event {
+    sig: u32,
+    info: SigInfo,
+    /// The target thread, or the whole process if [`None`].
+    thread: Option<AxTaskRef>,
+}
+
+struct Timer {
+    clock: u32,
+    /// The next expiration on the monotonic clock, or [`None`] if disarmed.
+    deadline: Option<TimeValue>,
+    interval: Duration,
+    /// The signal to send, or [`None`] for `SIGEV_NONE`.
+    event: Option<Event>,
+    /// The number of expirations missed before the last one.
+    overrun: usize,
+}
+
+impl Timer {
+    /// Returns the time until the next expiration, and the interval.
+    fn get(&self) -> (Duration, Duration) {
+        let remaining = self
+            .deadline
+            .map_or(Duration::ZERO, |ddl| ddl.saturating_sub(monotonic_time()));
+        (remaining, self.interval)
+    }
+
+    /// Arms the timer to expire at `deadline` (or disarms it if [`None`]), and
+    /// then every `interval` if it is not zero.
+    fn set(&mut self, deadline: Option<TimeValue>, interval: Duration) {
+        self.deadline = deadline;
+        self.interval = interval;
+        self.overrun = 0;
+    }
+}
+
+static TIMERS: SpinNoIrq<BTreeMap<usize, Timer>> = SpinNoIrq::new(BTreeMap::new());
+static NEXT_TIMER_ID: AtomicUsize = AtomicUsize::new(ITIMER_REAL_ID + 1);
+
+static TIMER_TASK: Once<AxTaskRef> = Once::new();
+static TIMER_WQ: WaitQueue = WaitQueue::new();
+/// Increased when the timers are changed, so that the timer task recomputes
+/// the earliest expiration.
+static TIMER_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+fn wake_timer_task() {
+    TIMER_TASK
+        .call_once(|| axtask::spawn_raw(timer_task, "timer".into(), axconfig::TASK_STACK_SIZE));
+    TIMER_GENERATION.fetch_add(1, Ordering::AcqRel);
+    TIMER_WQ.notify_one(false);
+}
+
+fn timer_task() {
+    loop {
+        let generation = TIMER_GENERATION.load(Ordering::Acquire);
+        let now = monotonic_time();
+        let mut expired = Vec::new();
+        let mut next = None;
+        for (&id, timer) in TIMERS.lock().iter_mut() {
+            let Some(deadline) = timer.deadline else {
+                continue;
+            };
+            if deadline <= now {
+                if timer.interval.is_zero() {
+                    timer.deadline = None;
+                } else {
+                    let missed = ((now - deadline).as_nanos() / timer.interval.as_nanos()) as u32;
+                    timer.overrun = missed as usize;
+                    timer.deadline = Some(deadline + timer.interval * (missed + 1));
+                }
+                expired.push(id);
+            }
+            if let Some(deadline) = timer.deadline {
+                next = Some(next.map_or(deadline, |next: TimeValue| next.min(deadline)));
+            }
+        }
+        // the signals are sent without holding the lock of the timers
+        for id in expired {
+            let event = TIMERS.lock().get(&id).and_then(|timer| {
+                let event = timer.event.as_ref()?;
+                Some((event.sig, event.info, event.thread.clone()))
+            });
+            match event {
+                Some((sig, info, Some(thread))) => send_task(&thread, sig, info),
+                Some((sig, info, None)) => send_process(sig, info),
+                None => {}
+            }
+        }
+
+        let changed = || TIMER_GENERATION.load(Ordering::Acquire) != generation;
+        match next {
+            Some(deadline) => {
+                let now = monotonic_time();
+                if deadline > now {
+                    TIMER_WQ.wait_timeout_until(deadline - now, changed);
+                }
+            }
+            None => TIMER_WQ.wait_until(changed),
+        }
+    }
+}
+
+fn duration_from_timeval(tv: &ctypes::timeval) -> LinuxResult<Duration> {
+    if tv.tv_sec < 0 || !(0..1_000_000).contains(&tv.tv_usec) {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(Duration::from(*tv))
+}
+
+fn duration_from_timespec(ts: &ctypes::timespec) -> LinuxResult<Duration> {
+    if ts.tv_sec < 0 || !(0..1_000_000_000).contains(&ts.tv_nsec) {
+        return Err(LinuxError::EINVAL);
+    }
+    Ok(Duration::from(*ts))
+}
+
+/// Sets the timer of `setitimer(ITIMER_REAL)`, and returns its old value.
+fn set_real_timer(value: Duration, interval: Duration) -> (Duration, Duration) {
+    let mut timers = TIMERS.lock();
+    let timer = timers.entry(ITIMER_REAL_ID).or_insert_with(|| Timer {
+        clock: ctypes::CLOCK_REALTIME,
+        deadline: None,
+        interval: Duration::ZERO,
+        event: Some(Event {
+            sig: ctypes::SIGALRM,
+            info: SigInfo::new(ctypes::SI_KERNEL as _),
+            thread: None,
+        }),
+        overrun: 0,
+    });
+    let old = timer.get();
+    let deadline = (!value.is_zero()).then(|| monotonic_time() + value);
+    timer.set(deadline, interval);
+    drop(timers);
+    wake_timer_task();
+    old
+}
+
+/// Arrange for a `SIGALRM` signal to be delivered after `seconds`, or cancel
+/// the pending alarm if `seconds` is zero.
+///
+/// Returns the number of seconds remaining of the previous alarm.
+pub fn sys_alarm(seconds: c_uint) -> c_uint {
+    debug!("sys_alarm <= {}", seconds);
+    syscall_body!(sys_alarm, {
+        let (old, _) = set_real_timer(Duration::from_secs(seconds as u64), Duration::ZERO);
+        // round to the nearest second, but never return 0 for an active alarm
+        let mut secs = old.as_secs();
+        if (secs == 0 && !old.is_zero()) || old.subsec_micros() >= 500_000 {
+            secs += 1;
+        }
+        Ok(secs as c_uint)
+    })
+}
+
+/// Get the value of an interval timer.
+pub unsafe fn sys_getitimer(which: c_int, curr_value: *mut ctypes::itimerval) -> c_int {
+    debug!("sys_getitimer <= {}, {:#x}", which, curr_value as usize);
+    syscall_body!(sys_getitimer, {
+        crate::utils::check_null_mut_ptr(curr_value)?;
+        if which as u32 != ctypes::ITIMER_REAL {
+            return Err(LinuxError::EINVAL);
+        }
+        let (value, interval) = TIMERS
+            .lock()
+            .get(&ITIMER_REAL_ID)
+            .map_or((Duration::ZERO, Duration::ZERO), Timer::get);
+        unsafe {
+            *curr_value = ctypes::itimerval {
+                it_interval: interval.into(),
+                it_value: value.into(),
+            }
+        };
+        Ok(0)
+    })
+}
+
+/// Set the value of an interval timer, which sends `SIGALRM` to the process
+/// as it expires.
+///
+/// Only `ITIMER_REAL` is supported.
+pub unsafe fn sys_setitimer(
+    which: c_int,
+    new_value: *const ctypes::itimerval,
+    old_value: *mut ctypes::itimerval,
+) -> c_int {
+    debug!(
+        "sys_setitimer <= {}, {:#x}, {:#x}",
+        which, new_value as usize, old_value as usize
+    );
+    syscall_body!(sys_setitimer, {
+        crate::utils::check_null_ptr(new_value)?;
+        if which as u32 != ctypes::ITIMER_REAL {
+            return Err(LinuxError::EINVAL);
+        }
+        let new_value = unsafe { &*new_value };
+        let value = duration_from_timeval(&new_value.it_value)?;
+        let interval = duration_from_timeval(&new_value.it_interval)?;
+        let (old, old_interval) = set_real_timer(value, interval);
+        if let Some(old_value) = unsafe { old_value.as_mut() } {
+            *old_value = ctypes::itimerval {
+                it_interval: old_interval.into(),
+                it_value: old.into(),
+            };
+        }
+        Ok(0)
+    })
+}
+
+/// Create a per-process timer on `clockid`, which notifies as `sevp` (or
+/// sends `SIGALRM` to the process if it is null) as it expires.
+pub unsafe fn sys_timer_create(
+    clockid: ctypes::clockid_t,
+    sevp: *mut ctypes::sigevent,
+    timerid: *mut ctypes::timer_t,
+) -> c_int {
+    debug!(
+        "sys_timer_create <= {}, {:#x}, {:#x}",
+        clockid, sevp as usize, timerid as usize
+    );
+    syscall_body!(sys_timer_create, {
+        crate::utils::check_null_mut_ptr(timerid)?;
+        if !matches!(
+            clockid as u32,
+            ctypes::CLOCK_REALTIME | ctypes::CLOCK_MONOTONIC
+        ) {
+            return Err(LinuxError::EINVAL);
+        }
+        let id = NEXT_TIMER_ID.fetch_add(1, Ordering::Relaxed);
+        let mut info = SigInfo::new(ctypes::SI_TIMER);
+        let event = match unsafe { sevp.as_ref() } {
+            None => {
+                info.value = id;
+                Some(Event {
+                    sig: ctypes::SIGALRM,
+                    info,
+                    thread: None,
+                })
+            }
+            Some(sev) => {
+                info.value = unsafe { sev.sigev_value.sival_ptr } as usize;
+                let sig = || super::check_signo(sev.sigev_signo, false);
+                match sev.sigev_notify as u32 {
+                    ctypes::SIGEV_NONE => None,
+                    ctypes::SIGEV_SIGNAL => Some(Event {
+                        sig: sig()?,
+                        info,
+                        thread: None,
+                    }),
+                    ctypes::SIGEV_THREAD_ID => {
+                        let tid = unsafe { sev.__sev_fields.sigev_notify_thread_id } as u64;
+                        let thread = axtask::tasks()
+                            .into_iter()
+                            .find(|t| t.id().as_u64() == tid && t.state() != TaskState::Exited)
+                            .ok_or(LinuxError::EINVAL)?;
+                        Some(Event {
+                            sig: sig()?,
+                            info,
+                            thread: Some(thread),
+                        })
+                    }
+                    _ => return Err(LinuxError::EINVAL),
+                }
+            }
+        };
+        TIMERS.lock().insert(id, Timer {
+            clock: clockid as u32,
+            deadline: None,
+            interval: Duration::ZERO,
+            event,
+            overrun: 0,
+        });
+        unsafe { *timerid = id as ctypes::timer_t };
+        Ok(0)
+    })
+}
+
+/// Arm or disarm a per-process timer.
+///
+/// The expiration is an absolute time of the clock of the timer if `flags`
+/// contains `TIMER_ABSTIME`, or relative to the current time otherwise.
+pub unsafe fn sys_timer_settime(
+    timerid: ctypes::timer_t,
+    flags: c_int,
+    new_value: *const ctypes::itimerspec,
+    old_value: *mut ctypes::itimerspec,
+) -> c_int {
+    debug!(
+        "sys_timer_settime <= {:#x}, {}, {:#x}, {:#x}",
+        timerid as usize, flags, new_value as usize, old_value as usize
+    );
+    syscall_body!(sys_timer_settime, {
+        crate::utils::check_null_ptr(new_value)?;
+        let new_value = unsafe { &*new_value };
+        let value = duration_from_timespec(&new_value.it_value)?;
+        let interval = duration_from_timespec(&new_value.it_interval)?;
+
+        let mut timers = TIMERS.lock();
+        let timer = timers
+            .get_mut(&(timerid as usize))
+            .filter(|_| timerid as usize != ITIMER_REAL_ID)
+            .ok_or(LinuxError::EINVAL)?;
+        let deadline = if value.is_zero() {
+            None
+        } else if flags as u32 & ctypes::TIMER_ABSTIME == 0 {
+            Some(monotonic_time() + value)
+        } else if timer.clock == ctypes::CLOCK_REALTIME {
+            Some(monotonic_time() + value.saturating_sub(wall_time()))
+        } else {
+            Some(value)
+        };
+        let (old, old_interval) = timer.get();
+        timer.set(deadline, interval);
+        drop(timers);
+        wake_timer_task();
+        if let Some(old_value) = unsafe { old_value.as_mut() } {
+            *old_value = ctypes::itimerspec {
+                it_interval: old_interval.into(),
+                it_value: old.into(),
+            };
+        }
+        Ok(0)
+    })
+}
+
+/// Get the time until the next expiration of a per-process timer, and its
+/// interval.
+pub unsafe fn sys_timer_gettime(
+    timerid: ctypes::timer_t,
+    curr_value: *mut ctypes::itimerspec,
+) -> c_int {
+    debug!(
+        "sys_timer_gettime <= {:#x}, {:#x}",
+        timerid as usize, curr_value as usize
+    );
+    syscall_body!(sys_timer_gettime, {
+        crate::utils::check_null_mut_ptr(curr_value)?;
+        let (value, interval) = TIMERS
+            .lock()
+            .get(&(timerid as usize))
+            .filter(|_| timerid as usize != ITIMER_REAL_ID)
+            .map(Timer::get)
+            .ok_or(LinuxError::EINVAL)?;
+        unsafe {
+            *curr_value = ctypes::itimerspec {
+                it_interval: interval.into(),
+                it_value: value.into(),
+            }
+        };
+        Ok(0)
+    })
+}
+
+/// Get the number of expirations missed before the last one of a
+/// per-process timer.
+pub fn sys_timer_getoverrun(timerid: ctypes::timer_t) -> c_int {
+    debug!("sys_timer_getoverrun <= {:#x}", timerid as usize);
+    syscall_body!(sys_timer_getoverrun, {
+        TIMERS
+            .lock()
+            .get(&(timerid as usize))
+            .filter(|_| timerid as usize != ITIMER_REAL_ID)
+            .map(|timer| timer.overrun.min(c_int::MAX as usize))
+            .ok_or(LinuxError::EINVAL)
+    })
+}
+
+/// Delete a per-process timer.
+pub fn sys_timer_delete(timerid: ctypes::timer_t) -> c_int {
+    debug!("sys_timer_delete <= {:#x}", timerid as usize);
+    syscall_body!(sys_timer_delete, {
+        if timerid as usize == ITIMER_REAL_ID {
+            return Err(LinuxError::EINVAL);
+        }
+        TIMERS
+            .lock()
+            .remove(&(timerid as usize))
+            .ok_or(LinuxError::EINVAL)?;
+        Ok(0)
+    })
+}
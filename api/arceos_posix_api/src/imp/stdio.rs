@@ -53,8 +53,12 @@ pub struct Stdin {
 }
 
 impl Stdin {
-    // Block until at least one byte is read.
-    fn read_blocked(&self, buf: &mut [u8]) -> AxResult<usize> {
+    // Block until at least one byte is read, or `check` fails.
+    fn read_blocked<E: From<axerrno::AxError>>(
+        &self,
+        buf: &mut [u8],
+        mut check: impl FnMut() -> Result<(), E>,
+    ) -> Result<usize, E> {
         let read_len = self.inner.lock().read(buf)?;
         if buf.is_empty() || read_len > 0 {
             return Ok(read_len);
@@ -66,13 +70,14 @@ impl Stdin {
                 return Ok(read_len);
             }
             crate::sys_sched_yield();
+            check()?;
         }
     }
 }
 
 impl Read for Stdin {
     fn read(&mut self, buf: &mut [u8]) -> AxResult<usize> {
-        self.read_blocked(buf)
+        self.read_blocked(buf, || Ok(()))
     }
 }
 
@@ -105,7 +110,11 @@ pub fn stdout() -> Stdout {
 #[cfg(feature = "fd")]
 impl super::fd_ops::FileLike for Stdin {
     fn read(&self, buf: &mut [u8]) -> LinuxResult<usize> {
-        Ok(self.read_blocked(buf)?)
+        self.read_blocked(buf, || {
+            #[cfg(feature = "signal")]
+            crate::imp::signal::check_interrupted(true)?;
+            Ok(())
+        })
     }
 
     fn write(&self, _buf: &[u8]) -> LinuxResult<usize> {
@@ -127,8 +136,10 @@ impl super::fd_ops::FileLike for Stdin {
     }
 
     fn poll(&self) -> LinuxResult<PollState> {
+        // polled periodically, as the console raises no interrupt on input
+        let readable = !self.inner.lock().fill_buf()?.is_empty();
         Ok(PollState {
-            readable: true,
+            readable,
             writable: true,
         })
     }
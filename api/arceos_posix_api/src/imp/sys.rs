@@ -28,7 +28,7 @@ pub fn sys_sysconf(name: c_int) -> c_long {
             // Page size
             ctypes::_SC_PAGE_SIZE => Ok(PAGE_SIZE_4K),
             // Number of processors in use
-            ctypes::_SC_NPROCESSORS_ONLN => Ok(axconfig::SMP),
+            ctypes::_SC_NPROCESSORS_ONLN => Ok(axhal::dtb::cpu_count()),
             // Total physical pages
             ctypes::_SC_PHYS_PAGES => Ok(phys_pages),
             // Avaliable physical pages
@@ -1,4 +1,6 @@
 use core::ffi::c_int;
+#[cfg(feature = "multitask")]
+use core::sync::atomic::{AtomicU64, Ordering};
 
 /// Relinquish the CPU, and switches to another task.
 ///
@@ -16,12 +18,30 @@ pub fn sys_sched_yield() -> c_int {
     0
 }
 
-/// Get current thread ID.
+/// The ID of the main thread, recorded as the constructors run on it.
+#[cfg(feature = "multitask")]
+static MAIN_TID: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(feature = "multitask")]
+#[ctor_bare::register_ctor]
+fn init_main_tid() {
+    if let Some(curr) = axtask::current_may_uninit() {
+        MAIN_TID.store(curr.id().as_u64(), Ordering::Release);
+    }
+}
+
+/// Returns the ID of the main thread, which is also the process ID.
+#[cfg(feature = "multitask")]
+pub(crate) fn main_tid() -> u64 {
+    MAIN_TID.load(Ordering::Acquire)
+}
+
+/// Get the process ID, which is the ID of the main thread.
 pub fn sys_getpid() -> c_int {
     syscall_body!(sys_getpid,
         #[cfg(feature = "multitask")]
         {
-            Ok(axtask::current().id().as_u64() as c_int)
+            Ok(main_tid() as c_int)
         }
         #[cfg(not(feature = "multitask"))]
         {
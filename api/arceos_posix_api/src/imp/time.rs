@@ -57,7 +57,8 @@ pub unsafe fn sys_clock_gettime(clk: ctypes::clockid_t, ts: *mut ctypes::timespe
 
 /// Sleep some nanoseconds
 ///
-/// TODO: should be woken by signals, and set errno
+/// Fails with `EINTR` and stores the remaining time in `rem` if interrupted by
+/// a signal.
 pub unsafe fn sys_nanosleep(req: *const ctypes::timespec, rem: *mut ctypes::timespec) -> c_int {
     syscall_body!(sys_nanosleep, {
         unsafe {
@@ -71,22 +72,21 @@ pub unsafe fn sys_nanosleep(req: *const ctypes::timespec, rem: *mut ctypes::time
             Duration::from(*req)
         };
 
-        let now = axhal::time::monotonic_time();
-
-        #[cfg(feature = "multitask")]
-        axtask::sleep(dur);
-        #[cfg(not(feature = "multitask"))]
-        axhal::time::busy_wait(dur);
-
-        let after = axhal::time::monotonic_time();
-        let actual = after - now;
-
-        if let Some(diff) = dur.checked_sub(actual) {
-            if !rem.is_null() {
-                unsafe { (*rem) = diff.into() };
+        let deadline = axhal::time::monotonic_time() + dur;
+        #[cfg(feature = "signal")]
+        if let Err(e) = crate::imp::signal::sleep_until(deadline) {
+            if let Some(rem) = unsafe { rem.as_mut() } {
+                let now = axhal::time::monotonic_time();
+                *rem = deadline.saturating_sub(now).into();
             }
-            return Err(LinuxError::EINTR);
+            return Err(e);
         }
+        #[cfg(not(feature = "signal"))]
+        let _ = rem; // never interrupted
+        #[cfg(all(feature = "multitask", not(feature = "signal")))]
+        axtask::sleep_until(deadline);
+        #[cfg(not(feature = "multitask"))]
+        axhal::time::busy_wait_until(deadline);
         Ok(0)
     })
 }
@@ -0,0 +1,922 @@
+//! Unix domain sockets.
+//!
+//! Sockets are bound to paths or to names in the abstract namespace, and
+//! are found by their addresses in a global registry. With the `fs` feature,
+//! binding to a path also creates a file there, which must be removed before
+//! the path can be bound again, as on Linux.
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::mem::{offset_of, size_of};
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+
+use axerrno::{LinuxError, LinuxResult};
+use axio::PollState;
+use axsync::Mutex;
+
+use super::fd_ops::{FileWaiter, PollWakers};
+use crate::ctypes;
+
+/// The capacity of each direction of a stream connection.
+const STREAM_BUF_LEN: usize = 64 * 1024;
+/// The maximum size of a datagram.
+const MAX_DGRAM_LEN: usize = 64 * 1024;
+/// The maximum number of datagrams queued on a socket.
+const DGRAM_QUEUE_LEN: usize = 64;
+/// The maximum number of connections waiting to be accepted.
+const MAX_BACKLOG: usize = 128;
+
+/// The bound sockets, by address.
+static REGISTRY: Mutex<BTreeMap<UnixAddr, Endpoint>> = Mutex::new(BTreeMap::new());
+
+/// The address of a Unix domain socket.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UnixAddr {
+    /// The address of a socket that is not bound.
+    Unnamed,
+    /// An absolute path in the file system.
+    Path(String),
+    /// A name in the abstract namespace, without the leading NUL byte.
+    Abstract(Vec<u8>),
+}
+
+impl UnixAddr {
+    /// Loads the address from a `sockaddr_un` of `addrlen` bytes.
+    ///
+    /// Relative paths are resolved against the current directory.
+    pub(super) unsafe fn from_sockaddr(
+        addr: *const ctypes::sockaddr,
+        addrlen: ctypes::socklen_t,
+    ) -> LinuxResult<Self> {
+        let offset = offset_of!(ctypes::sockaddr_un, sun_path);
+        let len = (addrlen as usize).min(size_of::<ctypes::sockaddr_un>());
+        if len < offset {
+            return Err(LinuxError::EINVAL);
+        }
+        let path =
+            unsafe { core::slice::from_raw_parts((addr as *const u8).add(offset), len - offset) };
+        match path.first() {
+            None => Ok(Self::Unnamed),
+            Some(0) => Ok(Self::Abstract(path[1..].to_vec())),
+            Some(_) => {
+                let end = path.iter().position(|&c| c == 0).unwrap_or(path.len());
+                let path = core::str::from_utf8(&path[..end]).map_err(|_| LinuxError::EINVAL)?;
+                #[cfg(feature = "fs")]
+                let path = axfs::api::canonicalize(path)?;
+                Ok(Self::Path(path.into()))
+            }
+        }
+    }
+
+    /// Converts the address to a `sockaddr_un`, and returns it with its
+    /// length.
+    pub(super) fn to_sockaddr(&self) -> (ctypes::sockaddr_un, usize) {
+        let mut addr = ctypes::sockaddr_un {
+            sun_family: ctypes::AF_UNIX as _,
+            ..Default::default()
+        };
+        let (name, start) = match self {
+            Self::Unnamed => (&[][..], 0),
+            Self::Path(path) => (path.as_bytes(), 0),
+            Self::Abstract(name) => (&name[..], 1),
+        };
+        let name_len = name.len().min(addr.sun_path.len() - start);
+        for (dst, &src) in addr.sun_path[start..].iter_mut().zip(&name[..name_len]) {
+            *dst = src as _;
+        }
+        let path_len = match self {
+            Self::Unnamed => 0,
+            // with the terminating NUL byte if it fits
+            Self::Path(_) => (name_len + 1).min(addr.sun_path.len()),
+            Self::Abstract(_) => name_len + 1,
+        };
+        (addr, offset_of!(ctypes::sockaddr_un, sun_path) + path_len)
+    }
+}
+
+/// What a bound address refers to in the registry.
+enum Endpoint {
+    Stream(Weak<Backlog>),
+    Datagram(Weak<Inbox>),
+}
+
+/// The connections waiting to be accepted by a bound stream socket.
+struct Backlog {
+    queue: Mutex<VecDeque<Connection>>,
+    /// Whether the socket is listening.
+    listening: AtomicBool,
+    wakers: PollWakers,
+}
+
+/// One direction of a stream connection.
+struct StreamBuf {
+    data: Mutex<VecDeque<u8>>,
+    /// No more data will be written.
+    write_shut: AtomicBool,
+    /// No more data will be read.
+    read_shut: AtomicBool,
+    /// Woken when data is written or read, or when either end shuts down.
+    wakers: PollWakers,
+}
+
+impl Drop for Backlog {
+    fn drop(&mut self) {
+        // fail the pending connections
+        self.wakers.wake();
+    }
+}
+
+impl StreamBuf {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            data: Mutex::new(VecDeque::new()),
+            write_shut: AtomicBool::new(false),
+            read_shut: AtomicBool::new(false),
+            wakers: PollWakers::new(),
+        })
+    }
+}
+
+/// One end of a stream connection.
+struct Connection {
+    rx: Arc<StreamBuf>,
+    tx: Arc<StreamBuf>,
+    peer_addr: UnixAddr,
+}
+
+impl Connection {
+    /// Creates both ends of a connection, with the address of the peer of
+    /// each end.
+    fn pair(peer_addr0: UnixAddr, peer_addr1: UnixAddr) -> (Self, Self) {
+        let (buf0, buf1) = (StreamBuf::new(), StreamBuf::new());
+        let end0 = Self {
+            rx: buf0.clone(),
+            tx: buf1.clone(),
+            peer_addr: peer_addr0,
+        };
+        let end1 = Self {
+            rx: buf1,
+            tx: buf0,
+            peer_addr: peer_addr1,
+        };
+        (end0, end1)
+    }
+
+    fn shutdown(&self) {
+        self.tx.write_shut.store(true, Ordering::Release);
+        self.rx.read_shut.store(true, Ordering::Release);
+        self.tx.wakers.wake();
+        self.rx.wakers.wake();
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// The datagrams received by a datagram socket, with their origins.
+struct Inbox {
+    queue: Mutex<VecDeque<(Vec<u8>, UnixAddr)>>,
+    /// Woken when a datagram is queued or received.
+    wakers: PollWakers,
+}
+
+impl Inbox {
+    fn new() -> Arc<Self> {
+        Arc::new(Self {
+            queue: Mutex::new(VecDeque::new()),
+            wakers: PollWakers::new(),
+        })
+    }
+}
+
+impl Drop for Inbox {
+    fn drop(&mut self) {
+        // fail the pending sends
+        self.wakers.wake();
+    }
+}
+
+enum Kind {
+    Stream {
+        /// Created when the socket is bound.
+        backlog: Mutex<Option<Arc<Backlog>>>,
+        conn: Mutex<Option<Arc<Connection>>>,
+    },
+    Datagram {
+        inbox: Arc<Inbox>,
+        peer: Mutex<Option<(Weak<Inbox>, UnixAddr)>>,
+    },
+}
+
+/// A Unix domain socket of type `SOCK_STREAM` or `SOCK_DGRAM`.
+pub struct UnixSocket {
+    kind: Kind,
+    local_addr: Mutex<UnixAddr>,
+    nonblock: AtomicBool,
+}
+
+impl UnixSocket {
+    fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            local_addr: Mutex::new(UnixAddr::Unnamed),
+            nonblock: AtomicBool::new(false),
+        }
+    }
+
+    fn stream(conn: Option<Connection>) -> Self {
+        Self::new(Kind::Stream {
+            backlog: Mutex::new(None),
+            conn: Mutex::new(conn.map(Arc::new)),
+        })
+    }
+
+    fn datagram(inbox: Arc<Inbox>, peer: Option<(Weak<Inbox>, UnixAddr)>) -> Self {
+        Self::new(Kind::Datagram {
+            inbox,
+            peer: Mutex::new(peer),
+        })
+    }
+
+    /// Creates a new stream socket.
+    pub fn new_stream() -> Self {
+        Self::stream(None)
+    }
+
+    /// Creates a new datagram socket.
+    pub fn new_datagram() -> Self {
+        Self::datagram(Inbox::new(), None)
+    }
+
+    /// Creates a pair of connected sockets, of type `SOCK_STREAM` if `stream`
+    /// is true, or `SOCK_DGRAM` otherwise.
+    pub fn pair(stream: bool) -> (Self, Self) {
+        if stream {
+            let (end0, end1) = Connection::pair(UnixAddr::Unnamed, UnixAddr::Unnamed);
+            (Self::stream(Some(end0)), Self::stream(Some(end1)))
+        } else {
+            let (inbox0, inbox1) = (Inbox::new(), Inbox::new());
+            let peer0 = (Arc::downgrade(&inbox1), UnixAddr::Unnamed);
+            let peer1 = (Arc::downgrade(&inbox0), UnixAddr::Unnamed);
+            (
+                Self::datagram(inbox0, Some(peer0)),
+                Self::datagram(inbox1, Some(peer1)),
+            )
+        }
+    }
+
+    /// Returns `SOCK_STREAM` or `SOCK_DGRAM`.
+    pub fn socktype(&self) -> u32 {
+        match self.kind {
+            Kind::Stream { .. } => ctypes::SOCK_STREAM,
+            Kind::Datagram { .. } => ctypes::SOCK_DGRAM,
+        }
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblock.store(nonblocking, Ordering::Release);
+    }
+
+    pub fn local_addr(&self) -> UnixAddr {
+        self.local_addr.lock().clone()
+    }
+
+    pub fn peer_addr(&self) -> LinuxResult<UnixAddr> {
+        let peer_addr = match &self.kind {
+            Kind::Stream { conn, .. } => conn.lock().as_ref().map(|c| c.peer_addr.clone()),
+            Kind::Datagram { peer, .. } => peer.lock().as_ref().map(|(_, addr)| addr.clone()),
+        };
+        peer_addr.ok_or(LinuxError::ENOTCONN)
+    }
+
+    pub fn bind(&self, addr: UnixAddr) -> LinuxResult {
+        let mut local_addr = self.local_addr.lock();
+        if *local_addr != UnixAddr::Unnamed {
+            return Err(LinuxError::EINVAL);
+        }
+        if addr == UnixAddr::Unnamed {
+            return Err(LinuxError::EINVAL);
+        }
+
+        let mut registry = REGISTRY.lock();
+        if registry.get(&addr).is_some_and(Endpoint::is_alive) {
+            return Err(LinuxError::EADDRINUSE);
+        }
+        #[cfg(feature = "fs")]
+        if let UnixAddr::Path(path) = &addr {
+            axfs::api::File::create_new(path).map_err(|e| match e {
+                axerrno::AxError::AlreadyExists => LinuxError::EADDRINUSE,
+                e => e.into(),
+            })?;
+        }
+        let endpoint = match &self.kind {
+            Kind::Stream { backlog, .. } => {
+                let new_backlog = Arc::new(Backlog {
+                    queue: Mutex::new(VecDeque::new()),
+                    listening: AtomicBool::new(false),
+                    wakers: PollWakers::new(),
+                });
+                let endpoint = Endpoint::Stream(Arc::downgrade(&new_backlog));
+                *backlog.lock() = Some(new_backlog);
+                endpoint
+            }
+            Kind::Datagram { inbox, .. } => Endpoint::Datagram(Arc::downgrade(inbox)),
+        };
+        registry.insert(addr.clone(), endpoint);
+        *local_addr = addr;
+        Ok(())
+    }
+
+    pub fn listen(&self) -> LinuxResult {
+        let Kind::Stream { backlog, conn } = &self.kind else {
+            return Err(LinuxError::EOPNOTSUPP);
+        };
+        if conn.lock().is_some() {
+            return Err(LinuxError::EINVAL);
+        }
+        let backlog = backlog.lock();
+        // Linux binds the socket implicitly, which is not supported
+        let backlog = backlog.as_ref().ok_or(LinuxError::EINVAL)?;
+        backlog.listening.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    pub fn accept(&self) -> LinuxResult<Self> {
+        let Kind::Stream { backlog, .. } = &self.kind else {
+            return Err(LinuxError::EOPNOTSUPP);
+        };
+        let backlog = backlog
+            .lock()
+            .clone()
+            .filter(|b| b.listening.load(Ordering::Acquire))
+            .ok_or(LinuxError::EINVAL)?;
+        let conn = self.block_on(|waker| {
+            backlog.wakers.register(waker);
+            backlog.queue.lock().pop_front().ok_or(LinuxError::EAGAIN)
+        })?;
+        // wake up the connecting sockets if the backlog was full
+        backlog.wakers.wake();
+
+        let socket = Self::stream(Some(conn));
+        *socket.local_addr.lock() = self.local_addr();
+        Ok(socket)
+    }
+
+    pub fn connect(&self, addr: UnixAddr) -> LinuxResult {
+        match &self.kind {
+            Kind::Stream { backlog, conn } => {
+                if conn.lock().is_some() {
+                    return Err(LinuxError::EISCONN);
+                }
+                if backlog
+                    .lock()
+                    .as_ref()
+                    .is_some_and(|b| b.listening.load(Ordering::Acquire))
+                {
+                    return Err(LinuxError::EINVAL);
+                }
+                let Endpoint::Stream(server) = lookup(&addr)? else {
+                    return Err(LinuxError::EPROTOTYPE);
+                };
+                let (client_end, server_end) = Connection::pair(addr, self.local_addr());
+                let mut server_end = Some(server_end);
+                self.block_on(|waker| {
+                    let server = server.upgrade().ok_or(LinuxError::ECONNREFUSED)?;
+                    if !server.listening.load(Ordering::Acquire) {
+                        return Err(LinuxError::ECONNREFUSED);
+                    }
+                    server.wakers.register(waker);
+                    let mut queue = server.queue.lock();
+                    if queue.len() >= MAX_BACKLOG {
+                        return Err(LinuxError::EAGAIN);
+                    }
+                    queue.push_back(server_end.take().unwrap());
+                    drop(queue);
+                    server.wakers.wake();
+                    Ok(())
+                })?;
+                *conn.lock() = Some(Arc::new(client_end));
+                Ok(())
+            }
+            Kind::Datagram { peer, .. } => {
+                let Endpoint::Datagram(inbox) = lookup(&addr)? else {
+                    return Err(LinuxError::EPROTOTYPE);
+                };
+                *peer.lock() = Some((inbox, addr));
+                Ok(())
+            }
+        }
+    }
+
+    pub fn send(&self, buf: &[u8]) -> LinuxResult<usize> {
+        match &self.kind {
+            Kind::Stream { conn, .. } => {
+                let conn = conn.lock().clone().ok_or(LinuxError::ENOTCONN)?;
+                self.stream_send(&conn, buf)
+            }
+            Kind::Datagram { peer, .. } => {
+                let inbox = peer.lock().as_ref().ok_or(LinuxError::ENOTCONN)?.0.clone();
+                self.dgram_send(&inbox, buf)
+            }
+        }
+    }
+
+    pub fn send_to(&self, buf: &[u8], addr: UnixAddr) -> LinuxResult<usize> {
+        match &self.kind {
+            Kind::Stream { .. } => Err(LinuxError::EISCONN),
+            Kind::Datagram { .. } => {
+                let Endpoint::Datagram(inbox) = lookup(&addr)? else {
+                    return Err(LinuxError::EPROTOTYPE);
+                };
+                self.dgram_send(&inbox, buf)
+            }
+        }
+    }
+
+    /// Receives data on the socket, and returns its length and origin.
+    ///
+    /// The origin is `None` for stream sockets. A datagram is truncated if
+    /// `buf` is too small.
+    pub fn recv_from(&self, buf: &mut [u8]) -> LinuxResult<(usize, Option<UnixAddr>)> {
+        match &self.kind {
+            Kind::Stream { conn, .. } => {
+                let conn = conn.lock().clone().ok_or(LinuxError::ENOTCONN)?;
+                if buf.is_empty() {
+                    return Ok((0, None));
+                }
+                let len = self.block_on(|waker| {
+                    conn.rx.wakers.register(waker);
+                    let mut data = conn.rx.data.lock();
+                    if data.is_empty() {
+                        return if conn.rx.write_shut.load(Ordering::Acquire)
+                            || conn.rx.read_shut.load(Ordering::Acquire)
+                        {
+                            Ok(0) // EOF
+                        } else {
+                            Err(LinuxError::EAGAIN)
+                        };
+                    }
+                    let len = buf.len().min(data.len());
+                    for (dst, src) in buf.iter_mut().zip(data.drain(..len)) {
+                        *dst = src;
+                    }
+                    Ok(len)
+                })?;
+                conn.rx.wakers.wake();
+                Ok((len, None))
+            }
+            Kind::Datagram { inbox, .. } => {
+                let (data, from) = self.block_on(|waker| {
+                    inbox.wakers.register(waker);
+                    inbox.queue.lock().pop_front().ok_or(LinuxError::EAGAIN)
+                })?;
+                inbox.wakers.wake();
+                let len = buf.len().min(data.len());
+                buf[..len].copy_from_slice(&data[..len]);
+                Ok((len, Some(from)))
+            }
+        }
+    }
+
+    /// Shuts down both directions of a connection.
+    pub fn shutdown(&self) -> LinuxResult {
+        match &self.kind {
+            Kind::Stream { conn, .. } => {
+                conn.lock().as_ref().ok_or(LinuxError::ENOTCONN)?.shutdown();
+                Ok(())
+            }
+            Kind::Datagram { peer, .. } => {
+                peer.lock().as_ref().ok_or(LinuxError::ENOTCONN)?;
+                Ok(())
+            }
+        }
+    }
+
+    pub fn poll(&self) -> LinuxResult<PollState> {
+        let state = match &self.kind {
+            Kind::Stream { backlog, conn } => match conn.lock().as_ref() {
+                Some(conn) => PollState {
+                    readable: !conn.rx.data.lock().is_empty()
+                        || conn.rx.write_shut.load(Ordering::Acquire),
+                    // writing to a closed connection fails immediately
+                    writable: conn.tx.data.lock().len() < STREAM_BUF_LEN
+                        || conn.tx.read_shut.load(Ordering::Acquire),
+                },
+                None => PollState {
+                    readable: backlog
+                        .lock()
+                        .as_ref()
+                        .is_some_and(|b| !b.queue.lock().is_empty()),
+                    writable: false,
+                },
+            },
+            Kind::Datagram { inbox, peer } => PollState {
+                readable: !inbox.queue.lock().is_empty(),
+                writable: match peer.lock().as_ref() {
+                    Some((inbox, _)) => inbox
+                        .upgrade()
+                        .is_none_or(|inbox| inbox.queue.lock().len() < DGRAM_QUEUE_LEN),
+                    None => true,
+                },
+            },
+        };
+        Ok(state)
+    }
+
+    /// Registers `waker` to be woken once, when the socket may have become
+    /// readable or writable.
+    ///
+    /// Returns `false` if a stream socket is neither connected nor listening,
+    /// which has nothing to register `waker` on.
+    pub fn register_waker(&self, waker: &Waker) -> bool {
+        match &self.kind {
+            Kind::Stream { backlog, conn } => match conn.lock().as_ref() {
+                Some(conn) => {
+                    conn.rx.wakers.register(waker);
+                    conn.tx.wakers.register(waker);
+                    true
+                }
+                None => match backlog.lock().as_ref() {
+                    Some(backlog) => {
+                        backlog.wakers.register(waker);
+                        true
+                    }
+                    None => false,
+                },
+            },
+            Kind::Datagram { inbox, peer } => {
+                inbox.wakers.register(waker);
+                if let Some(peer) = peer.lock().as_ref().and_then(|(p, _)| p.upgrade()) {
+                    peer.wakers.register(waker);
+                }
+                true
+            }
+        }
+    }
+
+    /// Whether the peer has closed the connection or shut down writing.
+    pub fn peer_closed(&self) -> bool {
+        match &self.kind {
+            Kind::Stream { conn, .. } => conn
+                .lock()
+                .as_ref()
+                .is_some_and(|conn| conn.rx.write_shut.load(Ordering::Acquire)),
+            Kind::Datagram { .. } => false,
+        }
+    }
+
+    /// Whether the connection is closed in both directions, as the peer is
+    /// closed or both ends are shut down.
+    pub fn hung_up(&self) -> bool {
+        match &self.kind {
+            Kind::Stream { conn, .. } => conn.lock().as_ref().is_some_and(|conn| {
+                conn.rx.write_shut.load(Ordering::Acquire)
+                    && conn.tx.read_shut.load(Ordering::Acquire)
+            }),
+            Kind::Datagram { .. } => false,
+        }
+    }
+}
+
+/// Private methods
+impl UnixSocket {
+    /// Calls `f` until it returns anything other than `Err(EAGAIN)`, or only
+    /// once in nonblocking mode.
+    ///
+    /// `f` registers the given waker on what it waits for before checking it,
+    /// and the task sleeps until the waker is woken, or a signal is sent to
+    /// it.
+    fn block_on<F, T>(&self, mut f: F) -> LinuxResult<T>
+    where
+        F: FnMut(&Waker) -> LinuxResult<T>,
+    {
+        let waiter = FileWaiter::new();
+        let waker = Waker::from(waiter.clone());
+        loop {
+            waiter.reset();
+            #[cfg(feature = "signal")]
+            super::signal::register_interrupt_waker(&waker);
+            match f(&waker) {
+                Err(LinuxError::EAGAIN) if !self.nonblock.load(Ordering::Acquire) => {
+                    #[cfg(feature = "signal")]
+                    super::signal::check_interrupted(true)?;
+                    waiter.wait(None);
+                }
+                res => return res,
+            }
+        }
+    }
+
+    fn stream_send(&self, conn: &Connection, buf: &[u8]) -> LinuxResult<usize> {
+        let mut sent = 0;
+        while sent < buf.len() {
+            let res = self.block_on(|waker| {
+                conn.tx.wakers.register(waker);
+                if conn.tx.read_shut.load(Ordering::Acquire)
+                    || conn.tx.write_shut.load(Ordering::Acquire)
+                {
+                    return Err(LinuxError::EPIPE);
+                }
+                let mut data = conn.tx.data.lock();
+                let len = (buf.len() - sent).min(STREAM_BUF_LEN - data.len());
+                if len == 0 {
+                    return Err(LinuxError::EAGAIN);
+                }
+                data.extend(&buf[sent..sent + len]);
+                drop(data);
+                conn.tx.wakers.wake();
+                Ok(len)
+            });
+            match res {
+                Ok(len) => sent += len,
+                // report the partial write
+                Err(_) if sent > 0 => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(sent)
+    }
+
+    fn dgram_send(&self, inbox: &Weak<Inbox>, buf: &[u8]) -> LinuxResult<usize> {
+        if buf.len() > MAX_DGRAM_LEN {
+            return Err(LinuxError::EMSGSIZE);
+        }
+        let from = self.local_addr();
+        let mut data = Some(buf.to_vec());
+        self.block_on(|waker| {
+            let inbox = inbox.upgrade().ok_or(LinuxError::ECONNREFUSED)?;
+            inbox.wakers.register(waker);
+            let mut queue = inbox.queue.lock();
+            if queue.len() >= DGRAM_QUEUE_LEN {
+                return Err(LinuxError::EAGAIN);
+            }
+            queue.push_back((data.take().unwrap(), from.clone()));
+            drop(queue);
+            inbox.wakers.wake();
+            Ok(buf.len())
+        })
+    }
+}
+
+impl Drop for UnixSocket {
+    fn drop(&mut self) {
+        let addr = self.local_addr.lock();
+        if *addr == UnixAddr::Unnamed {
+            return;
+        }
+        let mut registry = REGISTRY.lock();
+        // the address may have been bound again after the file was removed
+        let owned = match (registry.get(&*addr), &self.kind) {
+            (Some(Endpoint::Stream(b)), Kind::Stream { backlog, .. }) => backlog
+                .lock()
+                .as_ref()
+                .is_some_and(|backlog| b.as_ptr() == Arc::as_ptr(backlog)),
+            (Some(Endpoint::Datagram(i)), Kind::Datagram { inbox, .. }) => {
+                i.as_ptr() == Arc::as_ptr(inbox)
+            }
+            _ => false,
+        };
+        if owned {
+            registry.remove(&*addr);
+        }
+    }
+}
+
+impl Endpoint {
+    fn is_alive(&self) -> bool {
+        match self {
+            Self::Stream(backlog) => backlog.strong_count() > 0,
+            Self::Datagram(inbox) => inbox.strong_count() > 0,
+        }
+    }
+}
+
+/// Finds the socket bound to `addr`.
+fn lookup(addr: &UnixAddr) -> LinuxResult<Endpoint> {
+    #[cfg(feature = "fs")]
+    if let UnixAddr::Path(path) = addr {
+        if !axfs::api::absolute_path_exists(path) {
+            return Err(LinuxError::ENOENT);
+        }
+    }
+    match REGISTRY.lock().get(addr) {
+        Some(Endpoint::Stream(backlog)) if backlog.strong_count() > 0 => {
+            Ok(Endpoint::Stream(backlog.clone()))
+        }
+        Some(Endpoint::Datagram(inbox)) if inbox.strong_count() > 0 => {
+            Ok(Endpoint::Datagram(inbox.clone()))
+        }
+        _ => match addr {
+            UnixAddr::Unnamed => Err(LinuxError::EINVAL),
+            #[cfg(not(feature = "fs"))]
+            UnixAddr::Path(_) => Err(LinuxError::ENOENT),
+            _ => Err(LinuxError::ECONNREFUSED),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(s: &str) -> UnixAddr {
+        UnixAddr::Abstract(s.as_bytes().to_vec())
+    }
+
+    fn recv(socket: &UnixSocket) -> LinuxResult<Vec<u8>> {
+        let mut buf = [0; 64];
+        let (len, _) = socket.recv_from(&mut buf)?;
+        Ok(buf[..len].to_vec())
+    }
+
+    #[test]
+    fn test_stream_pair() {
+        let (a, b) = UnixSocket::pair(true);
+        assert_eq!(a.socktype(), ctypes::SOCK_STREAM);
+        assert_eq!(a.send(b"hello"), Ok(5));
+        assert_eq!(b.send(b"world"), Ok(5));
+        assert!(b.poll().unwrap().readable);
+        assert_eq!(recv(&b).unwrap(), b"hello");
+        assert_eq!(recv(&a).unwrap(), b"world");
+        assert_eq!(a.peer_addr(), Ok(UnixAddr::Unnamed));
+
+        // nothing to read
+        b.set_nonblocking(true);
+        assert!(!b.poll().unwrap().readable);
+        assert_eq!(recv(&b), Err(LinuxError::EAGAIN));
+
+        // EOF once the peer is closed, and no more writes
+        drop(a);
+        assert!(b.peer_closed());
+        assert!(b.poll().unwrap().readable);
+        assert_eq!(recv(&b).unwrap(), b"");
+        assert_eq!(b.send(b"x"), Err(LinuxError::EPIPE));
+    }
+
+    #[test]
+    fn test_stream_shutdown() {
+        let (a, b) = UnixSocket::pair(true);
+        assert_eq!(a.send(b"bye"), Ok(3));
+        a.shutdown().unwrap();
+        // the data sent before is still received
+        assert_eq!(recv(&b).unwrap(), b"bye");
+        assert_eq!(recv(&b).unwrap(), b"");
+        assert_eq!(a.send(b"x"), Err(LinuxError::EPIPE));
+    }
+
+    #[test]
+    fn test_stream_full() {
+        let (a, b) = UnixSocket::pair(true);
+        a.set_nonblocking(true);
+        let buf = vec![0xaa; STREAM_BUF_LEN + 1];
+        // partial write
+        assert_eq!(a.send(&buf), Ok(STREAM_BUF_LEN));
+        assert!(!a.poll().unwrap().writable);
+        assert_eq!(a.send(&buf), Err(LinuxError::EAGAIN));
+        assert_eq!(recv(&b).unwrap(), [0xaa; 64]);
+        assert!(a.poll().unwrap().writable);
+    }
+
+    #[test]
+    fn test_dgram_pair() {
+        let (a, b) = UnixSocket::pair(false);
+        assert_eq!(a.socktype(), ctypes::SOCK_DGRAM);
+        assert_eq!(a.send(b"one"), Ok(3));
+        assert_eq!(a.send(b"two"), Ok(3));
+        // message boundaries are kept
+        assert_eq!(recv(&b).unwrap(), b"one");
+        assert_eq!(recv(&b).unwrap(), b"two");
+
+        // datagrams are truncated
+        assert_eq!(b.send(b"truncated"), Ok(9));
+        let mut buf = [0; 5];
+        assert_eq!(a.recv_from(&mut buf), Ok((5, Some(UnixAddr::Unnamed))));
+        assert_eq!(&buf, b"trunc");
+
+        a.set_nonblocking(true);
+        for _ in 0..DGRAM_QUEUE_LEN {
+            a.send(b"x").unwrap();
+        }
+        assert_eq!(a.send(b"x"), Err(LinuxError::EAGAIN));
+
+        drop(b);
+        assert_eq!(a.send(b"x"), Err(LinuxError::ECONNREFUSED));
+    }
+
+    #[test]
+    fn test_stream_connect() {
+        let server = UnixSocket::new_stream();
+        server.bind(name("test_stream_connect")).unwrap();
+        let client = UnixSocket::new_stream();
+        // bound but not listening
+        assert_eq!(
+            client.connect(name("test_stream_connect")),
+            Err(LinuxError::ECONNREFUSED)
+        );
+        server.listen().unwrap();
+        server.set_nonblocking(true);
+        assert!(!server.poll().unwrap().readable);
+        assert_eq!(server.accept().err(), Some(LinuxError::EAGAIN));
+
+        client.connect(name("test_stream_connect")).unwrap();
+        assert_eq!(
+            client.connect(name("test_stream_connect")),
+            Err(LinuxError::EISCONN)
+        );
+        assert!(server.poll().unwrap().readable);
+        let conn = server.accept().unwrap();
+        assert_eq!(conn.local_addr(), name("test_stream_connect"));
+        assert_eq!(conn.peer_addr(), Ok(UnixAddr::Unnamed));
+        assert_eq!(client.peer_addr(), Ok(name("test_stream_connect")));
+
+        assert_eq!(client.send(b"ping"), Ok(4));
+        assert_eq!(recv(&conn).unwrap(), b"ping");
+        assert_eq!(conn.send(b"pong"), Ok(4));
+        assert_eq!(recv(&client).unwrap(), b"pong");
+
+        // the name is released once the server is closed
+        drop(server);
+        let client = UnixSocket::new_stream();
+        assert_eq!(
+            client.connect(name("test_stream_connect")),
+            Err(LinuxError::ECONNREFUSED)
+        );
+    }
+
+    #[test]
+    fn test_dgram_send_to() {
+        let a = UnixSocket::new_datagram();
+        let b = UnixSocket::new_datagram();
+        a.bind(name("test_dgram_send_to_a")).unwrap();
+        b.bind(name("test_dgram_send_to_b")).unwrap();
+        assert_eq!(a.send_to(b"hi", name("test_dgram_send_to_b")), Ok(2));
+        let mut buf = [0; 8];
+        assert_eq!(
+            b.recv_from(&mut buf),
+            Ok((2, Some(name("test_dgram_send_to_a"))))
+        );
+
+        // a stream socket is not a datagram one
+        let stream = UnixSocket::new_stream();
+        stream.bind(name("test_dgram_send_to_s")).unwrap();
+        assert_eq!(
+            a.send_to(b"hi", name("test_dgram_send_to_s")),
+            Err(LinuxError::EPROTOTYPE)
+        );
+        assert_eq!(
+            a.send_to(b"hi", name("test_dgram_send_to_none")),
+            Err(LinuxError::ECONNREFUSED)
+        );
+    }
+
+    #[test]
+    fn test_bind() {
+        let a = UnixSocket::new_stream();
+        a.bind(name("test_bind")).unwrap();
+        assert_eq!(a.local_addr(), name("test_bind"));
+        // already bound
+        assert_eq!(a.bind(name("test_bind2")), Err(LinuxError::EINVAL));
+
+        let b = UnixSocket::new_datagram();
+        assert_eq!(b.bind(name("test_bind")), Err(LinuxError::EADDRINUSE));
+        drop(a);
+        b.bind(name("test_bind")).unwrap();
+    }
+
+    #[cfg(feature = "multitask")]
+    #[test]
+    fn test_blocking_wakeup() {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(axtask::init_scheduler);
+
+        let (a, b) = UnixSocket::pair(true);
+        let (a, b) = (Arc::new(a), Arc::new(b));
+        let b2 = b.clone();
+        axtask::spawn(move || {
+            // blocks until the main task reads
+            let buf = vec![0; STREAM_BUF_LEN + 16];
+            assert_eq!(b2.send(&buf), Ok(buf.len()));
+            assert_eq!(b2.send(b"done"), Ok(4));
+        });
+        let mut buf = vec![0; STREAM_BUF_LEN + 20];
+        let mut len = 0;
+        while len < buf.len() {
+            len += a.recv_from(&mut buf[len..]).unwrap().0;
+        }
+        assert_eq!(&buf[STREAM_BUF_LEN + 16..], b"done");
+
+        // blocks until the peer is closed
+        let (c, d) = UnixSocket::pair(true);
+        axtask::spawn(move || {
+            axtask::yield_now();
+            drop(d);
+        });
+        assert_eq!(recv(&c).unwrap(), b"");
+    }
+}
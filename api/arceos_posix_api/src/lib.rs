@@ -45,24 +45,74 @@ pub use imp::fd_ops::{
 };
 #[cfg(feature = "fs")]
 pub use imp::fs::{
-    Directory, File, sys_fstat, sys_getcwd, sys_lseek, sys_lstat, sys_open, sys_openat, sys_rename,
-    sys_stat,
+    Directory, File, sys_chmod, sys_chown, sys_fchmod, sys_fchown, sys_fdatasync, sys_fstat,
+    sys_fsync, sys_getcwd, sys_link, sys_lseek, sys_lstat, sys_mount, sys_open, sys_openat,
+    sys_readlink, sys_rename, sys_stat, sys_symlink, sys_sync, sys_umount2, sys_unlink,
+    sys_utimensat,
 };
 #[cfg(feature = "select")]
 pub use imp::io_mpx::sys_select;
 #[cfg(feature = "epoll")]
 pub use imp::io_mpx::{sys_epoll_create, sys_epoll_ctl, sys_epoll_wait};
-#[cfg(feature = "net")]
+#[cfg(feature = "poll")]
+pub use imp::io_mpx::{sys_poll, sys_ppoll};
+#[cfg(feature = "mmap")]
+pub use imp::mmap::{sys_mmap, sys_mprotect, sys_mremap, sys_msync, sys_munmap};
+#[cfg(feature = "unix")]
 pub use imp::net::{
-    sys_accept, sys_bind, sys_connect, sys_freeaddrinfo, sys_getaddrinfo, sys_getpeername,
-    sys_getsockname, sys_listen, sys_recv, sys_recvfrom, sys_send, sys_sendto, sys_shutdown,
-    sys_socket,
+    sys_accept, sys_bind, sys_connect, sys_getpeername, sys_getsockname, sys_getsockopt,
+    sys_listen, sys_recv, sys_recvfrom, sys_send, sys_sendto, sys_setsockopt, sys_shutdown,
+    sys_socket, sys_socketpair,
 };
+#[cfg(feature = "net")]
+pub use imp::net::{sys_freeaddrinfo, sys_getaddrinfo};
 #[cfg(feature = "pipe")]
 pub use imp::pipe::sys_pipe;
 #[cfg(feature = "multitask")]
+pub use imp::pthread::barrier::{
+    sys_pthread_barrier_destroy, sys_pthread_barrier_init, sys_pthread_barrier_wait,
+};
+#[cfg(all(feature = "multitask", feature = "irq"))]
+pub use imp::pthread::cond::sys_pthread_cond_timedwait;
+#[cfg(feature = "multitask")]
+pub use imp::pthread::cond::{
+    sys_pthread_cond_broadcast, sys_pthread_cond_destroy, sys_pthread_cond_init,
+    sys_pthread_cond_signal, sys_pthread_cond_wait,
+};
+#[cfg(feature = "multitask")]
+pub use imp::pthread::key::{
+    sys_pthread_getspecific, sys_pthread_key_create, sys_pthread_key_delete,
+    sys_pthread_setspecific,
+};
+#[cfg(feature = "multitask")]
 pub use imp::pthread::mutex::{
     sys_pthread_mutex_init, sys_pthread_mutex_lock, sys_pthread_mutex_unlock,
 };
 #[cfg(feature = "multitask")]
+pub use imp::pthread::once::sys_pthread_once;
+#[cfg(feature = "multitask")]
+pub use imp::pthread::rwlock::{
+    sys_pthread_rwlock_destroy, sys_pthread_rwlock_init, sys_pthread_rwlock_rdlock,
+    sys_pthread_rwlock_tryrdlock, sys_pthread_rwlock_trywrlock, sys_pthread_rwlock_unlock,
+    sys_pthread_rwlock_wrlock,
+};
+#[cfg(all(feature = "multitask", feature = "irq"))]
+pub use imp::pthread::rwlock::{sys_pthread_rwlock_timedrdlock, sys_pthread_rwlock_timedwrlock};
+#[cfg(all(feature = "multitask", feature = "irq"))]
+pub use imp::pthread::sem::sys_sem_timedwait;
+#[cfg(feature = "multitask")]
+pub use imp::pthread::sem::{
+    sys_sem_destroy, sys_sem_getvalue, sys_sem_init, sys_sem_post, sys_sem_trywait, sys_sem_wait,
+};
+#[cfg(feature = "multitask")]
 pub use imp::pthread::{sys_pthread_create, sys_pthread_exit, sys_pthread_join, sys_pthread_self};
+#[cfg(feature = "signal")]
+pub use imp::signal::timer::{
+    sys_alarm, sys_getitimer, sys_setitimer, sys_timer_create, sys_timer_delete,
+    sys_timer_getoverrun, sys_timer_gettime, sys_timer_settime,
+};
+#[cfg(feature = "signal")]
+pub use imp::signal::{
+    sys_kill, sys_pause, sys_pthread_kill, sys_raise, sys_sigaction, sys_sigpending,
+    sys_sigprocmask, sys_sigsuspend,
+};
@@ -34,6 +34,9 @@ pub fn check_null_mut_ptr<T>(ptr: *mut T) -> LinuxResult {
 
 macro_rules! syscall_body {
     ($fn: ident, $($stmt: tt)*) => {{
+        // pending signals are delivered as the outermost call returns
+        #[cfg(feature = "signal")]
+        let _guard = $crate::imp::signal::CallGuard::enter();
         #[allow(clippy::redundant_closure_call)]
         let res = (|| -> axerrno::LinuxResult<_> { $($stmt)* })();
         match res {
@@ -51,6 +54,8 @@ macro_rules! syscall_body {
 
 macro_rules! syscall_body_no_debug {
     ($($stmt: tt)*) => {{
+        #[cfg(feature = "signal")]
+        let _guard = $crate::imp::signal::CallGuard::enter();
         #[allow(clippy::redundant_closure_call)]
         let res = (|| -> axerrno::LinuxResult<_> { $($stmt)* })();
         match res {
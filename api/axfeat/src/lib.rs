@@ -22,7 +22,9 @@
 //! - Upperlayer stacks (fs, net, display)
 //!     - `fs`: Enable file system support.
 //!     - `myfs`: Allow users to define their custom filesystems to override the default.
+//!     - `page-cache`: Cache the pages of opened files.
 //!     - `net`: Enable networking support.
+//!     - `dhcp`: Configure the network interfaces by DHCPv4.
 //!     - `display`: Enable graphics support.
 //! - Device drivers
 //!     - `bus-mmio`: Use device tree to probe all MMIO devices.
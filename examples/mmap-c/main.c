@@ -0,0 +1,143 @@
+#include <assert.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <unistd.h>
+
+#define PAGE_SIZE 4096
+#define FILE_PATH "/mmap-test.txt"
+
+static void write_file(const char *data)
+{
+    int fd = open(FILE_PATH, O_WRONLY | O_CREAT | O_TRUNC, 0644);
+    assert(fd >= 0);
+    assert(write(fd, data, strlen(data)) == strlen(data));
+    close(fd);
+}
+
+static void check_file(const char *data)
+{
+    char buf[64] = {0};
+    int fd = open(FILE_PATH, O_RDONLY);
+    assert(fd >= 0);
+    assert(read(fd, buf, sizeof(buf)) == strlen(data));
+    assert(strcmp(buf, data) == 0);
+    close(fd);
+}
+
+static void test_anonymous(void)
+{
+    char *p = mmap(NULL, 3 * PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+    assert(p != MAP_FAILED);
+    for (int i = 0; i < 3 * PAGE_SIZE; i++) assert(p[i] == 0);
+    strcpy(p + PAGE_SIZE, "anonymous");
+
+    // made read-only and writable again, keeping the content
+    assert(mprotect(p, 3 * PAGE_SIZE, PROT_READ) == 0);
+    assert(mprotect(p, 3 * PAGE_SIZE, PROT_READ | PROT_WRITE) == 0);
+    assert(strcmp(p + PAGE_SIZE, "anonymous") == 0);
+    assert(mprotect(p + 1, PAGE_SIZE, PROT_READ) == -1 && errno == EINVAL);
+
+    // grown and moved with the content
+    char *q = mremap(p, 3 * PAGE_SIZE, 64 * PAGE_SIZE, MREMAP_MAYMOVE);
+    assert(q != MAP_FAILED);
+    assert(strcmp(q + PAGE_SIZE, "anonymous") == 0);
+    q[64 * PAGE_SIZE - 1] = 1;
+    assert(munmap(q, 64 * PAGE_SIZE) == 0);
+    assert(mmap(NULL, 0, PROT_READ, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0) == MAP_FAILED);
+    assert(errno == EINVAL);
+    puts("test_anonymous OK");
+}
+
+static void test_private_file(void)
+{
+    write_file("hello, private");
+    int fd = open(FILE_PATH, O_RDONLY);
+    assert(fd >= 0);
+    char *p = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE, fd, 0);
+    assert(p != MAP_FAILED);
+    close(fd);
+
+    // the pages are read on access, even after `mprotect`
+    assert(mprotect(p, PAGE_SIZE, PROT_READ) == 0);
+    assert(strcmp(p, "hello, private") == 0);
+    assert(p[PAGE_SIZE - 1] == 0);
+    assert(mprotect(p, PAGE_SIZE, PROT_READ | PROT_WRITE) == 0);
+
+    // the changes are not written back
+    memcpy(p, "HELLO", 5);
+    assert(msync(p, PAGE_SIZE, MS_SYNC) == 0);
+    assert(munmap(p, PAGE_SIZE) == 0);
+    check_file("hello, private");
+    puts("test_private_file OK");
+}
+
+static void test_shared_file(void)
+{
+    write_file("hello, shared");
+    int fd = open(FILE_PATH, O_RDWR);
+    assert(fd >= 0);
+    char *p = mmap(NULL, 2 * PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0);
+    assert(p != MAP_FAILED);
+    assert(strcmp(p, "hello, shared") == 0);
+
+    // written back by `msync`, except beyond the end of file
+    memcpy(p, "HELLO", 5);
+    p[PAGE_SIZE] = 'x';
+    assert(msync(p, 2 * PAGE_SIZE, MS_SYNC) == 0);
+    check_file("HELLO, shared");
+
+    // reads and writes of the file from and to its own mapping
+    assert(lseek(fd, 7, SEEK_SET) == 7);
+    assert(write(fd, p, 5) == 5);
+    assert(lseek(fd, 0, SEEK_SET) == 0);
+    assert(read(fd, p + 7, 6) == 6);
+    assert(strcmp(p, "HELLO, HELLO,") == 0);
+    assert(msync(p, PAGE_SIZE, MS_SYNC) == 0);
+    check_file("HELLO, HELLO,");
+
+    // written back by `munmap`
+    memcpy(p, "world", 5);
+    assert(munmap(p, 2 * PAGE_SIZE) == 0);
+    close(fd);
+    check_file("world, HELLO,");
+    puts("test_shared_file OK");
+}
+
+static void test_access(void)
+{
+    write_file("read-only");
+    int fd = open(FILE_PATH, O_RDONLY);
+    assert(fd >= 0);
+
+    // a writable shared mapping needs the file opened for writing
+    assert(mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_SHARED, fd, 0) == MAP_FAILED);
+    assert(errno == EACCES);
+    char *p = mmap(NULL, PAGE_SIZE, PROT_READ, MAP_SHARED, fd, 0);
+    assert(p != MAP_FAILED);
+    assert(mprotect(p, PAGE_SIZE, PROT_READ | PROT_WRITE) == -1 && errno == EACCES);
+    assert(strcmp(p, "read-only") == 0);
+    assert(munmap(p, PAGE_SIZE) == 0);
+
+    // but a private one does not
+    p = mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE, MAP_PRIVATE, fd, 0);
+    assert(p != MAP_FAILED);
+    assert(munmap(p, PAGE_SIZE) == 0);
+    close(fd);
+    assert(mmap(NULL, PAGE_SIZE, PROT_READ, MAP_SHARED, fd, 0) == MAP_FAILED);
+    assert(errno == EBADF);
+    unlink(FILE_PATH);
+    puts("test_access OK");
+}
+
+int main()
+{
+    test_anonymous();
+    test_private_file();
+    test_shared_file();
+    test_access();
+    puts("All tests passed!");
+    return 0;
+}
@@ -2,6 +2,8 @@ use std::fs::{self, File, FileType};
 use std::io::{self, prelude::*};
 use std::{string::String, vec::Vec};
 
+use chrono::{Datelike, Timelike};
+
 #[cfg(all(not(feature = "axstd"), unix))]
 use std::os::unix::fs::{FileTypeExt, PermissionsExt};
 
@@ -26,6 +28,8 @@ const CMD_TABLE: &[(&str, CmdHandler)] = &[
     ("help", do_help),
     ("ls", do_ls),
     ("mkdir", do_mkdir),
+    #[cfg(feature = "net")]
+    ("ping", crate::ping::do_ping),
     ("pwd", do_pwd),
     ("rm", do_rm),
     ("uname", do_uname),
@@ -68,6 +72,31 @@ const fn file_perm_to_rwx(mode: u32) -> [u8; 9] {
     perm
 }
 
+/// Formats the seconds since the UNIX epoch as `YYYY-MM-DD HH:MM` in UTC.
+fn format_time(secs: u64) -> String {
+    let Some(utc) = chrono::DateTime::from_timestamp(secs as i64, 0) else {
+        return std::format!("{secs}");
+    };
+    std::format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        utc.year(),
+        utc.month(),
+        utc.day(),
+        utc.hour(),
+        utc.minute()
+    )
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    #[cfg(feature = "axstd")]
+    let modified = metadata.modified();
+    #[cfg(not(feature = "axstd"))]
+    let modified = metadata
+        .modified()
+        .map(|t| t.duration_since(std::time::UNIX_EPOCH).unwrap_or_default());
+    modified.map_or(0, |d| d.as_secs())
+}
+
 fn do_ls(args: &str) {
     let current_dir = std::env::current_dir().unwrap();
     let args = if args.is_empty() {
@@ -84,7 +113,8 @@ fn do_ls(args: &str) {
         let file_type_char = file_type_to_char(file_type);
         let rwx = file_perm_to_rwx(metadata.permissions().mode());
         let rwx = unsafe { core::str::from_utf8_unchecked(&rwx) };
-        println!("{}{} {:>8} {}", file_type_char, rwx, size, entry);
+        let mtime = format_time(modified_secs(&metadata));
+        println!("{}{} {:>8} {} {}", file_type_char, rwx, size, mtime, entry);
         Ok(())
     }
 
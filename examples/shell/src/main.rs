@@ -20,6 +20,9 @@ mod cmd;
 #[cfg(feature = "use-ramfs")]
 mod ramfs;
 
+#[cfg(feature = "net")]
+mod ping;
+
 use std::io::prelude::*;
 
 const LF: u8 = b'\n';
@@ -0,0 +1,111 @@
+use std::io;
+use std::net::IpAddr;
+use std::os::arceos::api::net as api;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const DEFAULT_COUNT: u16 = 4;
+const ECHO_HEADER_LEN: usize = 8;
+const PAYLOAD_LEN: usize = 56;
+const INTERVAL: Duration = Duration::from_secs(1);
+
+fn resolve(host: &str) -> Option<IpAddr> {
+    match host.parse() {
+        Ok(addr) => Some(addr),
+        Err(_) => api::ax_dns_query(host).ok()?.first().copied(),
+    }
+}
+
+fn ping(host: &str, addr: IpAddr, count: u16) -> io::Result<()> {
+    let (request_type, reply_type) = if addr.is_ipv6() { (128, 129) } else { (8, 0) };
+    let socket = api::ax_icmp_socket(addr.is_ipv6());
+    let mut options = api::ax_icmp_options(&socket);
+    options.recv_timeout = Some(INTERVAL);
+    api::ax_icmp_set_options(&socket, options)?;
+
+    println!("PING {} ({}): {} data bytes", host, addr, PAYLOAD_LEN);
+    let mut request = [0; ECHO_HEADER_LEN + PAYLOAD_LEN];
+    let mut reply = [0; ECHO_HEADER_LEN + PAYLOAD_LEN];
+    request[0] = request_type;
+    for (i, byte) in request[ECHO_HEADER_LEN..].iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    let mut received = 0;
+    for seq in 0..count {
+        // the identifier and the checksum are filled by the stack
+        request[6..8].copy_from_slice(&seq.to_be_bytes());
+        let start = Instant::now();
+        api::ax_icmp_send_to(&socket, &request, addr)?;
+        loop {
+            match api::ax_icmp_recv_from(&socket, &mut reply) {
+                Ok((len, from))
+                    if len >= ECHO_HEADER_LEN
+                        && reply[0] == reply_type
+                        && reply[6..8] == seq.to_be_bytes() =>
+                {
+                    let rtt = start.elapsed().as_secs_f64() * 1000.0;
+                    println!(
+                        "{} bytes from {}: icmp_seq={} time={:.3} ms",
+                        len, from, seq, rtt
+                    );
+                    received += 1;
+                    break;
+                }
+                // our own request on the loopback interface, or a late reply
+                Ok(_) if start.elapsed() < INTERVAL => {}
+                Ok(_) | Err(io::Error::WouldBlock) => {
+                    println!("Request timeout for icmp_seq={}", seq);
+                    break;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        if seq + 1 < count {
+            thread::sleep(INTERVAL.saturating_sub(start.elapsed()));
+        }
+    }
+
+    println!("--- {} ping statistics ---", host);
+    println!(
+        "{} packets transmitted, {} received, {}% packet loss",
+        count,
+        received,
+        (count - received) as u32 * 100 / count as u32
+    );
+    Ok(())
+}
+
+pub fn do_ping(args: &str) {
+    let mut count = DEFAULT_COUNT;
+    let mut host = None;
+    let mut words = args.split_whitespace();
+    while let Some(word) = words.next() {
+        match word {
+            "-c" => match words.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => count = n,
+                _ => {
+                    println!("ping: invalid count");
+                    return;
+                }
+            },
+            _ if host.is_none() => host = Some(word),
+            _ => {
+                println!("ping: too many arguments");
+                return;
+            }
+        }
+    }
+
+    let Some(host) = host else {
+        println!("usage: ping [-c count] host");
+        return;
+    };
+    let Some(addr) = resolve(host) else {
+        println!("ping: {}: cannot resolve host", host);
+        return;
+    };
+    if let Err(e) = ping(host, addr, count) {
+        println!("ping: {}: {}", host, e);
+    }
+}
@@ -0,0 +1,280 @@
+#include <assert.h>
+#include <errno.h>
+#include <poll.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/time.h>
+#include <time.h>
+#include <unistd.h>
+
+static volatile int caught[_NSIG];
+static volatile int last_code;
+static volatile int last_value;
+static pid_t main_pid;
+
+static void handler(int sig)
+{
+    caught[sig]++;
+}
+
+static void info_handler(int sig, siginfo_t *info, void *ucontext)
+{
+    (void)ucontext;
+    caught[sig]++;
+    last_code = info->si_code;
+    last_value = info->si_value.sival_int;
+}
+
+static void set_handler(int sig, void (*h)(int), int flags)
+{
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_handler = h;
+    sa.sa_flags = flags;
+    sigemptyset(&sa.sa_mask);
+    assert(sigaction(sig, &sa, NULL) == 0);
+}
+
+static void set_info_handler(int sig)
+{
+    struct sigaction sa;
+    memset(&sa, 0, sizeof(sa));
+    sa.sa_sigaction = info_handler;
+    sa.sa_flags = SA_SIGINFO;
+    sigemptyset(&sa.sa_mask);
+    assert(sigaction(sig, &sa, NULL) == 0);
+}
+
+static void block(int how, int sig)
+{
+    sigset_t set;
+    sigemptyset(&set);
+    sigaddset(&set, sig);
+    assert(sigprocmask(how, &set, NULL) == 0);
+}
+
+static void set_timer(long usec)
+{
+    struct itimerval it = {{0, 0}, {usec / 1000000, usec % 1000000}};
+    assert(setitimer(ITIMER_REAL, &it, NULL) == 0);
+}
+
+static void test_raise(void)
+{
+    set_handler(SIGUSR1, handler, 0);
+    assert(raise(SIGUSR1) == 0);
+    assert(caught[SIGUSR1] == 1);
+
+    set_info_handler(SIGUSR2);
+    assert(raise(SIGUSR2) == 0);
+    assert(caught[SIGUSR2] == 1 && last_code == SI_TKILL);
+    assert(kill(getpid(), SIGUSR2) == 0);
+    assert(caught[SIGUSR2] == 2 && last_code == SI_USER);
+
+    // ignored signals are discarded
+    set_handler(SIGUSR1, SIG_IGN, 0);
+    assert(raise(SIGUSR1) == 0);
+    assert(caught[SIGUSR1] == 1);
+    puts("test_raise OK");
+}
+
+static void test_mask(void)
+{
+    set_handler(SIGUSR1, handler, 0);
+    caught[SIGUSR1] = 0;
+
+    // blocked signals stay pending until unblocked
+    block(SIG_BLOCK, SIGUSR1);
+    assert(raise(SIGUSR1) == 0);
+    assert(caught[SIGUSR1] == 0);
+    sigset_t pending;
+    assert(sigpending(&pending) == 0);
+    assert(sigismember(&pending, SIGUSR1));
+    block(SIG_UNBLOCK, SIGUSR1);
+    assert(caught[SIGUSR1] == 1);
+    assert(sigpending(&pending) == 0);
+    assert(!sigismember(&pending, SIGUSR1));
+    puts("test_mask OK");
+}
+
+static void test_kill(void)
+{
+    set_handler(SIGUSR1, handler, 0);
+    caught[SIGUSR1] = 0;
+    pid_t pid = main_pid = getpid();
+    assert(kill(pid, SIGUSR1) == 0);
+    assert(kill(0, SIGUSR1) == 0);
+    assert(kill(-pid, SIGUSR1) == 0);
+    assert(caught[SIGUSR1] == 3);
+
+    // there are no other processes
+    assert(kill(pid + 1, SIGUSR1) == -1 && errno == ESRCH);
+    assert(kill(pid, 0) == 0);
+    assert(kill(pid, _NSIG) == -1 && errno == EINVAL);
+    assert(caught[SIGUSR1] == 3);
+    puts("test_kill OK");
+}
+
+static void *pause_thread(void *arg)
+{
+    (void)arg;
+    // all threads are in the same process
+    assert(getpid() == main_pid);
+    assert(pause() == -1 && errno == EINTR);
+    assert(caught[SIGUSR2] == 1);
+    return NULL;
+}
+
+static void test_pthread_kill(void)
+{
+    set_handler(SIGUSR2, handler, 0);
+    caught[SIGUSR2] = 0;
+    pthread_t t;
+    assert(pthread_create(&t, NULL, pause_thread, NULL) == 0);
+    usleep(100000);
+    assert(pthread_kill(t, SIGUSR2) == 0);
+    assert(pthread_join(t, NULL) == 0);
+    assert(caught[SIGUSR2] == 1);
+    puts("test_pthread_kill OK");
+}
+
+static void test_timers(void)
+{
+    set_handler(SIGALRM, handler, 0);
+    caught[SIGALRM] = 0;
+
+    // `alarm` interrupts `pause`
+    assert(alarm(1) == 0);
+    assert(pause() == -1 && errno == EINTR);
+    assert(caught[SIGALRM] == 1);
+
+    // `setitimer` interrupts `nanosleep`, which reports the remaining time
+    set_timer(100000);
+    struct timespec req = {2, 0}, rem = {0, 0};
+    assert(nanosleep(&req, &rem) == -1 && errno == EINTR);
+    assert(caught[SIGALRM] == 2);
+    assert(rem.tv_sec >= 1 && rem.tv_sec < 2);
+
+    // POSIX timers carry their value
+    set_info_handler(SIGUSR1);
+    caught[SIGUSR1] = 0;
+    struct sigevent sev;
+    memset(&sev, 0, sizeof(sev));
+    sev.sigev_notify = SIGEV_SIGNAL;
+    sev.sigev_signo = SIGUSR1;
+    sev.sigev_value.sival_int = 42;
+    timer_t timer;
+    assert(timer_create(CLOCK_MONOTONIC, &sev, &timer) == 0);
+    struct itimerspec its = {{0, 0}, {0, 50000000}};
+    assert(timer_settime(timer, 0, &its, NULL) == 0);
+    assert(pause() == -1 && errno == EINTR);
+    assert(caught[SIGUSR1] == 1 && last_code == SI_TIMER && last_value == 42);
+    assert(timer_delete(timer) == 0);
+    puts("test_timers OK");
+}
+
+static void test_sigpipe(void)
+{
+    set_handler(SIGPIPE, handler, 0);
+    caught[SIGPIPE] = 0;
+    int fds[2];
+    assert(pipe(fds) == 0);
+    close(fds[0]);
+    assert(write(fds[1], "x", 1) == -1 && errno == EPIPE);
+    assert(caught[SIGPIPE] == 1);
+    close(fds[1]);
+    puts("test_sigpipe OK");
+}
+
+static void test_temporary_mask(void)
+{
+    set_handler(SIGALRM, handler, 0);
+    set_handler(SIGUSR1, handler, 0);
+    caught[SIGALRM] = 0;
+    caught[SIGUSR1] = 0;
+    sigset_t empty;
+    sigemptyset(&empty);
+
+    // `sigsuspend` unblocks the signal until it arrives
+    block(SIG_BLOCK, SIGALRM);
+    set_timer(50000);
+    assert(sigsuspend(&empty) == -1 && errno == EINTR);
+    assert(caught[SIGALRM] == 1);
+    block(SIG_UNBLOCK, SIGALRM);
+
+    // so does `ppoll`, for the pending signal
+    int fds[2];
+    assert(pipe(fds) == 0);
+    struct pollfd pfd = {fds[0], POLLIN, 0};
+    struct timespec timeout = {0, 10000000};
+    block(SIG_BLOCK, SIGUSR1);
+    assert(raise(SIGUSR1) == 0);
+    assert(ppoll(&pfd, 1, &timeout, NULL) == 0);
+    timeout.tv_sec = 1;
+    assert(caught[SIGUSR1] == 0);
+    assert(ppoll(&pfd, 1, &timeout, &empty) == -1 && errno == EINTR);
+    assert(caught[SIGUSR1] == 1);
+
+    // and restores the old mask
+    assert(raise(SIGUSR1) == 0);
+    assert(caught[SIGUSR1] == 1);
+    block(SIG_UNBLOCK, SIGUSR1);
+    assert(caught[SIGUSR1] == 2);
+    close(fds[0]);
+    close(fds[1]);
+    puts("test_temporary_mask OK");
+}
+
+static int restart_fds[2];
+
+static void *read_thread(void *arg)
+{
+    char c;
+    ssize_t n = read(restart_fds[0], &c, 1);
+    if (arg) {
+        assert(n == 1 && c == 'x');
+    } else {
+        assert(n == -1 && errno == EINTR);
+    }
+    return NULL;
+}
+
+static void check_restart(int flags)
+{
+    set_handler(SIGUSR2, handler, flags);
+    caught[SIGUSR2] = 0;
+    assert(pipe(restart_fds) == 0);
+    pthread_t t;
+    assert(pthread_create(&t, NULL, read_thread, (void *)(long)(flags & SA_RESTART)) == 0);
+    usleep(100000);
+    assert(pthread_kill(t, SIGUSR2) == 0);
+    usleep(100000);
+    assert(caught[SIGUSR2] == 1);
+    assert(write(restart_fds[1], "x", 1) == 1);
+    assert(pthread_join(t, NULL) == 0);
+    close(restart_fds[0]);
+    close(restart_fds[1]);
+}
+
+static void test_restart(void)
+{
+    check_restart(0);
+    check_restart(SA_RESTART);
+    puts("test_restart OK");
+}
+
+int main()
+{
+    test_raise();
+    test_mask();
+    test_kill();
+    test_pthread_kill();
+    test_timers();
+    test_sigpipe();
+    test_temporary_mask();
+    test_restart();
+    puts("All tests passed!");
+    return 0;
+}
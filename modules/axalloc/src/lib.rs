@@ -21,6 +21,19 @@ use kspin::SpinNoIrq;
 const PAGE_SIZE: usize = 0x1000;
 const MIN_HEAP_SIZE: usize = 0x8000; // 32 K
 
+cfg_if::cfg_if! {
+    if #[cfg(feature = "page-alloc-64g")] {
+        /// The most memory that the page allocator can manage.
+        const MAX_PAGE_ALLOC_SIZE: usize = 64 << 30;
+    } else if #[cfg(feature = "page-alloc-4g")] {
+        /// The most memory that the page allocator can manage.
+        const MAX_PAGE_ALLOC_SIZE: usize = 4 << 30;
+    } else {
+        /// The most memory that the page allocator can manage.
+        const MAX_PAGE_ALLOC_SIZE: usize = 256 << 20;
+    }
+}
+
 pub use page::GlobalPage;
 
 cfg_if::cfg_if! {
@@ -79,7 +92,19 @@ impl GlobalAllocator {
     /// It firstly adds the whole region to the page allocator, then allocates
     /// a small region (32 KB) to initialize the byte allocator. Therefore,
     /// the given region must be larger than 32 KB.
+    ///
+    /// The part of the region beyond the capacity of the page allocator is
+    /// not used, see the `page-alloc-*` features.
     pub fn init(&self, start_vaddr: usize, size: usize) {
+        // the page allocator counts its pages from the 1 GB boundary below
+        let capacity = MAX_PAGE_ALLOC_SIZE.saturating_sub(start_vaddr % (1 << 30));
+        if size > capacity {
+            warn!(
+                "only {:#x} of {:#x} bytes of memory can be managed",
+                capacity, size
+            );
+        }
+        let size = size.min(capacity);
         assert!(size > MIN_HEAP_SIZE);
         let init_heap_size = MIN_HEAP_SIZE;
         self.palloc.lock().init(start_vaddr, size);
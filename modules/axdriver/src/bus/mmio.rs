@@ -1,11 +1,12 @@
 #[allow(unused_imports)]
-use crate::{AllDevices, prelude::*};
+use crate::{AllDevices, DeviceIrq, prelude::*};
 
 impl AllDevices {
     pub(crate) fn probe_bus_devices(&mut self) {
-        // TODO: parse device tree
+        // VirtIO MMIO slots come from the device tree if one was passed at boot,
+        // or from the platform configuration otherwise.
         #[cfg(feature = "virtio")]
-        for reg in axconfig::devices::VIRTIO_MMIO_REGIONS {
+        for reg in axhal::dtb::virtio_mmio_regions() {
             for_each_drivers!(type Driver, {
                 if let Some(dev) = Driver::probe_mmio(reg.0, reg.1) {
                     info!(
@@ -14,7 +15,10 @@ impl AllDevices {
                         reg.0, reg.0 + reg.1,
                         dev.device_name(),
                     );
-                    self.add_device(dev);
+                    let regs_vaddr = axhal::mem::phys_to_virt(reg.0.into()).as_usize();
+                    let irq = axhal::dtb::virtio_mmio_irq(reg.0)
+                        .map(|irq_num| DeviceIrq::virtio_mmio(irq_num, regs_vaddr));
+                    self.add_device(dev, irq);
                     continue; // skip to the next device
                 }
             });
@@ -4,8 +4,80 @@ use axdriver_pci::{
 };
 use axhal::mem::phys_to_virt;
 
+#[cfg(feature = "irq")]
+use crate::DeviceIrq;
+#[cfg(feature = "irq")]
+use axdriver_pci::DeviceFunctionInfo;
+#[cfg(feature = "irq")]
+use axhal::mem::VirtAddr;
+
 const PCI_BAR_NUM: u8 = 6;
 
+/// Returns the interrupt line of a device, if its INTx interrupt is routed
+/// and the device can be told that the interrupt has been handled.
+///
+/// Only VirtIO devices are supported, which have a standard ISR status
+/// register. MSI and MSI-X are not used.
+#[cfg(feature = "irq")]
+fn device_irq(
+    root: &mut PciRoot,
+    ecam_vaddr: VirtAddr,
+    bdf: DeviceFunction,
+    dev_info: &DeviceFunctionInfo,
+) -> Option<DeviceIrq> {
+    const VIRTIO_VENDOR_ID: u16 = 0x1af4;
+    const STATUS_CAP_LIST: u32 = 1 << 20;
+    const CAP_ID_VENDOR: u32 = 0x09;
+    const VIRTIO_PCI_CAP_ISR_CFG: u32 = 3;
+
+    if dev_info.vendor_id != VIRTIO_VENDOR_ID {
+        return None;
+    }
+    let read = |offset: usize| {
+        let func_base = ((bdf.bus as usize) << 20)
+            | ((bdf.device as usize) << 15)
+            | ((bdf.function as usize) << 12);
+        // SAFETY: the configuration space of the device is in the ECAM.
+        unsafe { ((ecam_vaddr.as_usize() + func_base + offset) as *const u32).read_volatile() }
+    };
+
+    // Interrupt Pin and Interrupt Line
+    let intx = read(0x3c);
+    let irq_num = axhal::irq::pci_intx_irq(
+        bdf.bus,
+        bdf.device,
+        bdf.function,
+        (intx >> 8) as u8,
+        intx as u8,
+    )?;
+
+    // find the ISR status register in the VirtIO vendor capabilities
+    if read(0x04) & STATUS_CAP_LIST == 0 {
+        return None;
+    }
+    let mut cap = read(0x34) as usize & 0xfc;
+    // at most 48 capabilities fit in the configuration space
+    for _ in 0..48 {
+        if cap == 0 {
+            break;
+        }
+        let header = read(cap);
+        if header & 0xff == CAP_ID_VENDOR && header >> 24 == VIRTIO_PCI_CAP_ISR_CFG {
+            let bar = read(cap + 4) as u8;
+            let offset = read(cap + 8) as u64;
+            return match root.bar_info(bdf, bar).ok()? {
+                BarInfo::Memory { address, .. } if address != 0 => {
+                    let isr_vaddr = phys_to_virt(((address + offset) as usize).into()).as_usize();
+                    Some(DeviceIrq::virtio_pci(irq_num, isr_vaddr))
+                }
+                _ => None,
+            };
+        }
+        cap = (header >> 8) as usize & 0xfc;
+    }
+    None
+}
+
 fn config_pci_device(
     root: &mut PciRoot,
     bdf: DeviceFunction,
@@ -107,7 +179,11 @@ impl AllDevices {
                                 bdf,
                                 dev.device_name(),
                             );
-                            self.add_device(dev);
+                            #[cfg(feature = "irq")]
+                            let irq = device_irq(&mut root, base_vaddr, bdf, &dev_info);
+                            #[cfg(not(feature = "irq"))]
+                            let irq = None;
+                            self.add_device(dev, irq);
                             continue; // skip to the next device
                         }
                     }),
@@ -0,0 +1,80 @@
+//! Interrupt lines of devices.
+
+/// How a device is told that its interrupt has been handled.
+#[derive(Debug, Clone, Copy)]
+enum IrqAck {
+    /// The interrupt status register of a VirtIO MMIO device is written back
+    /// to its interrupt acknowledge register.
+    VirtIoMmio { regs_vaddr: usize },
+    /// The ISR status register of a VirtIO PCI device is read, which clears
+    /// it.
+    VirtIoPci { isr_vaddr: usize },
+}
+
+/// The interrupt line of a device.
+#[derive(Debug, Clone, Copy)]
+pub struct DeviceIrq {
+    /// The IRQ number, to be passed to `axhal::irq::register_handler`.
+    pub irq_num: usize,
+    ack: IrqAck,
+}
+
+impl DeviceIrq {
+    /// The interrupt line of a VirtIO MMIO device, whose registers are mapped
+    /// at `regs_vaddr`.
+    #[allow(dead_code)]
+    pub(crate) const fn virtio_mmio(irq_num: usize, regs_vaddr: usize) -> Self {
+        Self {
+            irq_num,
+            ack: IrqAck::VirtIoMmio { regs_vaddr },
+        }
+    }
+
+    /// The INTx interrupt line of a VirtIO PCI device, whose ISR status
+    /// register is mapped at `isr_vaddr`.
+    #[allow(dead_code)]
+    pub(crate) const fn virtio_pci(irq_num: usize, isr_vaddr: usize) -> Self {
+        Self {
+            irq_num,
+            ack: IrqAck::VirtIoPci { isr_vaddr },
+        }
+    }
+
+    /// Acknowledges the interrupt on the device side, so that the device
+    /// deasserts the interrupt line.
+    ///
+    /// It only touches device registers, and can be called in IRQ handlers.
+    pub fn ack(&self) {
+        match self.ack {
+            IrqAck::VirtIoMmio { regs_vaddr } => {
+                const INTERRUPT_STATUS: usize = 0x60;
+                const INTERRUPT_ACK: usize = 0x64;
+                // SAFETY: the registers are mapped when the device is probed.
+                unsafe {
+                    let status = ((regs_vaddr + INTERRUPT_STATUS) as *const u32).read_volatile();
+                    ((regs_vaddr + INTERRUPT_ACK) as *mut u32).write_volatile(status);
+                }
+            }
+            IrqAck::VirtIoPci { isr_vaddr } => {
+                // SAFETY: the BAR is mapped when the device is probed.
+                unsafe { (isr_vaddr as *const u8).read_volatile() };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtio_mmio_ack() {
+        let mut regs = [0u32; 0x80 / 4];
+        regs[0x60 / 4] = 0b11;
+        let irq = DeviceIrq::virtio_mmio(8, regs.as_mut_ptr() as usize);
+        irq.ack();
+        // the pending interrupts are written back to be acknowledged
+        assert_eq!(regs[0x64 / 4], 0b11);
+        assert_eq!(irq.irq_num, 8);
+    }
+}
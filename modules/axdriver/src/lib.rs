@@ -48,6 +48,9 @@
 //!    features, a dummy struct is used for [`AxNetDevice`].
 //! - `block`: use block storage devices. Similar to the `net` feature.
 //! - `display`: use graphics display devices. Similar to the `net` feature.
+//! - `irq`: report the INTx interrupt lines of VirtIO PCI devices, as routed
+//!   by `axhal::irq::pci_intx_irq`. The lines of VirtIO MMIO devices are
+//!   always reported if the device tree describes them.
 //!
 //! [`VirtioNetDev`]: axdriver_virtio::VirtIoNetDev
 //! [`Box<dyn NetDriverOps>`]: axdriver_net::NetDriverOps
@@ -70,6 +73,7 @@ mod macros;
 mod bus;
 mod drivers;
 mod dummy;
+mod irq;
 mod structs;
 
 #[cfg(feature = "virtio")]
@@ -80,6 +84,7 @@ mod ixgbe;
 
 pub mod prelude;
 
+pub use self::irq::DeviceIrq;
 #[allow(unused_imports)]
 use self::prelude::*;
 pub use self::structs::{AxDeviceContainer, AxDeviceEnum};
@@ -126,7 +131,7 @@ impl AllDevices {
                     dev.device_type(),
                     dev.device_name(),
                 );
-                self.add_device(dev);
+                self.add_device(dev, None);
             }
         });
 
@@ -134,15 +139,17 @@ impl AllDevices {
     }
 
     /// Adds one device into the corresponding container, according to its device category.
+    ///
+    /// `irq` is the interrupt line of the device, if it is known.
     #[allow(dead_code)]
-    fn add_device(&mut self, dev: AxDeviceEnum) {
+    fn add_device(&mut self, dev: AxDeviceEnum, irq: Option<DeviceIrq>) {
         match dev {
             #[cfg(feature = "net")]
-            AxDeviceEnum::Net(dev) => self.net.push(dev),
+            AxDeviceEnum::Net(dev) => self.net.push(dev, irq),
             #[cfg(feature = "block")]
-            AxDeviceEnum::Block(dev) => self.block.push(dev),
+            AxDeviceEnum::Block(dev) => self.block.push(dev, irq),
             #[cfg(feature = "display")]
-            AxDeviceEnum::Display(dev) => self.display.push(dev),
+            AxDeviceEnum::Display(dev) => self.display.push(dev, irq),
         }
     }
 }
@@ -1,6 +1,6 @@
 #![allow(unused_imports)]
 
-use crate::prelude::*;
+use crate::{DeviceIrq, prelude::*};
 use alloc::{boxed::Box, vec, vec::Vec};
 
 /// The unified type of the NIC devices.
@@ -37,7 +37,7 @@ impl super::AxDeviceEnum {
 ///
 /// If the feature `dyn` is enabled, the inner type is [`Vec<D>`]. Otherwise,
 /// the inner type is [`Option<D>`] and at most one device can be contained.
-pub struct AxDeviceContainer<D>(Vec<D>);
+pub struct AxDeviceContainer<D>(Vec<D>, Vec<Option<DeviceIrq>>);
 
 impl<D> AxDeviceContainer<D> {
     /// Returns number of devices in this container.
@@ -52,22 +52,29 @@ impl<D> AxDeviceContainer<D> {
 
     /// Takes one device out of the container (will remove it from the container).
     pub fn take_one(&mut self) -> Option<D> {
+        self.take_one_with_irq().map(|(dev, _)| dev)
+    }
+
+    /// Takes one device out of the container, along with its interrupt line
+    /// if it is known.
+    pub fn take_one_with_irq(&mut self) -> Option<(D, Option<DeviceIrq>)> {
         if self.is_empty() {
             None
         } else {
-            Some(self.0.remove(0))
+            Some((self.0.remove(0), self.1.remove(0)))
         }
     }
 
     /// Constructs the container from one device.
     pub fn from_one(dev: D) -> Self {
-        Self(vec![dev])
+        Self(vec![dev], vec![None])
     }
 
     /// Adds one device into the container.
     #[allow(dead_code)]
-    pub(crate) fn push(&mut self, dev: D) {
+    pub(crate) fn push(&mut self, dev: D, irq: Option<DeviceIrq>) {
         self.0.push(dev);
+        self.1.push(irq);
     }
 }
 
@@ -80,6 +87,6 @@ impl<D> core::ops::Deref for AxDeviceContainer<D> {
 
 impl<D> Default for AxDeviceContainer<D> {
     fn default() -> Self {
-        Self(Default::default())
+        Self(Vec::new(), Vec::new())
     }
 }
@@ -1,3 +1,5 @@
+use crate::DeviceIrq;
+
 #[cfg(feature = "block")]
 pub use crate::drivers::AxBlockDevice;
 #[cfg(feature = "display")]
@@ -29,7 +31,7 @@ impl super::AxDeviceEnum {
 ///
 /// If the feature `dyn` is enabled, the inner type is [`Vec<D>`]. Otherwise,
 /// the inner type is [`Option<D>`] and at most one device can be contained.
-pub struct AxDeviceContainer<D>(Option<D>);
+pub struct AxDeviceContainer<D>(Option<D>, Option<DeviceIrq>);
 
 impl<D> AxDeviceContainer<D> {
     /// Returns number of devices in this container.
@@ -44,19 +46,26 @@ impl<D> AxDeviceContainer<D> {
 
     /// Takes one device out of the container (will remove it from the container).
     pub fn take_one(&mut self) -> Option<D> {
-        self.0.take()
+        self.take_one_with_irq().map(|(dev, _)| dev)
+    }
+
+    /// Takes one device out of the container, along with its interrupt line
+    /// if it is known.
+    pub fn take_one_with_irq(&mut self) -> Option<(D, Option<DeviceIrq>)> {
+        self.0.take().map(|dev| (dev, self.1.take()))
     }
 
     /// Constructs the container from one device.
     pub const fn from_one(dev: D) -> Self {
-        Self(Some(dev))
+        Self(Some(dev), None)
     }
 
     /// Adds one device into the container.
     #[allow(dead_code)]
-    pub(crate) fn push(&mut self, dev: D) {
+    pub(crate) fn push(&mut self, dev: D, irq: Option<DeviceIrq>) {
         if self.0.is_none() {
             self.0 = Some(dev);
+            self.1 = irq;
         }
     }
 }
@@ -70,6 +79,6 @@ impl<D> core::ops::Deref for AxDeviceContainer<D> {
 
 impl<D> Default for AxDeviceContainer<D> {
     fn default() -> Self {
-        Self(Default::default())
+        Self(None, None)
     }
 }
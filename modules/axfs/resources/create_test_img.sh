@@ -26,5 +26,20 @@ create_test_img() {
 	sudo umount mnt
 }
 
+# An ext4 volume with a hard link and a symbolic link, made without mounting.
+create_ext4_img() {
+	local name=$1
+	local blkcount=$2
+	mkdir -p ext4/very/long/path
+	echo "Rust is cool!" >"ext4/short.txt"
+	echo "Rust is cool!" >"ext4/very/long/path/test.txt"
+	ln "ext4/short.txt" "ext4/hard.txt"
+	ln -s "short.txt" "ext4/sym"
+	mkfs.ext4 -q -b 1024 -O ^metadata_csum,^orphan_file -d ext4 "$name" $blkcount
+	rm -rf ext4
+}
+
+create_test_img "$CUR_DIR/fat12.img" 1000 12
 create_test_img "$CUR_DIR/fat16.img" 2500 16
 create_test_img "$CUR_DIR/fat32.img" 34000 32
+create_ext4_img "$CUR_DIR/ext4.img" 2048
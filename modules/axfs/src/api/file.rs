@@ -1,6 +1,7 @@
 use alloc::vec::Vec;
-use axio::{Result, SeekFrom, default_read_to_end, prelude::*};
-use core::fmt;
+use axfs_vfs::VfsNodeRef;
+use axio::{Error, Result, SeekFrom, default_read_to_end, prelude::*};
+use core::{fmt, time::Duration};
 
 use crate::fops;
 
@@ -17,7 +18,7 @@ pub struct File {
 }
 
 /// Metadata information about a file.
-pub struct Metadata(pub(super) fops::FileAttr);
+pub struct Metadata(pub(super) fops::FileAttr, pub(super) fops::FileMeta);
 
 /// Options and flags which can be used to configure how a file is opened.
 #[derive(Default, Clone, Debug)]
@@ -67,7 +68,8 @@ impl OpenOptions {
 
     /// Opens a file at `path` with the options specified by `self`.
     pub fn open(&self, path: &str) -> Result<File> {
-        fops::File::open(path, &self.0).map(|inner| File { inner })
+        let inner = fops::File::open(path, &self.0)?;
+        Ok(File { inner })
     }
 }
 
@@ -91,6 +93,12 @@ impl Metadata {
         self.0.is_file()
     }
 
+    /// Returns `true` if this metadata is for a symbolic link, which is only
+    /// possible for metadata from [`symlink_metadata`](super::symlink_metadata).
+    pub const fn is_symlink(&self) -> bool {
+        self.0.file_type().is_symlink()
+    }
+
     /// Returns the size of the file, in bytes, this metadata is for.
     #[allow(clippy::len_without_is_empty)]
     pub const fn len(&self) -> u64 {
@@ -111,6 +119,58 @@ impl Metadata {
     pub const fn blocks(&self) -> u64 {
         self.0.blocks()
     }
+
+    /// Returns the inode number of this file, or 0 if it is unknown.
+    pub const fn ino(&self) -> u64 {
+        self.1.ino
+    }
+
+    /// Returns the number of hard links to this file, or 0 if it is unknown.
+    pub const fn nlink(&self) -> u64 {
+        self.1.nlink
+    }
+
+    /// Returns the user ID of the owner of this file.
+    pub const fn uid(&self) -> u32 {
+        self.1.uid
+    }
+
+    /// Returns the group ID of the owner of this file.
+    pub const fn gid(&self) -> u32 {
+        self.1.gid
+    }
+
+    /// Returns the last access time of this file, since the UNIX epoch.
+    pub const fn accessed(&self) -> Result<Duration> {
+        Ok(self.1.atime)
+    }
+
+    /// Returns the last modification time of this file, since the UNIX
+    /// epoch.
+    pub const fn modified(&self) -> Result<Duration> {
+        Ok(self.1.mtime)
+    }
+
+    /// Returns the creation time of this file, since the UNIX epoch.
+    ///
+    /// It fails with [`Unsupported`](Error::Unsupported) if the filesystem
+    /// does not record it.
+    pub const fn created(&self) -> Result<Duration> {
+        match self.1.crtime {
+            Some(crtime) => Ok(crtime),
+            None => Err(Error::Unsupported),
+        }
+    }
+
+    /// Returns the underlying attributes, ownership and timestamps of the
+    /// file.
+    pub const fn into_parts(self) -> (fops::FileAttr, fops::FileMeta) {
+        (self.0, self.1)
+    }
+
+    pub(super) fn of_node(node: &VfsNodeRef) -> Result<Self> {
+        Ok(Self(node.get_attr()?, crate::fs::node_meta(node)?))
+    }
 }
 
 impl fmt::Debug for Metadata {
@@ -119,7 +179,9 @@ impl fmt::Debug for Metadata {
             .field("file_type", &self.file_type())
             .field("is_dir", &self.is_dir())
             .field("is_file", &self.is_file())
+            .field("is_symlink", &self.is_symlink())
             .field("permissions", &self.permissions())
+            .field("modified", &self.modified())
             .finish_non_exhaustive()
     }
 }
@@ -161,7 +223,18 @@ impl File {
 
     /// Queries metadata about the underlying file.
     pub fn metadata(&self) -> Result<Metadata> {
-        self.inner.get_attr().map(Metadata)
+        Ok(Metadata(self.inner.get_attr()?, self.inner.get_meta()?))
+    }
+
+    /// Changes the permissions of the underlying file.
+    pub fn set_permissions(&self, perm: Permissions) -> Result<()> {
+        self.inner.set_perm(perm)
+    }
+
+    /// Changes the access and modification times of the underlying file,
+    /// which are unchanged if `None`.
+    pub fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> Result<()> {
+        self.inner.set_times(atime, mtime)
     }
 }
 
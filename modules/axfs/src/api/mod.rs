@@ -5,9 +5,13 @@ mod file;
 
 pub use self::dir::{DirBuilder, DirEntry, ReadDir};
 pub use self::file::{File, FileType, Metadata, OpenOptions, Permissions};
+pub use crate::fops::FsStat;
+#[cfg(feature = "fatfs")]
+pub use crate::fs::fatfs::fsck::{FatType, FsckIssue, FsckReport};
 
 use alloc::{string::String, vec::Vec};
 use axio::{self as io, prelude::*};
+use core::time::Duration;
 
 /// Returns an iterator over the entries within a directory.
 pub fn read_dir(path: &str) -> io::Result<ReadDir> {
@@ -56,7 +60,58 @@ pub fn write<C: AsRef<[u8]>>(path: &str, contents: C) -> io::Result<()> {
 /// Given a path, query the file system to get information about a file,
 /// directory, etc.
 pub fn metadata(path: &str) -> io::Result<Metadata> {
-    crate::root::lookup(None, path)?.get_attr().map(Metadata)
+    Metadata::of_node(&crate::root::lookup(None, path)?)
+}
+
+/// Query the metadata about a file without following symbolic links.
+pub fn symlink_metadata(path: &str) -> io::Result<Metadata> {
+    Metadata::of_node(&crate::root::lookup_nofollow(None, path)?)
+}
+
+/// Changes the permissions found on a file or a directory.
+pub fn set_permissions(path: &str, perm: Permissions) -> io::Result<()> {
+    crate::fs::set_perm(&crate::root::lookup(None, path)?, perm)
+}
+
+/// Changes the owner and the group of a file or a directory, which are
+/// unchanged if `None`.
+pub fn chown(path: &str, uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+    crate::fs::set_owner(&crate::root::lookup(None, path)?, uid, gid)
+}
+
+/// Changes the access and modification times of a file or a directory, which
+/// are unchanged if `None`. Times are durations since the UNIX epoch.
+pub fn set_times(path: &str, atime: Option<Duration>, mtime: Option<Duration>) -> io::Result<()> {
+    crate::fs::set_times(&crate::root::lookup(None, path)?, atime, mtime)
+}
+
+/// Changes the access and modification times like [`set_times`], but of the
+/// symbolic link itself if `path` is one.
+pub fn set_symlink_times(
+    path: &str,
+    atime: Option<Duration>,
+    mtime: Option<Duration>,
+) -> io::Result<()> {
+    crate::fs::set_times(&crate::root::lookup_nofollow(None, path)?, atime, mtime)
+}
+
+/// Creates a new symbolic link at `link`, which points to `original`.
+///
+/// `original` is not required to exist, and relative targets are resolved
+/// from the directory containing `link`.
+pub fn symlink(original: &str, link: &str) -> io::Result<()> {
+    crate::root::create_symlink(None, original, link)
+}
+
+/// Creates a new hard link at `link` to the file at `original`, which must be
+/// in the same mounted filesystem.
+pub fn hard_link(original: &str, link: &str) -> io::Result<()> {
+    crate::root::hard_link(original, link)
+}
+
+/// Reads a symbolic link, returning the path that it points to.
+pub fn read_link(path: &str) -> io::Result<String> {
+    Ok(crate::root::read_link(None, path)?)
 }
 
 /// Creates a new, empty directory at the provided path.
@@ -88,6 +143,61 @@ pub fn rename(old: &str, new: &str) -> io::Result<()> {
     crate::root::rename(old, new)
 }
 
+bitflags::bitflags! {
+    /// Flags of [`mount`].
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    pub struct MountFlags: u32 {
+        /// Attaches the directory `source` instead of a new filesystem.
+        const BIND = 1 << 0;
+    }
+}
+
+/// Mounts a new filesystem of type `fstype` on the directory `target`.
+///
+/// Supported types are `devfs`, `ramfs` (or `tmpfs`), `proc` and `sysfs`,
+/// depending on the enabled features, and `source` is ignored for them. With
+/// [`MountFlags::BIND`], the directory `source` is attached instead, and
+/// `fstype` is ignored.
+///
+/// Disk filesystems are opened on the block device `source`, e.g.,
+/// `/dev/vda1`, and their type is detected if `fstype` is empty or `auto`.
+/// With `vfat`, `source` can also be a FAT image file on a FAT volume.
+///
+/// Filesystems can be mounted on directories of other mounted filesystems.
+pub fn mount(source: &str, target: &str, fstype: &str, flags: MountFlags) -> io::Result<()> {
+    crate::root::mount(source, target, fstype, flags)
+}
+
+/// Unmounts the filesystem mounted on `target`.
+///
+/// It fails with [`ResourceBusy`](io::Error::ResourceBusy) if files or
+/// directories are opened in the filesystem, other filesystems are mounted
+/// inside it, or it contains the current directory.
+pub fn umount(target: &str) -> io::Result<()> {
+    crate::root::umount(target)
+}
+
+/// Returns the usage of the filesystem that `path` is in.
+pub fn statfs(path: &str) -> io::Result<FsStat> {
+    crate::root::statfs(path)
+}
+
+/// Checks the consistency of the FAT volume on the block device `device`,
+/// e.g. `/dev/vdb`, without repairing it.
+///
+/// It fails with [`ResourceBusy`](io::Error::ResourceBusy) if a filesystem is
+/// mounted on the device.
+#[cfg(feature = "fatfs")]
+pub fn fsck(device: &str) -> io::Result<FsckReport> {
+    let disk = crate::dev::find_block_device(device)?.open_disk()?;
+    crate::fs::fatfs::fsck::check_disk(disk)
+}
+
+/// Writes back all cached data of the block devices.
+pub fn sync() -> io::Result<()> {
+    crate::cache::sync_all().map_err(|_| io::Error::Io)
+}
+
 /// check whether absolute path exists.
 pub fn absolute_path_exists(path: &str) -> bool {
     crate::root::lookup(None, path).is_ok()
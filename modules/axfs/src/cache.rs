@@ -0,0 +1,210 @@
+//! The block cache, shared by all block devices.
+//!
+//! Single blocks are cached in LRU order, up to [`CACHE_CAPACITY`] blocks in
+//! total. Written blocks are marked dirty, and written back when they are
+//! evicted or flushed. Runs of whole blocks are read and written on the
+//! devices directly, without being cached.
+
+use alloc::{boxed::Box, collections::BTreeMap, vec::Vec};
+
+use axdriver::prelude::*;
+use axsync::{Mutex, MutexGuard};
+use lazyinit::LazyInit;
+
+use crate::dev::BLOCK_SIZE;
+
+/// The maximum number of cached blocks.
+pub(crate) const CACHE_CAPACITY: usize = 1024;
+/// The maximum number of blocks in one request to the devices.
+pub(crate) const MAX_REQUEST_BLOCKS: usize = 64;
+
+static BLOCK_CACHE: LazyInit<Mutex<BlockCache>> = LazyInit::new();
+
+/// The index of a whole device, and a block on it.
+type BlockKey = (usize, u64);
+
+struct CachedBlock {
+    data: Box<[u8; BLOCK_SIZE]>,
+    dirty: bool,
+    /// The key of the block in the LRU list.
+    last_used: u64,
+}
+
+/// The whole block devices, and the cached blocks of them.
+pub(crate) struct BlockCache {
+    devs: Vec<AxBlockDevice>,
+    blocks: BTreeMap<BlockKey, CachedBlock>,
+    /// The cached blocks, from the least recently used.
+    lru: BTreeMap<u64, BlockKey>,
+    clock: u64,
+}
+
+impl BlockCache {
+    /// Reads the part of block `block_id` from `offset` into `buf`.
+    pub fn read(&mut self, dev: usize, block_id: u64, offset: usize, buf: &mut [u8]) -> DevResult {
+        let block = self.get(dev, block_id)?;
+        buf.copy_from_slice(&block.data[offset..offset + buf.len()]);
+        Ok(())
+    }
+
+    /// Writes `buf` to the part of block `block_id` from `offset`.
+    pub fn write(&mut self, dev: usize, block_id: u64, offset: usize, buf: &[u8]) -> DevResult {
+        let block = if offset == 0 && buf.len() == BLOCK_SIZE {
+            // no need to read the old data
+            self.get_or_insert_with(dev, block_id, |_| Ok([0; BLOCK_SIZE]))?
+        } else {
+            self.get(dev, block_id)?
+        };
+        block.data[offset..offset + buf.len()].copy_from_slice(buf);
+        block.dirty = true;
+        Ok(())
+    }
+
+    /// Reads whole blocks from block `block_id` into `buf`, at most
+    /// [`MAX_REQUEST_BLOCKS`] blocks.
+    pub fn read_blocks(&mut self, dev: usize, block_id: u64, buf: &mut [u8]) -> DevResult {
+        if buf.len() == BLOCK_SIZE {
+            return self.read(dev, block_id, 0, buf);
+        }
+        self.devs[dev].read_block(block_id, buf)?;
+        // cached blocks may be newer
+        let end = block_id + (buf.len() / BLOCK_SIZE) as u64;
+        for (&(_, id), block) in self.blocks.range((dev, block_id)..(dev, end)) {
+            if block.dirty {
+                let offset = (id - block_id) as usize * BLOCK_SIZE;
+                buf[offset..offset + BLOCK_SIZE].copy_from_slice(block.data.as_slice());
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes whole blocks from `buf` to block `block_id`, at most
+    /// [`MAX_REQUEST_BLOCKS`] blocks.
+    pub fn write_blocks(&mut self, dev: usize, block_id: u64, buf: &[u8]) -> DevResult {
+        if buf.len() == BLOCK_SIZE {
+            return self.write(dev, block_id, 0, buf);
+        }
+        self.devs[dev].write_block(block_id, buf)?;
+        // keep cached blocks up to date
+        let end = block_id + (buf.len() / BLOCK_SIZE) as u64;
+        for (&(_, id), block) in self.blocks.range_mut((dev, block_id)..(dev, end)) {
+            let offset = (id - block_id) as usize * BLOCK_SIZE;
+            block
+                .data
+                .copy_from_slice(&buf[offset..offset + BLOCK_SIZE]);
+            block.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Writes back the dirty blocks of the whole device `dev`, or of all
+    /// devices if it is `None`, and flushes the devices.
+    pub fn flush(&mut self, dev: Option<usize>) -> DevResult {
+        let (start, end) = match dev {
+            Some(dev) => ((dev, 0), (dev, u64::MAX)),
+            None => ((0, 0), (usize::MAX, u64::MAX)),
+        };
+        let dirty = self
+            .blocks
+            .range(start..=end)
+            .filter(|(_, block)| block.dirty)
+            .map(|(&key, _)| key)
+            .collect::<Vec<_>>();
+        // merge consecutive blocks into one request
+        let runs = dirty.chunk_by(|a, b| a.0 == b.0 && a.1 + 1 == b.1);
+        for run in runs.flat_map(|run| run.chunks(MAX_REQUEST_BLOCKS)) {
+            let mut buf = Vec::with_capacity(run.len() * BLOCK_SIZE);
+            for key in run {
+                buf.extend_from_slice(self.blocks[key].data.as_slice());
+            }
+            let (dev, block_id) = run[0];
+            self.devs[dev].write_block(block_id, &buf)?;
+            for key in run {
+                self.blocks.get_mut(key).unwrap().dirty = false;
+            }
+        }
+
+        match dev {
+            Some(dev) => self.devs[dev].flush(),
+            None => self.devs.iter_mut().try_for_each(|dev| dev.flush()),
+        }
+    }
+
+    /// Returns the cached block, reading it from the device if it is not
+    /// cached.
+    fn get(&mut self, dev: usize, block_id: u64) -> DevResult<&mut CachedBlock> {
+        self.get_or_insert_with(dev, block_id, |this| {
+            let mut data = [0; BLOCK_SIZE];
+            this.devs[dev].read_block(block_id, &mut data)?;
+            Ok(data)
+        })
+    }
+
+    fn get_or_insert_with<F>(
+        &mut self,
+        dev: usize,
+        block_id: u64,
+        f: F,
+    ) -> DevResult<&mut CachedBlock>
+    where
+        F: FnOnce(&mut Self) -> DevResult<[u8; BLOCK_SIZE]>,
+    {
+        let key = (dev, block_id);
+        self.clock += 1;
+        let clock = self.clock;
+        if let Some(block) = self.blocks.get_mut(&key) {
+            self.lru.remove(&block.last_used);
+            self.lru.insert(clock, key);
+            block.last_used = clock;
+        } else {
+            let data = f(self)?;
+            if self.blocks.len() >= CACHE_CAPACITY {
+                self.evict()?;
+            }
+            self.lru.insert(clock, key);
+            self.blocks.insert(key, CachedBlock {
+                data: Box::new(data),
+                dirty: false,
+                last_used: clock,
+            });
+        }
+        Ok(self.blocks.get_mut(&key).unwrap())
+    }
+
+    /// Evicts the least recently used block, writing it back if it is dirty.
+    fn evict(&mut self) -> DevResult {
+        let Some((_, key)) = self.lru.pop_first() else {
+            return Ok(());
+        };
+        let block = self.blocks.remove(&key).unwrap();
+        if block.dirty {
+            if let Err(e) = self.devs[key.0].write_block(key.1, block.data.as_slice()) {
+                // keep it, so that it is not lost
+                self.lru.insert(block.last_used, key);
+                self.blocks.insert(key, block);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Takes the whole block devices into the cache.
+pub(crate) fn init(devs: Vec<AxBlockDevice>) {
+    BLOCK_CACHE.init_once(Mutex::new(BlockCache {
+        devs,
+        blocks: BTreeMap::new(),
+        lru: BTreeMap::new(),
+        clock: 0,
+    }));
+}
+
+/// Locks the block cache.
+pub(crate) fn lock() -> MutexGuard<'static, BlockCache> {
+    BLOCK_CACHE.lock()
+}
+
+/// Writes back the dirty blocks of all devices, and flushes the devices.
+pub(crate) fn sync_all() -> DevResult {
+    lock().flush(None)
+}
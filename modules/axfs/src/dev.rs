@@ -1,28 +1,268 @@
+use alloc::{format, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+
 use axdriver::prelude::*;
+use axerrno::{AxError, AxResult, ax_err, ax_err_type};
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+use lazyinit::LazyInit;
+
+use crate::cache::{self, MAX_REQUEST_BLOCKS};
+use crate::partition;
+
+pub(crate) const BLOCK_SIZE: usize = 512;
+
+static BLOCK_DEVICES: LazyInit<Vec<Arc<BlockDevice>>> = LazyInit::new();
+
+/// A registered block device, shared by the filesystem on it and its device
+/// file in `/dev`.
+///
+/// It is either a whole device, or a partition of it. All accesses go through
+/// the [block cache](cache).
+pub(crate) struct BlockDevice {
+    name: &'static str,
+    /// The index of the whole device in the block cache.
+    dev: usize,
+    /// The first block in the whole device.
+    start: u64,
+    num_blocks: u64,
+    /// Whether a filesystem is mounted on the device.
+    in_use: AtomicBool,
+}
+
+impl BlockDevice {
+    fn new(name: &'static str, dev: usize, start: u64, num_blocks: u64) -> Self {
+        Self {
+            name,
+            dev,
+            start,
+            num_blocks,
+            in_use: AtomicBool::new(false),
+        }
+    }
+
+    /// The name of the device file, e.g. `vda`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get the size of the device.
+    pub fn size(&self) -> u64 {
+        self.num_blocks * BLOCK_SIZE as u64
+    }
 
-const BLOCK_SIZE: usize = 512;
+    /// The first block of the partition in the whole device, or `None` if
+    /// this is a whole device.
+    pub fn partition_start(&self) -> Option<u64> {
+        // partitions never start at the first block, where the table is
+        (self.start > 0).then_some(self.start)
+    }
+
+    /// Whether this device and `other` are on the same whole device, and
+    /// share some blocks.
+    fn overlaps(&self, other: &Self) -> bool {
+        self.dev == other.dev
+            && self.start < other.start + other.num_blocks
+            && other.start < self.start + self.num_blocks
+    }
+
+    /// Opens the device for a filesystem to be mounted on it.
+    ///
+    /// Returns [`ResourceBusy`](axerrno::AxError::ResourceBusy) if a
+    /// filesystem is already on it, or on a partition overlapping it. The
+    /// device is released when the returned disk is dropped.
+    pub fn open_disk(self: &Arc<Self>) -> AxResult<Disk> {
+        // also serializes the opening
+        let _cache = cache::lock();
+        if block_devices()
+            .iter()
+            .any(|dev| dev.overlaps(self) && dev.in_use.load(Ordering::Acquire))
+        {
+            return ax_err!(ResourceBusy, "block device is in use");
+        }
+        self.in_use.store(true, Ordering::Release);
+        Ok(Disk {
+            block_id: 0,
+            offset: 0,
+            dev: self.clone(),
+        })
+    }
+
+    /// Read the whole block `block_id`.
+    pub fn read_block(&self, block_id: u64, buf: &mut [u8; BLOCK_SIZE]) -> DevResult {
+        if block_id >= self.num_blocks {
+            return Err(DevError::InvalidParam);
+        }
+        cache::lock().read(self.dev, self.start + block_id, 0, buf)
+    }
+
+    /// Read from the byte `pos`, returns the number of bytes read.
+    ///
+    /// Runs of whole blocks are read by multi-block requests.
+    pub fn read_bytes(&self, pos: u64, buf: &mut [u8]) -> DevResult<usize> {
+        let len = buf.len().min(self.size().saturating_sub(pos) as usize);
+        let mut cache = cache::lock();
+        let mut done = 0;
+        while done < len {
+            let block_id = self.start + (pos + done as u64) / BLOCK_SIZE as u64;
+            let offset = ((pos + done as u64) % BLOCK_SIZE as u64) as usize;
+            let rest = &mut buf[done..len];
+            let count = if offset == 0 && rest.len() >= BLOCK_SIZE {
+                // whole blocks
+                let count = (rest.len() / BLOCK_SIZE).min(MAX_REQUEST_BLOCKS) * BLOCK_SIZE;
+                cache.read_blocks(self.dev, block_id, &mut rest[..count])?;
+                count
+            } else {
+                // partial block
+                let count = rest.len().min(BLOCK_SIZE - offset);
+                cache.read(self.dev, block_id, offset, &mut rest[..count])?;
+                count
+            };
+            done += count;
+        }
+        Ok(len)
+    }
+
+    /// Write to the byte `pos`, returns the number of bytes written.
+    ///
+    /// Runs of whole blocks are written by multi-block requests.
+    pub fn write_bytes(&self, pos: u64, buf: &[u8]) -> DevResult<usize> {
+        let len = buf.len().min(self.size().saturating_sub(pos) as usize);
+        let mut cache = cache::lock();
+        let mut done = 0;
+        while done < len {
+            let block_id = self.start + (pos + done as u64) / BLOCK_SIZE as u64;
+            let offset = ((pos + done as u64) % BLOCK_SIZE as u64) as usize;
+            let rest = &buf[done..len];
+            let count = if offset == 0 && rest.len() >= BLOCK_SIZE {
+                // whole blocks
+                let count = (rest.len() / BLOCK_SIZE).min(MAX_REQUEST_BLOCKS) * BLOCK_SIZE;
+                cache.write_blocks(self.dev, block_id, &rest[..count])?;
+                count
+            } else {
+                // partial block
+                let count = rest.len().min(BLOCK_SIZE - offset);
+                cache.write(self.dev, block_id, offset, &rest[..count])?;
+                count
+            };
+            done += count;
+        }
+        Ok(len)
+    }
+
+    /// Writes back the cached blocks of the whole device.
+    fn flush(&self) -> DevResult {
+        cache::lock().flush(Some(self.dev))
+    }
+}
+
+/// The device file of a block device.
+impl VfsNodeOps for BlockDevice {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o660),
+            VfsNodeType::BlockDevice,
+            self.size(),
+            self.num_blocks,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        self.read_bytes(offset, buf).map_err(|_| AxError::Io)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.write_bytes(offset, buf).map_err(|_| AxError::Io)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        self.flush().map_err(|_| AxError::Io)
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
+
+/// Returns the name of the `index`-th block device: `vda` to `vdz`, then
+/// `vdaa` to `vdzz`, and so on, as Linux names its disks.
+fn disk_name(index: usize) -> alloc::string::String {
+    let mut suffix = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        n -= 1;
+        suffix.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    suffix.reverse();
+    format!("vd{}", core::str::from_utf8(&suffix).unwrap())
+}
+
+/// Registers the block devices, named `vda`, `vdb`, ... in order, and their
+/// partitions, named `vda1`, `vda2`, ... by their partition numbers.
+pub(crate) fn init_block_devices(devs: Vec<AxBlockDevice>) {
+    let mut all_devs = Vec::new();
+    for (i, dev) in devs.iter().enumerate() {
+        assert_eq!(BLOCK_SIZE, dev.block_size());
+        // names live as long as the devices, which are never removed
+        let name = disk_name(i);
+        info!("  block device {}: {:?}", name, dev.device_name());
+        let whole = BlockDevice::new(name.leak(), i, 0, dev.num_blocks());
+        all_devs.push(Arc::new(whole));
+    }
+    cache::init(devs);
+
+    let mut parts = Vec::new();
+    for whole in all_devs.iter() {
+        let table = partition::parse(whole.num_blocks, |block_id, buf| {
+            whole.read_block(block_id, buf)
+        });
+        for part in table {
+            let name = format!("{}{}", whole.name, part.number);
+            info!(
+                "    partition {}: start {}, {} blocks",
+                name, part.start, part.num_blocks
+            );
+            let dev = BlockDevice::new(name.leak(), whole.dev, part.start, part.num_blocks);
+            parts.push(Arc::new(dev));
+        }
+    }
+    all_devs.extend(parts);
+    BLOCK_DEVICES.init_once(all_devs);
+}
+
+/// Returns all registered block devices.
+pub(crate) fn block_devices() -> &'static [Arc<BlockDevice>] {
+    BLOCK_DEVICES.as_slice()
+}
+
+/// Finds a block device by its name, with or without the `/dev/` prefix.
+pub(crate) fn find_block_device(name: &str) -> AxResult<Arc<BlockDevice>> {
+    let name = name.strip_prefix("/dev/").unwrap_or(name);
+    block_devices()
+        .iter()
+        .find(|dev| dev.name == name)
+        .cloned()
+        .ok_or_else(|| ax_err_type!(NotFound, "no such block device"))
+}
 
 /// A disk device with a cursor.
 pub struct Disk {
     block_id: u64,
     offset: usize,
-    dev: AxBlockDevice,
+    dev: Arc<BlockDevice>,
 }
 
 impl Disk {
-    /// Create a new disk.
-    pub fn new(dev: AxBlockDevice) -> Self {
-        assert_eq!(BLOCK_SIZE, dev.block_size());
-        Self {
-            block_id: 0,
-            offset: 0,
-            dev,
-        }
+    /// Get the block device of the disk.
+    pub(crate) fn device(&self) -> &Arc<BlockDevice> {
+        &self.dev
     }
 
     /// Get the size of the disk.
     pub fn size(&self) -> u64 {
-        self.dev.num_blocks() * BLOCK_SIZE as u64
+        self.dev.size()
     }
 
     /// Get the position of the cursor.
@@ -36,70 +276,33 @@ impl Disk {
         self.offset = pos as usize % BLOCK_SIZE;
     }
 
-    /// Read within one block, returns the number of bytes read.
-    pub fn read_one(&mut self, buf: &mut [u8]) -> DevResult<usize> {
-        let read_size = if self.offset == 0 && buf.len() >= BLOCK_SIZE {
-            // whole block
-            let mut data = [0u8; BLOCK_SIZE];
-            self.dev.read_block(self.block_id, &mut data)?;
-            buf[0..BLOCK_SIZE].copy_from_slice(&data);
-            // self.dev
-            //     .read_block(self.block_id, &mut buf[0..BLOCK_SIZE])?;
-            self.block_id += 1;
-            BLOCK_SIZE
-        } else {
-            // partial block
-            let mut data = [0u8; BLOCK_SIZE];
-            let start = self.offset;
-            let count = buf.len().min(BLOCK_SIZE - self.offset);
-
-            self.dev.read_block(self.block_id, &mut data)?;
-            buf[..count].copy_from_slice(&data[start..start + count]);
-
-            self.offset += count;
-            if self.offset >= BLOCK_SIZE {
-                self.block_id += 1;
-                self.offset -= BLOCK_SIZE;
-            }
-            count
-        };
+    /// Read from the cursor, returns the number of bytes read.
+    pub fn read_bytes(&mut self, buf: &mut [u8]) -> DevResult<usize> {
+        let pos = self.position();
+        let read_size = self.dev.read_bytes(pos, buf)?;
+        self.set_position(pos + read_size as u64);
         Ok(read_size)
     }
 
-    /// Write within one block, returns the number of bytes written.
-    pub fn write_one(&mut self, buf: &[u8]) -> DevResult<usize> {
-        let write_size = if self.offset == 0 && buf.len() >= BLOCK_SIZE {
-            // whole block
-            self.dev.write_block(self.block_id, &buf[0..BLOCK_SIZE])?;
-            self.block_id += 1;
-            BLOCK_SIZE
-        } else {
-            // partial block
-            let mut data = [0u8; BLOCK_SIZE];
-            let start = self.offset;
-            let count = buf.len().min(BLOCK_SIZE - self.offset);
-
-            self.dev.read_block(self.block_id, &mut data)?;
-            data[start..start + count].copy_from_slice(&buf[..count]);
-            self.dev.write_block(self.block_id, &data)?;
-
-            self.offset += count;
-            if self.offset >= BLOCK_SIZE {
-                self.block_id += 1;
-                self.offset -= BLOCK_SIZE;
-            }
-            count
-        };
+    /// Write from the cursor, returns the number of bytes written.
+    pub fn write_bytes(&mut self, buf: &[u8]) -> DevResult<usize> {
+        let pos = self.position();
+        let write_size = self.dev.write_bytes(pos, buf)?;
+        self.set_position(pos + write_size as u64);
         Ok(write_size)
     }
 
+    /// Write back the cached blocks of the disk.
+    pub fn flush(&mut self) -> DevResult {
+        self.dev.flush()
+    }
+
     /// Read a single block starting from the specified offset.
     #[allow(unused)]
     pub fn read_offset(&mut self, offset: usize) -> [u8; BLOCK_SIZE] {
-        let block_id = offset / BLOCK_SIZE;
         let mut block_data = [0u8; BLOCK_SIZE];
         self.dev
-            .read_block(block_id as u64, &mut block_data)
+            .read_block((offset / BLOCK_SIZE) as u64, &mut block_data)
             .unwrap();
         block_data
     }
@@ -112,8 +315,29 @@ impl Disk {
             "Buffer length must be equal to BLOCK_SIZE"
         );
         assert!(offset % BLOCK_SIZE == 0);
-        let block_id = offset / BLOCK_SIZE;
-        self.dev.write_block(block_id as u64, buf).unwrap();
+        self.dev.write_bytes(offset as u64, buf).unwrap();
         Ok(buf.len())
     }
 }
+
+impl Drop for Disk {
+    fn drop(&mut self) {
+        self.flush().ok();
+        self.dev.in_use.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::disk_name;
+
+    #[test]
+    fn test_disk_name() {
+        assert_eq!(disk_name(0), "vda");
+        assert_eq!(disk_name(25), "vdz");
+        assert_eq!(disk_name(26), "vdaa");
+        assert_eq!(disk_name(27), "vdab");
+        assert_eq!(disk_name(701), "vdzz");
+        assert_eq!(disk_name(702), "vdaaa");
+    }
+}
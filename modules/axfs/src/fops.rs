@@ -1,10 +1,15 @@
 //! Low-level filesystem operations.
 
-use axerrno::{AxError, AxResult, ax_err, ax_err_type};
+use alloc::{format, string::String, sync::Arc};
+use axerrno::{AxError, AxResult, LinuxError, ax_err_type};
 use axfs_vfs::{VfsError, VfsNodeRef};
 use axio::SeekFrom;
 use cap_access::{Cap, WithCap};
-use core::fmt;
+use core::{fmt, ptr::NonNull, time::Duration};
+
+#[cfg(feature = "page-cache")]
+use crate::page_cache::PageCache;
+use crate::root::MountPoint;
 
 #[cfg(feature = "myfs")]
 pub use crate::dev::Disk;
@@ -20,11 +25,102 @@ pub type FileAttr = axfs_vfs::VfsNodeAttr;
 /// Alias of [`axfs_vfs::VfsNodePerm`].
 pub type FilePerm = axfs_vfs::VfsNodePerm;
 
+/// Ownership and timestamps of a file, which are not in [`FileAttr`].
+///
+/// Timestamps are durations since the UNIX epoch. Filesystems that do not
+/// store them, such as devfs, report zeros.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileMeta {
+    /// The inode number, unique in the filesystem, or 0 if it is unknown.
+    pub ino: u64,
+    /// The number of hard links, or 0 if it is unknown.
+    pub nlink: u64,
+    /// The user ID of the owner.
+    pub uid: u32,
+    /// The group ID of the owner.
+    pub gid: u32,
+    /// The time of the last access.
+    pub atime: Duration,
+    /// The time of the last modification of the content.
+    pub mtime: Duration,
+    /// The time of the last change of the content or the metadata.
+    pub ctime: Duration,
+    /// The time of the creation, if the filesystem records it.
+    pub crtime: Option<Duration>,
+}
+
+/// Usage of a mounted filesystem, as `statfs`.
+///
+/// Filesystems that do not keep track of their space, such as ramfs, report
+/// zero blocks.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FsStat {
+    /// The type of the filesystem, as in `/proc/mounts`.
+    pub fstype: String,
+    /// The size of the allocation units in bytes.
+    pub block_size: u64,
+    /// The total number of blocks.
+    pub blocks: u64,
+    /// The number of free blocks.
+    pub blocks_free: u64,
+    /// The maximum length of file names.
+    pub name_max: u64,
+    /// The volume label, if the filesystem has one.
+    pub label: Option<String>,
+}
+
+/// An error of an operation on a path, which tells apart the failures that
+/// have no [`AxError`] kind.
+///
+/// It converts to [`AxError`] for the callers that do not care, and to
+/// [`LinuxError`] for the POSIX layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// Too many levels of symbolic links were followed, or the last component
+    /// is a symbolic link that must not be followed (`ELOOP`).
+    Loop,
+    /// Any other error.
+    Other(AxError),
+}
+
+/// A specialized [`Result`] type with [`PathError`] as the error type.
+pub type PathResult<T = ()> = Result<T, PathError>;
+
+impl From<AxError> for PathError {
+    fn from(e: AxError) -> Self {
+        Self::Other(e)
+    }
+}
+
+impl From<PathError> for AxError {
+    fn from(e: PathError) -> Self {
+        match e {
+            // `AxError` has no kind for it
+            PathError::Loop => AxError::InvalidInput,
+            PathError::Other(e) => e,
+        }
+    }
+}
+
+impl From<PathError> for LinuxError {
+    fn from(e: PathError) -> Self {
+        match e {
+            PathError::Loop => LinuxError::ELOOP,
+            PathError::Other(e) => e.into(),
+        }
+    }
+}
+
 /// An opened file object, with open permissions and a cursor.
 pub struct File {
     node: WithCap<VfsNodeRef>,
     is_append: bool,
     offset: u64,
+    /// The cached pages, shared by the files opened on the same inode.
+    #[cfg(feature = "page-cache")]
+    cache: Option<Arc<PageCache>>,
+    /// Keeps the mounted filesystem busy.
+    _mount: Option<Arc<MountPoint>>,
 }
 
 /// An opened directory object, with open permissions and a cursor for
@@ -32,6 +128,10 @@ pub struct File {
 pub struct Directory {
     node: WithCap<VfsNodeRef>,
     entry_idx: usize,
+    /// The canonical absolute path, to resolve relative paths.
+    path: String,
+    /// Keeps the mounted filesystem busy.
+    _mount: Option<Arc<MountPoint>>,
 }
 
 /// Options and flags which can be used to configure how a file is opened.
@@ -46,6 +146,7 @@ pub struct OpenOptions {
     create: bool,
     create_new: bool,
     directory: bool,
+    nofollow: bool,
     // system-specific
     _custom_flags: i32,
     _mode: u32,
@@ -64,6 +165,7 @@ impl OpenOptions {
             create: false,
             create_new: false,
             directory: false,
+            nofollow: false,
             // system-specific
             _custom_flags: 0,
             _mode: 0o666,
@@ -101,6 +203,11 @@ impl OpenOptions {
     pub fn directory(&mut self, directory: bool) {
         self.directory = directory;
     }
+    /// Sets the option to fail if the last component of the path is a
+    /// symbolic link, instead of following it.
+    pub fn nofollow(&mut self, nofollow: bool) {
+        self.nofollow = nofollow;
+    }
     /// check whether contains directory.
     pub fn has_directory(&self) -> bool {
         self.directory
@@ -151,24 +258,36 @@ impl File {
         self.node.access_or_err(cap, AxError::PermissionDenied)
     }
 
-    fn _open_at(dir: Option<&VfsNodeRef>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
+    /// Opens a file at `path` relative to `dir`, or to the current directory
+    /// if `dir` is `None`. `abs_path` is the canonical absolute path of it.
+    fn _open_at(
+        dir: Option<&VfsNodeRef>,
+        abs_path: &str,
+        path: &str,
+        opts: &OpenOptions,
+    ) -> PathResult<Self> {
         debug!("open file: {} {:?}", path, opts);
         if !opts.is_valid() {
-            return ax_err!(InvalidInput);
+            return Err(ax_err_type!(InvalidInput).into());
         }
 
-        let node_option = crate::root::lookup(dir, path);
+        let mount = crate::root::mount_point_of(abs_path)?;
+        let node_option = crate::root::lookup_at(dir, path, !opts.nofollow);
+        let mut created = false;
         let node = if opts.create || opts.create_new {
             match node_option {
                 Ok(node) => {
                     // already exists
                     if opts.create_new {
-                        return ax_err!(AlreadyExists);
+                        return Err(ax_err_type!(AlreadyExists).into());
                     }
                     node
                 }
                 // not exists, create new
-                Err(VfsError::NotFound) => crate::root::create_file(dir, path)?,
+                Err(PathError::Other(VfsError::NotFound)) => {
+                    created = true;
+                    crate::root::create_file(dir, path)?
+                }
                 Err(e) => return Err(e),
             }
         } else {
@@ -178,43 +297,85 @@ impl File {
 
         let attr = node.get_attr()?;
         if attr.is_dir() {
-            return ax_err!(IsADirectory);
+            return Err(ax_err_type!(IsADirectory).into());
+        } else if attr.file_type().is_symlink() {
+            // opened with `O_NOFOLLOW`
+            return Err(PathError::Loop);
         }
         let access_cap = opts.into();
         if !perm_to_cap(attr.perm()).contains(access_cap) {
-            return ax_err!(PermissionDenied);
+            return Err(ax_err_type!(PermissionDenied).into());
         }
 
         node.open()?;
         if opts.truncate {
             node.truncate(0)?;
         }
+        #[cfg(feature = "page-cache")]
+        let cache =
+            crate::page_cache::cache_key(abs_path, &node)?.map(|key| PageCache::of(key, created));
+        #[cfg(feature = "page-cache")]
+        if let (true, Some(cache)) = (opts.truncate, &cache) {
+            cache.truncate(0);
+        }
+        #[cfg(not(feature = "page-cache"))]
+        let _ = created;
         Ok(Self {
             node: WithCap::new(node, access_cap),
             is_append: opts.append,
             offset: 0,
+            #[cfg(feature = "page-cache")]
+            cache,
+            _mount: mount,
         })
     }
 
     /// Opens a file at the path relative to the current directory. Returns a
     /// [`File`] object.
-    pub fn open(path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_at(None, path, opts)
+    pub fn open(path: &str, opts: &OpenOptions) -> PathResult<Self> {
+        Self::_open_at(None, &crate::root::absolute_path(path)?, path, opts)
     }
 
     /// Truncates the file to the specified size.
     pub fn truncate(&self, size: u64) -> AxResult {
         self.access_node(Cap::WRITE)?.truncate(size)?;
+        #[cfg(feature = "page-cache")]
+        if let Some(cache) = &self.cache {
+            cache.truncate(size);
+        }
         Ok(())
     }
 
+    fn read_node_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+        let node = self.access_node(Cap::READ)?;
+        #[cfg(feature = "page-cache")]
+        let len = match &self.cache {
+            Some(cache) => cache.read_at(node, offset, buf)?,
+            None => node.read_at(offset, buf)?,
+        };
+        #[cfg(not(feature = "page-cache"))]
+        let len = node.read_at(offset, buf)?;
+        Ok(len)
+    }
+
+    fn write_node_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
+        let node = self.access_node(Cap::WRITE)?;
+        #[cfg(feature = "page-cache")]
+        let len = match &self.cache {
+            Some(cache) => cache.write_at(node, offset, buf)?,
+            None => node.write_at(offset, buf)?,
+        };
+        #[cfg(not(feature = "page-cache"))]
+        let len = node.write_at(offset, buf)?;
+        Ok(len)
+    }
+
     /// Reads the file at the current position. Returns the number of bytes
     /// read.
     ///
     /// After the read, the cursor will be advanced by the number of bytes read.
     pub fn read(&mut self, buf: &mut [u8]) -> AxResult<usize> {
-        let node = self.access_node(Cap::READ)?;
-        let read_len = node.read_at(self.offset, buf)?;
+        let read_len = self.read_node_at(self.offset, buf)?;
         self.offset += read_len as u64;
         Ok(read_len)
     }
@@ -223,9 +384,7 @@ impl File {
     ///
     /// It does not update the file cursor.
     pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
-        let node = self.access_node(Cap::READ)?;
-        let read_len = node.read_at(offset, buf)?;
-        Ok(read_len)
+        self.read_node_at(offset, buf)
     }
 
     /// Writes the file at the current position. Returns the number of bytes
@@ -239,8 +398,7 @@ impl File {
         } else {
             self.offset
         };
-        let node = self.access_node(Cap::WRITE)?;
-        let write_len = node.write_at(offset, buf)?;
+        let write_len = self.write_node_at(offset, buf)?;
         self.offset = offset + write_len as u64;
         Ok(write_len)
     }
@@ -250,15 +408,67 @@ impl File {
     ///
     /// It does not update the file cursor.
     pub fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
+        self.write_node_at(offset, buf)
+    }
+
+    /// Checks that the file is opened for reading, and also for writing if
+    /// `write` is true, e.g., before it is mapped into memory.
+    pub fn check_access(&self, write: bool) -> AxResult {
+        let cap = if write {
+            Cap::READ | Cap::WRITE
+        } else {
+            Cap::READ
+        };
+        self.access_node(cap).map(|_| ())
+    }
+
+    /// Maps the page of the file at `offset`, a multiple of 4 KiB, into
+    /// memory, and returns the address of its 4 KiB of data. Returns `None`
+    /// if the file has no page cache.
+    ///
+    /// The data is shared with the page cache, and stays cached until it is
+    /// unmapped by [`unmap_page`](Self::unmap_page). The changes made through
+    /// it are written to the file by [`sync_page`](Self::sync_page).
+    pub fn map_page(&self, offset: u64) -> AxResult<Option<NonNull<u8>>> {
+        let node = self.access_node(Cap::READ)?;
+        #[cfg(feature = "page-cache")]
+        if let Some(cache) = &self.cache {
+            let data = cache.map_page(node, offset / crate::page_cache::PAGE_SIZE as u64)?;
+            return Ok(Some(data.cast()));
+        }
+        let _ = (node, offset);
+        Ok(None)
+    }
+
+    /// Unmaps the page of the file at `offset` mapped by
+    /// [`map_page`](Self::map_page).
+    pub fn unmap_page(&self, offset: u64) {
+        #[cfg(feature = "page-cache")]
+        if let Some(cache) = &self.cache {
+            cache.unmap_page(offset / crate::page_cache::PAGE_SIZE as u64);
+        }
+        #[cfg(not(feature = "page-cache"))]
+        let _ = offset;
+    }
+
+    /// Writes the page of the file at `offset` mapped by
+    /// [`map_page`](Self::map_page) to the file, except the data after the end
+    /// of file.
+    pub fn sync_page(&self, offset: u64) -> AxResult {
         let node = self.access_node(Cap::WRITE)?;
-        let write_len = node.write_at(offset, buf)?;
-        Ok(write_len)
+        #[cfg(feature = "page-cache")]
+        if let Some(cache) = &self.cache {
+            cache.sync_page(node, offset / crate::page_cache::PAGE_SIZE as u64)?;
+        }
+        let _ = (node, offset);
+        Ok(())
     }
 
     /// Flushes the file, writes all buffered data to the underlying device.
+    ///
+    /// The cached blocks of the device are written back as well.
     pub fn flush(&self) -> AxResult {
-        self.access_node(Cap::WRITE)?.fsync()?;
-        Ok(())
+        self.access_node(Cap::empty())?.fsync()
     }
 
     /// Sets the cursor of the file to the specified offset. Returns the new
@@ -279,6 +489,28 @@ impl File {
     pub fn get_attr(&self) -> AxResult<FileAttr> {
         self.access_node(Cap::empty())?.get_attr()
     }
+
+    /// Gets the ownership and timestamps of the file.
+    pub fn get_meta(&self) -> AxResult<FileMeta> {
+        crate::fs::node_meta(self.access_node(Cap::empty())?)
+    }
+
+    /// Sets the permissions of the file.
+    pub fn set_perm(&self, perm: FilePerm) -> AxResult {
+        crate::fs::set_perm(self.access_node(Cap::empty())?, perm)
+    }
+
+    /// Sets the owner and the group of the file, which are unchanged if
+    /// `None`.
+    pub fn set_owner(&self, uid: Option<u32>, gid: Option<u32>) -> AxResult {
+        crate::fs::set_owner(self.access_node(Cap::empty())?, uid, gid)
+    }
+
+    /// Sets the access and modification times of the file, which are
+    /// unchanged if `None`.
+    pub fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> AxResult {
+        crate::fs::set_times(self.access_node(Cap::empty())?, atime, mtime)
+    }
 }
 
 impl Directory {
@@ -286,24 +518,33 @@ impl Directory {
         self.node.access_or_err(cap, AxError::PermissionDenied)
     }
 
-    fn _open_dir_at(dir: Option<&VfsNodeRef>, path: &str, opts: &OpenOptions) -> AxResult<Self> {
+    /// Opens a directory at `path` relative to `dir`, or to the current
+    /// directory if `dir` is `None`. `abs_path` is the canonical absolute path
+    /// of it.
+    fn _open_dir_at(
+        dir: Option<&VfsNodeRef>,
+        abs_path: String,
+        path: &str,
+        opts: &OpenOptions,
+    ) -> PathResult<Self> {
         debug!("open dir: {}", path);
         if !opts.read {
-            return ax_err!(InvalidInput);
+            return Err(ax_err_type!(InvalidInput).into());
         }
         if opts.create || opts.create_new || opts.write || opts.append || opts.truncate {
-            return ax_err!(InvalidInput);
+            return Err(ax_err_type!(InvalidInput).into());
         }
 
-        let node = crate::root::lookup(dir, path)?;
+        let mount = crate::root::mount_point_of(&abs_path)?;
+        let node = crate::root::lookup_at(dir, path, !opts.nofollow)?;
         let attr = node.get_attr()?;
         if !attr.is_dir() {
-            return ax_err!(NotADirectory);
+            return Err(ax_err_type!(NotADirectory).into());
         }
         let access_cap = opts.into();
         let cap = perm_to_cap(attr.perm());
         if !cap.contains(access_cap) {
-            return ax_err!(PermissionDenied);
+            return Err(ax_err_type!(PermissionDenied).into());
         }
 
         node.open()?;
@@ -313,6 +554,8 @@ impl Directory {
             // directories that don't have this permission.
             node: WithCap::new(node, cap),
             entry_idx: 0,
+            path: abs_path,
+            _mount: mount,
         })
     }
 
@@ -324,22 +567,30 @@ impl Directory {
         }
     }
 
+    fn path_at(&self, path: &str) -> String {
+        if path.starts_with('/') {
+            axfs_vfs::path::canonicalize(path)
+        } else {
+            axfs_vfs::path::canonicalize(&format!("{}/{}", self.path, path))
+        }
+    }
+
     /// Opens a directory at the path relative to the current directory.
     /// Returns a [`Directory`] object.
-    pub fn open_dir(path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_dir_at(None, path, opts)
+    pub fn open_dir(path: &str, opts: &OpenOptions) -> PathResult<Self> {
+        Self::_open_dir_at(None, crate::root::absolute_path(path)?, path, opts)
     }
 
     /// Opens a directory at the path relative to this directory. Returns a
     /// [`Directory`] object.
-    pub fn open_dir_at(&self, path: &str, opts: &OpenOptions) -> AxResult<Self> {
-        Self::_open_dir_at(self.access_at(path)?, path, opts)
+    pub fn open_dir_at(&self, path: &str, opts: &OpenOptions) -> PathResult<Self> {
+        Self::_open_dir_at(self.access_at(path)?, self.path_at(path), path, opts)
     }
 
     /// Opens a file at the path relative to this directory. Returns a [`File`]
     /// object.
-    pub fn open_file_at(&self, path: &str, opts: &OpenOptions) -> AxResult<File> {
-        File::_open_at(self.access_at(path)?, path, opts)
+    pub fn open_file_at(&self, path: &str, opts: &OpenOptions) -> PathResult<File> {
+        File::_open_at(self.access_at(path)?, &self.path_at(path), path, opts)
     }
 
     /// Creates an empty file at the path relative to this directory.
@@ -362,6 +613,33 @@ impl Directory {
         crate::root::remove_dir(self.access_at(path)?, path)
     }
 
+    /// Gets the directory attributes.
+    pub fn get_attr(&self) -> AxResult<FileAttr> {
+        self.access_node(Cap::empty())?.get_attr()
+    }
+
+    /// Gets the ownership and timestamps of the directory.
+    pub fn get_meta(&self) -> AxResult<FileMeta> {
+        crate::fs::node_meta(self.access_node(Cap::empty())?)
+    }
+
+    /// Sets the permissions of the directory.
+    pub fn set_perm(&self, perm: FilePerm) -> AxResult {
+        crate::fs::set_perm(self.access_node(Cap::empty())?, perm)
+    }
+
+    /// Sets the owner and the group of the directory, which are unchanged if
+    /// `None`.
+    pub fn set_owner(&self, uid: Option<u32>, gid: Option<u32>) -> AxResult {
+        crate::fs::set_owner(self.access_node(Cap::empty())?, uid, gid)
+    }
+
+    /// Sets the access and modification times of the directory, which are
+    /// unchanged if `None`.
+    pub fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> AxResult {
+        crate::fs::set_times(self.access_node(Cap::empty())?, atime, mtime)
+    }
+
     /// Reads directory entries starts from the current position into the
     /// given buffer. Returns the number of entries read.
     ///
@@ -417,6 +695,7 @@ impl fmt::Debug for OpenOptions {
         fmt_opt!(truncate, "TRUNC");
         fmt_opt!(create, "CREATE");
         fmt_opt!(create_new, "CREATE_NEW");
+        fmt_opt!(nofollow, "NOFOLLOW");
         Ok(())
     }
 }
@@ -437,6 +716,18 @@ impl From<&OpenOptions> for Cap {
     }
 }
 
+/// Returns the attributes and the metadata of the file at `path`, following
+/// the symbolic link of the last component if `follow` is true.
+pub fn file_attr(path: &str, follow: bool) -> PathResult<(FileAttr, FileMeta)> {
+    let node = crate::root::lookup_at(None, path, follow)?;
+    Ok((node.get_attr()?, crate::fs::node_meta(&node)?))
+}
+
+/// Reads the target of the symbolic link at `path`.
+pub fn read_link(path: &str) -> PathResult<String> {
+    crate::root::read_link(None, path)
+}
+
 fn perm_to_cap(perm: FilePerm) -> Cap {
     let mut cap = Cap::empty();
     if perm.owner_readable() {
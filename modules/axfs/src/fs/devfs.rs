@@ -0,0 +1,214 @@
+//! Device filesystem, with the character devices of the kernel and the block
+//! devices, built on [`axfs_devfs`].
+//!
+//! Drivers and applications add their own character devices by
+//! [`register_char_device`], which works both before and after devfs is
+//! mounted.
+
+use alloc::{collections::BTreeMap, string::String, sync::Arc};
+
+use axerrno::{AxResult, ax_err};
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeRef, VfsNodeType, VfsResult};
+use rand_chacha::ChaCha20Rng;
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use spin::{Mutex, once::Once};
+
+pub use axfs_devfs::{DeviceFileSystem, DirNode, NullDev, ZeroDev};
+
+/// A character device, which can be added to devfs by
+/// [`register_char_device`].
+pub trait CharDevice: Send + Sync {
+    /// Reads data from the device at `offset`, which is ignored by the
+    /// devices without positions, e.g., terminals.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize>;
+
+    /// Writes data to the device at `offset`, which is ignored by the
+    /// devices without positions.
+    fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize>;
+
+    /// Returns the size of the device in bytes, or 0 if it has no size.
+    fn size(&self) -> u64 {
+        0
+    }
+
+    /// Flushes the written data, e.g., to the screen.
+    fn flush(&self) -> AxResult {
+        Ok(())
+    }
+}
+
+/// The node of a [`CharDevice`] in devfs.
+struct CharDevNode(Arc<dyn CharDevice>);
+
+impl VfsNodeOps for CharDevNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o666),
+            VfsNodeType::CharDevice,
+            self.0.size(),
+            0,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        self.0.read_at(offset, buf)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        self.0.write_at(offset, buf)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        self.0.flush()
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
+
+/// The console of [`axhal::console`], as `/dev/console` and `/dev/tty`.
+///
+/// The console raises no interrupt on input on most platforms, so reads
+/// sleep for a short interval between the checks for input.
+pub struct ConsoleDev;
+
+/// How long a read of [`ConsoleDev`] sleeps before checking for input again.
+#[cfg(feature = "multitask")]
+const CONSOLE_POLL_INTERVAL: core::time::Duration = core::time::Duration::from_millis(10);
+
+impl CharDevice for ConsoleDev {
+    fn read_at(&self, _offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+        // wait until something is typed, as a terminal in canonical mode
+        loop {
+            let len = axhal::console::read_bytes(buf);
+            if len > 0 || buf.is_empty() {
+                return Ok(len);
+            }
+            #[cfg(feature = "multitask")]
+            axtask::sleep(CONSOLE_POLL_INTERVAL);
+            #[cfg(not(feature = "multitask"))]
+            core::hint::spin_loop();
+        }
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> AxResult<usize> {
+        axhal::console::write_bytes(buf);
+        Ok(buf.len())
+    }
+}
+
+/// `/dev/random` and `/dev/urandom`, generated by ChaCha20 seeded from
+/// [`axhal::random::entropy`].
+///
+/// The generator is rekeyed with its own output after each read, so that the
+/// bytes already read cannot be recovered from its state. What is written to
+/// it is accepted but not used.
+pub struct RandomDev(Mutex<ChaCha20Rng>);
+
+impl RandomDev {
+    /// Creates the device, or returns `None` if the platform has no source
+    /// of entropy, since predictable numbers would be worse than none.
+    pub fn new() -> Option<Self> {
+        let seed = axhal::random::entropy()?;
+        Some(Self(Mutex::new(ChaCha20Rng::from_seed(seed))))
+    }
+}
+
+impl CharDevice for RandomDev {
+    fn read_at(&self, _offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+        let mut rng = self.0.lock();
+        rng.fill_bytes(buf);
+        let mut key = [0; 32];
+        rng.fill_bytes(&mut key);
+        *rng = ChaCha20Rng::from_seed(key);
+        Ok(buf.len())
+    }
+
+    fn write_at(&self, _offset: u64, buf: &[u8]) -> AxResult<usize> {
+        Ok(buf.len())
+    }
+}
+
+static DEVFS: Once<Arc<DeviceFileSystem>> = Once::new();
+
+/// The directories in devfs by their paths, which are all created by
+/// [`dir_at`].
+static DIRS: Mutex<BTreeMap<String, Arc<DirNode>>> = Mutex::new(BTreeMap::new());
+
+/// Returns the device filesystem.
+pub fn devfs() -> Arc<DeviceFileSystem> {
+    DEVFS
+        .call_once(|| Arc::new(DeviceFileSystem::new()))
+        .clone()
+}
+
+/// Returns the directory at `path` in devfs, or `None` for the root,
+/// creating the missing ones.
+fn dir_at(path: &str) -> AxResult<Option<Arc<DirNode>>> {
+    let fs = devfs();
+    let mut dirs = DIRS.lock();
+    let mut dir = None::<Arc<DirNode>>;
+    let mut dir_path = String::new();
+    for name in path.split('/').filter(|name| !name.is_empty()) {
+        if !dir_path.is_empty() {
+            dir_path.push('/');
+        }
+        dir_path.push_str(name);
+        let next = match dirs.get(&dir_path) {
+            Some(next) => next.clone(),
+            None => {
+                let parent = dir.clone().map_or_else(|| fs.root_dir(), |dir| dir as _);
+                if parent.lookup(name).is_ok() {
+                    return ax_err!(NotADirectory);
+                }
+                let name = leak(name);
+                let next = match &dir {
+                    Some(dir) => dir.mkdir(name),
+                    None => fs.mkdir(name),
+                };
+                dirs.insert(dir_path.clone(), next.clone());
+                next
+            }
+        };
+        dir = Some(next);
+    }
+    Ok(dir)
+}
+
+/// Adds `node` at `path` in devfs, e.g. `input/event0`, creating the missing
+/// directories.
+pub(crate) fn add_node(path: &str, node: VfsNodeRef) -> AxResult {
+    let path = path.trim_matches('/');
+    let (dir_path, name) = path.rsplit_once('/').unwrap_or(("", path));
+    if name.is_empty() {
+        return ax_err!(InvalidInput);
+    }
+    let dir = dir_at(dir_path)?;
+    let fs = devfs();
+    let parent = dir.clone().map_or_else(|| fs.root_dir(), |dir| dir as _);
+    if parent.lookup(name).is_ok() {
+        return ax_err!(AlreadyExists);
+    }
+    match dir {
+        Some(dir) => dir.add(leak(name), node),
+        None => fs.add(leak(name), node),
+    }
+    Ok(())
+}
+
+/// Adds `dev` as a character device at `path` in devfs, e.g. `input/event0`,
+/// creating the missing directories.
+///
+/// Returns [`AlreadyExists`](axerrno::AxError::AlreadyExists) if there is a
+/// device at `path`.
+pub fn register_char_device(path: &str, dev: Arc<dyn CharDevice>) -> AxResult {
+    add_node(path, Arc::new(CharDevNode(dev)))
+}
+
+/// Device names live as long as devfs, which is never dropped.
+fn leak(name: &str) -> &'static str {
+    String::from(name).leak()
+}
@@ -0,0 +1,558 @@
+//! Offline consistency check of FAT volumes, like `fsck.fat -n`.
+//!
+//! The volume is parsed by itself instead of by rust-fatfs, so that the
+//! damage that rust-fatfs would trip over is reported. Nothing is repaired.
+
+use alloc::{format, string::String, vec, vec::Vec};
+
+use axerrno::{AxResult, ax_err};
+
+use crate::dev::Disk;
+
+/// The size of directory entries.
+pub(super) const DIR_ENTRY_SIZE: usize = 32;
+/// The attribute of long file name entries.
+pub(super) const ATTR_LONG_NAME: u8 = 0x0f;
+/// The attribute of the volume label entry.
+pub(super) const ATTR_VOLUME_ID: u8 = 0x08;
+/// The attribute of directories.
+const ATTR_DIRECTORY: u8 = 0x10;
+/// The first byte of the names of deleted entries.
+pub(super) const DELETED: u8 = 0xe5;
+/// The number of the first data cluster.
+pub(super) const FIRST_CLUSTER: u32 = 2;
+
+/// Types of FAT volumes, by the size of their FAT entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatType {
+    /// FAT12, of less than 4085 clusters.
+    Fat12,
+    /// FAT16, of less than 65525 clusters.
+    Fat16,
+    /// FAT32.
+    Fat32,
+}
+
+impl FatType {
+    fn from_clusters(clusters: u32) -> Self {
+        if clusters < 4085 {
+            Self::Fat12
+        } else if clusters < 65525 {
+            Self::Fat16
+        } else {
+            Self::Fat32
+        }
+    }
+
+    /// The FAT entry of bad clusters, and larger ones mark the end of chains.
+    pub(super) fn bad_cluster(self) -> u32 {
+        match self {
+            Self::Fat12 => 0xff7,
+            Self::Fat16 => 0xfff7,
+            Self::Fat32 => 0x0fff_fff7,
+        }
+    }
+
+    /// The position of the FAT entry of `cluster` in a FAT.
+    pub(super) fn entry_offset(self, cluster: u32) -> usize {
+        let n = cluster as usize;
+        match self {
+            Self::Fat12 => n + n / 2,
+            Self::Fat16 => n * 2,
+            Self::Fat32 => n * 4,
+        }
+    }
+
+    /// Decodes the FAT entry of `cluster` from the bytes at its position.
+    pub(super) fn decode_entry(self, cluster: u32, bytes: &[u8]) -> u32 {
+        match self {
+            Self::Fat12 => {
+                let entry = read_u16(bytes, 0) as u32;
+                if cluster & 1 == 0 {
+                    entry & 0xfff
+                } else {
+                    entry >> 4
+                }
+            }
+            Self::Fat16 => read_u16(bytes, 0) as u32,
+            Self::Fat32 => read_u32(bytes, 0) & 0x0fff_ffff,
+        }
+    }
+}
+
+/// A problem found by [`fsck`](crate::api::fsck).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FsckIssue {
+    /// The volume was not unmounted cleanly.
+    Dirty,
+    /// The copies of the FAT differ.
+    FatMismatch,
+    /// The cluster chain of the file or directory at `path` is broken, or
+    /// has a loop.
+    BadChain {
+        /// The path of the file or directory.
+        path: String,
+    },
+    /// The file or directory at `path` uses `cluster`, which is also used by
+    /// an earlier one.
+    CrossLinked {
+        /// The path of the file or directory.
+        path: String,
+        /// The first shared cluster.
+        cluster: u32,
+    },
+    /// The size of the file at `path` does not match its `clusters`
+    /// clusters.
+    SizeMismatch {
+        /// The path of the file.
+        path: String,
+        /// The size in the directory entry.
+        size: u32,
+        /// The length of the cluster chain.
+        clusters: u32,
+    },
+    /// The `.` or `..` entry of the directory at `path` is missing, or does
+    /// not point to the directory or its parent.
+    BadDotEntry {
+        /// The path of the directory.
+        path: String,
+    },
+    /// `count` clusters are allocated, but are not used by any file or
+    /// directory.
+    LostClusters {
+        /// The number of lost clusters.
+        count: u32,
+    },
+}
+
+/// The result of [`fsck`](crate::api::fsck).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsckReport {
+    /// The type of the volume.
+    pub fat_type: FatType,
+    /// The size of clusters in bytes.
+    pub cluster_size: u32,
+    /// The number of data clusters.
+    pub total_clusters: u32,
+    /// The number of free clusters in the FAT.
+    pub free_clusters: u32,
+    /// The number of files.
+    pub files: usize,
+    /// The number of directories, without the root directory.
+    pub dirs: usize,
+    /// The problems found.
+    pub issues: Vec<FsckIssue>,
+}
+
+impl FsckReport {
+    /// Whether no problem is found.
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// The layout of a FAT volume, from its boot sector. Offsets and sizes are
+/// in bytes.
+pub(super) struct Volume {
+    pub fat_type: FatType,
+    pub cluster_size: u32,
+    pub clusters: u32,
+    num_fats: u64,
+    pub fat_start: u64,
+    fat_size: u64,
+    /// The root directory of FAT12 and FAT16, which is not in clusters.
+    pub root_dir_start: u64,
+    pub root_dir_size: u64,
+    /// The first cluster of the root directory of FAT32.
+    pub root_cluster: u32,
+    pub data_start: u64,
+    dirty: bool,
+}
+
+impl Volume {
+    /// Parses the boot sector of a volume of `size` bytes.
+    pub fn parse(boot: &[u8], size: u64) -> AxResult<Self> {
+        let bytes_per_sector = read_u16(boot, 11) as u64;
+        let sectors_per_cluster = boot[13] as u64;
+        let reserved_sectors = read_u16(boot, 14) as u64;
+        let num_fats = boot[16] as u64;
+        let root_entries = read_u16(boot, 17) as u64;
+        let total_sectors = match read_u16(boot, 19) {
+            0 => read_u32(boot, 32) as u64,
+            n => n as u64,
+        };
+        let fat_sectors = match read_u16(boot, 22) {
+            0 => read_u32(boot, 36) as u64,
+            n => n as u64,
+        };
+        if boot[510..512] != [0x55, 0xaa]
+            || ![512, 1024, 2048, 4096].contains(&bytes_per_sector)
+            || !sectors_per_cluster.is_power_of_two()
+            || reserved_sectors == 0
+            || num_fats == 0
+            || fat_sectors == 0
+            || total_sectors * bytes_per_sector > size
+        {
+            return ax_err!(InvalidData, "not a FAT volume");
+        }
+
+        let root_dir_sectors = (root_entries * DIR_ENTRY_SIZE as u64).div_ceil(bytes_per_sector);
+        let data_sector = reserved_sectors + num_fats * fat_sectors + root_dir_sectors;
+        if data_sector >= total_sectors {
+            return ax_err!(InvalidData, "not a FAT volume");
+        }
+        let clusters = ((total_sectors - data_sector) / sectors_per_cluster) as u32;
+        let fat_type = FatType::from_clusters(clusters);
+        let entry_bits = match fat_type {
+            FatType::Fat12 => 12,
+            FatType::Fat16 => 16,
+            FatType::Fat32 => 32,
+        };
+        let fat_size = fat_sectors * bytes_per_sector;
+        if (clusters as u64 + FIRST_CLUSTER as u64) * entry_bits > fat_size * 8 {
+            return ax_err!(InvalidData, "FAT is too small");
+        }
+        // the flags of the volume, which are written by Windows, Linux and
+        // rust-fatfs, are in a reserved field of the extended BPB
+        let flags_offset = if fat_type == FatType::Fat32 {
+            0x41
+        } else {
+            0x25
+        };
+        Ok(Self {
+            fat_type,
+            cluster_size: (sectors_per_cluster * bytes_per_sector) as u32,
+            clusters,
+            num_fats,
+            fat_start: reserved_sectors * bytes_per_sector,
+            fat_size,
+            root_dir_start: (reserved_sectors + num_fats * fat_sectors) * bytes_per_sector,
+            root_dir_size: root_entries * DIR_ENTRY_SIZE as u64,
+            root_cluster: read_u32(boot, 44),
+            data_start: data_sector * bytes_per_sector,
+            dirty: boot[flags_offset] & 1 != 0,
+        })
+    }
+
+    pub fn is_data_cluster(&self, cluster: u32) -> bool {
+        (FIRST_CLUSTER..self.clusters + FIRST_CLUSTER).contains(&cluster)
+    }
+
+    /// The position of the data cluster `cluster`.
+    pub fn cluster_pos(&self, cluster: u32) -> u64 {
+        self.data_start + (cluster - FIRST_CLUSTER) as u64 * self.cluster_size as u64
+    }
+}
+
+/// Checks the FAT volume on `disk`.
+pub(crate) fn check_disk(mut disk: Disk) -> AxResult<FsckReport> {
+    let size = disk.size();
+    check(size, |pos, buf| {
+        disk.set_position(pos);
+        match disk.read_bytes(buf) {
+            Ok(len) if len == buf.len() => Ok(()),
+            Ok(_) => ax_err!(UnexpectedEof),
+            Err(_) => ax_err!(Io),
+        }
+    })
+}
+
+/// Checks a FAT volume of `size` bytes, whose bytes at a position are read
+/// by `read`.
+fn check<R>(size: u64, read: R) -> AxResult<FsckReport>
+where
+    R: FnMut(u64, &mut [u8]) -> AxResult,
+{
+    let mut checker = Checker::new(size, read)?;
+    if checker.vol.dirty {
+        checker.report.issues.push(FsckIssue::Dirty);
+    }
+    checker.check_fats()?;
+    checker.check_tree()?;
+    checker.check_lost();
+    Ok(checker.report)
+}
+
+struct Checker<R> {
+    vol: Volume,
+    read: R,
+    /// The first FAT.
+    fat: Vec<u8>,
+    /// The ID of the file or directory that uses each cluster, or 0 if it is
+    /// not used.
+    owners: Vec<u32>,
+    /// The ID of the last checked file or directory.
+    last_id: u32,
+    report: FsckReport,
+}
+
+impl<R: FnMut(u64, &mut [u8]) -> AxResult> Checker<R> {
+    fn new(size: u64, mut read: R) -> AxResult<Self> {
+        let mut boot = [0; 512];
+        read(0, &mut boot)?;
+        let vol = Volume::parse(&boot, size)?;
+        let mut fat = vec![0; vol.fat_size as usize];
+        read(vol.fat_start, &mut fat)?;
+        let report = FsckReport {
+            fat_type: vol.fat_type,
+            cluster_size: vol.cluster_size,
+            total_clusters: vol.clusters,
+            free_clusters: 0,
+            files: 0,
+            dirs: 0,
+            issues: Vec::new(),
+        };
+        Ok(Self {
+            owners: vec![0; (vol.clusters + FIRST_CLUSTER) as usize],
+            vol,
+            read,
+            fat,
+            last_id: 0,
+            report,
+        })
+    }
+
+    /// Returns the FAT entry of `cluster`.
+    fn fat_entry(&self, cluster: u32) -> u32 {
+        let fat_type = self.vol.fat_type;
+        fat_type.decode_entry(cluster, &self.fat[fat_type.entry_offset(cluster)..])
+    }
+
+    fn is_data_cluster(&self, cluster: u32) -> bool {
+        self.vol.is_data_cluster(cluster)
+    }
+
+    /// Compares the other FATs with the first one.
+    fn check_fats(&mut self) -> AxResult {
+        let mut fat = vec![0; self.fat.len()];
+        for i in 1..self.vol.num_fats {
+            (self.read)(self.vol.fat_start + i * self.vol.fat_size, &mut fat)?;
+            if fat != self.fat {
+                self.report.issues.push(FsckIssue::FatMismatch);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Walks the directory tree from the root directory.
+    fn check_tree(&mut self) -> AxResult {
+        let root = match self.vol.fat_type {
+            FatType::Fat32 => {
+                let root_cluster = self.vol.root_cluster;
+                match self.claim_chain("/", root_cluster) {
+                    Some(chain) => self.read_chain(&chain)?,
+                    None => return Ok(()),
+                }
+            }
+            _ => {
+                let mut data = vec![0; self.vol.root_dir_size as usize];
+                (self.read)(self.vol.root_dir_start, &mut data)?;
+                data
+            }
+        };
+        // the directories to check, with their first clusters and the first
+        // clusters of their parents
+        let mut dirs = Vec::new();
+        self.check_entries(&root, "", None, &mut dirs);
+        while let Some((path, cluster, parent)) = dirs.pop() {
+            let Some(chain) = self.claim_chain(&path, cluster) else {
+                continue;
+            };
+            let data = self.read_chain(&chain)?;
+            self.check_entries(&data, &path, Some((cluster, parent)), &mut dirs);
+        }
+        Ok(())
+    }
+
+    /// Checks the entries of the directory at `path`, whose first cluster
+    /// and the first cluster of its parent are `clusters`, or `None` if it is
+    /// the root directory. Subdirectories are added to `dirs`.
+    fn check_entries(
+        &mut self,
+        data: &[u8],
+        path: &str,
+        clusters: Option<(u32, u32)>,
+        dirs: &mut Vec<(String, u32, u32)>,
+    ) {
+        let mut entries = data
+            .chunks_exact(DIR_ENTRY_SIZE)
+            .take_while(|entry| entry[0] != 0)
+            .filter(|entry| entry[0] != DELETED)
+            .peekable();
+        if let Some((cluster, parent)) = clusters {
+            // some implementations give them long names too
+            let mut dots = entries.by_ref().filter(|entry| entry[11] != ATTR_LONG_NAME);
+            let dot = dots.next();
+            let dot_dot = dots.next();
+            if !is_dot_entry(dot, b".          ", &[cluster])
+                || !is_dot_entry(dot_dot, b"..         ", &self.root_clusters(parent))
+            {
+                self.report
+                    .issues
+                    .push(FsckIssue::BadDotEntry { path: path.into() });
+            }
+        }
+
+        let mut long_name = Vec::new();
+        for entry in entries {
+            let attr = entry[11];
+            if attr == ATTR_LONG_NAME {
+                push_long_name(&mut long_name, entry);
+                continue;
+            }
+            let name = match String::from_utf16(&long_name) {
+                Ok(name) if !name.is_empty() => name,
+                _ => short_name(entry),
+            };
+            long_name.clear();
+            if attr & ATTR_VOLUME_ID != 0 || name == "." || name == ".." {
+                continue;
+            }
+
+            let path = format!("{path}/{name}");
+            let mut cluster = read_u16(entry, 26) as u32;
+            if self.vol.fat_type == FatType::Fat32 {
+                cluster |= (read_u16(entry, 20) as u32) << 16;
+            }
+            if attr & ATTR_DIRECTORY != 0 {
+                self.report.dirs += 1;
+                dirs.push((path, cluster, clusters.map_or(0, |(cluster, _)| cluster)));
+            } else {
+                self.report.files += 1;
+                self.check_file(path, cluster, read_u32(entry, 28));
+            }
+        }
+    }
+
+    /// The values of `..` entries that point to the directory starting at
+    /// `cluster`, where 0 is the root directory.
+    fn root_clusters(&self, cluster: u32) -> [u32; 2] {
+        // some implementations point to the root directory of FAT32 by its
+        // first cluster
+        if cluster == 0 && self.vol.fat_type == FatType::Fat32 {
+            [0, self.vol.root_cluster]
+        } else {
+            [cluster, cluster]
+        }
+    }
+
+    fn check_file(&mut self, path: String, cluster: u32, size: u32) {
+        let clusters = if cluster == 0 {
+            0
+        } else {
+            match self.claim_chain(&path, cluster) {
+                Some(chain) => chain.len() as u32,
+                None => return,
+            }
+        };
+        if clusters != size.div_ceil(self.vol.cluster_size) {
+            self.report.issues.push(FsckIssue::SizeMismatch {
+                path,
+                size,
+                clusters,
+            });
+        }
+    }
+
+    /// Claims the cluster chain from `start` for the file or directory at
+    /// `path`, and returns its clusters, or `None` if it is broken.
+    fn claim_chain(&mut self, path: &str, start: u32) -> Option<Vec<u32>> {
+        self.last_id += 1;
+        let id = self.last_id;
+        let bad_cluster = self.vol.fat_type.bad_cluster();
+        let mut chain = Vec::new();
+        let mut cluster = start;
+        loop {
+            if !self.is_data_cluster(cluster) {
+                break;
+            }
+            match self.owners[cluster as usize] {
+                0 => {}
+                owner if owner == id => break, // a loop
+                _ => {
+                    let path = path.into();
+                    self.report
+                        .issues
+                        .push(FsckIssue::CrossLinked { path, cluster });
+                    return None;
+                }
+            }
+            self.owners[cluster as usize] = id;
+            chain.push(cluster);
+            match self.fat_entry(cluster) {
+                next if next > bad_cluster => return Some(chain),
+                next => cluster = next,
+            }
+        }
+        let path = path.into();
+        self.report.issues.push(FsckIssue::BadChain { path });
+        None
+    }
+
+    fn read_chain(&mut self, chain: &[u32]) -> AxResult<Vec<u8>> {
+        let cluster_size = self.vol.cluster_size as usize;
+        let mut data = vec![0; chain.len() * cluster_size];
+        for (&cluster, buf) in chain.iter().zip(data.chunks_exact_mut(cluster_size)) {
+            (self.read)(self.vol.cluster_pos(cluster), buf)?;
+        }
+        Ok(data)
+    }
+
+    /// Counts the free clusters, and the allocated ones that are not used.
+    fn check_lost(&mut self) {
+        let bad_cluster = self.vol.fat_type.bad_cluster();
+        let mut lost = 0;
+        for cluster in FIRST_CLUSTER..self.vol.clusters + FIRST_CLUSTER {
+            match self.fat_entry(cluster) {
+                0 => self.report.free_clusters += 1,
+                entry if entry != bad_cluster && self.owners[cluster as usize] == 0 => lost += 1,
+                _ => {}
+            }
+        }
+        if lost > 0 {
+            let issue = FsckIssue::LostClusters { count: lost };
+            self.report.issues.push(issue);
+        }
+    }
+}
+
+/// Whether `entry` is a `.` or `..` directory named `name`, which points to
+/// one of `clusters`.
+fn is_dot_entry(entry: Option<&[u8]>, name: &[u8; 11], clusters: &[u32]) -> bool {
+    entry.is_some_and(|entry| {
+        let cluster = ((read_u16(entry, 20) as u32) << 16) | read_u16(entry, 26) as u32;
+        entry[..11] == *name && entry[11] & ATTR_DIRECTORY != 0 && clusters.contains(&cluster)
+    })
+}
+
+/// Prepends the characters in the long file name entry `entry` to
+/// `long_name`, as the entries are stored in reverse order.
+pub(super) fn push_long_name(long_name: &mut Vec<u16>, entry: &[u8]) {
+    let chars = [1..11, 14..26, 28..32]
+        .into_iter()
+        .flat_map(|range| entry[range].chunks_exact(2))
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&c| c != 0 && c != 0xffff);
+    long_name.splice(0..0, chars);
+}
+
+/// Returns the 8.3 name in `entry`, e.g. `README.TXT`.
+pub(super) fn short_name(entry: &[u8]) -> String {
+    let base = String::from_utf8_lossy(&entry[..8]);
+    let ext = String::from_utf8_lossy(&entry[8..11]);
+    let (base, ext) = (base.trim_end(), ext.trim_end());
+    if ext.is_empty() {
+        base.into()
+    } else {
+        format!("{base}.{ext}")
+    }
+}
+
+pub(super) fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+
+pub(super) fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
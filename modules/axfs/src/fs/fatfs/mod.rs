@@ -0,0 +1,694 @@
+//! FAT filesystem, by [rust-fatfs](https://github.com/rafalh/rust-fatfs).
+
+pub mod fsck;
+mod raw;
+
+use alloc::{format, string::String, sync::Arc};
+use core::{
+    any::Any,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use axerrno::AxResult;
+use axfs_vfs::{VfsDirEntry, VfsError, VfsNodePerm, VfsResult};
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps};
+use axsync::Mutex;
+use chrono::{Datelike, NaiveDate, Timelike};
+use fatfs::{Date, DateTime, Time, TimeProvider};
+use fatfs::{Dir, File, LossyOemCpConverter, Read, Seek, SeekFrom, Write};
+
+use self::raw::RawVolume;
+use super::{NodeMetaOps, now};
+use crate::dev::Disk;
+use crate::fops::{FileMeta, FsStat};
+
+const BLOCK_SIZE: usize = 512;
+/// 1980-01-01 00:00:00, the earliest time of FAT.
+const MIN_FAT_TIME: u64 = 315_532_800;
+/// 2107-12-31 23:59:59, the latest time of FAT.
+const MAX_FAT_TIME: u64 = 4_354_819_199;
+
+pub struct FatFileSystem {
+    volume: Arc<FatVolume<Disk>>,
+}
+
+/// The volume that a node is in.
+struct FatVolume<IO: IoTrait> {
+    fs: fatfs::FileSystem<IO, WallTimeProvider, LossyOemCpConverter>,
+    /// The volume on the block device, to edit the directory entries that
+    /// rust-fatfs cannot, or `None` if it is not on a block device.
+    raw: Option<RawVolume>,
+}
+
+/// The handles of the nodes borrow their volume, which each node owns, and
+/// which is dropped after the handles as the last field.
+///
+/// Files also record whether they are written since they were last flushed.
+pub struct FileWrapper<'a, IO: IoTrait>(
+    Mutex<File<'a, IO, WallTimeProvider, LossyOemCpConverter>>,
+    EntryLoc<'a, IO>,
+    AtomicBool,
+    Arc<FatVolume<IO>>,
+);
+pub struct DirWrapper<'a, IO: IoTrait>(
+    Dir<'a, IO, WallTimeProvider, LossyOemCpConverter>,
+    Option<EntryLoc<'a, IO>>,
+    Arc<FatVolume<IO>>,
+);
+
+/// Where the directory entry of a node is, which has its timestamps.
+pub struct EntryLoc<'a, IO: IoTrait> {
+    dir: Dir<'a, IO, WallTimeProvider, LossyOemCpConverter>,
+    name: String,
+    /// The path in the volume, without the leading `/`.
+    path: String,
+}
+
+/// Provides the wall time in UTC to FAT volumes, which is used to update the
+/// timestamps of files.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WallTimeProvider;
+
+pub trait IoTrait: Read + Write + Seek {}
+
+unsafe impl Sync for FatFileSystem {}
+unsafe impl Send for FatFileSystem {}
+unsafe impl<'a, IO: IoTrait> Send for FileWrapper<'a, IO> {}
+unsafe impl<'a, IO: IoTrait> Sync for FileWrapper<'a, IO> {}
+unsafe impl<'a, IO: IoTrait> Send for DirWrapper<'a, IO> {}
+unsafe impl<'a, IO: IoTrait> Sync for DirWrapper<'a, IO> {}
+
+impl FatFileSystem {
+    /// Formats `disk` as an empty FAT volume.
+    #[cfg(feature = "use-ramdisk")]
+    pub fn format(disk: &mut Disk) -> AxResult {
+        let opts = fatfs::FormatVolumeOptions::new();
+        fatfs::format_volume(disk, opts).map_err(as_vfs_err)
+    }
+
+    /// Opens the FAT volume on `disk`.
+    pub fn new(disk: Disk) -> AxResult<Arc<Self>> {
+        let raw = RawVolume::new(disk.device().clone()).ok();
+        let opts = fatfs::FsOptions::new().time_provider(WallTimeProvider);
+        let fs = fatfs::FileSystem::new(disk, opts).map_err(as_vfs_err)?;
+        Ok(Arc::new(Self {
+            volume: Arc::new(FatVolume { fs, raw }),
+        }))
+    }
+
+    /// Returns the root directory of `volume`.
+    fn volume_root<IO: IoTrait + 'static>(
+        volume: &Arc<FatVolume<IO>>,
+    ) -> Arc<DirWrapper<'static, IO>> {
+        // SAFETY: the volume is not moved out of the `Arc`, and outlives the
+        // handles borrowing it, as the nodes own it.
+        let this: &'static FatVolume<IO> = unsafe { &*Arc::as_ptr(volume) };
+        Self::new_dir(this.fs.root_dir(), None, volume.clone())
+    }
+
+    fn new_file<'a, IO: IoTrait>(
+        file: File<'a, IO, WallTimeProvider, LossyOemCpConverter>,
+        loc: EntryLoc<'a, IO>,
+        volume: Arc<FatVolume<IO>>,
+    ) -> Arc<FileWrapper<'a, IO>> {
+        Arc::new(FileWrapper(
+            Mutex::new(file),
+            loc,
+            AtomicBool::new(false),
+            volume,
+        ))
+    }
+
+    fn new_dir<'a, IO: IoTrait>(
+        dir: Dir<'a, IO, WallTimeProvider, LossyOemCpConverter>,
+        loc: Option<EntryLoc<'a, IO>>,
+        volume: Arc<FatVolume<IO>>,
+    ) -> Arc<DirWrapper<'a, IO>> {
+        Arc::new(DirWrapper(dir, loc, volume))
+    }
+}
+
+/// Returns the usage and the label of `volume`.
+fn volume_stat<IO: IoTrait>(volume: &FatVolume<IO>) -> VfsResult<FsStat> {
+    let stats = volume.fs.stats().map_err(as_vfs_err)?;
+    let label = volume.fs.volume_label();
+    let label = label.trim_end();
+    Ok(FsStat {
+        block_size: stats.cluster_size() as u64,
+        blocks: stats.total_clusters() as u64,
+        blocks_free: stats.free_clusters() as u64,
+        name_max: 255,
+        // `NO NAME` is written by formatters when no label is given
+        label: (!label.is_empty() && label != "NO NAME").then(|| label.into()),
+        ..Default::default()
+    })
+}
+
+/// Writes `len` zeros at the cursor of `file`, as FAT has no holes.
+fn write_zeros<IO: IoTrait>(
+    file: &mut File<'_, IO, WallTimeProvider, LossyOemCpConverter>,
+    mut len: u64,
+) -> VfsResult {
+    const ZEROS: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+    while len > 0 {
+        let buf = &ZEROS[..len.min(BLOCK_SIZE as u64) as usize];
+        match file.write(buf).map_err(as_vfs_err)? {
+            0 => return Err(VfsError::WriteZero),
+            n => len -= n as u64,
+        }
+    }
+    Ok(())
+}
+
+impl<'a, IO: IoTrait> EntryLoc<'a, IO> {
+    /// Returns the location of the entry at `path` relative to `dir`, which
+    /// is at `dir_path` in the volume.
+    fn new(
+        dir: &Dir<'a, IO, WallTimeProvider, LossyOemCpConverter>,
+        dir_path: &str,
+        path: &str,
+    ) -> Option<Self> {
+        let (dir, name) = match path.rsplit_once('/') {
+            Some((parent, name)) => (dir.open_dir(parent).ok()?, name),
+            None => (dir.clone(), path),
+        };
+        let full_path = axfs_vfs::path::canonicalize(&format!("/{dir_path}/{path}"));
+        Some(Self {
+            dir,
+            name: name.into(),
+            path: full_path.trim_start_matches('/').into(),
+        })
+    }
+
+    /// Reads the timestamps in the entry.
+    fn meta(&self) -> VfsResult<FileMeta> {
+        let entry = self
+            .dir
+            .iter()
+            .filter_map(Result::ok)
+            .find(|entry| entry.eq_name(&self.name))
+            .ok_or(VfsError::NotFound)?;
+        let mtime = unix_time(entry.modified());
+        Ok(FileMeta {
+            ino: fat_ino(&self.path),
+            nlink: 1,
+            atime: unix_time(DateTime::new(entry.accessed(), Time::new(0, 0, 0, 0))),
+            mtime,
+            // FAT does not record the change time
+            ctime: mtime,
+            crtime: Some(unix_time(entry.created())),
+            ..Default::default()
+        })
+    }
+}
+
+impl<IO: IoTrait> Clone for EntryLoc<'_, IO> {
+    fn clone(&self) -> Self {
+        Self {
+            dir: self.dir.clone(),
+            name: self.name.clone(),
+            path: self.path.clone(),
+        }
+    }
+}
+
+/// Returns the inode number of the entry at `path` in the volume.
+///
+/// FAT has neither inode numbers nor hard links, so they are made up from the
+/// paths, which are case-insensitive, by the FNV-1a hash.
+fn fat_ino(path: &str) -> u64 {
+    path.to_uppercase()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+            (hash ^ b as u64).wrapping_mul(0x100_0000_01b3)
+        })
+}
+
+impl TimeProvider for WallTimeProvider {
+    fn get_current_date(&self) -> Date {
+        fat_date_time(now()).date
+    }
+
+    fn get_current_date_time(&self) -> DateTime {
+        fat_date_time(now())
+    }
+}
+
+/// Converts the time since the UNIX epoch to a FAT date and time, which is
+/// clamped to the range of FAT.
+fn fat_date_time(time: Duration) -> DateTime {
+    let secs = time.as_secs().clamp(MIN_FAT_TIME, MAX_FAT_TIME);
+    let utc = chrono::DateTime::from_timestamp(secs as i64, 0).unwrap();
+    DateTime::new(
+        Date::new(utc.year() as u16, utc.month() as u16, utc.day() as u16),
+        Time::new(
+            utc.hour() as u16,
+            utc.minute() as u16,
+            utc.second() as u16,
+            time.subsec_millis() as u16,
+        ),
+    )
+}
+
+/// Converts a FAT date and time to the time since the UNIX epoch.
+fn unix_time(date_time: DateTime) -> Duration {
+    let (date, time) = (date_time.date, date_time.time);
+    let secs = NaiveDate::from_ymd_opt(date.year as i32, date.month as u32, date.day as u32)
+        .and_then(|date| date.and_hms_opt(time.hour as u32, time.min as u32, time.sec as u32))
+        // invalid dates on the disk
+        .map_or(MIN_FAT_TIME, |utc| utc.and_utc().timestamp() as u64);
+    Duration::from_secs(secs) + Duration::from_millis(time.millis as u64)
+}
+
+/// Returns the metadata operations of `node`, if it is a FAT node.
+pub(super) fn meta_ops(node: &dyn Any) -> Option<&dyn NodeMetaOps> {
+    if let Some(node) = node.downcast_ref::<FileWrapper<'static, Disk>>() {
+        Some(node)
+    } else if let Some(node) = node.downcast_ref::<DirWrapper<'static, Disk>>() {
+        Some(node)
+    } else {
+        None
+    }
+}
+
+impl<IO: IoTrait> FileWrapper<'static, IO> {
+    /// Writes back the file, and the timestamps of writes that are kept in
+    /// it until then.
+    fn flush(&self) -> VfsResult {
+        let mut file = self.0.lock();
+        self.2.store(false, Ordering::Relaxed);
+        file.flush().map_err(as_vfs_err)
+    }
+}
+
+impl<IO: IoTrait> NodeMetaOps for FileWrapper<'static, IO> {
+    fn meta(&self) -> VfsResult<FileMeta> {
+        if self.2.load(Ordering::Relaxed) {
+            self.flush()?;
+        }
+        self.1.meta()
+    }
+
+    fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+        {
+            let mut file = self.0.lock();
+            if let Some(atime) = atime {
+                file.set_accessed(fat_date_time(atime).date);
+            }
+            if let Some(mtime) = mtime {
+                file.set_modified(fat_date_time(mtime));
+            }
+        }
+        self.flush()
+    }
+
+    fn fs_stat(&self) -> VfsResult<FsStat> {
+        volume_stat(&self.3)
+    }
+}
+
+impl<IO: IoTrait> NodeMetaOps for DirWrapper<'static, IO> {
+    fn meta(&self) -> VfsResult<FileMeta> {
+        // the root directory has no entry
+        let root_meta = FileMeta {
+            ino: fat_ino(""),
+            nlink: 1,
+            ..Default::default()
+        };
+        self.1.as_ref().map_or(Ok(root_meta), EntryLoc::meta)
+    }
+
+    fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+        // rust-fatfs only changes the entries of files
+        let (Some(loc), Some(raw)) = (&self.1, &self.2.raw) else {
+            return Err(VfsError::Unsupported);
+        };
+        let atime = atime.map(|atime| fat_date_time(atime).date);
+        raw.set_times(&loc.path, atime, mtime.map(fat_date_time))
+    }
+
+    fn fs_stat(&self) -> VfsResult<FsStat> {
+        volume_stat(&self.2)
+    }
+}
+
+impl<IO: IoTrait> VfsNodeOps for FileWrapper<'static, IO> {
+    axfs_vfs::impl_vfs_non_dir_default! {}
+
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let size = self.0.lock().seek(SeekFrom::End(0)).map_err(as_vfs_err)?;
+        let blocks = (size + BLOCK_SIZE as u64 - 1) / BLOCK_SIZE as u64;
+        // FAT fs doesn't support permissions, we just set everything to 755
+        let perm = VfsNodePerm::from_bits_truncate(0o755);
+        Ok(VfsNodeAttr::new(perm, VfsNodeType::File, size, blocks))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut file = self.0.lock();
+        file.seek(SeekFrom::Start(offset)).map_err(as_vfs_err)?; // TODO: more efficient
+        file.read(buf).map_err(as_vfs_err)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let mut file = self.0.lock();
+        self.2.store(true, Ordering::Relaxed);
+        // seeking stops at the end, so the gap after it is filled first
+        let pos = file.seek(SeekFrom::Start(offset)).map_err(as_vfs_err)?;
+        if pos < offset {
+            write_zeros(&mut file, offset - pos)?;
+        }
+        file.write(buf).map_err(as_vfs_err)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        // also writes back the disk
+        self.flush()
+    }
+
+    fn truncate(&self, size: u64) -> VfsResult {
+        let mut file = self.0.lock();
+        self.2.store(true, Ordering::Relaxed);
+        let pos = file.seek(SeekFrom::Start(size)).map_err(as_vfs_err)?;
+        if pos < size {
+            return write_zeros(&mut file, size - pos);
+        }
+        file.truncate().map_err(as_vfs_err)
+    }
+}
+
+impl<IO: IoTrait> VfsNodeOps for DirWrapper<'static, IO> {
+    axfs_vfs::impl_vfs_dir_default! {}
+
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        // FAT fs doesn't support permissions, we just set everything to 755
+        Ok(VfsNodeAttr::new(
+            VfsNodePerm::from_bits_truncate(0o755),
+            VfsNodeType::Dir,
+            BLOCK_SIZE as u64,
+            1,
+        ))
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        // the root directory has no parent
+        let loc = self.1.as_ref()?;
+        let parent_loc = match loc.path.rsplit_once('/') {
+            // the entry of the parent is in the grandparent
+            Some((parent, _)) => {
+                let (grandparent, name) = parent.rsplit_once('/').unwrap_or(("", parent));
+                Some(EntryLoc::new(
+                    &loc.dir.open_dir("..").ok()?,
+                    grandparent,
+                    name,
+                )?)
+            }
+            None => None, // the parent is the root directory
+        };
+        Some(FatFileSystem::new_dir(
+            loc.dir.clone(),
+            parent_loc,
+            self.2.clone(),
+        ))
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        debug!("lookup at fatfs: {}", path);
+        let path = path.trim_matches('/');
+        if path.is_empty() || path == "." {
+            return Ok(self.clone());
+        }
+        if let Some(rest) = path.strip_prefix("./") {
+            return self.lookup(rest);
+        }
+
+        // TODO: use `fatfs::Dir::find_entry`, but it's not public.
+        let dir_path = self.1.as_ref().map_or("", |loc| loc.path.as_str());
+        let loc = EntryLoc::new(&self.0, dir_path, path).ok_or(VfsError::NotFound)?;
+        if let Ok(file) = self.0.open_file(path) {
+            Ok(FatFileSystem::new_file(file, loc, self.2.clone()))
+        } else if let Ok(dir) = self.0.open_dir(path) {
+            Ok(FatFileSystem::new_dir(dir, Some(loc), self.2.clone()))
+        } else {
+            Err(VfsError::NotFound)
+        }
+    }
+
+    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
+        debug!("create {:?} at fatfs: {}", ty, path);
+        let path = path.trim_matches('/');
+        if path.is_empty() || path == "." {
+            return Ok(());
+        }
+        if let Some(rest) = path.strip_prefix("./") {
+            return self.create(rest, ty);
+        }
+
+        match ty {
+            VfsNodeType::File => {
+                self.0.create_file(path).map_err(as_vfs_err)?;
+                Ok(())
+            }
+            VfsNodeType::Dir => {
+                self.0.create_dir(path).map_err(as_vfs_err)?;
+                Ok(())
+            }
+            _ => Err(VfsError::Unsupported),
+        }
+    }
+
+    fn remove(&self, path: &str) -> VfsResult {
+        debug!("remove at fatfs: {}", path);
+        let path = path.trim_matches('/');
+        assert!(!path.is_empty()); // already check at `root.rs`
+        if let Some(rest) = path.strip_prefix("./") {
+            return self.remove(rest);
+        }
+        self.0.remove(path).map_err(as_vfs_err)
+    }
+
+    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        let mut iter = self.0.iter().skip(start_idx);
+        for (i, out_entry) in dirents.iter_mut().enumerate() {
+            let x = iter.next();
+            match x {
+                Some(Ok(entry)) => {
+                    let ty = if entry.is_dir() {
+                        VfsNodeType::Dir
+                    } else if entry.is_file() {
+                        VfsNodeType::File
+                    } else {
+                        unreachable!()
+                    };
+                    *out_entry = VfsDirEntry::new(&entry.file_name(), ty);
+                }
+                _ => return Ok(i),
+            }
+        }
+        Ok(dirents.len())
+    }
+
+    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        // `src_path` and `dst_path` should in the same mounted fs
+        debug!(
+            "rename at fatfs, src_path: {}, dst_path: {}",
+            src_path, dst_path
+        );
+        let src_path = src_path.trim_matches('/');
+        let dst_path = dst_path.trim_matches('/');
+        let parent_of = |path: &str| path.rsplit_once('/').map_or("", |(parent, _)| parent);
+        if parent_of(src_path) != parent_of(dst_path) && self.0.open_dir(src_path).is_ok() {
+            return self.move_dir(src_path, dst_path);
+        }
+        self.0
+            .rename(src_path, &self.0, dst_path)
+            .map_err(as_vfs_err)
+    }
+}
+
+impl<IO: IoTrait> DirWrapper<'static, IO> {
+    /// Moves the directory at `src_path` to another directory.
+    ///
+    /// rust-fatfs only moves the entry of a directory, with its timestamps,
+    /// which leaves its `..` entry pointing to the old parent, so that is
+    /// updated on the raw volume.
+    fn move_dir(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        let raw = self.2.raw.as_ref().ok_or(VfsError::Unsupported)?;
+        self.0
+            .rename(src_path, &self.0, dst_path)
+            .map_err(as_vfs_err)?;
+        let dir_path = self.1.as_ref().map_or("", |loc| loc.path.as_str());
+        raw.update_dot_dot(&format!("{dir_path}/{dst_path}"))
+    }
+}
+
+impl VfsOps for FatFileSystem {
+    fn root_dir(&self) -> VfsNodeRef {
+        Self::volume_root(&self.volume)
+    }
+}
+
+impl fatfs::IoBase for Disk {
+    type Error = ();
+}
+
+impl IoTrait for Disk {}
+
+impl Read for Disk {
+    fn read(&mut self, mut buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut read_len = 0;
+        while !buf.is_empty() {
+            match self.read_bytes(buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    let tmp = buf;
+                    buf = &mut tmp[n..];
+                    read_len += n;
+                }
+                Err(_) => return Err(()),
+            }
+        }
+        Ok(read_len)
+    }
+}
+
+impl Write for Disk {
+    fn write(&mut self, mut buf: &[u8]) -> Result<usize, Self::Error> {
+        let mut write_len = 0;
+        while !buf.is_empty() {
+            match self.write_bytes(buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    buf = &buf[n..];
+                    write_len += n;
+                }
+                Err(_) => return Err(()),
+            }
+        }
+        Ok(write_len)
+    }
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Disk::flush(self).map_err(|_| ())
+    }
+}
+
+impl Seek for Disk {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Self::Error> {
+        let size = self.size();
+        let new_pos = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::Current(off) => self.position().checked_add_signed(off),
+            SeekFrom::End(off) => size.checked_add_signed(off),
+        }
+        .ok_or(())?;
+        if new_pos > size {
+            warn!("Seek beyond the end of the block device");
+        }
+        self.set_position(new_pos);
+        Ok(new_pos)
+    }
+}
+
+fn as_vfs_err<E>(err: fatfs::Error<E>) -> VfsError {
+    use fatfs::Error::*;
+    match err {
+        AlreadyExists => VfsError::AlreadyExists,
+        CorruptedFileSystem => VfsError::InvalidData,
+        DirectoryIsNotEmpty => VfsError::DirectoryNotEmpty,
+        InvalidInput | InvalidFileNameLength | UnsupportedFileNameCharacter => {
+            VfsError::InvalidInput
+        }
+        NotEnoughSpace => VfsError::StorageFull,
+        NotFound => VfsError::NotFound,
+        UnexpectedEof => VfsError::UnexpectedEof,
+        WriteZero => VfsError::WriteZero,
+        Io(_) => VfsError::Io,
+        _ => VfsError::Io,
+    }
+}
+
+impl Clone for FileWrapper<'static, Disk> {
+    fn clone(&self) -> Self {
+        let file = self.0.lock();
+        let cloned_file = file.clone();
+        let dirty = AtomicBool::new(self.2.load(Ordering::Relaxed));
+        Self(
+            Mutex::new(cloned_file),
+            self.1.clone(),
+            dirty,
+            self.3.clone(),
+        )
+    }
+}
+
+/// The FAT volume in an image file, which is on a FAT volume itself.
+pub struct FatFileSystemFromFile {
+    volume: Arc<FatVolume<FileWrapper<'static, Disk>>>,
+}
+
+unsafe impl Sync for FatFileSystemFromFile {}
+unsafe impl Send for FatFileSystemFromFile {}
+
+impl FatFileSystemFromFile {
+    /// Opens the FAT volume in the image file `node`, or fails with
+    /// [`Unsupported`](VfsError::Unsupported) if it is not a file on a FAT
+    /// volume.
+    pub fn new(node: &VfsNodeRef) -> VfsResult<Self> {
+        let file = node
+            .as_any()
+            .downcast_ref::<FileWrapper<'static, Disk>>()
+            .ok_or(VfsError::Unsupported)?
+            .clone();
+        let opts = fatfs::FsOptions::new().time_provider(WallTimeProvider);
+        let fs = fatfs::FileSystem::new(file, opts).map_err(as_vfs_err)?;
+        Ok(Self {
+            volume: Arc::new(FatVolume { fs, raw: None }),
+        })
+    }
+}
+
+impl VfsOps for FatFileSystemFromFile {
+    fn root_dir(&self) -> VfsNodeRef {
+        FatFileSystem::volume_root(&self.volume)
+    }
+}
+
+impl<'a> fatfs::IoBase for FileWrapper<'a, Disk> {
+    type Error = ();
+}
+
+impl<'a> IoTrait for FileWrapper<'a, Disk> {}
+
+impl<'a> fatfs::Read for FileWrapper<'a, Disk> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let mut file = self.0.lock();
+        file.read(buf)
+            .inspect_err(|e| error!("read error: {e:?}"))
+            .map_err(|_| ())
+    }
+}
+
+impl<'a> fatfs::Write for FileWrapper<'a, Disk> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        let mut file = self.0.lock();
+        file.write(buf)
+            .inspect_err(|e| error!("write error: {e:?}"))
+            .map_err(|_| ())
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        let mut file = self.0.lock();
+        file.flush()
+            .inspect_err(|e| error!("flush error: {e:?}"))
+            .map_err(|_| ())
+    }
+}
+
+impl<'a> fatfs::Seek for FileWrapper<'a, Disk> {
+    fn seek(&mut self, pos: fatfs::SeekFrom) -> Result<u64, Self::Error> {
+        let mut file = self.0.lock();
+        file.seek(pos)
+            .inspect_err(|e| error!("seek error: {e:?}"))
+            .map_err(|_| ())
+    }
+}
@@ -0,0 +1,204 @@
+//! Edits of directory entries that rust-fatfs does not make, done on the
+//! bytes of the volume: the timestamps of directories, and the `..` entries
+//! of moved directories.
+//!
+//! rust-fatfs keeps no directory entries in memory, other than those of the
+//! opened files, so it sees the edits.
+
+use alloc::{string::String, sync::Arc, vec, vec::Vec};
+
+use axerrno::{AxError, AxResult, ax_err};
+use fatfs::{Date, DateTime};
+
+use super::fsck::{self, DELETED, DIR_ENTRY_SIZE, FatType, Volume};
+use crate::dev::BlockDevice;
+
+/// The offsets of the fields of directory entries.
+const ATTR: usize = 11;
+const ACCESS_DATE: usize = 18;
+const CLUSTER_HIGH: usize = 20;
+const MODIFY_TIME: usize = 22;
+const MODIFY_DATE: usize = 24;
+const CLUSTER_LOW: usize = 26;
+
+/// A FAT volume on a block device, whose bytes are accessed directly.
+pub struct RawVolume {
+    dev: Arc<BlockDevice>,
+    vol: Volume,
+}
+
+/// A directory entry found by [`RawVolume::find`].
+struct RawEntry {
+    /// The position of the entry in the volume.
+    pos: u64,
+    /// The first cluster of the file or directory, or 0 if it has none.
+    cluster: u32,
+}
+
+impl RawVolume {
+    /// Opens the FAT volume on `dev`.
+    pub fn new(dev: Arc<BlockDevice>) -> AxResult<Self> {
+        let mut boot = [0; 512];
+        read_exact(&dev, 0, &mut boot)?;
+        let vol = Volume::parse(&boot, dev.size())?;
+        Ok(Self { dev, vol })
+    }
+
+    fn read(&self, pos: u64, buf: &mut [u8]) -> AxResult {
+        read_exact(&self.dev, pos, buf)
+    }
+
+    fn write(&self, pos: u64, buf: &[u8]) -> AxResult {
+        match self.dev.write_bytes(pos, buf) {
+            Ok(len) if len == buf.len() => Ok(()),
+            Ok(_) => ax_err!(WriteZero),
+            Err(_) => ax_err!(Io),
+        }
+    }
+
+    /// Returns the FAT entry of `cluster` in the first FAT.
+    fn fat_entry(&self, cluster: u32) -> AxResult<u32> {
+        let fat_type = self.vol.fat_type;
+        let mut bytes = [0; 4];
+        let len = if fat_type == FatType::Fat32 { 4 } else { 2 };
+        let pos = self.vol.fat_start + fat_type.entry_offset(cluster) as u64;
+        self.read(pos, &mut bytes[..len])?;
+        Ok(fat_type.decode_entry(cluster, &bytes))
+    }
+
+    /// Returns the extents `(position, size)` of the directory starting at
+    /// `cluster`, where 0 is the root directory.
+    fn dir_extents(&self, cluster: u32) -> AxResult<Vec<(u64, u64)>> {
+        let vol = &self.vol;
+        let mut cluster = match cluster {
+            0 if vol.fat_type == FatType::Fat32 => vol.root_cluster,
+            0 => return Ok(vec![(vol.root_dir_start, vol.root_dir_size)]),
+            cluster => cluster,
+        };
+        let bad_cluster = vol.fat_type.bad_cluster();
+        let mut extents = Vec::new();
+        // a chain longer than the volume has a loop
+        while extents.len() < vol.clusters as usize {
+            if !vol.is_data_cluster(cluster) {
+                break;
+            }
+            extents.push((vol.cluster_pos(cluster), vol.cluster_size as u64));
+            match self.fat_entry(cluster)? {
+                next if next > bad_cluster => return Ok(extents),
+                next => cluster = next,
+            }
+        }
+        ax_err!(InvalidData, "broken cluster chain")
+    }
+
+    /// Finds the entry named `name` in the directory starting at `dir`, where
+    /// 0 is the root directory. Names are compared case-insensitively.
+    fn find_in(&self, dir: u32, name: &str) -> AxResult<RawEntry> {
+        let name = name.to_uppercase();
+        let mut entry = [0; DIR_ENTRY_SIZE];
+        let mut long_name = Vec::new();
+        for (start, size) in self.dir_extents(dir)? {
+            for pos in (start..start + size).step_by(DIR_ENTRY_SIZE) {
+                self.read(pos, &mut entry)?;
+                match entry[0] {
+                    0 => return ax_err!(NotFound),
+                    DELETED => continue,
+                    _ => {}
+                }
+                if entry[ATTR] == fsck::ATTR_LONG_NAME {
+                    fsck::push_long_name(&mut long_name, &entry);
+                    continue;
+                }
+                let long = String::from_utf16(&long_name).unwrap_or_default();
+                long_name.clear();
+                if entry[ATTR] & fsck::ATTR_VOLUME_ID == 0
+                    && (long.to_uppercase() == name
+                        || fsck::short_name(&entry).to_uppercase() == name)
+                {
+                    let mut cluster = fsck::read_u16(&entry, CLUSTER_LOW) as u32;
+                    if self.vol.fat_type == FatType::Fat32 {
+                        cluster |= (fsck::read_u16(&entry, CLUSTER_HIGH) as u32) << 16;
+                    }
+                    return Ok(RawEntry { pos, cluster });
+                }
+            }
+        }
+        ax_err!(NotFound)
+    }
+
+    /// Finds the entry at `path` in the volume. The root directory has none.
+    fn find(&self, path: &str) -> AxResult<RawEntry> {
+        let mut found = None;
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            let dir = found.map_or(0, |entry: RawEntry| entry.cluster);
+            found = Some(self.find_in(dir, name)?);
+        }
+        found.ok_or(AxError::Unsupported)
+    }
+
+    /// Sets the last access date and the modification time in the entry at
+    /// `path`, which are unchanged if `None`.
+    pub fn set_times(&self, path: &str, atime: Option<Date>, mtime: Option<DateTime>) -> AxResult {
+        let entry = self.find(path)?;
+        if let Some(date) = atime {
+            self.write(entry.pos + ACCESS_DATE as u64, &encode_date(date))?;
+        }
+        if let Some(mtime) = mtime {
+            let (date, time) = (mtime.date, mtime.time);
+            let time = (time.hour << 11) | (time.min << 5) | (time.sec / 2);
+            self.write(entry.pos + MODIFY_TIME as u64, &time.to_le_bytes())?;
+            self.write(entry.pos + MODIFY_DATE as u64, &encode_date(date))?;
+        }
+        Ok(())
+    }
+
+    /// Points the `..` entry of the directory at `path` to the directory that
+    /// it is in now, after it is moved.
+    pub fn update_dot_dot(&self, path: &str) -> AxResult {
+        let path = path.trim_matches('/');
+        let dir = self.find(path)?;
+        let parent = match path.rsplit_once('/') {
+            Some((parent, _)) => self.find(parent)?.cluster,
+            None => 0, // the root directory
+        };
+        if !self.vol.is_data_cluster(dir.cluster) {
+            return ax_err!(InvalidData, "directory without clusters");
+        }
+        // the second entry after `.`, other than long name entries
+        let start = self.vol.cluster_pos(dir.cluster);
+        let mut entry = [0; DIR_ENTRY_SIZE];
+        let mut shorts = 0;
+        for pos in (start..start + self.vol.cluster_size as u64).step_by(DIR_ENTRY_SIZE) {
+            self.read(pos, &mut entry)?;
+            if entry[ATTR] == fsck::ATTR_LONG_NAME {
+                continue;
+            }
+            shorts += 1;
+            if shorts < 2 {
+                continue;
+            }
+            if entry[..ATTR] != *b"..         " {
+                break;
+            }
+            self.write(
+                pos + CLUSTER_HIGH as u64,
+                &((parent >> 16) as u16).to_le_bytes(),
+            )?;
+            return self.write(pos + CLUSTER_LOW as u64, &(parent as u16).to_le_bytes());
+        }
+        ax_err!(InvalidData, "no `..` entry")
+    }
+}
+
+fn read_exact(dev: &BlockDevice, pos: u64, buf: &mut [u8]) -> AxResult {
+    match dev.read_bytes(pos, buf) {
+        Ok(len) if len == buf.len() => Ok(()),
+        Ok(_) => ax_err!(UnexpectedEof),
+        Err(_) => ax_err!(Io),
+    }
+}
+
+/// Encodes a date as in directory entries.
+fn encode_date(date: Date) -> [u8; 2] {
+    (((date.year - 1980) << 9) | (date.month << 5) | date.day).to_le_bytes()
+}
@@ -1,43 +1,160 @@
 use crate::alloc::string::String;
-use alloc::sync::Arc;
-use axerrno::AxError;
+use alloc::{boxed::Box, ffi::CString, format, sync::Arc, vec, vec::Vec};
+use axerrno::{AxError, AxResult, ax_err};
 use axfs_vfs::{VfsDirEntry, VfsError, VfsNodePerm, VfsResult};
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps};
 use axsync::Mutex;
+use core::ffi::{CStr, c_int, c_void};
+use core::time::Duration;
 use lwext4_rust::bindings::{
-    O_CREAT, O_RDONLY, O_RDWR, O_TRUNC, O_WRONLY, SEEK_CUR, SEEK_END, SEEK_SET,
+    EIO, O_CREAT, O_RDONLY, O_RDWR, O_TRUNC, O_WRONLY, SEEK_CUR, SEEK_END, SEEK_SET,
+    ext4_atime_get, ext4_atime_set, ext4_blockdev, ext4_blockdev_iface, ext4_cache_flush,
+    ext4_ctime_get, ext4_ctime_set, ext4_device_register, ext4_device_unregister, ext4_flink,
+    ext4_fsymlink, ext4_inode, ext4_mode_set, ext4_mount, ext4_mtime_get, ext4_mtime_set,
+    ext4_owner_get, ext4_owner_set, ext4_raw_inode_fill, ext4_readlink, ext4_umount,
 };
-use lwext4_rust::{Ext4BlockWrapper, Ext4File, InodeTypes, KernelDevOp};
+use lwext4_rust::{Ext4File, InodeTypes, KernelDevOp};
 
+use super::{NodeMetaOps, now};
 use crate::dev::Disk;
+use crate::fops::FileMeta;
 pub const BLOCK_SIZE: usize = 512;
+/// The maximum length of the target of symbolic links.
+const MAX_LINK_LEN: usize = 4096;
 
-#[allow(dead_code)]
+/// An ext4 volume, registered with lwext4 under the name of its block device
+/// and mounted at `/<name>/`, so that several volumes can be opened at once.
 pub struct Ext4FileSystem {
-    inner: Ext4BlockWrapper<Disk>,
     root: VfsNodeRef,
+    name: CString,
+    mount_point: CString,
+    // lwext4 keeps pointers to these until the volume is unmounted
+    _blockdev: Box<ext4_blockdev>,
+    _iface: Box<ext4_blockdev_iface>,
+    _buf: Box<[u8; BLOCK_SIZE]>,
+    disk: Box<Disk>,
 }
 
 unsafe impl Sync for Ext4FileSystem {}
 unsafe impl Send for Ext4FileSystem {}
 
 impl Ext4FileSystem {
-    #[cfg(feature = "use-ramdisk")]
-    pub fn new(mut disk: Disk) -> Self {
-        unimplemented!()
-    }
-
-    #[cfg(not(feature = "use-ramdisk"))]
-    pub fn new(disk: Disk) -> Self {
+    /// Opens the ext4 volume on `disk`.
+    pub fn new(disk: Disk) -> AxResult<Self> {
         info!(
             "Got Disk size:{}, position:{}",
             disk.size(),
             disk.position()
         );
-        let inner =
-            Ext4BlockWrapper::<Disk>::new(disk).expect("failed to initialize EXT4 filesystem");
-        let root = Arc::new(FileWrapper::new("/", InodeTypes::EXT4_DE_DIR));
-        Self { inner, root }
+        let dev_name = disk.device().name();
+        let name = CString::new(dev_name).unwrap();
+        let mount_point = CString::new(format!("/{}/", dev_name)).unwrap();
+
+        let mut disk = Box::new(disk);
+        let mut buf = Box::new([0u8; BLOCK_SIZE]);
+        let mut iface = Box::new(ext4_blockdev_iface {
+            open: Some(disk_open),
+            bread: Some(disk_read),
+            bwrite: Some(disk_write),
+            close: Some(disk_close),
+            ph_bsize: BLOCK_SIZE as u32,
+            ph_bcnt: disk.size() / BLOCK_SIZE as u64,
+            ph_bbuf: buf.as_mut_ptr(),
+            p_user: &mut *disk as *mut Disk as *mut c_void,
+            ..unsafe { core::mem::zeroed() }
+        });
+        let mut blockdev = Box::new(ext4_blockdev {
+            bdif: &mut *iface,
+            part_offset: 0,
+            part_size: disk.size(),
+            ..unsafe { core::mem::zeroed() }
+        });
+
+        let ret = unsafe { ext4_device_register(&mut *blockdev, name.as_ptr()) };
+        if ret != 0 {
+            error!("failed to register EXT4 device {}: {}", dev_name, ret);
+            return ax_err!(ResourceBusy, "cannot register the ext4 volume");
+        }
+        let ret = unsafe { ext4_mount(name.as_ptr(), mount_point.as_ptr(), false) };
+        if ret != 0 {
+            unsafe { ext4_device_unregister(name.as_ptr()) };
+            error!("failed to initialize EXT4 filesystem: {}", ret);
+            return ax_err!(InvalidData);
+        }
+
+        let root_path = mount_point.to_str().unwrap();
+        let root = Arc::new(FileWrapper::new(root_path, InodeTypes::EXT4_DE_DIR));
+        Ok(Self {
+            root,
+            name,
+            mount_point,
+            _blockdev: blockdev,
+            _iface: iface,
+            _buf: buf,
+            disk,
+        })
+    }
+}
+
+impl Drop for Ext4FileSystem {
+    fn drop(&mut self) {
+        let ret = unsafe { ext4_umount(self.mount_point.as_ptr()) };
+        if ret != 0 {
+            error!("failed to unmount EXT4 filesystem: {}", ret);
+        }
+        unsafe { ext4_device_unregister(self.name.as_ptr()) };
+        if self.disk.flush().is_err() {
+            error!("failed to flush EXT4 device {:?}", self.name);
+        }
+    }
+}
+
+/// Returns the disk of the lwext4 block device `bdev`.
+///
+/// # Safety
+///
+/// `bdev` must be the block device of an [`Ext4FileSystem`].
+unsafe fn bdev_disk<'a>(bdev: *mut ext4_blockdev) -> &'a mut Disk {
+    unsafe { &mut *((*(*bdev).bdif).p_user as *mut Disk) }
+}
+
+unsafe extern "C" fn disk_open(_bdev: *mut ext4_blockdev) -> c_int {
+    0
+}
+
+unsafe extern "C" fn disk_close(_bdev: *mut ext4_blockdev) -> c_int {
+    0
+}
+
+unsafe extern "C" fn disk_read(
+    bdev: *mut ext4_blockdev,
+    buf: *mut c_void,
+    blk_id: u64,
+    blk_cnt: u32,
+) -> c_int {
+    let disk = unsafe { bdev_disk(bdev) };
+    let len = blk_cnt as usize * BLOCK_SIZE;
+    let buf = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, len) };
+    disk.set_position(blk_id * BLOCK_SIZE as u64);
+    match <Disk as KernelDevOp>::read(disk, buf) {
+        Ok(n) if n == len => 0,
+        _ => EIO as c_int,
+    }
+}
+
+unsafe extern "C" fn disk_write(
+    bdev: *mut ext4_blockdev,
+    buf: *const c_void,
+    blk_id: u64,
+    blk_cnt: u32,
+) -> c_int {
+    let disk = unsafe { bdev_disk(bdev) };
+    let len = blk_cnt as usize * BLOCK_SIZE;
+    let buf = unsafe { core::slice::from_raw_parts(buf as *const u8, len) };
+    disk.set_position(blk_id * BLOCK_SIZE as u64);
+    match <Disk as KernelDevOp>::write(disk, buf) {
+        Ok(n) if n == len => 0,
+        _ => EIO as c_int,
     }
 }
 
@@ -91,6 +208,70 @@ impl FileWrapper {
         info!("dealt with full path: {}", fpath.as_str());
         fpath
     }
+
+    /// Adds a hard link to the file `node` named `name` in this directory.
+    pub fn link(&self, name: &str, node: &FileWrapper) -> VfsResult {
+        let path = node.0.lock().get_path();
+        let link_path =
+            CString::new(self.path_deal_with(name)).map_err(|_| VfsError::InvalidInput)?;
+        ext4_result(unsafe { ext4_flink(path.as_ptr(), link_path.as_ptr()) })
+    }
+}
+
+/// Reads the target of the symbolic link at `path`.
+fn read_link(path: &CStr) -> VfsResult<Vec<u8>> {
+    let mut buf = vec![0u8; MAX_LINK_LEN];
+    let mut len = 0;
+    ext4_result(unsafe {
+        ext4_readlink(path.as_ptr(), buf.as_mut_ptr() as _, buf.len(), &mut len)
+    })?;
+    buf.truncate(len);
+    Ok(buf)
+}
+
+/// Sets the target of the symbolic link at `path`, creating it if it does not
+/// exist.
+fn set_link(path: &CStr, target: &[u8]) -> VfsResult {
+    let target = CString::new(target).map_err(|_| VfsError::InvalidInput)?;
+    ext4_result(unsafe { ext4_fsymlink(target.as_ptr(), path.as_ptr()) })
+}
+
+/// Sets the access, modification and change times of the inode at `path`,
+/// which are unchanged if `None`.
+fn set_times(
+    path: &CStr,
+    atime: Option<Duration>,
+    mtime: Option<Duration>,
+    ctime: Option<Duration>,
+) -> VfsResult {
+    let path = path.as_ptr();
+    if let Some(atime) = atime {
+        ext4_result(unsafe { ext4_atime_set(path, inode_time(atime)) })?;
+    }
+    if let Some(mtime) = mtime {
+        ext4_result(unsafe { ext4_mtime_set(path, inode_time(mtime)) })?;
+    }
+    if let Some(ctime) = ctime {
+        ext4_result(unsafe { ext4_ctime_set(path, inode_time(ctime)) })?;
+    }
+    Ok(())
+}
+
+/// Converts a time to the seconds stored in an inode.
+///
+/// lwext4 writes only the 32-bit seconds, without the epoch bits of the extra
+/// fields, which Linux reads as signed. So times are clamped to 2038-01-19,
+/// as Linux does for inodes without the extra fields, instead of wrapping.
+fn inode_time(time: Duration) -> u32 {
+    time.as_secs().min(i32::MAX as u64) as u32
+}
+
+/// Converts the return value of lwext4 functions.
+fn ext4_result(ret: c_int) -> VfsResult {
+    match ret {
+        0 => Ok(()),
+        e => Err(e.try_into().unwrap()),
+    }
 }
 
 /// The [`VfsNodeOps`] trait provides operations on a file or a directory.
@@ -116,7 +297,9 @@ impl VfsNodeOps for FileWrapper {
             }
         };
 
-        let size = if vtype == VfsNodeType::File {
+        let size = if vtype == VfsNodeType::SymLink {
+            read_link(&file.get_path())?.len() as u64
+        } else if vtype == VfsNodeType::File {
             let path = file.get_path();
             let path = path.to_str().unwrap();
             file.file_open(path, O_RDONLY)
@@ -162,17 +345,25 @@ impl VfsNodeOps for FileWrapper {
         if file.check_inode_exist(fpath, types.clone()) {
             Ok(())
         } else {
-            if types == InodeTypes::EXT4_DE_DIR {
+            let created: VfsResult = if types == InodeTypes::EXT4_DE_DIR {
                 file.dir_mk(fpath)
                     .map(|_v| ())
                     .map_err(|e| e.try_into().unwrap())
+            } else if types == InodeTypes::EXT4_DE_SYMLINK {
+                // the target is set by `write_at`
+                let fpath = CString::new(fpath).map_err(|_| VfsError::InvalidInput)?;
+                set_link(&fpath, b"")
             } else {
                 file.file_open(fpath, O_WRONLY | O_CREAT | O_TRUNC)
                     .expect("create file failed");
                 file.file_close()
                     .map(|_v| ())
                     .map_err(|e| e.try_into().unwrap())
-            }
+            };
+            created?;
+            let now = Some(now());
+            let fpath = CString::new(fpath).map_err(|_| VfsError::InvalidInput)?;
+            set_times(&fpath, now, now, now)
         }
     }
 
@@ -205,7 +396,8 @@ impl VfsNodeOps for FileWrapper {
             let path = path.to_str().unwrap();
             info!("Get the parent dir of {}", path);
             let path = path.trim_end_matches('/').trim_end_matches(|c| c != '/');
-            if !path.is_empty() {
+            // the root directory is the mount point `/<name>/`
+            if path.len() > 1 {
                 return Some(Arc::new(Self::new(path, InodeTypes::EXT4_DE_DIR)));
             }
         }
@@ -266,6 +458,9 @@ impl VfsNodeOps for FileWrapper {
         } else if file.check_inode_exist(fpath, InodeTypes::EXT4_DE_REG_FILE) {
             trace!("lookup new FILE FileWrapper");
             Ok(Arc::new(Self::new(fpath, InodeTypes::EXT4_DE_REG_FILE)))
+        } else if file.check_inode_exist(fpath, InodeTypes::EXT4_DE_SYMLINK) {
+            trace!("lookup new SYMLINK FileWrapper");
+            Ok(Arc::new(Self::new(fpath, InodeTypes::EXT4_DE_SYMLINK)))
         } else {
             Err(VfsError::NotFound)
         }
@@ -273,6 +468,14 @@ impl VfsNodeOps for FileWrapper {
 
     fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
         let mut file = self.0.lock();
+        if file.get_type() == InodeTypes::EXT4_DE_SYMLINK {
+            // the content of symbolic links is the target
+            let target = read_link(&file.get_path())?;
+            let start = target.len().min(offset as usize);
+            let len = (target.len() - start).min(buf.len());
+            buf[..len].copy_from_slice(&target[start..start + len]);
+            return Ok(len);
+        }
         let path = file.get_path();
         let path = path.to_str().unwrap();
         file.file_open(path, O_RDONLY)
@@ -288,6 +491,14 @@ impl VfsNodeOps for FileWrapper {
 
     fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
         let mut file = self.0.lock();
+        if file.get_type() == InodeTypes::EXT4_DE_SYMLINK {
+            // only the whole target can be set
+            if offset != 0 {
+                return Err(VfsError::InvalidInput);
+            }
+            set_link(&file.get_path(), buf)?;
+            return Ok(buf.len());
+        }
         let path = file.get_path();
         let path = path.to_str().unwrap();
         file.file_open(path, O_RDWR)
@@ -298,7 +509,19 @@ impl VfsNodeOps for FileWrapper {
         let r = file.file_write(buf);
 
         let _ = file.file_close();
-        r.map_err(|e| e.try_into().unwrap())
+        let len = r.map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+        let now = Some(now());
+        set_times(&file.get_path(), None, now, now)?;
+        Ok(len)
+    }
+
+    fn fsync(&self) -> VfsResult {
+        let path = self.0.lock().get_path();
+        // the volume is mounted at `/<name>/`, after its block device
+        let name = path.to_str().unwrap().split('/').nth(1).unwrap_or_default();
+        let mount_point = CString::new(format!("/{}/", name)).unwrap();
+        ext4_result(unsafe { ext4_cache_flush(mount_point.as_ptr()) })?;
+        crate::dev::find_block_device(name)?.fsync()
     }
 
     fn truncate(&self, size: u64) -> VfsResult {
@@ -311,12 +534,16 @@ impl VfsNodeOps for FileWrapper {
         let t = file.file_truncate(size);
 
         let _ = file.file_close();
-        t.map(|_v| ()).map_err(|e| e.try_into().unwrap())
+        t.map_err(|e| <i32 as TryInto<AxError>>::try_into(e).unwrap())?;
+        let now = Some(now());
+        set_times(&file.get_path(), None, now, now)
     }
 
     fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        let src_path = self.path_deal_with(src_path);
+        let dst_path = self.path_deal_with(dst_path);
         let mut file = self.0.lock();
-        file.file_rename(src_path, dst_path)
+        file.file_rename(&src_path, &dst_path)
             .map(|_v| ())
             .map_err(|e| e.try_into().unwrap())
     }
@@ -326,6 +553,54 @@ impl VfsNodeOps for FileWrapper {
     }
 }
 
+impl NodeMetaOps for FileWrapper {
+    fn meta(&self) -> VfsResult<FileMeta> {
+        let path = self.0.lock().get_path();
+        let path = path.as_ptr();
+        let (mut ino, mut inode) = (0, unsafe { core::mem::zeroed::<ext4_inode>() });
+        let (mut uid, mut gid) = (0, 0);
+        let (mut atime, mut mtime, mut ctime) = (0, 0, 0);
+        unsafe {
+            ext4_result(ext4_raw_inode_fill(path, &mut ino, &mut inode))?;
+            ext4_result(ext4_owner_get(path, &mut uid, &mut gid))?;
+            ext4_result(ext4_atime_get(path, &mut atime))?;
+            ext4_result(ext4_mtime_get(path, &mut mtime))?;
+            ext4_result(ext4_ctime_get(path, &mut ctime))?;
+        }
+        Ok(FileMeta {
+            ino: ino as u64,
+            nlink: inode.links_count as u64,
+            uid,
+            gid,
+            atime: Duration::from_secs(atime as u64),
+            mtime: Duration::from_secs(mtime as u64),
+            ctime: Duration::from_secs(ctime as u64),
+            // lwext4 does not expose the creation time
+            crtime: None,
+        })
+    }
+
+    fn set_perm(&self, perm: VfsNodePerm) -> VfsResult {
+        let path = self.0.lock().get_path();
+        // the file type bits are kept
+        ext4_result(unsafe { ext4_mode_set(path.as_ptr(), perm.bits() as u32) })?;
+        set_times(&path, None, None, Some(now()))
+    }
+
+    fn set_owner(&self, uid: Option<u32>, gid: Option<u32>) -> VfsResult {
+        let meta = self.meta()?;
+        let path = self.0.lock().get_path();
+        let (uid, gid) = (uid.unwrap_or(meta.uid), gid.unwrap_or(meta.gid));
+        ext4_result(unsafe { ext4_owner_set(path.as_ptr(), uid, gid) })?;
+        set_times(&path, None, None, Some(now()))
+    }
+
+    fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+        let path = self.0.lock().get_path();
+        set_times(&path, atime, mtime, Some(now()))
+    }
+}
+
 impl Drop for FileWrapper {
     fn drop(&mut self) {
         let mut file = self.0.lock();
@@ -343,7 +618,7 @@ impl KernelDevOp for Disk {
         trace!("READ block device buf={}", buf.len());
         let mut read_len = 0;
         while !buf.is_empty() {
-            match dev.read_one(buf) {
+            match dev.read_bytes(buf) {
                 Ok(0) => break,
                 Ok(n) => {
                     let tmp = buf;
@@ -360,7 +635,7 @@ impl KernelDevOp for Disk {
         trace!("WRITE block device buf={}", buf.len());
         let mut write_len = 0;
         while !buf.is_empty() {
-            match dev.write_one(buf) {
+            match dev.write_bytes(buf) {
                 Ok(0) => break,
                 Ok(n) => {
                     buf = &buf[n..];
@@ -372,7 +647,8 @@ impl KernelDevOp for Disk {
         trace!("WRITE rt len={}", write_len);
         Ok(write_len)
     }
-    fn flush(_dev: &mut Self::DevType) -> Result<usize, i32> {
+    fn flush(dev: &mut Self::DevType) -> Result<usize, i32> {
+        dev.flush().map_err(|_| -1)?;
         Ok(0)
     }
     fn seek(dev: &mut Disk, off: i64, whence: i32) -> Result<i64, i32> {
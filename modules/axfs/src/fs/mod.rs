@@ -1,16 +1,187 @@
-cfg_if::cfg_if! {
-    if #[cfg(feature = "myfs")] {
-        pub mod myfs;
-    } else if #[cfg(feature = "lwext4_rs")] {
-        pub mod lwext4_rust;
-    } else if #[cfg(feature = "fatfs")] {
-        pub mod fatfs;
-    }
+#[cfg(feature = "myfs")]
+pub mod myfs;
 
-}
+#[cfg(feature = "lwext4_rs")]
+pub mod lwext4_rust;
+
+#[cfg(feature = "fatfs")]
+pub mod fatfs;
 
 #[cfg(feature = "devfs")]
-pub use axfs_devfs as devfs;
+pub mod devfs;
 
 #[cfg(feature = "ramfs")]
-pub use axfs_ramfs as ramfs;
+pub mod ramfs;
+
+#[cfg(any(feature = "procfs", feature = "sysfs"))]
+pub mod procfs;
+
+#[cfg(feature = "sysfs")]
+pub mod sysfs;
+
+use axfs_vfs::{VfsError, VfsNodePerm, VfsNodeRef, VfsResult};
+use core::time::Duration;
+
+use crate::dev::{BLOCK_SIZE, BlockDevice};
+use crate::fops::{FileMeta, FsStat};
+
+/// Operations on the ownership and timestamps of nodes, which are not in
+/// [`VfsNodeOps`](axfs_vfs::VfsNodeOps).
+pub(crate) trait NodeMetaOps {
+    /// Gets the ownership and timestamps.
+    fn meta(&self) -> VfsResult<FileMeta>;
+
+    /// Sets the permissions.
+    fn set_perm(&self, _perm: VfsNodePerm) -> VfsResult {
+        Err(VfsError::Unsupported)
+    }
+
+    /// Sets the owner and the group, which are unchanged if `None`.
+    fn set_owner(&self, _uid: Option<u32>, _gid: Option<u32>) -> VfsResult {
+        Err(VfsError::Unsupported)
+    }
+
+    /// Sets the access and modification times, which are unchanged if `None`.
+    fn set_times(&self, _atime: Option<Duration>, _mtime: Option<Duration>) -> VfsResult {
+        Err(VfsError::Unsupported)
+    }
+
+    /// Gets the usage of the filesystem that the node is in, without its
+    /// type.
+    fn fs_stat(&self) -> VfsResult<FsStat> {
+        Err(VfsError::Unsupported)
+    }
+}
+
+/// Returns the current time, since the UNIX epoch.
+#[allow(dead_code)]
+pub(crate) fn now() -> Duration {
+    axhal::time::wall_time()
+}
+
+/// Returns the metadata operations of `node`, if its filesystem stores them.
+fn meta_ops(node: &VfsNodeRef) -> Option<&dyn NodeMetaOps> {
+    let any = node.as_any();
+    #[cfg(feature = "ramfs")]
+    if let Some(node) = ramfs::meta_ops(any) {
+        return Some(node);
+    }
+    #[cfg(feature = "fatfs")]
+    if let Some(node) = fatfs::meta_ops(any) {
+        return Some(node);
+    }
+    #[cfg(feature = "lwext4_rs")]
+    if let Some(node) = any.downcast_ref::<lwext4_rust::FileWrapper>() {
+        return Some(node);
+    }
+    let _ = any;
+    None
+}
+
+/// Gets the ownership and timestamps of `node`, which are zeros if its
+/// filesystem does not store them.
+pub(crate) fn node_meta(node: &VfsNodeRef) -> VfsResult<FileMeta> {
+    meta_ops(node).map_or(Ok(FileMeta::default()), |ops| ops.meta())
+}
+
+/// Sets the permissions of `node`.
+pub(crate) fn set_perm(node: &VfsNodeRef, perm: VfsNodePerm) -> VfsResult {
+    meta_ops(node).ok_or(VfsError::Unsupported)?.set_perm(perm)
+}
+
+/// Sets the owner and the group of `node`, which are unchanged if `None`.
+pub(crate) fn set_owner(node: &VfsNodeRef, uid: Option<u32>, gid: Option<u32>) -> VfsResult {
+    meta_ops(node)
+        .ok_or(VfsError::Unsupported)?
+        .set_owner(uid, gid)
+}
+
+/// Sets the access and modification times of `node`, which are unchanged if
+/// `None`.
+pub(crate) fn set_times(
+    node: &VfsNodeRef,
+    atime: Option<Duration>,
+    mtime: Option<Duration>,
+) -> VfsResult {
+    meta_ops(node)
+        .ok_or(VfsError::Unsupported)?
+        .set_times(atime, mtime)
+}
+
+/// Gets the usage of the filesystem that `node` is in, without its type.
+pub(crate) fn fs_stat(node: &VfsNodeRef) -> VfsResult<FsStat> {
+    match meta_ops(node).map(|ops| ops.fs_stat()) {
+        Some(Err(VfsError::Unsupported)) | None => Ok(FsStat {
+            name_max: 255,
+            ..Default::default()
+        }),
+        Some(res) => res,
+    }
+}
+
+/// Filesystem types that can be on block devices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DiskFsType {
+    Fat,
+    Ext4,
+}
+
+impl DiskFsType {
+    /// Returns the name of the filesystem type, as in `/proc/mounts`.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Fat => "vfat",
+            Self::Ext4 => "ext4",
+        }
+    }
+
+    /// Parses the filesystem type given to `mount`.
+    pub fn from_name(fstype: &str) -> Option<Self> {
+        match fstype {
+            "vfat" | "fat" | "msdos" => Some(Self::Fat),
+            "ext4" => Some(Self::Ext4),
+            _ => None,
+        }
+    }
+
+    /// Detects the filesystem on `dev` by the magic numbers of its
+    /// superblock.
+    pub fn probe(dev: &BlockDevice) -> Option<Self> {
+        let mut block = [0; BLOCK_SIZE];
+        // the ext4 superblock is at 1024, with the magic number at 56
+        if dev.read_block(2, &mut block).is_ok() && block[56..58] == [0x53, 0xef] {
+            return Some(Self::Ext4);
+        }
+        if dev.read_block(0, &mut block).is_ok() && is_fat_boot_sector(&block) {
+            return Some(Self::Fat);
+        }
+        None
+    }
+}
+
+/// Whether `block` is the boot sector of a FAT volume.
+pub(crate) fn is_fat_boot_sector(block: &[u8; BLOCK_SIZE]) -> bool {
+    // the filesystem type is at 54 (FAT12/16) or 82 (FAT32)
+    block[510..512] == [0x55, 0xaa] && (block[54..57] == *b"FAT" || block[82..87] == *b"FAT32")
+}
+
+/// Adds a hard link to the file `node` named `name` in the directory `dir`,
+/// which are in the same filesystem.
+///
+/// Only ramfs and ext4 support hard links.
+#[allow(unused_variables)]
+pub(crate) fn link_node(dir: &VfsNodeRef, name: &str, node: &VfsNodeRef) -> VfsResult {
+    #[cfg(feature = "ramfs")]
+    if let Some(dir) = dir.as_any().downcast_ref::<ramfs::DirNode>() {
+        return dir.link_node(name, node.clone());
+    }
+    #[cfg(feature = "lwext4_rs")]
+    if let Some(dir) = dir.as_any().downcast_ref::<lwext4_rust::FileWrapper>() {
+        let node = node
+            .as_any()
+            .downcast_ref::<lwext4_rust::FileWrapper>()
+            .ok_or(VfsError::InvalidInput)?;
+        return dir.link(name, node);
+    }
+    Err(VfsError::Unsupported)
+}
@@ -0,0 +1,372 @@
+//! Process filesystem, whose files are generated from the kernel state when
+//! they are read.
+//!
+//! As `seq_file` on Linux, the content is generated on the first read of each
+//! opened file, and again on reads from the start, so a file read in pieces
+//! is consistent.
+//!
+//! Other modules add their files by [`add_file`], and the entries that
+//! change over time, e.g., a directory for each task, by [`add_entries`].
+//! Files added by [`add_writable_file`] pass what is written to them to a
+//! handler, to change the kernel state.
+
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+use axerrno::{AxResult, ax_err};
+use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeRef, VfsNodeType};
+use axfs_vfs::{VfsError, VfsOps, VfsResult};
+use spin::{Mutex, RwLock, once::Once};
+
+/// Generates the content of a [`ProcFile`].
+type ContentFn = dyn Fn() -> String + Send + Sync;
+
+/// Handles what is written to a [`ProcFile`].
+type StoreFn = dyn Fn(&str) -> AxResult + Send + Sync;
+
+/// Generates the entries of a [`ProcDir`] on each lookup.
+type EntriesFn = dyn Fn() -> Vec<(String, VfsNodeRef)> + Send + Sync;
+
+/// A file in procfs, whose content is generated when it is read.
+///
+/// Each lookup returns a new handle of the file, which keeps the content
+/// generated by its first read.
+pub struct ProcFile {
+    content: Arc<ContentFn>,
+    store: Option<Arc<StoreFn>>,
+    /// The content of this handle, generated by the last read from the start.
+    snapshot: Mutex<Option<String>>,
+}
+
+impl ProcFile {
+    /// Creates a read-only file whose content is generated by `content`.
+    pub fn new<F>(content: F) -> Arc<Self>
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        Arc::new(Self {
+            content: Arc::new(content),
+            store: None,
+            snapshot: Mutex::new(None),
+        })
+    }
+
+    /// Creates a file whose content is generated by `content`, and what is
+    /// written to it is passed to `store`.
+    ///
+    /// Each write passes the whole value, without the trailing newline. It
+    /// fails with the error returned by `store`, e.g., when the value is
+    /// invalid.
+    pub fn new_writable<F, S>(content: F, store: S) -> Arc<Self>
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+        S: Fn(&str) -> AxResult + Send + Sync + 'static,
+    {
+        Arc::new(Self {
+            content: Arc::new(content),
+            store: Some(Arc::new(store)),
+            snapshot: Mutex::new(None),
+        })
+    }
+
+    /// Returns a new handle of this file, without the generated content.
+    fn handle(&self) -> Arc<Self> {
+        Arc::new(Self {
+            content: self.content.clone(),
+            store: self.store.clone(),
+            snapshot: Mutex::new(None),
+        })
+    }
+}
+
+impl VfsNodeOps for ProcFile {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        // the size is unknown until the content is generated, as on Linux
+        let perm = if self.store.is_some() { 0o644 } else { 0o444 };
+        let perm = VfsNodePerm::from_bits_truncate(perm);
+        Ok(VfsNodeAttr::new(perm, VfsNodeType::File, 0, 0))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut snapshot = self.snapshot.lock();
+        if offset == 0 || snapshot.is_none() {
+            *snapshot = Some((self.content)());
+        }
+        let content = snapshot.as_ref().unwrap();
+        let start = content.len().min(offset as usize);
+        let len = buf.len().min(content.len() - start);
+        buf[..len].copy_from_slice(&content.as_bytes()[start..start + len]);
+        Ok(len)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let store = self.store.as_ref().ok_or(VfsError::PermissionDenied)?;
+        // values are not written in pieces
+        if offset != 0 {
+            return Err(VfsError::InvalidInput);
+        }
+        let value = core::str::from_utf8(buf).map_err(|_| VfsError::InvalidInput)?;
+        store(value.strip_suffix('\n').unwrap_or(value))?;
+        *self.snapshot.lock() = None;
+        Ok(buf.len())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        // opened with `O_TRUNC` before being written
+        match self.store {
+            Some(_) => Ok(()),
+            None => Err(VfsError::PermissionDenied),
+        }
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
+
+/// A directory in procfs, which has the fixed entries added to it and the
+/// entries generated on each lookup.
+pub struct ProcDir {
+    this: Weak<ProcDir>,
+    parent: RwLock<Weak<dyn VfsNodeOps>>,
+    children: RwLock<BTreeMap<String, VfsNodeRef>>,
+    generators: RwLock<Vec<Box<EntriesFn>>>,
+}
+
+impl ProcDir {
+    /// Creates an empty directory.
+    pub fn new() -> Arc<Self> {
+        Arc::new_cyclic(|this| Self {
+            this: this.clone(),
+            parent: RwLock::new(Weak::<Self>::new()),
+            children: RwLock::new(BTreeMap::new()),
+            generators: RwLock::new(Vec::new()),
+        })
+    }
+
+    fn set_parent(&self, parent: Option<&VfsNodeRef>) {
+        *self.parent.write() = parent.map_or(Weak::<Self>::new() as _, Arc::downgrade);
+    }
+
+    /// Adds `node` with the given name to this directory, replacing the
+    /// existing one.
+    pub fn add(&self, name: &str, node: VfsNodeRef) {
+        self.adopt(&node);
+        self.children.write().insert(name.into(), node);
+    }
+
+    /// Adds entries generated by `entries` on each lookup to this directory.
+    pub fn add_entries<F>(&self, entries: F)
+    where
+        F: Fn() -> Vec<(String, VfsNodeRef)> + Send + Sync + 'static,
+    {
+        self.generators.write().push(Box::new(entries));
+    }
+
+    /// Returns the subdirectory with the given name, which is created if it
+    /// does not exist.
+    fn subdir(&self, name: &str) -> AxResult<Arc<Self>> {
+        let mut children = self.children.write();
+        if let Some(node) = children.get(name) {
+            return match node.as_any().downcast_ref::<Self>() {
+                Some(dir) => Ok(dir.this.upgrade().unwrap()),
+                None => ax_err!(NotADirectory),
+            };
+        }
+        let dir = Self::new();
+        *dir.parent.write() = self.this.clone();
+        children.insert(name.into(), dir.clone());
+        Ok(dir)
+    }
+
+    /// Sets the parent of `node` to this directory, if it is a [`ProcDir`].
+    fn adopt(&self, node: &VfsNodeRef) {
+        if let Some(dir) = node.as_any().downcast_ref::<Self>() {
+            *dir.parent.write() = self.this.clone();
+        }
+    }
+
+    /// Returns the entry with the given name, which is a new handle if it is
+    /// a [`ProcFile`].
+    fn entry(&self, name: &str) -> Option<VfsNodeRef> {
+        if let Some(node) = self.children.read().get(name) {
+            return Some(match node.as_any().downcast_ref::<ProcFile>() {
+                Some(file) => file.handle(),
+                None => node.clone(),
+            });
+        }
+        self.generated()
+            .into_iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, node)| node)
+    }
+
+    /// Generates the entries that change over time.
+    fn generated(&self) -> Vec<(String, VfsNodeRef)> {
+        let mut entries = Vec::new();
+        for generate in self.generators.read().iter() {
+            entries.extend(generate());
+        }
+        for (_, node) in &entries {
+            self.adopt(node);
+        }
+        entries
+    }
+}
+
+impl VfsNodeOps for ProcDir {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let perm = VfsNodePerm::from_bits_truncate(0o555);
+        Ok(VfsNodeAttr::new(perm, VfsNodeType::Dir, 0, 0))
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        self.parent.read().upgrade()
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        let (name, rest) = split_path(path);
+        let node = match name {
+            "" | "." => Ok(self.clone() as VfsNodeRef),
+            ".." => self.parent().ok_or(VfsError::NotFound),
+            _ => self.entry(name).ok_or(VfsError::NotFound),
+        }?;
+
+        if let Some(rest) = rest {
+            node.lookup(rest)
+        } else {
+            Ok(node)
+        }
+    }
+
+    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        let children = self.children.read().clone();
+        let generated = self.generated();
+        let mut entries = children
+            .iter()
+            .chain(generated.iter().map(|(name, node)| (name, node)))
+            .skip(start_idx.max(2) - 2);
+        for (i, ent) in dirents.iter_mut().enumerate() {
+            match i + start_idx {
+                0 => *ent = VfsDirEntry::new(".", VfsNodeType::Dir),
+                1 => *ent = VfsDirEntry::new("..", VfsNodeType::Dir),
+                _ => {
+                    if let Some((name, node)) = entries.next() {
+                        *ent = VfsDirEntry::new(name, node.get_attr()?.file_type());
+                    } else {
+                        return Ok(i);
+                    }
+                }
+            }
+        }
+        Ok(dirents.len())
+    }
+
+    fn create(&self, _path: &str, _ty: VfsNodeType) -> VfsResult {
+        Err(VfsError::PermissionDenied)
+    }
+
+    fn remove(&self, _path: &str) -> VfsResult {
+        Err(VfsError::PermissionDenied)
+    }
+
+    axfs_vfs::impl_vfs_dir_default! {}
+}
+
+/// The process filesystem that implements [`axfs_vfs::VfsOps`].
+///
+/// There is only one instance, which is shared by all the mounts. The
+/// instance of [sysfs](super::sysfs) is built in the same way.
+pub struct ProcFileSystem {
+    parent: Once<VfsNodeRef>,
+    root: Arc<ProcDir>,
+}
+
+static PROCFS: Once<Arc<ProcFileSystem>> = Once::new();
+
+impl ProcFileSystem {
+    pub(super) fn new() -> Self {
+        Self {
+            parent: Once::new(),
+            root: ProcDir::new(),
+        }
+    }
+
+    /// Returns the root directory node in [`Arc<ProcDir>`](ProcDir).
+    pub fn root_dir_node(&self) -> Arc<ProcDir> {
+        self.root.clone()
+    }
+
+    /// Adds `file` at `path`, creating the missing directories.
+    pub(super) fn add_node(&self, path: &str, file: Arc<ProcFile>) -> AxResult {
+        let path = path.trim_matches('/');
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+        if name.is_empty() {
+            return ax_err!(InvalidInput);
+        }
+        self.dir_at(dir)?.add(name, file);
+        Ok(())
+    }
+
+    /// Returns the directory at `path`, creating the missing ones.
+    pub(super) fn dir_at(&self, path: &str) -> AxResult<Arc<ProcDir>> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self.root.clone(), |dir, name| dir.subdir(name))
+    }
+}
+
+impl VfsOps for ProcFileSystem {
+    fn mount(&self, _path: &str, mount_point: VfsNodeRef) -> VfsResult {
+        if let Some(parent) = mount_point.parent() {
+            self.root.set_parent(Some(self.parent.call_once(|| parent)));
+        } else {
+            self.root.set_parent(None);
+        }
+        Ok(())
+    }
+
+    fn root_dir(&self) -> VfsNodeRef {
+        self.root.clone()
+    }
+}
+
+/// Returns the process filesystem.
+pub fn procfs() -> Arc<ProcFileSystem> {
+    PROCFS.call_once(|| Arc::new(ProcFileSystem::new())).clone()
+}
+
+/// Adds a file at `path` in procfs, whose content is generated by `content`
+/// when it is read. The missing directories in `path` are created.
+pub fn add_file<F>(path: &str, content: F) -> AxResult
+where
+    F: Fn() -> String + Send + Sync + 'static,
+{
+    procfs().add_node(path, ProcFile::new(content))
+}
+
+/// Adds a writable file at `path` in procfs, as [`ProcFile::new_writable`].
+/// The missing directories in `path` are created.
+pub fn add_writable_file<F, S>(path: &str, content: F, store: S) -> AxResult
+where
+    F: Fn() -> String + Send + Sync + 'static,
+    S: Fn(&str) -> AxResult + Send + Sync + 'static,
+{
+    procfs().add_node(path, ProcFile::new_writable(content, store))
+}
+
+/// Adds the entries generated by `entries` on each lookup to the directory at
+/// `path` in procfs. The missing directories in `path` are created.
+pub fn add_entries<F>(path: &str, entries: F) -> AxResult
+where
+    F: Fn() -> Vec<(String, VfsNodeRef)> + Send + Sync + 'static,
+{
+    procfs().dir_at(path)?.add_entries(entries);
+    Ok(())
+}
+
+fn split_path(path: &str) -> (&str, Option<&str>) {
+    let trimmed_path = path.trim_start_matches('/');
+    trimmed_path.find('/').map_or((trimmed_path, None), |n| {
+        (&trimmed_path[..n], Some(&trimmed_path[n + 1..]))
+    })
+}
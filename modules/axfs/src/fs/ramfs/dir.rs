@@ -0,0 +1,265 @@
+use alloc::collections::BTreeMap;
+use alloc::sync::{Arc, Weak};
+use alloc::{string::String, vec::Vec};
+
+use axfs_vfs::{VfsDirEntry, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeRef, VfsNodeType};
+use axfs_vfs::{VfsError, VfsResult};
+use spin::RwLock;
+
+use super::{FileNode, NodeMeta, SymlinkNode, node_meta};
+
+/// The directory node in the RAM filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`].
+pub struct DirNode {
+    this: Weak<DirNode>,
+    parent: RwLock<Weak<dyn VfsNodeOps>>,
+    children: RwLock<BTreeMap<String, VfsNodeRef>>,
+    pub(super) meta: RwLock<NodeMeta>,
+}
+
+impl DirNode {
+    pub(super) fn new(parent: Option<Weak<dyn VfsNodeOps>>) -> Arc<Self> {
+        let mut meta = NodeMeta::new(VfsNodePerm::default_dir());
+        // the entry in the parent, and `.`
+        meta.meta.nlink = 2;
+        Arc::new_cyclic(|this| Self {
+            this: this.clone(),
+            parent: RwLock::new(parent.unwrap_or_else(|| Weak::<Self>::new())),
+            children: RwLock::new(BTreeMap::new()),
+            meta: RwLock::new(meta),
+        })
+    }
+
+    pub(super) fn set_parent(&self, parent: Option<&VfsNodeRef>) {
+        *self.parent.write() = parent.map_or(Weak::<Self>::new() as _, Arc::downgrade);
+    }
+
+    /// Returns a string list of all entries in this directory.
+    pub fn get_entries(&self) -> Vec<String> {
+        self.children.read().keys().cloned().collect()
+    }
+
+    /// Checks whether a node with the given name exists in this directory.
+    pub fn exist(&self, name: &str) -> bool {
+        self.children.read().contains_key(name)
+    }
+
+    /// Creates a new node with the given name and type in this directory.
+    pub fn create_node(&self, name: &str, ty: VfsNodeType) -> VfsResult {
+        if self.exist(name) {
+            error!("AlreadyExists {}", name);
+            return Err(VfsError::AlreadyExists);
+        }
+        let node: VfsNodeRef = match ty {
+            VfsNodeType::File => Arc::new(FileNode::new()),
+            VfsNodeType::Dir => Self::new(Some(self.this.clone())),
+            VfsNodeType::SymLink => Arc::new(SymlinkNode::new()),
+            _ => return Err(VfsError::Unsupported),
+        };
+        self.children.write().insert(name.into(), node);
+        let mut meta = self.meta.write();
+        meta.modified();
+        if ty == VfsNodeType::Dir {
+            // `..` in the new directory
+            meta.linked(1);
+        }
+        Ok(())
+    }
+
+    /// Adds a hard link to `node` with the given name in this directory.
+    ///
+    /// `node` must be a file or a symbolic link in the same filesystem.
+    pub fn link_node(&self, name: &str, node: VfsNodeRef) -> VfsResult {
+        let any = node.as_any();
+        if !any.is::<FileNode>() && !any.is::<SymlinkNode>() {
+            return Err(VfsError::InvalidInput);
+        }
+        let mut children = self.children.write();
+        if children.contains_key(name) {
+            return Err(VfsError::AlreadyExists);
+        }
+        if let Some(meta) = node_meta(&node) {
+            meta.write().linked(1);
+        }
+        children.insert(name.into(), node);
+        self.meta.write().modified();
+        Ok(())
+    }
+
+    /// Removes a node by the given name in this directory.
+    pub fn remove_node(&self, name: &str) -> VfsResult {
+        let mut children = self.children.write();
+        let node = children.get(name).ok_or(VfsError::NotFound)?;
+        if let Some(dir) = node.as_any().downcast_ref::<DirNode>() {
+            if !dir.children.read().is_empty() {
+                return Err(VfsError::DirectoryNotEmpty);
+            }
+        }
+        let node = children.remove(name).unwrap();
+        self.unlinked(&node);
+        self.meta.write().modified();
+        Ok(())
+    }
+
+    /// Updates the link counts after `node` is removed from this directory.
+    fn unlinked(&self, node: &VfsNodeRef) {
+        if let Some(meta) = node_meta(node) {
+            meta.write().linked(-1);
+        }
+        if node.as_any().is::<DirNode>() {
+            self.meta.write().linked(-1);
+        }
+    }
+
+    /// Returns the directory that `path` is in, and the last component of it.
+    fn parent_of(self: Arc<Self>, path: &str) -> VfsResult<(Arc<Self>, String)> {
+        let path = path.trim_end_matches('/');
+        let (dir, name) = match path.rfind('/') {
+            Some(n) => (self.lookup(&path[..n])?, &path[n + 1..]),
+            None => (self as VfsNodeRef, path),
+        };
+        if name.is_empty() || name == "." || name == ".." {
+            return Err(VfsError::InvalidInput);
+        }
+        let dir = dir
+            .as_any()
+            .downcast_ref::<Self>()
+            .and_then(|dir| dir.this.upgrade())
+            .ok_or(VfsError::NotADirectory)?;
+        Ok((dir, name.into()))
+    }
+}
+
+impl VfsNodeOps for DirNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let perm = self.meta.read().perm;
+        Ok(VfsNodeAttr::new(perm, VfsNodeType::Dir, 4096, 0))
+    }
+
+    fn parent(&self) -> Option<VfsNodeRef> {
+        self.parent.read().upgrade()
+    }
+
+    fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
+        let (name, rest) = split_path(path);
+        let node = match name {
+            "" | "." => Ok(self.clone() as VfsNodeRef),
+            ".." => self.parent().ok_or(VfsError::NotFound),
+            _ => self
+                .children
+                .read()
+                .get(name)
+                .cloned()
+                .ok_or(VfsError::NotFound),
+        }?;
+
+        if let Some(rest) = rest {
+            node.lookup(rest)
+        } else {
+            Ok(node)
+        }
+    }
+
+    fn read_dir(&self, start_idx: usize, dirents: &mut [VfsDirEntry]) -> VfsResult<usize> {
+        let children = self.children.read();
+        let mut children = children.iter().skip(start_idx.max(2) - 2);
+        for (i, ent) in dirents.iter_mut().enumerate() {
+            match i + start_idx {
+                0 => *ent = VfsDirEntry::new(".", VfsNodeType::Dir),
+                1 => *ent = VfsDirEntry::new("..", VfsNodeType::Dir),
+                _ => {
+                    if let Some((name, node)) = children.next() {
+                        *ent = VfsDirEntry::new(name, node.get_attr()?.file_type());
+                    } else {
+                        return Ok(i);
+                    }
+                }
+            }
+        }
+        Ok(dirents.len())
+    }
+
+    fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
+        debug!("create {:?} at ramfs: {}", ty, path);
+        let (name, rest) = split_path(path);
+        if let Some(rest) = rest {
+            match name {
+                "" | "." => self.create(rest, ty),
+                ".." => self.parent().ok_or(VfsError::NotFound)?.create(rest, ty),
+                _ => {
+                    let subdir = self
+                        .children
+                        .read()
+                        .get(name)
+                        .ok_or(VfsError::NotFound)?
+                        .clone();
+                    subdir.create(rest, ty)
+                }
+            }
+        } else if name.is_empty() || name == "." || name == ".." {
+            Ok(()) // already exists
+        } else {
+            self.create_node(name, ty)
+        }
+    }
+
+    fn remove(&self, path: &str) -> VfsResult {
+        debug!("remove at ramfs: {}", path);
+        let (name, rest) = split_path(path);
+        if let Some(rest) = rest {
+            match name {
+                "" | "." => self.remove(rest),
+                ".." => self.parent().ok_or(VfsError::NotFound)?.remove(rest),
+                _ => {
+                    let subdir = self
+                        .children
+                        .read()
+                        .get(name)
+                        .ok_or(VfsError::NotFound)?
+                        .clone();
+                    subdir.remove(rest)
+                }
+            }
+        } else if name.is_empty() || name == "." || name == ".." {
+            Err(VfsError::InvalidInput) // remove '.' or '..
+        } else {
+            self.remove_node(name)
+        }
+    }
+
+    fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
+        debug!(
+            "rename at ramfs, src_path: {}, dst_path: {}",
+            src_path, dst_path
+        );
+        let this = self.this.upgrade().ok_or(VfsError::NotFound)?;
+        let (src_dir, src_name) = this.clone().parent_of(src_path)?;
+        let (dst_dir, dst_name) = this.parent_of(dst_path)?;
+        let node = src_dir
+            .children
+            .write()
+            .remove(&src_name)
+            .ok_or(VfsError::NotFound)?;
+        if let Some(dir) = node.as_any().downcast_ref::<DirNode>() {
+            *dir.parent.write() = dst_dir.this.clone() as Weak<dyn VfsNodeOps>;
+            src_dir.meta.write().linked(-1);
+            dst_dir.meta.write().linked(1);
+        }
+        if let Some(old) = dst_dir.children.write().insert(dst_name, node) {
+            dst_dir.unlinked(&old);
+        }
+        src_dir.meta.write().modified();
+        dst_dir.meta.write().modified();
+        Ok(())
+    }
+
+    axfs_vfs::impl_vfs_dir_default! {}
+}
+
+fn split_path(path: &str) -> (&str, Option<&str>) {
+    let trimmed_path = path.trim_start_matches('/');
+    trimmed_path.find('/').map_or((trimmed_path, None), |n| {
+        (&trimmed_path[..n], Some(&trimmed_path[n + 1..]))
+    })
+}
@@ -0,0 +1,64 @@
+use alloc::vec::Vec;
+use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+use spin::RwLock;
+
+use super::NodeMeta;
+
+/// The file node in the RAM filesystem.
+///
+/// It implements [`axfs_vfs::VfsNodeOps`].
+pub struct FileNode {
+    content: RwLock<Vec<u8>>,
+    pub(super) meta: RwLock<NodeMeta>,
+}
+
+impl FileNode {
+    pub(super) fn new() -> Self {
+        Self {
+            content: RwLock::new(Vec::new()),
+            meta: RwLock::new(NodeMeta::new(VfsNodePerm::default_file())),
+        }
+    }
+}
+
+impl VfsNodeOps for FileNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        let size = self.content.read().len() as u64;
+        let perm = self.meta.read().perm;
+        Ok(VfsNodeAttr::new(perm, VfsNodeType::File, size, 0))
+    }
+
+    fn truncate(&self, size: u64) -> VfsResult {
+        let mut content = self.content.write();
+        if size < content.len() as u64 {
+            content.truncate(size as _);
+        } else {
+            content.resize(size as _, 0);
+        }
+        self.meta.write().modified();
+        Ok(())
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let content = self.content.read();
+        let start = content.len().min(offset as usize);
+        let end = content.len().min(offset as usize + buf.len());
+        let src = &content[start..end];
+        buf[..src.len()].copy_from_slice(src);
+        self.meta.write().accessed();
+        Ok(src.len())
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let offset = offset as usize;
+        let mut content = self.content.write();
+        if offset + buf.len() > content.len() {
+            content.resize(offset + buf.len(), 0);
+        }
+        content[offset..offset + buf.len()].copy_from_slice(buf);
+        self.meta.write().modified();
+        Ok(buf.len())
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
@@ -0,0 +1,173 @@
+//! RAM filesystem, with symbolic links and hard links.
+
+mod dir;
+mod file;
+mod symlink;
+
+pub use self::dir::DirNode;
+pub use self::file::FileNode;
+pub use self::symlink::SymlinkNode;
+
+use alloc::sync::Arc;
+use axfs_vfs::{VfsNodePerm, VfsNodeRef, VfsOps, VfsResult};
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::{any::Any, time::Duration};
+use spin::{RwLock, once::Once};
+
+use super::{NodeMetaOps, now};
+use crate::fops::FileMeta;
+
+/// The next inode number, shared by all ramfs instances.
+static NEXT_INO: AtomicU64 = AtomicU64::new(1);
+
+/// The permissions, ownership and timestamps of a node.
+struct NodeMeta {
+    perm: VfsNodePerm,
+    meta: FileMeta,
+}
+
+impl NodeMeta {
+    /// Creates the metadata of a node created now, owned by root.
+    fn new(perm: VfsNodePerm) -> Self {
+        let now = now();
+        Self {
+            perm,
+            meta: FileMeta {
+                ino: NEXT_INO.fetch_add(1, Ordering::Relaxed),
+                nlink: 1,
+                atime: now,
+                mtime: now,
+                ctime: now,
+                crtime: Some(now),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Updates the times after the content is accessed.
+    fn accessed(&mut self) {
+        self.meta.atime = now();
+    }
+
+    /// Updates the times after the content is modified.
+    fn modified(&mut self) {
+        let now = now();
+        self.meta.mtime = now;
+        self.meta.ctime = now;
+    }
+
+    /// Updates the link count after `delta` links are added, or removed if
+    /// it is negative.
+    fn linked(&mut self, delta: i64) {
+        self.meta.nlink = self.meta.nlink.saturating_add_signed(delta);
+        self.meta.ctime = now();
+    }
+}
+
+/// Returns the metadata of `node`, if it is a ramfs node.
+fn node_meta(node: &VfsNodeRef) -> Option<&RwLock<NodeMeta>> {
+    let node = node.as_any();
+    if let Some(node) = node.downcast_ref::<DirNode>() {
+        Some(&node.meta)
+    } else if let Some(node) = node.downcast_ref::<FileNode>() {
+        Some(&node.meta)
+    } else if let Some(node) = node.downcast_ref::<SymlinkNode>() {
+        Some(&node.meta)
+    } else {
+        None
+    }
+}
+
+/// Implements [`NodeMetaOps`] for a node with a `meta: RwLock<NodeMeta>`
+/// field.
+macro_rules! impl_node_meta_ops {
+    ($ty: ty) => {
+        impl NodeMetaOps for $ty {
+            fn meta(&self) -> VfsResult<FileMeta> {
+                Ok(self.meta.read().meta)
+            }
+
+            fn set_perm(&self, perm: VfsNodePerm) -> VfsResult {
+                let mut meta = self.meta.write();
+                meta.perm = perm;
+                meta.meta.ctime = now();
+                Ok(())
+            }
+
+            fn set_owner(&self, uid: Option<u32>, gid: Option<u32>) -> VfsResult {
+                let mut meta = self.meta.write();
+                meta.meta.uid = uid.unwrap_or(meta.meta.uid);
+                meta.meta.gid = gid.unwrap_or(meta.meta.gid);
+                meta.meta.ctime = now();
+                Ok(())
+            }
+
+            fn set_times(&self, atime: Option<Duration>, mtime: Option<Duration>) -> VfsResult {
+                let mut meta = self.meta.write();
+                meta.meta.atime = atime.unwrap_or(meta.meta.atime);
+                meta.meta.mtime = mtime.unwrap_or(meta.meta.mtime);
+                meta.meta.ctime = now();
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_node_meta_ops!(DirNode);
+impl_node_meta_ops!(FileNode);
+impl_node_meta_ops!(SymlinkNode);
+
+/// Returns the metadata operations of `node`, if it is a ramfs node.
+pub(super) fn meta_ops(node: &dyn Any) -> Option<&dyn NodeMetaOps> {
+    if let Some(node) = node.downcast_ref::<DirNode>() {
+        Some(node)
+    } else if let Some(node) = node.downcast_ref::<FileNode>() {
+        Some(node)
+    } else if let Some(node) = node.downcast_ref::<SymlinkNode>() {
+        Some(node)
+    } else {
+        None
+    }
+}
+
+/// A RAM filesystem that implements [`axfs_vfs::VfsOps`].
+pub struct RamFileSystem {
+    parent: Once<VfsNodeRef>,
+    root: Arc<DirNode>,
+}
+
+impl RamFileSystem {
+    /// Create a new instance.
+    pub fn new() -> Self {
+        Self {
+            parent: Once::new(),
+            root: DirNode::new(None),
+        }
+    }
+
+    /// Returns the root directory node in [`Arc<DirNode>`](DirNode).
+    pub fn root_dir_node(&self) -> Arc<DirNode> {
+        self.root.clone()
+    }
+}
+
+impl VfsOps for RamFileSystem {
+    fn mount(&self, _path: &str, mount_point: VfsNodeRef) -> VfsResult {
+        if let Some(parent) = mount_point.parent() {
+            self.root.set_parent(Some(self.parent.call_once(|| parent)));
+        } else {
+            self.root.set_parent(None);
+        }
+        Ok(())
+    }
+
+    fn root_dir(&self) -> VfsNodeRef {
+        self.root.clone()
+    }
+}
+
+impl Default for RamFileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
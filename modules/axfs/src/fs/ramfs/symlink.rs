@@ -0,0 +1,59 @@
+use alloc::string::String;
+use axfs_vfs::{VfsError, VfsNodeAttr, VfsNodeOps, VfsNodePerm, VfsNodeType, VfsResult};
+use spin::RwLock;
+
+use super::NodeMeta;
+
+/// The symbolic link node in the RAM filesystem.
+///
+/// The target path is its content, which is set by writing the whole path at
+/// offset 0.
+pub struct SymlinkNode {
+    target: RwLock<String>,
+    pub(super) meta: RwLock<NodeMeta>,
+}
+
+impl SymlinkNode {
+    pub(super) fn new() -> Self {
+        Self {
+            target: RwLock::new(String::new()),
+            meta: RwLock::new(NodeMeta::new(VfsNodePerm::from_bits_truncate(0o777))),
+        }
+    }
+}
+
+impl VfsNodeOps for SymlinkNode {
+    fn get_attr(&self) -> VfsResult<VfsNodeAttr> {
+        Ok(VfsNodeAttr::new(
+            self.meta.read().perm,
+            VfsNodeType::SymLink,
+            self.target.read().len() as _,
+            0,
+        ))
+    }
+
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let target = self.target.read();
+        let target = target.as_bytes();
+        let start = target.len().min(offset as usize);
+        let len = (target.len() - start).min(buf.len());
+        buf[..len].copy_from_slice(&target[start..start + len]);
+        Ok(len)
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        if offset != 0 {
+            return Err(VfsError::InvalidInput);
+        }
+        let target = core::str::from_utf8(buf).map_err(|_| VfsError::InvalidData)?;
+        *self.target.write() = target.into();
+        self.meta.write().modified();
+        Ok(buf.len())
+    }
+
+    fn truncate(&self, _size: u64) -> VfsResult {
+        Err(VfsError::InvalidInput)
+    }
+
+    axfs_vfs::impl_vfs_non_dir_default! {}
+}
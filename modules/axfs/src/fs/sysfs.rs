@@ -0,0 +1,50 @@
+//! System filesystem, which shows the devices and the tunables of the kernel.
+//!
+//! It is built from the nodes of [procfs](super::procfs): files are generated
+//! when they are read, and the tunables are changed when they are written.
+//! Other modules add their files by [`add_file`] and [`add_writable_file`],
+//! e.g., `/sys/class/net/<name>/` by the network module.
+
+use alloc::{string::String, sync::Arc, vec::Vec};
+
+use axerrno::AxResult;
+use axfs_vfs::VfsNodeRef;
+use spin::once::Once;
+
+use super::procfs::{ProcFile, ProcFileSystem};
+
+static SYSFS: Once<Arc<ProcFileSystem>> = Once::new();
+
+/// Returns the system filesystem.
+pub fn sysfs() -> Arc<ProcFileSystem> {
+    SYSFS.call_once(|| Arc::new(ProcFileSystem::new())).clone()
+}
+
+/// Adds a read-only file at `path` in sysfs, whose content is generated by
+/// `content` when it is read. The missing directories in `path` are created.
+pub fn add_file<F>(path: &str, content: F) -> AxResult
+where
+    F: Fn() -> String + Send + Sync + 'static,
+{
+    sysfs().add_node(path, ProcFile::new(content))
+}
+
+/// Adds a writable file at `path` in sysfs, as [`ProcFile::new_writable`].
+/// The missing directories in `path` are created.
+pub fn add_writable_file<F, S>(path: &str, content: F, store: S) -> AxResult
+where
+    F: Fn() -> String + Send + Sync + 'static,
+    S: Fn(&str) -> AxResult + Send + Sync + 'static,
+{
+    sysfs().add_node(path, ProcFile::new_writable(content, store))
+}
+
+/// Adds the entries generated by `entries` on each lookup to the directory at
+/// `path` in sysfs. The missing directories in `path` are created.
+pub fn add_entries<F>(path: &str, entries: F) -> AxResult
+where
+    F: Fn() -> Vec<(String, VfsNodeRef)> + Send + Sync + 'static,
+{
+    sysfs().dir_at(path)?.add_entries(entries);
+    Ok(())
+}
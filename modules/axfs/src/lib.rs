@@ -4,20 +4,47 @@
 //!
 //! # Cargo Features
 //!
-//! - `fatfs`: Use [FAT] as the main filesystem and mount it on `/`. This feature
-//!    is **enabled** by default.
-//! - `devfs`: Mount [`axfs_devfs::DeviceFileSystem`] on `/dev`. This feature is
-//!    **enabled** by default.
-//! - `ramfs`: Mount [`axfs_ramfs::RamFileSystem`] on `/tmp`. This feature is
+//! - `fatfs`: Support [FAT] on block devices, and check FAT volumes that are
+//!    not mounted by [`api::fsck`]. This feature is **enabled** by default.
+//! - `lwext4_rs`: Support ext4 on block devices, by [lwext4].
+//! - `devfs`: Mount [`axfs_devfs::DeviceFileSystem`] on `/dev`, with the
+//!    console, the random number generator and the block devices. Other
+//!    modules add their character devices by [`devfs::register_char_device`].
+//!    This feature is **enabled** by default.
+//! - `ramfs`: Mount a RAM filesystem on `/tmp`, which supports symbolic links
+//!    and hard links. This feature is **enabled** by default.
+//! - `procfs`: Mount a process filesystem on `/proc`, whose files are
+//!    generated from the kernel state when they are read. Other modules add
+//!    their files by [`procfs::add_file`]. This feature is **enabled** by
+//!    default.
+//! - `sysfs`: Mount a system filesystem on `/sys`, which shows the devices
+//!    and the tunables of the kernel, built in the same way as procfs. Other
+//!    modules add their files by [`sysfs::add_file`]. This feature is
 //!    **enabled** by default.
+//! - `page-cache`: Cache the pages of opened files, shared by the files opened
+//!    on the same inode and by their memory mappings. This feature is
+//!    **disabled** by default.
+//! - `multitask`: Yield to other tasks while waiting for input from the
+//!    console devices. This feature is **disabled** by default, and is enabled
+//!    by `axfeat/multitask`.
 //! - `myfs`: Allow users to define their custom filesystems to override the
-//!    default. In this case, [`MyFileSystemIf`] is required to be implemented
+//!    root filesystem. In this case, [`MyFileSystemIf`] is required to be implemented
 //!    to create and initialize other filesystems. This feature is **disabled** by
 //!    by default, but it will override other filesystem selection features if
 //!    both are enabled.
 //!
+//! The filesystem on each block device, including the root one, is detected
+//! by its superblock. Blocks of all devices are cached in a shared LRU cache,
+//! which is written back on [`api::sync`], on [`File::flush`], and when the
+//! filesystem is unmounted.
+//!
+//! Symbolic links are followed when paths are resolved, on the filesystems
+//! that support them (ramfs and ext4).
+//!
 //! [FAT]: https://en.wikipedia.org/wiki/File_Allocation_Table
+//! [lwext4]: https://github.com/gkostka/lwext4
 //! [`MyFileSystemIf`]: fops::MyFileSystemIf
+//! [`File::flush`]: fops::File::flush
 
 #![cfg_attr(all(not(test), not(doc)), no_std)]
 #![feature(doc_auto_cfg)]
@@ -26,22 +53,66 @@
 extern crate log;
 extern crate alloc;
 
+mod cache;
 mod dev;
 mod fs;
 mod mounts;
+#[cfg(feature = "page-cache")]
+mod page_cache;
+mod partition;
 mod root;
 
 pub mod api;
 pub mod fops;
 pub use root::{CURRENT_DIR, CURRENT_DIR_PATH};
 
+#[cfg(feature = "devfs")]
+pub use fs::devfs;
+#[cfg(any(feature = "procfs", feature = "sysfs"))]
+pub use fs::procfs;
+#[cfg(feature = "sysfs")]
+pub use fs::sysfs;
+
+use alloc::vec::Vec;
 use axdriver::{AxDeviceContainer, prelude::*};
 
+/// The name of the root block device (e.g., `vdb`), or empty for the first
+/// one.
+const ROOT_DEV: &str = match option_env!("AX_ROOT") {
+    Some(dev) => dev,
+    None => "",
+};
+
 /// Initializes filesystems by block devices.
+///
+/// All devices are registered as `/dev/vda`, `/dev/vdb`, ... in order, and
+/// their partitions as `/dev/vda1`, `/dev/vda2`, ... The root filesystem is
+/// opened on the device or partition set by the `AX_ROOT` environment
+/// variable at build time, or the first one with a known filesystem by
+/// default.
 pub fn init_filesystems(mut blk_devs: AxDeviceContainer<AxBlockDevice>) {
     info!("Initialize filesystems...");
 
-    let dev = blk_devs.take_one().expect("No block device found!");
-    info!("  use block device 0: {:?}", dev.device_name());
-    self::root::init_rootfs(self::dev::Disk::new(dev));
+    let mut devs = Vec::new();
+    while let Some(dev) = blk_devs.take_one() {
+        devs.push(dev);
+    }
+    self::dev::init_block_devices(devs);
+
+    let devs = self::dev::block_devices();
+    let root_dev = if ROOT_DEV.is_empty() {
+        // prefer the first device or partition with a known filesystem
+        devs.iter()
+            .find(|dev| self::fs::DiskFsType::probe(dev).is_some())
+            .or(devs.first())
+            .cloned()
+            .expect("No block device found!")
+    } else {
+        self::dev::find_block_device(ROOT_DEV).expect("Root block device not found!")
+    };
+    info!("  use block device {} as root", root_dev.name());
+    let disk = root_dev
+        .open_disk()
+        .expect("failed to open root block device");
+    self::root::init_rootfs(disk);
 }
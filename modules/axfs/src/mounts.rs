@@ -1,19 +1,94 @@
-use alloc::sync::Arc;
-use axfs_vfs::{VfsNodeType, VfsOps, VfsResult};
+use alloc::{string::String, sync::Arc};
+use axerrno::{AxResult, ax_err, ax_err_type};
+use axfs_vfs::VfsOps;
 
-use crate::fs;
+use crate::dev::{self, Disk};
+use crate::fs::{self, DiskFsType};
+
+/// Creates a filesystem of type `fstype` to be mounted by
+/// [`mount`](crate::api::mount).
+///
+/// Filesystems on block devices are opened on the device at `source`, and
+/// their type is detected if `fstype` is empty or `auto`. FAT volumes are
+/// also opened in the image file at `source` if it is not a device.
+pub(crate) fn new_fs(source: &str, fstype: &str) -> AxResult<Arc<dyn VfsOps>> {
+    match fstype {
+        #[cfg(feature = "devfs")]
+        "devfs" => Ok(devfs()),
+        #[cfg(feature = "ramfs")]
+        "ramfs" | "tmpfs" => Ok(ramfs()),
+        #[cfg(feature = "procfs")]
+        "proc" => Ok(procfs()?),
+        #[cfg(feature = "sysfs")]
+        "sysfs" => Ok(sysfs()?),
+        "" | "auto" => disk_fs(dev::find_block_device(source)?.open_disk()?, None),
+        _ => match DiskFsType::from_name(fstype) {
+            #[cfg(feature = "fatfs")]
+            Some(DiskFsType::Fat) if dev::find_block_device(source).is_err() => {
+                let image = crate::root::lookup(None, source)?;
+                Ok(Arc::new(fs::fatfs::FatFileSystemFromFile::new(&image)?))
+            }
+            Some(ty) => disk_fs(dev::find_block_device(source)?.open_disk()?, Some(ty)),
+            None => ax_err!(Unsupported, "unknown filesystem type"),
+        },
+    }
+}
+
+/// Returns the name of the filesystem type `fstype` given to `new_fs`, which is
+/// detected on the device at `source` if it is empty or `auto`.
+pub(crate) fn fs_type_name(source: &str, fstype: &str) -> String {
+    match fstype {
+        "" | "auto" => dev::find_block_device(source)
+            .ok()
+            .and_then(|dev| DiskFsType::probe(&dev))
+            .map_or("unknown", DiskFsType::name)
+            .into(),
+        "ramfs" => "tmpfs".into(),
+        _ => fstype.into(),
+    }
+}
+
+/// Creates a filesystem of type `ty` on `disk`, or of the detected type if
+/// `ty` is `None`.
+pub(crate) fn disk_fs(disk: Disk, ty: Option<DiskFsType>) -> AxResult<Arc<dyn VfsOps>> {
+    let ty = match ty {
+        Some(ty) => ty,
+        None => DiskFsType::probe(disk.device())
+            .ok_or_else(|| ax_err_type!(Unsupported, "unknown filesystem on the device"))?,
+    };
+    info!("  open {:?} filesystem", ty);
+    match ty {
+        #[cfg(feature = "fatfs")]
+        DiskFsType::Fat => Ok(fs::fatfs::FatFileSystem::new(disk)?),
+        #[cfg(feature = "lwext4_rs")]
+        DiskFsType::Ext4 => Ok(Arc::new(fs::lwext4_rust::Ext4FileSystem::new(disk)?)),
+        #[allow(unreachable_patterns)]
+        _ => ax_err!(Unsupported, "filesystem type is not enabled"),
+    }
+}
 
 #[cfg(feature = "devfs")]
 pub(crate) fn devfs() -> Arc<fs::devfs::DeviceFileSystem> {
-    let null = fs::devfs::NullDev;
-    let zero = fs::devfs::ZeroDev;
-    let bar = fs::devfs::ZeroDev;
-    let devfs = fs::devfs::DeviceFileSystem::new();
-    let foo_dir = devfs.mkdir("foo");
-    devfs.add("null", Arc::new(null));
-    devfs.add("zero", Arc::new(zero));
-    foo_dir.add("bar", Arc::new(bar));
-    Arc::new(devfs)
+    use fs::devfs::{ConsoleDev, NullDev, RandomDev, ZeroDev, add_node, register_char_device};
+
+    static INIT: spin::Once = spin::Once::new();
+    // devfs is shared by all the mounts
+    INIT.call_once(|| {
+        add_node("null", Arc::new(NullDev)).unwrap();
+        add_node("zero", Arc::new(ZeroDev)).unwrap();
+        for path in ["console", "tty"] {
+            register_char_device(path, Arc::new(ConsoleDev)).unwrap();
+        }
+        if let Some(random) = RandomDev::new().map(Arc::new) {
+            for path in ["random", "urandom"] {
+                register_char_device(path, random.clone()).unwrap();
+            }
+        }
+        for dev in dev::block_devices() {
+            add_node(dev.name(), dev.clone()).unwrap();
+        }
+    });
+    fs::devfs::devfs()
 }
 
 #[cfg(feature = "ramfs")]
@@ -21,60 +96,61 @@ pub(crate) fn ramfs() -> Arc<fs::ramfs::RamFileSystem> {
     Arc::new(fs::ramfs::RamFileSystem::new())
 }
 
+/// Returns procfs, with the files about filesystems and the fixed ones.
+///
+/// The files about other kernel states are added by their modules, through
+/// [`crate::procfs`].
 #[cfg(feature = "procfs")]
-pub(crate) fn procfs() -> VfsResult<Arc<fs::ramfs::RamFileSystem>> {
-    let procfs = fs::ramfs::RamFileSystem::new();
-    let proc_root = procfs.root_dir();
-
-    // Create /proc/sys/net/core/somaxconn
-    proc_root.create("sys", VfsNodeType::Dir)?;
-    proc_root.create("sys/net", VfsNodeType::Dir)?;
-    proc_root.create("sys/net/core", VfsNodeType::Dir)?;
-    proc_root.create("sys/net/core/somaxconn", VfsNodeType::File)?;
-    let file_somaxconn = proc_root.clone().lookup("./sys/net/core/somaxconn")?;
-    file_somaxconn.write_at(0, b"4096\n")?;
-
-    // Create /proc/sys/vm/overcommit_memory
-    proc_root.create("sys/vm", VfsNodeType::Dir)?;
-    proc_root.create("sys/vm/overcommit_memory", VfsNodeType::File)?;
-    let file_over = proc_root.clone().lookup("./sys/vm/overcommit_memory")?;
-    file_over.write_at(0, b"0\n")?;
+pub(crate) fn procfs() -> AxResult<Arc<fs::procfs::ProcFileSystem>> {
+    use alloc::{format, string::ToString};
+    use fs::procfs::add_file;
 
-    // Create /proc/self/stat
-    proc_root.create("self", VfsNodeType::Dir)?;
-    proc_root.create("self/stat", VfsNodeType::File)?;
-
-    Ok(Arc::new(procfs))
+    add_file("sys/net/core/somaxconn", || "4096\n".into())?;
+    add_file("sys/vm/overcommit_memory", || "0\n".into())?;
+    add_file("mounts", || {
+        crate::root::mount_table()
+            .into_iter()
+            .map(|(device, path, fstype)| format!("{device} {path} {fstype} rw 0 0\n"))
+            .collect()
+    })?;
+    add_file("filesystems", || {
+        let mut types = alloc::vec!["nodev\tproc"];
+        #[cfg(feature = "devfs")]
+        types.push("nodev\tdevfs");
+        #[cfg(feature = "ramfs")]
+        types.push("nodev\ttmpfs");
+        #[cfg(feature = "sysfs")]
+        types.push("nodev\tsysfs");
+        #[cfg(feature = "fatfs")]
+        types.push("\tvfat");
+        #[cfg(feature = "lwext4_rs")]
+        types.push("\text4");
+        types.iter().map(|ty| ty.to_string() + "\n").collect()
+    })?;
+    Ok(fs::procfs::procfs())
 }
 
+/// Returns sysfs, with the block devices and the fixed files.
+///
+/// The files about other devices and the tunables are added by their modules,
+/// through [`crate::sysfs`].
 #[cfg(feature = "sysfs")]
-pub(crate) fn sysfs() -> VfsResult<Arc<fs::ramfs::RamFileSystem>> {
-    let sysfs = fs::ramfs::RamFileSystem::new();
-    let sys_root = sysfs.root_dir();
-
-    // Create /sys/kernel/mm/transparent_hugepage/enabled
-    sys_root.create("kernel", VfsNodeType::Dir)?;
-    sys_root.create("kernel/mm", VfsNodeType::Dir)?;
-    sys_root.create("kernel/mm/transparent_hugepage", VfsNodeType::Dir)?;
-    sys_root.create("kernel/mm/transparent_hugepage/enabled", VfsNodeType::File)?;
-    let file_hp = sys_root
-        .clone()
-        .lookup("./kernel/mm/transparent_hugepage/enabled")?;
-    file_hp.write_at(0, b"always [madvise] never\n")?;
-
-    // Create /sys/devices/system/clocksource/clocksource0/current_clocksource
-    sys_root.create("devices", VfsNodeType::Dir)?;
-    sys_root.create("devices/system", VfsNodeType::Dir)?;
-    sys_root.create("devices/system/clocksource", VfsNodeType::Dir)?;
-    sys_root.create("devices/system/clocksource/clocksource0", VfsNodeType::Dir)?;
-    sys_root.create(
-        "devices/system/clocksource/clocksource0/current_clocksource",
-        VfsNodeType::File,
-    )?;
-    let file_cc = sys_root
-        .clone()
-        .lookup("devices/system/clocksource/clocksource0/current_clocksource")?;
-    file_cc.write_at(0, b"tsc\n")?;
+pub(crate) fn sysfs() -> AxResult<Arc<fs::procfs::ProcFileSystem>> {
+    use alloc::format;
+    use fs::sysfs::add_file;
 
-    Ok(Arc::new(sysfs))
+    add_file("kernel/mm/transparent_hugepage/enabled", || {
+        "always [madvise] never\n".into()
+    })?;
+    for dev in dev::block_devices() {
+        let dir = format!("class/block/{}", dev.name());
+        let sectors = dev.size() / 512;
+        add_file(&format!("{dir}/size"), move || format!("{sectors}\n"))?;
+        add_file(&format!("{dir}/ro"), || "0\n".into())?;
+        // blocks are sectors of 512 bytes
+        if let Some(start) = dev.partition_start() {
+            add_file(&format!("{dir}/start"), move || format!("{start}\n"))?;
+        }
+    }
+    Ok(fs::sysfs::sysfs())
 }
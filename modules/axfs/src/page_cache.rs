@@ -0,0 +1,246 @@
+//! The page cache of opened files.
+//!
+//! Pages read from a file are cached in a [`PageCache`], which is shared by
+//! all the handles that opened the same inode, whichever path or hard link
+//! they opened it by. Writes go through to the file and update the cached
+//! pages, so nothing is lost if the cache is dropped.
+//!
+//! Pages can also be mapped into memory, e.g., by `mmap`. Mapped pages are
+//! not evicted, and the changes made through the mappings are written to the
+//! file when they are synced.
+//!
+//! Only regular files with inode numbers are cached. Device files have the
+//! block cache instead.
+
+use alloc::{
+    boxed::Box,
+    collections::BTreeMap,
+    sync::{Arc, Weak},
+};
+use core::ptr::NonNull;
+
+use axerrno::AxResult;
+use axfs_vfs::{VfsNodeRef, VfsResult};
+use axsync::Mutex;
+
+/// The size of cached pages.
+pub(crate) const PAGE_SIZE: usize = 4096;
+/// The maximum number of cached pages of a file.
+const MAX_PAGES: usize = 256;
+
+/// Identifies an inode by the ID of its filesystem and its inode number.
+pub(crate) type CacheKey = (usize, u64);
+
+/// The page caches of opened files, by their inodes.
+static PAGE_CACHES: Mutex<BTreeMap<CacheKey, Weak<PageCache>>> = Mutex::new(BTreeMap::new());
+
+/// The data of a page, aligned to be mapped into memory.
+#[repr(align(4096))]
+struct PageData([u8; PAGE_SIZE]);
+
+struct Page {
+    /// Zeros after `len`, unless written through a mapping.
+    data: Box<PageData>,
+    /// The number of bytes in the file, less than `PAGE_SIZE` at the end of
+    /// the file.
+    len: usize,
+    /// The key of the page in the LRU list.
+    last_used: u64,
+    /// The number of mappings of the page.
+    maps: usize,
+}
+
+#[derive(Default)]
+struct PageCacheInner {
+    pages: BTreeMap<u64, Page>,
+    /// The cached pages that are not mapped, from the least recently used.
+    lru: BTreeMap<u64, u64>,
+    clock: u64,
+}
+
+/// The cached pages of a file.
+#[derive(Default)]
+pub(crate) struct PageCache {
+    inner: Mutex<PageCacheInner>,
+}
+
+impl PageCacheInner {
+    /// Returns the cached page `idx`, reading it from `node` if it is not
+    /// cached.
+    fn get(&mut self, node: &VfsNodeRef, idx: u64) -> VfsResult<&mut Page> {
+        self.clock += 1;
+        let clock = self.clock;
+        if !self.pages.contains_key(&idx) {
+            let mut data = Box::new(PageData([0; PAGE_SIZE]));
+            let mut len = 0;
+            while len < PAGE_SIZE {
+                match node.read_at(idx * PAGE_SIZE as u64 + len as u64, &mut data.0[len..])? {
+                    0 => break,
+                    n => len += n,
+                }
+            }
+            if self.pages.len() >= MAX_PAGES {
+                if let Some((_, old)) = self.lru.pop_first() {
+                    self.pages.remove(&old);
+                }
+            }
+            let page = Page {
+                data,
+                len,
+                last_used: clock,
+                maps: 0,
+            };
+            self.pages.insert(idx, page);
+        }
+        let page = self.pages.get_mut(&idx).unwrap();
+        if page.maps == 0 {
+            self.lru.remove(&page.last_used);
+            self.lru.insert(clock, idx);
+        }
+        page.last_used = clock;
+        Ok(page)
+    }
+}
+
+impl PageCache {
+    /// Returns the page cache of the inode `key`.
+    ///
+    /// A new one is returned if `fresh` is true, e.g. when the file is just
+    /// created, as inode numbers may be reused.
+    pub fn of(key: CacheKey, fresh: bool) -> Arc<Self> {
+        let mut caches = PAGE_CACHES.lock();
+        caches.retain(|_, cache| cache.strong_count() > 0);
+        if !fresh {
+            if let Some(cache) = caches.get(&key).and_then(Weak::upgrade) {
+                return cache;
+            }
+        }
+        let cache = Arc::new(Self::default());
+        caches.insert(key, Arc::downgrade(&cache));
+        cache
+    }
+
+    /// Reads the file `node` at `offset` through the cache.
+    pub fn read_at(&self, node: &VfsNodeRef, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut inner = self.inner.lock();
+        let mut read_len = 0;
+        while read_len < buf.len() {
+            let pos = offset + read_len as u64;
+            let page = inner.get(node, pos / PAGE_SIZE as u64)?;
+            let start = (pos % PAGE_SIZE as u64) as usize;
+            if start >= page.len {
+                break; // end of file
+            }
+            let count = (page.len - start).min(buf.len() - read_len);
+            buf[read_len..read_len + count].copy_from_slice(&page.data.0[start..start + count]);
+            read_len += count;
+        }
+        Ok(read_len)
+    }
+
+    /// Writes to the file `node` at `offset`, and updates the cached pages.
+    pub fn write_at(&self, node: &VfsNodeRef, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let mut inner = self.inner.lock();
+        let write_len = node.write_at(offset, buf)?;
+        let end = offset + write_len as u64;
+        for (&idx, page) in inner.pages.range_mut(..end.div_ceil(PAGE_SIZE as u64)) {
+            let page_start = idx * PAGE_SIZE as u64;
+            // pages before the written data may have grown by a hole
+            let len_in_page = (end - page_start).min(PAGE_SIZE as u64) as usize;
+            page.len = page.len.max(len_in_page);
+            let from = offset.max(page_start);
+            if from < end {
+                let start = (from - page_start) as usize;
+                let count = ((end - from) as usize).min(PAGE_SIZE - start);
+                let src = (from - offset) as usize;
+                page.data.0[start..start + count].copy_from_slice(&buf[src..src + count]);
+            }
+        }
+        Ok(write_len)
+    }
+
+    /// Resizes the cached data to `size`, after the file is truncated or
+    /// extended with zeros.
+    ///
+    /// Mapped pages after the end of file are kept until they are unmapped,
+    /// but they are zeroed.
+    pub fn truncate(&self, size: u64) {
+        let mut inner = self.inner.lock();
+        let inner = &mut *inner;
+        let first = size.div_ceil(PAGE_SIZE as u64);
+        for (idx, page) in inner.pages.split_off(&first) {
+            if page.maps > 0 {
+                inner.pages.insert(idx, page);
+            } else {
+                inner.lru.remove(&page.last_used);
+            }
+        }
+        for (&idx, page) in inner.pages.iter_mut() {
+            let len = size
+                .saturating_sub(idx * PAGE_SIZE as u64)
+                .min(PAGE_SIZE as u64) as usize;
+            // the data after `page.len` is already zero, if not mapped
+            if len < page.len || page.maps > 0 {
+                page.data.0[len..].fill(0);
+            }
+            page.len = len;
+        }
+    }
+
+    /// Maps the page `idx` of the file `node` into memory, and returns its
+    /// data, which is not evicted until [`unmap_page`](Self::unmap_page).
+    ///
+    /// The changes made through the data are shared with the reads and
+    /// writes through the cache, and are written to the file by
+    /// [`sync_page`](Self::sync_page).
+    pub fn map_page(&self, node: &VfsNodeRef, idx: u64) -> VfsResult<NonNull<[u8; PAGE_SIZE]>> {
+        let mut inner = self.inner.lock();
+        let page = inner.get(node, idx)?;
+        page.maps += 1;
+        let (last_used, data) = (page.last_used, NonNull::from(&mut page.data.0));
+        inner.lru.remove(&last_used);
+        Ok(data)
+    }
+
+    /// Unmaps the page `idx` mapped by [`map_page`](Self::map_page). It can
+    /// be evicted after it is unmapped as many times as it is mapped.
+    pub fn unmap_page(&self, idx: u64) {
+        let mut inner = self.inner.lock();
+        let inner = &mut *inner;
+        if let Some(page) = inner.pages.get_mut(&idx) {
+            page.maps -= 1;
+            if page.maps == 0 {
+                inner.lru.insert(page.last_used, idx);
+            }
+        }
+    }
+
+    /// Writes the page `idx` to the file `node`, after it is changed through
+    /// a mapping. The data after the end of file is not written.
+    pub fn sync_page(&self, node: &VfsNodeRef, idx: u64) -> VfsResult {
+        let inner = self.inner.lock();
+        if let Some(page) = inner.pages.get(&idx) {
+            node.write_at(idx * PAGE_SIZE as u64, &page.data.0[..page.len])?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the key of the page cache of the file `node` at `path`, or `None`
+/// if it is not cached.
+pub(crate) fn cache_key(path: &str, node: &VfsNodeRef) -> AxResult<Option<CacheKey>> {
+    let ino = crate::fs::node_meta(node)?.ino;
+    if ino == 0 || !node.get_attr()?.is_file() {
+        return Ok(None);
+    }
+    Ok(Some((crate::root::fs_id(path)?, ino)))
+}
+
+/// Moves the page cache of the inode `old` to `new`, after a file is renamed
+/// on a filesystem whose inode numbers change with the names, such as FAT.
+pub(crate) fn rename(old: CacheKey, new: CacheKey) {
+    let mut caches = PAGE_CACHES.lock();
+    if let Some(cache) = caches.remove(&old) {
+        caches.insert(new, cache);
+    }
+}
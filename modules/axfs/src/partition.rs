@@ -0,0 +1,212 @@
+//! Partition tables on block devices.
+//!
+//! Both MBR (with logical partitions in an extended partition) and GPT are
+//! supported. The CRC checksums of GPT are not verified, and the backup GPT
+//! header is not used.
+
+use alloc::vec::Vec;
+
+use axdriver::prelude::*;
+
+use crate::dev::BLOCK_SIZE;
+use crate::fs::is_fat_boot_sector;
+
+/// The offset of the partition entries in an MBR.
+const MBR_ENTRIES_OFFSET: usize = 446;
+/// The number of primary partitions in an MBR.
+const MBR_NUM_ENTRIES: usize = 4;
+/// The first number of logical partitions in an extended partition.
+const MBR_FIRST_LOGICAL: usize = 5;
+/// The maximum number of logical partitions, against loops in the chain.
+const MBR_MAX_LOGICAL: usize = 64;
+/// The partition type of the protective MBR of GPT.
+const MBR_TYPE_GPT: u8 = 0xee;
+/// Partition types of extended partitions.
+const MBR_TYPES_EXTENDED: [u8; 3] = [0x05, 0x0f, 0x85];
+
+/// The block of the GPT header.
+const GPT_HEADER_BLOCK: u64 = 1;
+/// The signature of the GPT header.
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+/// The maximum number of GPT entries that are read.
+const GPT_MAX_ENTRIES: u32 = 128;
+/// The minimum size of GPT entries.
+const GPT_MIN_ENTRY_SIZE: usize = 128;
+
+/// A partition of a block device, in blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Partition {
+    /// The partition number, from 1.
+    pub number: usize,
+    /// The first block.
+    pub start: u64,
+    /// The number of blocks.
+    pub num_blocks: u64,
+}
+
+/// An entry of an MBR or an EBR.
+struct MbrEntry {
+    status: u8,
+    ty: u8,
+    start: u64,
+    num_blocks: u64,
+}
+
+impl MbrEntry {
+    fn parse(block: &[u8; BLOCK_SIZE], idx: usize) -> Self {
+        let entry = &block[MBR_ENTRIES_OFFSET + idx * 16..][..16];
+        Self {
+            status: entry[0],
+            ty: entry[4],
+            start: read_u32(entry, 8) as u64,
+            num_blocks: read_u32(entry, 12) as u64,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ty == 0 || self.num_blocks == 0
+    }
+
+    fn is_extended(&self) -> bool {
+        MBR_TYPES_EXTENDED.contains(&self.ty)
+    }
+}
+
+/// Parses the partition table on a device of `num_blocks` blocks, whose
+/// blocks are read by `read_block`.
+///
+/// Returns no partitions if there is no valid partition table, or if the
+/// device is a FAT volume, whose boot sector looks like an MBR.
+pub(crate) fn parse<F>(num_blocks: u64, mut read_block: F) -> Vec<Partition>
+where
+    F: FnMut(u64, &mut [u8; BLOCK_SIZE]) -> DevResult,
+{
+    let mut block = [0; BLOCK_SIZE];
+    if read_block(0, &mut block).is_err() || block[510..512] != [0x55, 0xaa] {
+        return Vec::new();
+    }
+    if is_fat_boot_sector(&block) {
+        return Vec::new();
+    }
+    let entries = (0..MBR_NUM_ENTRIES)
+        .map(|idx| MbrEntry::parse(&block, idx))
+        .collect::<Vec<_>>();
+    if entries.iter().any(|e| e.status & 0x7f != 0) {
+        return Vec::new();
+    }
+    // the protective MBR may cover more than the device
+    if entries.iter().any(|e| e.ty == MBR_TYPE_GPT) {
+        return parse_gpt(num_blocks, read_block);
+    }
+    if entries
+        .iter()
+        .any(|e| !e.is_empty() && (e.start == 0 || e.start + e.num_blocks > num_blocks))
+    {
+        return Vec::new();
+    }
+
+    let mut parts = Vec::new();
+    let mut extended = None;
+    for (idx, entry) in entries.iter().enumerate() {
+        if entry.is_empty() {
+            continue;
+        }
+        if entry.is_extended() {
+            extended.get_or_insert((entry.start, entry.num_blocks));
+            continue;
+        }
+        parts.push(Partition {
+            number: idx + 1,
+            start: entry.start,
+            num_blocks: entry.num_blocks,
+        });
+    }
+    if let Some((ext_start, ext_blocks)) = extended {
+        parse_logical(ext_start, ext_blocks, &mut read_block, &mut parts);
+    }
+    parts
+}
+
+/// Parses the chain of EBRs in the extended partition.
+fn parse_logical<F>(ext_start: u64, ext_blocks: u64, read_block: &mut F, parts: &mut Vec<Partition>)
+where
+    F: FnMut(u64, &mut [u8; BLOCK_SIZE]) -> DevResult,
+{
+    let mut block = [0; BLOCK_SIZE];
+    // the first EBR is at the start of the extended partition
+    let mut ebr_offset = 0;
+    for number in MBR_FIRST_LOGICAL..MBR_FIRST_LOGICAL + MBR_MAX_LOGICAL {
+        let ebr = ext_start + ebr_offset;
+        if read_block(ebr, &mut block).is_err() || block[510..512] != [0x55, 0xaa] {
+            break;
+        }
+        // the logical partition is relative to its EBR
+        let entry = MbrEntry::parse(&block, 0);
+        if !entry.is_empty() && ebr_offset + entry.start + entry.num_blocks <= ext_blocks {
+            parts.push(Partition {
+                number,
+                start: ebr + entry.start,
+                num_blocks: entry.num_blocks,
+            });
+        }
+        // the next EBR is relative to the extended partition
+        let next = MbrEntry::parse(&block, 1);
+        if next.is_empty() || next.start <= ebr_offset || next.start >= ext_blocks {
+            break;
+        }
+        ebr_offset = next.start;
+    }
+}
+
+/// Parses the GPT header and entries.
+fn parse_gpt<F>(num_blocks: u64, mut read_block: F) -> Vec<Partition>
+where
+    F: FnMut(u64, &mut [u8; BLOCK_SIZE]) -> DevResult,
+{
+    let mut block = [0; BLOCK_SIZE];
+    if read_block(GPT_HEADER_BLOCK, &mut block).is_err() || &block[0..8] != GPT_SIGNATURE {
+        warn!("invalid GPT header");
+        return Vec::new();
+    }
+    let entries_block = read_u64(&block, 72);
+    let num_entries = read_u32(&block, 80).min(GPT_MAX_ENTRIES) as usize;
+    let entry_size = read_u32(&block, 84) as usize;
+    if entry_size < GPT_MIN_ENTRY_SIZE || BLOCK_SIZE % entry_size != 0 {
+        warn!("invalid GPT entry size: {}", entry_size);
+        return Vec::new();
+    }
+
+    let entries_per_block = BLOCK_SIZE / entry_size;
+    let mut parts = Vec::new();
+    for idx in 0..num_entries {
+        if idx % entries_per_block == 0 {
+            let block_id = entries_block + (idx / entries_per_block) as u64;
+            if read_block(block_id, &mut block).is_err() {
+                break;
+            }
+        }
+        let entry = &block[idx % entries_per_block * entry_size..][..entry_size];
+        // unused entries have a zero type GUID
+        if entry[0..16].iter().all(|&b| b == 0) {
+            continue;
+        }
+        let (first, last) = (read_u64(entry, 32), read_u64(entry, 40));
+        if first == 0 || first > last || last >= num_blocks {
+            continue;
+        }
+        parts.push(Partition {
+            number: idx + 1,
+            start: first,
+            num_blocks: last - first + 1,
+        });
+    }
+    parts
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
@@ -1,9 +1,10 @@
-//! Root directory of the filesystem
+//! Root directory of the filesystem, and the mount table.
 //!
-//! TODO: it doesn't work very well if the mount points have containment relationships.
+//! Paths are resolved to the filesystem mounted at their longest prefix, so
+//! filesystems can be mounted on directories of other mounted filesystems.
 
-use alloc::{string::String, sync::Arc, vec::Vec};
-use axerrno::{AxError, AxResult, ax_err};
+use alloc::{format, string::String, sync::Arc, vec, vec::Vec};
+use axerrno::{AxError, AxResult, ax_err, ax_err_type};
 use axfs_vfs::{VfsNodeAttr, VfsNodeOps, VfsNodeRef, VfsNodeType, VfsOps, VfsResult};
 use axns::{ResArc, def_resource};
 use axsync::Mutex;
@@ -11,8 +12,9 @@ use lazyinit::LazyInit;
 use spin::RwLock;
 
 use crate::{
-    api::FileType,
-    fs::{self},
+    api::{FileType, MountFlags},
+    dev::Disk,
+    fops::{FsStat, PathError, PathResult},
     mounts,
 };
 
@@ -35,90 +37,181 @@ impl CURRENT_DIR {
     }
 }
 
-struct MountPoint {
-    path: &'static str,
-    fs: Arc<dyn VfsOps>,
+/// A mounted filesystem, or a directory tree attached by a bind mount.
+///
+/// Opened files and directories hold a reference to the mount point they are
+/// in, which keeps it busy.
+pub(crate) struct MountPoint {
+    /// The canonical absolute path.
+    path: String,
+    /// The root of the attached tree.
+    root: VfsNodeRef,
+    /// What the tree belongs to.
+    source: MountSource,
+    /// The device or the name of the filesystem, as in `/proc/mounts`.
+    device: String,
+    /// The type of the filesystem.
+    fstype: String,
+}
+
+/// What the tree attached to a mount point belongs to.
+enum MountSource {
+    /// A mounted filesystem.
+    Fs(Arc<dyn VfsOps>),
+    /// The mount point that the bound tree is in, which is kept busy, or
+    /// `None` for the main filesystem.
+    Bind(Option<Arc<MountPoint>>),
 }
 
 struct RootDirectory {
     main_fs: Arc<dyn VfsOps>,
-    mounts: RwLock<Vec<MountPoint>>,
+    /// The device and the type of the main filesystem.
+    main_info: (String, String),
+    mounts: RwLock<Vec<Arc<MountPoint>>>,
 }
 
 static ROOT_DIR: LazyInit<Arc<RootDirectory>> = LazyInit::new();
 
 impl MountPoint {
-    pub fn new(path: &'static str, fs: Arc<dyn VfsOps>) -> Self {
-        Self { path, fs }
+    /// Returns the rest of `path` if it is in this mount point, where `path`
+    /// is relative to the root directory and has no empty or `.` components.
+    fn strip<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let rest = path.strip_prefix(&self.path[1..])?;
+        if rest.is_empty() {
+            Some(rest)
+        } else {
+            rest.strip_prefix('/')
+        }
     }
 }
 
 impl Drop for MountPoint {
     fn drop(&mut self) {
-        self.fs.umount().ok();
+        if let MountSource::Fs(fs) = &self.source {
+            fs.umount().ok();
+        }
+    }
+}
+
+/// Whether the canonical absolute path `path` is `dir` or inside it.
+fn is_inside(path: &str, dir: &str) -> bool {
+    match path.strip_prefix(dir) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/') || dir == "/",
+        None => false,
     }
 }
 
 impl RootDirectory {
-    pub const fn new(main_fs: Arc<dyn VfsOps>) -> Self {
+    pub const fn new(main_fs: Arc<dyn VfsOps>, device: String, fstype: String) -> Self {
         Self {
             main_fs,
+            main_info: (device, fstype),
             mounts: RwLock::new(Vec::new()),
         }
     }
 
-    pub fn mount(&self, path: &'static str, fs: Arc<dyn VfsOps>) -> AxResult {
+    /// Mounts `fs` of type `fstype` at `path` during the initialization,
+    /// creating the mount point if it does not exist.
+    fn mount_at_init(&self, path: &str, fs: Arc<dyn VfsOps>, fstype: &str) -> AxResult {
+        // create the mount point in its filesystem if it does not exist
+        self.create(path, FileType::Dir)?;
+        let mount_point = self.lookup_mounted_fs(path, |root, rest| root.lookup(rest))?;
+        fs.mount(path, mount_point)?;
+        let info = (fstype.into(), fstype.into());
+        self.attach(path.into(), fs.root_dir(), MountSource::Fs(fs), info)
+    }
+
+    /// Attaches the tree at `root` to the canonical absolute path `path`,
+    /// with the device and the filesystem type in `info`.
+    fn attach(
+        &self,
+        path: String,
+        root: VfsNodeRef,
+        source: MountSource,
+        (device, fstype): (String, String),
+    ) -> AxResult {
         if path == "/" {
             return ax_err!(InvalidInput, "cannot mount root filesystem");
         }
         if !path.starts_with('/') {
             return ax_err!(InvalidInput, "mount path must start with '/'");
         }
-        if self.mounts.read().iter().any(|mp| mp.path == path) {
-            return ax_err!(InvalidInput, "mount point already exists");
+        let mut mounts = self.mounts.write();
+        if mounts.iter().any(|mp| mp.path == path) {
+            return ax_err!(ResourceBusy, "mount point already exists");
         }
-        // create the mount point in the main filesystem if it does not exist
-        self.main_fs.root_dir().create(path, FileType::Dir)?;
-        fs.mount(path, self.main_fs.root_dir().lookup(path)?)?;
-        self.mounts.write().push(MountPoint::new(path, fs));
+        mounts.push(Arc::new(MountPoint {
+            path,
+            root,
+            source,
+            device,
+            fstype,
+        }));
         Ok(())
     }
 
-    pub fn _umount(&self, path: &str) {
-        self.mounts.write().retain(|mp| mp.path != path);
+    /// Detaches the tree at the canonical absolute path `path`.
+    ///
+    /// Fails if it has files or directories opened, has other trees mounted
+    /// inside, or contains the current directory.
+    fn detach(&self, path: &str) -> AxResult {
+        let mut mounts = self.mounts.write();
+        let idx = mounts
+            .iter()
+            .position(|mp| mp.path == path)
+            .ok_or_else(|| ax_err_type!(InvalidInput, "not a mount point"))?;
+        if Arc::strong_count(&mounts[idx]) > 1
+            || mounts
+                .iter()
+                .any(|mp| mp.path != path && is_inside(&mp.path, path))
+            || is_inside(CURRENT_DIR_PATH.lock().trim_end_matches('/'), path)
+        {
+            return ax_err!(ResourceBusy, "filesystem is busy");
+        }
+        mounts.remove(idx);
+        Ok(())
     }
 
     pub fn contains(&self, path: &str) -> bool {
         self.mounts.read().iter().any(|mp| mp.path == path)
     }
 
+    /// Returns the mount point that the canonical absolute path `path` is in.
+    fn mount_point_of(&self, path: &str) -> Option<Arc<MountPoint>> {
+        self.mounts
+            .read()
+            .iter()
+            .filter(|mp| is_inside(path, &mp.path))
+            .max_by_key(|mp| mp.path.len())
+            .cloned()
+    }
+
     fn lookup_mounted_fs<F, T>(&self, path: &str, f: F) -> AxResult<T>
     where
-        F: FnOnce(Arc<dyn VfsOps>, &str) -> AxResult<T>,
+        F: FnOnce(VfsNodeRef, &str) -> AxResult<T>,
     {
         debug!("lookup at root: {}", path);
-        let path = path.trim_matches('/');
-        if let Some(rest) = path.strip_prefix("./") {
-            return self.lookup_mounted_fs(rest, f);
-        }
-
-        let mut idx = 0;
-        let mut max_len = 0;
+        // `..` is resolved by the filesystems, as a mount point may be reached
+        // by other paths
+        let path = path
+            .split('/')
+            .filter(|c| !c.is_empty() && *c != ".")
+            .collect::<Vec<_>>()
+            .join("/");
 
         // Find the filesystem that has the longest mounted path match
         // TODO: more efficient, e.g. trie
-        for (i, mp) in self.mounts.read().iter().enumerate() {
-            // skip the first '/'
-            if path.starts_with(&mp.path[1..]) && mp.path.len() - 1 > max_len {
-                max_len = mp.path.len() - 1;
-                idx = i;
-            }
-        }
-
-        if max_len == 0 {
-            f(self.main_fs.clone(), path) // not matched any mount point
-        } else {
-            f(self.mounts.read()[idx].fs.clone(), &path[max_len..]) // matched at `idx`
+        let matched = self
+            .mounts
+            .read()
+            .iter()
+            .filter_map(|mp| Some((mp, mp.strip(&path)?)))
+            .max_by_key(|(mp, _)| mp.path.len())
+            .map(|(mp, rest)| (mp.root.clone(), path.len() - rest.len()));
+
+        match matched {
+            Some((root, idx)) => f(root, &path[idx..]), // matched a mount point
+            None => f(self.main_fs.root_dir(), &path),  // not matched any mount point
         }
     }
 }
@@ -131,78 +224,89 @@ impl VfsNodeOps for RootDirectory {
     }
 
     fn lookup(self: Arc<Self>, path: &str) -> VfsResult<VfsNodeRef> {
-        self.lookup_mounted_fs(path, |fs, rest_path| fs.root_dir().lookup(rest_path))
+        self.lookup_mounted_fs(path, |root, rest_path| {
+            if rest_path.is_empty() {
+                Ok(root)
+            } else {
+                root.lookup(rest_path)
+            }
+        })
     }
 
     fn create(&self, path: &str, ty: VfsNodeType) -> VfsResult {
-        self.lookup_mounted_fs(path, |fs, rest_path| {
+        self.lookup_mounted_fs(path, |root, rest_path| {
             if rest_path.is_empty() {
                 Ok(()) // already exists
             } else {
-                fs.root_dir().create(rest_path, ty)
+                root.create(rest_path, ty)
             }
         })
     }
 
     fn remove(&self, path: &str) -> VfsResult {
-        self.lookup_mounted_fs(path, |fs, rest_path| {
+        self.lookup_mounted_fs(path, |root, rest_path| {
             if rest_path.is_empty() {
                 ax_err!(PermissionDenied) // cannot remove mount points
             } else {
-                fs.root_dir().remove(rest_path)
+                root.remove(rest_path)
             }
         })
     }
 
+    /// Renames `src_path` to `dst_path`, which must be in the same mounted
+    /// filesystem.
     fn rename(&self, src_path: &str, dst_path: &str) -> VfsResult {
-        self.lookup_mounted_fs(src_path, |fs, rest_path| {
-            if rest_path.is_empty() {
+        let dst_rest = self.lookup_mounted_fs(dst_path, |_, rest| Ok(String::from(rest)))?;
+        self.lookup_mounted_fs(src_path, |root, rest_path| {
+            if rest_path.is_empty() || dst_rest.is_empty() {
                 ax_err!(PermissionDenied) // cannot rename mount points
             } else {
-                fs.root_dir().rename(rest_path, dst_path)
+                root.rename(rest_path, &dst_rest)
             }
         })
     }
 }
 
-pub(crate) fn init_rootfs(disk: crate::dev::Disk) {
+pub(crate) fn init_rootfs(disk: Disk) {
+    // the ramdisk is empty
+    #[cfg(all(feature = "use-ramdisk", feature = "fatfs"))]
+    let disk = {
+        let mut disk = disk;
+        crate::fs::fatfs::FatFileSystem::format(&mut disk).expect("failed to format volume");
+        disk
+    };
+
+    let device = format!("/dev/{}", disk.device().name());
     cfg_if::cfg_if! {
         if #[cfg(feature = "myfs")] { // override the default filesystem
-            let main_fs = fs::myfs::new_myfs(disk);
-        } else if #[cfg(feature = "lwext4_rs")] {
-            static EXT4_FS: LazyInit<Arc<fs::lwext4_rust::Ext4FileSystem>> = LazyInit::new();
-            EXT4_FS.init_once(Arc::new(fs::lwext4_rust::Ext4FileSystem::new(disk)));
-            let main_fs = EXT4_FS.clone();
-        } else if #[cfg(feature = "fatfs")] {
-            static FAT_FS: LazyInit<Arc<fs::fatfs::FatFileSystem>> = LazyInit::new();
-            FAT_FS.init_once(Arc::new(fs::fatfs::FatFileSystem::new(disk)));
-            FAT_FS.init();
-            let main_fs = FAT_FS.clone();
+            let fstype = "myfs";
+            let main_fs = crate::fs::myfs::new_myfs(disk);
+        } else {
+            let fstype = crate::fs::DiskFsType::probe(disk.device()).map_or("unknown", |ty| ty.name());
+            let main_fs = mounts::disk_fs(disk, None).expect("failed to initialize root filesystem");
         }
     }
 
-    let root_dir = RootDirectory::new(main_fs);
+    let root_dir = RootDirectory::new(main_fs, device, fstype.into());
 
     #[cfg(feature = "devfs")]
     root_dir
-        .mount("/dev", mounts::devfs())
+        .mount_at_init("/dev", mounts::devfs(), "devfs")
         .expect("failed to mount devfs at /dev");
 
     #[cfg(feature = "ramfs")]
     root_dir
-        .mount("/tmp", mounts::ramfs())
+        .mount_at_init("/tmp", mounts::ramfs(), "tmpfs")
         .expect("failed to mount ramfs at /tmp");
 
-    // Mount another ramfs as procfs
     #[cfg(feature = "procfs")]
     root_dir // should not fail
-        .mount("/proc", mounts::procfs().unwrap())
+        .mount_at_init("/proc", mounts::procfs().unwrap(), "proc")
         .expect("fail to mount procfs at /proc");
 
-    // Mount another ramfs as sysfs
     #[cfg(feature = "sysfs")]
     root_dir // should not fail
-        .mount("/sys", mounts::sysfs().unwrap())
+        .mount_at_init("/sys", mounts::sysfs().unwrap(), "sysfs")
         .expect("fail to mount sysfs at /sys");
 
     ROOT_DIR.init_once(Arc::new(root_dir));
@@ -229,13 +333,128 @@ pub(crate) fn absolute_path(path: &str) -> AxResult<String> {
     }
 }
 
+/// The maximum number of symbolic links followed in a path.
+const MAX_SYMLINKS: usize = 40;
+
+/// Reads the target of the symbolic link `node`.
+fn read_link_node(node: &VfsNodeRef) -> AxResult<String> {
+    let mut buf = vec![0; node.get_attr()?.size() as usize];
+    let mut len = 0;
+    while len < buf.len() {
+        match node.read_at(len as u64, &mut buf[len..])? {
+            0 => break,
+            n => len += n,
+        }
+    }
+    buf.truncate(len);
+    String::from_utf8(buf).map_err(|_| ax_err_type!(InvalidData, "invalid symbolic link"))
+}
+
+/// Resolves the symbolic links in `path`, which is relative to `dir`, or to
+/// the current directory if `dir` is `None`. The last component is not
+/// resolved if `follow` is false, unless `path` ends with `/`.
+///
+/// Returns the directory that the resolved path is relative to, and the path.
+/// Components after a missing one are kept as they are.
+fn resolve_links(
+    dir: Option<&VfsNodeRef>,
+    path: &str,
+    follow: bool,
+) -> PathResult<(VfsNodeRef, String)> {
+    let mut base = parent_node_of(dir, path);
+    let mut resolved = String::from(if path.starts_with('/') { "/" } else { "" });
+    // the components to be resolved, in reverse order
+    let mut rest = path
+        .split('/')
+        .rev()
+        .filter(|c| !c.is_empty() && *c != ".")
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let follow = follow || path.ends_with('/');
+    let mut links = 0;
+
+    while let Some(name) = rest.pop() {
+        if !resolved.is_empty() && !resolved.ends_with('/') {
+            resolved.push('/');
+        }
+        let parent_len = resolved.len();
+        resolved += &name;
+        if name == ".." || (rest.is_empty() && !follow) {
+            continue;
+        }
+        let node = match base.clone().lookup(&resolved) {
+            Ok(node) => node,
+            Err(AxError::NotFound) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if !node.get_attr()?.file_type().is_symlink() {
+            continue;
+        }
+
+        links += 1;
+        if links > MAX_SYMLINKS {
+            return Err(PathError::Loop);
+        }
+        let target = read_link_node(&node)?;
+        resolved.truncate(parent_len);
+        if target.starts_with('/') {
+            base = ROOT_DIR.clone();
+            resolved = "/".into();
+        }
+        rest.extend(
+            target
+                .split('/')
+                .rev()
+                .filter(|c| !c.is_empty() && *c != ".")
+                .map(String::from),
+        );
+    }
+
+    for name in rest.iter().rev() {
+        if !resolved.is_empty() && !resolved.ends_with('/') {
+            resolved.push('/');
+        }
+        resolved += name;
+    }
+    if path.ends_with('/') && !resolved.ends_with('/') {
+        resolved.push('/');
+    }
+    Ok((base, resolved))
+}
+
+/// Returns the canonical absolute path of `abs_path` with all symbolic links
+/// resolved, which is `abs_path` itself if it does not exist.
+#[cfg(feature = "page-cache")]
+pub(crate) fn real_path(abs_path: &str) -> AxResult<String> {
+    let (_, path) = resolve_links(None, abs_path, true)?;
+    Ok(axfs_vfs::path::canonicalize(&path))
+}
+
+/// Looks up `path`, following symbolic links.
 pub(crate) fn lookup(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+    Ok(lookup_at(dir, path, true)?)
+}
+
+/// Looks up `path`, without following the symbolic link of the last
+/// component.
+pub(crate) fn lookup_nofollow(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsNodeRef> {
+    Ok(lookup_at(dir, path, false)?)
+}
+
+/// Looks up `path`, following the symbolic link of the last component if
+/// `follow` is true.
+pub(crate) fn lookup_at(
+    dir: Option<&VfsNodeRef>,
+    path: &str,
+    follow: bool,
+) -> PathResult<VfsNodeRef> {
     if path.is_empty() {
-        return ax_err!(NotFound);
+        return Err(ax_err_type!(NotFound).into());
     }
-    let node = parent_node_of(dir, path).lookup(path)?;
+    let (base, path) = resolve_links(dir, path, follow)?;
+    let node = base.lookup(&path)?;
     if path.ends_with('/') && !node.get_attr()?.is_dir() {
-        ax_err!(NotADirectory)
+        Err(ax_err_type!(NotADirectory).into())
     } else {
         Ok(node)
     }
@@ -247,28 +466,91 @@ pub(crate) fn create_file(dir: Option<&VfsNodeRef>, path: &str) -> AxResult<VfsN
     } else if path.ends_with('/') {
         return ax_err!(NotADirectory);
     }
-    let parent = parent_node_of(dir, path);
-    parent.create(path, VfsNodeType::File)?;
-    parent.lookup(path)
+    let (parent, path) = resolve_links(dir, path, false)?;
+    parent.create(&path, VfsNodeType::File)?;
+    parent.lookup(&path)
 }
 
 pub(crate) fn create_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
-    match lookup(dir, path) {
+    match lookup_nofollow(dir, path) {
         Ok(_) => ax_err!(AlreadyExists),
-        Err(AxError::NotFound) => parent_node_of(dir, path).create(path, VfsNodeType::Dir),
+        Err(AxError::NotFound) => {
+            let (parent, path) = resolve_links(dir, path, false)?;
+            parent.create(&path, VfsNodeType::Dir)
+        }
         Err(e) => Err(e),
     }
 }
 
+/// Creates a symbolic link at `path` to `target`.
+///
+/// Symbolic links are created empty by [`VfsNodeOps::create`], then `target`
+/// is written to them. Filesystems that do not support them fail to create
+/// them.
+pub(crate) fn create_symlink(dir: Option<&VfsNodeRef>, target: &str, path: &str) -> AxResult {
+    if target.is_empty() {
+        return ax_err!(NotFound);
+    }
+    match lookup_nofollow(dir, path) {
+        Ok(_) => return ax_err!(AlreadyExists),
+        Err(AxError::NotFound) => {}
+        Err(e) => return Err(e),
+    }
+    let (parent, path) = resolve_links(dir, path.trim_end_matches('/'), false)?;
+    parent.create(&path, VfsNodeType::SymLink)?;
+    let node = parent.clone().lookup(&path)?;
+    if let Err(e) = node.write_at(0, target.as_bytes()) {
+        parent.remove(&path).ok();
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Reads the target of the symbolic link at `path`.
+pub(crate) fn read_link(dir: Option<&VfsNodeRef>, path: &str) -> PathResult<String> {
+    let node = lookup_at(dir, path, false)?;
+    if !node.get_attr()?.file_type().is_symlink() {
+        return Err(ax_err_type!(InvalidInput, "not a symbolic link").into());
+    }
+    Ok(read_link_node(&node)?)
+}
+
+/// Creates a hard link at `new` to the file at `old`.
+///
+/// Both paths must be in the same mounted filesystem.
+pub(crate) fn hard_link(old: &str, new: &str) -> AxResult {
+    let node = lookup_nofollow(None, old)?;
+    if node.get_attr()?.is_dir() {
+        return ax_err!(PermissionDenied, "cannot link directories");
+    }
+    match lookup_nofollow(None, new) {
+        Ok(_) => return ax_err!(AlreadyExists),
+        Err(AxError::NotFound) => {}
+        Err(e) => return Err(e),
+    }
+    if !in_same_mount(old, new)? {
+        return ax_err!(InvalidInput, "cannot link across filesystems");
+    }
+
+    let (base, new) = resolve_links(None, new.trim_end_matches('/'), false)?;
+    let (parent, name) = match new.rfind('/') {
+        Some(n) => (&new[..n + 1], &new[n + 1..]),
+        None => ("", new.as_str()),
+    };
+    let dir = base.lookup(parent)?;
+    crate::fs::link_node(&dir, name, &node)
+}
+
 pub(crate) fn remove_file(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
-    let node = lookup(dir, path)?;
+    let node = lookup_nofollow(dir, path)?;
     let attr = node.get_attr()?;
     if attr.is_dir() {
         ax_err!(IsADirectory)
     } else if !attr.perm().owner_writable() {
         ax_err!(PermissionDenied)
     } else {
-        parent_node_of(dir, path).remove(path)
+        let (parent, path) = resolve_links(dir, path, false)?;
+        parent.remove(&path)
     }
 }
 
@@ -290,17 +572,107 @@ pub(crate) fn remove_dir(dir: Option<&VfsNodeRef>, path: &str) -> AxResult {
         return ax_err!(PermissionDenied);
     }
 
-    let node = lookup(dir, path)?;
+    let node = lookup_nofollow(dir, path.trim_end_matches('/'))?;
     let attr = node.get_attr()?;
     if !attr.is_dir() {
         ax_err!(NotADirectory)
     } else if !attr.perm().owner_writable() {
         ax_err!(PermissionDenied)
     } else {
-        parent_node_of(dir, path).remove(path)
+        let (parent, path) = resolve_links(dir, path, false)?;
+        parent.remove(&path)
     }
 }
 
+pub(crate) fn mount(source: &str, target: &str, fstype: &str, flags: MountFlags) -> AxResult {
+    let target = absolute_path(target)?;
+    let mount_point = lookup(None, &target)?;
+    if !mount_point.get_attr()?.is_dir() {
+        return ax_err!(NotADirectory);
+    }
+    if ROOT_DIR.contains(&target) {
+        return ax_err!(ResourceBusy, "mount point already exists");
+    }
+
+    if flags.contains(MountFlags::BIND) {
+        let source = absolute_path(source)?;
+        let root = lookup(None, &source)?;
+        if !root.get_attr()?.is_dir() {
+            return ax_err!(NotADirectory);
+        }
+        let source_mount = ROOT_DIR.mount_point_of(&source);
+        // bind mounts show the filesystem that the tree is in
+        let info = match &source_mount {
+            Some(mp) => (mp.device.clone(), mp.fstype.clone()),
+            None => ROOT_DIR.main_info.clone(),
+        };
+        ROOT_DIR.attach(target, root, MountSource::Bind(source_mount), info)
+    } else {
+        let source = absolute_path(source)?;
+        let fs = mounts::new_fs(&source, fstype)?;
+        let fstype = mounts::fs_type_name(&source, fstype);
+        fs.mount(&target, mount_point)?;
+        ROOT_DIR.attach(target, fs.root_dir(), MountSource::Fs(fs), (source, fstype))
+    }
+}
+
+/// Returns the device, the path and the filesystem type of each mount,
+/// starting with the root filesystem.
+pub(crate) fn mount_table() -> Vec<(String, String, String)> {
+    let (device, fstype) = ROOT_DIR.main_info.clone();
+    let mut table = vec![(device, String::from("/"), fstype)];
+    for mp in ROOT_DIR.mounts.read().iter() {
+        table.push((mp.device.clone(), mp.path.clone(), mp.fstype.clone()));
+    }
+    table
+}
+
+/// Returns the usage of the filesystem that `path` is in.
+pub(crate) fn statfs(path: &str) -> AxResult<FsStat> {
+    let node = lookup(None, path)?;
+    let (_, path) = resolve_links(None, &absolute_path(path)?, true)?;
+    let fstype = match mount_point_of(&path)? {
+        Some(mp) => mp.fstype.clone(),
+        None => ROOT_DIR.main_info.1.clone(),
+    };
+    Ok(FsStat {
+        fstype,
+        ..crate::fs::fs_stat(&node)?
+    })
+}
+
+pub(crate) fn umount(target: &str) -> AxResult {
+    ROOT_DIR.detach(&absolute_path(target)?)
+}
+
+/// Returns the mount point that `path` is in, or `None` for the main
+/// filesystem.
+pub(crate) fn mount_point_of(path: &str) -> AxResult<Option<Arc<MountPoint>>> {
+    Ok(ROOT_DIR.mount_point_of(&absolute_path(path)?))
+}
+
+/// Returns an ID of the filesystem that `path` is in, which is unique among
+/// the mounted filesystems. Bind mounts have the ID of their source.
+#[cfg(feature = "page-cache")]
+pub(crate) fn fs_id(path: &str) -> AxResult<usize> {
+    let mut mount = ROOT_DIR.mount_point_of(&real_path(path)?);
+    while let Some(mp) = mount {
+        match &mp.source {
+            MountSource::Fs(_) => return Ok(Arc::as_ptr(&mp) as usize),
+            MountSource::Bind(source) => mount = source.clone(),
+        }
+    }
+    Ok(0) // the main filesystem
+}
+
+/// Whether `a` and `b` are in the same mount point.
+fn in_same_mount(a: &str, b: &str) -> AxResult<bool> {
+    Ok(match (mount_point_of(a)?, mount_point_of(b)?) {
+        (Some(a), Some(b)) => Arc::ptr_eq(&a, &b),
+        (a, b) => a.is_none() && b.is_none(),
+    })
+}
+
 pub(crate) fn current_dir() -> AxResult<String> {
     Ok(CURRENT_DIR_PATH.lock().clone())
 }
@@ -329,10 +701,39 @@ pub(crate) fn set_current_dir(path: &str) -> AxResult {
     }
 }
 
+/// Renames `old` to `new`, which replaces `new` if it is a file, or an empty
+/// directory when `old` is a directory.
 pub(crate) fn rename(old: &str, new: &str) -> AxResult {
-    if parent_node_of(None, new).lookup(new).is_ok() {
-        warn!("dst file already exist, now remove it");
-        remove_file(None, new)?;
+    let (_, old_path) = resolve_links(None, &absolute_path(old)?, false)?;
+    let (_, new_path) = resolve_links(None, &absolute_path(new)?, false)?;
+    if !in_same_mount(&old_path, &new_path)? {
+        return ax_err!(InvalidInput, "cannot rename across filesystems");
+    }
+    let node = lookup_nofollow(None, old)?;
+    let is_dir = node.get_attr()?.is_dir();
+    let (old_abs, new_abs) = (absolute_path(&old_path)?, absolute_path(&new_path)?);
+    if old_abs == new_abs {
+        return Ok(());
+    } else if is_dir && is_inside(&new_abs, &old_abs) {
+        return ax_err!(InvalidInput, "cannot move a directory into itself");
+    }
+    if let Ok(dst) = lookup_nofollow(None, new) {
+        match (is_dir, dst.get_attr()?.is_dir()) {
+            (true, true) => remove_dir(None, new)?,
+            (false, false) => remove_file(None, new)?,
+            (true, false) => return ax_err!(NotADirectory),
+            (false, true) => return ax_err!(IsADirectory),
+        }
+    }
+    #[cfg(feature = "page-cache")]
+    let old_key = crate::page_cache::cache_key(&old_abs, &node)?;
+    ROOT_DIR.rename(&old_path, &new_path)?;
+    #[cfg(feature = "page-cache")]
+    if let Some(old_key) = old_key {
+        let new_key = crate::page_cache::cache_key(&new_abs, &lookup_nofollow(None, new)?)?;
+        if let Some(new_key) = new_key {
+            crate::page_cache::rename(old_key, new_key);
+        }
     }
-    parent_node_of(None, old).rename(old, new)
+    Ok(())
 }
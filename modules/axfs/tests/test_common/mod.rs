@@ -1,7 +1,11 @@
 use axfs::api as fs;
+use axfs::devfs::{self, CharDevice};
 use axio as io;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+use std::sync::Arc;
 
-use fs::{File, FileType, OpenOptions};
+use fs::{File, FileType, MountFlags, OpenOptions};
 use io::{Error, Result, prelude::*};
 
 macro_rules! assert_err {
@@ -207,6 +211,47 @@ fn test_devfs_ramfs() -> Result<()> {
     assert!(!md.is_file());
     assert!(md.is_dir());
 
+    // a registered character device
+    struct TestDev;
+    impl CharDevice for TestDev {
+        fn read_at(&self, _offset: u64, buf: &mut [u8]) -> Result<usize> {
+            buf.fill(b'x');
+            Ok(buf.len())
+        }
+        fn write_at(&self, _offset: u64, _buf: &[u8]) -> Result<usize> {
+            Err(Error::Unsupported)
+        }
+    }
+    devfs::register_char_device("foo/bar", Arc::new(TestDev))?;
+    assert_err!(
+        devfs::register_char_device("foo/bar", Arc::new(TestDev)),
+        AlreadyExists
+    );
+    assert_err!(
+        devfs::register_char_device("null/bar", Arc::new(TestDev)),
+        NotADirectory
+    );
+    assert_eq!(fs::read_dir("/dev/foo")?.count(), 1);
+    let mut file = File::options()
+        .read(true)
+        .write(true)
+        .open("/dev/foo/bar")?;
+    assert_eq!(file.read(&mut buf)?, N);
+    assert_eq!(buf, [b'x'; N]);
+    assert_err!(file.write(&buf), Unsupported);
+
+    // read /dev/urandom, which only exists with a source of entropy
+    if axhal::random::entropy().is_some() {
+        let mut file = File::open("/dev/urandom")?;
+        let (mut rand1, mut rand2) = ([0; N], [0; N]);
+        assert_eq!(file.read(&mut rand1)?, N);
+        assert_eq!(file.read(&mut rand2)?, N);
+        assert_ne!(rand1, rand2);
+    } else {
+        assert_err!(File::open("/dev/urandom"), NotFound);
+    }
+    assert_eq!(fs::metadata("/dev/tty")?.file_type(), FileType::CharDevice);
+
     // stat /dev/foo/bar
     let fname = ".//.///././/./dev///.///./foo//././bar";
     let md = fs::metadata(fname)?;
@@ -250,6 +295,537 @@ fn test_devfs_ramfs() -> Result<()> {
     Ok(())
 }
 
+fn test_mount() -> Result<()> {
+    println!("test mount and umount:");
+    let no_flags = MountFlags::empty();
+
+    // mount a filesystem, and another one inside it
+    fs::create_dir("/mnt")?;
+    fs::mount("", "/mnt", "ramfs", no_flags)?;
+    fs::write("/mnt/test.txt", "mounted\n")?;
+    fs::create_dir("/mnt/inner")?;
+    fs::mount("", "mnt//inner/", "tmpfs", no_flags)?;
+    fs::write("/mnt/./inner//test.txt", "nested\n")?;
+    assert_eq!(fs::read_to_string("/mnt/test.txt")?, "mounted\n");
+    assert_eq!(fs::read_to_string("mnt/inner/test.txt")?, "nested\n");
+    assert_eq!(fs::read_dir("/mnt/inner")?.count(), 1);
+
+    // a bind mount shows the same files
+    fs::create_dir("/mnt2")?;
+    fs::mount("/mnt", "/mnt2", "", MountFlags::BIND)?;
+    assert_eq!(fs::read_to_string("/mnt2/test.txt")?, "mounted\n");
+    fs::write("/mnt2/bind.txt", "bind\n")?;
+    assert_eq!(fs::read_to_string("/mnt/bind.txt")?, "bind\n");
+
+    // busy filesystems
+    assert_err!(fs::umount("/mnt"), ResourceBusy);
+    let file = File::open("/mnt/inner/test.txt")?;
+    assert_err!(fs::umount("/mnt/inner"), ResourceBusy);
+    drop(file);
+    fs::umount("/mnt/inner")?;
+    assert_err!(fs::metadata("/mnt/inner/test.txt"), NotFound);
+    fs::umount("/mnt2/")?;
+    fs::umount("/mnt")?;
+    assert_err!(fs::metadata("/mnt/test.txt"), NotFound);
+    assert_err!(fs::metadata("/mnt2/test.txt"), NotFound);
+
+    // error cases
+    assert_err!(fs::umount("/mnt"), InvalidInput);
+    assert_err!(fs::mount("", "/mnt", "unknown", no_flags), Unsupported);
+    assert_err!(fs::mount("", "/not-exist", "ramfs", no_flags), NotFound);
+    assert_err!(
+        fs::mount("", "/short.txt", "ramfs", no_flags),
+        NotADirectory
+    );
+    assert_err!(fs::mount("", "/dev", "ramfs", no_flags), ResourceBusy);
+
+    // block devices
+    let md = fs::metadata("/dev/vda")?;
+    assert_eq!(md.file_type(), FileType::BlockDevice);
+    assert_err!(fs::mount("/dev/vda", "/mnt", "", no_flags), ResourceBusy); // the root device
+    assert_err!(fs::mount("/dev/vdz", "/mnt", "auto", no_flags), NotFound);
+    fs::remove_dir("/mnt")?;
+    fs::remove_dir("/mnt2")?;
+
+    println!("test_mount() OK!");
+    Ok(())
+}
+
+fn test_sync() -> Result<()> {
+    let fname = "/sync.bin";
+    println!("test sync with file {:?}:", fname);
+
+    // more blocks than the block cache
+    let data = (0..600 * 1024).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let mut file = File::create(fname)?;
+    file.write_all(&data)?;
+    file.flush()?;
+    drop(file);
+    assert_eq!(fs::read(fname)?, data);
+
+    // unaligned writes in the middle of blocks
+    let mut file = File::options().read(true).write(true).open(fname)?;
+    file.seek(io::SeekFrom::Start(1000))?;
+    file.write_all(&[0xaa; 3000])?;
+    fs::sync()?;
+    let mut buf = vec![0; 4000];
+    file.seek(io::SeekFrom::Start(500))?;
+    file.read_exact(&mut buf)?;
+    assert_eq!(buf[..500], data[500..1000]);
+    assert!(buf[500..3500].iter().all(|&b| b == 0xaa));
+    assert_eq!(buf[3500..], data[4000..4500]);
+    drop(file);
+
+    fs::remove_file(fname)?;
+    println!("test_sync() OK!");
+    Ok(())
+}
+
+fn test_grow_rename() -> Result<()> {
+    // the same results on ramfs and on the FAT root filesystem
+    let mut dirs = vec!["/tmp"];
+    if fs::statfs("/")?.fstype == "vfat" {
+        dirs.push("");
+    }
+    for dir in dirs {
+        println!("test growing files and renaming in {:?}:", dir);
+        let path = |name: &str| format!("{dir}/{name}");
+
+        // extend by truncating and by writing after the end
+        let fname = path("grow.txt");
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .open(&fname)?;
+        file.write_all(b"abc")?;
+        file.set_len(1000)?;
+        assert_eq!(file.metadata()?.len(), 1000);
+        file.seek(io::SeekFrom::Start(2000))?;
+        file.write_all(b"x")?;
+        drop(file);
+        let data = fs::read(&fname)?;
+        assert_eq!(data.len(), 2001);
+        assert_eq!(data[..3], *b"abc");
+        assert!(data[3..2000].iter().all(|&b| b == 0));
+        File::options().write(true).open(&fname)?.set_len(2)?;
+        assert_eq!(fs::read(&fname)?, b"ab");
+
+        // move a directory to another one, whose `..` is the new parent
+        fs::create_dir(&path("src"))?;
+        fs::create_dir(&path("src/sub"))?;
+        fs::write(&path("src/sub/file.txt"), "moved\n")?;
+        fs::create_dir(&path("dst"))?;
+        fs::rename(&path("src/sub"), &path("dst/sub"))?;
+        assert_eq!(fs::read_to_string(&path("dst/sub/file.txt"))?, "moved\n");
+        assert_err!(fs::metadata(&path("src/sub")), NotFound);
+        let dirents = fs::read_dir(&path("dst/sub/.."))?
+            .map(|e| e.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert!(dirents.contains(&"sub".into()));
+
+        // error cases
+        fs::write(&path("dst/file.txt"), "file\n")?;
+        assert_err!(
+            fs::rename(&path("dst"), &path("dst/sub/inner")),
+            InvalidInput
+        );
+        assert_err!(
+            fs::rename(&path("dst/file.txt"), &path("dst/sub")),
+            IsADirectory
+        );
+        assert_err!(
+            fs::rename(&path("dst/sub"), &path("dst/file.txt")),
+            NotADirectory
+        );
+        assert_err!(fs::rename(&path("src"), &path("dst")), DirectoryNotEmpty);
+
+        // replace an empty directory, and a file
+        fs::create_dir(&path("src/sub"))?;
+        fs::rename(&path("dst/sub"), &path("src/sub"))?;
+        assert_eq!(fs::read_to_string(&path("src/sub/file.txt"))?, "moved\n");
+        fs::rename(&fname, &path("dst/file.txt"))?;
+        assert_eq!(fs::read(&path("dst/file.txt"))?, b"ab");
+
+        fs::remove_file(&path("src/sub/file.txt"))?;
+        fs::remove_dir(&path("src/sub"))?;
+        fs::remove_dir(&path("src"))?;
+        fs::remove_file(&path("dst/file.txt"))?;
+        fs::remove_dir(&path("dst"))?;
+    }
+    println!("test_grow_rename() OK!");
+    Ok(())
+}
+
+fn test_shared_file() -> Result<()> {
+    // the same results on ramfs and on the FAT root filesystem
+    let mut dirs = vec!["/tmp"];
+    if fs::statfs("/")?.fstype == "vfat" {
+        dirs.push("");
+    }
+    let read_all = |file: &mut File| -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        file.seek(io::SeekFrom::Start(0))?;
+        file.read_to_end(&mut buf)?;
+        Ok(buf)
+    };
+    for dir in dirs {
+        println!("test a file opened by several handles in {:?}:", dir);
+        let fname = format!("{dir}/shared.txt");
+        fs::write(&fname, "abc")?;
+        let mut reader = File::open(&fname)?;
+        assert_eq!(read_all(&mut reader)?, b"abc");
+
+        // extended with zeros by another handle
+        File::options().write(true).open(&fname)?.set_len(6)?;
+        assert_eq!(read_all(&mut reader)?, b"abc\0\0\0");
+
+        // truncated and written by its new name
+        let renamed = format!("{dir}/renamed.txt");
+        fs::rename(&fname, &renamed)?;
+        fs::write(&renamed, "xy")?;
+        assert_eq!(read_all(&mut reader)?, b"xy");
+        drop(reader);
+        fs::remove_file(&renamed)?;
+    }
+
+    println!("test a file opened by its hard links in /tmp:");
+    fs::write("/tmp/shared.txt", "abc")?;
+    fs::hard_link("/tmp/shared.txt", "/tmp/hard.txt")?;
+    let mut reader = File::open("/tmp/shared.txt")?;
+    assert_eq!(read_all(&mut reader)?, b"abc");
+    fs::write("/tmp/hard.txt", "hello")?;
+    assert_eq!(read_all(&mut reader)?, b"hello");
+    drop(reader);
+    fs::remove_file("/tmp/shared.txt")?;
+    fs::remove_file("/tmp/hard.txt")?;
+
+    println!("test_shared_file() OK!");
+    Ok(())
+}
+
+#[cfg(feature = "page-cache")]
+fn test_mapped_page() -> Result<()> {
+    println!("test a page of a file mapped into memory:");
+    let fname = "/tmp/mapped.txt";
+    fs::write(fname, "hello")?;
+    let mut opts = axfs::fops::OpenOptions::new();
+    opts.read(true);
+    opts.write(true);
+    // the data is read through raw pointers, as the cache writes it too
+    let data = |page: core::ptr::NonNull<u8>, len| unsafe {
+        core::slice::from_raw_parts(page.as_ptr(), len).to_vec()
+    };
+    let file = axfs::fops::File::open(fname, &opts)?;
+    let page = file.map_page(0)?.expect("no page cache");
+    assert_eq!(data(page, 6), b"hello\0");
+
+    // shared with the reads and writes of the file
+    File::options().write(true).open(fname)?.write_all(b"J")?;
+    assert_eq!(data(page, 5), b"Jello");
+    unsafe { core::ptr::copy_nonoverlapping(b"ELLO".as_ptr(), page.as_ptr().add(1), 4) };
+    assert_eq!(fs::read_to_string(fname)?, "JELLO");
+
+    // written to the file when synced, then dropped with the cache
+    file.sync_page(0)?;
+    file.unmap_page(0);
+    drop(file);
+    assert_eq!(fs::read_to_string(fname)?, "JELLO");
+
+    // zeroed but still mapped after the file is truncated
+    let file = axfs::fops::File::open(fname, &opts)?;
+    let page = file.map_page(0)?.expect("no page cache");
+    file.truncate(0)?;
+    assert_eq!(data(page, 5), [0; 5]);
+    file.sync_page(0)?;
+    assert_eq!(file.get_attr()?.size(), 0);
+    file.unmap_page(0);
+    drop(file);
+    fs::remove_file(fname)?;
+
+    println!("test_mapped_page() OK!");
+    Ok(())
+}
+
+fn test_statfs() -> Result<()> {
+    println!("test statfs:");
+    let stat = fs::statfs("/tmp/")?;
+    assert_eq!((stat.fstype.as_str(), stat.blocks), ("tmpfs", 0));
+    assert_eq!(fs::statfs("/dev/null")?.fstype, "devfs");
+    assert_eq!(fs::statfs("/proc")?.fstype, "proc");
+    assert_err!(fs::statfs("/not-exist"), NotFound);
+
+    let stat = fs::statfs("/")?;
+    println!("statfs of /: {:?}", stat);
+    if stat.fstype == "vfat" {
+        // the volume made by `resources/create_test_img.sh`
+        assert_eq!(stat.label.as_deref(), Some("Test!"));
+        assert_eq!(stat.block_size, 512);
+        let free = stat.blocks_free;
+        fs::write("/statfs.bin", [0; 4096])?;
+        assert_eq!(fs::statfs("/")?.blocks_free, free - 8);
+        fs::remove_file("/statfs.bin")?;
+        assert_eq!(fs::statfs("/")?.blocks_free, free);
+    }
+
+    println!("test_statfs() OK!");
+    Ok(())
+}
+
+fn test_links() -> Result<()> {
+    println!("test symbolic links and hard links in /tmp:");
+
+    fs::create_dir("/tmp/links")?;
+    assert_eq!(fs::metadata("/tmp/links")?.nlink(), 2);
+    fs::write("/tmp/links/file.txt", "hello")?;
+
+    // relative and absolute targets
+    fs::symlink("file.txt", "/tmp/links/rel")?;
+    fs::symlink("/tmp/links/file.txt", "/tmp/links/abs")?;
+    assert_eq!(fs::read_to_string("/tmp/links/rel")?, "hello");
+    assert_eq!(fs::read_to_string("/tmp/links/abs")?, "hello");
+    assert_eq!(fs::read_link("/tmp/links/rel")?, "file.txt");
+    assert!(fs::symlink_metadata("/tmp/links/rel")?.is_symlink());
+    assert!(fs::metadata("/tmp/links/rel")?.is_file());
+    assert_err!(fs::read_link("/tmp/links/file.txt"), InvalidInput);
+    assert_err!(fs::symlink("file.txt", "/tmp/links/rel"), AlreadyExists);
+
+    // links to directories, and through them
+    fs::symlink("..", "/tmp/links/up")?;
+    assert_eq!(fs::read_to_string("/tmp/links/up/links/abs")?, "hello");
+    assert!(fs::metadata("/tmp/links/up/")?.is_dir());
+    fs::write("/tmp/links/up/links/new.txt", "world")?;
+    assert_eq!(fs::read_to_string("/tmp/links/new.txt")?, "world");
+
+    // dangling links, loops and O_NOFOLLOW
+    fs::symlink("missing", "/tmp/links/dangling")?;
+    assert_err!(fs::metadata("/tmp/links/dangling"), NotFound);
+    assert!(fs::symlink_metadata("/tmp/links/dangling")?.is_symlink());
+    fs::symlink("loop", "/tmp/links/loop")?;
+    assert_err!(fs::metadata("/tmp/links/loop"));
+    let loop_err = Some(axfs::fops::PathError::Loop);
+    assert_eq!(
+        axfs::fops::file_attr("/tmp/links/loop", true).err(),
+        loop_err
+    );
+    assert!(axfs::fops::file_attr("/tmp/links/loop", false).is_ok());
+    assert_eq!(
+        axfs::fops::file_attr("/tmp/links/dangling", true).err(),
+        Some(axfs::fops::PathError::Other(Error::NotFound))
+    );
+    assert_err!(OpenOptions::new().read(true).open("/tmp/links/loop"));
+    let mut opts = axfs::fops::OpenOptions::new();
+    opts.read(true);
+    opts.nofollow(true);
+    assert_eq!(
+        axfs::fops::File::open("/tmp/links/rel", &opts).err(),
+        loop_err
+    );
+    assert!(axfs::fops::File::open("/tmp/links/file.txt", &opts).is_ok());
+
+    // removing a link keeps its target
+    fs::remove_file("/tmp/links/rel")?;
+    assert_eq!(fs::read_to_string("/tmp/links/abs")?, "hello");
+
+    // hard links share the content
+    fs::hard_link("/tmp/links/file.txt", "/tmp/links/hard")?;
+    fs::write("/tmp/links/hard", "changed")?;
+    assert_eq!(fs::read_to_string("/tmp/links/file.txt")?, "changed");
+    let (meta, hard_meta) = (
+        fs::metadata("/tmp/links/file.txt")?,
+        fs::metadata("/tmp/links/hard")?,
+    );
+    assert_eq!((meta.nlink(), hard_meta.nlink()), (2, 2));
+    assert_ne!(meta.ino(), 0);
+    assert_eq!(meta.ino(), hard_meta.ino());
+    assert_ne!(meta.ino(), fs::symlink_metadata("/tmp/links/abs")?.ino());
+    fs::remove_file("/tmp/links/file.txt")?;
+    assert_eq!(fs::read_to_string("/tmp/links/hard")?, "changed");
+    assert_eq!(fs::metadata("/tmp/links/hard")?.nlink(), 1);
+    assert_err!(fs::hard_link("/tmp/links", "/tmp/links2"), PermissionDenied);
+    assert_err!(fs::hard_link("/tmp/links/hard", "/hard"), InvalidInput);
+
+    // the root filesystem does not support symbolic links
+    assert_err!(fs::symlink("/tmp/links/hard", "/sym"), Unsupported);
+
+    for name in ["abs", "up", "new.txt", "dangling", "loop", "hard"] {
+        fs::remove_file(&format!("/tmp/links/{}", name))?;
+    }
+    fs::remove_dir("/tmp/links")?;
+    println!("test_links() OK!");
+    Ok(())
+}
+
+fn test_metadata() -> Result<()> {
+    println!("test ownership, permissions and timestamps in /tmp:");
+
+    let fname = "/tmp/meta.txt";
+    fs::write(fname, "hello")?;
+    let meta = fs::metadata(fname)?;
+    assert_eq!((meta.uid(), meta.gid()), (0, 0));
+    assert!(meta.created().is_ok());
+
+    // chmod and chown
+    let perm = fs::Permissions::from_bits_truncate(0o640);
+    fs::set_permissions(fname, perm)?;
+    assert_eq!(fs::metadata(fname)?.permissions(), perm);
+    fs::chown(fname, Some(1000), None)?;
+    fs::chown(fname, None, Some(100))?;
+    let meta = fs::metadata(fname)?;
+    assert_eq!((meta.uid(), meta.gid()), (1000, 100));
+
+    // set the times, and writes update the modification time
+    let (atime, mtime) = (
+        Duration::from_secs(1_000_000),
+        Duration::from_secs(2_000_000),
+    );
+    fs::set_times(fname, Some(atime), Some(mtime))?;
+    let meta = fs::metadata(fname)?;
+    assert_eq!((meta.accessed()?, meta.modified()?), (atime, mtime));
+    fs::set_times(fname, None, Some(atime))?;
+    assert_eq!(fs::metadata(fname)?.accessed()?, atime);
+    fs::write(fname, "world")?;
+    assert_ne!(fs::metadata(fname)?.modified()?, atime);
+
+    // through the opened file and symbolic links
+    let file = File::open(fname)?;
+    file.set_times(None, Some(mtime))?;
+    assert_eq!(file.metadata()?.modified()?, mtime);
+    fs::symlink(fname, "/tmp/meta.lnk")?;
+    fs::set_permissions("/tmp/meta.lnk", fs::Permissions::from_bits_truncate(0o600))?;
+    assert_eq!(fs::metadata(fname)?.permissions().bits(), 0o600);
+    assert!(fs::symlink_metadata("/tmp/meta.lnk")?.is_symlink());
+    fs::set_symlink_times("/tmp/meta.lnk", None, Some(atime))?;
+    assert_eq!(fs::symlink_metadata("/tmp/meta.lnk")?.modified()?, atime);
+    assert_eq!(fs::metadata("/tmp/meta.lnk")?.modified()?, mtime);
+    drop(file);
+
+    fs::remove_file("/tmp/meta.lnk")?;
+    fs::remove_file(fname)?;
+
+    // FAT keeps only the date of the last access, and the modification time
+    // in units of 2 seconds
+    if fs::statfs("/")?.fstype == "vfat" {
+        let fname = "/fat-times.txt";
+        fs::write(fname, "hello")?;
+        assert!(fs::metadata(fname)?.created().is_ok());
+        // 2000-02-29 12:34:56
+        let time = Duration::from_secs(951_827_696);
+        fs::set_times(fname, Some(time), Some(time))?;
+        let meta = fs::metadata(fname)?;
+        assert_eq!(meta.modified()?, time);
+        assert_eq!(meta.accessed()?, Duration::from_secs(951_782_400));
+        // clamped to 1980-01-01
+        fs::set_times(fname, None, Some(Duration::ZERO))?;
+        assert_eq!(
+            fs::metadata(fname)?.modified()?,
+            Duration::from_secs(315_532_800)
+        );
+        fs::remove_file(fname)?;
+    }
+    println!("test_metadata() OK!");
+    Ok(())
+}
+
+fn test_procfs() -> Result<()> {
+    println!("test generated files in /proc:");
+
+    let mounts = fs::read_to_string("/proc/mounts")?;
+    assert!(
+        mounts
+            .lines()
+            .any(|line| line.starts_with("tmpfs /tmp tmpfs "))
+    );
+    assert!(mounts.lines().any(|line| line.contains(" /proc proc ")));
+    assert_eq!(
+        fs::read_to_string("/proc/sys/net/core/somaxconn")?,
+        "4096\n"
+    );
+
+    // files are generated again for each open
+    static READS: AtomicUsize = AtomicUsize::new(0);
+    axfs::procfs::add_file("test/reads", || {
+        format!("{}\n", READS.fetch_add(1, Ordering::Relaxed))
+    })?;
+    assert_eq!(fs::read_to_string("/proc/test/reads")?, "0\n");
+    assert_eq!(fs::read_to_string("/proc/test/reads")?, "1\n");
+    let mut file = File::open("/proc/test/reads")?;
+    let mut buf = [0; 1];
+    file.seek(io::SeekFrom::Start(1))?;
+    assert_eq!(file.read(&mut buf)?, 1);
+    assert_eq!(&buf, b"\n");
+
+    // entries generated on each lookup
+    axfs::procfs::add_entries("test", || {
+        let node = axfs::procfs::ProcFile::new(|| "generated\n".into());
+        vec![("gen".into(), node as _)]
+    })?;
+    assert_eq!(fs::read_to_string("/proc/test/gen")?, "generated\n");
+    let mut names = fs::read_dir("/proc/test")?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<Result<Vec<_>>>()?;
+    names.sort();
+    assert_eq!(names, ["gen", "reads"]);
+    assert_eq!(fs::read_to_string("/proc/sys/vm/overcommit_memory")?, "0\n");
+
+    // the content is kept while an opened file is read in pieces, and is
+    // generated again when it is read from the start
+    static PIECES: AtomicUsize = AtomicUsize::new(1);
+    axfs::procfs::add_file("test/pieces", || {
+        "ab".repeat(PIECES.fetch_add(1, Ordering::Relaxed))
+    })?;
+    let mut file = File::open("/proc/test/pieces")?;
+    let mut content = Vec::new();
+    while file.read(&mut buf)? > 0 {
+        content.push(buf[0]);
+    }
+    assert_eq!(content, b"ab");
+    file.seek(io::SeekFrom::Start(0))?;
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    assert_eq!(content, "abab");
+
+    // read-only
+    assert_err!(fs::write("/proc/test/reads", "1"), PermissionDenied);
+    assert_err!(fs::write("/proc/test/new", "1"), PermissionDenied);
+    assert_err!(fs::remove_file("/proc/test/gen"), PermissionDenied);
+
+    println!("test_procfs() OK!");
+    Ok(())
+}
+
+fn test_sysfs() -> Result<()> {
+    println!("test devices and tunables in /sys:");
+
+    let size = fs::read_to_string("/sys/class/block/vda/size")?;
+    assert!(size.trim().parse::<u64>().unwrap() > 0);
+    assert_eq!(fs::read_to_string("/sys/class/block/vda/ro")?, "0\n");
+
+    // tunables take effect when written
+    static VALUE: AtomicUsize = AtomicUsize::new(1);
+    axfs::sysfs::add_writable_file(
+        "module/test/parameters/value",
+        || format!("{}\n", VALUE.load(Ordering::Relaxed)),
+        |value| {
+            let value = value.parse().map_err(|_| Error::InvalidInput)?;
+            VALUE.store(value, Ordering::Relaxed);
+            Ok(())
+        },
+    )?;
+    let path = "/sys/module/test/parameters/value";
+    assert_eq!(fs::metadata(path)?.permissions().bits(), 0o644);
+    assert_eq!(fs::read_to_string(path)?, "1\n");
+    fs::write(path, "42\n")?;
+    assert_eq!(VALUE.load(Ordering::Relaxed), 42);
+    assert_eq!(fs::read_to_string(path)?, "42\n");
+    assert_err!(fs::write(path, "invalid"), InvalidInput);
+    assert_eq!(fs::read_to_string(path)?, "42\n");
+    assert_err!(fs::write("/sys/class/block/vda/ro", "1"), PermissionDenied);
+
+    println!("test_sysfs() OK!");
+    Ok(())
+}
+
 pub fn test_all() {
     test_read_write_file().expect("test_read_write_file() failed");
     test_read_dir().expect("test_read_dir() failed");
@@ -257,4 +833,15 @@ pub fn test_all() {
     test_create_file_dir().expect("test_create_file_dir() failed");
     test_remove_file_dir().expect("test_remove_file_dir() failed");
     test_devfs_ramfs().expect("test_devfs_ramfs() failed");
+    test_mount().expect("test_mount() failed");
+    test_sync().expect("test_sync() failed");
+    test_grow_rename().expect("test_grow_rename() failed");
+    test_shared_file().expect("test_shared_file() failed");
+    #[cfg(feature = "page-cache")]
+    test_mapped_page().expect("test_mapped_page() failed");
+    test_statfs().expect("test_statfs() failed");
+    test_links().expect("test_links() failed");
+    test_metadata().expect("test_metadata() failed");
+    test_procfs().expect("test_procfs() failed");
+    test_sysfs().expect("test_sysfs() failed");
 }
@@ -0,0 +1,92 @@
+#![cfg(feature = "lwext4_rs")]
+
+use axdriver::AxDeviceContainer;
+use axdriver_block::ramdisk::RamDisk;
+use axfs::api as fs;
+use core::time::Duration;
+
+const IMG_PATH: &str = "resources/ext4.img";
+
+fn make_disk() -> std::io::Result<RamDisk> {
+    let path = std::env::current_dir()?.join(IMG_PATH);
+    println!("Loading disk image from {:?} ...", path);
+    let data = std::fs::read(path)?;
+    println!("size = {} bytes", data.len());
+    Ok(RamDisk::from(&data))
+}
+
+fn test_links() -> axio::Result<()> {
+    // the links made by mkfs.ext4
+    let meta = fs::metadata("/short.txt")?;
+    assert_eq!(meta.nlink(), 2);
+    assert_ne!(meta.ino(), 0);
+    assert_eq!(fs::metadata("/hard.txt")?.ino(), meta.ino());
+    assert!(fs::symlink_metadata("/sym")?.is_symlink());
+    assert_ne!(fs::symlink_metadata("/sym")?.ino(), meta.ino());
+    assert_eq!(fs::read_link("/sym")?, "short.txt");
+    assert_eq!(fs::read_to_string("/sym")?, "Rust is cool!\n");
+    // `.`, the entry in the root, and `..` in `long`
+    assert_eq!(fs::metadata("/very")?.nlink(), 3);
+
+    // hard links share the content
+    fs::hard_link("/very/long/path/test.txt", "/very/test.txt")?;
+    assert_eq!(fs::metadata("/very/test.txt")?.nlink(), 2);
+    fs::write("/very/test.txt", "changed")?;
+    assert_eq!(fs::read_to_string("/very/long/path/test.txt")?, "changed");
+    fs::remove_file("/very/long/path/test.txt")?;
+    assert_eq!(fs::read_to_string("/very/test.txt")?, "changed");
+    assert_eq!(fs::metadata("/very/test.txt")?.nlink(), 1);
+    assert_eq!(
+        fs::hard_link("/very", "/very2").err(),
+        Some(axio::Error::PermissionDenied)
+    );
+    assert_eq!(
+        fs::hard_link("/short.txt", "/tmp/hard").err(),
+        Some(axio::Error::InvalidInput)
+    );
+
+    // symbolic links, to directories and in loops
+    fs::symlink("long/path", "/very/dir")?;
+    assert!(fs::metadata("/very/dir")?.is_dir());
+    fs::write("/very/dir/new.txt", "new")?;
+    assert_eq!(fs::read_to_string("/very/long/path/new.txt")?, "new");
+    fs::symlink("/very/loop", "/very/loop")?;
+    assert!(fs::metadata("/very/loop").is_err());
+    assert_eq!(
+        axfs::fops::file_attr("/very/loop", true).err(),
+        Some(axfs::fops::PathError::Loop)
+    );
+    assert!(fs::symlink_metadata("/very/loop")?.is_symlink());
+
+    for name in ["test.txt", "dir", "loop", "long/path/new.txt"] {
+        fs::remove_file(&format!("/very/{}", name))?;
+    }
+    Ok(())
+}
+
+fn test_times() -> axio::Result<()> {
+    let time = Duration::from_secs(2_000_000_000);
+    fs::set_times("/short.txt", Some(time), Some(time))?;
+    let meta = fs::metadata("/short.txt")?;
+    assert_eq!((meta.accessed()?, meta.modified()?), (time, time));
+    // clamped to 2038-01-19 instead of wrapping
+    fs::set_times("/short.txt", None, Some(Duration::from_secs(1 << 32)))?;
+    let max = Duration::from_secs(i32::MAX as u64);
+    assert_eq!(fs::metadata("/short.txt")?.modified()?, max);
+    Ok(())
+}
+
+#[test]
+fn test_ext4() {
+    println!("Testing ext4 with ramdisk ...");
+
+    let disk = make_disk().expect("failed to load disk image");
+    axtask::init_scheduler(); // call this to use `axsync::Mutex`.
+    axfs::init_filesystems(AxDeviceContainer::from_one(disk));
+
+    assert_eq!(fs::statfs("/").unwrap().fstype, "ext4");
+    test_links().expect("test_links() failed");
+    println!("test_links() OK!");
+    test_times().expect("test_times() failed");
+    println!("test_times() OK!");
+}
@@ -4,6 +4,8 @@ mod test_common;
 
 use axdriver::AxDeviceContainer;
 use axdriver_block::ramdisk::RamDisk;
+use axfs::api::{self as fs, MountFlags};
+use axio::Error;
 
 const IMG_PATH: &str = "resources/fat16.img";
 
@@ -15,6 +17,34 @@ fn make_disk() -> std::io::Result<RamDisk> {
     Ok(RamDisk::from(&data))
 }
 
+fn test_image_file() -> axio::Result<()> {
+    println!("test mounting a FAT image file:");
+    let no_flags = MountFlags::empty();
+    let image =
+        std::fs::read(std::env::current_dir().unwrap().join("resources/fat12.img")).unwrap();
+    fs::write("/fat12.img", image)?;
+    fs::create_dir("/image")?;
+
+    fs::mount("/fat12.img", "/image", "vfat", no_flags)?;
+    assert_eq!(fs::read_to_string("/image/short.txt")?, "Rust is cool!\n");
+    fs::write("/image/new.txt", "in the image\n")?;
+    fs::umount("/image")?;
+    assert_eq!(fs::metadata("/image/new.txt").err(), Some(Error::NotFound));
+
+    // the files written are kept in the image
+    fs::mount("/fat12.img", "/image", "vfat", no_flags)?;
+    assert_eq!(fs::read_to_string("/image/new.txt")?, "in the image\n");
+    fs::umount("/image")?;
+
+    // only FAT images are opened from files, and only if the type is given
+    assert_eq!(
+        fs::mount("/fat12.img", "/image", "", no_flags).err(),
+        Some(Error::NotFound)
+    );
+    fs::remove_file("/fat12.img")?;
+    fs::remove_dir("/image")
+}
+
 #[test]
 fn test_fatfs() {
     println!("Testing fatfs with ramdisk ...");
@@ -24,4 +54,5 @@ fn test_fatfs() {
     axfs::init_filesystems(AxDeviceContainer::from_one(disk));
 
     test_common::test_all();
+    test_image_file().expect("test_image_file() failed");
 }
@@ -0,0 +1,136 @@
+#![cfg(not(feature = "myfs"))]
+
+use axdriver::AxDeviceContainer;
+use axdriver_block::ramdisk::RamDisk;
+use axfs::api::{self as fs, FatType, File, FsckIssue, MountFlags};
+use axio::{Read, Seek, SeekFrom, Write};
+use core::time::Duration;
+
+const BLOCK_SIZE: usize = 512;
+/// The alignment of the partitions in blocks.
+const PART_ALIGN: usize = 2048;
+/// The images in the partitions, with their MBR partition types and their
+/// FAT types. The root filesystem is on the first one, and the others are
+/// checked.
+const PARTS: [(&str, u8, FatType); 4] = [
+    ("resources/fat16.img", 0x06, FatType::Fat16),
+    ("resources/fat12.img", 0x01, FatType::Fat12),
+    ("resources/fat16.img", 0x06, FatType::Fat16),
+    ("resources/fat32.img", 0x0c, FatType::Fat32),
+];
+/// A cluster that is free in all the images, even after `change_files`.
+const FREE_CLUSTER: usize = 1000;
+
+/// Makes a disk with an MBR, and a copy of a FAT volume in each of its
+/// partitions.
+fn make_disk() -> std::io::Result<RamDisk> {
+    let mut data = vec![0; PART_ALIGN * BLOCK_SIZE];
+    for (i, (img_path, part_type, _)) in PARTS.into_iter().enumerate() {
+        let path = std::env::current_dir()?.join(img_path);
+        println!("Loading disk image from {:?} ...", path);
+        let volume = std::fs::read(path)?;
+        let start = data.len() / BLOCK_SIZE;
+        let entry = &mut data[446 + i * 16..446 + (i + 1) * 16];
+        entry[4] = part_type;
+        entry[8..12].copy_from_slice(&(start as u32).to_le_bytes());
+        entry[12..16].copy_from_slice(&((volume.len() / BLOCK_SIZE) as u32).to_le_bytes());
+        data.extend_from_slice(&volume);
+        data.resize(data.len().next_multiple_of(PART_ALIGN * BLOCK_SIZE), 0);
+    }
+    data[510..512].copy_from_slice(&[0x55, 0xaa]);
+    println!("size = {} bytes", data.len());
+    Ok(RamDisk::from(&data))
+}
+
+fn change_files(dev: &str) -> axio::Result<()> {
+    fs::mount(dev, "/mnt", "vfat", MountFlags::empty())?;
+    let stat = fs::statfs("/mnt/short.txt")?;
+    assert_eq!(stat.fstype, "vfat");
+    assert_eq!(stat.label.as_deref(), Some("Test!"));
+
+    // grow and shrink files, and move directories
+    let mut file = File::create("/mnt/grow.bin")?;
+    file.write_all(&[1; 3000])?;
+    file.set_len(10_000)?;
+    drop(file);
+    File::options()
+        .write(true)
+        .open("/mnt/long.txt")?
+        .set_len(100)?;
+    // FAT keeps the modification time in 2 seconds, and the access date
+    let time = Duration::from_secs(1_700_000_000);
+    let date = Duration::from_secs(1_700_000_000 / 86400 * 86400);
+    fs::set_times("/mnt/very/long", Some(time), Some(time))?;
+    fs::rename("/mnt/very/long", "/mnt/very-long-dir-name/long")?;
+    let meta = fs::metadata("/mnt/very-long-dir-name/long")?;
+    assert_eq!((meta.accessed()?, meta.modified()?), (date, time));
+    fs::rename("/mnt/short.txt", "/mnt/very-long-dir-name/long/short.txt")?;
+    fs::remove_file("/mnt/very-long-dir-name/very-long-file-name.txt")?;
+    fs::create_dir("/mnt/very/new")?;
+
+    // busy while mounted
+    assert_eq!(fs::fsck(dev).err(), Some(axio::Error::ResourceBusy));
+    fs::umount("/mnt")
+}
+
+/// Allocates [`FREE_CLUSTER`] in the first FAT only of the volume on `dev`.
+fn corrupt_fat(dev: &str, fat_type: FatType) -> axio::Result<()> {
+    let mut dev = File::options().read(true).write(true).open(dev)?;
+    let mut boot = [0; BLOCK_SIZE];
+    dev.read_exact(&mut boot)?;
+    let sector_size = u16::from_le_bytes([boot[11], boot[12]]) as usize;
+    let fat_start = u16::from_le_bytes([boot[14], boot[15]]) as usize * sector_size;
+    // end of chain, without the next entry of FAT12
+    let (offset, entry): (_, &[u8]) = match fat_type {
+        FatType::Fat12 => (FREE_CLUSTER * 3 / 2, &[0xff, 0x0f]),
+        FatType::Fat16 => (FREE_CLUSTER * 2, &[0xff, 0xff]),
+        FatType::Fat32 => (FREE_CLUSTER * 4, &[0xff, 0xff, 0xff, 0x0f]),
+    };
+    dev.seek(SeekFrom::Start((fat_start + offset) as u64))?;
+    dev.write_all(entry)?;
+    dev.flush()
+}
+
+#[test]
+fn test_fsck() {
+    println!("Testing fsck of FAT with ramdisk ...");
+
+    let disk = make_disk().expect("failed to load disk image");
+    axtask::init_scheduler(); // call this to use `axsync::Mutex`.
+    axfs::init_filesystems(AxDeviceContainer::from_one(disk));
+    fs::create_dir("/mnt").unwrap();
+
+    // the root filesystem is mounted
+    assert_eq!(fs::fsck("/dev/vda1").err(), Some(axio::Error::ResourceBusy));
+    assert_eq!(fs::fsck("/dev/vdb").err(), Some(axio::Error::NotFound));
+
+    for (i, (_, _, fat_type)) in PARTS.into_iter().enumerate().skip(1) {
+        let dev = format!("/dev/vda{}", i + 1);
+        println!("Checking {dev} ...");
+
+        // the volume made by `resources/create_test_img.sh`
+        let report = fs::fsck(&dev).unwrap();
+        println!("{:?}", report);
+        assert!(report.is_clean());
+        assert_eq!(report.fat_type, fat_type);
+        assert_eq!((report.files, report.dirs), (4, 4));
+        let free = report.free_clusters;
+
+        change_files(&dev).expect("failed to change files");
+        let report = fs::fsck(&dev).unwrap();
+        println!("{:?}", report);
+        assert!(report.is_clean());
+        assert_eq!((report.files, report.dirs), (4, 5));
+        // 20 clusters of `grow.bin`, and the new directory, but `long.txt`
+        // shrinks from 28 clusters to 1, and the removed file takes 1
+        assert_eq!(report.free_clusters, free - 21 + 27 + 1);
+
+        corrupt_fat(&dev, fat_type).unwrap();
+        let report = fs::fsck(&dev).unwrap();
+        println!("{:?}", report);
+        assert_eq!(report.issues, [
+            FsckIssue::FatMismatch,
+            FsckIssue::LostClusters { count: 1 }
+        ]);
+    }
+}
@@ -0,0 +1,115 @@
+#![cfg(not(feature = "myfs"))]
+
+mod test_common;
+
+use axdriver::AxDeviceContainer;
+use axdriver_block::ramdisk::RamDisk;
+use axfs::api as fs;
+use axio::{SeekFrom, prelude::*};
+
+const IMG_PATH: &str = "resources/fat16.img";
+const BLOCK_SIZE: usize = 512;
+/// The first block of the partition.
+const PART_START: usize = 2048;
+/// The first block and the number of blocks of the extended partition.
+const EXT_START: usize = 8192;
+const EXT_BLOCKS: usize = 2048;
+/// The EBRs and the logical partitions in them, relative to their EBRs.
+const LOGICAL: [(usize, usize, usize); 2] = [(0, 63, 100), (1024, 63, 200)];
+
+/// Writes the `idx`-th partition entry of the MBR or EBR in `block`.
+fn write_entry(block: &mut [u8], idx: usize, ty: u8, start: usize, num_blocks: usize) {
+    let entry = &mut block[446 + idx * 16..446 + (idx + 1) * 16];
+    entry[4] = ty;
+    entry[8..12].copy_from_slice(&(start as u32).to_le_bytes());
+    entry[12..16].copy_from_slice(&(num_blocks as u32).to_le_bytes());
+    block[510..512].copy_from_slice(&[0x55, 0xaa]);
+}
+
+/// Makes a disk with an MBR, the FAT volume as its second partition, and an
+/// extended partition as its third one, with two logical partitions.
+fn make_disk() -> std::io::Result<RamDisk> {
+    let path = std::env::current_dir()?.join(IMG_PATH);
+    println!("Loading disk image from {:?} ...", path);
+    let volume = std::fs::read(path)?;
+    let mut data = vec![0; (EXT_START + EXT_BLOCKS) * BLOCK_SIZE];
+    write_entry(&mut data, 1, 0x06, PART_START, volume.len() / BLOCK_SIZE); // FAT16
+    write_entry(&mut data, 2, 0x05, EXT_START, EXT_BLOCKS);
+    for (i, (ebr, start, num_blocks)) in LOGICAL.into_iter().enumerate() {
+        let block = &mut data[(EXT_START + ebr) * BLOCK_SIZE..][..BLOCK_SIZE];
+        write_entry(block, 0, 0x83, start, num_blocks);
+        // the next EBR is relative to the extended partition
+        if let Some((next, ..)) = LOGICAL.get(i + 1) {
+            write_entry(block, 1, 0x05, *next, EXT_BLOCKS - next);
+        }
+    }
+    data[PART_START * BLOCK_SIZE..][..volume.len()].copy_from_slice(&volume);
+    println!("size = {} bytes", data.len());
+    Ok(RamDisk::from(&data))
+}
+
+#[test]
+fn test_partition() {
+    println!("Testing fatfs on a partition with ramdisk ...");
+
+    let disk = make_disk().expect("failed to load disk image");
+    axtask::init_scheduler(); // call this to use `axsync::Mutex`.
+    axfs::init_filesystems(AxDeviceContainer::from_one(disk));
+
+    // the root filesystem is on the partition
+    assert!(fs::metadata("/dev/vda").is_ok());
+    assert!(fs::metadata("/dev/vda2").is_ok());
+    assert_eq!(fs::metadata("/dev/vda1").err(), Some(axio::Error::NotFound));
+    // the extended partition is not a device, but its logical partitions are
+    assert_eq!(fs::metadata("/dev/vda3").err(), Some(axio::Error::NotFound));
+    for (i, (_, _, num_blocks)) in LOGICAL.into_iter().enumerate() {
+        let meta = fs::metadata(&format!("/dev/vda{}", i + 5)).unwrap();
+        assert_eq!(meta.len(), (num_blocks * BLOCK_SIZE) as u64);
+    }
+    assert_eq!(fs::metadata("/dev/vda7").err(), Some(axio::Error::NotFound));
+
+    // raw reads and writes, where the partitions are windows of the disk
+    let mut disk = fs::File::open("/dev/vda").unwrap();
+    let mut block = [0; BLOCK_SIZE];
+    disk.read_exact(&mut block).unwrap();
+    assert_eq!(block[510..], [0x55, 0xaa]);
+    let mut boot = [0; BLOCK_SIZE];
+    fs::File::open("/dev/vda2")
+        .unwrap()
+        .read_exact(&mut boot)
+        .unwrap();
+    disk.seek(SeekFrom::Start((PART_START * BLOCK_SIZE) as u64))
+        .unwrap();
+    disk.read_exact(&mut block).unwrap();
+    assert_eq!(block, boot);
+    let mut part = fs::File::options().write(true).open("/dev/vda6").unwrap();
+    part.seek(SeekFrom::Start(100)).unwrap();
+    part.write_all(b"raw write").unwrap();
+    // writes stop at the end of the partition
+    let end = 200 * BLOCK_SIZE as u64;
+    part.seek(SeekFrom::Start(end - 2)).unwrap();
+    assert_eq!(part.write(b"tail").unwrap(), 2);
+    assert_eq!(part.write(b"tail").unwrap(), 0);
+    part.flush().unwrap();
+    let (ebr, start, _) = LOGICAL[1];
+    let part_start = ((EXT_START + ebr + start) * BLOCK_SIZE) as u64;
+    let mut buf = [0; 9];
+    disk.seek(SeekFrom::Start(part_start + 100)).unwrap();
+    disk.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"raw write");
+    disk.seek(SeekFrom::Start(part_start + end - 2)).unwrap();
+    disk.read_exact(&mut buf[..4]).unwrap();
+    assert_eq!(&buf[..2], b"ta");
+    let mut part = fs::File::open("/dev/vda6").unwrap();
+    part.seek(SeekFrom::Start(end)).unwrap();
+    assert_eq!(part.read(&mut buf).unwrap(), 0);
+
+    fs::create_dir("/data").unwrap();
+    assert_eq!(
+        fs::mount("/dev/vda2", "/data", "", fs::MountFlags::empty()).err(),
+        Some(axio::Error::ResourceBusy)
+    );
+    fs::remove_dir("/data").unwrap();
+
+    test_common::test_all();
+}
@@ -0,0 +1,82 @@
+#![cfg(not(feature = "myfs"))]
+
+mod test_common;
+
+use axdriver::AxDeviceContainer;
+use axdriver_block::ramdisk::RamDisk;
+use axfs::api as fs;
+
+const IMG_PATH: &str = "resources/fat16.img";
+const BLOCK_SIZE: usize = 512;
+const NUM_BLOCKS: usize = 8192;
+/// The block of the partition entries.
+const ENTRIES_BLOCK: usize = 2;
+const ENTRY_SIZE: usize = 128;
+/// The first block of the FAT partition, the third entry.
+const PART_START: usize = 2048;
+/// The first and last blocks of the first entry.
+const FIRST_PART: (usize, usize) = (64, 1063);
+
+/// Writes the `idx`-th GPT entry, with a non-zero type GUID.
+fn write_entry(data: &mut [u8], idx: usize, first: usize, last: usize) {
+    let entry = &mut data[ENTRIES_BLOCK * BLOCK_SIZE + idx * ENTRY_SIZE..][..ENTRY_SIZE];
+    entry[0..16].fill(0xaf);
+    entry[32..40].copy_from_slice(&(first as u64).to_le_bytes());
+    entry[40..48].copy_from_slice(&(last as u64).to_le_bytes());
+}
+
+/// Makes a disk with a GPT, and the FAT volume as its third partition.
+fn make_disk() -> std::io::Result<RamDisk> {
+    let path = std::env::current_dir()?.join(IMG_PATH);
+    println!("Loading disk image from {:?} ...", path);
+    let volume = std::fs::read(path)?;
+    let mut data = vec![0; NUM_BLOCKS * BLOCK_SIZE];
+
+    // the protective MBR
+    let entry = &mut data[446..446 + 16];
+    entry[4] = 0xee;
+    entry[8..12].copy_from_slice(&1u32.to_le_bytes());
+    entry[12..16].copy_from_slice(&(NUM_BLOCKS as u32 - 1).to_le_bytes());
+    data[510..512].copy_from_slice(&[0x55, 0xaa]);
+
+    let header = &mut data[BLOCK_SIZE..2 * BLOCK_SIZE];
+    header[0..8].copy_from_slice(b"EFI PART");
+    header[72..80].copy_from_slice(&(ENTRIES_BLOCK as u64).to_le_bytes());
+    header[80..84].copy_from_slice(&128u32.to_le_bytes());
+    header[84..88].copy_from_slice(&(ENTRY_SIZE as u32).to_le_bytes());
+
+    // the second entry is unused, and the fourth one is past the end
+    write_entry(&mut data, 0, FIRST_PART.0, FIRST_PART.1);
+    let last = PART_START + volume.len() / BLOCK_SIZE - 1;
+    write_entry(&mut data, 2, PART_START, last);
+    write_entry(&mut data, 3, last + 1, NUM_BLOCKS);
+
+    data[PART_START * BLOCK_SIZE..][..volume.len()].copy_from_slice(&volume);
+    println!("size = {} bytes", data.len());
+    Ok(RamDisk::from(&data))
+}
+
+#[test]
+fn test_partition_gpt() {
+    println!("Testing fatfs on a GPT partition with ramdisk ...");
+
+    let disk = make_disk().expect("failed to load disk image");
+    axtask::init_scheduler(); // call this to use `axsync::Mutex`.
+    axfs::init_filesystems(AxDeviceContainer::from_one(disk));
+
+    // the root filesystem is on the third partition
+    let meta = fs::metadata("/dev/vda1").unwrap();
+    let first_blocks = FIRST_PART.1 - FIRST_PART.0 + 1;
+    assert_eq!(meta.len(), (first_blocks * BLOCK_SIZE) as u64);
+    assert_eq!(fs::metadata("/dev/vda2").err(), Some(axio::Error::NotFound));
+    assert!(fs::metadata("/dev/vda3").is_ok());
+    assert_eq!(fs::metadata("/dev/vda4").err(), Some(axio::Error::NotFound));
+    fs::create_dir("/data").unwrap();
+    assert_eq!(
+        fs::mount("/dev/vda3", "/data", "", fs::MountFlags::empty()).err(),
+        Some(axio::Error::ResourceBusy)
+    );
+    fs::remove_dir("/data").unwrap();
+
+    test_common::test_all();
+}
@@ -0,0 +1,987 @@
+//! Platform discovery from the flattened device tree (FDT).
+//!
+//! On platforms whose bootloader passes a device tree blob (DTB), the blob is
+//! parsed once at boot by [`init`], before the global allocator is available.
+//! Only the information that the kernel needs to bring itself up is extracted
+//! and stored in fixed-size tables:
+//!
+//! - physical memory ranges (`device_type = "memory"` nodes),
+//! - VirtIO MMIO slots (`compatible = "virtio,mmio"`) and their IRQs,
+//! - the number of CPUs (`device_type = "cpu"` nodes),
+//! - the base addresses of the PLIC or GIC interrupt controller,
+//! - the `interrupt-map` of the PCI host bridge, which routes the INTx
+//!   interrupts of PCI devices to the interrupt controller,
+//! - the random seed passed by the bootloader (`rng-seed` in `/chosen`).
+//!
+//! Interrupt specifiers are decoded into IRQ numbers for the PLIC (one cell,
+//! the interrupt source) and the GIC (three cells, SPIs and PPIs). Interrupts
+//! routed to other controllers are not dispatched by the kernel, and are
+//! reported as unknown.
+//!
+//! All the query functions fall back to the static platform configuration in
+//! [`axconfig`] if no DTB was found.
+
+use lazyinit::LazyInit;
+use memory_addr::PhysAddr;
+
+use crate::mem::phys_to_virt;
+
+const FDT_MAGIC: u32 = 0xd00d_feed;
+
+const FDT_BEGIN_NODE: u32 = 0x1;
+const FDT_END_NODE: u32 = 0x2;
+const FDT_PROP: u32 = 0x3;
+const FDT_NOP: u32 = 0x4;
+const FDT_END: u32 = 0x9;
+
+/// The maximum node depth that the parser keeps track of.
+const MAX_DEPTH: usize = 16;
+/// The maximum number of physical memory ranges recorded.
+const MAX_MEM_RANGES: usize = 8;
+/// The maximum number of VirtIO MMIO slots recorded.
+const MAX_VIRTIO_MMIO: usize = 32;
+/// The maximum number of interrupt controllers recorded.
+const MAX_INTCS: usize = 4;
+/// The maximum number of cells in an interrupt specifier.
+const MAX_IRQ_CELLS: usize = 3;
+/// The maximum number of cells of the PCI `interrupt-map` recorded.
+const MAX_PCI_IRQ_MAP_CELLS: usize = 256;
+/// The number of cells of a PCI unit address and a PCI interrupt specifier,
+/// i.e., the key of an `interrupt-map` entry.
+const PCI_IRQ_KEY_CELLS: usize = 4;
+/// The maximum number of bytes of the random seed recorded.
+const MAX_RNG_SEED: usize = 64;
+
+/// The kind of an interrupt controller, which tells how to decode its
+/// interrupt specifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IntcKind {
+    /// The RISC-V platform-level interrupt controller.
+    Plic,
+    /// The ARM generic interrupt controller.
+    Gic,
+}
+
+/// An interrupt controller that the kernel dispatches interrupts from.
+#[derive(Debug, Clone, Copy)]
+struct IntcInfo {
+    kind: IntcKind,
+    phandle: Option<u32>,
+    /// `#address-cells`, the size of the parent unit address in the
+    /// `interrupt-map` entries that refer to this controller.
+    address_cells: u32,
+    /// `#interrupt-cells`, the size of its interrupt specifiers.
+    interrupt_cells: u32,
+}
+
+impl IntcInfo {
+    /// Decodes an interrupt specifier of this controller into an IRQ number.
+    fn decode(&self, spec: &[u32]) -> Option<usize> {
+        match self.kind {
+            // source 0 means no interrupt
+            IntcKind::Plic => spec.first().map(|&src| src as usize).filter(|&n| n != 0),
+            // `<type number flags>`, where the type is 0 for SPIs and 1 for PPIs
+            IntcKind::Gic => match spec {
+                [0, num, ..] => Some(*num as usize + 32),
+                [1, num, ..] => Some(*num as usize + 16),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// The first interrupt specifier of an `interrupts` property, decoded once
+/// all the interrupt controllers are known.
+#[derive(Debug, Clone, Copy)]
+struct IrqSpec {
+    /// The phandle of the interrupt parent.
+    parent: Option<u32>,
+    cells: [u32; MAX_IRQ_CELLS],
+    len: usize,
+}
+
+impl IrqSpec {
+    fn new(parent: Option<u32>, mut interrupts: &[u8]) -> Option<Self> {
+        let mut cells = [0; MAX_IRQ_CELLS];
+        let mut len = 0;
+        while len < MAX_IRQ_CELLS {
+            match read_cells(&mut interrupts, 1) {
+                Some(cell) => cells[len] = cell as u32,
+                None => break,
+            }
+            len += 1;
+        }
+        (len > 0).then_some(Self { parent, cells, len })
+    }
+}
+
+/// Information discovered from the device tree.
+struct DtbInfo {
+    mem_ranges: [(usize, usize); MAX_MEM_RANGES],
+    mem_range_count: usize,
+    virtio_mmio: [(usize, usize); MAX_VIRTIO_MMIO],
+    virtio_mmio_irq_specs: [Option<IrqSpec>; MAX_VIRTIO_MMIO],
+    virtio_mmio_irqs: [Option<usize>; MAX_VIRTIO_MMIO],
+    virtio_mmio_count: usize,
+    cpu_count: usize,
+    plic_paddr: Option<usize>,
+    gic_paddrs: Option<(usize, usize)>,
+    intcs: [Option<IntcInfo>; MAX_INTCS],
+    /// The `interrupt-map-mask` of the PCI host bridge.
+    pci_irq_map_mask: [u32; PCI_IRQ_KEY_CELLS],
+    /// The `interrupt-map` of the PCI host bridge.
+    pci_irq_map: [u32; MAX_PCI_IRQ_MAP_CELLS],
+    pci_irq_map_len: usize,
+    rng_seed: [u8; MAX_RNG_SEED],
+    rng_seed_len: usize,
+}
+
+static DTB_INFO: LazyInit<DtbInfo> = LazyInit::new();
+
+/// Big-endian reader over the raw DTB bytes.
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn be32(&self, off: usize) -> Option<u32> {
+        let bytes = self.data.get(off..off + 4)?;
+        Some(u32::from_be_bytes(bytes.try_into().ok()?))
+    }
+
+    fn cstr(&self, off: usize) -> Option<&'a [u8]> {
+        let rest = self.data.get(off..)?;
+        let len = rest.iter().position(|&b| b == 0)?;
+        Some(&rest[..len])
+    }
+}
+
+/// Properties of interest collected while walking a node.
+#[derive(Clone, Copy)]
+struct NodeState<'a> {
+    address_cells: u32,
+    size_cells: u32,
+    reg: Option<&'a [u8]>,
+    /// Whether `#address-cells` is present, instead of the default value.
+    has_address_cells: bool,
+    interrupt_cells: Option<u32>,
+    /// The phandle of the interrupt parent, inherited from the ancestors.
+    interrupt_parent: Option<u32>,
+    phandle: Option<u32>,
+    interrupts: Option<&'a [u8]>,
+    interrupt_map: Option<&'a [u8]>,
+    interrupt_map_mask: Option<&'a [u8]>,
+    compatible: Option<&'a [u8]>,
+    device_type: Option<&'a [u8]>,
+    rng_seed: Option<&'a [u8]>,
+    disabled: bool,
+}
+
+impl NodeState<'_> {
+    const fn new() -> Self {
+        Self {
+            // default values defined by the devicetree specification
+            address_cells: 2,
+            size_cells: 1,
+            reg: None,
+            has_address_cells: false,
+            interrupt_cells: None,
+            interrupt_parent: None,
+            phandle: None,
+            interrupts: None,
+            interrupt_map: None,
+            interrupt_map_mask: None,
+            compatible: None,
+            device_type: None,
+            rng_seed: None,
+            disabled: false,
+        }
+    }
+
+    /// Whether one of the strings in the `compatible` list equals `name`.
+    fn is_compatible(&self, name: &[u8]) -> bool {
+        self.compatible
+            .is_some_and(|list| list.split(|&b| b == 0).any(|s| s == name))
+    }
+
+    /// Whether the `device_type` property equals `name`.
+    fn is_device_type(&self, name: &[u8]) -> bool {
+        self.device_type
+            .is_some_and(|ty| ty.split(|&b| b == 0).next() == Some(name))
+    }
+}
+
+/// Reads a number of `cells` 32-bit cells from the front of `data`.
+fn read_cells(data: &mut &[u8], cells: u32) -> Option<usize> {
+    let len = cells as usize * 4;
+    if cells > 2 || data.len() < len {
+        return None;
+    }
+    let mut value = 0u64;
+    for chunk in data[..len].chunks_exact(4) {
+        value = (value << 32) | u32::from_be_bytes(chunk.try_into().unwrap()) as u64;
+    }
+    *data = &data[len..];
+    Some(value as usize)
+}
+
+/// Iterates over the `(address, size)` pairs of a `reg` property.
+fn reg_entries<'a>(
+    mut reg: &'a [u8],
+    address_cells: u32,
+    size_cells: u32,
+) -> impl Iterator<Item = (usize, usize)> + 'a {
+    core::iter::from_fn(move || {
+        if reg.is_empty() {
+            return None;
+        }
+        let addr = read_cells(&mut reg, address_cells)?;
+        let size = read_cells(&mut reg, size_cells)?;
+        Some((addr, size))
+    })
+}
+
+impl DtbInfo {
+    const fn new() -> Self {
+        Self {
+            mem_ranges: [(0, 0); MAX_MEM_RANGES],
+            mem_range_count: 0,
+            virtio_mmio: [(0, 0); MAX_VIRTIO_MMIO],
+            virtio_mmio_irq_specs: [None; MAX_VIRTIO_MMIO],
+            virtio_mmio_irqs: [None; MAX_VIRTIO_MMIO],
+            virtio_mmio_count: 0,
+            cpu_count: 0,
+            plic_paddr: None,
+            gic_paddrs: None,
+            intcs: [None; MAX_INTCS],
+            pci_irq_map_mask: [0; PCI_IRQ_KEY_CELLS],
+            pci_irq_map: [0; MAX_PCI_IRQ_MAP_CELLS],
+            pci_irq_map_len: 0,
+            rng_seed: [0; MAX_RNG_SEED],
+            rng_seed_len: 0,
+        }
+    }
+
+    /// Records an interrupt controller that the kernel dispatches interrupts
+    /// from.
+    fn add_intc(&mut self, kind: IntcKind, node: &NodeState, default_interrupt_cells: u32) {
+        if let Some(slot) = self.intcs.iter_mut().find(|intc| intc.is_none()) {
+            *slot = Some(IntcInfo {
+                kind,
+                phandle: node.phandle,
+                address_cells: if node.has_address_cells {
+                    node.address_cells
+                } else {
+                    0
+                },
+                interrupt_cells: node.interrupt_cells.unwrap_or(default_interrupt_cells),
+            });
+        }
+    }
+
+    /// Finds the interrupt controller with the given phandle.
+    ///
+    /// Without a phandle, the only recorded controller is used.
+    fn intc(&self, phandle: Option<u32>) -> Option<&IntcInfo> {
+        let mut intcs = self.intcs.iter().flatten();
+        match phandle {
+            Some(phandle) => intcs.find(|intc| intc.phandle == Some(phandle)),
+            None => {
+                let first = intcs.next();
+                if intcs.next().is_some() { None } else { first }
+            }
+        }
+    }
+
+    /// Decodes an interrupt specifier, if its controller is recorded.
+    fn decode_irq(&self, spec: &IrqSpec) -> Option<usize> {
+        let intc = self.intc(spec.parent)?;
+        let len = (intc.interrupt_cells as usize).min(spec.len);
+        intc.decode(&spec.cells[..len])
+    }
+
+    /// Records the `interrupt-map` of a PCI host bridge.
+    ///
+    /// Only the standard layout is supported, where the key of each entry is
+    /// a 3-cell PCI unit address followed by the 1-cell interrupt pin.
+    fn set_pci_irq_map(&mut self, node: &NodeState) {
+        let Some(map) = node.interrupt_map else {
+            return;
+        };
+        if node.address_cells != 3 || node.interrupt_cells != Some(1) {
+            return;
+        }
+        let len = map.len() / 4;
+        if len > MAX_PCI_IRQ_MAP_CELLS {
+            warn!("PCI interrupt-map is too large, INTx interrupts are ignored");
+            return;
+        }
+        let mut map = map;
+        for cell in &mut self.pci_irq_map[..len] {
+            *cell = read_cells(&mut map, 1).unwrap() as u32;
+        }
+        self.pci_irq_map_len = len;
+        self.pci_irq_map_mask = [u32::MAX; PCI_IRQ_KEY_CELLS];
+        if let Some(mut mask) = node.interrupt_map_mask {
+            for cell in &mut self.pci_irq_map_mask {
+                match read_cells(&mut mask, 1) {
+                    Some(value) => *cell = value as u32,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    /// Looks up the PCI `interrupt-map` for the INTx interrupt of a device,
+    /// and decodes it into an IRQ number.
+    fn pci_intx_irq(&self, bus: u8, device: u8, function: u8, pin: u8) -> Option<usize> {
+        let phys_hi = (bus as u32) << 16 | (device as u32) << 11 | (function as u32) << 8;
+        let key = [phys_hi, 0, 0, pin as u32];
+        let mut map = &self.pci_irq_map[..self.pci_irq_map_len];
+        // each entry is `<child-address child-irq parent parent-address parent-irq>`
+        while map.len() > PCI_IRQ_KEY_CELLS {
+            let intc = self.intc(Some(map[PCI_IRQ_KEY_CELLS]))?;
+            let spec_start = PCI_IRQ_KEY_CELLS + 1 + intc.address_cells as usize;
+            let entry_len = spec_start + intc.interrupt_cells as usize;
+            let entry = map.get(..entry_len)?;
+            let matched =
+                (0..PCI_IRQ_KEY_CELLS).all(|i| entry[i] == key[i] & self.pci_irq_map_mask[i]);
+            if matched {
+                return intc.decode(&entry[spec_start..]);
+            }
+            map = &map[entry_len..];
+        }
+        None
+    }
+
+    /// Records the information of a node when its end is reached.
+    ///
+    /// `parent` holds the `#address-cells` and `#size-cells` that are used to
+    /// decode the `reg` property of `node`.
+    fn finish_node(&mut self, node: &NodeState, parent: &NodeState) {
+        if node.disabled {
+            return;
+        }
+        if let Some(seed) = node.rng_seed {
+            let len = seed.len().min(MAX_RNG_SEED);
+            self.rng_seed[..len].copy_from_slice(&seed[..len]);
+            self.rng_seed_len = len;
+        }
+        let mut regs = node
+            .reg
+            .into_iter()
+            .flat_map(|reg| reg_entries(reg, parent.address_cells, parent.size_cells));
+        if node.is_device_type(b"memory") {
+            for (base, size) in regs {
+                if size != 0 && self.mem_range_count < MAX_MEM_RANGES {
+                    self.mem_ranges[self.mem_range_count] = (base, size);
+                    self.mem_range_count += 1;
+                }
+            }
+        } else if node.is_device_type(b"cpu") {
+            self.cpu_count += 1;
+        } else if node.is_compatible(b"virtio,mmio") {
+            if let Some(reg) = regs.next() {
+                if self.virtio_mmio_count < MAX_VIRTIO_MMIO {
+                    self.virtio_mmio[self.virtio_mmio_count] = reg;
+                    self.virtio_mmio_irq_specs[self.virtio_mmio_count] = node
+                        .interrupts
+                        .and_then(|irqs| IrqSpec::new(node.interrupt_parent, irqs));
+                    self.virtio_mmio_count += 1;
+                }
+            }
+        } else if node.is_compatible(b"riscv,plic0") || node.is_compatible(b"sifive,plic-1.0.0") {
+            self.plic_paddr = regs.next().map(|(base, _)| base);
+            self.add_intc(IntcKind::Plic, node, 1);
+        } else if node.is_compatible(b"arm,cortex-a15-gic") || node.is_compatible(b"arm,gic-400") {
+            if let (Some((gicd, _)), Some((gicc, _))) = (regs.next(), regs.next()) {
+                self.gic_paddrs = Some((gicd, gicc));
+            }
+            self.add_intc(IntcKind::Gic, node, 3);
+        } else if node.is_device_type(b"pci") {
+            self.set_pci_irq_map(node);
+        }
+    }
+
+    /// Decodes the recorded interrupt specifiers, once all the interrupt
+    /// controllers that they may refer to are known.
+    fn resolve_irqs(&mut self) {
+        for i in 0..self.virtio_mmio_count {
+            self.virtio_mmio_irqs[i] = self.virtio_mmio_irq_specs[i]
+                .as_ref()
+                .and_then(|spec| self.decode_irq(spec));
+        }
+    }
+
+    /// Walks the structure block of the DTB and collects the information.
+    fn parse(data: &[u8]) -> Option<Self> {
+        let reader = Reader { data };
+        if reader.be32(0)? != FDT_MAGIC {
+            return None;
+        }
+        let off_struct = reader.be32(8)? as usize;
+        let off_strings = reader.be32(12)? as usize;
+
+        let mut info = Self::new();
+        let mut stack = [NodeState::new(); MAX_DEPTH + 1];
+        let mut depth = 0;
+        let mut pos = off_struct;
+        loop {
+            let token = reader.be32(pos)?;
+            pos += 4;
+            match token {
+                FDT_BEGIN_NODE => {
+                    let name = reader.cstr(pos)?;
+                    pos = (pos + name.len() + 1).next_multiple_of(4);
+                    depth += 1;
+                    if depth > MAX_DEPTH {
+                        return None;
+                    }
+                    stack[depth] = NodeState::new();
+                    stack[depth].interrupt_parent = stack[depth - 1].interrupt_parent;
+                }
+                FDT_END_NODE => {
+                    if depth == 0 {
+                        return None;
+                    }
+                    if depth >= 2 {
+                        let (parents, nodes) = stack.split_at(depth);
+                        info.finish_node(&nodes[0], &parents[depth - 1]);
+                    }
+                    depth -= 1;
+                }
+                FDT_PROP => {
+                    let len = reader.be32(pos)? as usize;
+                    let name_off = reader.be32(pos + 4)? as usize;
+                    let value = data.get(pos + 8..pos + 8 + len)?;
+                    pos = (pos + 8 + len).next_multiple_of(4);
+                    let node = &mut stack[depth];
+                    match reader.cstr(off_strings + name_off)? {
+                        b"#address-cells" => {
+                            node.address_cells = read_cells(&mut &value[..], 1)? as u32;
+                            node.has_address_cells = true;
+                        }
+                        b"#size-cells" => node.size_cells = read_cells(&mut &value[..], 1)? as u32,
+                        b"#interrupt-cells" => {
+                            node.interrupt_cells = Some(read_cells(&mut &value[..], 1)? as u32)
+                        }
+                        b"interrupt-parent" => {
+                            node.interrupt_parent = Some(read_cells(&mut &value[..], 1)? as u32)
+                        }
+                        b"phandle" | b"linux,phandle" => {
+                            node.phandle = Some(read_cells(&mut &value[..], 1)? as u32)
+                        }
+                        b"reg" => node.reg = Some(value),
+                        b"interrupts" => node.interrupts = Some(value),
+                        b"interrupt-map" => node.interrupt_map = Some(value),
+                        b"interrupt-map-mask" => node.interrupt_map_mask = Some(value),
+                        b"compatible" => node.compatible = Some(value),
+                        b"device_type" => node.device_type = Some(value),
+                        b"rng-seed" => node.rng_seed = Some(value),
+                        b"status" => {
+                            node.disabled = !matches!(value, b"okay\0" | b"ok\0");
+                        }
+                        _ => {}
+                    }
+                }
+                FDT_NOP => {}
+                FDT_END => break,
+                _ => return None,
+            }
+        }
+        info.resolve_irqs();
+        Some(info)
+    }
+}
+
+/// Parses the device tree blob at the given physical address.
+///
+/// It must be called on the primary CPU before any of the query functions in
+/// this module, while the boot page table still maps the DTB. It does nothing
+/// if `dtb_paddr` is zero or does not point to a valid DTB.
+pub(crate) fn init(dtb_paddr: usize) {
+    if dtb_paddr == 0 {
+        return;
+    }
+    let ptr = phys_to_virt(dtb_paddr.into()).as_ptr();
+    // Safety: the header is read first to get the total size of the blob.
+    let header = unsafe { core::slice::from_raw_parts(ptr, 8) };
+    if u32::from_be_bytes(header[..4].try_into().unwrap()) != FDT_MAGIC {
+        return;
+    }
+    let total_size = u32::from_be_bytes(header[4..8].try_into().unwrap()) as usize;
+    let data = unsafe { core::slice::from_raw_parts(ptr, total_size) };
+    if let Some(info) = DtbInfo::parse(data) {
+        DTB_INFO.init_once(info);
+    }
+}
+
+/// Returns whether a valid device tree was found at boot.
+pub fn is_present() -> bool {
+    DTB_INFO.is_inited()
+}
+
+/// Returns the physical memory ranges `(base, size)` found in the device tree.
+///
+/// Returns `None` if no DTB is present or it contains no memory node, in which
+/// case [`axconfig::plat::PHYS_MEMORY_BASE`] and
+/// [`axconfig::plat::PHYS_MEMORY_SIZE`] should be used.
+pub fn phys_memory_ranges() -> Option<&'static [(usize, usize)]> {
+    let info = DTB_INFO.get()?;
+    let ranges = &info.mem_ranges[..info.mem_range_count];
+    (!ranges.is_empty()).then_some(ranges)
+}
+
+/// Returns the VirtIO MMIO slots `(base, size)` of the platform.
+///
+/// Falls back to [`axconfig::devices::VIRTIO_MMIO_REGIONS`] if no DTB is
+/// present.
+pub fn virtio_mmio_regions() -> &'static [(usize, usize)] {
+    match DTB_INFO.get() {
+        Some(info) => &info.virtio_mmio[..info.virtio_mmio_count],
+        None => axconfig::devices::VIRTIO_MMIO_REGIONS,
+    }
+}
+
+/// Returns the IRQ number of the VirtIO MMIO slot at the given base address.
+///
+/// Returns `None` if no DTB is present, the slot is not described in it, or
+/// its interrupt cannot be dispatched by the platform.
+pub fn virtio_mmio_irq(base: usize) -> Option<usize> {
+    let info = DTB_INFO.get()?;
+    let idx = info.virtio_mmio[..info.virtio_mmio_count]
+        .iter()
+        .position(|&(b, _)| b == base)?;
+    info.virtio_mmio_irqs[idx]
+}
+
+/// Returns the IRQ number of the INTx interrupt of a PCI device, as routed by
+/// the `interrupt-map` of the PCI host bridge.
+///
+/// `pin` is the value of the Interrupt Pin register of the device, from 1
+/// for INTA# to 4 for INTD#. Returns `None` if no DTB is present, the
+/// interrupt is not routed, or it cannot be dispatched by the platform.
+pub fn pci_intx_irq(bus: u8, device: u8, function: u8, pin: u8) -> Option<usize> {
+    if pin == 0 {
+        return None;
+    }
+    DTB_INFO.get()?.pci_intx_irq(bus, device, function, pin)
+}
+
+/// Returns the number of CPUs that the kernel can use.
+///
+/// It is the number of CPU nodes in the device tree, capped by
+/// [`axconfig::SMP`] since per-CPU resources are allocated statically. Falls
+/// back to [`axconfig::SMP`] if no DTB is present.
+pub fn cpu_count() -> usize {
+    match DTB_INFO.get() {
+        Some(info) if info.cpu_count > 0 => info.cpu_count.min(axconfig::SMP),
+        _ => axconfig::SMP,
+    }
+}
+
+/// Returns the base physical address of the RISC-V PLIC, if one is described
+/// in the device tree.
+pub fn plic_paddr() -> Option<PhysAddr> {
+    DTB_INFO.get()?.plic_paddr.map(PhysAddr::from)
+}
+
+/// Returns the base physical addresses of the GIC distributor and the GIC CPU
+/// interface, if a GICv2 is described in the device tree.
+pub fn gic_paddrs() -> Option<(PhysAddr, PhysAddr)> {
+    DTB_INFO
+        .get()?
+        .gic_paddrs
+        .map(|(gicd, gicc)| (PhysAddr::from(gicd), PhysAddr::from(gicc)))
+}
+
+/// Returns the random seed that the bootloader passed in the `rng-seed`
+/// property of `/chosen`, if any.
+pub fn rng_seed() -> Option<&'static [u8]> {
+    let info = DTB_INFO.get()?;
+    let seed = &info.rng_seed[..info.rng_seed_len];
+    (!seed.is_empty()).then_some(seed)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{format, vec, vec::Vec};
+
+    use super::*;
+
+    /// Builds a flattened device tree with the structure and strings blocks.
+    #[derive(Default)]
+    struct FdtBuilder {
+        structure: Vec<u8>,
+        strings: Vec<u8>,
+    }
+
+    impl FdtBuilder {
+        fn token(&mut self, token: u32) -> &mut Self {
+            self.structure.extend_from_slice(&token.to_be_bytes());
+            self
+        }
+
+        fn begin(&mut self, name: &str) -> &mut Self {
+            self.token(FDT_BEGIN_NODE);
+            self.structure.extend_from_slice(name.as_bytes());
+            self.structure.push(0);
+            self.structure
+                .resize(self.structure.len().next_multiple_of(4), 0);
+            self
+        }
+
+        fn end(&mut self) -> &mut Self {
+            self.token(FDT_END_NODE)
+        }
+
+        fn prop(&mut self, name: &str, value: &[u8]) -> &mut Self {
+            let name_off = self.strings.len() as u32;
+            self.strings.extend_from_slice(name.as_bytes());
+            self.strings.push(0);
+            self.token(FDT_PROP);
+            self.structure
+                .extend_from_slice(&(value.len() as u32).to_be_bytes());
+            self.structure.extend_from_slice(&name_off.to_be_bytes());
+            self.structure.extend_from_slice(value);
+            self.structure
+                .resize(self.structure.len().next_multiple_of(4), 0);
+            self
+        }
+
+        fn prop_cells(&mut self, name: &str, cells: &[u32]) -> &mut Self {
+            let value: Vec<u8> = cells.iter().flat_map(|c| c.to_be_bytes()).collect();
+            self.prop(name, &value)
+        }
+
+        fn build(&mut self) -> Vec<u8> {
+            self.token(FDT_END);
+            let off_struct = 40;
+            let off_strings = off_struct + self.structure.len();
+            let total_size = off_strings + self.strings.len();
+            let header = [
+                FDT_MAGIC,
+                total_size as u32,
+                off_struct as u32,
+                off_strings as u32,
+                off_struct as u32, // empty memory reservation block
+                17,
+                16,
+                0,
+                self.strings.len() as u32,
+                self.structure.len() as u32,
+            ];
+            let mut blob: Vec<u8> = header.iter().flat_map(|w| w.to_be_bytes()).collect();
+            blob.extend_from_slice(&self.structure);
+            blob.extend_from_slice(&self.strings);
+            blob
+        }
+    }
+
+    fn qemu_virt_like() -> Vec<u8> {
+        let mut fdt = FdtBuilder::default();
+        fdt.begin("")
+            .prop_cells("#address-cells", &[2])
+            .prop_cells("#size-cells", &[2]);
+        fdt.begin("memory@40000000")
+            .prop("device_type", b"memory\0")
+            .prop_cells("reg", &[
+                0,
+                0x4000_0000,
+                0,
+                0x800_0000,
+                1,
+                0,
+                0,
+                0x1000_0000,
+            ])
+            .end();
+        fdt.begin("cpus")
+            .prop_cells("#address-cells", &[1])
+            .prop_cells("#size-cells", &[0]);
+        for i in 0..3 {
+            fdt.begin(&format!("cpu@{i}"))
+                .prop("device_type", b"cpu\0")
+                .prop_cells("reg", &[i])
+                .end();
+        }
+        fdt.end();
+        fdt.begin("intc@8000000")
+            .prop("compatible", b"arm,cortex-a15-gic\0")
+            .prop_cells("reg", &[
+                0, 0x800_0000, 0, 0x1_0000, 0, 0x801_0000, 0, 0x1_0000,
+            ])
+            .end();
+        fdt.begin("virtio_mmio@a000000")
+            .prop("compatible", b"virtio,mmio\0")
+            .prop_cells("reg", &[0, 0xa00_0000, 0, 0x200])
+            .prop_cells("interrupts", &[0, 0x10, 1])
+            .end();
+        fdt.begin("virtio_mmio@a000200")
+            .prop("compatible", b"virtio,mmio\0")
+            .prop_cells("reg", &[0, 0xa00_0200, 0, 0x200])
+            .prop_cells("interrupts", &[1, 0x3, 4])
+            .end();
+        fdt.begin("virtio_mmio@a000400")
+            .prop("compatible", b"virtio,mmio\0")
+            .prop("status", b"disabled\0")
+            .prop_cells("reg", &[0, 0xa00_0400, 0, 0x200])
+            .end();
+        fdt.begin("chosen").prop("rng-seed", &[0x5a; 80]).end();
+        fdt.end();
+        fdt.build()
+    }
+
+    #[test]
+    fn test_parse() {
+        let info = DtbInfo::parse(&qemu_virt_like()).unwrap();
+        assert_eq!(&info.mem_ranges[..info.mem_range_count], &[
+            (0x4000_0000, 0x800_0000),
+            (0x1_0000_0000, 0x1000_0000)
+        ]);
+        assert_eq!(info.cpu_count, 3);
+        assert_eq!(info.gic_paddrs, Some((0x800_0000, 0x801_0000)));
+        assert_eq!(info.plic_paddr, None);
+        // the disabled slot is skipped
+        assert_eq!(&info.virtio_mmio[..info.virtio_mmio_count], &[
+            (0xa00_0000, 0x200),
+            (0xa00_0200, 0x200)
+        ]);
+        // an SPI and a PPI
+        assert_eq!(&info.virtio_mmio_irqs[..info.virtio_mmio_count], &[
+            Some(0x10 + 32),
+            Some(0x3 + 16)
+        ]);
+        // truncated to the recorded size
+        assert_eq!(&info.rng_seed[..info.rng_seed_len], &[0x5a; MAX_RNG_SEED]);
+    }
+
+    #[test]
+    fn test_large_memory() {
+        // `-m 8G` on riscv64 QEMU virt
+        let mut fdt = FdtBuilder::default();
+        fdt.begin("")
+            .prop_cells("#address-cells", &[2])
+            .prop_cells("#size-cells", &[2]);
+        fdt.begin("memory@80000000")
+            .prop("device_type", b"memory\0")
+            .prop_cells("reg", &[0, 0x8000_0000, 2, 0])
+            .end();
+        fdt.end();
+        let info = DtbInfo::parse(&fdt.build()).unwrap();
+        let ranges = &info.mem_ranges[..info.mem_range_count];
+        assert_eq!(ranges, &[(0x8000_0000, 0x2_0000_0000)]);
+
+        // only the memory mapped at boot is free, above the kernel image
+        let free: Vec<_> =
+            crate::mem::free_ranges(ranges, pa!(0x8040_0123), pa!(0x1_8000_0000)).collect();
+        assert_eq!(free, [(pa!(0x8040_1000), 0x1_8000_0000 - 0x8040_1000)]);
+        // and the ranges beyond it are dropped
+        let ranges = [(0x8000_0000, 0x1000_0000), (0x2_0000_0000, 0x1000_0000)];
+        let free: Vec<_> =
+            crate::mem::free_ranges(&ranges, pa!(0x8000_0000), pa!(0x1_8000_0000)).collect();
+        assert_eq!(free, [(pa!(0x8000_0000), 0x1000_0000)]);
+    }
+
+    #[test]
+    fn test_one_cell_addresses() {
+        let mut fdt = FdtBuilder::default();
+        fdt.begin("")
+            .prop_cells("#address-cells", &[1])
+            .prop_cells("#size-cells", &[1]);
+        fdt.begin("memory")
+            .prop("device_type", b"memory\0")
+            .prop_cells("reg", &[0x8000_0000, 0x800_0000])
+            .end();
+        fdt.begin("soc");
+        // the properties of the parent apply to the children only
+        fdt.prop_cells("#address-cells", &[2]);
+        fdt.begin("plic@c000000")
+            .prop("compatible", b"sifive,plic-1.0.0\0riscv,plic0\0")
+            .prop_cells("reg", &[0, 0xc00_0000, 0x60_0000])
+            .end();
+        fdt.end();
+        fdt.end();
+        let info = DtbInfo::parse(&fdt.build()).unwrap();
+        assert_eq!(&info.mem_ranges[..info.mem_range_count], &[(
+            0x8000_0000,
+            0x800_0000
+        )]);
+        assert_eq!(info.plic_paddr, Some(0xc00_0000));
+        assert_eq!(info.cpu_count, 0);
+    }
+
+    /// Builds the interrupt-map of a PCI host bridge in the layout of QEMU
+    /// virt: the INTx pins of slot `s` are rotated by `s % 4`, and routed to
+    /// `irq(0..4)` of the controller with the given phandle.
+    fn pci_irq_map(
+        parent: u32,
+        parent_address_cells: usize,
+        irq: impl Fn(u32) -> Vec<u32>,
+    ) -> Vec<u32> {
+        let mut map = Vec::new();
+        for slot in 0..4 {
+            for pin in 1..=4 {
+                map.extend_from_slice(&[slot << 11, 0, 0, pin, parent]);
+                map.extend(core::iter::repeat_n(0, parent_address_cells));
+                map.extend(irq((pin - 1 + slot) % 4));
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn test_plic_irqs() {
+        let mut fdt = FdtBuilder::default();
+        fdt.begin("")
+            .prop_cells("#address-cells", &[2])
+            .prop_cells("#size-cells", &[2]);
+        fdt.begin("soc")
+            .prop_cells("#address-cells", &[2])
+            .prop_cells("#size-cells", &[2]);
+        // the PLIC is described after the devices that refer to it
+        fdt.begin("virtio_mmio@10001000")
+            .prop_cells("interrupt-parent", &[3])
+            .prop_cells("interrupts", &[1])
+            .prop("compatible", b"virtio,mmio\0")
+            .prop_cells("reg", &[0, 0x1000_1000, 0, 0x1000])
+            .end();
+        fdt.begin("virtio_mmio@10002000")
+            .prop_cells("interrupt-parent", &[3])
+            .prop_cells("interrupts", &[2])
+            .prop("compatible", b"virtio,mmio\0")
+            .prop_cells("reg", &[0, 0x1000_2000, 0, 0x1000])
+            .end();
+        fdt.begin("pci@30000000")
+            .prop("device_type", b"pci\0")
+            .prop("compatible", b"pci-host-ecam-generic\0")
+            .prop_cells("#address-cells", &[3])
+            .prop_cells("#size-cells", &[2])
+            .prop_cells("#interrupt-cells", &[1])
+            .prop_cells("interrupt-map-mask", &[0x1800, 0, 0, 7])
+            .prop_cells("interrupt-map", &pci_irq_map(3, 0, |i| vec![0x20 + i]))
+            .end();
+        fdt.begin("cpus")
+            .prop_cells("#address-cells", &[1])
+            .prop_cells("#size-cells", &[0]);
+        fdt.begin("cpu@0")
+            .prop("device_type", b"cpu\0")
+            .prop_cells("reg", &[0]);
+        // a hart-local controller that the kernel does not dispatch from
+        fdt.begin("interrupt-controller")
+            .prop("compatible", b"riscv,cpu-intc\0")
+            .prop("interrupt-controller", b"")
+            .prop_cells("#interrupt-cells", &[1])
+            .prop_cells("phandle", &[1])
+            .end();
+        fdt.end();
+        fdt.end();
+        fdt.begin("plic@c000000")
+            .prop_cells("phandle", &[3])
+            .prop_cells("#address-cells", &[0])
+            .prop_cells("#interrupt-cells", &[1])
+            .prop("interrupt-controller", b"")
+            .prop("compatible", b"sifive,plic-1.0.0\0riscv,plic0\0")
+            .prop_cells("reg", &[0, 0xc00_0000, 0, 0x60_0000])
+            .end();
+        fdt.end();
+        fdt.end();
+        let info = DtbInfo::parse(&fdt.build()).unwrap();
+        assert_eq!(info.plic_paddr, Some(0xc00_0000));
+        assert_eq!(&info.virtio_mmio_irqs[..info.virtio_mmio_count], &[
+            Some(1),
+            Some(2)
+        ]);
+        // INTA# of slot 0 to INTD# of slot 3 (the bus and the function are
+        // masked out)
+        assert_eq!(info.pci_intx_irq(0, 0, 0, 1), Some(0x20));
+        assert_eq!(info.pci_intx_irq(0, 1, 0, 1), Some(0x21));
+        assert_eq!(info.pci_intx_irq(0, 2, 0, 3), Some(0x20));
+        assert_eq!(info.pci_intx_irq(1, 7, 2, 4), Some(0x22));
+        // no such pin
+        assert_eq!(info.pci_intx_irq(0, 1, 0, 5), None);
+    }
+
+    #[test]
+    fn test_gic_pci_irqs() {
+        let mut fdt = FdtBuilder::default();
+        fdt.begin("")
+            .prop_cells("interrupt-parent", &[0x8002])
+            .prop_cells("#address-cells", &[2])
+            .prop_cells("#size-cells", &[2]);
+        fdt.begin("intc@8000000")
+            .prop_cells("phandle", &[0x8002])
+            .prop("compatible", b"arm,cortex-a15-gic\0")
+            .prop_cells("#address-cells", &[2])
+            .prop_cells("#interrupt-cells", &[3])
+            .prop("interrupt-controller", b"")
+            .prop_cells("reg", &[
+                0, 0x800_0000, 0, 0x1_0000, 0, 0x801_0000, 0, 0x1_0000,
+            ])
+            .end();
+        fdt.begin("pcie@10000000")
+            .prop("device_type", b"pci\0")
+            .prop_cells("#address-cells", &[3])
+            .prop_cells("#size-cells", &[2])
+            .prop_cells("#interrupt-cells", &[1])
+            .prop_cells("interrupt-map-mask", &[0x1800, 0, 0, 7])
+            .prop_cells(
+                "interrupt-map",
+                &pci_irq_map(0x8002, 2, |i| vec![0, 3 + i, 4]),
+            )
+            .end();
+        // a device routed to an unknown controller
+        fdt.begin("virtio_mmio@a000000")
+            .prop_cells("interrupt-parent", &[0x9000])
+            .prop_cells("interrupts", &[0, 0x10, 1])
+            .prop("compatible", b"virtio,mmio\0")
+            .prop_cells("reg", &[0, 0xa00_0000, 0, 0x200])
+            .end();
+        // a device that inherits the interrupt parent of the root
+        fdt.begin("virtio_mmio@a000200")
+            .prop_cells("interrupts", &[0, 0x11, 1])
+            .prop("compatible", b"virtio,mmio\0")
+            .prop_cells("reg", &[0, 0xa00_0200, 0, 0x200])
+            .end();
+        fdt.end();
+        let info = DtbInfo::parse(&fdt.build()).unwrap();
+        assert_eq!(&info.virtio_mmio_irqs[..info.virtio_mmio_count], &[
+            None,
+            Some(0x11 + 32)
+        ]);
+        // SPIs 3 to 6
+        assert_eq!(info.pci_intx_irq(0, 0, 0, 1), Some(35));
+        assert_eq!(info.pci_intx_irq(0, 1, 0, 1), Some(36));
+        assert_eq!(info.pci_intx_irq(0, 3, 0, 2), Some(35));
+        assert_eq!(info.pci_intx_irq(0, 5, 0, 4), Some(35));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let blob = qemu_virt_like();
+        // bad magic
+        let mut bad = blob.clone();
+        bad[0] = 0;
+        assert!(DtbInfo::parse(&bad).is_none());
+        // truncated in the middle of the structure block
+        assert!(DtbInfo::parse(&blob[..100]).is_none());
+        // a property whose length runs past the end of the blob
+        let mut fdt = FdtBuilder::default();
+        fdt.begin("").prop_cells("reg", &[1]);
+        let mut blob = fdt.build();
+        blob[40 + 8 + 4..40 + 8 + 8].copy_from_slice(&0x1000u32.to_be_bytes());
+        assert!(DtbInfo::parse(&blob).is_none());
+        // an unbalanced end of node
+        let mut fdt = FdtBuilder::default();
+        fdt.end();
+        assert!(DtbInfo::parse(&fdt.build()).is_none());
+        // too deep
+        let mut fdt = FdtBuilder::default();
+        for _ in 0..=MAX_DEPTH {
+            fdt.begin("n");
+        }
+        assert!(DtbInfo::parse(&fdt.build()).is_none());
+    }
+}
@@ -1,8 +1,11 @@
 //! Interrupt management.
 
+use core::iter;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
 use handler_table::HandlerTable;
 
-use crate::platform::irq::{MAX_IRQ_COUNT, dispatch_irq};
+use crate::platform::irq::{MAX_IRQ_COUNT, TIMER_IRQ_NUM, dispatch_irq};
 use crate::trap::{IRQ, register_trap_handler};
 
 pub use crate::platform::irq::{register_handler, set_enable};
@@ -12,10 +15,44 @@ pub type IrqHandler = handler_table::Handler;
 
 static IRQ_HANDLER_TABLE: HandlerTable<MAX_IRQ_COUNT> = HandlerTable::new();
 
+/// The number of times each IRQ has been dispatched.
+static IRQ_COUNTS: [AtomicUsize; MAX_IRQ_COUNT] = [const { AtomicUsize::new(0) }; MAX_IRQ_COUNT];
+
+/// The number of times the timer IRQ has been dispatched, if
+/// [`TIMER_IRQ_NUM`] is out of the range of [`IRQ_COUNTS`], e.g., on RISC-V.
+static TIMER_IRQ_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the IRQ numbers that have been dispatched, with the number of
+/// times of each, in ascending order of the IRQ numbers.
+///
+/// The timer IRQ is reported with [`TIMER_IRQ_NUM`].
+pub fn irq_counts() -> impl Iterator<Item = (usize, usize)> {
+    IRQ_COUNTS
+        .iter()
+        .enumerate()
+        .map(|(irq_num, count)| (irq_num, count.load(Ordering::Relaxed)))
+        .chain(iter::once((
+            TIMER_IRQ_NUM,
+            TIMER_IRQ_COUNT.load(Ordering::Relaxed),
+        )))
+        .filter(|&(_, count)| count > 0)
+}
+
+/// Counts a dispatch of the IRQ.
+#[allow(dead_code)]
+pub(crate) fn count_irq(irq_num: usize) {
+    if let Some(count) = IRQ_COUNTS.get(irq_num) {
+        count.fetch_add(1, Ordering::Relaxed);
+    } else if irq_num == TIMER_IRQ_NUM {
+        TIMER_IRQ_COUNT.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
 /// Platform-independent IRQ dispatching.
 #[allow(dead_code)]
 pub(crate) fn dispatch_irq_common(irq_num: usize) {
     trace!("IRQ {}", irq_num);
+    count_irq(irq_num);
     if !IRQ_HANDLER_TABLE.handle(irq_num) {
         warn!("Unhandled IRQ {}", irq_num);
     }
@@ -35,6 +72,27 @@ pub(crate) fn register_handler_common(irq_num: usize, handler: IrqHandler) -> bo
     false
 }
 
+/// Returns the IRQ number of the INTx interrupt of a PCI device.
+///
+/// `pin` is the Interrupt Pin register of the device, from 1 for INTA# to 4
+/// for INTD#, or 0 if it uses no INTx. `line` is its Interrupt Line register,
+/// set by the firmware on x86. Returns `None` if the interrupt is not routed
+/// or cannot be dispatched by the platform.
+pub fn pci_intx_irq(bus: u8, device: u8, function: u8, pin: u8, line: u8) -> Option<usize> {
+    cfg_if::cfg_if! {
+        if #[cfg(all(target_arch = "x86_64", platform_family = "x86-pc"))] {
+            let _ = (bus, device, function);
+            if pin == 0 {
+                return None;
+            }
+            crate::platform::irq::route_pci_intx(line)
+        } else {
+            let _ = line;
+            crate::dtb::pci_intx_irq(bus, device, function, pin)
+        }
+    }
+}
+
 #[register_trap_handler(IRQ)]
 fn handler_irq(irq_num: usize) -> bool {
     let guard = kernel_guard::NoPreempt::new();
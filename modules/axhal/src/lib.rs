@@ -44,7 +44,9 @@ pub mod trap;
 
 pub mod arch;
 pub mod cpu;
+pub mod dtb;
 pub mod mem;
+pub mod random;
 pub mod time;
 
 #[cfg(feature = "tls")]
@@ -111,32 +111,76 @@ fn kernel_image_regions() -> impl Iterator<Item = MemRegion> {
 }
 
 /// Returns the default MMIO memory regions (from [`axconfig::MMIO_REGIONS`]).
+///
+/// VirtIO MMIO slots discovered from the device tree that are not covered by
+/// the configured regions are also included.
 #[allow(dead_code)]
 pub(crate) fn default_mmio_regions() -> impl Iterator<Item = MemRegion> {
-    axconfig::devices::MMIO_REGIONS.iter().map(|reg| MemRegion {
-        paddr: reg.0.into(),
-        size: reg.1,
-        flags: MemRegionFlags::RESERVED
-            | MemRegionFlags::DEVICE
-            | MemRegionFlags::READ
-            | MemRegionFlags::WRITE,
-        name: "mmio",
-    })
+    let mmio_regions = axconfig::devices::MMIO_REGIONS;
+    let is_covered = move |base: usize, size: usize| {
+        mmio_regions
+            .iter()
+            .any(|&(start, len)| base >= start && base + size <= start + len)
+    };
+    let extra_virtio_regions = crate::dtb::virtio_mmio_regions()
+        .iter()
+        .filter(move |&&(base, size)| !is_covered(base, size));
+    mmio_regions
+        .iter()
+        .chain(extra_virtio_regions)
+        .map(|reg| MemRegion {
+            paddr: reg.0.into(),
+            size: reg.1,
+            flags: MemRegionFlags::RESERVED
+                | MemRegionFlags::DEVICE
+                | MemRegionFlags::READ
+                | MemRegionFlags::WRITE,
+            name: "mmio",
+        })
 }
 
 /// Returns the default free memory regions (kernel image end to physical memory end).
+///
+/// If the device tree describes the physical memory, every memory range is
+/// used, except the parts below the end of the kernel image. Otherwise, the
+/// range [`PHYS_MEMORY_BASE`], [`PHYS_MEMORY_SIZE`] from the platform
+/// configuration is used.
+///
+/// The memory above the end of the boot page table mapping (the platform's
+/// `BOOT_MAPPED_END`) is left out, as the allocator accesses the free memory
+/// before the kernel page table is set.
 #[allow(dead_code)]
 pub(crate) fn default_free_regions() -> impl Iterator<Item = MemRegion> {
-    let start = virt_to_phys((_ekernel as usize).into()).align_up_4k();
-    let end = pa!(PHYS_MEMORY_BASE + PHYS_MEMORY_SIZE).align_down_4k();
-    core::iter::once(MemRegion {
-        paddr: start,
-        size: end.as_usize() - start.as_usize(),
+    const DEFAULT_RANGES: &[(usize, usize)] = &[(PHYS_MEMORY_BASE, PHYS_MEMORY_SIZE)];
+    let ranges = crate::dtb::phys_memory_ranges().unwrap_or(DEFAULT_RANGES);
+    let kernel_end = virt_to_phys((_ekernel as usize).into());
+    let mapped_end = pa!(crate::platform::mem::BOOT_MAPPED_END);
+    free_ranges(ranges, kernel_end, mapped_end).map(|(paddr, size)| MemRegion {
+        paddr,
+        size,
         flags: MemRegionFlags::FREE | MemRegionFlags::READ | MemRegionFlags::WRITE,
         name: "free memory",
     })
 }
 
+/// Clips the physical memory ranges `(base, size)` to the pages between
+/// `kernel_end` and `mapped_end`, skipping the ranges left empty.
+pub(crate) fn free_ranges(
+    ranges: &[(usize, usize)],
+    kernel_end: PhysAddr,
+    mapped_end: PhysAddr,
+) -> impl Iterator<Item = (PhysAddr, usize)> + '_ {
+    let kernel_end = kernel_end.align_up_4k();
+    let mapped_end = mapped_end.align_down_4k();
+    ranges.iter().filter_map(move |&(base, size)| {
+        let start = pa!(base).align_up_4k().max(kernel_end);
+        let end = pa!(base.saturating_add(size))
+            .align_down_4k()
+            .min(mapped_end);
+        (start < end).then(|| (start, end.as_usize() - start.as_usize()))
+    })
+}
+
 /// Fills the `.bss` section with zeros.
 #[allow(dead_code)]
 pub(crate) fn clear_bss() {
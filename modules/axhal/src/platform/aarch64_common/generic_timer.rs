@@ -9,6 +9,9 @@ static mut NANOS_TO_CNTPCT_RATIO: Ratio = Ratio::zero();
 /// RTC wall time offset in nanoseconds at monotonic time base.
 static mut RTC_EPOCHOFFSET_NANOS: u64 = 0;
 
+/// The name of the clock source of [`current_ticks`], as on Linux.
+pub const CLOCK_SOURCE: &str = "arch_sys_counter";
+
 /// Returns the current clock time in hardware ticks.
 #[inline]
 pub fn current_ticks() -> u64 {
@@ -2,6 +2,7 @@ use crate::{irq::IrqHandler, mem::phys_to_virt};
 use arm_gicv2::{GicCpuInterface, GicDistributor, InterruptType, translate_irq};
 use axconfig::devices::{GICC_PADDR, GICD_PADDR, UART_IRQ};
 use kspin::SpinNoIrq;
+use lazyinit::LazyInit;
 use memory_addr::PhysAddr;
 
 /// The maximum number of IRQs.
@@ -13,14 +14,16 @@ pub const TIMER_IRQ_NUM: usize = translate_irq(14, InterruptType::PPI).unwrap();
 /// The UART IRQ number.
 pub const UART_IRQ_NUM: usize = translate_irq(UART_IRQ, InterruptType::SPI).unwrap();
 
-const GICD_BASE: PhysAddr = pa!(GICD_PADDR);
-const GICC_BASE: PhysAddr = pa!(GICC_PADDR);
-
-static GICD: SpinNoIrq<GicDistributor> =
-    SpinNoIrq::new(GicDistributor::new(phys_to_virt(GICD_BASE).as_mut_ptr()));
+static GICD: LazyInit<SpinNoIrq<GicDistributor>> = LazyInit::new();
 
 // per-CPU, no lock
-static GICC: GicCpuInterface = GicCpuInterface::new(phys_to_virt(GICC_BASE).as_mut_ptr());
+static GICC: LazyInit<GicCpuInterface> = LazyInit::new();
+
+/// Returns the base addresses of GICD and GICC, preferring the ones described
+/// in the device tree.
+fn gic_base() -> (PhysAddr, PhysAddr) {
+    crate::dtb::gic_paddrs().unwrap_or((pa!(GICD_PADDR), pa!(GICC_PADDR)))
+}
 
 /// Enables or disables the given IRQ.
 pub fn set_enable(irq_num: usize, enabled: bool) {
@@ -48,7 +51,15 @@ pub fn dispatch_irq(_unused: usize) {
 
 /// Initializes GICD, GICC on the primary CPU.
 pub(crate) fn init_primary() {
-    info!("Initialize GICv2...");
+    let (gicd_base, gicc_base) = gic_base();
+    info!(
+        "Initialize GICv2 at GICD {:?}, GICC {:?}...",
+        gicd_base, gicc_base
+    );
+    GICD.init_once(SpinNoIrq::new(GicDistributor::new(
+        phys_to_virt(gicd_base).as_mut_ptr(),
+    )));
+    GICC.init_once(GicCpuInterface::new(phys_to_virt(gicc_base).as_mut_ptr()));
     GICD.lock().init();
     GICC.init();
 }
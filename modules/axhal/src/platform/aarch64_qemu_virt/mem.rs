@@ -1,6 +1,9 @@
 use crate::mem::MemRegion;
 use page_table_entry::{GenericPTE, MappingFlags, aarch64::A64PTE};
 
+/// The end of the physical memory mapped by [`init_boot_page_table`].
+pub(crate) const BOOT_MAPPED_END: usize = 0x1_4000_0000;
+
 /// Returns platform-specific memory regions.
 pub(crate) fn platform_regions() -> impl Iterator<Item = MemRegion> {
     crate::mem::default_free_regions().chain(crate::mem::default_mmio_regions())
@@ -20,10 +23,16 @@ pub(crate) unsafe fn init_boot_page_table(
         MappingFlags::READ | MappingFlags::WRITE | MappingFlags::DEVICE,
         true,
     );
-    // 0x0000_4000_0000..0x0000_8000_0000, 1G block, normal memory
-    boot_pt_l1[1] = A64PTE::new_page(
-        pa!(0x4000_0000),
-        MappingFlags::READ | MappingFlags::WRITE | MappingFlags::EXECUTE,
-        true,
-    );
+    // 0x0000_4000_0000..0x0001_4000_0000, 4 * 1G block, normal memory
+    //
+    // The physical memory size may be discovered from the device tree, so map
+    // enough memory for the allocator to use before the kernel page table is set.
+    // The memory above is not used, see `BOOT_MAPPED_END`.
+    for (i, pte) in boot_pt_l1.iter_mut().enumerate().take(5).skip(1) {
+        *pte = A64PTE::new_page(
+            pa!(i * 0x4000_0000),
+            MappingFlags::READ | MappingFlags::WRITE | MappingFlags::EXECUTE,
+            true,
+        );
+    }
 }
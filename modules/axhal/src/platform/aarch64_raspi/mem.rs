@@ -1,6 +1,9 @@
 use crate::mem::{MemRegion, MemRegionFlags};
 use page_table_entry::{GenericPTE, MappingFlags, aarch64::A64PTE};
 
+/// The end of the physical memory mapped by [`init_boot_page_table`].
+pub(crate) const BOOT_MAPPED_END: usize = 0xc000_0000;
+
 /// Returns platform-specific memory regions.
 pub(crate) fn platform_regions() -> impl Iterator<Item = MemRegion> {
     core::iter::once(MemRegion {
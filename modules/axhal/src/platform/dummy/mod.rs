@@ -28,6 +28,9 @@ pub mod mp {
 }
 
 pub mod mem {
+    /// The end of the physical memory mapped at boot.
+    pub(crate) const BOOT_MAPPED_END: usize = usize::MAX;
+
     /// Returns platform-specific memory regions.
     pub(crate) fn platform_regions() -> impl Iterator<Item = crate::mem::MemRegion> {
         core::iter::empty()
@@ -35,6 +38,9 @@ pub mod mem {
 }
 
 pub mod time {
+    /// The name of the clock source of [`current_ticks`].
+    pub const CLOCK_SOURCE: &str = "jiffies";
+
     /// Returns the current clock time in hardware ticks.
     pub fn current_ticks() -> u64 {
         0
@@ -1,3 +1,6 @@
+/// The end of the physical memory mapped by the boot page table in `boot.rs`.
+pub(crate) const BOOT_MAPPED_END: usize = 0xc000_0000;
+
 /// Returns platform-specific memory regions.
 pub(crate) fn platform_regions() -> impl Iterator<Item = crate::mem::MemRegion> {
     crate::mem::default_free_regions().chain(crate::mem::default_mmio_regions())
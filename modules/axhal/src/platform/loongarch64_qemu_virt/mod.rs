@@ -16,6 +16,10 @@ pub fn platform_init() {}
 #[cfg(feature = "smp")]
 pub fn platform_init_secondary() {}
 
+/// The physical address where QEMU loads the device tree (`FDT_BASE`), as the
+/// boot protocol does not pass it in a register.
+const DTB_PADDR: usize = 0x10_0000;
+
 unsafe extern "C" {
     fn rust_main(cpu_id: usize, dtb: usize);
     #[cfg(feature = "smp")]
@@ -27,13 +31,19 @@ unsafe extern "C" {
 /// This function will be called after assembly boot stage.
 unsafe extern "C" fn rust_entry(cpu_id: usize) {
     crate::mem::clear_bss();
+    crate::dtb::init(DTB_PADDR);
     super::console::init_early();
     crate::cpu::init_primary(cpu_id);
     super::time::init_primary();
     super::time::init_percpu();
 
+    let dtb = if crate::dtb::is_present() {
+        DTB_PADDR
+    } else {
+        0
+    };
     unsafe {
-        rust_main(cpu_id, 0);
+        rust_main(cpu_id, dtb);
     }
 }
 
@@ -6,6 +6,9 @@ static NANOS_PER_TICK: LazyInit<u64> = LazyInit::new();
 /// RTC wall time offset in nanoseconds at monotonic time base.
 static mut RTC_EPOCHOFFSET_NANOS: u64 = 0;
 
+/// The name of the clock source of [`current_ticks`], as on Linux.
+pub const CLOCK_SOURCE: &str = "Constant";
+
 /// Returns the current clock time in hardware ticks.
 #[inline]
 pub fn current_ticks() -> u64 {
@@ -12,12 +12,18 @@ static mut BOOT_PT_SV39: [u64; 512] = [0; 512];
 unsafe fn init_boot_page_table() {
     // 0x0000_0000..0x4000_0000, VRWX_GAD, 1G block
     BOOT_PT_SV39[0] = (0x0 << 10) | 0xef;
-    // 0x8000_0000..0xc000_0000, VRWX_GAD, 1G block
-    BOOT_PT_SV39[2] = (0x80000 << 10) | 0xef;
     // 0xffff_ffc0_0000_0000..0xffff_ffc0_4000_0000, VRWX_GAD, 1G block
     BOOT_PT_SV39[0x100] = (0x0 << 10) | 0xef;
-    // 0xffff_ffc0_8000_0000..0xffff_ffc0_c000_0000, VRWX_GAD, 1G block
-    BOOT_PT_SV39[0x102] = (0x80000 << 10) | 0xef;
+    // 0x8000_0000..0x1_8000_0000, VRWX_GAD, 4 * 1G block
+    // 0xffff_ffc0_8000_0000..0xffff_ffc1_8000_0000, VRWX_GAD, 4 * 1G block
+    //
+    // The physical memory size may be discovered from the device tree, so map
+    // enough memory for the allocator to use before the kernel page table is set.
+    // The memory above is not used, see `BOOT_MAPPED_END`.
+    for i in 0..4 {
+        BOOT_PT_SV39[2 + i] = ((0x80000 + (i << 18)) << 10) as u64 | 0xef;
+        BOOT_PT_SV39[0x102 + i] = ((0x80000 + (i << 18)) << 10) as u64 | 0xef;
+    }
 }
 
 unsafe fn init_mmu() {
@@ -1,6 +1,11 @@
-//! TODO: PLIC
+//! Interrupts of the RISC-V hart, and external interrupts through the PLIC.
+//!
+//! IRQ numbers without the `Interrupt` bit of `scause` are PLIC interrupt
+//! sources, which are routed to the supervisor external interrupt of every
+//! hart.
 
 use crate::irq::IrqHandler;
+use crate::mem::{PhysAddr, VirtAddr, phys_to_virt};
 use lazyinit::LazyInit;
 use riscv::register::sie;
 
@@ -22,6 +27,45 @@ static TIMER_HANDLER: LazyInit<IrqHandler> = LazyInit::new();
 /// The maximum number of IRQs.
 pub const MAX_IRQ_COUNT: usize = 1024;
 
+/// The base physical address of the PLIC of QEMU virt, used if the device tree
+/// does not describe one.
+const PLIC_PADDR: usize = 0x0c00_0000;
+
+const PLIC_PRIORITY: usize = 0x0;
+const PLIC_ENABLE: usize = 0x2000;
+const PLIC_ENABLE_STRIDE: usize = 0x80;
+const PLIC_CONTEXT: usize = 0x20_0000;
+const PLIC_CONTEXT_STRIDE: usize = 0x1000;
+const PLIC_THRESHOLD: usize = 0x0;
+const PLIC_CLAIM: usize = 0x4;
+
+fn plic_base() -> VirtAddr {
+    phys_to_virt(crate::dtb::plic_paddr().unwrap_or(PhysAddr::from(PLIC_PADDR)))
+}
+
+fn plic_reg(offset: usize) -> *mut u32 {
+    (plic_base().as_usize() + offset) as *mut u32
+}
+
+/// The PLIC context of the supervisor mode of the given hart.
+const fn plic_context(hart_id: usize) -> usize {
+    hart_id * 2 + 1
+}
+
+/// Claims the highest-priority pending interrupt of the current hart.
+fn plic_claim() -> usize {
+    let context = plic_context(crate::cpu::this_cpu_id());
+    let reg = plic_reg(PLIC_CONTEXT + context * PLIC_CONTEXT_STRIDE + PLIC_CLAIM);
+    unsafe { reg.read_volatile() as usize }
+}
+
+/// Signals the completion of the interrupt claimed by the current hart.
+fn plic_complete(irq_num: usize) {
+    let context = plic_context(crate::cpu::this_cpu_id());
+    let reg = plic_reg(PLIC_CONTEXT + context * PLIC_CONTEXT_STRIDE + PLIC_CLAIM);
+    unsafe { reg.write_volatile(irq_num as u32) }
+}
+
 /// The timer IRQ number (supervisor timer interrupt in `scause`).
 pub const TIMER_IRQ_NUM: usize = S_TIMER;
 
@@ -36,27 +80,39 @@ macro_rules! with_cause {
 }
 
 /// Enables or disables the given IRQ.
-pub fn set_enable(scause: usize, _enabled: bool) {
-    if scause == S_EXT {
-        // TODO: set enable in PLIC
+///
+/// Only the PLIC interrupt sources can be enabled or disabled. They are
+/// enabled on all harts, and handled by the first one that claims them.
+pub fn set_enable(irq_num: usize, enabled: bool) {
+    if irq_num == 0 || irq_num >= MAX_IRQ_COUNT {
+        return;
+    }
+    unsafe { plic_reg(PLIC_PRIORITY + irq_num * 4).write_volatile(enabled as u32) };
+    for hart_id in 0..crate::dtb::cpu_count() {
+        let context = plic_context(hart_id);
+        let reg = plic_reg(PLIC_ENABLE + context * PLIC_ENABLE_STRIDE + irq_num / 32 * 4);
+        let bit = 1 << (irq_num % 32);
+        unsafe {
+            let old = reg.read_volatile();
+            reg.write_volatile(if enabled { old | bit } else { old & !bit });
+        }
     }
 }
 
 /// Registers an IRQ handler for the given IRQ.
 ///
-/// It also enables the IRQ if the registration succeeds. It returns `false` if
-/// the registration failed.
-pub fn register_handler(scause: usize, handler: IrqHandler) -> bool {
-    with_cause!(
-        scause,
-        @TIMER => if !TIMER_HANDLER.is_inited() {
+/// The IRQ is either [`TIMER_IRQ_NUM`], or the number of a PLIC interrupt
+/// source. It also enables the IRQ if the registration succeeds. It returns
+/// `false` if the registration failed.
+pub fn register_handler(irq_num: usize, handler: IrqHandler) -> bool {
+    if irq_num == S_TIMER {
+        if !TIMER_HANDLER.is_inited() {
             TIMER_HANDLER.init_once(handler);
-            true
-        } else {
-            false
-        },
-        @EXT => crate::irq::register_handler_common(scause & !INTC_IRQ_BASE, handler),
-    )
+            return true;
+        }
+        return false;
+    }
+    irq_num != 0 && crate::irq::register_handler_common(irq_num, handler)
 }
 
 /// Dispatches the IRQ.
@@ -69,13 +125,26 @@ pub fn dispatch_irq(scause: usize) {
         scause,
         @TIMER => {
             trace!("IRQ: timer");
+            crate::irq::count_irq(TIMER_IRQ_NUM);
             TIMER_HANDLER();
         },
-        @EXT => crate::irq::dispatch_irq_common(0), // TODO: get IRQ number from PLIC
+        @EXT => {
+            // 0 means that another hart has claimed the interrupt
+            let irq_num = plic_claim();
+            if irq_num != 0 {
+                crate::irq::dispatch_irq_common(irq_num);
+                plic_complete(irq_num);
+            }
+        },
     );
 }
 
 pub(super) fn init_percpu() {
+    // accept the PLIC interrupts of any priority
+    let context = plic_context(crate::cpu::this_cpu_id());
+    unsafe {
+        plic_reg(PLIC_CONTEXT + context * PLIC_CONTEXT_STRIDE + PLIC_THRESHOLD).write_volatile(0);
+    }
     // enable soft interrupts, timer interrupts, and external interrupts
     unsafe {
         sie::set_ssoft();
@@ -1,5 +1,8 @@
 use crate::mem::MemRegion;
 
+/// The end of the physical memory mapped by the boot page table in `boot.rs`.
+pub(crate) const BOOT_MAPPED_END: usize = 0x1_8000_0000;
+
 /// Returns platform-specific memory regions.
 pub(crate) fn platform_regions() -> impl Iterator<Item = MemRegion> {
     crate::mem::default_free_regions().chain(crate::mem::default_mmio_regions())
@@ -4,6 +4,9 @@ const NANOS_PER_TICK: u64 = crate::time::NANOS_PER_SEC / axconfig::devices::TIME
 /// RTC wall time offset in nanoseconds at monotonic time base.
 static mut RTC_EPOCHOFFSET_NANOS: u64 = 0;
 
+/// The name of the clock source of [`current_ticks`], as on Linux.
+pub const CLOCK_SOURCE: &str = "riscv_clocksource";
+
 /// Returns the current clock time in hardware ticks.
 #[inline]
 pub fn current_ticks() -> u64 {
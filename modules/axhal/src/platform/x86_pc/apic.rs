@@ -5,7 +5,7 @@ use core::{cell::SyncUnsafeCell, mem::MaybeUninit};
 use kspin::SpinNoIrq;
 use lazyinit::LazyInit;
 use memory_addr::PhysAddr;
-use x2apic::ioapic::IoApic;
+use x2apic::ioapic::{IoApic, IrqFlags};
 use x2apic::lapic::{LocalApic, LocalApicBuilder, xapic_base};
 use x86_64::instructions::port::Port;
 
@@ -26,6 +26,12 @@ pub const TIMER_IRQ_NUM: usize = APIC_TIMER_VECTOR as usize;
 
 const IO_APIC_BASE: PhysAddr = pa!(0xFEC0_0000);
 
+/// The vector of the first input of the I/O APIC, right after the CPU
+/// exceptions.
+const IO_APIC_VECTOR_BASE: u8 = 0x20;
+/// The number of inputs of the I/O APIC.
+const IO_APIC_INPUTS: u8 = 24;
+
 static LOCAL_APIC: SyncUnsafeCell<MaybeUninit<LocalApic>> =
     SyncUnsafeCell::new(MaybeUninit::uninit());
 static mut IS_X2APIC: bool = false;
@@ -35,17 +41,44 @@ static IO_APIC: LazyInit<SpinNoIrq<IoApic>> = LazyInit::new();
 #[cfg(feature = "irq")]
 pub fn set_enable(vector: usize, enabled: bool) {
     // should not affect LAPIC interrupts
-    if vector < APIC_TIMER_VECTOR as _ {
-        unsafe {
-            if enabled {
-                IO_APIC.lock().enable_irq(vector as u8);
-            } else {
-                IO_APIC.lock().disable_irq(vector as u8);
-            }
+    let Some(input) = io_apic_input(vector) else {
+        return;
+    };
+    unsafe {
+        if enabled {
+            IO_APIC.lock().enable_irq(input);
+        } else {
+            IO_APIC.lock().disable_irq(input);
         }
     }
 }
 
+/// Routes the INTx interrupt of a PCI device through the I/O APIC, and
+/// returns its vector.
+///
+/// `line` is the Interrupt Line register of the device, which the firmware
+/// sets to the legacy IRQ that the pin is routed to, or 0xff if it is not
+/// routed. PCI interrupts are level-triggered and active-high.
+#[cfg(feature = "irq")]
+pub fn route_pci_intx(line: u8) -> Option<usize> {
+    if line >= IO_APIC_INPUTS {
+        return None;
+    }
+    let mut io_apic = IO_APIC.lock();
+    unsafe {
+        let mut entry = io_apic.table_entry(line);
+        entry.set_flags((entry.flags() | IrqFlags::LEVEL_TRIGGERED) - IrqFlags::LOW_ACTIVE);
+        io_apic.set_table_entry(line, entry);
+    }
+    Some((IO_APIC_VECTOR_BASE + line) as usize)
+}
+
+/// Returns the I/O APIC input that raises the given vector.
+fn io_apic_input(vector: usize) -> Option<u8> {
+    let input = vector.checked_sub(IO_APIC_VECTOR_BASE as usize)?;
+    (input < IO_APIC_INPUTS as usize).then_some(input as u8)
+}
+
 /// Registers an IRQ handler for the given IRQ.
 ///
 /// It also enables the IRQ if the registration succeeds. It returns `false` if
@@ -117,7 +150,9 @@ pub(super) fn init_primary() {
     }
 
     info!("Initialize IO APIC...");
-    let io_apic = unsafe { IoApic::new(phys_to_virt(IO_APIC_BASE).as_usize() as u64) };
+    let mut io_apic = unsafe { IoApic::new(phys_to_virt(IO_APIC_BASE).as_usize() as u64) };
+    // all inputs are masked, and delivered to the primary CPU when enabled
+    unsafe { io_apic.init(IO_APIC_VECTOR_BASE) };
     IO_APIC.init_once(SpinNoIrq::new(io_apic));
 }
 
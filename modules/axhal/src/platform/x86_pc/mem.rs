@@ -2,6 +2,9 @@
 
 use crate::mem::{MemRegion, MemRegionFlags};
 
+/// The end of the physical memory mapped by the boot page table in `multiboot.S`.
+pub(crate) const BOOT_MAPPED_END: usize = 0x1_0000_0000;
+
 /// Returns platform-specific memory regions.
 pub(crate) fn platform_regions() -> impl Iterator<Item = MemRegion> {
     core::iter::once(MemRegion {
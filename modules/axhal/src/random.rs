@@ -0,0 +1,117 @@
+//! Hardware entropy of the platform, to seed random number generators.
+//!
+//! The sources are:
+//!
+//! - the `RDRAND` instruction on x86_64,
+//! - the `RNDR` register on AArch64 with FEAT_RNG,
+//! - the `rng-seed` property of `/chosen` in the device tree.
+//!
+//! The generators themselves are implemented by the modules above.
+
+/// Returns a seed from all the available sources of entropy, or `None` if
+/// there is none.
+///
+/// The seed from the device tree never changes, so the result of the calls
+/// is only different from each other if the platform has a hardware random
+/// number generator.
+pub fn entropy() -> Option<[u8; 32]> {
+    let mut seed = [0; 32];
+    let mut seeded = false;
+    if let Some(dtb_seed) = crate::dtb::rng_seed() {
+        mix(&mut seed, dtb_seed);
+        seeded = true;
+    }
+    let mut hw_seed = [0; 32];
+    if hw_seed.chunks_mut(8).all(|chunk| {
+        hw_random().is_some_and(|value| {
+            chunk.copy_from_slice(&value.to_le_bytes());
+            true
+        })
+    }) {
+        mix(&mut seed, &hw_seed);
+        seeded = true;
+    }
+    seeded.then_some(seed)
+}
+
+/// XORs `bytes` into `seed`, wrapping around if it is longer than the seed.
+fn mix(seed: &mut [u8; 32], bytes: &[u8]) {
+    for (i, &b) in bytes.iter().enumerate() {
+        seed[i % seed.len()] ^= b;
+    }
+}
+
+/// Returns 64 random bits from the `RDRAND` instruction, if it is supported.
+#[cfg(target_arch = "x86_64")]
+fn hw_random() -> Option<u64> {
+    #[target_feature(enable = "rdrand")]
+    unsafe fn rdrand(value: &mut u64) -> bool {
+        unsafe { core::arch::x86_64::_rdrand64_step(value) == 1 }
+    }
+
+    let cpuid = raw_cpuid::CpuId::new();
+    if !cpuid.get_feature_info().is_some_and(|f| f.has_rdrand()) {
+        return None;
+    }
+    // it may fail transiently, and a few retries are recommended by Intel
+    for _ in 0..10 {
+        let mut value = 0;
+        // SAFETY: the instruction is supported, as checked above
+        if unsafe { rdrand(&mut value) } {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Returns 64 random bits from the `RNDR` register, if FEAT_RNG is
+/// implemented.
+#[cfg(target_arch = "aarch64")]
+fn hw_random() -> Option<u64> {
+    let isar0: u64;
+    // SAFETY: reading an ID register has no side effects
+    unsafe { core::arch::asm!("mrs {}, id_aa64isar0_el1", out(reg) isar0) };
+    if (isar0 >> 60) & 0xf == 0 {
+        return None;
+    }
+    for _ in 0..10 {
+        let (value, ok): (u64, u64);
+        // SAFETY: the register is implemented, as checked above. It is named
+        // by its encoding, which does not need the `rand` target feature.
+        unsafe {
+            core::arch::asm!(
+                "mrs {0}, s3_3_c2_c4_0",
+                "cset {1}, ne",
+                out(reg) value,
+                out(reg) ok,
+                options(nomem, nostack),
+            )
+        };
+        // the Z flag is set on failure
+        if ok != 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn hw_random() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mix() {
+        let mut seed = [0; 32];
+        mix(&mut seed, &[1, 2, 3]);
+        assert_eq!(seed[..4], [1, 2, 3, 0]);
+        // the bytes after the first 32 ones wrap around
+        mix(&mut seed, &[0xff; 33]);
+        assert_eq!(seed[..4], [1, 0xfd, 0xfc, 0xff]);
+        assert_eq!(seed[4..], [0xff; 28]);
+    }
+}
@@ -260,3 +260,16 @@ pub fn set_max_level(level: &str) {
         .unwrap_or(LevelFilter::Off);
     log::set_max_level(lf);
 }
+
+/// Returns the maximum log level, one of `off`, `error`, `warn`, `info`,
+/// `debug`, `trace`.
+pub fn max_level() -> &'static str {
+    match log::max_level() {
+        LevelFilter::Off => "off",
+        LevelFilter::Error => "error",
+        LevelFilter::Warn => "warn",
+        LevelFilter::Info => "info",
+        LevelFilter::Debug => "debug",
+        LevelFilter::Trace => "trace",
+    }
+}
@@ -2,7 +2,7 @@ use core::fmt;
 
 use axerrno::{AxError, AxResult, ax_err};
 use axhal::mem::phys_to_virt;
-use axhal::paging::{MappingFlags, PageTable, PagingError};
+use axhal::paging::{MappingFlags, PageSize, PageTable, PagingError};
 use memory_addr::{
     MemoryAddr, PAGE_SIZE_4K, PageIter4K, PhysAddr, VirtAddr, VirtAddrRange, is_aligned_4k,
 };
@@ -311,11 +311,17 @@ impl AddrSpace {
 
     /// Updates mapping within the specified virtual address range.
     ///
+    /// The pages of lazy allocation mappings that are not accessed yet stay
+    /// unmapped, and are mapped with the new flags on access.
+    ///
     /// Returns an error if the address range is out of the address space or not
-    /// aligned.
+    /// aligned, or if it is not fully mapped.
     pub fn protect(&mut self, start: VirtAddr, size: usize, flags: MappingFlags) -> AxResult {
-        // Populate the area first, which also checks the address range for us.
-        self.populate_area(start, size)?;
+        self.validate_region(start, size)?;
+        let range = VirtAddrRange::from_start_size(start, size);
+        if !self.check_region_access(range, MappingFlags::empty()) {
+            return ax_err!(NoMemory);
+        }
 
         self.areas
             .protect(start, size, |_| Some(flags), &mut self.pt)
@@ -381,6 +387,89 @@ impl AddrSpace {
         false
     }
 
+    /// Handles a page fault at the given address like
+    /// [`AddrSpace::handle_page_fault`], but fills the new zeroed page by
+    /// `fill` before it is mapped, e.g., with the content of a file.
+    ///
+    /// The page is mapped with `flags`, which must be within the flags of the
+    /// area, e.g., without `WRITE` to catch the first write to it.
+    ///
+    /// Returns `true` also if the page is already mapped, e.g., by another
+    /// task faulting on it at the same time.
+    pub fn handle_page_fault_with(
+        &mut self,
+        vaddr: VirtAddr,
+        flags: MappingFlags,
+        fill: impl FnOnce(&mut [u8]),
+    ) -> bool {
+        if !self.va_range.contains(vaddr) {
+            return false;
+        }
+        let Some(area) = self.areas.find(vaddr) else {
+            return false;
+        };
+        if !area.flags().contains(flags) {
+            return false;
+        }
+        if self.pt.query(vaddr).is_ok() {
+            return true;
+        }
+        area.backend()
+            .handle_page_fault_with(vaddr, flags, &mut self.pt, fill)
+    }
+
+    /// Maps the page at `vaddr` of a lazy allocation mapping to `frame` with
+    /// `flags`, which must be within the flags of the area.
+    ///
+    /// The frame is not owned by the address space, e.g., it is a page of a
+    /// file cache. It must be unmapped by [`AddrSpace::unmap_frame`] before
+    /// the area is unmapped, which would free it.
+    pub fn map_frame(&mut self, vaddr: VirtAddr, frame: PhysAddr, flags: MappingFlags) -> AxResult {
+        if !vaddr.is_aligned_4k() || !frame.is_aligned_4k() {
+            return ax_err!(InvalidInput, "address not aligned");
+        }
+        let Some(area) = self.areas.find(vaddr) else {
+            return ax_err!(BadAddress);
+        };
+        if !matches!(area.backend(), Backend::Alloc { populate: false })
+            || !area.flags().contains(flags)
+        {
+            return ax_err!(InvalidInput);
+        }
+        match self.pt.map(vaddr, frame, PageSize::Size4K, flags) {
+            Ok(tlb) => {
+                tlb.flush();
+                Ok(())
+            }
+            Err(PagingError::AlreadyMapped) => ax_err!(AlreadyExists),
+            Err(_) => ax_err!(NoMemory),
+        }
+    }
+
+    /// Unmaps the page at `vaddr` mapped by [`AddrSpace::map_frame`], and
+    /// returns the frame, which is not freed.
+    pub fn unmap_frame(&mut self, vaddr: VirtAddr) -> AxResult<PhysAddr> {
+        let (frame, _, tlb) = self.pt.unmap(vaddr).map_err(|_| AxError::BadAddress)?;
+        tlb.flush();
+        Ok(frame)
+    }
+
+    /// Changes the flags of the mapped page at `vaddr` to `flags`, which must
+    /// be within the flags of its area.
+    pub fn protect_page(&mut self, vaddr: VirtAddr, flags: MappingFlags) -> AxResult {
+        match self.areas.find(vaddr) {
+            Some(area) if area.flags().contains(flags) => {}
+            Some(_) => return ax_err!(InvalidInput),
+            None => return ax_err!(BadAddress),
+        }
+        let (_, tlb) = self
+            .pt
+            .protect(vaddr, flags)
+            .map_err(|_| AxError::BadAddress)?;
+        tlb.flush();
+        Ok(())
+    }
+
     /// Clone a [`AddrSpace`] by re-mapping all [`MemoryArea`]s in a new page table and copying data in user space.
     pub fn clone_or_err(&mut self) -> AxResult<Self> {
         let mut new_aspace = Self::new_empty(self.base(), self.size())?;
@@ -98,4 +98,34 @@ impl Backend {
             false
         }
     }
+
+    pub(crate) fn handle_page_fault_alloc_with(
+        vaddr: VirtAddr,
+        flags: MappingFlags,
+        pt: &mut PageTable,
+        populate: bool,
+        fill: impl FnOnce(&mut [u8]),
+    ) -> bool {
+        if populate {
+            return false;
+        }
+        let Some(frame) = alloc_frame(true) else {
+            return false;
+        };
+        // the page is filled before it is mapped, so that no one sees it
+        // half-filled
+        fill(unsafe {
+            core::slice::from_raw_parts_mut(phys_to_virt(frame).as_mut_ptr(), PAGE_SIZE_4K)
+        });
+        match pt.map(vaddr, frame, PageSize::Size4K, flags) {
+            Ok(tlb) => {
+                tlb.flush();
+                true
+            }
+            Err(_) => {
+                dealloc_frame(frame);
+                false
+            }
+        }
+    }
 }
@@ -1,7 +1,7 @@
 //! Memory mapping backends.
 
-use axhal::paging::{MappingFlags, PageTable};
-use memory_addr::VirtAddr;
+use axhal::paging::{MappingFlags, PageTable, PagingError};
+use memory_addr::{PageIter4K, VirtAddr};
 use memory_set::MappingBackend;
 
 mod alloc;
@@ -63,10 +63,26 @@ impl MappingBackend for Backend {
         new_flags: Self::Flags,
         page_table: &mut Self::PageTable,
     ) -> bool {
-        page_table
-            .protect_region(start, size, new_flags, true)
-            .map(|tlb| tlb.ignore())
-            .is_ok()
+        match *self {
+            // only the pages accessed are mapped, the others are mapped with
+            // the new flags on access
+            Self::Alloc { populate: false } => {
+                PageIter4K::new(start, start + size).unwrap().all(|addr| {
+                    match page_table.protect(addr, new_flags) {
+                        Ok((_, tlb)) => {
+                            tlb.flush();
+                            true
+                        }
+                        Err(PagingError::NotMapped) => true,
+                        Err(_) => false,
+                    }
+                })
+            }
+            _ => page_table
+                .protect_region(start, size, new_flags, true)
+                .map(|tlb| tlb.ignore())
+                .is_ok(),
+        }
     }
 }
 
@@ -84,4 +100,19 @@ impl Backend {
             }
         }
     }
+
+    pub(crate) fn handle_page_fault_with(
+        &self,
+        vaddr: VirtAddr,
+        flags: MappingFlags,
+        page_table: &mut PageTable,
+        fill: impl FnOnce(&mut [u8]),
+    ) -> bool {
+        match *self {
+            Self::Linear { .. } => false,
+            Self::Alloc { populate } => {
+                Self::handle_page_fault_alloc_with(vaddr, flags, page_table, populate, fill)
+            }
+        }
+    }
 }
@@ -4,16 +4,39 @@
 //! using various underlying network stacks. Currently, only [smoltcp] is
 //! supported.
 //!
+//! A loopback interface `lo` with `127.0.0.1/8` and `::1/128` is always
+//! present, even if there is no NIC.
+//!
+//! Both IPv4 and IPv6 are supported. Every NIC gets an IPv6 link-local
+//! address, and a global address and default gateway from router
+//! advertisements (SLAAC).
+//!
 //! # Organization
 //!
 //! - [`TcpSocket`]: A TCP socket that provides POSIX-like APIs.
 //! - [`UdpSocket`]: A UDP socket that provides POSIX-like APIs.
-//! - [`dns_query`]: Function for DNS query.
+//! - [`IcmpSocket`]: An ICMP socket for sending echo requests and receiving
+//!   echo replies, i.e., pinging.
+//! - [`RawSocket`]: A raw IP socket that sends and receives packets of a given
+//!   IP protocol.
+//! - [`dns_query`]: Function for DNS query. The resolver is configured by
+//!   [`set_dns_servers`], [`set_dns_options`] and [`add_dns_host`].
+//! - [`interfaces`]: Lists the network interfaces. Their addresses and the
+//!   default gateway can be changed at runtime with [`set_ip_addr`],
+//!   [`set_default_gateway`], etc.
 //!
 //! # Cargo Features
 //!
 //! - `smoltcp`: Use [smoltcp] as the underlying network stack. This is enabled
 //!   by default.
+//! - `dhcp`: Run a DHCPv4 client on every NIC during [`init_network`],
+//!   instead of using the compile-time `AX_IP` and `AX_GW`. Leases are renewed
+//!   by a background task, and can be queried by [`dhcp_lease`].
+//! - `fs`: Read the DNS servers from `/etc/resolv.conf` and static host
+//!   entries from `/etc/hosts` during [`init_network`], and list the TCP
+//!   sockets in `/proc/net/tcp` and `/proc/net/tcp6`. The interfaces are
+//!   shown in `/sys/class/net/`, and the default buffer sizes of sockets can
+//!   be changed in `/sys/module/axnet/parameters/`.
 //!
 //! [smoltcp]: https://github.com/smoltcp-rs/smoltcp
 
@@ -30,18 +53,37 @@ cfg_if::cfg_if! {
     }
 }
 
+pub use self::net_impl::IcmpSocket;
+pub use self::net_impl::RawSocket;
+pub use self::net_impl::SocketOptions;
 pub use self::net_impl::TcpSocket;
 pub use self::net_impl::UdpSocket;
+pub use self::net_impl::poll_interfaces;
+pub use self::net_impl::{
+    DhcpLease, InterfaceInfo, clear_default_gateway, clear_ip_addrs, dhcp_lease, interfaces,
+    set_default_gateway, set_ip_addr,
+};
+pub use self::net_impl::{
+    DnsOptions, DnsRecordType, add_dns_host, clear_dns_cache, dns_options, dns_query,
+    dns_query_type, dns_reverse_query, dns_servers, remove_dns_host, set_dns_options,
+    set_dns_servers,
+};
 pub use self::net_impl::{bench_receive, bench_transmit};
-pub use self::net_impl::{dns_query, poll_interfaces};
 
+use alloc::vec::Vec;
 use axdriver::{AxDeviceContainer, prelude::*};
 
 /// Initializes the network subsystem by NIC devices.
 pub fn init_network(mut net_devs: AxDeviceContainer<AxNetDevice>) {
     info!("Initialize network subsystem...");
 
-    let dev = net_devs.take_one().expect("No NIC device found!");
-    info!("  use NIC 0: {:?}", dev.device_name());
-    net_impl::init(dev);
+    let mut devs = Vec::new();
+    while let Some((dev, irq)) = net_devs.take_one_with_irq() {
+        info!("  use NIC {}: {:?}", devs.len(), dev.device_name());
+        devs.push((dev, irq));
+    }
+    if devs.is_empty() {
+        warn!("No NIC device found, only the loopback interface is available");
+    }
+    net_impl::init(devs);
 }
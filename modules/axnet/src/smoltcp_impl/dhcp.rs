@@ -0,0 +1,172 @@
+//! DHCPv4 client, enabled by the `dhcp` feature.
+//!
+//! A DHCP socket is added to the socket set of every NIC interface that has
+//! no IPv4 address after the static configuration. Events of the socket are
+//! handled each time the interface is polled, and a background task keeps
+//! polling the interfaces so that leases are renewed in time.
+
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv4Addr};
+use core::time::Duration;
+
+use axhal::time::monotonic_time;
+use axsync::Mutex;
+use smoltcp::iface::{Interface, SocketHandle, SocketSet};
+use smoltcp::socket::dhcpv4::{self, Event};
+use smoltcp::wire::IpCidr;
+
+use super::iface::DhcpLease;
+use super::{INTERFACES, poll_interfaces};
+
+/// How long `init_network` waits for the initial leases.
+const DHCP_INIT_TIMEOUT: Duration = Duration::from_secs(5);
+/// The longest time to sleep between polls while waiting for the initial
+/// leases, as the replies may arrive without waking anyone.
+const DHCP_INIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// How often the background task polls the interfaces.
+const DHCP_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The DHCP client state of an interface.
+pub(super) struct DhcpClient {
+    handle: Mutex<Option<SocketHandle>>,
+    lease: Mutex<Option<DhcpLease>>,
+}
+
+impl DhcpClient {
+    pub const fn new() -> Self {
+        Self {
+            handle: Mutex::new(None),
+            lease: Mutex::new(None),
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.handle.lock().is_some()
+    }
+
+    /// Returns the current lease, or `None` if the interface is not configured
+    /// by DHCP.
+    pub fn lease(&self) -> Option<DhcpLease> {
+        self.lease.lock().clone()
+    }
+
+    /// Processes the pending event of the DHCP socket.
+    ///
+    /// It is called each time after the interface is polled, with the
+    /// interface and its socket set locked.
+    pub fn handle_events(&self, name: &str, iface: &mut Interface, sockets: &mut SocketSet<'_>) {
+        let Some(handle) = *self.handle.lock() else {
+            return;
+        };
+        match sockets.get_mut::<dhcpv4::Socket>(handle).poll() {
+            None => {}
+            Some(Event::Configured(config)) => {
+                iface.update_ip_addrs(|ip_addrs| {
+                    ip_addrs.retain(|cidr| !matches!(cidr, IpCidr::Ipv4(_)));
+                    ip_addrs.push(IpCidr::Ipv4(config.address)).ok();
+                });
+                match config.router {
+                    Some(router) => {
+                        iface.routes_mut().add_default_ipv4_route(router).ok();
+                    }
+                    None => {
+                        iface.routes_mut().remove_default_ipv4_route();
+                    }
+                }
+                let lease = DhcpLease {
+                    addr: Ipv4Addr::from(config.address.address().0),
+                    prefix_len: config.address.prefix_len(),
+                    router: config.router.map(|ip| Ipv4Addr::from(ip.0)),
+                    dns_servers: config
+                        .dns_servers
+                        .iter()
+                        .map(|ip| Ipv4Addr::from(ip.0))
+                        .collect(),
+                    server: Ipv4Addr::from(config.server.address.0),
+                    acquired_at: monotonic_time(),
+                };
+                info!(
+                    "{}: DHCP lease {}/{} from {}, router {:?}, DNS servers {:?}",
+                    name,
+                    lease.addr,
+                    lease.prefix_len,
+                    lease.server,
+                    lease.router,
+                    lease.dns_servers
+                );
+                *self.lease.lock() = Some(lease);
+            }
+            Some(Event::Deconfigured) => {
+                iface.update_ip_addrs(|ip_addrs| {
+                    ip_addrs.retain(|cidr| !matches!(cidr, IpCidr::Ipv4(_)));
+                });
+                iface.routes_mut().remove_default_ipv4_route();
+                if self.lease.lock().take().is_some() {
+                    warn!("{}: DHCP lease lost", name);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the DNS servers of all DHCP leases.
+pub(super) fn dns_servers() -> Vec<IpAddr> {
+    INTERFACES
+        .iter()
+        .filter_map(|iface| iface.dhcp.lease())
+        .flat_map(|lease| lease.dns_servers)
+        .map(IpAddr::V4)
+        .collect()
+}
+
+/// Starts DHCP on the interfaces that have no IPv4 address, waits for the
+/// initial leases for a while, then spawns the background task to renew them.
+pub(super) fn init() {
+    for iface in INTERFACES.iter() {
+        let has_ipv4 = iface
+            .iface
+            .lock()
+            .ip_addrs()
+            .iter()
+            .any(|cidr| matches!(cidr, IpCidr::Ipv4(_)));
+        if !has_ipv4 && iface.ethernet_address().is_some() {
+            info!("{}: starting DHCP", iface.name());
+            let handle = iface.sockets.add(dhcpv4::Socket::new());
+            *iface.dhcp.handle.lock() = Some(handle);
+        }
+    }
+
+    let is_pending = || {
+        INTERFACES
+            .iter()
+            .any(|iface| iface.dhcp.is_running() && iface.dhcp.lease().is_none())
+    };
+    if !INTERFACES.iter().any(|iface| iface.dhcp.is_running()) {
+        return;
+    }
+
+    let deadline = monotonic_time() + DHCP_INIT_TIMEOUT;
+    loop {
+        poll_interfaces();
+        let now = monotonic_time();
+        if !is_pending() || now >= deadline {
+            break;
+        }
+        // sleep until the next DHCP retransmission is due
+        let delay = INTERFACES
+            .iter()
+            .filter_map(|iface| iface.poll_delay())
+            .fold(DHCP_INIT_POLL_INTERVAL.min(deadline - now), Duration::min);
+        axtask::sleep(delay);
+    }
+    if is_pending() {
+        warn!("DHCP: timed out waiting for leases, continuing in the background");
+    }
+
+    axtask::spawn(|| {
+        loop {
+            poll_interfaces();
+            axtask::sleep(DHCP_POLL_INTERVAL);
+        }
+    });
+}
@@ -1,90 +1,749 @@
-use alloc::vec::Vec;
-use axerrno::{AxError, AxResult, ax_err_type};
-use core::net::IpAddr;
+//! DNS resolver.
+//!
+//! A name is looked up in the static host table first, then in the answer
+//! cache, and finally queried from the DNS servers over UDP. Answers are
+//! cached until their TTL expires.
+//!
+//! The servers are chosen in the following order:
+//!
+//! 1. those set by [`set_dns_servers`] or read from `/etc/resolv.conf`,
+//! 2. those provided by DHCP,
+//! 3. `8.8.8.8`.
 
-use smoltcp::iface::SocketHandle;
-use smoltcp::socket::dns::{self, GetQueryResultError, StartQueryError};
-use smoltcp::wire::DnsQueryType;
+use alloc::collections::BTreeMap;
+use alloc::{format, string::String, vec, vec::Vec};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use core::sync::atomic::{AtomicU16, Ordering};
+use core::time::Duration;
 
-use super::addr::into_core_ipaddr;
-use super::{ETH0, SOCKET_SET, SocketSetWrapper};
+use axerrno::{AxError, AxResult, ax_err, ax_err_type};
+use axhal::time::monotonic_time;
+use axsync::Mutex;
 
-/// A DNS socket.
-struct DnsSocket {
-    handle: Option<SocketHandle>,
+use super::addr::{UNSPECIFIED_ENDPOINT, into_core_sockaddr};
+use super::{DNS_SERVER, UdpSocket};
+
+const DNS_PORT: u16 = 53;
+/// The maximum number of servers that are queried, as in glibc.
+const MAX_SERVERS: usize = 3;
+/// The maximum number of cached answers.
+const MAX_CACHE_ENTRIES: usize = 64;
+/// The maximum size of a DNS message over UDP.
+const MAX_MESSAGE_LEN: usize = 512;
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+
+const RCODE_NO_ERROR: u16 = 0;
+const RCODE_NAME_ERROR: u16 = 3;
+
+/// Options of the DNS resolver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DnsOptions {
+    /// How long to wait for the reply of a server.
+    pub timeout: Duration,
+    /// How many rounds of queries are sent to all the servers.
+    pub attempts: usize,
+}
+
+impl DnsOptions {
+    const DEFAULT: Self = Self {
+        timeout: Duration::from_secs(5),
+        attempts: 2,
+    };
 }
 
-impl DnsSocket {
-    #[allow(clippy::new_without_default)]
-    /// Creates a new DNS socket.
-    pub fn new() -> Self {
-        let socket = SocketSetWrapper::new_dns_socket();
-        let handle = Some(SOCKET_SET.add(socket));
-        Self { handle }
+impl Default for DnsOptions {
+    fn default() -> Self {
+        Self::DEFAULT
     }
+}
 
-    #[allow(dead_code)]
-    /// Update the list of DNS servers, will replace all existing servers.
-    pub fn update_servers(self, servers: &[smoltcp::wire::IpAddress]) {
-        SOCKET_SET.with_socket_mut::<dns::Socket, _, _>(self.handle.unwrap(), |socket| {
-            socket.update_servers(servers)
-        });
+/// The type of addresses to query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsRecordType {
+    /// IPv4 addresses (A records).
+    A,
+    /// IPv6 addresses (AAAA records).
+    Aaaa,
+}
+
+impl DnsRecordType {
+    const fn code(self) -> u16 {
+        match self {
+            Self::A => TYPE_A,
+            Self::Aaaa => TYPE_AAAA,
+        }
     }
+}
 
-    /// Query a address with given DNS query type.
-    pub fn query(&self, name: &str, query_type: DnsQueryType) -> AxResult<Vec<IpAddr>> {
-        // let local_addr = self.local_addr.unwrap_or_else(f);
-        let handle = self.handle.ok_or_else(|| ax_err_type!(InvalidInput))?;
-        let iface = &ETH0.iface;
-        let query_handle = SOCKET_SET
-            .with_socket_mut::<dns::Socket, _, _>(handle, |socket| {
-                socket.start_query(iface.lock().context(), name, query_type)
-            })
-            .map_err(|e| match e {
-                StartQueryError::NoFreeSlot => {
-                    ax_err_type!(ResourceBusy, "socket query() failed: no free slot")
+/// The data of a resource record.
+#[derive(Clone)]
+enum RecordData {
+    Addr(IpAddr),
+    Name(String),
+}
+
+struct CacheEntry {
+    records: Vec<RecordData>,
+    expires_at: Duration,
+}
+
+struct Resolver {
+    servers: Vec<IpAddr>,
+    options: DnsOptions,
+    hosts: Vec<(String, IpAddr)>,
+    /// Cached answers, keyed by the lowercase name and the record type.
+    cache: BTreeMap<(String, u16), CacheEntry>,
+}
+
+static RESOLVER: Mutex<Resolver> = Mutex::new(Resolver {
+    servers: Vec::new(),
+    options: DnsOptions::DEFAULT,
+    hosts: Vec::new(),
+    cache: BTreeMap::new(),
+});
+
+impl Resolver {
+    fn servers(&self) -> Vec<IpAddr> {
+        if !self.servers.is_empty() {
+            return self.servers.clone();
+        }
+        #[cfg(feature = "dhcp")]
+        {
+            let mut servers = super::dhcp::dns_servers();
+            if !servers.is_empty() {
+                servers.truncate(MAX_SERVERS);
+                return servers;
+            }
+        }
+        vec![DNS_SERVER.parse().expect("invalid DNS server address")]
+    }
+
+    fn lookup_hosts(&self, name: &str, qtype: u16) -> Vec<RecordData> {
+        self.hosts
+            .iter()
+            .filter_map(|(host, addr)| match (qtype, addr) {
+                (TYPE_A, IpAddr::V4(_)) | (TYPE_AAAA, IpAddr::V6(_))
+                    if host.eq_ignore_ascii_case(name) =>
+                {
+                    Some(RecordData::Addr(*addr))
                 }
-                StartQueryError::InvalidName => {
-                    ax_err_type!(InvalidInput, "socket query() failed: invalid name")
+                (TYPE_PTR, _) if reverse_name(*addr).eq_ignore_ascii_case(name) => {
+                    Some(RecordData::Name(host.clone()))
                 }
-                StartQueryError::NameTooLong => {
-                    ax_err_type!(InvalidInput, "socket query() failed: too long name")
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn lookup_cache(&mut self, key: &(String, u16)) -> Option<Vec<RecordData>> {
+        let now = monotonic_time();
+        self.cache.retain(|_, entry| entry.expires_at > now);
+        self.cache.get(key).map(|entry| entry.records.clone())
+    }
+
+    fn insert_cache(&mut self, key: (String, u16), records: Vec<RecordData>, ttl: u32) {
+        if ttl == 0 {
+            return;
+        }
+        if self.cache.len() >= MAX_CACHE_ENTRIES && !self.cache.contains_key(&key) {
+            // evict the entry that expires first
+            let first = self
+                .cache
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(key, _)| key.clone());
+            if let Some(first) = first {
+                self.cache.remove(&first);
+            }
+        }
+        let expires_at = monotonic_time() + Duration::from_secs(ttl as u64);
+        self.cache.insert(key, CacheEntry {
+            records,
+            expires_at,
+        });
+    }
+}
+
+/// Returns the name for the reverse lookup of `addr`, e.g.,
+/// `4.3.2.1.in-addr.arpa` for `1.2.3.4`.
+fn reverse_name(addr: IpAddr) -> String {
+    match addr {
+        IpAddr::V4(v4) => {
+            let [a, b, c, d] = v4.octets();
+            format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+        }
+        IpAddr::V6(v6) => {
+            let mut name = String::with_capacity(72);
+            for byte in v6.octets().iter().rev() {
+                name += &format!("{:x}.{:x}.", byte & 0xf, byte >> 4);
+            }
+            name + "ip6.arpa"
+        }
+    }
+}
+
+/// Builds a query message with recursion desired.
+fn build_query(id: u16, name: &str, qtype: u16) -> AxResult<Vec<u8>> {
+    let mut msg = Vec::with_capacity(MAX_MESSAGE_LEN);
+    msg.extend_from_slice(&id.to_be_bytes());
+    msg.extend_from_slice(&0x0100u16.to_be_bytes()); // RD
+    msg.extend_from_slice(&1u16.to_be_bytes()); // QDCOUNT
+    msg.extend_from_slice(&[0; 6]); // ANCOUNT, NSCOUNT, ARCOUNT
+
+    let name = name.strip_suffix('.').unwrap_or(name);
+    if name.is_empty() || name.len() > 253 {
+        return ax_err!(InvalidInput, "invalid domain name");
+    }
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return ax_err!(InvalidInput, "invalid domain name");
+        }
+        msg.push(label.len() as u8);
+        msg.extend_from_slice(label.as_bytes());
+    }
+    msg.push(0);
+    msg.extend_from_slice(&qtype.to_be_bytes());
+    msg.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(msg)
+}
+
+fn read_u16(msg: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(msg.get(pos..pos + 2)?.try_into().ok()?))
+}
+
+fn read_u32(msg: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(msg.get(pos..pos + 4)?.try_into().ok()?))
+}
+
+/// Reads a possibly compressed domain name at `pos`.
+///
+/// Returns the name and the position right after it.
+fn read_name(msg: &[u8], mut pos: usize) -> Option<(String, usize)> {
+    let mut name = String::new();
+    let mut end = None;
+    // bound the number of compression pointers to avoid loops
+    for _ in 0..64 {
+        let len = *msg.get(pos)? as usize;
+        match len {
+            0 => {
+                return Some((name, end.unwrap_or(pos + 1)));
+            }
+            1..=63 => {
+                let label = msg.get(pos + 1..pos + 1 + len)?;
+                if !name.is_empty() {
+                    name.push('.');
+                }
+                name.push_str(core::str::from_utf8(label).ok()?);
+                pos += 1 + len;
+            }
+            0xc0..=0xff => {
+                end.get_or_insert(pos + 2);
+                pos = (read_u16(msg, pos)? & 0x3fff) as usize;
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// A parsed response.
+struct Response {
+    rcode: u16,
+    records: Vec<RecordData>,
+    /// The minimum TTL of the records.
+    ttl: u32,
+}
+
+/// Parses the response to the query with the given ID and type.
+///
+/// Only the answer records of type `qtype` are returned. CNAME records are
+/// skipped, as recursive servers also return the records they point to.
+fn parse_response(msg: &[u8], id: u16, qtype: u16) -> Option<Response> {
+    let flags = read_u16(msg, 2)?;
+    if read_u16(msg, 0)? != id || flags & 0x8000 == 0 {
+        return None;
+    }
+    let qdcount = read_u16(msg, 4)?;
+    let ancount = read_u16(msg, 6)?;
+
+    let mut pos = 12;
+    for _ in 0..qdcount {
+        pos = read_name(msg, pos)?.1 + 4;
+    }
+    let mut records = Vec::new();
+    let mut ttl = u32::MAX;
+    for _ in 0..ancount {
+        pos = read_name(msg, pos)?.1;
+        let rtype = read_u16(msg, pos)?;
+        let rclass = read_u16(msg, pos + 2)?;
+        let rttl = read_u32(msg, pos + 4)?;
+        let rdlen = read_u16(msg, pos + 8)? as usize;
+        let rdata_pos = pos + 10;
+        let rdata = msg.get(rdata_pos..rdata_pos + rdlen)?;
+        pos = rdata_pos + rdlen;
+        if rtype != qtype || rclass != CLASS_IN {
+            continue;
+        }
+        let record = match rtype {
+            TYPE_A => RecordData::Addr(IpAddr::from(<[u8; 4]>::try_from(rdata).ok()?)),
+            TYPE_AAAA => RecordData::Addr(IpAddr::from(<[u8; 16]>::try_from(rdata).ok()?)),
+            TYPE_PTR => RecordData::Name(read_name(msg, rdata_pos)?.0),
+            _ => continue,
+        };
+        records.push(record);
+        ttl = ttl.min(rttl);
+    }
+    Some(Response {
+        rcode: flags & 0xf,
+        records,
+        ttl,
+    })
+}
+
+fn next_query_id() -> u16 {
+    static NEXT_ID: AtomicU16 = AtomicU16::new(0);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed) ^ (monotonic_time().subsec_nanos() as u16)
+}
+
+/// Sends the query to the servers in turn, until one of them answers.
+fn query_servers(name: &str, qtype: u16) -> AxResult<Response> {
+    let (servers, options) = {
+        let resolver = RESOLVER.lock();
+        (resolver.servers(), resolver.options)
+    };
+
+    let socket = UdpSocket::new();
+    socket.bind(into_core_sockaddr(UNSPECIFIED_ENDPOINT))?;
+    let mut buf = [0; MAX_MESSAGE_LEN];
+    for _ in 0..options.attempts.max(1) {
+        for &server in servers.iter() {
+            let server = SocketAddr::new(server, DNS_PORT);
+            let id = next_query_id();
+            let query = build_query(id, name, qtype)?;
+            if let Err(e) = socket.send_to(&query, server) {
+                debug!("DNS: failed to send query to {}: {:?}", server, e);
+                continue;
+            }
+
+            let deadline = monotonic_time() + options.timeout;
+            loop {
+                let now = monotonic_time();
+                if now >= deadline {
+                    break;
                 }
-            })?;
-        loop {
-            SOCKET_SET.poll_interfaces();
-            match SOCKET_SET.with_socket_mut::<dns::Socket, _, _>(handle, |socket| {
-                socket.get_query_result(query_handle).map_err(|e| match e {
-                    GetQueryResultError::Pending => AxError::WouldBlock,
-                    GetQueryResultError::Failed => {
-                        ax_err_type!(ConnectionRefused, "socket query() failed")
+                // sleep until a reply arrives or the deadline passes
+                let mut socket_options = socket.options();
+                socket_options.recv_timeout = Some(deadline - now);
+                socket.set_options(socket_options)?;
+                let n = match socket.recv_from(&mut buf) {
+                    Ok((n, from)) if from == server => n,
+                    Ok(_) => continue,
+                    Err(AxError::WouldBlock) => break,
+                    Err(e) => return Err(e),
+                };
+                match parse_response(&buf[..n], id, qtype) {
+                    Some(res) if matches!(res.rcode, RCODE_NO_ERROR | RCODE_NAME_ERROR) => {
+                        return Ok(res);
                     }
-                })
-            }) {
-                Ok(n) => {
-                    let mut res = Vec::with_capacity(n.capacity());
-                    for ip in n {
-                        res.push(into_core_ipaddr(ip))
+                    // try the next server on other errors
+                    Some(res) => {
+                        debug!("DNS: server {} replied rcode {}", server, res.rcode);
+                        break;
                     }
-                    return Ok(res);
+                    None => {}
                 }
-                Err(AxError::WouldBlock) => axtask::yield_now(),
-                Err(e) => return Err(e),
             }
         }
     }
+    ax_err!(ConnectionRefused, "DNS query timed out")
 }
 
-impl Drop for DnsSocket {
-    fn drop(&mut self) {
-        if let Some(handle) = self.handle {
-            SOCKET_SET.remove(handle);
+/// Resolves the records of type `qtype` of `name`.
+fn resolve(name: &str, qtype: u16) -> AxResult<Vec<RecordData>> {
+    let key = (name.trim_end_matches('.').to_ascii_lowercase(), qtype);
+    {
+        let mut resolver = RESOLVER.lock();
+        let records = resolver.lookup_hosts(&key.0, qtype);
+        if !records.is_empty() {
+            return Ok(records);
+        }
+        if let Some(records) = resolver.lookup_cache(&key) {
+            return Ok(records);
         }
     }
+
+    let res = query_servers(&key.0, qtype)?;
+    if res.rcode == RCODE_NAME_ERROR {
+        return ax_err!(NotFound, "DNS: name not found");
+    }
+    if res.records.is_empty() {
+        return ax_err!(NotFound, "DNS: no records of the requested type");
+    }
+    RESOLVER
+        .lock()
+        .insert_cache(key, res.records.clone(), res.ttl);
+    Ok(res.records)
+}
+
+/// Queries the addresses of the given type of a domain name.
+pub fn dns_query_type(name: &str, record_type: DnsRecordType) -> AxResult<Vec<IpAddr>> {
+    Ok(resolve(name, record_type.code())?
+        .into_iter()
+        .filter_map(|record| match record {
+            RecordData::Addr(addr) => Some(addr),
+            RecordData::Name(_) => None,
+        })
+        .collect())
 }
 
 /// Public function for DNS query.
-pub fn dns_query(name: &str) -> AxResult<alloc::vec::Vec<IpAddr>> {
-    let socket = DnsSocket::new();
-    socket.query(name, DnsQueryType::A)
+///
+/// Both A and AAAA records are queried, IPv4 addresses come first in the
+/// result. It fails only if both queries fail.
+pub fn dns_query(name: &str) -> AxResult<Vec<IpAddr>> {
+    let ipv4 = dns_query_type(name, DnsRecordType::A);
+    let ipv6 = dns_query_type(name, DnsRecordType::Aaaa);
+    match (ipv4, ipv6) {
+        (Ok(mut ipv4), Ok(ipv6)) => {
+            ipv4.extend(ipv6);
+            Ok(ipv4)
+        }
+        (Ok(res), Err(_)) | (Err(_), Ok(res)) => Ok(res),
+        (Err(e), Err(_)) => Err(e),
+    }
+}
+
+/// Queries the domain name of an address (PTR record).
+pub fn dns_reverse_query(addr: IpAddr) -> AxResult<String> {
+    resolve(&reverse_name(addr), TYPE_PTR)?
+        .into_iter()
+        .find_map(|record| match record {
+            RecordData::Name(name) => Some(name),
+            RecordData::Addr(_) => None,
+        })
+        .ok_or_else(|| ax_err_type!(NotFound, "DNS: no PTR record"))
+}
+
+/// Returns the DNS servers that are queried.
+pub fn dns_servers() -> Vec<IpAddr> {
+    RESOLVER.lock().servers()
+}
+
+/// Sets the DNS servers, replacing those from `/etc/resolv.conf`.
+///
+/// At most 3 servers are allowed. An empty list restores the default, i.e.,
+/// the servers provided by DHCP or `8.8.8.8`. The answer cache is cleared.
+pub fn set_dns_servers(servers: &[IpAddr]) -> AxResult {
+    if servers.len() > MAX_SERVERS {
+        return ax_err!(InvalidInput, "too many DNS servers");
+    }
+    let mut resolver = RESOLVER.lock();
+    resolver.servers = servers.to_vec();
+    resolver.cache.clear();
+    info!("DNS servers: {:?}", resolver.servers());
+    Ok(())
+}
+
+/// Returns the options of the resolver.
+pub fn dns_options() -> DnsOptions {
+    RESOLVER.lock().options
+}
+
+/// Sets the options of the resolver.
+pub fn set_dns_options(options: DnsOptions) -> AxResult {
+    if options.timeout.is_zero() || options.attempts == 0 {
+        return ax_err!(InvalidInput, "invalid DNS options");
+    }
+    RESOLVER.lock().options = options;
+    Ok(())
+}
+
+/// Adds a static entry to the host table, like a line in `/etc/hosts`.
+///
+/// Static entries take precedence over the DNS servers.
+pub fn add_dns_host(name: &str, addr: IpAddr) -> AxResult {
+    if name.is_empty() {
+        return ax_err!(InvalidInput, "empty host name");
+    }
+    let mut resolver = RESOLVER.lock();
+    let name = name.trim_end_matches('.').to_ascii_lowercase();
+    if !resolver.hosts.contains(&(name.clone(), addr)) {
+        resolver.hosts.push((name, addr));
+    }
+    Ok(())
+}
+
+/// Removes all the static entries of the host name.
+pub fn remove_dns_host(name: &str) -> AxResult {
+    let mut resolver = RESOLVER.lock();
+    let len = resolver.hosts.len();
+    let name = name.trim_end_matches('.');
+    resolver
+        .hosts
+        .retain(|(host, _)| !host.eq_ignore_ascii_case(name));
+    if resolver.hosts.len() == len {
+        return ax_err!(NotFound, "no such host entry");
+    }
+    Ok(())
+}
+
+/// Removes all cached answers.
+pub fn clear_dns_cache() {
+    RESOLVER.lock().cache.clear();
+}
+
+/// Parses the `nameserver` and `options` lines of `/etc/resolv.conf`.
+#[cfg(feature = "fs")]
+fn parse_resolv_conf(resolver: &mut Resolver, content: &str) {
+    for line in content.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("nameserver") => {
+                if let Some(Ok(addr)) = words.next().map(str::parse) {
+                    if resolver.servers.len() < MAX_SERVERS {
+                        resolver.servers.push(addr);
+                    }
+                }
+            }
+            Some("options") => {
+                for option in words {
+                    if let Some(Ok(secs)) = option.strip_prefix("timeout:").map(str::parse) {
+                        resolver.options.timeout =
+                            Duration::from_secs(secs).max(Duration::from_secs(1));
+                    } else if let Some(Ok(n)) = option.strip_prefix("attempts:").map(str::parse) {
+                        resolver.options.attempts = usize::max(n, 1);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Parses the lines of `/etc/hosts`, each has an address followed by names.
+#[cfg(feature = "fs")]
+fn parse_hosts(resolver: &mut Resolver, content: &str) {
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        let Some(Ok(addr)) = words.next().map(str::parse::<IpAddr>) else {
+            continue;
+        };
+        for name in words {
+            let name = name.to_ascii_lowercase();
+            if !resolver.hosts.contains(&(name.clone(), addr)) {
+                resolver.hosts.push((name, addr));
+            }
+        }
+    }
+}
+
+/// Initializes the resolver.
+///
+/// `localhost` is always resolved to the loopback addresses. With the `fs`
+/// feature, `/etc/resolv.conf` and `/etc/hosts` are also read if they exist.
+pub(super) fn init() {
+    let mut resolver = RESOLVER.lock();
+    for addr in [Ipv4Addr::LOCALHOST.into(), Ipv6Addr::LOCALHOST.into()] {
+        resolver.hosts.push(("localhost".into(), addr));
+    }
+    #[cfg(feature = "fs")]
+    {
+        if let Ok(content) = axfs::api::read_to_string("/etc/resolv.conf") {
+            parse_resolv_conf(&mut resolver, &content);
+        }
+        if let Ok(content) = axfs::api::read_to_string("/etc/hosts") {
+            parse_hosts(&mut resolver, &content);
+        }
+    }
+    debug!("DNS servers: {:?}", resolver.servers());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Appends a resource record to `msg`, whose name is a pointer to the
+    /// question.
+    fn push_record(msg: &mut Vec<u8>, rtype: u16, ttl: u32, rdata: &[u8]) {
+        msg.extend_from_slice(&0xc00cu16.to_be_bytes());
+        msg.extend_from_slice(&rtype.to_be_bytes());
+        msg.extend_from_slice(&CLASS_IN.to_be_bytes());
+        msg.extend_from_slice(&ttl.to_be_bytes());
+        msg.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        msg.extend_from_slice(rdata);
+    }
+
+    /// Turns a query into a response with the given code and answer count.
+    fn into_response(mut msg: Vec<u8>, rcode: u16, ancount: u16) -> Vec<u8> {
+        msg[2..4].copy_from_slice(&(0x8180 | rcode).to_be_bytes());
+        msg[6..8].copy_from_slice(&ancount.to_be_bytes());
+        msg
+    }
+
+    #[test]
+    fn test_build_query() {
+        let msg = build_query(0x1234, "www.Example.com.", TYPE_AAAA).unwrap();
+        assert_eq!(&msg[..12], &[0x12, 0x34, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&msg[12..], b"\x03www\x07Example\x03com\x00\x00\x1c\x00\x01");
+
+        assert!(build_query(0, "", TYPE_A).is_err());
+        assert!(build_query(0, ".", TYPE_A).is_err());
+        assert!(build_query(0, "a..b", TYPE_A).is_err());
+        assert!(build_query(0, &"a".repeat(64), TYPE_A).is_err());
+        assert!(build_query(0, &"a".repeat(63), TYPE_A).is_ok());
+        let long = vec!["a".repeat(63); 4].join(".");
+        assert!(build_query(0, &long, TYPE_A).is_err());
+    }
+
+    #[test]
+    fn test_read_name() {
+        let msg = b"\x03foo\x03bar\x00\x03www\xc0\x00\xc0\x09";
+        assert_eq!(read_name(msg, 0), Some(("foo.bar".into(), 9)));
+        // a pointer ends the name
+        assert_eq!(read_name(msg, 9), Some(("www.foo.bar".into(), 15)));
+        // a name made of a pointer to a pointer
+        assert_eq!(read_name(msg, 15), Some(("www.foo.bar".into(), 17)));
+        // the root name
+        assert_eq!(read_name(b"\x00", 0), Some(("".into(), 1)));
+
+        // truncated label and pointer
+        assert_eq!(read_name(b"\x05ab", 0), None);
+        assert_eq!(read_name(b"\x01a\xc0", 0), None);
+        // missing terminator
+        assert_eq!(read_name(b"\x01a", 0), None);
+        // reserved label types
+        assert_eq!(read_name(b"\x40a\x00", 0), None);
+        assert_eq!(read_name(b"\x80a\x00", 0), None);
+        // pointer loops
+        assert_eq!(read_name(b"\xc0\x00", 0), None);
+        assert_eq!(read_name(b"\x01a\xc0\x00", 0), None);
+        // labels that are not UTF-8
+        assert_eq!(read_name(b"\x01\xff\x00", 0), None);
+        // out of bounds
+        assert_eq!(read_name(b"\xc0\x10", 0), None);
+        assert_eq!(read_name(b"", 0), None);
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let query = build_query(7, "example.com", TYPE_A).unwrap();
+        let mut msg = into_response(query.clone(), RCODE_NO_ERROR, 3);
+        // a CNAME is skipped, the A records are returned
+        push_record(&mut msg, 5, 100, b"\x03www\xc0\x0c");
+        push_record(&mut msg, TYPE_A, 300, &[1, 2, 3, 4]);
+        push_record(&mut msg, TYPE_A, 60, &[5, 6, 7, 8]);
+        let res = parse_response(&msg, 7, TYPE_A).unwrap();
+        assert_eq!(res.rcode, RCODE_NO_ERROR);
+        assert_eq!(res.ttl, 60);
+        let addrs: Vec<_> = res
+            .records
+            .iter()
+            .filter_map(|r| match r {
+                RecordData::Addr(addr) => Some(*addr),
+                RecordData::Name(_) => None,
+            })
+            .collect();
+        assert_eq!(addrs, [
+            IpAddr::from([1, 2, 3, 4]),
+            IpAddr::from([5, 6, 7, 8])
+        ]);
+
+        // wrong ID, or not a response
+        assert!(parse_response(&msg, 8, TYPE_A).is_none());
+        assert!(parse_response(&query, 7, TYPE_A).is_none());
+        // other record types are not returned
+        assert!(
+            parse_response(&msg, 7, TYPE_AAAA)
+                .unwrap()
+                .records
+                .is_empty()
+        );
+        // truncated records
+        for len in [0, 11, query.len() - 1, msg.len() - 1] {
+            assert!(parse_response(&msg[..len], 7, TYPE_A).is_none());
+        }
+        // an A record of the wrong length
+        let mut bad = into_response(query.clone(), RCODE_NO_ERROR, 1);
+        push_record(&mut bad, TYPE_A, 1, &[1, 2, 3]);
+        assert!(parse_response(&bad, 7, TYPE_A).is_none());
+
+        // name error
+        let res = parse_response(&into_response(query, RCODE_NAME_ERROR, 0), 7, TYPE_A).unwrap();
+        assert_eq!(res.rcode, RCODE_NAME_ERROR);
+        assert!(res.records.is_empty());
+    }
+
+    #[test]
+    fn test_parse_ptr_response() {
+        let name = reverse_name(IpAddr::from([10, 0, 2, 15]));
+        assert_eq!(name, "15.2.0.10.in-addr.arpa");
+        let query = build_query(1, &name, TYPE_PTR).unwrap();
+        let mut msg = into_response(query, RCODE_NO_ERROR, 1);
+        push_record(&mut msg, TYPE_PTR, 10, b"\x04host\x03lan\x00");
+        let res = parse_response(&msg, 1, TYPE_PTR).unwrap();
+        assert!(matches!(&res.records[..], [RecordData::Name(n)] if n == "host.lan"));
+
+        let v6 = reverse_name(IpAddr::from(Ipv6Addr::LOCALHOST));
+        assert!(v6.starts_with("1.0.0.0.0."));
+        assert!(v6.ends_with(".0.ip6.arpa"));
+        assert_eq!(v6.len(), 72);
+    }
+
+    #[cfg(feature = "fs")]
+    fn empty_resolver() -> Resolver {
+        Resolver {
+            servers: Vec::new(),
+            options: DnsOptions::DEFAULT,
+            hosts: Vec::new(),
+            cache: BTreeMap::new(),
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_parse_resolv_conf() {
+        let mut resolver = empty_resolver();
+        parse_resolv_conf(
+            &mut resolver,
+            "# comment\nnameserver 1.1.1.1\nnameserver bogus\nnameserver ::1\n\
+             nameserver 8.8.8.8\nnameserver 9.9.9.9\nsearch lan\n\
+             options ndots:1 timeout:0 attempts:3 attempts:x\nnameserver\n",
+        );
+        assert_eq!(resolver.servers, [
+            IpAddr::from([1, 1, 1, 1]),
+            IpAddr::from(Ipv6Addr::LOCALHOST),
+            IpAddr::from([8, 8, 8, 8]),
+        ]);
+        assert_eq!(resolver.options.timeout, Duration::from_secs(1));
+        assert_eq!(resolver.options.attempts, 3);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_parse_hosts() {
+        let mut resolver = empty_resolver();
+        parse_hosts(
+            &mut resolver,
+            "127.0.0.1 localhost\n# 1.2.3.4 commented\n\
+             10.0.0.1\tGateway gw # trailing comment\n::1 localhost ip6-localhost\n\
+             not-an-address name\n10.0.0.1 gw\n",
+        );
+        assert_eq!(resolver.hosts.len(), 5);
+        assert!(matches!(
+            &resolver.lookup_hosts("gateway", TYPE_A)[..],
+            [RecordData::Addr(addr)] if *addr == IpAddr::from([10, 0, 0, 1])
+        ));
+        assert_eq!(resolver.lookup_hosts("localhost", TYPE_A).len(), 1);
+        assert_eq!(resolver.lookup_hosts("localhost", TYPE_AAAA).len(), 1);
+        assert!(resolver.lookup_hosts("name", TYPE_A).is_empty());
+        assert!(matches!(
+            &resolver.lookup_hosts("1.0.0.10.in-addr.arpa", TYPE_PTR)[..],
+            [RecordData::Name(a), RecordData::Name(b)] if a == "gateway" && b == "gw"
+        ));
+    }
 }
@@ -0,0 +1,429 @@
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::net::{IpAddr, SocketAddr};
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+
+use axerrno::{AxError, AxResult, ax_err, ax_err_type};
+use axio::PollState;
+use axsync::Mutex;
+use spin::RwLock;
+
+use smoltcp::socket::icmp::{self, BindError, SendError};
+use smoltcp::wire::{IpAddress, IpVersion};
+
+use super::addr::{from_core_ipaddr, from_core_sockaddr, into_core_ipaddr, is_unspecified};
+use super::wait::SocketWaiter;
+use super::{INTERFACES, SocketOptions, SocketRef, SocketSetWrapper, iface};
+
+/// The ICMP identifiers bound by ICMP sockets.
+static BOUND_IDENTS: Mutex<BTreeSet<u16>> = Mutex::new(BTreeSet::new());
+
+/// The type of ICMPv4 echo requests.
+const ICMPV4_ECHO_REQUEST: u8 = 8;
+/// The type of ICMPv6 echo requests.
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+/// The length of the header of ICMP echo messages.
+const ICMP_ECHO_HEADER_LEN: usize = 8;
+
+/// An ICMP socket for sending echo requests and receiving echo replies, like
+/// the "ping sockets" of Linux.
+///
+/// The socket is bound to an ICMP identifier, which plays the role of a port:
+/// the identifier of outgoing echo requests is set to it, and only echo
+/// messages with the same identifier are received. As with
+/// [`UdpSocket`](super::UdpSocket), a socket bound to an unspecified address
+/// is backed by one smoltcp socket per interface.
+pub struct IcmpSocket {
+    version: IpVersion,
+    sockets: RwLock<Vec<SocketRef>>,
+    local_addr: RwLock<Option<(IpAddress, u16)>>,
+    nonblock: AtomicBool,
+    options: Mutex<SocketOptions>,
+    /// The sizes of the receive and send buffers of the smoltcp sockets.
+    buf_sizes: Mutex<(usize, usize)>,
+    waiter: SocketWaiter,
+}
+
+impl IcmpSocket {
+    /// Creates a new ICMP socket, which sends and receives ICMPv6 messages if
+    /// `ipv6` is true, or ICMPv4 messages otherwise.
+    pub fn new(ipv6: bool) -> Self {
+        let options = SocketOptions::raw_default();
+        Self {
+            version: if ipv6 {
+                IpVersion::Ipv6
+            } else {
+                IpVersion::Ipv4
+            },
+            sockets: RwLock::new(Vec::new()),
+            local_addr: RwLock::new(None),
+            nonblock: AtomicBool::new(false),
+            options: Mutex::new(options),
+            buf_sizes: Mutex::new((options.recv_buf_size, options.send_buf_size)),
+            waiter: SocketWaiter::new(),
+        }
+    }
+
+    /// Returns the local address and the ICMP identifier as the port, or
+    /// [`Err(NotConnected)`](AxError::NotConnected) if not bound.
+    pub fn local_addr(&self) -> AxResult<SocketAddr> {
+        match *self.local_addr.read() {
+            Some((addr, ident)) => Ok(SocketAddr::new(into_core_ipaddr(addr), ident)),
+            None => Err(AxError::NotConnected),
+        }
+    }
+
+    /// Returns whether this socket is in nonblocking mode.
+    #[inline]
+    pub fn is_nonblocking(&self) -> bool {
+        self.nonblock.load(Ordering::Acquire)
+    }
+
+    /// Moves this ICMP socket into or out of nonblocking mode.
+    ///
+    /// This will result in `recv_from` and `send_to` operations becoming
+    /// nonblocking, i.e., immediately returning from their calls. If the IO
+    /// operation could not be completed and needs to be retried, an error
+    /// with kind [`Err(WouldBlock)`](AxError::WouldBlock) is returned.
+    #[inline]
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblock.store(nonblocking, Ordering::Release);
+    }
+
+    /// Returns the options of this socket.
+    pub fn options(&self) -> SocketOptions {
+        *self.options.lock()
+    }
+
+    /// Sets the options of this socket.
+    ///
+    /// See [`SocketOptions`] for when each option takes effect.
+    pub fn set_options(&self, options: SocketOptions) -> AxResult {
+        let options = options.validate()?;
+        *self.options.lock() = options;
+        for handle in self.sockets.read().iter() {
+            handle.with_mut::<icmp::Socket, _, _>(|socket| options.apply_icmp(socket));
+        }
+        self.resize_buffers();
+        Ok(())
+    }
+
+    /// Returns the sizes of the receive and send buffers.
+    ///
+    /// They differ from the sizes in the options until the buffers are
+    /// reallocated, see [`SocketOptions::recv_buf_size`].
+    pub fn buf_sizes(&self) -> (usize, usize) {
+        *self.buf_sizes.lock()
+    }
+
+    /// Binds an unbound socket to the given address, using its port as the
+    /// ICMP identifier.
+    ///
+    /// A zero port picks an unused identifier. It's called automatically by
+    /// the first [`send_to`](Self::send_to).
+    pub fn bind(&self, local_addr: SocketAddr) -> AxResult {
+        let mut self_local_addr = self.local_addr.write();
+        if self_local_addr.is_some() {
+            return ax_err!(InvalidInput, "socket bind() failed: already bound");
+        }
+
+        let local_endpoint = from_core_sockaddr(local_addr);
+        let addr = local_endpoint.addr;
+        let ifaces = if is_unspecified(addr) {
+            0..INTERFACES.len()
+        } else {
+            if addr.version() != self.version {
+                return ax_err!(InvalidInput, "socket bind() failed: invalid address");
+            }
+            let idx = iface::iface_of_addr(addr)
+                .ok_or_else(|| ax_err_type!(InvalidInput, "socket bind() failed"))?;
+            idx..idx + 1
+        };
+
+        let ident = claim_ident(local_endpoint.port)?;
+        let options = self.options();
+        let mut sockets = self.sockets.write();
+        for idx in ifaces {
+            let handle = SocketRef::add(idx, SocketSetWrapper::new_icmp_socket(&options));
+            sockets.push(handle);
+            handle
+                .with_mut::<icmp::Socket, _, _>(|socket| {
+                    socket
+                        .bind(icmp::Endpoint::Ident(ident))
+                        .or_else(|e| match e {
+                            BindError::InvalidState => {
+                                ax_err!(AlreadyExists, "socket bind() failed")
+                            }
+                            BindError::Unaddressable => {
+                                ax_err!(InvalidInput, "socket bind() failed")
+                            }
+                        })
+                })
+                .inspect_err(|_| {
+                    sockets.drain(..).for_each(SocketRef::remove);
+                    BOUND_IDENTS.lock().remove(&ident);
+                })?;
+            debug!("ICMP socket {}: bound with ident {}", handle, ident);
+        }
+
+        *self_local_addr = Some((addr, ident));
+        Ok(())
+    }
+
+    /// Sends an ICMP echo request to the given address. On success, returns
+    /// the number of bytes written.
+    ///
+    /// `buf` holds the whole ICMP message, starting with the echo header. Its
+    /// identifier is replaced by the one the socket is bound to, and its
+    /// checksum is computed by the stack.
+    pub fn send_to(&self, buf: &[u8], remote_addr: IpAddr) -> AxResult<usize> {
+        let remote_addr = from_core_ipaddr(remote_addr);
+        if remote_addr.version() != self.version || is_unspecified(remote_addr) {
+            return ax_err!(InvalidInput, "socket send_to() failed: invalid address");
+        }
+        if !is_echo_request(self.version, buf) {
+            return ax_err!(InvalidInput, "socket send_to() failed: not an echo request");
+        }
+
+        if self.local_addr.read().is_none() {
+            let unspecified = match self.version {
+                IpVersion::Ipv4 => IpAddr::from([0; 4]),
+                IpVersion::Ipv6 => IpAddr::from([0; 16]),
+            };
+            self.bind(SocketAddr::new(unspecified, 0))?;
+        }
+        let (local_addr, ident) = self.local_addr.read().unwrap();
+        self.resize_buffers();
+        let iface_idx = iface::select(local_addr, remote_addr)?;
+        let handle = {
+            let sockets = self.sockets.read();
+            sockets
+                .iter()
+                .find(|handle| handle.iface == iface_idx)
+                .or(sockets.first())
+                .copied()
+                .ok_or_else(|| ax_err_type!(NotConnected, "socket send_to() failed"))?
+        };
+
+        self.block_on(self.options().send_timeout, || {
+            handle.with_mut::<icmp::Socket, _, _>(|socket| {
+                if socket.can_send() {
+                    match socket.send(buf.len(), remote_addr) {
+                        Ok(packet) => {
+                            fill_echo_request(packet, buf, ident);
+                            return Ok(buf.len());
+                        }
+                        Err(SendError::Unaddressable) => {
+                            return ax_err!(ConnectionRefused, "socket send_to() failed");
+                        }
+                        Err(SendError::BufferFull) => {}
+                    }
+                }
+                // tx buffer is full
+                socket.register_send_waker(self.waiter.waker());
+                Err(AxError::WouldBlock)
+            })
+        })
+    }
+
+    /// Receives a single ICMP message on the socket. On success, returns the
+    /// number of bytes read and the origin.
+    ///
+    /// The message is truncated if `buf` is too small.
+    pub fn recv_from(&self, buf: &mut [u8]) -> AxResult<(usize, IpAddr)> {
+        if self.local_addr.read().is_none() {
+            return ax_err!(NotConnected, "socket recv_from() failed");
+        }
+        self.resize_buffers();
+
+        self.block_on(self.options().recv_timeout, || {
+            for handle in self.sockets.read().iter() {
+                let res = handle.with_mut::<icmp::Socket, _, _>(|socket| {
+                    while let Ok((packet, addr)) = socket.recv() {
+                        // the smoltcp socket receives messages of both versions
+                        if addr.version() == self.version {
+                            let len = packet.len().min(buf.len());
+                            buf[..len].copy_from_slice(&packet[..len]);
+                            return Some((len, into_core_ipaddr(addr)));
+                        }
+                    }
+                    socket.register_recv_waker(self.waiter.waker());
+                    None
+                });
+                if let Some(res) = res {
+                    return Ok(res);
+                }
+            }
+            // no more data
+            Err(AxError::WouldBlock)
+        })
+    }
+
+    /// Whether the socket is readable or writable.
+    pub fn poll(&self) -> AxResult<PollState> {
+        let mut state = PollState {
+            readable: false,
+            // sending binds the socket automatically
+            writable: self.local_addr.read().is_none(),
+        };
+        for handle in self.sockets.read().iter() {
+            handle.with::<icmp::Socket, _, _>(|socket| {
+                state.readable |= socket.can_recv();
+                state.writable |= socket.can_send();
+            });
+        }
+        Ok(state)
+    }
+}
+
+/// Private methods
+impl IcmpSocket {
+    /// Reallocates the buffers of the smoltcp sockets if their sizes differ
+    /// from the options.
+    ///
+    /// It is done only when no message is queued in any of them, otherwise it
+    /// is retried by the next operation.
+    fn resize_buffers(&self) {
+        let options = self.options();
+        let sizes = (options.recv_buf_size, options.send_buf_size);
+        let local_addr = *self.local_addr.read();
+        let mut buf_sizes = self.buf_sizes.lock();
+        let sockets = self.sockets.read();
+        let Some((_, ident)) = local_addr else {
+            // allocated with these sizes when bound
+            *buf_sizes = sizes;
+            return;
+        };
+        if *buf_sizes == sizes {
+            return;
+        }
+        let idle = sockets.iter().all(|handle| {
+            handle.with::<icmp::Socket, _, _>(|socket| {
+                socket.recv_queue() == 0 && socket.send_queue() == 0
+            })
+        });
+        if !idle {
+            return;
+        }
+        for handle in sockets.iter() {
+            handle.with_mut::<icmp::Socket, _, _>(|socket| {
+                let mut new_socket = SocketSetWrapper::new_icmp_socket(&options);
+                // the identifier is valid, as the old socket is bound to it
+                new_socket.bind(icmp::Endpoint::Ident(ident)).ok();
+                *socket = new_socket;
+            });
+        }
+        *buf_sizes = sizes;
+        debug!("ICMP socket: buffers resized to {:?}", sizes);
+        // let the blocked operations and the watchers register their wakers
+        // on the new sockets
+        self.waiter.waker().wake_by_ref();
+    }
+
+    fn block_on<F, T>(&self, timeout: Option<Duration>, f: F) -> AxResult<T>
+    where
+        F: FnMut() -> AxResult<T>,
+    {
+        self.waiter.block_on(self.is_nonblocking(), timeout, f)
+    }
+}
+
+impl Drop for IcmpSocket {
+    fn drop(&mut self) {
+        self.sockets.get_mut().drain(..).for_each(SocketRef::remove);
+        if let Some((_, ident)) = *self.local_addr.get_mut() {
+            BOUND_IDENTS.lock().remove(&ident);
+        }
+    }
+}
+
+/// Whether `buf` holds an ICMP echo request of the given IP version.
+fn is_echo_request(version: IpVersion, buf: &[u8]) -> bool {
+    let echo_request = match version {
+        IpVersion::Ipv4 => ICMPV4_ECHO_REQUEST,
+        IpVersion::Ipv6 => ICMPV6_ECHO_REQUEST,
+    };
+    buf.len() >= ICMP_ECHO_HEADER_LEN && buf[0] == echo_request && buf[1] == 0
+}
+
+/// Copies the echo request `buf` to `packet`, replacing its identifier with
+/// `ident` and clearing its checksum, which is computed by the stack.
+fn fill_echo_request(packet: &mut [u8], buf: &[u8], ident: u16) {
+    packet.copy_from_slice(buf);
+    packet[2..4].fill(0); // checksum
+    packet[4..6].copy_from_slice(&ident.to_be_bytes());
+}
+
+/// Records that a socket is bound to the ICMP identifier `ident`, or to an
+/// unused one if `ident` is zero.
+fn claim_ident(ident: u16) -> AxResult<u16> {
+    const IDENT_START: u16 = 0xc000;
+    const IDENT_END: u16 = 0xffff;
+    static CURR: Mutex<u16> = Mutex::new(IDENT_START);
+
+    let mut idents = BOUND_IDENTS.lock();
+    if ident != 0 {
+        if !idents.insert(ident) {
+            return ax_err!(AddrInUse, "socket bind() failed");
+        }
+        return Ok(ident);
+    }
+    let mut curr = CURR.lock();
+    for _ in IDENT_START..=IDENT_END {
+        let ident = *curr;
+        *curr = if ident == IDENT_END {
+            IDENT_START
+        } else {
+            ident + 1
+        };
+        if idents.insert(ident) {
+            return Ok(ident);
+        }
+    }
+    ax_err!(AddrInUse, "no available ICMP identifiers")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An echo request with the given type, identifier 0x1234, sequence
+    /// number 1, and a payload.
+    fn echo(ty: u8) -> Vec<u8> {
+        let mut msg = alloc::vec![ty, 0, 0xab, 0xcd, 0x12, 0x34, 0, 1];
+        msg.extend_from_slice(b"ping");
+        msg
+    }
+
+    #[test]
+    fn test_is_echo_request() {
+        assert!(is_echo_request(IpVersion::Ipv4, &echo(8)));
+        assert!(is_echo_request(IpVersion::Ipv6, &echo(128)));
+        // the type of the other version
+        assert!(!is_echo_request(IpVersion::Ipv4, &echo(128)));
+        assert!(!is_echo_request(IpVersion::Ipv6, &echo(8)));
+        // echo replies
+        assert!(!is_echo_request(IpVersion::Ipv4, &echo(0)));
+        assert!(!is_echo_request(IpVersion::Ipv6, &echo(129)));
+        // a non-zero code
+        let mut msg = echo(8);
+        msg[1] = 1;
+        assert!(!is_echo_request(IpVersion::Ipv4, &msg));
+        // the header only, and a truncated one
+        assert!(is_echo_request(IpVersion::Ipv4, &echo(8)[..8]));
+        assert!(!is_echo_request(IpVersion::Ipv4, &echo(8)[..7]));
+        assert!(!is_echo_request(IpVersion::Ipv4, &[]));
+    }
+
+    #[test]
+    fn test_fill_echo_request() {
+        let msg = echo(8);
+        let mut packet = alloc::vec![0xff; msg.len()];
+        fill_echo_request(&mut packet, &msg, 0xc001);
+        // the type, code, sequence number and payload are kept
+        assert_eq!(&packet[..2], &[8, 0]);
+        assert_eq!(&packet[2..4], &[0, 0]);
+        assert_eq!(&packet[4..6], &[0xc0, 0x01]);
+        assert_eq!(&packet[6..], &msg[6..]);
+    }
+}
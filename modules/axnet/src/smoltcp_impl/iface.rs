@@ -0,0 +1,326 @@
+//! Network interface management and egress routing.
+
+use alloc::{string::String, vec::Vec};
+use core::net::{IpAddr, Ipv4Addr};
+use core::time::Duration;
+
+use axerrno::{AxResult, ax_err, ax_err_type};
+use smoltcp::iface::Interface;
+use smoltcp::wire::{IpAddress, IpCidr, IpVersion};
+
+use super::INTERFACES;
+use super::addr::{from_core_ipaddr, into_core_ipaddr, is_unspecified};
+
+/// Information about a network interface.
+#[derive(Debug, Clone)]
+pub struct InterfaceInfo {
+    /// The interface name, e.g., `eth0`.
+    pub name: String,
+    /// The hardware (MAC) address, all zeros for the loopback interface.
+    pub mac_addr: [u8; 6],
+    /// The IP addresses assigned to the interface, with their prefix lengths.
+    pub ip_addrs: Vec<(IpAddr, u8)>,
+    /// The default IPv4 gateway, if the IPv4 default route goes through this
+    /// interface.
+    pub gateway: Option<IpAddr>,
+    /// The default IPv6 gateway, if the IPv6 default route goes through this
+    /// interface.
+    pub gateway6: Option<IpAddr>,
+}
+
+/// An IPv4 configuration leased from a DHCP server.
+#[derive(Debug, Clone)]
+pub struct DhcpLease {
+    /// The leased address.
+    pub addr: Ipv4Addr,
+    /// The prefix length of the subnet.
+    pub prefix_len: u8,
+    /// The router (default gateway), if the server provided one.
+    pub router: Option<Ipv4Addr>,
+    /// The DNS servers provided by the server.
+    pub dns_servers: Vec<Ipv4Addr>,
+    /// The address of the DHCP server.
+    pub server: Ipv4Addr,
+    /// The monotonic time when the lease was acquired or last renewed.
+    pub acquired_at: Duration,
+}
+
+/// Returns the default gateway of the given IP version configured on the
+/// interface.
+pub(super) fn default_gateway_of(iface: &mut Interface, version: IpVersion) -> Option<IpAddress> {
+    let mut gateway = None;
+    // `Routes` has no getter, so inspect the route list in place.
+    iface.routes_mut().update(|routes| {
+        gateway = routes
+            .iter()
+            .find(|r| r.cidr.prefix_len() == 0 && r.via_router.version() == version)
+            .map(|r| r.via_router);
+    });
+    gateway
+}
+
+/// Whether the address is an IPv6 link-local address, which is managed by
+/// SLAAC and kept when the addresses of the interface are changed.
+fn is_link_local(cidr: &IpCidr) -> bool {
+    matches!(cidr, IpCidr::Ipv6(cidr) if cidr.address().is_link_local())
+}
+
+fn find_iface(name: &str) -> AxResult<usize> {
+    INTERFACES
+        .iter()
+        .position(|iface| iface.name() == name)
+        .ok_or_else(|| ax_err_type!(NotFound, "no such network interface"))
+}
+
+/// Returns the index of the interface that owns the given local address.
+pub(super) fn iface_of_addr(addr: IpAddress) -> Option<usize> {
+    INTERFACES
+        .iter()
+        .position(|iface| iface.iface.lock().has_ip_addr(addr))
+}
+
+/// Selects the source address of the interface for packets sent to `dst`.
+///
+/// An address in the same subnet as `dst` is preferred. IPv6 link-local
+/// addresses are only used for link-local destinations.
+pub(super) fn source_addr(iface: usize, dst: IpAddress) -> Option<IpAddress> {
+    let iface = INTERFACES[iface].iface.lock();
+    let candidates = || {
+        iface.ip_addrs().iter().filter(|cidr| {
+            cidr.address().version() == dst.version()
+                && is_link_local(cidr) == matches!(dst, IpAddress::Ipv6(v6) if v6.is_link_local())
+        })
+    };
+    candidates()
+        .find(|cidr| cidr.contains_addr(&dst))
+        .or_else(|| candidates().next())
+        .map(|cidr| cidr.address())
+}
+
+/// Selects the egress interface for the given destination.
+///
+/// The interface whose subnet contains `dst` with the longest prefix is
+/// chosen. If no subnet matches, the interface that holds the default route
+/// of the same IP version is chosen.
+pub(super) fn route(dst: IpAddress) -> AxResult<usize> {
+    let routes = INTERFACES.iter().map(|iface| {
+        let mut iface = iface.iface.lock();
+        let has_default = default_gateway_of(&mut iface, dst.version()).is_some();
+        (Vec::from(iface.ip_addrs()), has_default)
+    });
+    select_route(dst, routes)
+        .ok_or_else(|| ax_err_type!(ConnectionRefused, "network is unreachable"))
+}
+
+/// Picks the egress interface for `dst` from the addresses of each interface,
+/// and whether it holds the default route of the same IP version as `dst`.
+fn select_route(
+    dst: IpAddress,
+    ifaces: impl IntoIterator<Item = (Vec<IpCidr>, bool)>,
+) -> Option<usize> {
+    let mut best: Option<(usize, u8)> = None;
+    let mut default = None;
+    for (idx, (ip_addrs, has_default)) in ifaces.into_iter().enumerate() {
+        if ip_addrs.iter().any(|cidr| cidr.address() == dst) {
+            // sending to ourselves
+            return Some(idx);
+        }
+        for cidr in &ip_addrs {
+            if cidr.contains_addr(&dst) && best.is_none_or(|(_, len)| cidr.prefix_len() > len) {
+                best = Some((idx, cidr.prefix_len()));
+            }
+        }
+        if default.is_none() && has_default {
+            default = Some(idx);
+        }
+    }
+    best.map(|(idx, _)| idx).or(default)
+}
+
+/// Selects the interface for a socket that is bound to `local` and sends
+/// packets to `remote`.
+///
+/// A socket bound to a specific address always uses the interface owning that
+/// address, otherwise the route to `remote` decides.
+pub(super) fn select(local: IpAddress, remote: IpAddress) -> AxResult<usize> {
+    if is_unspecified(local) {
+        route(remote)
+    } else {
+        iface_of_addr(local).ok_or_else(|| ax_err_type!(InvalidInput, "address is not available"))
+    }
+}
+
+/// Returns information about all network interfaces.
+pub fn interfaces() -> Vec<InterfaceInfo> {
+    INTERFACES
+        .iter()
+        .map(|iface| {
+            let mut inner = iface.iface.lock();
+            InterfaceInfo {
+                name: iface.name().into(),
+                mac_addr: iface.ethernet_address().map_or([0; 6], |addr| addr.0),
+                ip_addrs: inner
+                    .ip_addrs()
+                    .iter()
+                    .map(|cidr| (into_core_ipaddr(cidr.address()), cidr.prefix_len()))
+                    .collect(),
+                gateway: default_gateway_of(&mut inner, IpVersion::Ipv4).map(into_core_ipaddr),
+                gateway6: default_gateway_of(&mut inner, IpVersion::Ipv6).map(into_core_ipaddr),
+            }
+        })
+        .collect()
+}
+
+/// Assigns an IP address with the given prefix length to the interface.
+///
+/// Existing addresses of the same family are replaced, except the IPv6
+/// link-local address.
+pub fn set_ip_addr(name: &str, addr: IpAddr, prefix_len: u8) -> AxResult {
+    let ip = from_core_ipaddr(addr);
+    let max_prefix_len = match ip {
+        IpAddress::Ipv4(_) => 32,
+        IpAddress::Ipv6(_) => 128,
+    };
+    if prefix_len > max_prefix_len || is_unspecified(ip) || ip.is_multicast() {
+        return ax_err!(InvalidInput, "invalid interface address");
+    }
+    let iface = &INTERFACES[find_iface(name)?];
+    let mut res = Ok(());
+    iface.iface.lock().update_ip_addrs(|ip_addrs| {
+        ip_addrs.retain(|cidr| cidr.address().version() != ip.version() || is_link_local(cidr));
+        res = ip_addrs
+            .push(IpCidr::new(ip, prefix_len))
+            .map_err(|_| ax_err_type!(NoMemory, "too many IP addresses"));
+    });
+    res?;
+    info!("{}: set ip address {}/{}", name, addr, prefix_len);
+    Ok(())
+}
+
+/// Removes all IP addresses from the interface, except the IPv6 link-local
+/// address.
+pub fn clear_ip_addrs(name: &str) -> AxResult {
+    let iface = &INTERFACES[find_iface(name)?];
+    iface
+        .iface
+        .lock()
+        .update_ip_addrs(|ip_addrs| ip_addrs.retain(is_link_local));
+    info!("{}: cleared ip addresses", name);
+    Ok(())
+}
+
+/// Sets the default gateway, which is reached through the given interface.
+///
+/// The default route of the same IP version is removed from all other
+/// interfaces.
+pub fn set_default_gateway(name: &str, gateway: IpAddr) -> AxResult {
+    let idx = find_iface(name)?;
+    let gateway = from_core_ipaddr(gateway);
+    if is_unspecified(gateway) || gateway.is_multicast() {
+        return ax_err!(InvalidInput, "invalid gateway address");
+    }
+    for (i, iface) in INTERFACES.iter().enumerate() {
+        let mut inner = iface.iface.lock();
+        let routes = inner.routes_mut();
+        if i == idx {
+            match gateway {
+                IpAddress::Ipv4(v4) => routes.add_default_ipv4_route(v4).map(|_| ()),
+                IpAddress::Ipv6(v6) => routes.add_default_ipv6_route(v6).map(|_| ()),
+            }
+            .map_err(|_| ax_err_type!(NoMemory, "routing table is full"))?;
+        } else {
+            match gateway {
+                IpAddress::Ipv4(_) => routes.remove_default_ipv4_route(),
+                IpAddress::Ipv6(_) => routes.remove_default_ipv6_route(),
+            };
+        }
+    }
+    info!("{}: set default gateway {}", name, gateway);
+    Ok(())
+}
+
+/// Removes the default gateways of both IP versions.
+pub fn clear_default_gateway() -> AxResult {
+    for iface in INTERFACES.iter() {
+        let mut inner = iface.iface.lock();
+        inner.routes_mut().remove_default_ipv4_route();
+        inner.routes_mut().remove_default_ipv6_route();
+    }
+    info!("cleared default gateway");
+    Ok(())
+}
+
+/// Returns the current DHCP lease of the interface.
+///
+/// Returns `None` if the interface is not configured by DHCP, or DHCP is not
+/// enabled by the `dhcp` feature.
+pub fn dhcp_lease(name: &str) -> AxResult<Option<DhcpLease>> {
+    let _iface = &INTERFACES[find_iface(name)?];
+    #[cfg(feature = "dhcp")]
+    return Ok(_iface.dhcp.lease());
+    #[cfg(not(feature = "dhcp"))]
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn cidr(s: &str) -> IpCidr {
+        s.parse().unwrap()
+    }
+
+    fn addr(s: &str) -> IpAddress {
+        s.parse().unwrap()
+    }
+
+    /// The loopback interface, and two NICs where the first one holds the
+    /// default route.
+    fn ifaces() -> Vec<(Vec<IpCidr>, bool)> {
+        vec![
+            (vec![cidr("127.0.0.1/8"), cidr("::1/128")], false),
+            (vec![cidr("10.0.2.15/24"), cidr("fe80::1/64")], true),
+            (vec![cidr("10.0.0.1/16"), cidr("192.168.1.2/24")], false),
+        ]
+    }
+
+    #[test]
+    fn test_route_local() {
+        assert_eq!(select_route(addr("127.0.0.1"), ifaces()), Some(0));
+        assert_eq!(select_route(addr("127.1.2.3"), ifaces()), Some(0));
+        assert_eq!(select_route(addr("::1"), ifaces()), Some(0));
+        // an address of a NIC is reached on that NIC, even if another subnet
+        // matches it with a longer prefix
+        assert_eq!(select_route(addr("10.0.0.1"), ifaces()), Some(2));
+        assert_eq!(select_route(addr("10.0.2.15"), ifaces()), Some(1));
+    }
+
+    #[test]
+    fn test_route_longest_prefix() {
+        assert_eq!(select_route(addr("10.0.2.2"), ifaces()), Some(1));
+        assert_eq!(select_route(addr("10.0.3.2"), ifaces()), Some(2));
+        assert_eq!(select_route(addr("192.168.1.1"), ifaces()), Some(2));
+        assert_eq!(select_route(addr("fe80::2"), ifaces()), Some(1));
+    }
+
+    #[test]
+    fn test_route_default() {
+        assert_eq!(select_route(addr("8.8.8.8"), ifaces()), Some(1));
+        let mut ifaces = ifaces();
+        ifaces[1].1 = false;
+        ifaces[2].1 = true;
+        assert_eq!(select_route(addr("8.8.8.8"), ifaces.clone()), Some(2));
+        // the subnet still wins over the default route
+        assert_eq!(select_route(addr("10.0.2.2"), ifaces), Some(1));
+    }
+
+    #[test]
+    fn test_route_unreachable() {
+        let mut ifaces = ifaces();
+        ifaces[1].1 = false;
+        assert_eq!(select_route(addr("8.8.8.8"), ifaces.clone()), None);
+        assert_eq!(select_route(addr("2001:db8::1"), ifaces), None);
+        assert_eq!(select_route(addr("8.8.8.8"), []), None);
+    }
+}
@@ -1,26 +1,34 @@
 use alloc::{boxed::Box, collections::VecDeque};
 use core::ops::{Deref, DerefMut};
+use core::task::Waker;
 
 use axerrno::{AxError, AxResult, ax_err};
 use axsync::Mutex;
-use smoltcp::iface::{SocketHandle, SocketSet};
+use smoltcp::iface::SocketSet;
 use smoltcp::socket::tcp::{self, State};
 use smoltcp::wire::{IpAddress, IpEndpoint, IpListenEndpoint};
 
-use super::{LISTEN_QUEUE_SIZE, SOCKET_SET, SocketSetWrapper};
+use super::{LISTEN_QUEUE_SIZE, SocketOptions, SocketRef, SocketSetWrapper};
 
 const PORT_NUM: usize = 65536;
 
 struct ListenTableEntry {
     listen_endpoint: IpListenEndpoint,
-    syn_queue: VecDeque<SocketHandle>,
+    /// The options of the listening socket, inherited by new connections.
+    options: SocketOptions,
+    syn_queue: VecDeque<SocketRef>,
+    /// The waker of the task waiting in `accept()`, registered on new
+    /// connections.
+    waker: Option<Waker>,
 }
 
 impl ListenTableEntry {
-    pub fn new(listen_endpoint: IpListenEndpoint) -> Self {
+    pub fn new(listen_endpoint: IpListenEndpoint, options: SocketOptions) -> Self {
         Self {
             listen_endpoint,
+            options,
             syn_queue: VecDeque::with_capacity(LISTEN_QUEUE_SIZE),
+            waker: None,
         }
     }
 
@@ -36,7 +44,7 @@ impl ListenTableEntry {
 impl Drop for ListenTableEntry {
     fn drop(&mut self) {
         for &handle in &self.syn_queue {
-            SOCKET_SET.remove(handle);
+            handle.remove();
         }
     }
 }
@@ -61,23 +69,43 @@ impl ListenTable {
         self.tcp[port as usize].lock().is_none()
     }
 
-    pub fn listen(&self, listen_endpoint: IpListenEndpoint) -> AxResult {
+    pub fn listen(&self, listen_endpoint: IpListenEndpoint, options: SocketOptions) -> AxResult {
         let port = listen_endpoint.port;
         assert_ne!(port, 0);
         let mut entry = self.tcp[port as usize].lock();
         if entry.is_none() {
-            *entry = Some(Box::new(ListenTableEntry::new(listen_endpoint)));
+            *entry = Some(Box::new(ListenTableEntry::new(listen_endpoint, options)));
             Ok(())
         } else {
             ax_err!(AddrInUse, "socket listen() failed")
         }
     }
 
+    /// Returns the endpoint of each listening port, and the number of the
+    /// connections that are not accepted yet.
+    #[cfg(feature = "fs")]
+    pub fn listeners(&self) -> alloc::vec::Vec<(IpListenEndpoint, usize)> {
+        self.tcp
+            .iter()
+            .filter_map(|entry| {
+                let entry = entry.lock();
+                let entry = entry.as_ref()?;
+                Some((entry.listen_endpoint, entry.syn_queue.len()))
+            })
+            .collect()
+    }
+
     pub fn unlisten(&self, port: u16) {
         debug!("TCP socket unlisten on {}", port);
         *self.tcp[port as usize].lock() = None;
     }
 
+    pub fn set_options(&self, port: u16, options: SocketOptions) {
+        if let Some(entry) = self.tcp[port as usize].lock().deref_mut() {
+            entry.options = options;
+        }
+    }
+
     pub fn can_accept(&self, port: u16) -> AxResult<bool> {
         if let Some(entry) = self.tcp[port as usize].lock().deref() {
             Ok(entry.syn_queue.iter().any(|&handle| is_connected(handle)))
@@ -86,7 +114,18 @@ impl ListenTable {
         }
     }
 
-    pub fn accept(&self, port: u16) -> AxResult<(SocketHandle, (IpEndpoint, IpEndpoint))> {
+    /// Registers a waker that is woken when a connection on the port may
+    /// become ready to accept.
+    pub fn register_waker(&self, port: u16, waker: &Waker) {
+        if let Some(entry) = self.tcp[port as usize].lock().deref_mut() {
+            for &handle in &entry.syn_queue {
+                handle.with_mut::<tcp::Socket, _, _>(|socket| socket.register_recv_waker(waker));
+            }
+            entry.waker = Some(waker.clone());
+        }
+    }
+
+    pub fn accept(&self, port: u16) -> AxResult<(SocketRef, (IpEndpoint, IpEndpoint))> {
         if let Some(entry) = self.tcp[port as usize].lock().deref_mut() {
             let syn_queue = &mut entry.syn_queue;
             let (idx, addr_tuple) = syn_queue
@@ -114,6 +153,7 @@ impl ListenTable {
         &self,
         src: IpEndpoint,
         dst: IpEndpoint,
+        iface: usize,
         sockets: &mut SocketSet<'_>,
     ) {
         if let Some(entry) = self.tcp[dst.port as usize].lock().deref_mut() {
@@ -126,9 +166,15 @@ impl ListenTable {
                 warn!("SYN queue overflow!");
                 return;
             }
-            let mut socket = SocketSetWrapper::new_tcp_socket();
+            let mut socket = SocketSetWrapper::new_tcp_socket(&entry.options);
+            if let Some(waker) = &entry.waker {
+                socket.register_recv_waker(waker);
+            }
             if socket.listen(entry.listen_endpoint).is_ok() {
-                let handle = sockets.add(socket);
+                let handle = SocketRef {
+                    iface,
+                    handle: sockets.add(socket),
+                };
                 debug!(
                     "TCP socket {}: prepare for connection {} -> {}",
                     handle, src, entry.listen_endpoint
@@ -139,14 +185,14 @@ impl ListenTable {
     }
 }
 
-fn is_connected(handle: SocketHandle) -> bool {
-    SOCKET_SET.with_socket::<tcp::Socket, _, _>(handle, |socket| {
+fn is_connected(handle: SocketRef) -> bool {
+    handle.with::<tcp::Socket, _, _>(|socket| {
         !matches!(socket.state(), State::Listen | State::SynReceived)
     })
 }
 
-fn get_addr_tuple(handle: SocketHandle) -> (IpEndpoint, IpEndpoint) {
-    SOCKET_SET.with_socket::<tcp::Socket, _, _>(handle, |socket| {
+fn get_addr_tuple(handle: SocketRef) -> (IpEndpoint, IpEndpoint) {
+    handle.with::<tcp::Socket, _, _>(|socket| {
         (
             socket.local_endpoint().unwrap(),
             socket.remote_endpoint().unwrap(),
@@ -0,0 +1,171 @@
+//! The loopback interface `lo`.
+//!
+//! It is always present, even without any NIC, and owns `127.0.0.1/8` and
+//! `::1/128`. Packets sent through it are queued in memory and received by
+//! the same interface in the next round of polling.
+
+use alloc::{collections::VecDeque, vec, vec::Vec};
+
+use smoltcp::iface::SocketSet;
+use smoltcp::phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken};
+use smoltcp::time::Instant;
+use smoltcp::wire::{IpAddress, IpCidr};
+
+use super::snoop_ip_packet;
+
+/// The name of the loopback interface.
+pub(super) const LOOPBACK_NAME: &str = "lo";
+
+/// The MTU of the loopback interface, the maximum size of an IP packet.
+pub(super) const LOOPBACK_MTU: usize = 65535;
+
+/// Returns the addresses of the loopback interface.
+pub(super) fn loopback_addrs() -> [IpCidr; 2] {
+    [
+        IpCidr::new(IpAddress::v4(127, 0, 0, 1), 8),
+        IpCidr::new(IpAddress::v6(0, 0, 0, 0, 0, 0, 0, 1), 128),
+    ]
+}
+
+/// A device that receives the IP packets it transmits.
+pub(super) struct LoopbackDev {
+    queue: VecDeque<Vec<u8>>,
+    iface: usize,
+}
+
+impl LoopbackDev {
+    pub fn new(iface: usize) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            iface,
+        }
+    }
+}
+
+impl Device for LoopbackDev {
+    type RxToken<'a>
+        = LoopbackRxToken
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = LoopbackTxToken<'a>
+    where
+        Self: 'a;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let buf = self.queue.pop_front()?;
+        Some((
+            LoopbackRxToken(buf, self.iface),
+            LoopbackTxToken(&mut self.queue),
+        ))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(LoopbackTxToken(&mut self.queue))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.max_transmission_unit = LOOPBACK_MTU;
+        caps.max_burst_size = None;
+        caps.medium = Medium::Ip;
+        caps
+    }
+}
+
+pub(super) struct LoopbackRxToken(Vec<u8>, usize);
+pub(super) struct LoopbackTxToken<'a>(&'a mut VecDeque<Vec<u8>>);
+
+impl RxToken for LoopbackRxToken {
+    fn preprocess(&self, sockets: &mut SocketSet<'_>) {
+        snoop_ip_packet(&self.0, self.1, sockets).ok();
+    }
+
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        trace!("LOOPBACK RECV {} bytes", self.0.len());
+        f(&mut self.0)
+    }
+}
+
+impl TxToken for LoopbackTxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut buf = vec![0; len];
+        let ret = f(&mut buf);
+        trace!("LOOPBACK SEND {} bytes", len);
+        self.0.push_back(buf);
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smoltcp::iface::{Config, Interface};
+    use smoltcp::socket::udp;
+    use smoltcp::wire::{HardwareAddress, IpEndpoint};
+
+    use super::*;
+
+    fn udp_socket(port: u16) -> udp::Socket<'static> {
+        let buffer = || udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 1024]);
+        let mut socket = udp::Socket::new(buffer(), buffer());
+        socket.bind(port).unwrap();
+        socket
+    }
+
+    #[test]
+    fn test_queue() {
+        let mut dev = LoopbackDev::new(0);
+        let now = Instant::ZERO;
+        assert!(dev.receive(now).is_none());
+        let tx = dev.transmit(now).unwrap();
+        tx.consume(3, |buf| buf.copy_from_slice(b"abc"));
+        let tx = dev.transmit(now).unwrap();
+        tx.consume(2, |buf| buf.copy_from_slice(b"de"));
+
+        // received in the order they were sent
+        let (rx, _) = dev.receive(now).unwrap();
+        assert_eq!(rx.consume(|buf| buf.to_vec()), b"abc");
+        let (rx, _) = dev.receive(now).unwrap();
+        assert_eq!(rx.consume(|buf| buf.to_vec()), b"de");
+        assert!(dev.receive(now).is_none());
+    }
+
+    #[test]
+    fn test_udp_delivery() {
+        let now = Instant::ZERO;
+        let mut dev = LoopbackDev::new(0);
+        let mut iface = Interface::new(Config::new(HardwareAddress::Ip), &mut dev, now);
+        iface.update_ip_addrs(|ip_addrs| {
+            for cidr in loopback_addrs() {
+                ip_addrs.push(cidr).unwrap();
+            }
+        });
+        let mut sockets = SocketSet::new(vec![]);
+        let server = sockets.add(udp_socket(5000));
+        let client = sockets.add(udp_socket(5001));
+
+        for addr in loopback_addrs().map(|cidr| cidr.address()) {
+            let client_socket = sockets.get_mut::<udp::Socket>(client);
+            client_socket
+                .send_slice(b"ping", IpEndpoint::new(addr, 5000))
+                .unwrap();
+            // sent by the first poll, and received by the second one at the
+            // latest
+            iface.poll(now, &mut dev, &mut sockets);
+            iface.poll(now, &mut dev, &mut sockets);
+
+            let mut buf = [0; 16];
+            let server_socket = sockets.get_mut::<udp::Socket>(server);
+            let (len, meta) = server_socket.recv_slice(&mut buf).unwrap();
+            assert_eq!(&buf[..len], b"ping");
+            assert_eq!(meta.endpoint, IpEndpoint::new(addr, 5001));
+            assert!(dev.queue.is_empty());
+        }
+    }
+}
@@ -1,16 +1,32 @@
 mod addr;
 mod bench;
+#[cfg(feature = "dhcp")]
+mod dhcp;
 mod dns;
+mod icmp;
+mod iface;
 mod listen_table;
+mod loopback;
+mod options;
+#[cfg(feature = "fs")]
+mod procfs;
+mod raw;
+mod slaac;
+#[cfg(feature = "fs")]
+mod sysfs;
 mod tcp;
 mod udp;
+mod wait;
 
-use alloc::vec;
+use alloc::{format, string::String, vec, vec::Vec};
 use core::cell::RefCell;
+use core::fmt;
 use core::ops::DerefMut;
+use core::time::Duration;
 
-use axdriver::prelude::*;
+use axdriver::{DeviceIrq, prelude::*};
 use axdriver_net::{DevError, NetBufPtr};
+use axerrno::{AxResult, ax_err_type};
 use axhal::time::{NANOS_PER_MICROS, wall_time_nanos};
 use axsync::Mutex;
 use lazyinit::LazyInit;
@@ -18,11 +34,23 @@ use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet};
 use smoltcp::phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken};
 use smoltcp::socket::{self, AnySocket};
 use smoltcp::time::Instant;
-use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr};
+use smoltcp::wire::{EthernetAddress, HardwareAddress, IpAddress, IpCidr, IpProtocol, IpVersion};
 
 use self::listen_table::ListenTable;
-
-pub use self::dns::dns_query;
+use self::loopback::LoopbackDev;
+
+pub use self::dns::{
+    DnsOptions, DnsRecordType, add_dns_host, clear_dns_cache, dns_options, dns_query,
+    dns_query_type, dns_reverse_query, dns_servers, remove_dns_host, set_dns_options,
+    set_dns_servers,
+};
+pub use self::icmp::IcmpSocket;
+pub use self::iface::{
+    DhcpLease, InterfaceInfo, clear_default_gateway, clear_ip_addrs, dhcp_lease, interfaces,
+    set_default_gateway, set_ip_addr,
+};
+pub use self::options::SocketOptions;
+pub use self::raw::RawSocket;
 pub use self::tcp::TcpSocket;
 pub use self::udp::UdpSocket;
 
@@ -37,7 +65,7 @@ macro_rules! env_or_default {
 
 const IP: &str = env_or_default!("AX_IP");
 const GATEWAY: &str = env_or_default!("AX_GW");
-const DNS_SEVER: &str = "8.8.8.8";
+const DNS_SERVER: &str = "8.8.8.8";
 const IP_PREFIX: u8 = 24;
 
 const STANDARD_MTU: usize = 1500;
@@ -48,23 +76,43 @@ const TCP_RX_BUF_LEN: usize = 64 * 1024;
 const TCP_TX_BUF_LEN: usize = 64 * 1024;
 const UDP_RX_BUF_LEN: usize = 64 * 1024;
 const UDP_TX_BUF_LEN: usize = 64 * 1024;
+const RAW_RX_BUF_LEN: usize = 16 * 1024;
+const RAW_TX_BUF_LEN: usize = 16 * 1024;
 const LISTEN_QUEUE_SIZE: usize = 512;
 
 static LISTEN_TABLE: LazyInit<ListenTable> = LazyInit::new();
-static SOCKET_SET: LazyInit<SocketSetWrapper> = LazyInit::new();
-static ETH0: LazyInit<InterfaceWrapper> = LazyInit::new();
+static INTERFACES: LazyInit<Vec<InterfaceWrapper>> = LazyInit::new();
 
 struct SocketSetWrapper<'a>(Mutex<SocketSet<'a>>);
 
+/// A socket in the socket set of a specific interface.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct SocketRef {
+    iface: usize,
+    handle: SocketHandle,
+}
+
 struct DeviceWrapper {
     inner: RefCell<AxNetDevice>, // use `RefCell` is enough since it's wrapped in `Mutex` in `InterfaceWrapper`.
+    iface: usize,
+}
+
+/// The device under an interface.
+enum IfaceDevice {
+    Ethernet(DeviceWrapper),
+    Loopback(LoopbackDev),
 }
 
 struct InterfaceWrapper {
-    name: &'static str,
-    ether_addr: EthernetAddress,
-    dev: Mutex<DeviceWrapper>,
+    name: String,
+    /// The MAC address, or `None` for the loopback interface.
+    ether_addr: Option<EthernetAddress>,
+    dev: Mutex<IfaceDevice>,
     iface: Mutex<Interface>,
+    sockets: SocketSetWrapper<'static>,
+    slaac: slaac::Slaac,
+    #[cfg(feature = "dhcp")]
+    dhcp: dhcp::DhcpClient,
 }
 
 impl<'a> SocketSetWrapper<'a> {
@@ -72,33 +120,60 @@ impl<'a> SocketSetWrapper<'a> {
         Self(Mutex::new(SocketSet::new(vec![])))
     }
 
-    pub fn new_tcp_socket() -> socket::tcp::Socket<'a> {
-        let tcp_rx_buffer = socket::tcp::SocketBuffer::new(vec![0; TCP_RX_BUF_LEN]);
-        let tcp_tx_buffer = socket::tcp::SocketBuffer::new(vec![0; TCP_TX_BUF_LEN]);
-        socket::tcp::Socket::new(tcp_rx_buffer, tcp_tx_buffer)
+    pub fn new_tcp_socket(options: &SocketOptions) -> socket::tcp::Socket<'a> {
+        let tcp_rx_buffer = socket::tcp::SocketBuffer::new(vec![0; options.recv_buf_size]);
+        let tcp_tx_buffer = socket::tcp::SocketBuffer::new(vec![0; options.send_buf_size]);
+        let mut socket = socket::tcp::Socket::new(tcp_rx_buffer, tcp_tx_buffer);
+        options.apply_tcp(&mut socket);
+        socket
     }
 
-    pub fn new_udp_socket() -> socket::udp::Socket<'a> {
+    pub fn new_udp_socket(options: &SocketOptions) -> socket::udp::Socket<'a> {
         let udp_rx_buffer = socket::udp::PacketBuffer::new(
             vec![socket::udp::PacketMetadata::EMPTY; 8],
-            vec![0; UDP_RX_BUF_LEN],
+            vec![0; options.recv_buf_size],
         );
         let udp_tx_buffer = socket::udp::PacketBuffer::new(
             vec![socket::udp::PacketMetadata::EMPTY; 8],
-            vec![0; UDP_TX_BUF_LEN],
+            vec![0; options.send_buf_size],
         );
-        socket::udp::Socket::new(udp_rx_buffer, udp_tx_buffer)
+        let mut socket = socket::udp::Socket::new(udp_rx_buffer, udp_tx_buffer);
+        options.apply_udp(&mut socket);
+        socket
     }
 
-    pub fn new_dns_socket() -> socket::dns::Socket<'a> {
-        let server_addr = DNS_SEVER.parse().expect("invalid DNS server address");
-        socket::dns::Socket::new(&[server_addr], vec![])
+    pub fn new_icmp_socket(options: &SocketOptions) -> socket::icmp::Socket<'a> {
+        let icmp_rx_buffer = socket::icmp::PacketBuffer::new(
+            vec![socket::icmp::PacketMetadata::EMPTY; 8],
+            vec![0; options.recv_buf_size],
+        );
+        let icmp_tx_buffer = socket::icmp::PacketBuffer::new(
+            vec![socket::icmp::PacketMetadata::EMPTY; 8],
+            vec![0; options.send_buf_size],
+        );
+        let mut socket = socket::icmp::Socket::new(icmp_rx_buffer, icmp_tx_buffer);
+        options.apply_icmp(&mut socket);
+        socket
+    }
+
+    pub fn new_raw_socket(
+        version: IpVersion,
+        protocol: IpProtocol,
+        options: &SocketOptions,
+    ) -> socket::raw::Socket<'a> {
+        let raw_rx_buffer = socket::raw::PacketBuffer::new(
+            vec![socket::raw::PacketMetadata::EMPTY; 8],
+            vec![0; options.recv_buf_size],
+        );
+        let raw_tx_buffer = socket::raw::PacketBuffer::new(
+            vec![socket::raw::PacketMetadata::EMPTY; 8],
+            vec![0; options.send_buf_size],
+        );
+        socket::raw::Socket::new(version, protocol, raw_rx_buffer, raw_tx_buffer)
     }
 
     pub fn add<T: AnySocket<'a>>(&self, socket: T) -> SocketHandle {
-        let handle = self.0.lock().add(socket);
-        debug!("socket {}: created", handle);
-        handle
+        self.0.lock().add(socket)
     }
 
     pub fn with_socket<T: AnySocket<'a>, R, F>(&self, handle: SocketHandle, f: F) -> R
@@ -119,28 +194,98 @@ impl<'a> SocketSetWrapper<'a> {
         f(socket)
     }
 
-    pub fn poll_interfaces(&self) {
-        ETH0.poll(&self.0);
-    }
-
     pub fn remove(&self, handle: SocketHandle) {
         self.0.lock().remove(handle);
-        debug!("socket {}: destroyed", handle);
+    }
+}
+
+impl SocketRef {
+    /// Adds a socket into the socket set of the interface with index `iface`.
+    fn add<T: AnySocket<'static>>(iface: usize, socket: T) -> Self {
+        let handle = INTERFACES[iface].sockets.add(socket);
+        let socket_ref = Self { iface, handle };
+        debug!("socket {}: created", socket_ref);
+        socket_ref
+    }
+
+    /// Returns the interface that this socket belongs to.
+    fn iface(&self) -> &'static InterfaceWrapper {
+        &INTERFACES[self.iface]
+    }
+
+    fn with<T: AnySocket<'static>, R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&T) -> R,
+    {
+        self.iface().sockets.with_socket(self.handle, f)
+    }
+
+    fn with_mut<T: AnySocket<'static>, R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut T) -> R,
+    {
+        self.iface().sockets.with_socket_mut(self.handle, f)
+    }
+
+    fn remove(self) {
+        self.iface().sockets.remove(self.handle);
+        debug!("socket {}: destroyed", self);
+    }
+}
+
+impl fmt::Display for SocketRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.iface().name(), self.handle)
     }
 }
 
 impl InterfaceWrapper {
-    fn new(name: &'static str, dev: AxNetDevice, ether_addr: EthernetAddress) -> Self {
+    fn new(name: String, index: usize, dev: AxNetDevice, ether_addr: EthernetAddress) -> Self {
         let mut config = Config::new(HardwareAddress::Ethernet(ether_addr));
         config.random_seed = RANDOM_SEED;
 
-        let mut dev = DeviceWrapper::new(dev);
-        let iface = Mutex::new(Interface::new(config, &mut dev, Self::current_time()));
+        let mut dev = DeviceWrapper::new(dev, index);
+        let mut iface = Interface::new(config, &mut dev, Self::current_time());
+        iface.update_ip_addrs(|ip_addrs| {
+            ip_addrs.push(slaac::link_local_addr(ether_addr)).unwrap();
+        });
+        Self::from_parts(name, Some(ether_addr), IfaceDevice::Ethernet(dev), iface)
+    }
+
+    fn new_loopback(index: usize) -> Self {
+        let mut config = Config::new(HardwareAddress::Ip);
+        config.random_seed = RANDOM_SEED;
+
+        let mut dev = LoopbackDev::new(index);
+        let mut iface = Interface::new(config, &mut dev, Self::current_time());
+        iface.update_ip_addrs(|ip_addrs| {
+            for cidr in loopback::loopback_addrs() {
+                ip_addrs.push(cidr).unwrap();
+            }
+        });
+        Self::from_parts(
+            loopback::LOOPBACK_NAME.into(),
+            None,
+            IfaceDevice::Loopback(dev),
+            iface,
+        )
+    }
+
+    fn from_parts(
+        name: String,
+        ether_addr: Option<EthernetAddress>,
+        dev: IfaceDevice,
+        iface: Interface,
+    ) -> Self {
         Self {
             name,
             ether_addr,
             dev: Mutex::new(dev),
-            iface,
+            iface: Mutex::new(iface),
+            sockets: SocketSetWrapper::new(),
+            slaac: slaac::Slaac::new(),
+            #[cfg(feature = "dhcp")]
+            dhcp: dhcp::DhcpClient::new(),
         }
     }
 
@@ -149,40 +294,67 @@ impl InterfaceWrapper {
     }
 
     pub fn name(&self) -> &str {
-        self.name
+        &self.name
     }
 
-    pub fn ethernet_address(&self) -> EthernetAddress {
+    pub fn ethernet_address(&self) -> Option<EthernetAddress> {
         self.ether_addr
     }
 
-    pub fn setup_ip_addr(&self, ip: IpAddress, prefix_len: u8) {
+    pub fn setup_ip_addr(&self, ip: IpAddress, prefix_len: u8) -> AxResult {
         let mut iface = self.iface.lock();
+        let mut res = Ok(());
         iface.update_ip_addrs(|ip_addrs| {
-            ip_addrs.push(IpCidr::new(ip, prefix_len)).unwrap();
+            res = ip_addrs
+                .push(IpCidr::new(ip, prefix_len))
+                .map_err(|_| ax_err_type!(NoMemory, "too many IP addresses"));
         });
+        res
     }
 
-    pub fn setup_gateway(&self, gateway: IpAddress) {
+    pub fn setup_gateway(&self, gateway: IpAddress) -> AxResult {
         let mut iface = self.iface.lock();
         match gateway {
-            IpAddress::Ipv4(v4) => iface.routes_mut().add_default_ipv4_route(v4).unwrap(),
-        };
+            IpAddress::Ipv4(v4) => iface.routes_mut().add_default_ipv4_route(v4).map(|_| ()),
+            IpAddress::Ipv6(v6) => iface.routes_mut().add_default_ipv6_route(v6).map(|_| ()),
+        }
+        .map_err(|_| ax_err_type!(NoMemory, "routing table is full"))
     }
 
-    pub fn poll(&self, sockets: &Mutex<SocketSet>) {
+    pub fn poll(&self) {
         let mut dev = self.dev.lock();
         let mut iface = self.iface.lock();
-        let mut sockets = sockets.lock();
+        let mut sockets = self.sockets.0.lock();
         let timestamp = Self::current_time();
-        iface.poll(timestamp, dev.deref_mut(), &mut sockets);
+        match dev.deref_mut() {
+            IfaceDevice::Ethernet(dev) => iface.poll(timestamp, dev, &mut sockets),
+            IfaceDevice::Loopback(dev) => iface.poll(timestamp, dev, &mut sockets),
+        };
+        if let Some(ether_addr) = self.ether_addr {
+            self.slaac.apply(&self.name, ether_addr, &mut iface);
+        }
+        #[cfg(feature = "dhcp")]
+        self.dhcp
+            .handle_events(&self.name, &mut iface, &mut sockets);
+    }
+
+    /// Returns how long until the interface needs to be polled again, or
+    /// `None` if no timer is pending.
+    #[cfg_attr(not(all(feature = "irq", feature = "multitask")), allow(dead_code))]
+    pub fn poll_delay(&self) -> Option<Duration> {
+        let mut iface = self.iface.lock();
+        let sockets = self.sockets.0.lock();
+        iface
+            .poll_delay(Self::current_time(), &sockets)
+            .map(|delay| Duration::from_micros(delay.total_micros()))
     }
 }
 
 impl DeviceWrapper {
-    fn new(inner: AxNetDevice) -> Self {
+    fn new(inner: AxNetDevice, iface: usize) -> Self {
         Self {
             inner: RefCell::new(inner),
+            iface,
         }
     }
 }
@@ -216,7 +388,10 @@ impl Device for DeviceWrapper {
                 return None;
             }
         };
-        Some((AxNetRxToken(&self.inner, rx_buf), AxNetTxToken(&self.inner)))
+        Some((
+            AxNetRxToken(&self.inner, rx_buf, self.iface),
+            AxNetTxToken(&self.inner),
+        ))
     }
 
     fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
@@ -241,12 +416,12 @@ impl Device for DeviceWrapper {
     }
 }
 
-struct AxNetRxToken<'a>(&'a RefCell<AxNetDevice>, NetBufPtr);
+struct AxNetRxToken<'a>(&'a RefCell<AxNetDevice>, NetBufPtr, usize);
 struct AxNetTxToken<'a>(&'a RefCell<AxNetDevice>);
 
 impl RxToken for AxNetRxToken<'_> {
     fn preprocess(&self, sockets: &mut SocketSet<'_>) {
-        snoop_tcp_packet(self.1.packet(), sockets).ok();
+        snoop_packet(self.1.packet(), self.2, sockets).ok();
     }
 
     fn consume<R, F>(self, f: F) -> R
@@ -279,58 +454,153 @@ impl TxToken for AxNetTxToken<'_> {
     }
 }
 
-fn snoop_tcp_packet(buf: &[u8], sockets: &mut SocketSet<'_>) -> Result<(), smoltcp::wire::Error> {
-    use smoltcp::wire::{EthernetFrame, IpProtocol, Ipv4Packet, TcpPacket};
+fn snoop_packet(
+    buf: &[u8],
+    iface: usize,
+    sockets: &mut SocketSet<'_>,
+) -> Result<(), smoltcp::wire::Error> {
+    use smoltcp::wire::{Error, EthernetFrame, EthernetProtocol};
 
     let ether_frame = EthernetFrame::new_checked(buf)?;
-    let ipv4_packet = Ipv4Packet::new_checked(ether_frame.payload())?;
-
-    if ipv4_packet.next_header() == IpProtocol::Tcp {
-        let tcp_packet = TcpPacket::new_checked(ipv4_packet.payload())?;
-        let src_addr = (ipv4_packet.src_addr(), tcp_packet.src_port()).into();
-        let dst_addr = (ipv4_packet.dst_addr(), tcp_packet.dst_port()).into();
-        let is_first = tcp_packet.syn() && !tcp_packet.ack();
-        if is_first {
-            // create a socket for the first incoming TCP packet, as the later accept() returns.
-            LISTEN_TABLE.incoming_tcp_packet(src_addr, dst_addr, sockets);
+    match ether_frame.ethertype() {
+        EthernetProtocol::Ipv4 | EthernetProtocol::Ipv6 => {
+            snoop_ip_packet(ether_frame.payload(), iface, sockets)
         }
+        _ => Err(Error),
+    }
+}
+
+fn snoop_ip_packet(
+    buf: &[u8],
+    iface: usize,
+    sockets: &mut SocketSet<'_>,
+) -> Result<(), smoltcp::wire::Error> {
+    use smoltcp::wire::{IpProtocol, Ipv4Packet, Ipv6Packet, TcpPacket};
+
+    let (src_addr, dst_addr, protocol, payload) = match IpVersion::of_packet(buf)? {
+        IpVersion::Ipv4 => {
+            let packet = Ipv4Packet::new_checked(buf)?;
+            let src_addr = IpAddress::Ipv4(packet.src_addr());
+            let dst_addr = IpAddress::Ipv4(packet.dst_addr());
+            (src_addr, dst_addr, packet.next_header(), packet.payload())
+        }
+        IpVersion::Ipv6 => {
+            let packet = Ipv6Packet::new_checked(buf)?;
+            let src_addr = IpAddress::Ipv6(packet.src_addr());
+            let dst_addr = IpAddress::Ipv6(packet.dst_addr());
+            (src_addr, dst_addr, packet.next_header(), packet.payload())
+        }
+    };
+
+    match protocol {
+        IpProtocol::Tcp => {
+            let tcp_packet = TcpPacket::new_checked(payload)?;
+            let src_addr = (src_addr, tcp_packet.src_port()).into();
+            let dst_addr = (dst_addr, tcp_packet.dst_port()).into();
+            let is_first = tcp_packet.syn() && !tcp_packet.ack();
+            if is_first {
+                // create a socket for the first incoming TCP packet, as the later accept() returns.
+                LISTEN_TABLE.incoming_tcp_packet(src_addr, dst_addr, iface, sockets);
+            }
+        }
+        IpProtocol::Icmpv6 => INTERFACES[iface].slaac.snoop(src_addr, dst_addr, payload),
+        _ => {}
     }
     Ok(())
 }
 
 /// Poll the network stack.
 ///
-/// It may receive packets from the NIC and process them, and transmit queued
-/// packets to the NIC.
+/// It may receive packets from the NICs and process them, and transmit queued
+/// packets to the NICs.
 pub fn poll_interfaces() {
-    SOCKET_SET.poll_interfaces();
+    for iface in INTERFACES.iter() {
+        iface.poll();
+    }
+}
+
+/// Runs `f` on the device of the first NIC.
+fn with_first_nic(f: impl FnOnce(&mut DeviceWrapper)) {
+    for iface in INTERFACES.iter() {
+        if let IfaceDevice::Ethernet(dev) = iface.dev.lock().deref_mut() {
+            return f(dev);
+        }
+    }
+    warn!("No NIC device found!");
 }
 
 /// Benchmark raw socket transmit bandwidth.
 pub fn bench_transmit() {
-    ETH0.dev.lock().bench_transmit_bandwidth();
+    with_first_nic(DeviceWrapper::bench_transmit_bandwidth);
 }
 
 /// Benchmark raw socket receive bandwidth.
 pub fn bench_receive() {
-    ETH0.dev.lock().bench_receive_bandwidth();
+    with_first_nic(DeviceWrapper::bench_receive_bandwidth);
 }
 
-pub(crate) fn init(net_dev: AxNetDevice) {
-    let ether_addr = EthernetAddress(net_dev.mac_address().0);
-    let eth0 = InterfaceWrapper::new("eth0", net_dev, ether_addr);
+pub(crate) fn init(net_devs: Vec<(AxNetDevice, Option<DeviceIrq>)>) {
+    // The loopback interface always comes first.
+    let mut interfaces = Vec::with_capacity(net_devs.len() + 1);
+    let mut nic_irqs = Vec::with_capacity(net_devs.len());
+    interfaces.push(InterfaceWrapper::new_loopback(0));
+    for (index, (net_dev, irq)) in net_devs.into_iter().enumerate() {
+        nic_irqs.push(irq);
+        let ether_addr = EthernetAddress(net_dev.mac_address().0);
+        let name = format!("eth{}", index);
+        interfaces.push(InterfaceWrapper::new(name, index + 1, net_dev, ether_addr));
+    }
 
-    let ip = IP.parse().expect("invalid IP address");
-    let gateway = GATEWAY.parse().expect("invalid gateway IP address");
-    eth0.setup_ip_addr(ip, IP_PREFIX);
-    eth0.setup_gateway(gateway);
+    // The first NIC is configured by the compile-time `AX_IP` and `AX_GW`,
+    // unless DHCP is enabled. Others are left unconfigured, and can be set up
+    // at runtime.
+    if let Some(eth0) = interfaces.get(1).filter(|_| !cfg!(feature = "dhcp")) {
+        if !IP.is_empty() {
+            let ip = IP.parse().expect("invalid IP address");
+            if let Err(e) = eth0.setup_ip_addr(ip, IP_PREFIX) {
+                warn!("{}: failed to set ip address {}: {:?}", eth0.name(), ip, e);
+            }
+        }
+        if !GATEWAY.is_empty() {
+            let gateway = GATEWAY.parse().expect("invalid gateway IP address");
+            if let Err(e) = eth0.setup_gateway(gateway) {
+                warn!(
+                    "{}: failed to set gateway {}: {:?}",
+                    eth0.name(),
+                    gateway,
+                    e
+                );
+            }
+        }
+    }
 
-    ETH0.init_once(eth0);
-    SOCKET_SET.init_once(SocketSetWrapper::new());
+    INTERFACES.init_once(interfaces);
     LISTEN_TABLE.init_once(ListenTable::new());
+    wait::init(nic_irqs);
+
+    slaac::init();
+    #[cfg(feature = "dhcp")]
+    dhcp::init();
+    dns::init();
+    #[cfg(feature = "fs")]
+    {
+        procfs::init();
+        sysfs::init();
+    }
 
-    info!("created net interface {:?}:", ETH0.name());
-    info!("  ether:    {}", ETH0.ethernet_address());
-    info!("  ip:       {}/{}", ip, IP_PREFIX);
-    info!("  gateway:  {}", gateway);
+    for iface in INTERFACES.iter() {
+        let mut iface_inner = iface.iface.lock();
+        info!("created net interface {:?}:", iface.name());
+        if let Some(ether_addr) = iface.ethernet_address() {
+            info!("  ether:    {}", ether_addr);
+        }
+        for cidr in iface_inner.ip_addrs() {
+            info!("  ip:       {}", cidr);
+        }
+        for version in [IpVersion::Ipv4, IpVersion::Ipv6] {
+            if let Some(gateway) = iface::default_gateway_of(&mut iface_inner, version) {
+                info!("  gateway:  {}", gateway);
+            }
+        }
+    }
 }
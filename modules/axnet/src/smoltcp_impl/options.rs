@@ -0,0 +1,177 @@
+//! Socket options.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+
+use axerrno::{AxResult, ax_err};
+use smoltcp::socket::{icmp, tcp, udp};
+use smoltcp::time::Duration as SmolDuration;
+
+use super::{
+    RAW_RX_BUF_LEN, RAW_TX_BUF_LEN, TCP_RX_BUF_LEN, TCP_TX_BUF_LEN, UDP_RX_BUF_LEN, UDP_TX_BUF_LEN,
+};
+
+/// The minimum size of socket buffers.
+const MIN_BUF_LEN: usize = 1024;
+/// The maximum size of socket buffers.
+const MAX_BUF_LEN: usize = 16 * 1024 * 1024;
+/// The interval of TCP keep-alive packets on an idle connection.
+const TCP_KEEP_ALIVE_INTERVAL: SmolDuration = SmolDuration::from_secs(75);
+
+/// The default buffer sizes of a kind of sockets, which can be changed at
+/// runtime in `/sys/module/axnet/parameters/`.
+pub(super) struct DefaultBufSizes {
+    recv: AtomicUsize,
+    send: AtomicUsize,
+}
+
+impl DefaultBufSizes {
+    const fn new(recv: usize, send: usize) -> Self {
+        Self {
+            recv: AtomicUsize::new(recv),
+            send: AtomicUsize::new(send),
+        }
+    }
+
+    /// Returns the default sizes of the receive and the send buffers.
+    pub fn get(&self) -> (usize, usize) {
+        (
+            self.recv.load(Ordering::Relaxed),
+            self.send.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Sets the default size of the receive buffer, clamped into the
+    /// supported range. Sockets created afterwards use the new size.
+    pub fn set_recv(&self, size: usize) {
+        let size = size.clamp(MIN_BUF_LEN, MAX_BUF_LEN);
+        self.recv.store(size, Ordering::Relaxed);
+    }
+
+    /// Sets the default size of the send buffer, as
+    /// [`set_recv`](Self::set_recv).
+    pub fn set_send(&self, size: usize) {
+        let size = size.clamp(MIN_BUF_LEN, MAX_BUF_LEN);
+        self.send.store(size, Ordering::Relaxed);
+    }
+}
+
+pub(super) static TCP_BUF_SIZES: DefaultBufSizes =
+    DefaultBufSizes::new(TCP_RX_BUF_LEN, TCP_TX_BUF_LEN);
+pub(super) static UDP_BUF_SIZES: DefaultBufSizes =
+    DefaultBufSizes::new(UDP_RX_BUF_LEN, UDP_TX_BUF_LEN);
+pub(super) static RAW_BUF_SIZES: DefaultBufSizes =
+    DefaultBufSizes::new(RAW_RX_BUF_LEN, RAW_TX_BUF_LEN);
+
+/// Options of a [`TcpSocket`](super::TcpSocket), a
+/// [`UdpSocket`](super::UdpSocket), an [`IcmpSocket`](super::IcmpSocket) or a
+/// [`RawSocket`](super::RawSocket).
+///
+/// Options that do not apply to the kind of the socket are kept but ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocketOptions {
+    /// Allows binding to a port that is in use by another socket with the
+    /// same option (`SO_REUSEADDR`).
+    ///
+    /// Only UDP sockets are affected, as closed TCP connections never hold
+    /// their ports.
+    pub reuse_addr: bool,
+    /// The size of the receive buffer in bytes (`SO_RCVBUF`).
+    ///
+    /// Buffers are allocated when the socket is connected, listening or
+    /// bound, and raw sockets allocate them when created. The buffers of UDP,
+    /// ICMP and raw sockets are reallocated when the size changes, as soon as
+    /// no packet is queued in them. Those of a TCP connection cannot be
+    /// resized, accepted connections use the sizes of the listening socket.
+    pub recv_buf_size: usize,
+    /// The size of the send buffer in bytes (`SO_SNDBUF`), allocated in the
+    /// same way as the receive buffer.
+    pub send_buf_size: usize,
+    /// Sends keep-alive packets on an idle TCP connection (`SO_KEEPALIVE`).
+    pub keep_alive: bool,
+    /// Disables the Nagle algorithm of TCP (`TCP_NODELAY`).
+    pub no_delay: bool,
+    /// How long closing a TCP connection waits for the unsent data
+    /// (`SO_LINGER`).
+    ///
+    /// `None` closes in the background. A zero duration aborts the connection
+    /// by a reset.
+    pub linger: Option<Duration>,
+    /// The timeout of blocking receive operations (`SO_RCVTIMEO`), after which
+    /// [`WouldBlock`](axerrno::AxError::WouldBlock) is returned.
+    pub recv_timeout: Option<Duration>,
+    /// The timeout of blocking send operations (`SO_SNDTIMEO`).
+    pub send_timeout: Option<Duration>,
+    /// The TTL (hop limit) of outgoing packets (`IP_TTL`).
+    pub ttl: u8,
+    /// Allows sending UDP datagrams to broadcast addresses (`SO_BROADCAST`).
+    pub broadcast: bool,
+}
+
+impl SocketOptions {
+    /// The default TTL (hop limit) of outgoing packets.
+    pub const DEFAULT_TTL: u8 = 64;
+
+    /// The default options of TCP sockets.
+    pub(super) fn tcp_default() -> Self {
+        Self::new(TCP_BUF_SIZES.get())
+    }
+
+    /// The default options of UDP sockets.
+    pub(super) fn udp_default() -> Self {
+        Self::new(UDP_BUF_SIZES.get())
+    }
+
+    /// The default options of ICMP and raw sockets.
+    pub(super) fn raw_default() -> Self {
+        Self::new(RAW_BUF_SIZES.get())
+    }
+
+    const fn new((recv_buf_size, send_buf_size): (usize, usize)) -> Self {
+        Self {
+            reuse_addr: false,
+            recv_buf_size,
+            send_buf_size,
+            keep_alive: false,
+            no_delay: false,
+            linger: None,
+            recv_timeout: None,
+            send_timeout: None,
+            ttl: Self::DEFAULT_TTL,
+            broadcast: false,
+        }
+    }
+
+    /// Checks the options, and clamps the buffer sizes into the supported
+    /// range.
+    pub(super) fn validate(mut self) -> AxResult<Self> {
+        if self.ttl == 0 {
+            return ax_err!(InvalidInput, "invalid TTL");
+        }
+        if self.recv_timeout.is_some_and(|t| t.is_zero())
+            || self.send_timeout.is_some_and(|t| t.is_zero())
+        {
+            return ax_err!(InvalidInput, "invalid timeout");
+        }
+        self.recv_buf_size = self.recv_buf_size.clamp(MIN_BUF_LEN, MAX_BUF_LEN);
+        self.send_buf_size = self.send_buf_size.clamp(MIN_BUF_LEN, MAX_BUF_LEN);
+        Ok(self)
+    }
+
+    /// Applies the options that can be changed at any time to a TCP socket.
+    pub(super) fn apply_tcp(&self, socket: &mut tcp::Socket) {
+        socket.set_nagle_enabled(!self.no_delay);
+        socket.set_keep_alive(self.keep_alive.then_some(TCP_KEEP_ALIVE_INTERVAL));
+        socket.set_hop_limit(Some(self.ttl));
+    }
+
+    /// Applies the options that can be changed at any time to a UDP socket.
+    pub(super) fn apply_udp(&self, socket: &mut udp::Socket) {
+        socket.set_hop_limit(Some(self.ttl));
+    }
+
+    /// Applies the options that can be changed at any time to an ICMP socket.
+    pub(super) fn apply_icmp(&self, socket: &mut icmp::Socket) {
+        socket.set_hop_limit(Some(self.ttl));
+    }
+}
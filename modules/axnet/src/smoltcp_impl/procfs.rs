@@ -0,0 +1,116 @@
+//! `/proc/net/tcp` and `/proc/net/tcp6`, which list the TCP sockets in the
+//! format of Linux.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+use smoltcp::socket::{Socket, tcp::State};
+use smoltcp::wire::{IpAddress, IpVersion};
+
+use super::{INTERFACES, LISTEN_TABLE};
+
+const HEADER: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n";
+
+pub(crate) fn init() {
+    axfs::procfs::add_file("net/tcp", || tcp_table(IpVersion::Ipv4)).unwrap();
+    axfs::procfs::add_file("net/tcp6", || tcp_table(IpVersion::Ipv6)).unwrap();
+}
+
+/// The state codes of Linux.
+fn state_code(state: State) -> u8 {
+    match state {
+        State::Established => 0x01,
+        State::SynSent => 0x02,
+        State::SynReceived => 0x03,
+        State::FinWait1 => 0x04,
+        State::FinWait2 => 0x05,
+        State::TimeWait => 0x06,
+        State::Closed => 0x07,
+        State::CloseWait => 0x08,
+        State::LastAck => 0x09,
+        State::Listen => 0x0a,
+        State::Closing => 0x0b,
+    }
+}
+
+/// Formats `addr` as the words in the host byte order, or the unspecified
+/// address of `version` if it is `None`.
+fn write_addr(out: &mut String, version: IpVersion, addr: Option<IpAddress>, port: u16) {
+    match (version, addr) {
+        (IpVersion::Ipv4, Some(IpAddress::Ipv4(addr))) => {
+            let _ = write!(out, "{:08X}", u32::from_le_bytes(addr.0));
+        }
+        (IpVersion::Ipv6, Some(IpAddress::Ipv6(addr))) => {
+            for word in addr.0.chunks_exact(4) {
+                let _ = write!(out, "{:08X}", u32::from_le_bytes(word.try_into().unwrap()));
+            }
+        }
+        (IpVersion::Ipv4, _) => out.push_str("00000000"),
+        (IpVersion::Ipv6, _) => out.push_str("00000000000000000000000000000000"),
+    }
+    let _ = write!(out, ":{port:04X}");
+}
+
+fn write_entry(
+    out: &mut String,
+    version: IpVersion,
+    local: (Option<IpAddress>, u16),
+    remote: (Option<IpAddress>, u16),
+    state: State,
+    queues: (usize, usize),
+) {
+    let sl = out.lines().count() - 1;
+    let _ = write!(out, "{sl:4}: ");
+    write_addr(out, version, local.0, local.1);
+    out.push(' ');
+    write_addr(out, version, remote.0, remote.1);
+    let _ = writeln!(
+        out,
+        " {:02X} {:08X}:{:08X} 00:00000000 00000000     0        0 0",
+        state_code(state),
+        queues.0,
+        queues.1,
+    );
+}
+
+/// Lists the TCP sockets of `version`, including the listening ports.
+fn tcp_table(version: IpVersion) -> String {
+    let mut out = String::from(HEADER);
+    for (endpoint, backlog) in LISTEN_TABLE.listeners() {
+        // sockets bound to any address are listed in both tables
+        if endpoint.addr.is_some_and(|addr| addr.version() != version) {
+            continue;
+        }
+        let local = (endpoint.addr, endpoint.port);
+        write_entry(
+            &mut out,
+            version,
+            local,
+            (None, 0),
+            State::Listen,
+            (0, backlog),
+        );
+    }
+    for iface in INTERFACES.iter() {
+        for (_, socket) in iface.sockets.0.lock().iter() {
+            let Socket::Tcp(socket) = socket else {
+                continue;
+            };
+            let (Some(local), remote) = (socket.local_endpoint(), socket.remote_endpoint()) else {
+                continue;
+            };
+            if local.addr.version() != version {
+                continue;
+            }
+            write_entry(
+                &mut out,
+                version,
+                (Some(local.addr), local.port),
+                remote.map_or((None, 0), |ep| (Some(ep.addr), ep.port)),
+                socket.state(),
+                (socket.send_queue(), socket.recv_queue()),
+            );
+        }
+    }
+    out
+}
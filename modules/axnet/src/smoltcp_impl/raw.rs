@@ -0,0 +1,285 @@
+use alloc::vec::Vec;
+use core::net::IpAddr;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+
+use axerrno::{AxError, AxResult, ax_err, ax_err_type};
+use axio::PollState;
+use axsync::Mutex;
+
+use smoltcp::phy::ChecksumCapabilities;
+use smoltcp::socket::raw;
+use smoltcp::wire::{
+    IPV4_HEADER_LEN, IPV6_HEADER_LEN, Icmpv6Packet, IpAddress, IpProtocol, IpRepr, IpVersion,
+    Ipv4Packet, Ipv6Packet,
+};
+
+use super::addr::{from_core_ipaddr, into_core_ipaddr, is_unspecified};
+use super::wait::SocketWaiter;
+use super::{INTERFACES, SocketOptions, SocketRef, SocketSetWrapper, iface};
+
+/// The length of the ICMPv6 header up to the checksum.
+const ICMPV6_MIN_LEN: usize = 4;
+
+/// A raw IP socket that provides POSIX-like APIs.
+///
+/// It receives every IP packet of its IP version and protocol, after the
+/// packet is processed by the stack as usual, and sends packets whose IP
+/// header is built by the stack. The socket is backed by one smoltcp socket
+/// per interface.
+pub struct RawSocket {
+    version: IpVersion,
+    protocol: IpProtocol,
+    sockets: Vec<SocketRef>,
+    nonblock: AtomicBool,
+    options: Mutex<SocketOptions>,
+    /// The sizes of the receive and send buffers of the smoltcp sockets.
+    buf_sizes: Mutex<(usize, usize)>,
+    waiter: SocketWaiter,
+}
+
+impl RawSocket {
+    /// Creates a new raw socket for the given IP protocol number, over IPv6 if
+    /// `ipv6` is true, or IPv4 otherwise.
+    pub fn new(ipv6: bool, protocol: u8) -> Self {
+        let version = if ipv6 {
+            IpVersion::Ipv6
+        } else {
+            IpVersion::Ipv4
+        };
+        let protocol = IpProtocol::from(protocol);
+        let options = SocketOptions::raw_default();
+        let sockets = (0..INTERFACES.len())
+            .map(|idx| {
+                let socket = SocketSetWrapper::new_raw_socket(version, protocol, &options);
+                SocketRef::add(idx, socket)
+            })
+            .collect();
+        Self {
+            version,
+            protocol,
+            sockets,
+            nonblock: AtomicBool::new(false),
+            options: Mutex::new(options),
+            buf_sizes: Mutex::new((options.recv_buf_size, options.send_buf_size)),
+            waiter: SocketWaiter::new(),
+        }
+    }
+
+    /// Returns whether this socket is in nonblocking mode.
+    #[inline]
+    pub fn is_nonblocking(&self) -> bool {
+        self.nonblock.load(Ordering::Acquire)
+    }
+
+    /// Moves this raw socket into or out of nonblocking mode.
+    ///
+    /// This will result in `recv_from` and `send_to` operations becoming
+    /// nonblocking, i.e., immediately returning from their calls. If the IO
+    /// operation could not be completed and needs to be retried, an error
+    /// with kind [`Err(WouldBlock)`](AxError::WouldBlock) is returned.
+    #[inline]
+    pub fn set_nonblocking(&self, nonblocking: bool) {
+        self.nonblock.store(nonblocking, Ordering::Release);
+    }
+
+    /// Returns the options of this socket.
+    pub fn options(&self) -> SocketOptions {
+        *self.options.lock()
+    }
+
+    /// Sets the options of this socket.
+    ///
+    /// See [`SocketOptions`] for when each option takes effect.
+    pub fn set_options(&self, options: SocketOptions) -> AxResult {
+        *self.options.lock() = options.validate()?;
+        self.resize_buffers();
+        Ok(())
+    }
+
+    /// Returns the sizes of the receive and send buffers.
+    ///
+    /// They differ from the sizes in the options until the buffers are
+    /// reallocated, see [`SocketOptions::recv_buf_size`].
+    pub fn buf_sizes(&self) -> (usize, usize) {
+        *self.buf_sizes.lock()
+    }
+
+    /// Sends a packet with the payload in `buf` to the given address. On
+    /// success, returns the number of bytes written.
+    ///
+    /// The IP header is prepended by the stack. For ICMPv6, the checksum of
+    /// the message is also computed by the stack.
+    pub fn send_to(&self, buf: &[u8], remote_addr: IpAddr) -> AxResult<usize> {
+        let dst_addr = from_core_ipaddr(remote_addr);
+        if dst_addr.version() != self.version || is_unspecified(dst_addr) {
+            return ax_err!(InvalidInput, "socket send_to() failed: invalid address");
+        }
+        let iface_idx = iface::route(dst_addr)?;
+        let src_addr = iface::source_addr(iface_idx, dst_addr)
+            .ok_or_else(|| ax_err_type!(InvalidInput, "address is not available"))?;
+        let header_len = match self.version {
+            IpVersion::Ipv4 => IPV4_HEADER_LEN,
+            IpVersion::Ipv6 => IPV6_HEADER_LEN,
+        };
+        if header_len + buf.len() > u16::MAX as usize {
+            return ax_err!(InvalidInput, "socket send_to() failed: message too long");
+        }
+        self.resize_buffers();
+        let handle = self.sockets[iface_idx];
+        let options = self.options();
+
+        self.block_on(options.send_timeout, || {
+            handle.with_mut::<raw::Socket, _, _>(|socket| {
+                if header_len + buf.len() > socket.payload_send_capacity() {
+                    return ax_err!(InvalidInput, "socket send_to() failed: message too long");
+                }
+                match socket.send(header_len + buf.len()) {
+                    Ok(packet) => {
+                        let (header, payload) = packet.split_at_mut(header_len);
+                        payload.copy_from_slice(buf);
+                        let repr =
+                            IpRepr::new(src_addr, dst_addr, self.protocol, buf.len(), options.ttl);
+                        emit_packet(&repr, header, payload);
+                        Ok(buf.len())
+                    }
+                    Err(_) => {
+                        // tx buffer is full
+                        socket.register_send_waker(self.waiter.waker());
+                        Err(AxError::WouldBlock)
+                    }
+                }
+            })
+        })
+    }
+
+    /// Receives a single packet on the socket. On success, returns the number
+    /// of bytes read and the origin.
+    ///
+    /// As on Linux, IPv4 packets are returned with their IP header, and IPv6
+    /// packets without it. The packet is truncated if `buf` is too small.
+    pub fn recv_from(&self, buf: &mut [u8]) -> AxResult<(usize, IpAddr)> {
+        self.resize_buffers();
+        self.block_on(self.options().recv_timeout, || {
+            for handle in self.sockets.iter() {
+                let res = handle.with_mut::<raw::Socket, _, _>(|socket| match socket.recv() {
+                    Ok(packet) => {
+                        let (src_addr, data) = parse_packet(packet);
+                        let len = data.len().min(buf.len());
+                        buf[..len].copy_from_slice(&data[..len]);
+                        Some((len, into_core_ipaddr(src_addr)))
+                    }
+                    Err(_) => {
+                        socket.register_recv_waker(self.waiter.waker());
+                        None
+                    }
+                });
+                if let Some(res) = res {
+                    return Ok(res);
+                }
+            }
+            // no more data
+            Err(AxError::WouldBlock)
+        })
+    }
+
+    /// Whether the socket is readable or writable.
+    pub fn poll(&self) -> AxResult<PollState> {
+        let mut state = PollState {
+            readable: false,
+            writable: false,
+        };
+        for handle in self.sockets.iter() {
+            handle.with::<raw::Socket, _, _>(|socket| {
+                state.readable |= socket.can_recv();
+                state.writable |= socket.can_send();
+            });
+        }
+        Ok(state)
+    }
+}
+
+/// Private methods
+impl RawSocket {
+    /// Reallocates the buffers of the smoltcp sockets if their sizes differ
+    /// from the options.
+    ///
+    /// It is done only when no packet is queued in any of them, otherwise it
+    /// is retried by the next operation.
+    fn resize_buffers(&self) {
+        let options = self.options();
+        let sizes = (options.recv_buf_size, options.send_buf_size);
+        let mut buf_sizes = self.buf_sizes.lock();
+        if *buf_sizes == sizes {
+            return;
+        }
+        let idle = self.sockets.iter().all(|handle| {
+            handle.with::<raw::Socket, _, _>(|socket| {
+                socket.recv_queue() == 0 && socket.send_queue() == 0
+            })
+        });
+        if !idle {
+            return;
+        }
+        for handle in self.sockets.iter() {
+            handle.with_mut::<raw::Socket, _, _>(|socket| {
+                *socket = SocketSetWrapper::new_raw_socket(self.version, self.protocol, &options);
+            });
+        }
+        *buf_sizes = sizes;
+        debug!("raw socket: buffers resized to {:?}", sizes);
+        // let the blocked operations and the watchers register their wakers
+        // on the new sockets
+        self.waiter.waker().wake_by_ref();
+    }
+
+    fn block_on<F, T>(&self, timeout: Option<Duration>, f: F) -> AxResult<T>
+    where
+        F: FnMut() -> AxResult<T>,
+    {
+        self.waiter.block_on(self.is_nonblocking(), timeout, f)
+    }
+}
+
+impl Drop for RawSocket {
+    fn drop(&mut self) {
+        self.sockets.drain(..).for_each(SocketRef::remove);
+    }
+}
+
+/// Writes the IP header described by `repr` into `header`.
+///
+/// The checksum of an ICMPv6 message in `payload` is also filled, as it
+/// covers the addresses of the IP header.
+fn emit_packet(repr: &IpRepr, header: &mut [u8], payload: &mut [u8]) {
+    match repr {
+        IpRepr::Ipv4(repr) => {
+            repr.emit(
+                &mut Ipv4Packet::new_unchecked(header),
+                &ChecksumCapabilities::default(),
+            );
+        }
+        IpRepr::Ipv6(repr) => {
+            repr.emit(&mut Ipv6Packet::new_unchecked(header));
+            if repr.next_header == IpProtocol::Icmpv6 && payload.len() >= ICMPV6_MIN_LEN {
+                Icmpv6Packet::new_unchecked(payload)
+                    .fill_checksum(&repr.src_addr.into(), &repr.dst_addr.into());
+            }
+        }
+    }
+}
+
+/// Returns the source address of a received packet, and the data returned to
+/// the user.
+fn parse_packet(packet: &[u8]) -> (IpAddress, &[u8]) {
+    match IpVersion::of_packet(packet) {
+        Ok(IpVersion::Ipv6) => {
+            let src_addr = Ipv6Packet::new_unchecked(packet).src_addr();
+            (IpAddress::Ipv6(src_addr), &packet[IPV6_HEADER_LEN..])
+        }
+        _ => {
+            let src_addr = Ipv4Packet::new_unchecked(packet).src_addr();
+            (IpAddress::Ipv4(src_addr), packet)
+        }
+    }
+}
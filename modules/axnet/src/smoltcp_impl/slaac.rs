@@ -0,0 +1,164 @@
+//! IPv6 stateless address autoconfiguration (SLAAC).
+//!
+//! smoltcp processes neighbor discovery, but ignores router advertisements.
+//! So every interface gets a link-local address derived from its MAC address
+//! at creation, sends a router solicitation at startup, and snoops incoming
+//! router advertisements. An advertised /64 prefix with the autonomous flag
+//! yields a global address, and the advertising router becomes the default
+//! IPv6 gateway. Prefix and router lifetimes are not tracked.
+
+use alloc::vec;
+
+use axsync::Mutex;
+use smoltcp::iface::Interface;
+use smoltcp::phy::ChecksumCapabilities;
+use smoltcp::socket::icmp;
+use smoltcp::wire::{
+    EthernetAddress, Icmpv6Packet, Icmpv6Repr, IpAddress, IpCidr, Ipv6Address,
+    NdiscPrefixInfoFlags, NdiscRepr,
+};
+
+use super::{INTERFACES, SocketRef, poll_interfaces};
+
+/// The prefix length of addresses formed by SLAAC.
+const SLAAC_PREFIX_LEN: u8 = 64;
+
+/// Information learned from a router advertisement.
+#[derive(Clone, Copy)]
+struct RouterAdvert {
+    router: Option<Ipv6Address>,
+    prefix: Option<Ipv6Address>,
+}
+
+/// The SLAAC state of an interface.
+pub(super) struct Slaac {
+    pending: Mutex<Option<RouterAdvert>>,
+}
+
+/// Forms an address from a /64 prefix and the modified EUI-64 interface
+/// identifier of `mac` (RFC 4291, appendix A).
+fn eui64_addr(prefix: Ipv6Address, mac: EthernetAddress) -> Ipv6Address {
+    let mut bytes = prefix.0;
+    let mac = mac.0;
+    bytes[8..].copy_from_slice(&[
+        mac[0] ^ 0x02,
+        mac[1],
+        mac[2],
+        0xff,
+        0xfe,
+        mac[3],
+        mac[4],
+        mac[5],
+    ]);
+    Ipv6Address(bytes)
+}
+
+/// Returns the link-local address of the interface with the given MAC address.
+pub(super) fn link_local_addr(mac: EthernetAddress) -> IpCidr {
+    let prefix = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0);
+    IpCidr::new(eui64_addr(prefix, mac).into(), SLAAC_PREFIX_LEN)
+}
+
+impl Slaac {
+    pub const fn new() -> Self {
+        Self {
+            pending: Mutex::new(None),
+        }
+    }
+
+    /// Records a router advertisement seen on the interface, it will be
+    /// applied after the interface is polled.
+    ///
+    /// It is called before smoltcp processes the ICMPv6 packet.
+    pub fn snoop(&self, src_addr: IpAddress, dst_addr: IpAddress, payload: &[u8]) {
+        let IpAddress::Ipv6(router) = src_addr else {
+            return;
+        };
+        if !router.is_link_local() {
+            return;
+        }
+        let Ok(packet) = Icmpv6Packet::new_checked(payload) else {
+            return;
+        };
+        let caps = ChecksumCapabilities::default();
+        if let Ok(Icmpv6Repr::Ndisc(NdiscRepr::RouterAdvert {
+            router_lifetime,
+            prefix_info,
+            ..
+        })) = Icmpv6Repr::parse(&src_addr, &dst_addr, &packet, &caps)
+        {
+            let prefix = prefix_info
+                .filter(|info| {
+                    info.flags.contains(NdiscPrefixInfoFlags::ADDRCONF)
+                        && info.prefix_len == SLAAC_PREFIX_LEN
+                        && info.valid_lifetime.total_millis() > 0
+                })
+                .map(|info| info.prefix);
+            let router = (router_lifetime.total_millis() > 0).then_some(router);
+            *self.pending.lock() = Some(RouterAdvert { router, prefix });
+        }
+    }
+
+    /// Applies the last router advertisement to the interface.
+    ///
+    /// It is called each time after the interface is polled, with the
+    /// interface locked.
+    pub fn apply(&self, name: &str, mac: EthernetAddress, iface: &mut Interface) {
+        let Some(ra) = self.pending.lock().take() else {
+            return;
+        };
+        if let Some(prefix) = ra.prefix {
+            let cidr = IpCidr::new(eui64_addr(prefix, mac).into(), SLAAC_PREFIX_LEN);
+            if !iface.ip_addrs().contains(&cidr) {
+                let mut added = false;
+                iface.update_ip_addrs(|ip_addrs| added = ip_addrs.push(cidr).is_ok());
+                if added {
+                    info!("{}: SLAAC address {}", name, cidr);
+                } else {
+                    warn!("{}: no room for SLAAC address {}", name, cidr);
+                }
+            }
+        }
+        if let Some(router) = ra.router {
+            match iface.routes_mut().add_default_ipv6_route(router) {
+                Ok(Some(old)) if old.via_router == IpAddress::Ipv6(router) => {}
+                Ok(_) => info!("{}: IPv6 default gateway {}", name, router),
+                Err(_) => warn!("{}: no room for IPv6 default route", name),
+            }
+        }
+    }
+}
+
+/// Sends a router solicitation on every interface, so that routers reply with
+/// advertisements immediately instead of at their next periodic interval.
+pub(super) fn init() {
+    for (idx, iface) in INTERFACES.iter().enumerate() {
+        let Some(ether_addr) = iface.ethernet_address() else {
+            continue;
+        };
+        let repr = Icmpv6Repr::Ndisc(NdiscRepr::RouterSolicit {
+            lladdr: Some(ether_addr.into()),
+        });
+        let mut buf = vec![0; repr.buffer_len()];
+        let src_addr = link_local_addr(ether_addr).address();
+        let dst_addr = Ipv6Address::LINK_LOCAL_ALL_ROUTERS.into();
+        repr.emit(
+            &src_addr,
+            &dst_addr,
+            &mut Icmpv6Packet::new_unchecked(&mut buf),
+            &ChecksumCapabilities::default(),
+        );
+
+        let rx_buffer = icmp::PacketBuffer::new(vec![], vec![]);
+        let tx_buffer =
+            icmp::PacketBuffer::new(vec![icmp::PacketMetadata::EMPTY], vec![0; buf.len()]);
+        let mut socket = icmp::Socket::new(rx_buffer, tx_buffer);
+        // routers discard solicitations whose hop limit is not 255
+        socket.set_hop_limit(Some(255));
+        if socket.send_slice(&buf, dst_addr).is_ok() {
+            let handle = SocketRef::add(idx, socket);
+            poll_interfaces();
+            handle.remove();
+        }
+    }
+}
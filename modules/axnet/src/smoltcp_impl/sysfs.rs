@@ -0,0 +1,63 @@
+//! `/sys/class/net/<name>/` for each interface, and the default buffer sizes
+//! of sockets in `/sys/module/axnet/parameters/`.
+
+use alloc::{format, string::String};
+
+use axerrno::{AxResult, ax_err_type};
+use axfs::sysfs::{add_file, add_writable_file};
+
+use super::options::{DefaultBufSizes, RAW_BUF_SIZES, TCP_BUF_SIZES, UDP_BUF_SIZES};
+use super::{INTERFACES, STANDARD_MTU, loopback::LOOPBACK_MTU};
+
+pub(crate) fn init() {
+    for (idx, iface) in INTERFACES.iter().enumerate() {
+        let dir = format!("class/net/{}", iface.name());
+        let (address, mtu, ty, operstate) = match iface.ethernet_address() {
+            Some(addr) => (format!("{addr}"), STANDARD_MTU, 1, "up"),
+            None => ("00:00:00:00:00:00".into(), LOOPBACK_MTU, 772, "unknown"),
+        };
+        add_file(&format!("{dir}/address"), move || format!("{address}\n")).unwrap();
+        add_file(&format!("{dir}/mtu"), move || format!("{mtu}\n")).unwrap();
+        add_file(&format!("{dir}/ifindex"), move || format!("{}\n", idx + 1)).unwrap();
+        add_file(&format!("{dir}/type"), move || format!("{ty}\n")).unwrap();
+        add_file(&format!("{dir}/operstate"), move || {
+            format!("{operstate}\n")
+        })
+        .unwrap();
+    }
+
+    add_buf_sizes("tcp", &TCP_BUF_SIZES);
+    add_buf_sizes("udp", &UDP_BUF_SIZES);
+    add_buf_sizes("raw", &RAW_BUF_SIZES);
+}
+
+/// Adds `<kind>_rmem` and `<kind>_wmem`, the default sizes of the receive and
+/// the send buffers of the kind of sockets.
+fn add_buf_sizes(kind: &str, sizes: &'static DefaultBufSizes) {
+    let dir = "module/axnet/parameters";
+    add_writable_file(
+        &format!("{dir}/{kind}_rmem"),
+        move || format!("{}\n", sizes.get().0),
+        move |value| {
+            sizes.set_recv(parse_size(value)?);
+            Ok(())
+        },
+    )
+    .unwrap();
+    add_writable_file(
+        &format!("{dir}/{kind}_wmem"),
+        move || format!("{}\n", sizes.get().1),
+        move |value| {
+            sizes.set_send(parse_size(value)?);
+            Ok(())
+        },
+    )
+    .unwrap();
+}
+
+fn parse_size(value: &str) -> AxResult<usize> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| ax_err_type!(InvalidInput, "invalid buffer size"))
+}
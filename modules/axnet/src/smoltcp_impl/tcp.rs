@@ -1,17 +1,23 @@
 use core::cell::UnsafeCell;
 use core::net::SocketAddr;
 use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use core::task::Waker;
+use core::time::Duration;
 
 use axerrno::{AxError, AxResult, ax_err, ax_err_type};
 use axio::PollState;
 use axsync::Mutex;
 
-use smoltcp::iface::SocketHandle;
 use smoltcp::socket::tcp::{self, ConnectError, State};
 use smoltcp::wire::{IpEndpoint, IpListenEndpoint};
 
-use super::addr::{UNSPECIFIED_ENDPOINT, from_core_sockaddr, into_core_sockaddr, is_unspecified};
-use super::{ETH0, LISTEN_TABLE, SOCKET_SET, SocketSetWrapper};
+use super::addr::{
+    UNSPECIFIED_ENDPOINT, UNSPECIFIED_IP, from_core_sockaddr, into_core_sockaddr, is_unspecified,
+};
+use super::wait::SocketWaiter;
+use super::{
+    INTERFACES, LISTEN_TABLE, SocketOptions, SocketRef, SocketSetWrapper, iface, poll_interfaces,
+};
 
 // State transitions:
 // CLOSED -(connect)-> BUSY -> CONNECTING -> CONNECTED -(shutdown)-> BUSY -> CLOSED
@@ -37,31 +43,39 @@ const STATE_LISTENING: u8 = 4;
 /// [`accept`]: TcpSocket::accept
 pub struct TcpSocket {
     state: AtomicU8,
-    handle: UnsafeCell<Option<SocketHandle>>,
+    handle: UnsafeCell<Option<SocketRef>>,
     local_addr: UnsafeCell<IpEndpoint>,
     peer_addr: UnsafeCell<IpEndpoint>,
     nonblock: AtomicBool,
+    options: Mutex<SocketOptions>,
+    /// The error of a failed nonblocking connection, until it is taken.
+    error: Mutex<Option<AxError>>,
+    waiter: SocketWaiter,
 }
 
 unsafe impl Sync for TcpSocket {}
 
 impl TcpSocket {
     /// Creates a new TCP socket.
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             state: AtomicU8::new(STATE_CLOSED),
             handle: UnsafeCell::new(None),
             local_addr: UnsafeCell::new(UNSPECIFIED_ENDPOINT),
             peer_addr: UnsafeCell::new(UNSPECIFIED_ENDPOINT),
             nonblock: AtomicBool::new(false),
+            options: Mutex::new(SocketOptions::tcp_default()),
+            error: Mutex::new(None),
+            waiter: SocketWaiter::new(),
         }
     }
 
     /// Creates a new TCP socket that is already connected.
     const fn new_connected(
-        handle: SocketHandle,
+        handle: SocketRef,
         local_addr: IpEndpoint,
         peer_addr: IpEndpoint,
+        options: SocketOptions,
     ) -> Self {
         Self {
             state: AtomicU8::new(STATE_CONNECTED),
@@ -69,6 +83,9 @@ impl TcpSocket {
             local_addr: UnsafeCell::new(local_addr),
             peer_addr: UnsafeCell::new(peer_addr),
             nonblock: AtomicBool::new(false),
+            options: Mutex::new(options),
+            error: Mutex::new(None),
+            waiter: SocketWaiter::new(),
         }
     }
 
@@ -115,23 +132,90 @@ impl TcpSocket {
         self.nonblock.store(nonblocking, Ordering::Release);
     }
 
+    /// Returns the options of this socket.
+    pub fn options(&self) -> SocketOptions {
+        *self.options.lock()
+    }
+
+    /// Sets the options of this socket.
+    ///
+    /// See [`SocketOptions`] for when each option takes effect.
+    pub fn set_options(&self, options: SocketOptions) -> AxResult {
+        let options = options.validate()?;
+        *self.options.lock() = options;
+        match self.get_state() {
+            STATE_CONNECTING | STATE_CONNECTED => {
+                // SAFETY: `self.handle` should be initialized in a connected socket.
+                let handle = unsafe { self.handle.get().read().unwrap() };
+                handle.with_mut::<tcp::Socket, _, _>(|socket| options.apply_tcp(socket));
+            }
+            STATE_LISTENING => {
+                // SAFETY: `self.local_addr` should be initialized in a listening socket.
+                let local_port = unsafe { self.local_addr.get().read().port };
+                LISTEN_TABLE.set_options(local_port, options);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Returns the sizes of the receive and send buffers.
+    ///
+    /// They are the sizes of the buffers of the connection if connecting or
+    /// connected, as they cannot be resized. Otherwise, they are the sizes in
+    /// the options, which the next connection will use.
+    pub fn buf_sizes(&self) -> (usize, usize) {
+        match self.get_state() {
+            STATE_CONNECTING | STATE_CONNECTED => {
+                // SAFETY: `self.handle` should be initialized in a connected socket.
+                let handle = unsafe { self.handle.get().read().unwrap() };
+                handle.with::<tcp::Socket, _, _>(|socket| {
+                    (socket.recv_capacity(), socket.send_capacity())
+                })
+            }
+            _ => {
+                let options = self.options();
+                (options.recv_buf_size, options.send_buf_size)
+            }
+        }
+    }
+
+    /// Returns and clears the pending error of the socket (`SO_ERROR`).
+    ///
+    /// It is the error of a connection that failed after [`connect`] returned
+    /// [`Err(WouldBlock)`](AxError::WouldBlock) in nonblocking mode.
+    ///
+    /// [`connect`]: Self::connect
+    pub fn take_error(&self) -> Option<AxError> {
+        self.error.lock().take()
+    }
+
     /// Connects to the given address and port.
     ///
     /// The local port is generated automatically.
     pub fn connect(&self, remote_addr: SocketAddr) -> AxResult {
         self.update_state(STATE_CLOSED, STATE_CONNECTING, || {
-            // SAFETY: no other threads can read or write these fields.
-            let handle = unsafe { self.handle.get().read() }
-                .unwrap_or_else(|| SOCKET_SET.add(SocketSetWrapper::new_tcp_socket()));
-
-            // TODO: check remote addr unreachable
             let remote_endpoint = from_core_sockaddr(remote_addr);
             let bound_endpoint = self.bound_endpoint()?;
-            let iface = &ETH0.iface;
-            let (local_endpoint, remote_endpoint) = SOCKET_SET
-                .with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+            let iface_idx = iface::select(
+                bound_endpoint.addr.unwrap_or(UNSPECIFIED_IP),
+                remote_endpoint.addr,
+            )?;
+
+            // SAFETY: no other threads can read or write these fields.
+            if let Some(old) = unsafe { (*self.handle.get()).take() } {
+                old.remove();
+            }
+            // the socket must live in the socket set of the egress interface,
+            // and its buffers are sized by the current options
+            let options = self.options();
+            let handle = SocketRef::add(iface_idx, SocketSetWrapper::new_tcp_socket(&options));
+
+            let mut iface = INTERFACES[iface_idx].iface.lock();
+            let (local_endpoint, remote_endpoint) = handle
+                .with_mut::<tcp::Socket, _, _>(|socket| {
                     socket
-                        .connect(iface.lock().context(), remote_endpoint, bound_endpoint)
+                        .connect(iface.context(), remote_endpoint, bound_endpoint)
                         .or_else(|e| match e {
                             ConnectError::InvalidState => {
                                 ax_err!(BadState, "socket connect() failed")
@@ -144,7 +228,8 @@ impl TcpSocket {
                         socket.local_endpoint().unwrap(),
                         socket.remote_endpoint().unwrap(),
                     ))
-                })?;
+                })
+                .inspect_err(|_| handle.remove())?;
             unsafe {
                 // SAFETY: no other threads can read or write these fields as we
                 // have changed the state to `BUSY`.
@@ -152,6 +237,7 @@ impl TcpSocket {
                 self.peer_addr.get().write(remote_endpoint);
                 self.handle.get().write(Some(handle));
             }
+            drop(iface);
             Ok(())
         })
         .unwrap_or_else(|_| ax_err!(AlreadyExists, "socket connect() failed: already connected"))?; // EISCONN
@@ -160,14 +246,21 @@ impl TcpSocket {
         if self.is_nonblocking() {
             Err(AxError::WouldBlock)
         } else {
-            self.block_on(|| {
+            // SAFETY: `self.handle` should be initialized above.
+            let handle = unsafe { self.handle.get().read().unwrap() };
+            self.block_on(self.options().send_timeout, || {
+                // register before checking, so that no state change is missed
+                handle.with_mut::<tcp::Socket, _, _>(|socket| {
+                    socket.register_send_waker(self.waiter.waker())
+                });
                 let PollState { writable, .. } = self.poll_connect()?;
                 if !writable {
                     Err(AxError::WouldBlock)
                 } else if self.get_state() == STATE_CONNECTED {
                     Ok(())
                 } else {
-                    ax_err!(ConnectionRefused, "socket connect() failed")
+                    // reported here instead of by `SO_ERROR`
+                    Err(self.take_error().unwrap_or(AxError::ConnectionRefused))
                 }
             })
         }
@@ -209,7 +302,7 @@ impl TcpSocket {
             unsafe {
                 (*self.local_addr.get()).port = bound_endpoint.port;
             }
-            LISTEN_TABLE.listen(bound_endpoint)?;
+            LISTEN_TABLE.listen(bound_endpoint, self.options())?;
             debug!("TCP socket listening on {}", bound_endpoint);
             Ok(())
         })
@@ -229,10 +322,14 @@ impl TcpSocket {
 
         // SAFETY: `self.local_addr` should be initialized after `bind()`.
         let local_port = unsafe { self.local_addr.get().read().port };
-        self.block_on(|| {
+        let options = self.options();
+        self.block_on(options.recv_timeout, || {
+            LISTEN_TABLE.register_waker(local_port, self.waiter.waker());
             let (handle, (local_addr, peer_addr)) = LISTEN_TABLE.accept(local_port)?;
             debug!("TCP socket accepted a new connection {}", peer_addr);
-            Ok(TcpSocket::new_connected(handle, local_addr, peer_addr))
+            Ok(TcpSocket::new_connected(
+                handle, local_addr, peer_addr, options,
+            ))
         })
     }
 
@@ -243,12 +340,30 @@ impl TcpSocket {
             // SAFETY: `self.handle` should be initialized in a connected socket, and
             // no other threads can read or write it.
             let handle = unsafe { self.handle.get().read().unwrap() };
-            SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+            let linger = self.options().linger;
+            handle.with_mut::<tcp::Socket, _, _>(|socket| {
                 debug!("TCP socket {}: shutting down", handle);
-                socket.close();
+                if linger == Some(Duration::ZERO) {
+                    socket.abort();
+                } else {
+                    socket.close();
+                }
             });
             unsafe { self.local_addr.get().write(UNSPECIFIED_ENDPOINT) }; // clear bound address
-            SOCKET_SET.poll_interfaces();
+            poll_interfaces();
+            if let Some(linger) = linger.filter(|t| !t.is_zero()) {
+                // wait until our FIN is acknowledged
+                self.block_on(Some(linger), || {
+                    handle.with_mut::<tcp::Socket, _, _>(|socket| match socket.state() {
+                        State::Closed | State::TimeWait | State::FinWait2 => Ok(()),
+                        _ => {
+                            socket.register_send_waker(self.waiter.waker());
+                            Err(AxError::WouldBlock)
+                        }
+                    })
+                })
+                .ok();
+            }
             Ok(())
         })
         .unwrap_or(Ok(()))?;
@@ -260,7 +375,7 @@ impl TcpSocket {
             let local_port = unsafe { self.local_addr.get().read().port };
             unsafe { self.local_addr.get().write(UNSPECIFIED_ENDPOINT) }; // clear bound address
             LISTEN_TABLE.unlisten(local_port);
-            SOCKET_SET.poll_interfaces();
+            poll_interfaces();
             Ok(())
         })
         .unwrap_or(Ok(()))?;
@@ -279,8 +394,8 @@ impl TcpSocket {
 
         // SAFETY: `self.handle` should be initialized in a connected socket.
         let handle = unsafe { self.handle.get().read().unwrap() };
-        self.block_on(|| {
-            SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+        self.block_on(self.options().recv_timeout, || {
+            handle.with_mut::<tcp::Socket, _, _>(|socket| {
                 if !socket.is_active() {
                     // not open
                     ax_err!(ConnectionRefused, "socket recv() failed")
@@ -296,6 +411,7 @@ impl TcpSocket {
                     Ok(len)
                 } else {
                     // no more data
+                    socket.register_recv_waker(self.waiter.waker());
                     Err(AxError::WouldBlock)
                 }
             })
@@ -312,8 +428,8 @@ impl TcpSocket {
 
         // SAFETY: `self.handle` should be initialized in a connected socket.
         let handle = unsafe { self.handle.get().read().unwrap() };
-        self.block_on(|| {
-            SOCKET_SET.with_socket_mut::<tcp::Socket, _, _>(handle, |socket| {
+        self.block_on(self.options().send_timeout, || {
+            handle.with_mut::<tcp::Socket, _, _>(|socket| {
                 if !socket.is_active() || !socket.may_send() {
                     // closed by remote
                     ax_err!(ConnectionReset, "socket send() failed")
@@ -326,6 +442,7 @@ impl TcpSocket {
                     Ok(len)
                 } else {
                     // tx buffer is full
+                    socket.register_send_waker(self.waiter.waker());
                     Err(AxError::WouldBlock)
                 }
             })
@@ -344,6 +461,67 @@ impl TcpSocket {
             }),
         }
     }
+
+    /// Whether the peer has closed the connection or shut down writing.
+    pub fn peer_closed(&self) -> bool {
+        if !self.is_connected() {
+            return false;
+        }
+        // SAFETY: `self.handle` should be initialized in a connected socket.
+        let handle = unsafe { self.handle.get().read().unwrap() };
+        handle.with::<tcp::Socket, _, _>(|socket| {
+            matches!(
+                socket.state(),
+                State::CloseWait
+                    | State::LastAck
+                    | State::Closing
+                    | State::TimeWait
+                    | State::Closed
+            )
+        })
+    }
+
+    /// Whether the connection is closed in both directions, as both ends have
+    /// sent a FIN, or the connection is reset.
+    pub fn hung_up(&self) -> bool {
+        if !self.is_connected() {
+            return false;
+        }
+        // SAFETY: `self.handle` should be initialized in a connected socket.
+        let handle = unsafe { self.handle.get().read().unwrap() };
+        handle.with::<tcp::Socket, _, _>(|socket| {
+            matches!(
+                socket.state(),
+                State::LastAck | State::Closing | State::TimeWait | State::Closed
+            )
+        })
+    }
+
+    /// Registers `waker` to be woken once, when the socket may have become
+    /// readable or writable.
+    ///
+    /// Returns `false` if the socket is only woken while the interfaces are
+    /// polled, as the network stack is not driven by interrupts.
+    pub fn register_waker(&self, waker: &Waker) -> bool {
+        let irq_driven = self.waiter.watch(waker);
+        match self.get_state() {
+            STATE_CONNECTING | STATE_CONNECTED => {
+                // SAFETY: `self.handle` should be initialized above.
+                let handle = unsafe { self.handle.get().read().unwrap() };
+                handle.with_mut::<tcp::Socket, _, _>(|socket| {
+                    socket.register_recv_waker(self.waiter.waker());
+                    socket.register_send_waker(self.waiter.waker());
+                });
+            }
+            STATE_LISTENING => {
+                // SAFETY: `self.local_addr` should be initialized in a listening socket.
+                let local_addr = unsafe { self.local_addr.get().read() };
+                LISTEN_TABLE.register_waker(local_addr.port, self.waiter.waker());
+            }
+            _ => {}
+        }
+        irq_driven
+    }
 }
 
 /// Private methods
@@ -422,27 +600,27 @@ impl TcpSocket {
     fn poll_connect(&self) -> AxResult<PollState> {
         // SAFETY: `self.handle` should be initialized above.
         let handle = unsafe { self.handle.get().read().unwrap() };
-        let writable =
-            SOCKET_SET.with_socket::<tcp::Socket, _, _>(handle, |socket| match socket.state() {
-                State::SynSent => false, // wait for connection
-                State::Established => {
-                    self.set_state(STATE_CONNECTED); // connected
-                    debug!(
-                        "TCP socket {}: connected to {}",
-                        handle,
-                        socket.remote_endpoint().unwrap(),
-                    );
-                    true
-                }
-                _ => {
-                    unsafe {
-                        self.local_addr.get().write(UNSPECIFIED_ENDPOINT);
-                        self.peer_addr.get().write(UNSPECIFIED_ENDPOINT);
-                    }
-                    self.set_state(STATE_CLOSED); // connection failed
-                    true
+        let writable = handle.with::<tcp::Socket, _, _>(|socket| match socket.state() {
+            State::SynSent => false, // wait for connection
+            State::Established => {
+                self.set_state(STATE_CONNECTED); // connected
+                debug!(
+                    "TCP socket {}: connected to {}",
+                    handle,
+                    socket.remote_endpoint().unwrap(),
+                );
+                true
+            }
+            _ => {
+                unsafe {
+                    self.local_addr.get().write(UNSPECIFIED_ENDPOINT);
+                    self.peer_addr.get().write(UNSPECIFIED_ENDPOINT);
                 }
-            });
+                *self.error.lock() = Some(AxError::ConnectionRefused);
+                self.set_state(STATE_CLOSED); // connection failed
+                true
+            }
+        });
         Ok(PollState {
             readable: false,
             writable,
@@ -452,7 +630,7 @@ impl TcpSocket {
     fn poll_stream(&self) -> AxResult<PollState> {
         // SAFETY: `self.handle` should be initialized in a connected socket.
         let handle = unsafe { self.handle.get().read().unwrap() };
-        SOCKET_SET.with_socket::<tcp::Socket, _, _>(handle, |socket| {
+        handle.with::<tcp::Socket, _, _>(|socket| {
             Ok(PollState {
                 readable: !socket.may_recv() || socket.can_recv(),
                 writable: !socket.may_send() || socket.can_send(),
@@ -473,23 +651,13 @@ impl TcpSocket {
     ///
     /// If the socket is non-blocking, it calls the function once and returns
     /// immediately. Otherwise, it may call the function multiple times if it
-    /// returns [`Err(WouldBlock)`](AxError::WouldBlock).
-    fn block_on<F, T>(&self, mut f: F) -> AxResult<T>
+    /// returns [`Err(WouldBlock)`](AxError::WouldBlock), until `timeout`
+    /// expires.
+    fn block_on<F, T>(&self, timeout: Option<Duration>, f: F) -> AxResult<T>
     where
         F: FnMut() -> AxResult<T>,
     {
-        if self.is_nonblocking() {
-            f()
-        } else {
-            loop {
-                SOCKET_SET.poll_interfaces();
-                match f() {
-                    Ok(t) => return Ok(t),
-                    Err(AxError::WouldBlock) => axtask::yield_now(),
-                    Err(e) => return Err(e),
-                }
-            }
-        }
+        self.waiter.block_on(self.is_nonblocking(), timeout, f)
     }
 }
 
@@ -498,7 +666,7 @@ impl Drop for TcpSocket {
         self.shutdown().ok();
         // Safe because we have mut reference to `self`.
         if let Some(handle) = unsafe { self.handle.get().read() } {
-            SOCKET_SET.remove(handle);
+            handle.remove();
         }
     }
 }
@@ -1,37 +1,54 @@
+use alloc::{collections::BTreeMap, vec::Vec};
 use core::net::SocketAddr;
 use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+use core::time::Duration;
 
 use axerrno::{AxError, AxResult, ax_err, ax_err_type};
 use axio::PollState;
 use axsync::Mutex;
 use spin::RwLock;
 
-use smoltcp::iface::SocketHandle;
 use smoltcp::socket::udp::{self, BindError, SendError};
-use smoltcp::wire::{IpEndpoint, IpListenEndpoint};
+use smoltcp::wire::{IpAddress, IpCidr, IpEndpoint, IpListenEndpoint};
 
 use super::addr::{UNSPECIFIED_ENDPOINT, from_core_sockaddr, into_core_sockaddr, is_unspecified};
-use super::{SOCKET_SET, SocketSetWrapper};
+use super::wait::SocketWaiter;
+use super::{INTERFACES, SocketOptions, SocketRef, SocketSetWrapper, iface, poll_interfaces};
+
+/// The addresses bound by UDP sockets on each port, and whether the sockets
+/// allow address reuse.
+static BOUND_PORTS: Mutex<BTreeMap<u16, Vec<(Option<IpAddress>, bool)>>> =
+    Mutex::new(BTreeMap::new());
 
 /// A UDP socket that provides POSIX-like APIs.
+///
+/// A socket bound to an unspecified address listens on all interfaces, so it
+/// is backed by one smoltcp socket per interface.
 pub struct UdpSocket {
-    handle: SocketHandle,
+    sockets: RwLock<Vec<SocketRef>>,
     local_addr: RwLock<Option<IpEndpoint>>,
     peer_addr: RwLock<Option<IpEndpoint>>,
     nonblock: AtomicBool,
+    options: Mutex<SocketOptions>,
+    /// The sizes of the receive and send buffers of the smoltcp sockets.
+    buf_sizes: Mutex<(usize, usize)>,
+    waiter: SocketWaiter,
 }
 
 impl UdpSocket {
     /// Creates a new UDP socket.
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        let socket = SocketSetWrapper::new_udp_socket();
-        let handle = SOCKET_SET.add(socket);
+        let options = SocketOptions::udp_default();
         Self {
-            handle,
+            sockets: RwLock::new(Vec::new()),
             local_addr: RwLock::new(None),
             peer_addr: RwLock::new(None),
             nonblock: AtomicBool::new(false),
+            options: Mutex::new(options),
+            buf_sizes: Mutex::new((options.recv_buf_size, options.send_buf_size)),
+            waiter: SocketWaiter::new(),
         }
     }
 
@@ -69,6 +86,32 @@ impl UdpSocket {
         self.nonblock.store(nonblocking, Ordering::Release);
     }
 
+    /// Returns the options of this socket.
+    pub fn options(&self) -> SocketOptions {
+        *self.options.lock()
+    }
+
+    /// Sets the options of this socket.
+    ///
+    /// See [`SocketOptions`] for when each option takes effect.
+    pub fn set_options(&self, options: SocketOptions) -> AxResult {
+        let options = options.validate()?;
+        *self.options.lock() = options;
+        for handle in self.sockets.read().iter() {
+            handle.with_mut::<udp::Socket, _, _>(|socket| options.apply_udp(socket));
+        }
+        self.resize_buffers();
+        Ok(())
+    }
+
+    /// Returns the sizes of the receive and send buffers.
+    ///
+    /// They differ from the sizes in the options until the buffers are
+    /// reallocated, see [`SocketOptions::recv_buf_size`].
+    pub fn buf_sizes(&self) -> (usize, usize) {
+        *self.buf_sizes.lock()
+    }
+
     /// Binds an unbound socket to the given address and port.
     ///
     /// It's must be called before [`send_to`](Self::send_to) and
@@ -88,15 +131,36 @@ impl UdpSocket {
             addr: (!is_unspecified(local_endpoint.addr)).then_some(local_endpoint.addr),
             port: local_endpoint.port,
         };
-        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
-            socket.bind(endpoint).or_else(|e| match e {
-                BindError::InvalidState => ax_err!(AlreadyExists, "socket bind() failed"),
-                BindError::Unaddressable => ax_err!(InvalidInput, "socket bind() failed"),
-            })
-        })?;
+        let options = self.options();
+        let ifaces = match endpoint.addr {
+            Some(addr) => {
+                let idx = iface::iface_of_addr(addr)
+                    .ok_or_else(|| ax_err_type!(InvalidInput, "socket bind() failed"))?;
+                idx..idx + 1
+            }
+            None => 0..INTERFACES.len(),
+        };
+
+        claim_port(endpoint, options.reuse_addr)?;
+        let mut sockets = self.sockets.write();
+        for idx in ifaces {
+            let handle = SocketRef::add(idx, SocketSetWrapper::new_udp_socket(&options));
+            sockets.push(handle);
+            handle
+                .with_mut::<udp::Socket, _, _>(|socket| {
+                    socket.bind(endpoint).or_else(|e| match e {
+                        BindError::InvalidState => ax_err!(AlreadyExists, "socket bind() failed"),
+                        BindError::Unaddressable => ax_err!(InvalidInput, "socket bind() failed"),
+                    })
+                })
+                .inspect_err(|_| {
+                    sockets.drain(..).for_each(SocketRef::remove);
+                    release_port(endpoint);
+                })?;
+            debug!("UDP socket {}: bound on {}", handle, endpoint);
+        }
 
         *self_local_addr = Some(local_endpoint);
-        debug!("UDP socket {}: bound on {}", self.handle, endpoint);
         Ok(())
     }
 
@@ -142,7 +206,7 @@ impl UdpSocket {
         }
 
         *self_peer_addr = Some(from_core_sockaddr(addr));
-        debug!("UDP socket {}: connected to {}", self.handle, addr);
+        debug!("UDP socket {:?}: connected to {}", self.local_addr(), addr);
         Ok(())
     }
 
@@ -172,11 +236,13 @@ impl UdpSocket {
 
     /// Close the socket.
     pub fn shutdown(&self) -> AxResult {
-        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
-            debug!("UDP socket {}: shutting down", self.handle);
-            socket.close();
-        });
-        SOCKET_SET.poll_interfaces();
+        for handle in self.sockets.read().iter() {
+            handle.with_mut::<udp::Socket, _, _>(|socket| {
+                debug!("UDP socket {}: shutting down", handle);
+                socket.close();
+            });
+        }
+        poll_interfaces();
         Ok(())
     }
 
@@ -188,12 +254,33 @@ impl UdpSocket {
                 writable: false,
             });
         }
-        SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
-            Ok(PollState {
-                readable: socket.can_recv(),
-                writable: socket.can_send(),
-            })
-        })
+        let mut state = PollState {
+            readable: false,
+            writable: false,
+        };
+        for handle in self.sockets.read().iter() {
+            handle.with::<udp::Socket, _, _>(|socket| {
+                state.readable |= socket.can_recv();
+                state.writable |= socket.can_send();
+            });
+        }
+        Ok(state)
+    }
+
+    /// Registers `waker` to be woken once, when the socket may have become
+    /// readable or writable.
+    ///
+    /// Returns `false` if the socket is only woken while the interfaces are
+    /// polled, as the network stack is not driven by interrupts.
+    pub fn register_waker(&self, waker: &Waker) -> bool {
+        let irq_driven = self.waiter.watch(waker);
+        for handle in self.sockets.read().iter() {
+            handle.with_mut::<udp::Socket, _, _>(|socket| {
+                socket.register_recv_waker(self.waiter.waker());
+                socket.register_send_waker(self.waiter.waker());
+            });
+        }
+        irq_driven
     }
 }
 
@@ -207,26 +294,43 @@ impl UdpSocket {
     }
 
     fn send_impl(&self, buf: &[u8], remote_endpoint: IpEndpoint) -> AxResult<usize> {
-        if self.local_addr.read().is_none() {
-            return ax_err!(NotConnected, "socket send() failed");
+        let local_endpoint = match *self.local_addr.read() {
+            Some(endpoint) => endpoint,
+            None => return ax_err!(NotConnected, "socket send() failed"),
+        };
+        self.resize_buffers();
+        let iface_idx = iface::select(local_endpoint.addr, remote_endpoint.addr)?;
+        let options = self.options();
+        if !options.broadcast && is_broadcast(iface_idx, remote_endpoint.addr) {
+            return ax_err!(
+                PermissionDenied,
+                "socket send() failed: broadcast not allowed"
+            );
         }
+        let handle = {
+            let sockets = self.sockets.read();
+            sockets
+                .iter()
+                .find(|handle| handle.iface == iface_idx)
+                .or(sockets.first())
+                .copied()
+                .ok_or_else(|| ax_err_type!(NotConnected, "socket send() failed"))?
+        };
 
-        self.block_on(|| {
-            SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
+        self.block_on(options.send_timeout, || {
+            handle.with_mut::<udp::Socket, _, _>(|socket| {
                 if socket.can_send() {
-                    socket
-                        .send_slice(buf, remote_endpoint)
-                        .map_err(|e| match e {
-                            SendError::BufferFull => AxError::WouldBlock,
-                            SendError::Unaddressable => {
-                                ax_err_type!(ConnectionRefused, "socket send() failed")
-                            }
-                        })?;
-                    Ok(buf.len())
-                } else {
-                    // tx buffer is full
-                    Err(AxError::WouldBlock)
+                    match socket.send_slice(buf, remote_endpoint) {
+                        Ok(()) => return Ok(buf.len()),
+                        Err(SendError::Unaddressable) => {
+                            return ax_err!(ConnectionRefused, "socket send() failed");
+                        }
+                        Err(SendError::BufferFull) => {}
+                    }
                 }
+                // tx buffer is full
+                socket.register_send_waker(self.waiter.waker());
+                Err(AxError::WouldBlock)
             })
         })
     }
@@ -238,57 +342,155 @@ impl UdpSocket {
         if self.local_addr.read().is_none() {
             return ax_err!(NotConnected, "socket send() failed");
         }
-
-        self.block_on(|| {
-            SOCKET_SET.with_socket_mut::<udp::Socket, _, _>(self.handle, |socket| {
-                if socket.can_recv() {
-                    // data available
-                    op(socket)
-                } else {
-                    // no more data
-                    Err(AxError::WouldBlock)
+        self.resize_buffers();
+
+        self.block_on(self.options().recv_timeout, || {
+            for handle in self.sockets.read().iter() {
+                let res = handle.with_mut::<udp::Socket, _, _>(|socket| {
+                    if socket.can_recv() {
+                        // data available
+                        Some(op(socket))
+                    } else {
+                        socket.register_recv_waker(self.waiter.waker());
+                        None
+                    }
+                });
+                if let Some(res) = res {
+                    return res;
                 }
-            })
+            }
+            // no more data
+            Err(AxError::WouldBlock)
         })
     }
 
-    fn block_on<F, T>(&self, mut f: F) -> AxResult<T>
+    /// Reallocates the buffers of the smoltcp sockets if their sizes differ
+    /// from the options.
+    ///
+    /// It is done only when no datagram is queued in any of them, otherwise
+    /// it is retried by the next operation.
+    fn resize_buffers(&self) {
+        let options = self.options();
+        let sizes = (options.recv_buf_size, options.send_buf_size);
+        let mut buf_sizes = self.buf_sizes.lock();
+        let sockets = self.sockets.read();
+        if sockets.is_empty() {
+            // allocated with these sizes when bound
+            *buf_sizes = sizes;
+            return;
+        }
+        if *buf_sizes == sizes {
+            return;
+        }
+        let idle = sockets.iter().all(|handle| {
+            handle.with::<udp::Socket, _, _>(|socket| {
+                socket.recv_queue() == 0 && socket.send_queue() == 0
+            })
+        });
+        if !idle {
+            return;
+        }
+        for handle in sockets.iter() {
+            handle.with_mut::<udp::Socket, _, _>(|socket| {
+                let mut new_socket = SocketSetWrapper::new_udp_socket(&options);
+                // the endpoint is valid, as the old socket is bound to it
+                new_socket.bind(socket.endpoint()).ok();
+                *socket = new_socket;
+            });
+        }
+        *buf_sizes = sizes;
+        debug!("UDP socket: buffers resized to {:?}", sizes);
+        // let the blocked operations and the watchers register their wakers
+        // on the new sockets
+        self.waiter.waker().wake_by_ref();
+    }
+
+    fn block_on<F, T>(&self, timeout: Option<Duration>, f: F) -> AxResult<T>
     where
         F: FnMut() -> AxResult<T>,
     {
-        if self.is_nonblocking() {
-            f()
-        } else {
-            loop {
-                SOCKET_SET.poll_interfaces();
-                match f() {
-                    Ok(t) => return Ok(t),
-                    Err(AxError::WouldBlock) => axtask::yield_now(),
-                    Err(e) => return Err(e),
-                }
-            }
-        }
+        self.waiter.block_on(self.is_nonblocking(), timeout, f)
     }
 }
 
 impl Drop for UdpSocket {
     fn drop(&mut self) {
         self.shutdown().ok();
-        SOCKET_SET.remove(self.handle);
+        self.sockets.get_mut().drain(..).for_each(SocketRef::remove);
+        if let Some(local_endpoint) = *self.local_addr.get_mut() {
+            release_port(IpListenEndpoint {
+                addr: (!is_unspecified(local_endpoint.addr)).then_some(local_endpoint.addr),
+                port: local_endpoint.port,
+            });
+        }
+    }
+}
+
+/// Records that a socket is bound to `endpoint`.
+///
+/// It fails if another socket is bound to an overlapping address on the same
+/// port, unless both of them allow address reuse.
+fn claim_port(endpoint: IpListenEndpoint, reuse_addr: bool) -> AxResult {
+    let mut ports = BOUND_PORTS.lock();
+    let conflict = ports.get(&endpoint.port).is_some_and(|bindings| {
+        bindings.iter().any(|&(addr, reuse)| {
+            let overlaps = addr.is_none() || endpoint.addr.is_none() || addr == endpoint.addr;
+            overlaps && !(reuse && reuse_addr)
+        })
+    });
+    if conflict {
+        return ax_err!(AddrInUse, "socket bind() failed");
+    }
+    ports
+        .entry(endpoint.port)
+        .or_default()
+        .push((endpoint.addr, reuse_addr));
+    Ok(())
+}
+
+fn release_port(endpoint: IpListenEndpoint) {
+    let mut ports = BOUND_PORTS.lock();
+    if let Some(bindings) = ports.get_mut(&endpoint.port) {
+        if let Some(pos) = bindings.iter().position(|&(addr, _)| addr == endpoint.addr) {
+            bindings.swap_remove(pos);
+        }
+        if bindings.is_empty() {
+            ports.remove(&endpoint.port);
+        }
     }
 }
 
+/// Whether `addr` is the limited broadcast address, or the broadcast address
+/// of a subnet of the interface.
+fn is_broadcast(iface: usize, addr: IpAddress) -> bool {
+    let IpAddress::Ipv4(addr) = addr else {
+        return false;
+    };
+    addr.is_broadcast()
+        || INTERFACES[iface]
+            .iface
+            .lock()
+            .ip_addrs()
+            .iter()
+            .any(|cidr| matches!(cidr, IpCidr::Ipv4(cidr) if cidr.broadcast() == Some(addr)))
+}
+
 fn get_ephemeral_port() -> AxResult<u16> {
     const PORT_START: u16 = 0xc000;
     const PORT_END: u16 = 0xffff;
     static CURR: Mutex<u16> = Mutex::new(PORT_START);
     let mut curr = CURR.lock();
 
-    let port = *curr;
-    if *curr == PORT_END {
-        *curr = PORT_START;
-    } else {
-        *curr += 1;
+    for _ in PORT_START..=PORT_END {
+        let port = *curr;
+        if *curr == PORT_END {
+            *curr = PORT_START;
+        } else {
+            *curr += 1;
+        }
+        if !BOUND_PORTS.lock().contains_key(&port) {
+            return Ok(port);
+        }
     }
-    Ok(port)
+    ax_err!(AddrInUse, "no avaliable ports!")
 }
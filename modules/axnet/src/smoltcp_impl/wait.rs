@@ -0,0 +1,318 @@
+//! Blocking socket operations, and the network worker task.
+//!
+//! When a blocking operation cannot complete, it registers the waker of the
+//! socket on the smoltcp sockets it waits for, and then waits until smoltcp
+//! wakes it or the timeout expires.
+//!
+//! With the `irq` and `multitask` features, and if every NIC has a known
+//! interrupt line, the network stack is driven by interrupts: NIC interrupts
+//! wake a worker task, which polls the interfaces and then sleeps until the
+//! next smoltcp timer, and blocked operations sleep on the wait queues of
+//! their sockets. Otherwise, blocked operations keep polling the interfaces
+//! and yielding the CPU, since smoltcp only makes progress when polled.
+//!
+//! Other wakers can watch a socket as well, e.g., to wake up the tasks polling
+//! it in `epoll`, see [`SocketWaiter::watch`].
+
+use alloc::sync::Arc;
+use alloc::task::Wake;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+use core::time::Duration;
+
+use axerrno::{AxError, AxResult};
+use axhal::time::monotonic_time;
+use axsync::spin::SpinNoIrq;
+
+use super::poll_interfaces;
+
+#[cfg(all(feature = "irq", feature = "multitask"))]
+pub(super) use self::irq_driven::{init, wake_worker};
+
+/// Does nothing, as the network is polled by blocked operations.
+#[cfg(not(all(feature = "irq", feature = "multitask")))]
+pub(super) fn init(_nic_irqs: alloc::vec::Vec<Option<axdriver::DeviceIrq>>) {}
+
+/// Does nothing, as no worker task is running.
+#[cfg(not(all(feature = "irq", feature = "multitask")))]
+pub(super) fn wake_worker() {}
+
+/// Whether the interfaces are polled by the worker task on NIC interrupts.
+fn is_irq_driven() -> bool {
+    cfg_if::cfg_if! {
+        if #[cfg(all(feature = "irq", feature = "multitask"))] {
+            irq_driven::is_enabled()
+        } else {
+            false
+        }
+    }
+}
+
+/// Where the blocking operations of a socket wait.
+pub(super) struct SocketWaiter {
+    inner: Arc<WaiterInner>,
+    waker: Waker,
+}
+
+struct WaiterInner {
+    woken: AtomicBool,
+    /// Woken once, the next time the socket is woken.
+    watchers: SpinNoIrq<Vec<Waker>>,
+    #[cfg(all(feature = "irq", feature = "multitask"))]
+    wq: axtask::WaitQueue,
+}
+
+impl Wake for WaiterInner {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.woken.store(true, Ordering::Release);
+        #[cfg(all(feature = "irq", feature = "multitask"))]
+        self.wq.notify_all(false);
+        let watchers = core::mem::take(&mut *self.watchers.lock());
+        for watcher in watchers {
+            watcher.wake();
+        }
+    }
+}
+
+impl SocketWaiter {
+    pub fn new() -> Self {
+        let inner = Arc::new(WaiterInner {
+            woken: AtomicBool::new(false),
+            watchers: SpinNoIrq::new(Vec::new()),
+            #[cfg(all(feature = "irq", feature = "multitask"))]
+            wq: axtask::WaitQueue::new(),
+        });
+        Self {
+            waker: Waker::from(inner.clone()),
+            inner,
+        }
+    }
+
+    /// The waker to register on smoltcp sockets.
+    pub fn waker(&self) -> &Waker {
+        &self.waker
+    }
+
+    /// Registers `waker` to be woken once, the next time the socket is woken.
+    ///
+    /// The caller must then register the [`waker`](Self::waker) on the
+    /// sockets whose state it watches. Returns `false` if the network stack
+    /// is not driven by interrupts, as the socket is then only woken while
+    /// the interfaces are polled.
+    pub fn watch(&self, waker: &Waker) -> bool {
+        let mut watchers = self.inner.watchers.lock();
+        if !watchers.iter().any(|w| w.will_wake(waker)) {
+            watchers.push(waker.clone());
+        }
+        is_irq_driven()
+    }
+
+    /// Calls `f` until it returns anything other than
+    /// [`Err(WouldBlock)`](AxError::WouldBlock), or `timeout` expires.
+    ///
+    /// Before returning `Err(WouldBlock)`, `f` must register the
+    /// [`waker`](Self::waker) on the sockets whose state it waits for. If
+    /// `nonblocking` is true, `f` is called only once.
+    pub fn block_on<F, T>(
+        &self,
+        nonblocking: bool,
+        timeout: Option<Duration>,
+        mut f: F,
+    ) -> AxResult<T>
+    where
+        F: FnMut() -> AxResult<T>,
+    {
+        let deadline = timeout.map(|t| monotonic_time() + t);
+        let res = loop {
+            self.inner.woken.store(false, Ordering::Release);
+            if !nonblocking {
+                poll_interfaces();
+            }
+            match f() {
+                Err(AxError::WouldBlock) if !nonblocking => {
+                    if !self.wait(deadline) {
+                        break Err(AxError::WouldBlock);
+                    }
+                }
+                res => break res,
+            }
+        };
+        if res.is_ok() {
+            // let the stack process what the operation has changed, e.g.,
+            // transmit the queued data
+            wake_worker();
+        }
+        res
+    }
+
+    /// Waits until the waker is woken or `deadline` passes.
+    ///
+    /// Returns `false` if `deadline` has passed.
+    fn wait(&self, deadline: Option<Duration>) -> bool {
+        let now = monotonic_time();
+        if deadline.is_some_and(|d| now >= d) {
+            return false;
+        }
+        #[cfg(all(feature = "irq", feature = "multitask"))]
+        if is_irq_driven() {
+            let woken = || self.inner.woken.load(Ordering::Acquire);
+            match deadline {
+                Some(deadline) => {
+                    self.inner.wq.wait_timeout_until(deadline - now, woken);
+                }
+                None => self.inner.wq.wait_until(woken),
+            }
+            return true;
+        }
+        axtask::yield_now();
+        true
+    }
+}
+
+#[cfg(all(feature = "irq", feature = "multitask"))]
+mod irq_driven {
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use core::time::Duration;
+
+    use axdriver::DeviceIrq;
+    use axtask::WaitQueue;
+    use lazyinit::LazyInit;
+
+    use super::super::{INTERFACES, poll_interfaces};
+
+    /// The longest time that the worker sleeps if no smoltcp timer is
+    /// pending.
+    const MAX_POLL_DELAY: Duration = Duration::from_secs(1);
+
+    static ENABLED: AtomicBool = AtomicBool::new(false);
+    static NIC_IRQS: LazyInit<Vec<DeviceIrq>> = LazyInit::new();
+    static WORKER_WQ: WaitQueue = WaitQueue::new();
+    static WORKER_PENDING: AtomicBool = AtomicBool::new(false);
+
+    pub fn is_enabled() -> bool {
+        ENABLED.load(Ordering::Acquire)
+    }
+
+    /// Wakes the worker task to poll the interfaces.
+    pub fn wake_worker() {
+        WORKER_PENDING.store(true, Ordering::Release);
+        WORKER_WQ.notify_one(false);
+    }
+
+    fn handle_nic_irq() {
+        for irq in NIC_IRQS.iter() {
+            irq.ack();
+        }
+        wake_worker();
+    }
+
+    fn worker() {
+        loop {
+            WORKER_PENDING.store(false, Ordering::Release);
+            poll_interfaces();
+            let delay = INTERFACES
+                .iter()
+                .filter_map(|iface| iface.poll_delay())
+                .fold(MAX_POLL_DELAY, Duration::min);
+            WORKER_WQ.wait_timeout_until(delay, || WORKER_PENDING.load(Ordering::Acquire));
+        }
+    }
+
+    /// Registers the IRQ handlers of the NICs, and starts the worker task.
+    ///
+    /// `nic_irqs` holds the interrupt line of each NIC. Nothing is done if any
+    /// of them is unknown.
+    pub fn init(nic_irqs: Vec<Option<DeviceIrq>>) {
+        let Some(irqs) = nic_irqs.into_iter().collect::<Option<Vec<_>>>() else {
+            info!("  NIC without a known IRQ, polling the network actively");
+            return;
+        };
+        NIC_IRQS.init_once(irqs);
+        for (i, irq) in NIC_IRQS.iter().enumerate() {
+            if NIC_IRQS[..i].iter().any(|prev| prev.irq_num == irq.irq_num) {
+                continue; // shared by NICs
+            }
+            if !axhal::irq::register_handler(irq.irq_num, handle_nic_irq) {
+                warn!(
+                    "  failed to register NIC IRQ {}, polling the network actively",
+                    irq.irq_num
+                );
+                return;
+            }
+            info!("  registered NIC IRQ {}", irq.irq_num);
+        }
+        axtask::spawn(worker);
+        ENABLED.store(true, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.wake_by_ref();
+        }
+
+        fn wake_by_ref(self: &Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn counting_waker() -> (Arc<CountingWaker>, Waker) {
+        let count = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        (count.clone(), Waker::from(count))
+    }
+
+    #[test]
+    fn test_watchers_woken_once() {
+        let waiter = SocketWaiter::new();
+        let (count, waker) = counting_waker();
+        // registered twice, woken once
+        waiter.watch(&waker);
+        waiter.watch(&waker);
+        waiter.waker().wake_by_ref();
+        assert_eq!(count.0.load(Ordering::Relaxed), 1);
+        assert!(waiter.inner.woken.load(Ordering::Acquire));
+        // watchers must register again to be woken again
+        waiter.waker().wake_by_ref();
+        assert_eq!(count.0.load(Ordering::Relaxed), 1);
+        waiter.watch(&waker);
+        waiter.waker().wake_by_ref();
+        assert_eq!(count.0.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn test_not_irq_driven() {
+        let waiter = SocketWaiter::new();
+        let (_, waker) = counting_waker();
+        // no NIC interrupt is registered, so the watcher has to poll
+        assert!(!waiter.watch(&waker));
+        assert!(!is_irq_driven());
+    }
+
+    #[test]
+    fn test_block_on_nonblocking() {
+        let waiter = SocketWaiter::new();
+        let mut calls = 0;
+        let res: AxResult<()> = waiter.block_on(true, None, || {
+            calls += 1;
+            Err(AxError::WouldBlock)
+        });
+        assert_eq!(res, Err(AxError::WouldBlock));
+        assert_eq!(calls, 1);
+        let res = waiter.block_on(true, Some(Duration::ZERO), || Ok(42));
+        assert_eq!(res, Ok(42));
+    }
+}
@@ -0,0 +1,106 @@
+//! Devices in `/dev` of the drivers that this crate initializes, other than
+//! the block devices.
+
+use alloc::sync::Arc;
+use core::ptr;
+
+use axerrno::AxResult;
+use axfs::devfs::{CharDevice, register_char_device};
+
+/// The framebuffer of [`axdisplay`], as `/dev/fb0`.
+///
+/// Writes are shown on the screen immediately.
+struct FramebufferDev {
+    base: *mut u8,
+    size: usize,
+}
+
+// SAFETY: the framebuffer is only accessed by copies, which race with each
+// other as writes to a screen do, but never make references to it.
+unsafe impl Send for FramebufferDev {}
+unsafe impl Sync for FramebufferDev {}
+
+impl FramebufferDev {
+    /// Returns the start and the length of the range of the framebuffer from
+    /// `offset`, of at most `len` bytes.
+    fn range(&self, offset: u64, len: usize) -> (usize, usize) {
+        let start = offset.min(self.size as u64) as usize;
+        (start, len.min(self.size - start))
+    }
+
+    /// Copies from the framebuffer at `offset` into `buf`.
+    fn copy_to(&self, offset: u64, buf: &mut [u8]) -> usize {
+        let (start, len) = self.range(offset, buf.len());
+        // SAFETY: the range is in the framebuffer, which is mapped as long as
+        // the display exists, and `buf` cannot overlap it
+        unsafe { ptr::copy(self.base.add(start), buf.as_mut_ptr(), len) };
+        len
+    }
+
+    /// Copies `buf` into the framebuffer at `offset`.
+    fn copy_from(&self, offset: u64, buf: &[u8]) -> usize {
+        let (start, len) = self.range(offset, buf.len());
+        // SAFETY: as in `copy_to`
+        unsafe { ptr::copy(buf.as_ptr(), self.base.add(start), len) };
+        len
+    }
+}
+
+impl CharDevice for FramebufferDev {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> AxResult<usize> {
+        Ok(self.copy_to(offset, buf))
+    }
+
+    fn write_at(&self, offset: u64, buf: &[u8]) -> AxResult<usize> {
+        let len = self.copy_from(offset, buf);
+        axdisplay::framebuffer_flush();
+        Ok(len)
+    }
+
+    fn size(&self) -> u64 {
+        self.size as u64
+    }
+
+    fn flush(&self) -> AxResult {
+        axdisplay::framebuffer_flush();
+        Ok(())
+    }
+}
+
+/// Adds `/dev/fb0`, after the display is initialized.
+pub(crate) fn init_display() {
+    let info = axdisplay::framebuffer_info();
+    let dev = FramebufferDev {
+        base: info.fb_base_vaddr as *mut u8,
+        size: info.fb_size,
+    };
+    register_char_device("fb0", Arc::new(dev)).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_framebuffer_copy() {
+        let mut fb = [0u8; 16];
+        let dev = FramebufferDev {
+            base: fb.as_mut_ptr(),
+            size: fb.len(),
+        };
+        assert_eq!(dev.copy_from(4, &[1; 8]), 8);
+        // truncated at the end
+        assert_eq!(dev.copy_from(14, &[2; 8]), 2);
+        assert_eq!(dev.copy_from(16, &[3; 8]), 0);
+        assert_eq!(dev.copy_from(u64::MAX, &[3; 8]), 0);
+
+        let mut buf = [0xff; 20];
+        assert_eq!(dev.copy_to(0, &mut buf), 16);
+        assert_eq!(buf[..16], [0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 2, 2]);
+        assert_eq!(buf[16..], [0xff; 4]);
+        assert_eq!(dev.copy_to(12, &mut buf[..2]), 2);
+        assert_eq!(buf[..2], [0, 0]);
+        assert_eq!(dev.copy_to(100, &mut buf), 0);
+        assert_eq!(dev.size(), 16);
+    }
+}
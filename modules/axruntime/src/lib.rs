@@ -22,12 +22,22 @@
 #[macro_use]
 extern crate axlog;
 
+#[cfg(feature = "fs")]
+extern crate alloc;
+
 #[cfg(all(target_os = "none", not(test)))]
 mod lang_items;
 
 #[cfg(feature = "smp")]
 mod mp;
 
+#[cfg(all(feature = "fs", feature = "display"))]
+mod devfs;
+#[cfg(feature = "fs")]
+mod procfs;
+#[cfg(feature = "fs")]
+mod sysfs;
+
 #[cfg(feature = "smp")]
 pub use self::mp::rust_main_secondary;
 
@@ -83,12 +93,14 @@ impl axlog::LogIf for LogIfImpl {
     }
 }
 
+#[cfg(feature = "irq")]
+use core::sync::atomic::AtomicU64;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
 static INITED_CPUS: AtomicUsize = AtomicUsize::new(0);
 
 fn is_init_ok() -> bool {
-    INITED_CPUS.load(Ordering::Acquire) == axconfig::SMP
+    INITED_CPUS.load(Ordering::Acquire) == axhal::dtb::cpu_count()
 }
 
 /// The main entry point of the ArceOS runtime.
@@ -129,6 +141,12 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
     axlog::set_max_level(option_env!("AX_LOG").unwrap_or("")); // no effect if set `log-level-*` features
     info!("Logging is enabled.");
     info!("Primary CPU {} started, dtb = {:#x}.", cpu_id, dtb);
+    if axhal::dtb::is_present() {
+        info!(
+            "Platform discovered from device tree: {} CPU(s).",
+            axhal::dtb::cpu_count()
+        );
+    }
 
     info!("Found physcial memory regions:");
     for r in axhal::mem::memory_regions() {
@@ -159,13 +177,26 @@ pub extern "C" fn rust_main(cpu_id: usize, dtb: usize) -> ! {
         let all_devices = axdriver::init_drivers();
 
         #[cfg(feature = "fs")]
-        axfs::init_filesystems(all_devices.block);
+        {
+            axfs::init_filesystems(all_devices.block);
+            procfs::init();
+        }
 
         #[cfg(feature = "net")]
         axnet::init_network(all_devices.net);
 
         #[cfg(feature = "display")]
         axdisplay::init_display(all_devices.display);
+
+        #[cfg(feature = "fs")]
+        {
+            sysfs::init();
+            #[cfg(feature = "display")]
+            {
+                devfs::init_display();
+                sysfs::init_display();
+            }
+        }
     }
 
     #[cfg(feature = "smp")]
@@ -232,25 +263,46 @@ fn init_allocator() {
     }
 }
 
+/// The frequency of timer ticks, which drive the scheduler.
+#[cfg(feature = "irq")]
+static TICKS_PER_SEC: AtomicU64 = AtomicU64::new(axconfig::TICKS_PER_SEC as u64);
+
+/// The maximum frequency of timer ticks.
+#[cfg(feature = "irq")]
+const MAX_TICKS_PER_SEC: u64 = 10_000;
+
+#[cfg(feature = "irq")]
+fn ticks_per_sec() -> u64 {
+    TICKS_PER_SEC.load(Ordering::Relaxed)
+}
+
+/// Sets the frequency of timer ticks, which takes effect from the next tick.
+#[cfg(all(feature = "irq", feature = "fs"))]
+fn set_ticks_per_sec(ticks: u64) -> axerrno::AxResult {
+    if !(1..=MAX_TICKS_PER_SEC).contains(&ticks) {
+        return axerrno::ax_err!(InvalidInput, "ticks per second out of range");
+    }
+    TICKS_PER_SEC.store(ticks, Ordering::Relaxed);
+    Ok(())
+}
+
 #[cfg(feature = "irq")]
 fn init_interrupt() {
     use axhal::time::TIMER_IRQ_NUM;
 
     // Setup timer interrupt handler
-    const PERIODIC_INTERVAL_NANOS: u64 =
-        axhal::time::NANOS_PER_SEC / axconfig::TICKS_PER_SEC as u64;
-
     #[percpu::def_percpu]
     static NEXT_DEADLINE: u64 = 0;
 
     fn update_timer() {
+        let interval_nanos = axhal::time::NANOS_PER_SEC / ticks_per_sec();
         let now_ns = axhal::time::monotonic_time_nanos();
         // Safety: we have disabled preemption in IRQ handler.
         let mut deadline = unsafe { NEXT_DEADLINE.read_current_raw() };
         if now_ns >= deadline {
-            deadline = now_ns + PERIODIC_INTERVAL_NANOS;
+            deadline = now_ns + interval_nanos;
         }
-        unsafe { NEXT_DEADLINE.write_current_raw(deadline + PERIODIC_INTERVAL_NANOS) };
+        unsafe { NEXT_DEADLINE.write_current_raw(deadline + interval_nanos) };
         axhal::time::set_oneshot_timer(deadline);
     }
 
@@ -10,9 +10,10 @@ static ENTERED_CPUS: AtomicUsize = AtomicUsize::new(1);
 
 #[allow(clippy::absurd_extreme_comparisons)]
 pub fn start_secondary_cpus(primary_cpu_id: usize) {
+    let cpu_count = axhal::dtb::cpu_count();
     let mut logic_cpu_id = 0;
-    for i in 0..SMP {
-        if i != primary_cpu_id && logic_cpu_id < SMP - 1 {
+    for i in 0..cpu_count {
+        if i != primary_cpu_id && logic_cpu_id < cpu_count - 1 {
             let stack_top = virt_to_phys(VirtAddr::from(unsafe {
                 SECONDARY_BOOT_STACK[logic_cpu_id].as_ptr_range().end as usize
             }));
@@ -0,0 +1,135 @@
+//! Files in `/proc` about the kernel state that this crate initializes.
+
+use alloc::{format, string::String};
+use core::fmt::Write;
+
+use axfs::procfs::add_file;
+
+/// Adds the files about memory, CPUs, time, interrupts and tasks to procfs.
+pub(crate) fn init() {
+    #[cfg(feature = "alloc")]
+    add_file("meminfo", meminfo).unwrap();
+    add_file("cpuinfo", cpuinfo).unwrap();
+    add_file("uptime", uptime).unwrap();
+    #[cfg(feature = "irq")]
+    add_file("interrupts", interrupts).unwrap();
+    #[cfg(feature = "multitask")]
+    tasks::init();
+}
+
+#[cfg(feature = "alloc")]
+fn meminfo() -> String {
+    const PAGE_SIZE: usize = 0x1000;
+    let allocator = axalloc::global_allocator();
+    let used = allocator.used_pages() * PAGE_SIZE + allocator.used_bytes();
+    let free = allocator.available_pages() * PAGE_SIZE;
+    format!(
+        "MemTotal:{:>12} kB\nMemFree:{:>13} kB\nMemAvailable:{:>8} kB\nHeapUsed:{:>12} kB\nHeapFree:{:>12} kB\n",
+        (used + free) / 1024,
+        free / 1024,
+        free / 1024,
+        allocator.used_bytes() / 1024,
+        allocator.available_bytes() / 1024,
+    )
+}
+
+fn cpuinfo() -> String {
+    let mut info = String::new();
+    for cpu in 0..axhal::dtb::cpu_count() {
+        let _ = write!(
+            info,
+            "processor\t: {cpu}\narch\t\t: {}\nplatform\t: {}\n\n",
+            axconfig::ARCH,
+            axconfig::PLATFORM,
+        );
+    }
+    info
+}
+
+fn uptime() -> String {
+    let now = axhal::time::monotonic_time();
+    // the idle time is not accounted
+    format!("{}.{:02} 0.00\n", now.as_secs(), now.subsec_millis() / 10)
+}
+
+/// The dispatches of each IRQ, summed over all CPUs.
+#[cfg(feature = "irq")]
+fn interrupts() -> String {
+    let mut info = String::new();
+    for (irq, count) in axhal::irq::irq_counts() {
+        if irq == axhal::time::TIMER_IRQ_NUM {
+            let _ = writeln!(info, "LOC: {count:>10}   Local timer interrupts");
+        } else {
+            let _ = writeln!(info, "{irq:>3}: {count:>10}");
+        }
+    }
+    info
+}
+
+#[cfg(feature = "multitask")]
+mod tasks {
+    use alloc::sync::Arc;
+    use alloc::{format, string::String, vec::Vec};
+
+    use axfs::procfs::{ProcDir, ProcFile, add_entries};
+    use axtask::{AxTaskRef, TaskState, WeakAxTaskRef};
+
+    /// Adds `/proc/<tid>` for each task, and `/proc/self` for the current one.
+    pub(super) fn init() {
+        add_entries("", || {
+            let mut entries: Vec<(String, _)> = axtask::tasks()
+                .iter()
+                .map(|task| (format!("{}", task.id().as_u64()), task_dir(task) as _))
+                .collect();
+            let current = axtask::current();
+            entries.push(("self".into(), task_dir(current.as_task_ref()) as _));
+            entries
+        })
+        .unwrap();
+    }
+
+    fn task_dir(task: &AxTaskRef) -> Arc<ProcDir> {
+        let dir = ProcDir::new();
+        let weak = Arc::downgrade(task);
+        dir.add("status", ProcFile::new(move || with_task(&weak, status)));
+        let weak = Arc::downgrade(task);
+        dir.add("stat", ProcFile::new(move || with_task(&weak, stat)));
+        dir
+    }
+
+    /// Generates the content from the task, or nothing if it is dropped.
+    fn with_task(task: &WeakAxTaskRef, content: fn(&AxTaskRef) -> String) -> String {
+        task.upgrade()
+            .map(|task| content(&task))
+            .unwrap_or_default()
+    }
+
+    fn state_char(task: &AxTaskRef) -> char {
+        match task.state() {
+            TaskState::Running | TaskState::Ready => 'R',
+            TaskState::Blocked => 'S',
+            TaskState::Exited => 'Z',
+        }
+    }
+
+    fn status(task: &AxTaskRef) -> String {
+        let id = task.id().as_u64();
+        format!(
+            "Name:\t{}\nState:\t{}\nTgid:\t{id}\nPid:\t{id}\nPPid:\t0\n",
+            task.name(),
+            state_char(task),
+        )
+    }
+
+    /// Generates the 52 fields of `stat`, of which only the ID, the name and
+    /// the state are known.
+    fn stat(task: &AxTaskRef) -> String {
+        let id = task.id().as_u64();
+        let mut stat = format!("{id} ({}) {} 0 {id} {id}", task.name(), state_char(task));
+        for _ in 6..=52 {
+            stat.push_str(" 0");
+        }
+        stat.push('\n');
+        stat
+    }
+}
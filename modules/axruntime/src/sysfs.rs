@@ -0,0 +1,95 @@
+//! Files in `/sys` about the hardware and the tunables of this crate.
+
+use alloc::{format, string::String};
+
+use axerrno::{AxResult, ax_err};
+use axfs::sysfs::{add_file, add_writable_file};
+
+/// Adds the files about the clock source, CPUs and the tunables to sysfs.
+pub(crate) fn init() {
+    let dir = "devices/system/clocksource/clocksource0";
+    add_file(&format!("{dir}/current_clocksource"), clock_source).unwrap();
+    add_file(&format!("{dir}/available_clocksource"), clock_source).unwrap();
+
+    let cpus = axhal::dtb::cpu_count();
+    let range = move || match cpus {
+        1 => String::from("0\n"),
+        n => format!("0-{}\n", n - 1),
+    };
+    let dir = "devices/system/cpu";
+    add_file(&format!("{dir}/online"), range).unwrap();
+    add_file(&format!("{dir}/possible"), range).unwrap();
+    add_file(&format!("{dir}/present"), range).unwrap();
+    for cpu in 0..cpus {
+        // each CPU is a core of the only package
+        let dir = format!("{dir}/cpu{cpu}");
+        add_file(&format!("{dir}/online"), || "1\n".into()).unwrap();
+        add_file(&format!("{dir}/topology/core_id"), move || {
+            format!("{cpu}\n")
+        })
+        .unwrap();
+        add_file(&format!("{dir}/topology/physical_package_id"), || {
+            "0\n".into()
+        })
+        .unwrap();
+    }
+
+    // no effect if the level is fixed by the `log-level-*` features of axlog
+    add_writable_file(
+        "module/axlog/parameters/level",
+        || format!("{}\n", axlog::max_level()),
+        set_log_level,
+    )
+    .unwrap();
+
+    #[cfg(feature = "irq")]
+    add_writable_file(
+        "module/axruntime/parameters/ticks_per_sec",
+        || format!("{}\n", crate::ticks_per_sec()),
+        |value| match value.trim().parse() {
+            Ok(ticks) => crate::set_ticks_per_sec(ticks),
+            Err(_) => ax_err!(InvalidInput, "invalid ticks per second"),
+        },
+    )
+    .unwrap();
+}
+
+/// Adds the files about the framebuffer to sysfs, after the display is
+/// initialized.
+#[cfg(feature = "display")]
+pub(crate) fn init_display() {
+    let info = axdisplay::framebuffer_info();
+    let (width, height) = (info.width as usize, info.height as usize);
+    let bits_per_pixel = info.fb_size * 8 / (width * height).max(1);
+    let dir = "class/graphics/fb0";
+    add_file(&format!("{dir}/virtual_size"), move || {
+        format!("{width},{height}\n")
+    })
+    .unwrap();
+    add_file(&format!("{dir}/bits_per_pixel"), move || {
+        format!("{bits_per_pixel}\n")
+    })
+    .unwrap();
+    add_file(&format!("{dir}/stride"), move || {
+        format!("{}\n", width * bits_per_pixel / 8)
+    })
+    .unwrap();
+}
+
+fn clock_source() -> String {
+    format!("{}\n", axhal::time::CLOCK_SOURCE)
+}
+
+fn set_log_level(level: &str) -> AxResult {
+    let level = level.trim();
+    match ["off", "error", "warn", "info", "debug", "trace"]
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(level))
+    {
+        Some(name) => {
+            axlog::set_max_level(name);
+            Ok(())
+        }
+        None => ax_err!(InvalidInput, "invalid log level"),
+    }
+}
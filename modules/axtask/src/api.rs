@@ -137,6 +137,12 @@ where
     spawn_raw(f, "".into(), axconfig::TASK_STACK_SIZE)
 }
 
+/// Returns all the tasks that are not dropped yet, sorted by their IDs,
+/// including the exited ones that are not joined.
+pub fn tasks() -> alloc::vec::Vec<AxTaskRef> {
+    crate::task::all_tasks()
+}
+
 /// Set the priority for current task.
 ///
 /// The range of the priority is dependent on the underlying scheduler. For
@@ -91,21 +91,26 @@ pub(crate) fn current_run_queue<G: BaseGuard>() -> CurrentRunQueueRef<'static, G
 ///
 /// ## Panics
 ///
-/// This function will panic if `cpu_mask` is empty, indicating that there are no available CPUs for task execution.
+/// This function will panic if `cpu_mask` contains none of the CPUs that are brought up, indicating
+/// that there are no available CPUs for task execution.
 ///
 #[cfg(feature = "smp")]
-// The modulo operation is safe here because `axconfig::SMP` is always greater than 1 with "smp" enabled.
+// The modulo operation is safe here because the CPU count is always greater than 0.
 #[allow(clippy::modulo_one)]
 #[inline]
 fn select_run_queue_index(cpumask: AxCpuMask) -> usize {
     use core::sync::atomic::{AtomicUsize, Ordering};
     static RUN_QUEUE_INDEX: AtomicUsize = AtomicUsize::new(0);
 
-    assert!(!cpumask.is_empty(), "No available CPU for task execution");
-
-    // Round-robin selection of the run queue index.
+    // Round-robin selection of the run queue index, among the CPUs that are
+    // actually brought up (which may be fewer than `axconfig::SMP`).
+    let cpu_count = axhal::dtb::cpu_count();
+    assert!(
+        (0..cpu_count).any(|index| cpumask.get(index)),
+        "No available CPU for task execution"
+    );
     loop {
-        let index = RUN_QUEUE_INDEX.fetch_add(1, Ordering::SeqCst) % axconfig::SMP;
+        let index = RUN_QUEUE_INDEX.fetch_add(1, Ordering::SeqCst) % cpu_count;
         if cpumask.get(index) {
             return index;
         }
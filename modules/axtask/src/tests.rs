@@ -128,3 +128,27 @@ fn test_task_join() {
         assert_eq!(tasks[i].join(), Some(i as _));
     }
 }
+
+#[test]
+fn test_task_list() {
+    let _lock = SERIAL.lock();
+    INIT.call_once(axtask::init_scheduler);
+
+    let task = axtask::spawn_raw(|| axtask::exit(0), "listed".into(), 0x1000);
+    let id = task.id();
+    let listed = axtask::tasks();
+    assert!(listed.iter().any(|t| t.id() == id && t.name() == "listed"));
+    assert!(listed.iter().any(|t| t.id() == current().id()));
+    assert!(
+        listed
+            .windows(2)
+            .all(|w| w[0].id().as_u64() < w[1].id().as_u64())
+    );
+
+    assert_eq!(task.join(), Some(0));
+    drop(listed);
+    drop(task);
+    while axtask::tasks().iter().any(|t| t.id() == id) {
+        axtask::yield_now(); // wait for the task to be dropped by the gc task
+    }
+}
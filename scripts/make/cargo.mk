@@ -51,5 +51,9 @@ endef
 define unit_test
   $(call run_cmd,cargo test,-p axfs $(1) $(verbose) -- --nocapture)
   $(call run_cmd,cargo test,-p axfs $(1) --features "myfs" $(verbose) -- --nocapture)
+  $(call run_cmd,cargo test,-p axfs $(1) --features "page-cache" $(verbose) -- --nocapture)
+  $(call run_cmd,cargo test,-p axfs $(1) --features "lwext4_rs" $(verbose) -- --nocapture)
   $(call run_cmd,cargo test,--workspace --exclude axfs $(1) $(verbose) -- --nocapture)
+  $(call run_cmd,cargo test,-p axruntime $(1) --features "fs display" $(verbose) -- --nocapture)
+  $(call run_cmd,cargo test,-p arceos_posix_api $(1) --features "unix pipe poll epoll mmap multitask signal" $(verbose) -- --nocapture)
 endef
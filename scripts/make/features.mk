@@ -13,7 +13,7 @@
 
 ifeq ($(APP_TYPE),c)
   ax_feat_prefix := axfeat/
-  lib_features := fp_simd irq alloc multitask fs net fd pipe select epoll
+  lib_features := fp_simd irq alloc multitask signal fs net unix fd pipe select poll epoll mmap
 else
   ifeq ($(NO_AXSTD),y)
     ax_feat_prefix := axfeat/
@@ -31,7 +31,7 @@ ifeq ($(APP_TYPE), c)
   ifneq ($(wildcard $(APP)/features.txt),)    # check features.txt exists
     override FEATURES += $(shell cat $(APP)/features.txt)
   endif
-  ifneq ($(filter fs net pipe select epoll,$(FEATURES)),)
+  ifneq ($(filter fs net unix pipe select epoll,$(FEATURES)),)
     override FEATURES += fd
   endif
 endif
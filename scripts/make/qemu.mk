@@ -50,6 +50,12 @@ qemu_args-$(BLK) += \
   -device virtio-blk-$(vdev-suffix),drive=disk0 \
   -drive id=disk0,if=none,format=raw,file=$(DISK_IMG)
 
+ifneq ($(DATA_IMG),)
+  qemu_args-$(BLK) += \
+    -device virtio-blk-$(vdev-suffix),drive=disk1 \
+    -drive id=disk1,if=none,format=raw,file=$(DATA_IMG)
+endif
+
 qemu_args-$(NET) += \
   -device virtio-net-$(vdev-suffix),netdev=net0
 
@@ -2,6 +2,20 @@
 #include <stdio.h>
 #include <sys/mman.h>
 
+#ifdef AX_CONFIG_MMAP
+
+// TODO: remove this function in future work
+void *ax_mremap(void *old_address, size_t old_size, size_t new_size, int flags);
+
+void *mremap(void *old_address, size_t old_size, size_t new_size, int flags,
+             ... /* void *new_address */)
+{
+    // `MREMAP_FIXED` is not supported, so `new_address` is never used
+    return ax_mremap(old_address, old_size, new_size, flags);
+}
+
+#else
+
 // TODO:
 void *mmap(void *addr, size_t len, int prot, int flags, int fildes, off_t off)
 {
@@ -31,6 +45,8 @@ int mprotect(void *addr, size_t len, int prot)
     return 0;
 }
 
+#endif // AX_CONFIG_MMAP
+
 // TODO
 int madvise(void *addr, size_t len, int advice)
 {
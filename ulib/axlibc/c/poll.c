@@ -1,9 +1,13 @@
 #include <poll.h>
 #include <stdio.h>
 
+#ifndef AX_CONFIG_POLL
+
 // TODO
 int poll(struct pollfd *__fds, nfds_t __nfds, int __timeout)
 {
     unimplemented();
     return 0;
 }
+
+#endif // AX_CONFIG_POLL
@@ -46,35 +46,51 @@ int pthread_setname_np(pthread_t thread, const char *name)
     return 0;
 }
 
-int pthread_cond_init(pthread_cond_t *restrict c, const pthread_condattr_t *restrict a)
+int pthread_condattr_init(pthread_condattr_t *a)
 {
-    *c = (pthread_cond_t){0};
-    if (a) {
-        c->_c_clock = a->__attr & 0x7fffffff;
-        if (a->__attr >> 31)
-            c->_c_shared = (void *)-1;
-    }
+    *a = (pthread_condattr_t){0};
     return 0;
 }
 
-// TODO
-int pthread_cond_signal(pthread_cond_t *__cond)
+int pthread_condattr_destroy(pthread_condattr_t *a)
 {
-    unimplemented();
     return 0;
 }
 
-// TODO
-int pthread_cond_wait(pthread_cond_t *__restrict__ __cond, pthread_mutex_t *__restrict__ __mutex)
+int pthread_condattr_setclock(pthread_condattr_t *a, clockid_t clk)
 {
-    unimplemented();
+    if (clk != CLOCK_REALTIME && clk != CLOCK_MONOTONIC)
+        return EINVAL;
+    a->__attr &= 0x80000000;
+    a->__attr |= clk;
     return 0;
 }
 
-// TODO
-int pthread_cond_broadcast(pthread_cond_t *c)
+int pthread_condattr_getclock(const pthread_condattr_t *restrict a, clockid_t *restrict clk)
+{
+    *clk = a->__attr & 0x7fffffff;
+    return 0;
+}
+
+int pthread_rwlockattr_init(pthread_rwlockattr_t *a)
+{
+    *a = (pthread_rwlockattr_t){0};
+    return 0;
+}
+
+int pthread_rwlockattr_destroy(pthread_rwlockattr_t *a)
+{
+    return 0;
+}
+
+int pthread_barrierattr_init(pthread_barrierattr_t *a)
+{
+    *a = (pthread_barrierattr_t){0};
+    return 0;
+}
+
+int pthread_barrierattr_destroy(pthread_barrierattr_t *a)
 {
-    unimplemented();
     return 0;
 }
 
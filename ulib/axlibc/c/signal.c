@@ -3,18 +3,6 @@
 #include <stddef.h>
 #include <stdio.h>
 
-int sigaction_helper(int signum, const struct sigaction *act, struct sigaction *oldact,
-                     size_t sigsetsize)
-{
-    if (signum == SIGKILL || signum == SIGSTOP)
-        return -EINVAL;
-
-    if (oldact)
-        *oldact = (struct sigaction){0};
-
-    return 0;
-}
-
 void (*signal(int signum, void (*handler)(int)))(int)
 {
     struct sigaction old;
@@ -22,12 +10,26 @@ void (*signal(int signum, void (*handler)(int)))(int)
         .sa_handler = handler, .sa_flags = SA_RESTART, /* BSD signal semantics */
     };
 
-    if (sigaction_helper(signum, &act, &old, sizeof(sigset_t)) < 0)
+    if (sigaction(signum, &act, &old) < 0)
         return SIG_ERR;
 
     return (old.sa_flags & SA_SIGINFO) ? NULL : old.sa_handler;
 }
 
+#ifndef AX_CONFIG_SIGNAL
+
+int sigaction_helper(int signum, const struct sigaction *act, struct sigaction *oldact,
+                     size_t sigsetsize)
+{
+    if (signum == SIGKILL || signum == SIGSTOP)
+        return -EINVAL;
+
+    if (oldact)
+        *oldact = (struct sigaction){0};
+
+    return 0;
+}
+
 int sigaction(int sig, const struct sigaction *restrict act, struct sigaction *restrict oact)
 {
     return sigaction_helper(sig, act, oact, sizeof(sigset_t));
@@ -40,6 +42,8 @@ int kill(pid_t __pid, int __sig)
     return 0;
 }
 
+#endif // AX_CONFIG_SIGNAL
+
 int sigemptyset(sigset_t *set)
 {
     set->__bits[0] = 0;
@@ -52,10 +56,15 @@ int sigemptyset(sigset_t *set)
     return 0;
 }
 
-// TODO
-int raise(int __sig)
+int sigfillset(sigset_t *set)
 {
-    unimplemented();
+    set->__bits[0] = -1UL;
+    if (sizeof(long) == 4 || _NSIG > 65)
+        set->__bits[1] = -1UL;
+    if (sizeof(long) == 4 && _NSIG > 65) {
+        set->__bits[2] = -1UL;
+        set->__bits[3] = -1UL;
+    }
     return 0;
 }
 
@@ -70,6 +79,35 @@ int sigaddset(sigset_t *set, int sig)
     return 0;
 }
 
+int sigdelset(sigset_t *set, int sig)
+{
+    unsigned s = sig - 1;
+    if (s >= _NSIG - 1 || sig - 32U < 3) {
+        errno = EINVAL;
+        return -1;
+    }
+    set->__bits[s / 8 / sizeof *set->__bits] &= ~(1UL << (s & (8 * sizeof *set->__bits - 1)));
+    return 0;
+}
+
+int sigismember(const sigset_t *set, int sig)
+{
+    unsigned s = sig - 1;
+    if (s >= _NSIG - 1)
+        return 0;
+    return !!(set->__bits[s / 8 / sizeof *set->__bits] &
+              1UL << (s & (8 * sizeof *set->__bits - 1)));
+}
+
+#ifndef AX_CONFIG_SIGNAL
+
+// TODO
+int raise(int __sig)
+{
+    unimplemented();
+    return 0;
+}
+
 // TODO
 int pthread_sigmask(int __how, const sigset_t *restrict __newmask, sigset_t *restrict __oldmask)
 {
@@ -85,3 +123,5 @@ int pthread_kill(pthread_t t, int sig)
     return 0;
 }
 #endif
+
+#endif // AX_CONFIG_SIGNAL
@@ -47,6 +47,23 @@ uint16_t ntohs(uint16_t);
 #define IPPROTO_MPTCP    262
 #define IPPROTO_MAX      263
 
+#define IP_TOS             1
+#define IP_TTL             2
+#define IP_HDRINCL         3
+#define IP_OPTIONS         4
+#define IP_RECVOPTS        6
+#define IP_PKTINFO         8
+#define IP_MTU_DISCOVER    10
+#define IP_RECVERR         11
+#define IP_RECVTTL         12
+#define IP_RECVTOS         13
+#define IP_MTU             14
+#define IP_MULTICAST_IF    32
+#define IP_MULTICAST_TTL   33
+#define IP_MULTICAST_LOOP  34
+#define IP_ADD_MEMBERSHIP  35
+#define IP_DROP_MEMBERSHIP 36
+
 #define IPV6_ADDRFORM             1
 #define IPV6_2292PKTINFO          2
 #define IPV6_2292HOPOPTS          3
@@ -39,8 +39,41 @@ typedef struct {
         void *__p[12 * sizeof(int) / sizeof(void *)];
     } __u;
 } pthread_cond_t;
-#define _c_clock  __u.__i[4]
-#define _c_shared __u.__p[0]
+
+#define PTHREAD_COND_INITIALIZER {{{0}}}
+
+typedef struct {
+    union {
+        int __i[sizeof(long) == 8 ? 14 : 8];
+        volatile int __vi[sizeof(long) == 8 ? 14 : 8];
+        void *__p[sizeof(long) == 8 ? 7 : 8];
+    } __u;
+} pthread_rwlock_t;
+
+#define PTHREAD_RWLOCK_INITIALIZER {{{0}}}
+
+typedef struct {
+    unsigned __attr[2];
+} pthread_rwlockattr_t;
+
+typedef struct {
+    union {
+        int __i[sizeof(long) == 8 ? 8 : 5];
+        volatile int __vi[sizeof(long) == 8 ? 8 : 5];
+        void *__p[sizeof(long) == 8 ? 4 : 5];
+    } __u;
+} pthread_barrier_t;
+
+typedef struct {
+    unsigned __attr;
+} pthread_barrierattr_t;
+
+#define PTHREAD_BARRIER_SERIAL_THREAD (-1)
+
+typedef int pthread_once_t;
+#define PTHREAD_ONCE_INIT 0
+
+typedef unsigned pthread_key_t;
 
 typedef void *pthread_t;
 
@@ -70,10 +103,53 @@ int pthread_setname_np(pthread_t, const char *);
 
 int pthread_cond_init(pthread_cond_t *__restrict__ __cond,
                       const pthread_condattr_t *__restrict__ __cond_attr);
+int pthread_cond_destroy(pthread_cond_t *__cond);
 int pthread_cond_signal(pthread_cond_t *__cond);
 int pthread_cond_wait(pthread_cond_t *__restrict__ __cond, pthread_mutex_t *__restrict__ __mutex);
+#ifdef AX_CONFIG_IRQ
+int pthread_cond_timedwait(pthread_cond_t *__restrict__ __cond,
+                           pthread_mutex_t *__restrict__ __mutex,
+                           const struct timespec *__restrict__ __abstime);
+#endif
 int pthread_cond_broadcast(pthread_cond_t *);
 
+int pthread_condattr_init(pthread_condattr_t *);
+int pthread_condattr_destroy(pthread_condattr_t *);
+int pthread_condattr_setclock(pthread_condattr_t *, clockid_t);
+int pthread_condattr_getclock(const pthread_condattr_t *__restrict, clockid_t *__restrict);
+
+int pthread_rwlock_init(pthread_rwlock_t *__restrict, const pthread_rwlockattr_t *__restrict);
+int pthread_rwlock_destroy(pthread_rwlock_t *);
+int pthread_rwlock_rdlock(pthread_rwlock_t *);
+int pthread_rwlock_tryrdlock(pthread_rwlock_t *);
+#ifdef AX_CONFIG_IRQ
+int pthread_rwlock_timedrdlock(pthread_rwlock_t *__restrict, const struct timespec *__restrict);
+#endif
+int pthread_rwlock_wrlock(pthread_rwlock_t *);
+int pthread_rwlock_trywrlock(pthread_rwlock_t *);
+#ifdef AX_CONFIG_IRQ
+int pthread_rwlock_timedwrlock(pthread_rwlock_t *__restrict, const struct timespec *__restrict);
+#endif
+int pthread_rwlock_unlock(pthread_rwlock_t *);
+
+int pthread_rwlockattr_init(pthread_rwlockattr_t *);
+int pthread_rwlockattr_destroy(pthread_rwlockattr_t *);
+
+int pthread_barrier_init(pthread_barrier_t *__restrict, const pthread_barrierattr_t *__restrict,
+                         unsigned);
+int pthread_barrier_destroy(pthread_barrier_t *);
+int pthread_barrier_wait(pthread_barrier_t *);
+
+int pthread_barrierattr_init(pthread_barrierattr_t *);
+int pthread_barrierattr_destroy(pthread_barrierattr_t *);
+
+int pthread_once(pthread_once_t *, void (*)(void));
+
+int pthread_key_create(pthread_key_t *, void (*)(void *));
+int pthread_key_delete(pthread_key_t);
+void *pthread_getspecific(pthread_key_t);
+int pthread_setspecific(pthread_key_t, const void *);
+
 int pthread_attr_init(pthread_attr_t *__attr);
 int pthread_attr_getstacksize(const pthread_attr_t *__restrict__ __attr,
                               size_t *__restrict__ __stacksize);
@@ -0,0 +1,27 @@
+#ifndef _SEMAPHORE_H
+#define _SEMAPHORE_H
+
+#include <features.h>
+#include <time.h>
+
+typedef struct {
+    long __l[4];
+} sem_t;
+
+#define SEM_FAILED ((sem_t *)0)
+
+#ifdef AX_CONFIG_MULTITASK
+
+int sem_init(sem_t *, int, unsigned);
+int sem_destroy(sem_t *);
+int sem_wait(sem_t *);
+int sem_trywait(sem_t *);
+#ifdef AX_CONFIG_IRQ
+int sem_timedwait(sem_t *__restrict, const struct timespec *__restrict);
+#endif
+int sem_post(sem_t *);
+int sem_getvalue(sem_t *__restrict, int *__restrict);
+
+#endif // AX_CONFIG_MULTITASK
+
+#endif // _SEMAPHORE_H
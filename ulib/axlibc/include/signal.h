@@ -14,6 +14,29 @@ union sigval {
 
 typedef union sigval __sigval_t;
 
+#define SIGEV_SIGNAL    0
+#define SIGEV_NONE      1
+#define SIGEV_THREAD    2
+#define SIGEV_THREAD_ID 4
+
+struct sigevent {
+    union sigval sigev_value;
+    int sigev_signo;
+    int sigev_notify;
+    union {
+        char __pad[64 - 2 * sizeof(int) - sizeof(union sigval)];
+        pid_t sigev_notify_thread_id;
+        struct {
+            void (*sigev_notify_function)(union sigval);
+            pthread_attr_t *sigev_notify_attributes;
+        } __sev_thread;
+    } __sev_fields;
+};
+
+#define sigev_notify_thread_id  __sev_fields.sigev_notify_thread_id
+#define sigev_notify_function   __sev_fields.__sev_thread.sigev_notify_function
+#define sigev_notify_attributes __sev_fields.__sev_thread.sigev_notify_attributes
+
 #define SA_NOCLDSTOP 1
 #define SA_NOCLDWAIT 2
 #define SA_SIGINFO   4
@@ -166,9 +189,15 @@ struct sigaction {
 void (*signal(int, void (*)(int)))(int);
 int sigaction(int, const struct sigaction *__restrict, struct sigaction *__restrict);
 int sigemptyset(sigset_t *);
-int raise(int);
+int sigfillset(sigset_t *);
 int sigaddset(sigset_t *, int);
+int sigdelset(sigset_t *, int);
+int sigismember(const sigset_t *, int);
+int raise(int);
+int sigprocmask(int, const sigset_t *__restrict, sigset_t *__restrict);
 int pthread_sigmask(int, const sigset_t *__restrict, sigset_t *__restrict);
+int sigpending(sigset_t *);
+int sigsuspend(const sigset_t *);
 
 int kill(pid_t, int);
 
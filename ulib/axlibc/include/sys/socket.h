@@ -47,6 +47,11 @@ struct sockaddr {
     char sa_data[14];
 };
 
+struct linger {
+    int l_onoff;
+    int l_linger;
+};
+
 struct sockaddr_storage {
     sa_family_t ss_family;
     char __ss_padding[128 - sizeof(long) - sizeof(sa_family_t)];
@@ -54,6 +59,7 @@ struct sockaddr_storage {
 };
 
 int socket(int, int, int);
+int socketpair(int, int, int, int[2]);
 int shutdown(int, int);
 
 int bind(int, const struct sockaddr *, socklen_t);
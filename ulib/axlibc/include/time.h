@@ -8,6 +8,8 @@
 #define CLOCK_MONOTONIC 1
 #define CLOCKS_PER_SEC  1000000L
 
+#define TIMER_ABSTIME 1
+
 struct tm {
     int tm_sec;   /* seconds of minute */
     int tm_min;   /* minutes of hour */
@@ -22,6 +24,13 @@ struct tm {
     const char *__tm_zone;
 };
 
+struct itimerspec {
+    struct timespec it_interval;
+    struct timespec it_value;
+};
+
+struct sigevent;
+
 clock_t clock(void);
 time_t time(time_t *);
 double difftime(time_t, time_t);
@@ -40,4 +49,10 @@ void tzset(void);
 int nanosleep(const struct timespec *requested_time, struct timespec *remaining);
 int clock_gettime(clockid_t _clk, struct timespec *ts);
 
+int timer_create(clockid_t, struct sigevent *__restrict, timer_t *__restrict);
+int timer_delete(timer_t);
+int timer_settime(timer_t, int, const struct itimerspec *__restrict, struct itimerspec *__restrict);
+int timer_gettime(timer_t, struct itimerspec *);
+int timer_getoverrun(timer_t);
+
 #endif // __TIME_H__
@@ -1,7 +1,9 @@
-use core::ffi::{c_char, c_int};
+use core::ffi::{c_char, c_int, c_ulong, c_void};
 
 use arceos_posix_api::{
-    sys_fstat, sys_getcwd, sys_lseek, sys_lstat, sys_open, sys_rename, sys_stat,
+    sys_chmod, sys_chown, sys_fchmod, sys_fchown, sys_fdatasync, sys_fstat, sys_fsync, sys_getcwd,
+    sys_link, sys_lseek, sys_lstat, sys_mount, sys_open, sys_readlink, sys_rename, sys_stat,
+    sys_symlink, sys_sync, sys_umount2, sys_unlink, sys_utimensat,
 };
 
 use crate::{ctypes, utils::e};
@@ -27,6 +29,28 @@ pub unsafe extern "C" fn lseek(fd: c_int, offset: ctypes::off_t, whence: c_int)
     e(sys_lseek(fd, offset, whence) as _) as _
 }
 
+/// Write back the data of the file indicated by `fd` to the device.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fsync(fd: c_int) -> c_int {
+    e(sys_fsync(fd))
+}
+
+/// Write back the data of the file indicated by `fd` to the device.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fdatasync(fd: c_int) -> c_int {
+    e(sys_fdatasync(fd))
+}
+
+/// Write back all cached data of the filesystems to the devices.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sync() {
+    sys_sync()
+}
+
 /// Get the file metadata by `path` and write into `buf`.
 ///
 /// Return 0 if success.
@@ -51,6 +75,60 @@ pub unsafe extern "C" fn lstat(path: *const c_char, buf: *mut ctypes::stat) -> c
     e(sys_lstat(path, buf) as _)
 }
 
+/// Change the permissions of the file `path` to `mode`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chmod(path: *const c_char, mode: ctypes::mode_t) -> c_int {
+    e(sys_chmod(path, mode))
+}
+
+/// Change the permissions of the file indicated by `fd` to `mode`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fchmod(fd: c_int, mode: ctypes::mode_t) -> c_int {
+    e(sys_fchmod(fd, mode))
+}
+
+/// Change the owner and group of the file `path`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chown(path: *const c_char, owner: u32, group: u32) -> c_int {
+    e(sys_chown(path, owner, group))
+}
+
+/// Change the owner and group of the file indicated by `fd`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fchown(fd: c_int, owner: u32, group: u32) -> c_int {
+    e(sys_fchown(fd, owner, group))
+}
+
+/// Change the access and modification times of the file `path` relative to
+/// `dirfd`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn utimensat(
+    dirfd: c_int,
+    path: *const c_char,
+    times: *const ctypes::timespec,
+    flags: c_int,
+) -> c_int {
+    e(sys_utimensat(dirfd, path, times, flags))
+}
+
+/// Change the access and modification times of the file indicated by `fd`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn futimens(fd: c_int, times: *const ctypes::timespec) -> c_int {
+    e(sys_utimensat(fd, core::ptr::null(), times, 0))
+}
+
 /// Get the path of the current directory.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn getcwd(buf: *mut c_char, size: usize) -> *mut c_char {
@@ -65,3 +143,69 @@ pub unsafe extern "C" fn getcwd(buf: *mut c_char, size: usize) -> *mut c_char {
 pub unsafe extern "C" fn rename(old: *const c_char, new: *const c_char) -> c_int {
     e(sys_rename(old, new))
 }
+
+/// Create a symbolic link `linkpath` that points to `target`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn symlink(target: *const c_char, linkpath: *const c_char) -> c_int {
+    e(sys_symlink(target, linkpath))
+}
+
+/// Read the target of the symbolic link `path` into `buf`.
+///
+/// Return the number of bytes placed in `buf`, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn readlink(
+    path: *const c_char,
+    buf: *mut c_char,
+    bufsiz: usize,
+) -> ctypes::ssize_t {
+    e(sys_readlink(path, buf, bufsiz) as _) as _
+}
+
+/// Create a hard link `new` to the file `old`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn link(old: *const c_char, new: *const c_char) -> c_int {
+    e(sys_link(old, new))
+}
+
+/// Remove the name `path` from the filesystem.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn unlink(path: *const c_char) -> c_int {
+    e(sys_unlink(path))
+}
+
+/// Mount the filesystem `fstype` on `target`, or bind `source` to it.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mount(
+    source: *const c_char,
+    target: *const c_char,
+    fstype: *const c_char,
+    flags: c_ulong,
+    data: *const c_void,
+) -> c_int {
+    e(sys_mount(source, target, fstype, flags, data))
+}
+
+/// Unmount the filesystem mounted on `target`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn umount(target: *const c_char) -> c_int {
+    e(sys_umount2(target, 0))
+}
+
+/// Unmount the filesystem mounted on `target` with `flags`.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn umount2(target: *const c_char, flags: c_int) -> c_int {
+    e(sys_umount2(target, flags))
+}
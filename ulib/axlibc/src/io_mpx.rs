@@ -6,6 +6,8 @@ use core::ffi::c_int;
 use arceos_posix_api::sys_select;
 #[cfg(feature = "epoll")]
 use arceos_posix_api::{sys_epoll_create, sys_epoll_ctl, sys_epoll_wait};
+#[cfg(feature = "poll")]
+use arceos_posix_api::{sys_poll, sys_ppoll};
 
 /// Creates a new epoll instance.
 ///
@@ -52,3 +54,26 @@ pub unsafe extern "C" fn select(
 ) -> c_int {
     e(sys_select(nfds, readfds, writefds, exceptfds, timeout))
 }
+
+/// Wait for some event on a set of file descriptors, for `timeout` milliseconds if it is not negative.
+#[cfg(feature = "poll")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn poll(
+    fds: *mut ctypes::pollfd,
+    nfds: ctypes::nfds_t,
+    timeout: c_int,
+) -> c_int {
+    e(sys_poll(fds, nfds, timeout))
+}
+
+/// Like `poll`, but with a `timespec` timeout and a signal mask.
+#[cfg(feature = "poll")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ppoll(
+    fds: *mut ctypes::pollfd,
+    nfds: ctypes::nfds_t,
+    timeout: *const ctypes::timespec,
+    sigmask: *const ctypes::sigset_t,
+) -> c_int {
+    e(sys_ppoll(fds, nfds, timeout, sigmask))
+}
@@ -10,20 +10,28 @@
 //! - Memory
 //!     - `alloc`: Enable dynamic memory allocation.
 //!     - `tls`: Enable thread-local storage.
+//!     - `mmap`: Enable memory mappings ([mmap]) support.
 //! - Task management
-//!     - `multitask`: Enable multi-threading support.
+//!     - `multitask`: Enable multi-threading support. The timed waits of
+//!       pthreads and semaphores also need `irq`.
+//!     - `signal`: Enable signals and timers that send signals.
 //! - Upperlayer stacks
 //!     - `fs`: Enable file system support.
-//!     - `net`: Enable networking support.
+//!     - `net`: Enable networking support, including Unix domain sockets.
 //! - Lib C functions
 //!     - `fd`: Enable file descriptor table.
 //!     - `pipe`: Enable pipe support.
+//!     - `unix`: Enable Unix domain sockets ([unix]) without networking.
 //!     - `select`: Enable synchronous I/O multiplexing ([select]) support.
+//!     - `poll`: Enable synchronous I/O multiplexing ([poll]) support.
 //!     - `epoll`: Enable event polling ([epoll]) support.
 //!
 //! [ArceOS]: https://github.com/arceos-org/arceos
 //! [select]: https://man7.org/linux/man-pages/man2/select.2.html
+//! [poll]: https://man7.org/linux/man-pages/man2/poll.2.html
 //! [epoll]: https://man7.org/linux/man-pages/man7/epoll.7.html
+//! [mmap]: https://man7.org/linux/man-pages/man2/mmap.2.html
+//! [unix]: https://man7.org/linux/man-pages/man7/unix.7.html
 
 #![cfg_attr(all(not(test), not(doc)), no_std)]
 #![feature(doc_cfg)]
@@ -53,16 +61,20 @@ mod utils;
 mod fd_ops;
 #[cfg(feature = "fs")]
 mod fs;
-#[cfg(any(feature = "select", feature = "epoll"))]
+#[cfg(any(feature = "select", feature = "poll", feature = "epoll"))]
 mod io_mpx;
 #[cfg(feature = "alloc")]
 mod malloc;
-#[cfg(feature = "net")]
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "unix")]
 mod net;
 #[cfg(feature = "pipe")]
 mod pipe;
 #[cfg(feature = "multitask")]
 mod pthread;
+#[cfg(feature = "signal")]
+mod signal;
 #[cfg(feature = "alloc")]
 mod strftime;
 #[cfg(feature = "fp_simd")]
@@ -100,18 +112,48 @@ pub use self::strftime::strftime;
 pub use self::fd_ops::{ax_fcntl, close, dup, dup2, dup3};
 
 #[cfg(feature = "fs")]
-pub use self::fs::{ax_open, fstat, getcwd, lseek, lstat, rename, stat};
+pub use self::fs::{
+    ax_open, chmod, chown, fchmod, fchown, fdatasync, fstat, fsync, futimens, getcwd, link, lseek,
+    lstat, mount, readlink, rename, stat, symlink, sync, umount, umount2, unlink, utimensat,
+};
 
-#[cfg(feature = "net")]
+#[cfg(feature = "unix")]
 pub use self::net::{
-    accept, bind, connect, freeaddrinfo, getaddrinfo, getpeername, getsockname, listen, recv,
-    recvfrom, send, sendto, shutdown, socket,
+    accept, bind, connect, getpeername, getsockname, listen, recv, recvfrom, send, sendto,
+    shutdown, socket, socketpair,
 };
+#[cfg(feature = "net")]
+pub use self::net::{freeaddrinfo, getaddrinfo};
 
+#[cfg(feature = "multitask")]
+pub use self::pthread::{
+    pthread_barrier_destroy, pthread_barrier_init, pthread_barrier_wait, pthread_cond_broadcast,
+    pthread_cond_destroy, pthread_cond_init, pthread_cond_signal, pthread_cond_wait,
+    pthread_getspecific, pthread_key_create, pthread_key_delete, pthread_once,
+    pthread_rwlock_destroy, pthread_rwlock_init, pthread_rwlock_rdlock, pthread_rwlock_tryrdlock,
+    pthread_rwlock_trywrlock, pthread_rwlock_unlock, pthread_rwlock_wrlock, pthread_setspecific,
+};
 #[cfg(feature = "multitask")]
 pub use self::pthread::{pthread_create, pthread_exit, pthread_join, pthread_self};
 #[cfg(feature = "multitask")]
 pub use self::pthread::{pthread_mutex_init, pthread_mutex_lock, pthread_mutex_unlock};
+#[cfg(feature = "multitask")]
+pub use self::pthread::{sem_destroy, sem_getvalue, sem_init, sem_post, sem_trywait, sem_wait};
+// timed waits need timer interrupts
+#[cfg(all(feature = "multitask", feature = "irq"))]
+pub use self::pthread::{
+    pthread_cond_timedwait, pthread_rwlock_timedrdlock, pthread_rwlock_timedwrlock, sem_timedwait,
+};
+
+#[cfg(feature = "signal")]
+pub use self::signal::{
+    alarm, getitimer, kill, pause, pthread_kill, pthread_sigmask, raise, setitimer, sigaction,
+    sigpending, sigprocmask, sigsuspend, timer_create, timer_delete, timer_getoverrun,
+    timer_gettime, timer_settime,
+};
+
+#[cfg(feature = "mmap")]
+pub use self::mmap::{ax_mremap, mmap, mprotect, msync, munmap};
 
 #[cfg(feature = "pipe")]
 pub use self::pipe::pipe;
@@ -120,6 +162,8 @@ pub use self::pipe::pipe;
 pub use self::io_mpx::select;
 #[cfg(feature = "epoll")]
 pub use self::io_mpx::{epoll_create, epoll_ctl, epoll_wait};
+#[cfg(feature = "poll")]
+pub use self::io_mpx::{poll, ppoll};
 
 #[cfg(feature = "fp_simd")]
 pub use self::strtod::{strtod, strtof};
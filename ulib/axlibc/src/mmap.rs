@@ -0,0 +1,70 @@
+use core::ffi::{c_int, c_void};
+
+use arceos_posix_api::{sys_mmap, sys_mprotect, sys_mremap, sys_msync, sys_munmap};
+
+use crate::{ctypes, utils::e};
+
+/// Sets `errno` and returns `MAP_FAILED` if `ret` is a negative error code,
+/// otherwise returns it as an address.
+fn e_ptr(ret: *mut c_void) -> *mut c_void {
+    let ret = ret as isize;
+    if (-4095..0).contains(&ret) {
+        crate::errno::set_errno(-ret as c_int);
+        usize::MAX as *mut c_void
+    } else {
+        ret as *mut c_void
+    }
+}
+
+/// Map files or anonymous memory into the address space.
+///
+/// Return the start address of the mapping, or `MAP_FAILED` on error.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mmap(
+    addr: *mut c_void,
+    len: usize,
+    prot: c_int,
+    flags: c_int,
+    fildes: c_int,
+    off: ctypes::off_t,
+) -> *mut c_void {
+    e_ptr(sys_mmap(addr, len, prot, flags, fildes, off))
+}
+
+/// Remove the mappings in the given range.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn munmap(addr: *mut c_void, length: usize) -> c_int {
+    e(sys_munmap(addr, length))
+}
+
+/// Set the access permissions of the mappings in the given range.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mprotect(addr: *mut c_void, len: usize, prot: c_int) -> c_int {
+    e(sys_mprotect(addr, len, prot))
+}
+
+/// Write the dirty pages of the shared file mappings in the given range back
+/// to the files.
+///
+/// Return 0 if the operation succeeds, otherwise return -1.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn msync(addr: *mut c_void, length: usize, flags: c_int) -> c_int {
+    e(sys_msync(addr, length, flags))
+}
+
+/// Resize a mapping, moving it if `MREMAP_MAYMOVE` is set.
+///
+/// TODO: remove this function in future work
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ax_mremap(
+    old_address: *mut c_void,
+    old_size: usize,
+    new_size: usize,
+    flags: c_int,
+) -> *mut c_void {
+    e_ptr(sys_mremap(old_address, old_size, new_size, flags))
+}
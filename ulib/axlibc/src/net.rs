@@ -1,9 +1,12 @@
 use arceos_posix_api::{
-    sys_accept, sys_bind, sys_connect, sys_freeaddrinfo, sys_getaddrinfo, sys_getpeername,
-    sys_getsockname, sys_listen, sys_recv, sys_recvfrom, sys_send, sys_sendto, sys_shutdown,
-    sys_socket,
+    sys_accept, sys_bind, sys_connect, sys_getpeername, sys_getsockname, sys_getsockopt,
+    sys_listen, sys_recv, sys_recvfrom, sys_send, sys_sendto, sys_setsockopt, sys_shutdown,
+    sys_socket, sys_socketpair,
 };
-use core::ffi::{c_char, c_int, c_void};
+#[cfg(feature = "net")]
+use arceos_posix_api::{sys_freeaddrinfo, sys_getaddrinfo};
+use axerrno::LinuxError;
+use core::ffi::{c_int, c_void};
 
 use crate::{ctypes, utils::e};
 
@@ -15,6 +18,23 @@ pub unsafe extern "C" fn socket(domain: c_int, socktype: c_int, protocol: c_int)
     e(sys_socket(domain, socktype, protocol))
 }
 
+/// Create a pair of connected sockets.
+///
+/// Return 0 if success.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn socketpair(
+    domain: c_int,
+    socktype: c_int,
+    protocol: c_int,
+    sv: *mut c_int,
+) -> c_int {
+    if sv.is_null() {
+        return e((LinuxError::EFAULT as c_int).wrapping_neg());
+    }
+    let sv = unsafe { core::slice::from_raw_parts_mut(sv, 2) };
+    e(sys_socketpair(domain, socktype, protocol, sv))
+}
+
 /// Bind a address to a socket.
 ///
 /// Return 0 if success.
@@ -138,10 +158,11 @@ pub unsafe extern "C" fn shutdown(
 /// Query addresses for a domain name.
 ///
 /// Return address number if success.
+#[cfg(feature = "net")]
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn getaddrinfo(
-    nodename: *const c_char,
-    servname: *const c_char,
+    nodename: *const core::ffi::c_char,
+    servname: *const core::ffi::c_char,
     hints: *const ctypes::addrinfo,
     res: *mut *mut ctypes::addrinfo,
 ) -> c_int {
@@ -154,6 +175,7 @@ pub unsafe extern "C" fn getaddrinfo(
 }
 
 /// Free queried `addrinfo` struct
+#[cfg(feature = "net")]
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn freeaddrinfo(res: *mut ctypes::addrinfo) {
     sys_freeaddrinfo(res);
@@ -178,3 +200,31 @@ pub unsafe extern "C" fn getpeername(
 ) -> c_int {
     e(sys_getpeername(sock_fd, addr, addrlen))
 }
+
+/// Set options on the socket.
+///
+/// Return 0 if success.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn setsockopt(
+    socket_fd: c_int,
+    level: c_int,
+    optname: c_int,
+    optval: *const c_void,
+    optlen: ctypes::socklen_t,
+) -> c_int {
+    e(sys_setsockopt(socket_fd, level, optname, optval, optlen))
+}
+
+/// Get options on the socket.
+///
+/// Return 0 if success.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn getsockopt(
+    socket_fd: c_int,
+    level: c_int,
+    optname: c_int,
+    optval: *mut c_void,
+    optlen: *mut ctypes::socklen_t,
+) -> c_int {
+    e(sys_getsockopt(socket_fd, level, optname, optval, optlen))
+}
@@ -1,6 +1,12 @@
 use crate::{ctypes, utils::e};
 use arceos_posix_api as api;
-use core::ffi::{c_int, c_void};
+use core::ffi::{c_int, c_uint, c_void};
+
+/// Converts the result of `api::sys_pthread_*`, as pthread functions return
+/// the error number instead of setting `errno`.
+fn err(ret: c_int) -> c_int {
+    if ret < 0 { -ret } else { ret }
+}
 
 /// Returns the `pthread` struct of current thread.
 #[unsafe(no_mangle)]
@@ -19,7 +25,7 @@ pub unsafe extern "C" fn pthread_create(
     start_routine: extern "C" fn(arg: *mut c_void) -> *mut c_void,
     arg: *mut c_void,
 ) -> c_int {
-    e(api::sys_pthread_create(res, attr, start_routine, arg))
+    err(api::sys_pthread_create(res, attr, start_routine, arg))
 }
 
 /// Exits the current thread. The value `retval` will be returned to the joiner.
@@ -34,7 +40,7 @@ pub unsafe extern "C" fn pthread_join(
     thread: ctypes::pthread_t,
     retval: *mut *mut c_void,
 ) -> c_int {
-    e(api::sys_pthread_join(thread, retval))
+    err(api::sys_pthread_join(thread, retval))
 }
 
 /// Initialize a mutex.
@@ -43,17 +49,241 @@ pub unsafe extern "C" fn pthread_mutex_init(
     mutex: *mut ctypes::pthread_mutex_t,
     attr: *const ctypes::pthread_mutexattr_t,
 ) -> c_int {
-    e(api::sys_pthread_mutex_init(mutex, attr))
+    err(api::sys_pthread_mutex_init(mutex, attr))
 }
 
 /// Lock the given mutex.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn pthread_mutex_lock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
-    e(api::sys_pthread_mutex_lock(mutex))
+    err(api::sys_pthread_mutex_lock(mutex))
 }
 
 /// Unlock the given mutex.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn pthread_mutex_unlock(mutex: *mut ctypes::pthread_mutex_t) -> c_int {
-    e(api::sys_pthread_mutex_unlock(mutex))
+    err(api::sys_pthread_mutex_unlock(mutex))
+}
+
+/// Initialize a condition variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_cond_init(
+    cond: *mut ctypes::pthread_cond_t,
+    attr: *const ctypes::pthread_condattr_t,
+) -> c_int {
+    err(api::sys_pthread_cond_init(cond, attr))
+}
+
+/// Destroy a condition variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_cond_destroy(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    err(api::sys_pthread_cond_destroy(cond))
+}
+
+/// Unlock `mutex` and wait on the condition variable, then lock `mutex` again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_cond_wait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+) -> c_int {
+    err(api::sys_pthread_cond_wait(cond, mutex))
+}
+
+/// Like `pthread_cond_wait`, but gives up at the absolute time `abstime`.
+#[cfg(feature = "irq")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_cond_timedwait(
+    cond: *mut ctypes::pthread_cond_t,
+    mutex: *mut ctypes::pthread_mutex_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    err(api::sys_pthread_cond_timedwait(cond, mutex, abstime))
+}
+
+/// Wake up one of the threads waiting on the condition variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_cond_signal(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    err(api::sys_pthread_cond_signal(cond))
+}
+
+/// Wake up all threads waiting on the condition variable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_cond_broadcast(cond: *mut ctypes::pthread_cond_t) -> c_int {
+    err(api::sys_pthread_cond_broadcast(cond))
+}
+
+/// Initialize a read-write lock.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_rwlock_init(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    attr: *const ctypes::pthread_rwlockattr_t,
+) -> c_int {
+    err(api::sys_pthread_rwlock_init(rwlock, attr))
+}
+
+/// Destroy a read-write lock.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_rwlock_destroy(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    err(api::sys_pthread_rwlock_destroy(rwlock))
+}
+
+/// Lock the given read-write lock for reading.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_rwlock_rdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    err(api::sys_pthread_rwlock_rdlock(rwlock))
+}
+
+/// Try to lock the given read-write lock for reading.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_rwlock_tryrdlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    err(api::sys_pthread_rwlock_tryrdlock(rwlock))
+}
+
+/// Lock the given read-write lock for reading, until the absolute time
+/// `abstime`.
+#[cfg(feature = "irq")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_rwlock_timedrdlock(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    err(api::sys_pthread_rwlock_timedrdlock(rwlock, abstime))
+}
+
+/// Lock the given read-write lock for writing.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_rwlock_wrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    err(api::sys_pthread_rwlock_wrlock(rwlock))
+}
+
+/// Try to lock the given read-write lock for writing.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_rwlock_trywrlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    err(api::sys_pthread_rwlock_trywrlock(rwlock))
+}
+
+/// Lock the given read-write lock for writing, until the absolute time
+/// `abstime`.
+#[cfg(feature = "irq")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_rwlock_timedwrlock(
+    rwlock: *mut ctypes::pthread_rwlock_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    err(api::sys_pthread_rwlock_timedwrlock(rwlock, abstime))
+}
+
+/// Unlock the given read-write lock.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_rwlock_unlock(rwlock: *mut ctypes::pthread_rwlock_t) -> c_int {
+    err(api::sys_pthread_rwlock_unlock(rwlock))
+}
+
+/// Initialize a barrier for `count` threads.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_barrier_init(
+    barrier: *mut ctypes::pthread_barrier_t,
+    attr: *const ctypes::pthread_barrierattr_t,
+    count: c_uint,
+) -> c_int {
+    err(api::sys_pthread_barrier_init(barrier, attr, count))
+}
+
+/// Destroy a barrier.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_barrier_destroy(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    err(api::sys_pthread_barrier_destroy(barrier))
+}
+
+/// Wait on a barrier until enough threads have arrived.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_barrier_wait(barrier: *mut ctypes::pthread_barrier_t) -> c_int {
+    match api::sys_pthread_barrier_wait(barrier) {
+        ctypes::PTHREAD_BARRIER_SERIAL_THREAD => ctypes::PTHREAD_BARRIER_SERIAL_THREAD,
+        ret => err(ret),
+    }
+}
+
+/// Call `init_routine` only once for `once_control`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_once(
+    once_control: *mut ctypes::pthread_once_t,
+    init_routine: extern "C" fn(),
+) -> c_int {
+    err(api::sys_pthread_once(once_control, init_routine))
+}
+
+/// Create a thread-specific data key.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_key_create(
+    key: *mut ctypes::pthread_key_t,
+    destructor: Option<unsafe extern "C" fn(*mut c_void)>,
+) -> c_int {
+    err(api::sys_pthread_key_create(key, destructor))
+}
+
+/// Delete a thread-specific data key.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_key_delete(key: ctypes::pthread_key_t) -> c_int {
+    err(api::sys_pthread_key_delete(key))
+}
+
+/// Returns the value of `key` in the current thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_getspecific(key: ctypes::pthread_key_t) -> *mut c_void {
+    api::sys_pthread_getspecific(key)
+}
+
+/// Set the value of `key` in the current thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_setspecific(
+    key: ctypes::pthread_key_t,
+    value: *const c_void,
+) -> c_int {
+    err(api::sys_pthread_setspecific(key, value))
+}
+
+/// Initialize an unnamed semaphore with `value`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sem_init(sem: *mut ctypes::sem_t, pshared: c_int, value: c_uint) -> c_int {
+    e(api::sys_sem_init(sem, pshared, value))
+}
+
+/// Destroy an unnamed semaphore.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sem_destroy(sem: *mut ctypes::sem_t) -> c_int {
+    e(api::sys_sem_destroy(sem))
+}
+
+/// Decrement the semaphore, waiting until its value is positive.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sem_wait(sem: *mut ctypes::sem_t) -> c_int {
+    e(api::sys_sem_wait(sem))
+}
+
+/// Try to decrement the semaphore.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sem_trywait(sem: *mut ctypes::sem_t) -> c_int {
+    e(api::sys_sem_trywait(sem))
+}
+
+/// Decrement the semaphore, waiting until the absolute time `abstime`.
+#[cfg(feature = "irq")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sem_timedwait(
+    sem: *mut ctypes::sem_t,
+    abstime: *const ctypes::timespec,
+) -> c_int {
+    e(api::sys_sem_timedwait(sem, abstime))
+}
+
+/// Increment the semaphore.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sem_post(sem: *mut ctypes::sem_t) -> c_int {
+    e(api::sys_sem_post(sem))
+}
+
+/// Get the value of the semaphore.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sem_getvalue(sem: *mut ctypes::sem_t, sval: *mut c_int) -> c_int {
+    e(api::sys_sem_getvalue(sem, sval))
 }
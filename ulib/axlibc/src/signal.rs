@@ -0,0 +1,138 @@
+use arceos_posix_api as api;
+use core::ffi::{c_int, c_uint};
+
+use crate::{ctypes, utils::e};
+
+/// Examine and change a signal action.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sigaction(
+    signum: c_int,
+    act: *const ctypes::sigaction,
+    oldact: *mut ctypes::sigaction,
+) -> c_int {
+    e(api::sys_sigaction(signum, act, oldact))
+}
+
+/// Examine and change the signal mask of the current thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sigprocmask(
+    how: c_int,
+    set: *const ctypes::sigset_t,
+    oldset: *mut ctypes::sigset_t,
+) -> c_int {
+    e(api::sys_sigprocmask(how, set, oldset))
+}
+
+/// Examine and change the signal mask of the current thread.
+///
+/// Returns the error number instead of setting `errno`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_sigmask(
+    how: c_int,
+    set: *const ctypes::sigset_t,
+    oldset: *mut ctypes::sigset_t,
+) -> c_int {
+    -api::sys_sigprocmask(how, set, oldset)
+}
+
+/// Examine the signals that are pending while blocked.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sigpending(set: *mut ctypes::sigset_t) -> c_int {
+    e(api::sys_sigpending(set))
+}
+
+/// Wait for a signal with the given signal mask.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sigsuspend(mask: *const ctypes::sigset_t) -> c_int {
+    e(api::sys_sigsuspend(mask))
+}
+
+/// Wait for a signal.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pause() -> c_int {
+    e(api::sys_pause())
+}
+
+/// Send a signal to the process.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn kill(pid: ctypes::pid_t, sig: c_int) -> c_int {
+    e(api::sys_kill(pid, sig))
+}
+
+/// Send a signal to the current thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn raise(sig: c_int) -> c_int {
+    e(api::sys_raise(sig))
+}
+
+/// Send a signal to a thread.
+///
+/// Returns the error number instead of setting `errno`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pthread_kill(thread: ctypes::pthread_t, sig: c_int) -> c_int {
+    -api::sys_pthread_kill(thread, sig)
+}
+
+/// Arrange for a `SIGALRM` signal to be delivered after `seconds`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn alarm(seconds: c_uint) -> c_uint {
+    api::sys_alarm(seconds)
+}
+
+/// Get the value of an interval timer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn getitimer(which: c_int, curr_value: *mut ctypes::itimerval) -> c_int {
+    e(api::sys_getitimer(which, curr_value))
+}
+
+/// Set the value of an interval timer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn setitimer(
+    which: c_int,
+    new_value: *const ctypes::itimerval,
+    old_value: *mut ctypes::itimerval,
+) -> c_int {
+    e(api::sys_setitimer(which, new_value, old_value))
+}
+
+/// Create a per-process timer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn timer_create(
+    clockid: ctypes::clockid_t,
+    sevp: *mut ctypes::sigevent,
+    timerid: *mut ctypes::timer_t,
+) -> c_int {
+    e(api::sys_timer_create(clockid, sevp, timerid))
+}
+
+/// Arm or disarm a per-process timer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn timer_settime(
+    timerid: ctypes::timer_t,
+    flags: c_int,
+    new_value: *const ctypes::itimerspec,
+    old_value: *mut ctypes::itimerspec,
+) -> c_int {
+    e(api::sys_timer_settime(timerid, flags, new_value, old_value))
+}
+
+/// Get the time until the next expiration of a per-process timer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn timer_gettime(
+    timerid: ctypes::timer_t,
+    curr_value: *mut ctypes::itimerspec,
+) -> c_int {
+    e(api::sys_timer_gettime(timerid, curr_value))
+}
+
+/// Get the overrun count of a per-process timer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn timer_getoverrun(timerid: ctypes::timer_t) -> c_int {
+    e(api::sys_timer_getoverrun(timerid))
+}
+
+/// Delete a per-process timer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn timer_delete(timerid: ctypes::timer_t) -> c_int {
+    e(api::sys_timer_delete(timerid))
+}
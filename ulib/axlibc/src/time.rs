@@ -11,7 +11,7 @@ pub unsafe extern "C" fn clock_gettime(clk: ctypes::clockid_t, ts: *mut ctypes::
 
 /// Sleep some nanoseconds
 ///
-/// TODO: should be woken by signals, and set errno
+/// Fails with `EINTR` if interrupted by a signal.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn nanosleep(
     req: *const ctypes::timespec,
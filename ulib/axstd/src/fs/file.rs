@@ -1,4 +1,5 @@
-use crate::io::{Result, SeekFrom, prelude::*};
+use crate::io::{Error, Result, SeekFrom, prelude::*};
+use crate::time::Duration;
 use core::fmt;
 
 use arceos_api::fs as api;
@@ -16,7 +17,7 @@ pub struct File {
 }
 
 /// Metadata information about a file.
-pub struct Metadata(api::AxFileAttr);
+pub struct Metadata(pub(super) api::AxFileAttr, pub(super) api::AxFileMeta);
 
 /// Options and flags which can be used to configure how a file is opened.
 #[derive(Clone, Debug)]
@@ -90,6 +91,12 @@ impl Metadata {
         self.0.is_file()
     }
 
+    /// Returns `true` if this metadata is for a symbolic link, which is only
+    /// possible for metadata from [`symlink_metadata`](super::symlink_metadata).
+    pub const fn is_symlink(&self) -> bool {
+        self.0.file_type().is_symlink()
+    }
+
     /// Returns the size of the file, in bytes, this metadata is for.
     #[allow(clippy::len_without_is_empty)]
     pub const fn len(&self) -> u64 {
@@ -110,6 +117,39 @@ impl Metadata {
     pub const fn blocks(&self) -> u64 {
         self.0.blocks()
     }
+
+    /// Returns the user ID of the owner of this file.
+    pub const fn uid(&self) -> u32 {
+        self.1.uid
+    }
+
+    /// Returns the group ID of the owner of this file.
+    pub const fn gid(&self) -> u32 {
+        self.1.gid
+    }
+
+    /// Returns the last modification time listed in this metadata, as the
+    /// duration since the UNIX epoch.
+    pub const fn modified(&self) -> Result<Duration> {
+        Ok(self.1.mtime)
+    }
+
+    /// Returns the last access time of this metadata, as the duration since
+    /// the UNIX epoch.
+    pub const fn accessed(&self) -> Result<Duration> {
+        Ok(self.1.atime)
+    }
+
+    /// Returns the creation time listed in this metadata, as the duration
+    /// since the UNIX epoch.
+    ///
+    /// Returns an error if the filesystem does not record it.
+    pub const fn created(&self) -> Result<Duration> {
+        match self.1.crtime {
+            Some(crtime) => Ok(crtime),
+            None => Err(Error::Unsupported),
+        }
+    }
 }
 
 impl fmt::Debug for Metadata {
@@ -118,7 +158,11 @@ impl fmt::Debug for Metadata {
             .field("file_type", &self.file_type())
             .field("is_dir", &self.is_dir())
             .field("is_file", &self.is_file())
+            .field("is_symlink", &self.is_symlink())
             .field("permissions", &self.permissions())
+            .field("modified", &self.modified())
+            .field("accessed", &self.accessed())
+            .field("created", &self.created())
             .finish_non_exhaustive()
     }
 }
@@ -160,7 +204,20 @@ impl File {
 
     /// Queries metadata about the underlying file.
     pub fn metadata(&self) -> Result<Metadata> {
-        api::ax_file_attr(&self.inner).map(Metadata)
+        Ok(Metadata(
+            api::ax_file_attr(&self.inner)?,
+            api::ax_file_meta(&self.inner)?,
+        ))
+    }
+
+    /// Changes the permissions on the underlying file.
+    pub fn set_permissions(&self, perm: Permissions) -> Result<()> {
+        api::ax_set_file_perm(&self.inner, perm)
+    }
+
+    /// Changes the modification time of the underlying file.
+    pub fn set_modified(&self, time: Duration) -> Result<()> {
+        api::ax_set_file_times(&self.inner, None, Some(time))
     }
 }
 
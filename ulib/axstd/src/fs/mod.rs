@@ -42,6 +42,16 @@ pub fn metadata(path: &str) -> io::Result<Metadata> {
     File::open(path)?.metadata()
 }
 
+/// Query the metadata about a file without following symbolic links.
+pub fn symlink_metadata(path: &str) -> io::Result<Metadata> {
+    arceos_api::fs::ax_symlink_metadata(path).map(|(attr, meta)| Metadata(attr, meta))
+}
+
+/// Changes the permissions found on a file or a directory.
+pub fn set_permissions(path: &str, perm: Permissions) -> io::Result<()> {
+    arceos_api::fs::ax_set_permissions(path, perm)
+}
+
 /// Returns an iterator over the entries within a directory.
 pub fn read_dir(path: &str) -> io::Result<ReadDir> {
     ReadDir::new(path)
@@ -75,3 +85,24 @@ pub fn remove_file(path: &str) -> io::Result<()> {
 pub fn rename(old: &str, new: &str) -> io::Result<()> {
     arceos_api::fs::ax_rename(old, new)
 }
+
+/// Creates a new symbolic link at `link`, which points to `original`.
+///
+/// `original` is not required to exist, and relative targets are resolved
+/// from the directory containing `link`.
+pub fn symlink(original: &str, link: &str) -> io::Result<()> {
+    arceos_api::fs::ax_symlink(original, link)
+}
+
+/// Creates a new hard link at `link` to the file at `original`.
+///
+/// This only works when both paths are in the same mounted fs.
+pub fn hard_link(original: &str, link: &str) -> io::Result<()> {
+    arceos_api::fs::ax_hard_link(original, link)
+}
+
+/// Reads a symbolic link, returning the path that it points to.
+#[cfg(feature = "alloc")]
+pub fn read_link(path: &str) -> io::Result<String> {
+    arceos_api::fs::ax_read_link(path)
+}
@@ -31,6 +31,7 @@
 //!     - `fs`: Enable file system support.
 //!     - `myfs`: Allow users to define their custom filesystems to override the default.
 //!     - `net`: Enable networking support.
+//!     - `dhcp`: Configure the network interfaces by DHCPv4.
 //!     - `dns`: Enable DNS lookup support.
 //!     - `display`: Enable graphics support.
 //! - Device drivers
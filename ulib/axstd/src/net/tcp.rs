@@ -1,7 +1,8 @@
 use super::{SocketAddr, ToSocketAddrs};
 use crate::io::{self, prelude::*};
+use crate::time::Duration;
 
-use arceos_api::net::{self as api, AxTcpSocketHandle};
+use arceos_api::net::{self as api, AxSocketOptions, AxTcpSocketHandle};
 
 /// A TCP stream between a local and a remote socket.
 pub struct TcpStream(AxTcpSocketHandle);
@@ -43,6 +44,115 @@ impl TcpStream {
     pub fn shutdown(&self) -> io::Result<()> {
         api::ax_tcp_shutdown(&self.0)
     }
+
+    /// Sets the read timeout to the timeout specified.
+    ///
+    /// If the value specified is [`None`], then [`read`](Read::read) calls
+    /// will block indefinitely. An [`Err`] is returned if the zero
+    /// [`Duration`] is passed to this method.
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        update_options(&self.0, |opts| opts.recv_timeout = dur)
+    }
+
+    /// Returns the read timeout of this socket.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(api::ax_tcp_options(&self.0).recv_timeout)
+    }
+
+    /// Sets the write timeout to the timeout specified.
+    ///
+    /// If the value specified is [`None`], then [`write`](Write::write) calls
+    /// will block indefinitely. An [`Err`] is returned if the zero
+    /// [`Duration`] is passed to this method.
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        update_options(&self.0, |opts| opts.send_timeout = dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(api::ax_tcp_options(&self.0).send_timeout)
+    }
+
+    /// Sets the value of the `TCP_NODELAY` option on this socket.
+    ///
+    /// If set, this option disables the Nagle algorithm. This means that
+    /// segments are always sent as soon as possible, even if there is only a
+    /// small amount of data.
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        update_options(&self.0, |opts| opts.no_delay = nodelay)
+    }
+
+    /// Gets the value of the `TCP_NODELAY` option on this socket.
+    pub fn nodelay(&self) -> io::Result<bool> {
+        Ok(api::ax_tcp_options(&self.0).no_delay)
+    }
+
+    /// Sets the value of the `SO_LINGER` option on this socket.
+    ///
+    /// This value controls how the socket is closed when data remains to be
+    /// sent. If `SO_LINGER` is set, [`shutdown`](Self::shutdown) waits up to
+    /// the given time for the data to be acknowledged, and a zero timeout
+    /// resets the connection instead.
+    pub fn set_linger(&self, linger: Option<Duration>) -> io::Result<()> {
+        update_options(&self.0, |opts| opts.linger = linger)
+    }
+
+    /// Gets the value of the `SO_LINGER` option on this socket.
+    pub fn linger(&self) -> io::Result<Option<Duration>> {
+        Ok(api::ax_tcp_options(&self.0).linger)
+    }
+
+    /// Sets the value of the `SO_KEEPALIVE` option on this socket.
+    ///
+    /// If set, keep-alive packets are sent when the connection is idle, and
+    /// the connection is dropped if the peer stops answering.
+    pub fn set_keepalive(&self, keepalive: bool) -> io::Result<()> {
+        update_options(&self.0, |opts| opts.keep_alive = keepalive)
+    }
+
+    /// Gets the value of the `SO_KEEPALIVE` option on this socket.
+    pub fn keepalive(&self) -> io::Result<bool> {
+        Ok(api::ax_tcp_options(&self.0).keep_alive)
+    }
+
+    /// Sets the size of the receive buffer of this socket (`SO_RCVBUF`).
+    ///
+    /// The size is clamped into the range supported by the network stack.
+    /// The buffers of an established connection cannot be resized, so this
+    /// only affects the connections made or accepted afterwards.
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        update_options(&self.0, |opts| opts.recv_buf_size = size)
+    }
+
+    /// Gets the size of the receive buffer of this socket (`SO_RCVBUF`).
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        Ok(api::ax_tcp_buf_sizes(&self.0).0)
+    }
+
+    /// Sets the size of the send buffer of this socket (`SO_SNDBUF`), as
+    /// [`set_recv_buffer_size`](Self::set_recv_buffer_size).
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        update_options(&self.0, |opts| opts.send_buf_size = size)
+    }
+
+    /// Gets the size of the send buffer of this socket (`SO_SNDBUF`).
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        Ok(api::ax_tcp_buf_sizes(&self.0).1)
+    }
+
+    /// Sets the value for the `IP_TTL` option on this socket.
+    ///
+    /// This value sets the time-to-live field that is used in every packet
+    /// sent from this socket.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        let ttl = to_ttl(ttl)?;
+        update_options(&self.0, |opts| opts.ttl = ttl)
+    }
+
+    /// Gets the value of the `IP_TTL` option for this socket.
+    pub fn ttl(&self) -> io::Result<u32> {
+        Ok(api::ax_tcp_options(&self.0).ttl as u32)
+    }
 }
 
 impl Read for TcpStream {
@@ -102,4 +212,59 @@ impl TcpListener {
     pub fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
         api::ax_tcp_accept(&self.0).map(|(a, b)| (TcpStream(a), b))
     }
+
+    /// Sets the value for the `IP_TTL` option on this socket.
+    ///
+    /// This value sets the time-to-live field that is used in every packet
+    /// sent from this socket, and is inherited by accepted connections.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        let ttl = to_ttl(ttl)?;
+        update_options(&self.0, |opts| opts.ttl = ttl)
+    }
+
+    /// Gets the value of the `IP_TTL` option for this socket.
+    pub fn ttl(&self) -> io::Result<u32> {
+        Ok(api::ax_tcp_options(&self.0).ttl as u32)
+    }
+
+    /// Sets the size of the receive buffer of this socket (`SO_RCVBUF`).
+    ///
+    /// The size is clamped into the range supported by the network stack.
+    /// The buffers of an established connection cannot be resized, so this
+    /// only affects the connections accepted afterwards.
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        update_options(&self.0, |opts| opts.recv_buf_size = size)
+    }
+
+    /// Gets the size of the receive buffer of this socket (`SO_RCVBUF`).
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        Ok(api::ax_tcp_buf_sizes(&self.0).0)
+    }
+
+    /// Sets the size of the send buffer of this socket (`SO_SNDBUF`), as
+    /// [`set_recv_buffer_size`](Self::set_recv_buffer_size).
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        update_options(&self.0, |opts| opts.send_buf_size = size)
+    }
+
+    /// Gets the size of the send buffer of this socket (`SO_SNDBUF`).
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        Ok(api::ax_tcp_buf_sizes(&self.0).1)
+    }
+}
+
+fn update_options<F>(socket: &AxTcpSocketHandle, f: F) -> io::Result<()>
+where
+    F: FnOnce(&mut AxSocketOptions),
+{
+    let mut opts = api::ax_tcp_options(socket);
+    f(&mut opts);
+    api::ax_tcp_set_options(socket, opts)
+}
+
+pub(super) fn to_ttl(ttl: u32) -> io::Result<u8> {
+    match u8::try_from(ttl) {
+        Ok(ttl) if ttl > 0 => Ok(ttl),
+        _ => axerrno::ax_err!(InvalidInput, "invalid TTL"),
+    }
 }
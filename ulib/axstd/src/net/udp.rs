@@ -1,7 +1,8 @@
-use super::{SocketAddr, ToSocketAddrs};
+use super::{SocketAddr, ToSocketAddrs, tcp::to_ttl};
 use crate::io;
+use crate::time::Duration;
 
-use arceos_api::net::{self as api, AxUdpSocketHandle};
+use arceos_api::net::{self as api, AxSocketOptions, AxUdpSocketHandle};
 
 /// A UDP socket.
 pub struct UdpSocket(AxUdpSocketHandle);
@@ -93,4 +94,105 @@ impl UdpSocket {
     pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
         api::ax_udp_recv(&self.0, buf)
     }
+
+    /// Sets the read timeout to the timeout specified.
+    ///
+    /// If the value specified is [`None`], then receiving calls will block
+    /// indefinitely. An [`Err`] is returned if the zero [`Duration`] is passed
+    /// to this method.
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.update_options(|opts| opts.recv_timeout = dur)
+    }
+
+    /// Returns the read timeout of this socket.
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(api::ax_udp_options(&self.0).recv_timeout)
+    }
+
+    /// Sets the write timeout to the timeout specified.
+    ///
+    /// If the value specified is [`None`], then sending calls will block
+    /// indefinitely. An [`Err`] is returned if the zero [`Duration`] is passed
+    /// to this method.
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.update_options(|opts| opts.send_timeout = dur)
+    }
+
+    /// Returns the write timeout of this socket.
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        Ok(api::ax_udp_options(&self.0).send_timeout)
+    }
+
+    /// Sets the value of the `SO_BROADCAST` option for this socket.
+    ///
+    /// When enabled, this socket is allowed to send packets to a broadcast
+    /// address.
+    pub fn set_broadcast(&self, broadcast: bool) -> io::Result<()> {
+        self.update_options(|opts| opts.broadcast = broadcast)
+    }
+
+    /// Gets the value of the `SO_BROADCAST` option for this socket.
+    pub fn broadcast(&self) -> io::Result<bool> {
+        Ok(api::ax_udp_options(&self.0).broadcast)
+    }
+
+    /// Sets the value of the `SO_REUSEADDR` option for this socket.
+    ///
+    /// When enabled before [`bind`](Self::bind)ing, this socket may share its
+    /// port with other sockets that enabled the option.
+    pub fn set_reuse_address(&self, reuse: bool) -> io::Result<()> {
+        self.update_options(|opts| opts.reuse_addr = reuse)
+    }
+
+    /// Gets the value of the `SO_REUSEADDR` option for this socket.
+    pub fn reuse_address(&self) -> io::Result<bool> {
+        Ok(api::ax_udp_options(&self.0).reuse_addr)
+    }
+
+    /// Sets the size of the receive buffer of this socket (`SO_RCVBUF`).
+    ///
+    /// The size is clamped into the range supported by the network stack, and
+    /// the buffer is reallocated once no datagram is queued in it.
+    pub fn set_recv_buffer_size(&self, size: usize) -> io::Result<()> {
+        self.update_options(|opts| opts.recv_buf_size = size)
+    }
+
+    /// Gets the size of the receive buffer of this socket (`SO_RCVBUF`).
+    pub fn recv_buffer_size(&self) -> io::Result<usize> {
+        Ok(api::ax_udp_buf_sizes(&self.0).0)
+    }
+
+    /// Sets the size of the send buffer of this socket (`SO_SNDBUF`), as
+    /// [`set_recv_buffer_size`](Self::set_recv_buffer_size).
+    pub fn set_send_buffer_size(&self, size: usize) -> io::Result<()> {
+        self.update_options(|opts| opts.send_buf_size = size)
+    }
+
+    /// Gets the size of the send buffer of this socket (`SO_SNDBUF`).
+    pub fn send_buffer_size(&self) -> io::Result<usize> {
+        Ok(api::ax_udp_buf_sizes(&self.0).1)
+    }
+
+    /// Sets the value for the `IP_TTL` option on this socket.
+    ///
+    /// This value sets the time-to-live field that is used in every packet
+    /// sent from this socket.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        let ttl = to_ttl(ttl)?;
+        self.update_options(|opts| opts.ttl = ttl)
+    }
+
+    /// Gets the value of the `IP_TTL` option for this socket.
+    pub fn ttl(&self) -> io::Result<u32> {
+        Ok(api::ax_udp_options(&self.0).ttl as u32)
+    }
+
+    fn update_options<F>(&self, f: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut AxSocketOptions),
+    {
+        let mut opts = api::ax_udp_options(&self.0);
+        f(&mut opts);
+        api::ax_udp_set_options(&self.0, opts)
+    }
 }